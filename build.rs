@@ -1,7 +1,9 @@
 fn main() {
     println!("cargo:rerun-if-changed=proto/event.proto");
+    println!("cargo:rerun-if-changed=proto/google/pubsub/v1/pubsub.proto");
     println!("cargo:rerun-if-changed=proto/prometheus-remote.proto");
     println!("cargo:rerun-if-changed=proto/prometheus-types.proto");
+    println!("cargo:rerun-if-changed=proto/vector.proto");
     let mut prost_build = prost_build::Config::new();
     prost_build.btree_map(&["."]);
     // It would be nice to just add these derives to all the types, but
@@ -14,5 +16,19 @@ fn main() {
             &["proto/"],
         )
         .unwrap();
+
+    // The services are generated in their own directory, so the messages
+    // they import don't overwrite the ones generated above.
+    let grpc_out_dir = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("grpc");
+    std::fs::create_dir_all(&grpc_out_dir).unwrap();
+    tonic_build::configure()
+        .out_dir(grpc_out_dir)
+        .extern_path(".event.proto", "crate::event::proto")
+        .compile(
+            &["proto/vector.proto", "proto/google/pubsub/v1/pubsub.proto"],
+            &["proto/"],
+        )
+        .unwrap();
+
     built::write_built_file().expect("Failed to acquire build-time information");
 }
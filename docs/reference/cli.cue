@@ -134,6 +134,51 @@ cli: #CommandLineTool & {
 	}
 
 	commands: {
+		"checkpoints": {
+			description: """
+				Inspect and change the checkpoints of `file` and `journald` sources. Vector
+				must be stopped, the command refuses to run while a Vector process is
+				using the source's data directory
+				"""
+
+			flags: _default_flags
+
+			options: {
+				"data-dir": {
+					_short:      "d"
+					description: "The data directory of the sources, as set by the global or the source's `data_dir` option"
+					type:        "string"
+					default:     "/var/lib/vector/"
+				}
+			}
+
+			args: {
+				action: {
+					description: """
+						One of:
+
+						`list [source] [--format text|json]` lists the fingerprint, path,
+						position and last modification of each file checkpoint, or the
+						journal cursor, of all sources or of a single one.
+
+						`reset <source> [--fingerprint <fingerprint>]` removes the
+						checkpoints of a source, or of a single file, so that they are read
+						again from the start.
+
+						`set <source> --fingerprint <fingerprint> --position <bytes>` sets the
+						position to continue reading a file from, and `set <source> --cursor
+						<cursor>` the journal cursor of a `journald` source.
+
+						`export <source> [--output <file>]` and `import <source> [--input
+						<file>]` copy the checkpoints of a source, e.g. to back them up or to
+						move them to another host. Importing replaces all checkpoints of the
+						source.
+						"""
+					type: "string"
+				}
+			}
+		}
+
 		"generate": {
 			description: "Generate a Vector configuration containing a list of components"
 
@@ -34,7 +34,7 @@ components: sinks: aws_s3: {
 				codec: {
 					enabled: true
 					default: "text"
-					enum: ["ndjson", "text"]
+					enum: ["ndjson", "parquet", "text"]
 				}
 			}
 			request: {
@@ -124,7 +124,7 @@ components: sinks: aws_s3: {
 		content_type: {
 			category:    "Content Type"
 			common:      false
-			description: "A standard MIME type describing the format of the contents."
+			description: "A standard MIME type describing the format of the contents. Defaults to `application/octet-stream` with the `parquet` encoding."
 			required:    false
 			warnings: []
 			type: string: {
@@ -215,6 +215,54 @@ components: sinks: aws_s3: {
 				templateable: true
 			}
 		}
+		parquet: {
+			common:      false
+			description: "Options for the `parquet` encoding. The objects are compressed per column with the `compression` algorithm, rather than as a whole."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: []
+				options: {
+					row_group_size: {
+						common:      false
+						description: "The maximum number of events in each row group of the files."
+						required:    false
+						warnings: []
+						type: uint: {
+							default: 10000
+							unit:    "events"
+						}
+					}
+					schema: {
+						common:      true
+						description: "The columns of the files, as a map of top level field names to column types. The other fields are dropped, and values that can't be converted to the type of their column are written as nulls. When not set, the schema is inferred from the events of each batch."
+						required:    false
+						warnings: []
+						type: object: {
+							examples: [{"message": "string", "status": "int64", "timestamp": "timestamp"}]
+							options: {
+								"*": {
+									common:      false
+									description: "The type of the column, one of `boolean`, `double`, `int64`, `string` or `timestamp`."
+									required:    false
+									warnings: []
+									type: string: {
+										default: null
+										enum: {
+											boolean:   "A boolean."
+											double:    "A 64 bits floating point number."
+											int64:     "A 64 bits integer."
+											string:    "An UTF-8 string. Maps and arrays are encoded as JSON."
+											timestamp: "A timestamp in milliseconds, from timestamps or RFC 3339 strings."
+										}
+									}
+								}
+							}
+						}
+					}
+				}
+			}
+		}
 		server_side_encryption: {
 			category:    "Encryption"
 			common:      false
@@ -289,6 +337,26 @@ components: sinks: aws_s3: {
 				"""
 		}
 
+		parquet: {
+			title: "Parquet"
+			body: """
+				The `parquet` encoding writes each batch as a
+				[Parquet](\(urls.parquet)) file, with one column per field, so
+				that query engines like Athena only read the columns they
+				need. The schema is either declared with the `parquet.schema`
+				option, or inferred from the top level fields of the batch:
+				integers, floats, booleans and timestamps get their own column
+				type, and strings, maps and arrays are written as strings.
+				Fields with different types across events fall back to
+				strings, or to doubles for integers and floats.
+
+				The events of a batch are kept in memory until it is written,
+				the `batch.max_bytes` limit being estimated from the size of
+				their values, and each file is split in row groups of
+				`parquet.row_group_size` events.
+				"""
+		}
+
 		object_acl: {
 			title: "Object Access Control List (ACL)"
 			body: """
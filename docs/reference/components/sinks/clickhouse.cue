@@ -119,10 +119,46 @@ components: sinks: clickhouse: {
 				examples: ["mytable"]
 			}
 		}
+		unknown_fields: {
+			common:      false
+			description: "What to do with the fields of the events that do not match any column of the table."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "error"
+				enum: {
+					skip:  "Insert the event without the fields."
+					error: "Drop the event. Until the schema of the table is known, the event is sent as is and rejected by ClickHouse."
+				}
+			}
+		}
 	}
 
 	input: {
 		logs:    true
 		metrics: null
 	}
+
+	how_it_works: {
+		type_conversion: {
+			title: "Type Conversion"
+			body: """
+				The schema of the table is fetched by the healthcheck, or before the first
+				request when the healthcheck is disabled, and fetched again after an insert
+				failed, in case the table was altered. The fields of the events are then
+				converted to the type of their column before being inserted: timestamps are
+				sent with the precision of `DateTime64` columns, arrays, maps and tuples are
+				converted element by element, and `Nullable` and `LowCardinality` columns
+				use the conversion of the type they wrap. The fields nested in an event map
+				to the columns named after their path, like `request.duration`.
+
+				Integers must fit in the width of their column, and null values are only
+				accepted by `Nullable` columns, rather than being replaced by the default
+				value of the column. An event that can't be converted, or that has unknown
+				fields when `unknown_fields` is set to `error`, is dropped and logged, the
+				other events of the batch are still inserted. The `MATERIALIZED` and `ALIAS`
+				columns are computed by ClickHouse and never inserted.
+				"""
+		}
+	}
 }
@@ -137,6 +137,75 @@ components: sinks: elasticsearch: {
 				}
 			}
 		}
+		bulk_action: {
+			common:      false
+			description: "The [bulk action][urls.elasticsearch_bulk] used to write the events. The `update` and `delete` actions require the `id_key` option, and `update` inserts the document when it does not exist yet. Defaults to `create` in the `data_stream` mode."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "index"
+				examples: ["create", "{{ action }}"]
+				templateable: true
+			}
+		}
+		data_stream: {
+			common:      false
+			description: "Options for the `data_stream` mode."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: []
+				options: {
+					auto_routing: {
+						common:      false
+						description: "Take the name of the data stream from the `data_stream.type`, `data_stream.dataset` and `data_stream.namespace` fields of the events when they are set, and fall back to the options below otherwise."
+						required:    false
+						warnings: []
+						type: bool: default: true
+					}
+					dataset: {
+						common:      true
+						description: "The dataset part of the data stream name."
+						required:    false
+						warnings: []
+						type: string: {
+							default: "generic"
+							examples: ["nginx", "{{ service }}"]
+							templateable: true
+						}
+					}
+					namespace: {
+						common:      true
+						description: "The namespace part of the data stream name."
+						required:    false
+						warnings: []
+						type: string: {
+							default: "default"
+							examples: ["production", "{{ environment }}"]
+							templateable: true
+						}
+					}
+					sync_fields: {
+						common:      false
+						description: "Set the `data_stream.type`, `data_stream.dataset` and `data_stream.namespace` fields of the events to the parts of the data stream they are written to."
+						required:    false
+						warnings: []
+						type: bool: default: true
+					}
+					type: {
+						common:      true
+						description: "The type part of the data stream name."
+						required:    false
+						warnings: []
+						type: string: {
+							default: "logs"
+							examples: ["logs", "metrics"]
+							templateable: true
+						}
+					}
+				}
+			}
+		}
 		doc_type: {
 			common:      false
 			description: "The `doc_type` for your index data. This is only relevant for Elasticsearch <= 6.X. If you are using >= 7.0 you do not need to set this option since Elasticsearch has removed it."
@@ -190,6 +259,19 @@ components: sinks: elasticsearch: {
 				templateable: true
 			}
 		}
+		mode: {
+			common:      false
+			description: "How the events are written to Elasticsearch."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "normal"
+				enum: {
+					normal:      "Write the events to the index named by the `index` option."
+					data_stream: "Write the events to the [data stream][urls.elasticsearch_data_streams] named by the `data_stream` options, with the `create` action. The timestamp of the events is renamed to `@timestamp`."
+				}
+			}
+		}
 		pipeline: {
 			common:      true
 			description: "Name of the pipeline to apply."
@@ -222,9 +304,23 @@ components: sinks: elasticsearch: {
 			title: "Conflicts"
 			body: """
 				Vector [batches](#buffers--batches) data flushes it to Elasticsearch's
-				[`_bulk` API endpoint][urls.elasticsearch_bulk]. All events are inserted
-				via the `index` action. In the case of an conflict, such as a document with the
-				same `id`, Vector will add or _replace_ the document as necessary.
+				[`_bulk` API endpoint][urls.elasticsearch_bulk]. By default, all events are
+				inserted via the `index` action. In the case of an conflict, such as a document
+				with the same `id`, Vector will add or _replace_ the document as necessary.
+				The `bulk_action` option selects another action, such as `create`, which
+				rejects the documents that already exist.
+				"""
+		}
+
+		data_streams: {
+			title: "Data Streams"
+			body: """
+				With `mode` set to `data_stream`, Vector writes the events to the
+				[data stream][urls.elasticsearch_data_streams] named
+				`<type>-<dataset>-<namespace>`, after the `data_stream` options or the
+				`data_stream.*` fields of the events. Data streams only accept the `create`
+				action and require an `@timestamp` field, to which Vector renames the
+				timestamp of the events.
 				"""
 		}
 
@@ -236,6 +332,13 @@ components: sinks: elasticsearch: {
 					mapping errors, where data keys are not consistently typed.
 					To change this behavior please refer to the Elasticsearch
 					[`ignore_malformed` setting](\(urls.elasticsearch_ignore_malformed)).
+
+					Vector inspects the result of each event of a bulk request. The
+					events rejected because of their content are logged and dropped,
+					while the ones rejected because Elasticsearch is overloaded or
+					unavailable, with a `429` or `5xx` status, are retried. Only those
+					events are sent again, so the events written by a previous attempt
+					aren't duplicated.
 					"""
 		}
 	}
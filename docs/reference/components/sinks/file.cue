@@ -70,6 +70,50 @@ components: sinks: file: {
 				templateable: true
 			}
 		}
+		rotation: {
+			common:      false
+			description: "Rotates the files, see [File Rotation](#file-rotation). If unspecified, events are appended to the files at `path`."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: []
+				options: {
+					max_age_secs: {
+						common:      true
+						description: "The maximum amount of time a file stays open before it's rotated."
+						required:    false
+						warnings: []
+						type: uint: {
+							default: null
+							examples: [3600]
+							unit: "seconds"
+						}
+					}
+					max_bytes: {
+						common:      true
+						description: "The maximum size of a file, before compression, before it's rotated."
+						required:    false
+						warnings: []
+						type: uint: {
+							default: null
+							examples: [104857600]
+							unit: "bytes"
+						}
+					}
+					max_files: {
+						common:      false
+						description: "The number of rotated files to keep for each path. The oldest files are removed. If unspecified, every file is kept."
+						required:    false
+						warnings: []
+						type: uint: {
+							default: null
+							examples: [10]
+							unit: null
+						}
+					}
+				}
+			}
+		}
 	}
 
 	input: {
@@ -87,5 +131,25 @@ components: sinks: file: {
 				to create and write to files in the specified directories.
 				"""
 		}
+
+		file_rotation: {
+			title: "File Rotation"
+			body: """
+				When `rotation` is set, each file is written under a
+				temporary name ending in `.tmp`, and atomically renamed once
+				closed: when it reaches `max_bytes` or `max_age_secs`, when
+				it's idle for `idle_timeout_secs`, or when Vector shuts
+				down. The final name has the time the file was opened, in
+				milliseconds since the Unix epoch, appended to its stem, so
+				`/var/log/app.log` becomes `/var/log/app-1603118400000.log`.
+				Readers can safely pick up every file not ending in `.tmp`.
+
+				With `max_files`, only the most recent files are kept for
+				each path. The files rotated by a previous run are found when
+				their path is first written to, and count towards
+				`max_files`. The `.tmp` files it left, after a crash, are
+				renamed to their final name then.
+				"""
+		}
 	}
 }
@@ -34,7 +34,7 @@ components: sinks: gcp_cloud_storage: {
 				codec: {
 					enabled: true
 					default: null
-					enum: ["ndjson", "text"]
+					enum: ["ndjson", "parquet", "text"]
 				}
 			}
 			request: {
@@ -177,6 +177,7 @@ components: sinks: gcp_cloud_storage: {
 				examples: []
 			}
 		}
+		parquet: components.sinks.aws_s3.configuration.parquet
 		storage_class: {
 			category:    "Storage"
 			common:      false
@@ -262,6 +263,17 @@ components: sinks: gcp_cloud_storage: {
 				"""
 		}
 
+		parquet: {
+			title: "Parquet"
+			body: """
+				The `parquet` encoding writes each batch as a
+				[Parquet](\(urls.parquet)) file, with one column per field, so
+				that query engines like BigQuery only read the columns they
+				need. See the [`aws_s3` sink][docs.sinks.aws_s3#parquet] for
+				how the schema is inferred and the row groups are sized.
+				"""
+		}
+
 		storage_class: {
 			title: "Storage Class"
 			body:  """
@@ -27,7 +27,7 @@ components: sinks: kafka: {
 				codec: {
 					enabled: true
 					default: null
-					enum: ["json", "text"]
+					enum: ["avro", "json", "protobuf", "text"]
 				}
 			}
 			request: enabled: false
@@ -46,6 +46,31 @@ components: sinks: kafka: {
 
 	configuration: {
 		bootstrap_servers: components._kafka.configuration.bootstrap_servers
+		headers: {
+			common:      false
+			description: "The headers added to the records, with the field of the event each one is taken from. The headers whose field is missing from the event are not added."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: [
+					{
+						"trace-id": "trace.id"
+						"tenant":   "tenant_id"
+					},
+				]
+				options: {
+					"*": {
+						common:      false
+						description: "The field of the event the header is taken from."
+						required:    false
+						type: string: {
+							default: null
+							examples: ["trace.id"]
+						}
+					}
+				}
+			}
+		}
 		key_field: {
 			description: "The log field name to use for the topic key. If unspecified, the key will be randomly generated. If the field does not exist on the log, a blank value will be used."
 			required:    true
@@ -55,6 +80,47 @@ components: sinks: kafka: {
 			}
 		}
 		librdkafka_options: components._kafka.configuration.librdkafka_options
+		producer_mode: {
+			common:      false
+			description: "The delivery guarantees of the producer."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "default"
+				enum: {
+					default:       "Retries may duplicate or reorder the records."
+					idempotent:    "Enable the [idempotent producer][urls.kafka_idempotence], whose retries can't duplicate or reorder the records."
+					transactional: "Produce the records in [transactions][urls.kafka_transactions], configured by the `transaction` option. Consumers reading committed records see each transaction atomically."
+				}
+			}
+		}
+		protobuf: {
+			common:      false
+			description: "The Protobuf message the events are encoded as with the `protobuf` codec."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: []
+				options: {
+					descriptor_set_file: {
+						description: "The descriptor set of the `.proto` files, generated with `protoc --include_imports --descriptor_set_out`."
+						required:    true
+						warnings: []
+						type: string: {
+							examples: ["/etc/vector/logs.desc"]
+						}
+					}
+					message_type: {
+						description: "The fully qualified name of the message."
+						required:    true
+						warnings: []
+						type: string: {
+							examples: ["logs.v1.Log"]
+						}
+					}
+				}
+			}
+		}
 		message_timeout_ms: {
 			common:      false
 			description: "Local message timeout."
@@ -114,6 +180,40 @@ components: sinks: kafka: {
 				}
 			}
 		}
+		schema_registry: {
+			common:      false
+			description: "The [Confluent Schema Registry][urls.confluent_schema_registry] the schemas of the `avro` and `protobuf` codecs are fetched from."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: []
+				options: {
+					auth: configuration._http_auth & {_args: {
+						password_example: "${SCHEMA_REGISTRY_PASSWORD}"
+						username_example: "${SCHEMA_REGISTRY_USERNAME}"
+					}}
+					subject: {
+						common:      false
+						description: "The subject whose latest schema is used. By default, the `<topic>-value` subject of the topic name strategy is used."
+						required:    false
+						warnings: []
+						type: string: {
+							default: null
+							examples: ["logs-value", "{{ service }}-value"]
+							templateable: true
+						}
+					}
+					url: {
+						description: "The URL of the schema registry."
+						required:    true
+						warnings: []
+						type: string: {
+							examples: ["http://localhost:8081"]
+						}
+					}
+				}
+			}
+		}
 		socket_timeout_ms: components._kafka.configuration.socket_timeout_ms
 		topic: {
 			description: "The Kafka topic name to write events to."
@@ -123,6 +223,45 @@ components: sinks: kafka: {
 				examples: ["topic-1234", "logs-{{unit}}-%Y-%m-%d"]
 			}
 		}
+		transaction: {
+			common:      false
+			description: "The transactions of the `transactional` producer mode."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: []
+				options: {
+					id: {
+						description: "The transactional ID of the producer. Starting a producer with the same ID fences the previous one, each instance of Vector needs its own ID."
+						required:    true
+						warnings: []
+						type: string: {
+							examples: ["vector-kafka-0"]
+						}
+					}
+					max_events: {
+						common:      false
+						description: "The maximum number of events in a transaction. A transaction is also committed whenever the sink is idle."
+						required:    false
+						warnings: []
+						type: uint: {
+							default: 1000
+							unit:    "events"
+						}
+					}
+					timeout_ms: {
+						common:      false
+						description: "How long the broker waits for a transaction to be committed before aborting it."
+						required:    false
+						warnings: []
+						type: uint: {
+							default: 60000
+							unit:    "milliseconds"
+						}
+					}
+				}
+			}
+		}
 	}
 
 	input: {
@@ -130,5 +269,39 @@ components: sinks: kafka: {
 		metrics: null
 	}
 
-	how_it_works: components._kafka.how_it_works
+	how_it_works: components._kafka.how_it_works & {
+		schema_registry: {
+			title: "Schema Registry"
+			body: """
+				The `avro` and `protobuf` codecs encode the events with the latest schema of
+				their subject in the `schema_registry`, in the wire format of the Confluent
+				serializers: a magic byte, the ID of the schema, and the encoded event. The
+				schema of each subject is fetched when it is first used, and fetched again when
+				it can't encode an event and is more than a minute old. Failing to fetch a
+				schema is retried, holding back the events that follow.
+
+				The fields of the events are converted to the types of the schema, the fields
+				that are not part of the schema are left out. The events that can't be
+				converted, even with a refreshed schema, are dropped and logged. The Protobuf schemas of the registry can't be
+				parsed by Vector, the messages are described by the descriptor set of the
+				`protobuf` option instead, which must match the schema of the registry.
+				"""
+		}
+
+		transactions: {
+			title: "Transactions"
+			body: """
+				With the `transactional` producer mode, the events are sent in transactions of
+				up to `transaction.max_events` events, and are acknowledged once their
+				transaction is committed. If a record of the transaction can't be delivered,
+				or the broker requires it, the transaction is aborted and its records are sent
+				again in a new transaction. The sink only stops on fatal producer errors, such
+				as being fenced by another producer with the same `transaction.id`, the events
+				that were not acknowledged are then sent again on restart when a disk buffer
+				is used.
+				Combined with consumers using the `read_committed` isolation level, this
+				prevents duplicated and partial writes.
+				"""
+		}
+	}
 }
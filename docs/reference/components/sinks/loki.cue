@@ -34,7 +34,7 @@ components: sinks: loki: {
 			}
 			request: {
 				enabled:                    true
-				in_flight_limit:            5
+				in_flight_limit:            1
 				rate_limit_duration_secs:   1
 				rate_limit_num:             5
 				retry_initial_backoff_secs: 1
@@ -97,6 +97,31 @@ components: sinks: loki: {
 				}
 			}
 		}
+		max_streams: {
+			common:      false
+			description: "The maximum number of distinct label sets the sink sends. Once it is reached, the events of new streams are dropped, which protects Loki from label values with a high cardinality. A stream stops counting once it hasn't been sent to for 30 minutes, the default `chunk_idle_period` of Loki."
+			required:    false
+			warnings: []
+			type: uint: {
+				default: null
+				examples: [10000]
+				unit: null
+			}
+		}
+		out_of_order_action: {
+			common:      false
+			description: "What to do with the events that are older than the latest event already sent for their stream, which older versions of Loki reject."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "accept"
+				enum: {
+					drop:              "Drop the event."
+					rewrite_timestamp: "Set the timestamp of the event to the latest timestamp of its stream."
+					accept:            "Send the event as is. Versions of Loki that don't accept out-of-order entries reject it."
+				}
+			}
+		}
 		remove_label_fields: {
 			common:      false
 			description: "If this is set to `true` then when labels are collected from events those fields will also get removed from the event."
@@ -145,11 +170,20 @@ components: sinks: loki: {
 		event_ordering: {
 			title: "Event Ordering"
 			body: """
-				The `loki` sink will ensure that all logs are sorted via
-				their `timestamp`. This is to ensure that logs will be
+				The `loki` sink groups the events of a batch by stream, as
+				identified by their labels, and sorts the events of each stream
+				via their `timestamp`. This is to ensure that logs will be
 				accepted by Loki. If no timestamp is supplied with events
 				then the Loki sink will supply its own monotonically
 				increasing timestamp.
+
+				Older versions of Loki also reject the events that are older than
+				the latest event they accepted for their stream, for example when
+				they arrive after their batch was sent. For those, set the
+				`out_of_order_action` to `drop` or `rewrite_timestamp`: the sink
+				then keeps track of the latest timestamp of each stream, applies
+				the action to such events, and sends the requests one at a time
+				to keep the batches in order.
 				"""
 		}
 	}
@@ -33,7 +33,7 @@ components: sinks: prometheus_exporter: {
 						direction: "incoming"
 						port:      _port
 						protocols: ["http"]
-						ssl: "disabled"
+						ssl: "optional"
 					}
 				}
 			}
@@ -72,6 +72,52 @@ components: sinks: prometheus_exporter: {
 				examples: ["0.0.0.0:\(_port)"]
 			}
 		}
+		auth: {
+			common:      false
+			description: "Requires the scrapers to authenticate."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: []
+				options: {
+					password: {
+						description: "The basic authentication password."
+						required:    true
+						warnings: []
+						type: string: {
+							examples: ["${PROMETHEUS_PASSWORD}", "password"]
+						}
+					}
+					strategy: {
+						description: "The authentication strategy to use."
+						required:    true
+						warnings: []
+						type: string: {
+							enum: {
+								basic:  "The [basic authentication strategy][urls.basic_auth]."
+								bearer: "The bearer token authentication strategy."
+							}
+						}
+					}
+					token: {
+						description: "The bearer token."
+						required:    true
+						warnings: []
+						type: string: {
+							examples: ["${PROMETHEUS_TOKEN}", "token"]
+						}
+					}
+					user: {
+						description: "The basic authentication user name."
+						required:    true
+						warnings: []
+						type: string: {
+							examples: ["${PROMETHEUS_USERNAME}", "username"]
+						}
+					}
+				}
+			}
+		}
 		buckets: {
 			common:      false
 			description: "Default buckets to use for aggregating [distribution][docs.data-model.metric#distribution] metrics into histograms."
@@ -106,6 +152,27 @@ components: sinks: prometheus_exporter: {
 				examples: ["service"]
 			}
 		}
+		exemplar_tags: {
+			common:      false
+			description: "The tags moved from the metrics to their [exemplars](\(urls.openmetrics_exemplars)), such as trace IDs. Exemplars are only exposed in the OpenMetrics format, see [Exposition Formats](#exposition-formats)."
+			required:    false
+			warnings: []
+			type: array: {
+				default: []
+				items: type: string: examples: ["trace_id"]
+			}
+		}
+		expire_metrics_secs: {
+			common:      true
+			description: "The time after which the series which didn't receive any event are removed, and not exposed anymore. If unspecified, the series are never removed."
+			required:    false
+			warnings: []
+			type: uint: {
+				default: null
+				examples: [300]
+				unit: "seconds"
+			}
+		}
 		quantiles: {
 			common:      false
 			description: "Quantiles to use for aggregating [distribution][docs.data-model.metric#distribution] metrics into a summary."
@@ -116,6 +183,11 @@ components: sinks: prometheus_exporter: {
 				items: type: float: examples: [0.5, 0.75, 0.9, 0.95, 0.99]
 			}
 		}
+		tls: configuration._tls_accept & {_args: {
+			can_enable:             true
+			can_verify_certificate: true
+			enabled_default:        false
+		}}
 	}
 
 	input: {
@@ -307,6 +379,27 @@ components: sinks: prometheus_exporter: {
 	]
 
 	how_it_works: {
+		exposition_formats: {
+			title: "Exposition Formats"
+			body: """
+				The metrics are exposed in the [OpenMetrics](\(urls.openmetrics))
+				format when the `Accept` header of the scrape requests it,
+				and in the [Prometheus text format](\(urls.prometheus_text_based_exposition_format))
+				otherwise. In the OpenMetrics format, the counters get the
+				`_total` suffix, the latest exemplar of each counter and
+				histogram is exposed, and the `_min`, `_max` and `_avg`
+				series of the summaries are omitted.
+				"""
+		}
+		metric_expiration: {
+			title: "Metric Expiration"
+			body: """
+				Vector keeps every series it receives until they expire.
+				When tags of high cardinality, like pod names, change
+				over time, set `expire_metrics_secs` so the series which
+				stopped receiving events are removed.
+				"""
+		}
 		histogram_buckets: {
 			title: "Histogram Buckets"
 			body: #"""
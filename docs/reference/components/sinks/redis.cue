@@ -0,0 +1,127 @@
+package metadata
+
+components: sinks: redis: {
+	title:       "Redis"
+	description: "[Redis](\(urls.redis)) is an in-memory data structure store, used as a database, cache, and message broker. Its lists and pub/sub channels are commonly used as lightweight queues between producers and consumers."
+
+	classes: {
+		commonly_used: false
+		delivery:      "at_least_once"
+		development:   "beta"
+		egress_method: "batch"
+		service_providers: []
+	}
+
+	features: {
+		buffer: enabled:      true
+		healthcheck: enabled: true
+		send: {
+			batch: {
+				enabled:      true
+				common:       false
+				max_bytes:    1000000
+				max_events:   100
+				timeout_secs: 1
+			}
+			compression: enabled: false
+			encoding: {
+				enabled: true
+				codec: {
+					enabled: true
+					default: null
+					enum: ["json", "text"]
+				}
+			}
+			request: {
+				enabled:                    true
+				in_flight_limit:            5
+				rate_limit_duration_secs:   1
+				rate_limit_num:             5
+				retry_initial_backoff_secs: 1
+				retry_max_duration_secs:    10
+				timeout_secs:               30
+			}
+			tls: enabled: false
+			to: {
+				name:     "Redis"
+				thing:    "a \(name) server"
+				url:      urls.redis
+				versions: null
+
+				interface: {
+					socket: {
+						direction: "outgoing"
+						protocols: ["tcp"]
+						ssl: "disabled"
+					}
+				}
+			}
+		}
+	}
+
+	support: {
+		platforms: {
+			"aarch64-unknown-linux-gnu":  true
+			"aarch64-unknown-linux-musl": true
+			"x86_64-apple-darwin":        true
+			"x86_64-pc-windows-msv":      true
+			"x86_64-unknown-linux-gnu":   true
+			"x86_64-unknown-linux-musl":  true
+		}
+
+		requirements: []
+		warnings: []
+		notices: []
+	}
+
+	configuration: {
+		data_type: {
+			common:      true
+			description: "The Redis data type to write events to."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "list"
+				enum: {
+					list:    "Append events to a list with `RPUSH`."
+					channel: "Publish events to a channel with `PUBLISH`."
+				}
+			}
+		}
+		key: {
+			description: "The name of the list or channel events are written to."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["vector", "logs-{{ application }}"]
+				templateable: true
+			}
+		}
+		url: {
+			description: "The Redis URL to connect to. The url _must_ take the form of `redis://server:port/db`."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["redis://127.0.0.1:6379/0"]
+			}
+		}
+	}
+
+	input: {
+		logs:    true
+		metrics: null
+	}
+
+	how_it_works: {
+		data_types: {
+			title: "Lists and Channels"
+			body:  """
+				Events are either appended to a list, where they are kept until a
+				consumer pops them, or published to a [channel](\(urls.redis_pubsub)),
+				where they are only delivered to the subscribers connected at the time.
+				Each batch is sent as a single pipeline of commands. A lost connection is
+				reestablished when the batch is retried.
+				"""
+		}
+	}
+}
@@ -0,0 +1,184 @@
+package metadata
+
+components: sinks: syslog: {
+	title:       "Syslog"
+	description: components.sources.syslog.description
+
+	classes: {
+		commonly_used: false
+		delivery:      "best_effort"
+		development:   "beta"
+		egress_method: "stream"
+		service_providers: []
+	}
+
+	features: {
+		buffer: enabled:      true
+		healthcheck: enabled: true
+		send: {
+			compression: enabled: false
+			encoding: {
+				enabled: true
+				codec: {
+					enabled: true
+					default: null
+					enum: ["json", "text"]
+				}
+			}
+			request: enabled: false
+			tls: {
+				enabled:                true
+				can_enable:             true
+				can_verify_certificate: true
+				can_verify_hostname:    true
+				enabled_default:        false
+			}
+			to: {
+				name:     "Syslog"
+				thing:    "a \(name) server"
+				url:      urls.syslog
+				versions: null
+
+				interface: {
+					socket: {
+						api: {
+							title: "Syslog"
+							url:   urls.syslog_5424
+						}
+						direction: "outgoing"
+						protocols: ["tcp", "udp", "unix"]
+						ssl: "optional"
+					}
+				}
+			}
+		}
+	}
+
+	support: components.sinks.socket.support
+
+	configuration: components.sinks.socket.configuration & {
+		app_name: {
+			common:      true
+			description: "The APP-NAME, or the TAG with the `rfc3164` format, of the messages."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "vector"
+				examples: ["{{ kubernetes.container_name }}", "nginx"]
+				templateable: true
+			}
+		}
+		facility: {
+			common:      true
+			description: "The facility of the messages, as a keyword like `local0`, or as a code. Events with an invalid facility are dropped."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "user"
+				examples: ["local0", "{{ facility }}", "16"]
+				templateable: true
+			}
+		}
+		format: {
+			common:      true
+			description: "The syslog message format."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "rfc5424"
+				enum: {
+					rfc5424: "The [RFC 5424](\(urls.syslog_5424)) format."
+					rfc3164: "The legacy [RFC 3164](\(urls.syslog_3164)) (BSD) format."
+				}
+			}
+		}
+		framing: {
+			common:      false
+			description: "How the messages are delimited with the `tcp` and `unix` modes, as described in [RFC 6587](\(urls.syslog_6587)). Each `udp` datagram holds a single message."
+			groups: ["tcp", "unix"]
+			required: false
+			warnings: []
+			type: string: {
+				default: "newline_delimited"
+				enum: {
+					newline_delimited: "Each message is followed by a new line. Messages containing new lines are split by the receiver."
+					octet_counting:    "Each message is prefixed with its length, so that they can contain new lines."
+				}
+			}
+		}
+		msg_id: {
+			common:      false
+			description: "The MSGID of the messages, with the `rfc5424` format."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["{{ event_type }}", "ID47"]
+				templateable: true
+			}
+		}
+		proc_id: {
+			common:      false
+			description: "The PROCID of the messages, or the PID following the TAG with the `rfc3164` format."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["{{ pid }}"]
+				templateable: true
+			}
+		}
+		severity: {
+			common:      true
+			description: "The severity of the messages, as a keyword like `err`, a common alias like `error`, or as a code. Events with an invalid severity are dropped."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "info"
+				examples: ["{{ level }}", "warning", "4"]
+				templateable: true
+			}
+		}
+		structured_data: {
+			common:      false
+			description: "The fields to send as [RFC 5424](\(urls.syslog_5424)) structured data elements, with the `rfc5424` format. Each field must be a map, which becomes an element with the field name as its SD-ID, and its keys and values as parameters. Arrays are sent as repeated parameters."
+			required:    false
+			warnings: []
+			type: array: {
+				default: []
+				items: type: string: examples: ["exampleSDID@32473", "origin"]
+			}
+		}
+	}
+
+	input: {
+		logs:    true
+		metrics: null
+	}
+
+	how_it_works: {
+		message_format: {
+			title: "Message Format"
+			body: """
+				The timestamp and the HOSTNAME of the messages are taken from
+				the `timestamp` and `host` fields of the events, and default to
+				the current time and the hostname of the machine running
+				Vector. The header fields are limited to printable ASCII
+				characters, others are removed.
+
+				The MSG part is encoded with the `encoding` options, so it is
+				either the `message` field, or the whole event as JSON.
+				"""
+		}
+
+		structured_data: {
+			title: "Structured Data"
+			body: """
+				The [`syslog` source][docs.sources.syslog] parses the
+				structured data elements into maps named after their SD-ID,
+				so they can be forwarded as is by listing them in
+				`structured_data`.
+				"""
+		}
+	}
+}
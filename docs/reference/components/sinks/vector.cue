@@ -15,12 +15,32 @@ components: sinks: vector: {
 		buffer: enabled:      true
 		healthcheck: enabled: true
 		send: {
-			compression: enabled: false
+			batch: {
+				enabled:      true
+				common:       false
+				max_bytes:    4000000
+				max_events:   1000
+				timeout_secs: 1
+			}
+			compression: {
+				enabled: true
+				default: "none"
+				algorithms: ["none", "gzip"]
+				levels: ["none", "fast", "default", "best", 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]
+			}
 			encoding: {
 				enabled: true
 				codec: enabled: false
 			}
-			request: enabled: false
+			request: {
+				enabled:                    true
+				in_flight_limit:            10
+				rate_limit_duration_secs:   1
+				rate_limit_num:             18446744073709551615
+				retry_initial_backoff_secs: 1
+				retry_max_duration_secs:    10
+				timeout_secs:               30
+			}
 			tls: {
 				enabled:                true
 				can_enable:             true
@@ -37,7 +57,7 @@ components: sinks: vector: {
 				interface: {
 					socket: {
 						direction: "outgoing"
-						protocols: ["tcp"]
+						protocols: ["tcp", "http"]
 						ssl: "optional"
 					}
 				}
@@ -56,7 +76,12 @@ components: sinks: vector: {
 		}
 
 		requirements: []
-		warnings: []
+		warnings: [
+			"""
+				The `batch`, `compression` and `request` options are only supported
+				by version 2 of the protocol, setting them with version 1 is an error.
+				""",
+		]
 		notices: []
 	}
 
@@ -81,6 +106,19 @@ components: sinks: vector: {
 				examples: ["92.12.333.224:5000"]
 			}
 		}
+		version: {
+			common:      true
+			description: "The version of the protocol, which must match the version of the downstream `vector` source."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "1"
+				enum: {
+					"1": "Events are streamed over TCP, without acknowledgements."
+					"2": "Batches of events are sent over [gRPC](\(urls.grpc)), and acknowledged once accepted by the source."
+				}
+			}
+		}
 	}
 
 	how_it_works: components.sources.vector.how_it_works
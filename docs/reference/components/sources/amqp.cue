@@ -0,0 +1,102 @@
+package metadata
+
+components: sources: amqp: {
+	title:       "AMQP"
+	description: components.sinks.amqp.description
+
+	features: {
+		collect: {
+			checkpoint: enabled: false
+			tls: enabled:        false
+			from: {
+				name:     "AMQP"
+				thing:    "an \(name) broker"
+				url:      urls.amqp
+				versions: "0.9.1"
+
+				interface: socket: {
+					direction: "outgoing"
+					protocols: ["tcp"]
+					ssl: "optional"
+				}
+			}
+		}
+		multiline: enabled: false
+	}
+
+	classes: {
+		commonly_used: false
+		deployment_roles: ["aggregator"]
+		delivery:      "at_least_once"
+		development:   "beta"
+		egress_method: "stream"
+	}
+
+	support: components.sinks.amqp.support
+
+	configuration: {
+		connection_string: components.sinks.amqp.configuration.connection_string
+		consumer: {
+			common:      false
+			description: "The consumer tag to register with the broker."
+			required:    false
+			warnings: []
+			type: string: default: "vector"
+		}
+		exchange_key: {
+			common:      false
+			description: "The log field name to use for the exchange a message was published to. If unspecified, the exchange is not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["exchange"]
+			}
+		}
+		queue: {
+			description: "The queue to consume messages from."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["vector"]
+			}
+		}
+		routing_key_field: {
+			common:      false
+			description: "The log field name to use for the routing key of a message. If unspecified, the routing key is not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["routing_key"]
+			}
+		}
+	}
+
+	output: logs: record: {
+		description: "An individual AMQP message."
+		fields: {
+			message: {
+				description: "The payload of the AMQP message."
+				required:    true
+				type: string: examples: ["53.126.150.246 - - [01/Oct/2020:11:25:58 -0400] \"GET /disintermediate HTTP/2.0\" 401 20308"]
+			}
+			timestamp: {
+				description: "The timestamp set on the message by the publisher, falling back to the time the message was received."
+				required:    true
+				type: timestamp: {}
+			}
+		}
+	}
+
+	how_it_works: {
+		acknowledgements: {
+			title: "Acknowledgements"
+			body:  """
+				Each message is acknowledged once it has been handed to the pipeline.
+				Messages that were delivered but not yet acknowledged when Vector stops
+				are redelivered by the broker.
+				"""
+		}
+	}
+}
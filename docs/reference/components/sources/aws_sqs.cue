@@ -0,0 +1,103 @@
+package metadata
+
+components: sources: aws_sqs: {
+	title:       "AWS SQS"
+	description: "[Amazon Simple Queue Service (Amazon SQS)][urls.aws_sqs] is a fully managed message queuing service that enables you to decouple and scale microservices, distributed systems, and serverless applications."
+
+	features: {
+		multiline: enabled: false
+		collect: {
+			tls: enabled:        false
+			checkpoint: enabled: false
+			from: {
+				name:     "AWS SQS"
+				thing:    "an \(name) queue"
+				url:      urls.aws_sqs
+				versions: null
+			}
+		}
+	}
+
+	classes: {
+		commonly_used: false
+		deployment_roles: ["aggregator"]
+		delivery:      "at_least_once"
+		development:   "beta"
+		egress_method: "stream"
+	}
+
+	support: components.sources.aws_s3.support
+
+	configuration: {
+		delete_message: {
+			common:      true
+			description: "Whether to delete messages once they have been handed to the pipeline. It can be useful to set this to `false` to debug or during initial Vector setup."
+			required:    false
+			warnings: []
+			type: bool: default: true
+		}
+		poll_secs: {
+			common:      true
+			description: "How long to wait for messages to arrive when the queue is empty, in seconds. SQS allows at most 20 seconds."
+			required:    false
+			warnings: []
+			type: uint: {
+				default: 15
+				unit:    "seconds"
+			}
+		}
+		queue_url: {
+			description: "The URL of the SQS queue to receive messages from."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["https://sqs.us-east-2.amazonaws.com/123456789012/MyQueue"]
+			}
+		}
+		visibility_timeout_secs: {
+			common:      false
+			description: "The visibility timeout to use for received messages, in seconds. Messages that have not been deleted before the timeout expires become available to other consumers again."
+			required:    false
+			warnings: ["Should be set higher than the time it takes to process a batch of messages to avoid them being reprocessed."]
+			type: uint: {
+				default: 300
+				unit:    "seconds"
+			}
+		}
+	}
+
+	output: logs: record: {
+		description: "An individual SQS message."
+		fields: {
+			message: {
+				description: "The body of the SQS message."
+				required:    true
+				type: string: examples: ["53.126.150.246 - - [01/Oct/2020:11:25:58 -0400] \"GET /disintermediate HTTP/2.0\" 401 20308"]
+			}
+			message_id: {
+				description: "The ID SQS assigned to the message."
+				required:    true
+				type: string: examples: ["5fea7756-0ea4-451a-a703-a558b933e274"]
+			}
+			timestamp: {
+				description: "The time the message was sent to the queue, falling back to the time the message was received."
+				required:    true
+				type: timestamp: {}
+			}
+		}
+	}
+
+	how_it_works: {
+		visibility: {
+			title: "Visibility and deletion"
+			body:  """
+				Messages are received in batches of up to 10 with long polling and
+				stay invisible to other consumers for `visibility_timeout_secs`.
+				Once every message of a batch has been handed to the pipeline the
+				batch is deleted from the queue. If Vector can't hand a batch over,
+				its remaining messages are made visible again right away so that
+				another consumer can pick them up.
+				"""
+		}
+	}
+}
@@ -169,6 +169,28 @@ components: sources: docker: {
 				default: 1
 			}
 		}
+		stats: {
+			common:      false
+			description: "Collect the resource usage metrics of the containers, like CPU, memory, network and block I/O usage, from the Docker stats API."
+			required:    false
+			type: object: {
+				examples: []
+				options: {
+					enabled: {
+						common:      true
+						description: "Whether to collect the container stats metrics."
+						required:    false
+						type: bool: default: false
+					}
+					namespace: {
+						common:      false
+						description: "The namespace of the container stats metrics."
+						required:    false
+						type: string: default: "docker"
+					}
+				}
+			}
+		}
 	}
 
 	output: logs: {
@@ -222,6 +244,82 @@ components: sources: docker: {
 		}
 	}
 
+	output: metrics: {
+		_tags: {
+			container_id: {
+				description: "The Docker container ID."
+				required:    true
+				examples: ["9b6247364a03"]
+			}
+			container_name: {
+				description: "The Docker container name."
+				required:    true
+				examples: ["evil_ptolemy"]
+			}
+			image: {
+				description: "The image name that the container is based on."
+				required:    true
+				examples: ["ubuntu:latest"]
+			}
+			"label.*": {
+				description: "Each container label, prefixed with `label.`."
+				required:    false
+				examples: ["label.com.example.vendor"]
+			}
+		}
+		_interface_tags: _tags & {
+			interface: {
+				description: "The network interface of the container."
+				required:    true
+				examples: ["eth0"]
+			}
+		}
+		_blkio_tags: _tags & {
+			device: {
+				description: "The block device, as `major:minor`."
+				required:    true
+				examples: ["8:0"]
+			}
+			op: {
+				description: "The block I/O operation."
+				required:    true
+				examples: ["Read", "Write"]
+			}
+		}
+
+		_counter: {
+			type: "counter"
+			tags: _tags
+		}
+		_gauge: {
+			type: "gauge"
+			tags: _tags
+		}
+		_blkio_counter: _counter & {tags: _blkio_tags}
+		_network_counter: _counter & {tags: _interface_tags}
+
+		blkio_service_bytes_total:      _blkio_counter & {description:   "The number of bytes transferred to and from the block device."}
+		blkio_serviced_total:           _blkio_counter & {description:   "The number of I/O operations performed on the block device."}
+		cpu_system_seconds_total:       _counter & {description:         "The number of CPU seconds spent in kernel mode."}
+		cpu_throttled_periods_total:    _counter & {description:         "The number of periods the container was throttled in."}
+		cpu_throttled_seconds_total:    _counter & {description:         "The number of seconds the container was throttled for."}
+		cpu_usage_percent:              _gauge & {description:           "The CPU usage since the previous stats, where 100 is one fully used CPU."}
+		cpu_usage_seconds_total:        _counter & {description:         "The number of CPU seconds used by the container."}
+		cpu_user_seconds_total:         _counter & {description:         "The number of CPU seconds spent in user mode."}
+		memory_failures_total:          _counter & {description:         "The number of times the memory usage hit the limit."}
+		memory_limit_bytes:             _gauge & {description:           "The memory limit of the container."}
+		memory_max_usage_bytes:         _gauge & {description:           "The maximum memory usage of the container."}
+		memory_usage_bytes:             _gauge & {description:           "The current memory usage of the container."}
+		network_receive_bytes_total:    _network_counter & {description: "The number of bytes received on the interface."}
+		network_receive_dropped_total:  _network_counter & {description: "The number of received packets dropped on the interface."}
+		network_receive_errors_total:   _network_counter & {description: "The number of receive errors on the interface."}
+		network_receive_packets_total:  _network_counter & {description: "The number of packets received on the interface."}
+		network_transmit_bytes_total:   _network_counter & {description: "The number of bytes transmitted on the interface."}
+		network_transmit_dropped_total: _network_counter & {description: "The number of transmitted packets dropped on the interface."}
+		network_transmit_errors_total:  _network_counter & {description: "The number of transmit errors on the interface."}
+		network_transmit_packets_total: _network_counter & {description: "The number of packets transmitted on the interface."}
+	}
+
 	examples: [
 		{
 			_container_name: "flog"
@@ -252,6 +350,21 @@ components: sources: docker: {
 	]
 
 	how_it_works: {
+		container_labels: {
+			title: "Container Labels"
+			body: """
+				Containers can adjust how Vector collects them with labels:
+
+				* `vector.exclude=true` excludes the container, even when it
+				  matches the `include_*` options.
+				* `vector.multiline.start_pattern`,
+				  `vector.multiline.condition_pattern`, `vector.multiline.mode`
+				  and `vector.multiline.timeout_ms` override the corresponding
+				  `multiline` options for the container's logs. Options that are
+				  not set by a label fall back to the `multiline` configuration.
+				"""
+		}
+
 		message_merging: {
 			title: "Merging Split Messages"
 			body: """
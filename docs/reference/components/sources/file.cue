@@ -98,13 +98,14 @@ components: sources: file: {
 						enum: {
 							checksum:         "Read `bytes` bytes from the head of the file to uniquely identify files via a checksum."
 							device_and_inode: "Uses the [device and inode](\(urls.inode)) to unique identify files."
+							device_inode_and_checksum: "Uses both the [device and inode](\(urls.inode)) and a checksum of `bytes` bytes from the head of the file to uniquely identify files."
 						}
 					}
 				}
 				bytes: {
 					common:        false
 					description:   "The number of bytes read off the head of the file to generate a unique fingerprint."
-					relevant_when: "strategy = \"checksum\""
+					relevant_when: "strategy = \"checksum\" or strategy = \"device_inode_and_checksum\""
 					required:      false
 					type: uint: {
 						default: 256
@@ -114,7 +115,7 @@ components: sources: file: {
 				ignored_header_bytes: {
 					common:        false
 					description:   "The number of bytes to skip ahead (or ignore) when generating a unique fingerprint. This is helpful if all files share a common header."
-					relevant_when: "strategy = \"checksum\""
+					relevant_when: "strategy = \"checksum\" or strategy = \"device_inode_and_checksum\""
 					required:      false
 					type: uint: {
 						default: 0
@@ -258,19 +259,22 @@ components: sources: file: {
 			title: "Compressed Files"
 			body: """
 				Vector will transparently detect files which have been compressed
-				using Gzip and decompress them for reading. This detection process
-				looks for the unique sequence of bytes in the Gzip header and does
-				not rely on the compressed files adhering to any kind of naming
-				convention.
-
-				One caveat with reading compressed files is that Vector is not able
-				to efficiently seek into them. Rather than implement a
-				potentially-expensive full scan as a seek mechanism, Vector
-				currently will not attempt to make further reads from a file for
-				which it has already stored a checkpoint in a previous run. For
-				this reason, users should take care to allow Vector to fully
-				process anycompressed files before shutting the process down or moving the
-				files to another location on disk.
+				using Gzip, Zstandard or Bzip2 and decompress them for reading. This
+				detection process looks for the unique sequence of bytes in the
+				header of each format and does not rely on the compressed files
+				adhering to any kind of naming convention.
+
+				Checkpoints of compressed files record positions in their
+				uncompressed content, and fingerprints are computed from the
+				uncompressed content as well. A log file that is compressed after
+				it has been rotated is therefore recognized as the file Vector was
+				already reading, and reading resumes where it left off. The
+				checkpoints of compressed files fingerprinted by earlier versions,
+				from their compressed bytes, are moved to their new fingerprint when
+				the files are first found. Compressed
+				files can't be seeked into, so resuming requires decompressing
+				everything up to the checkpoint. Compressed files older than
+				`ignore_older` are skipped entirely.
 				"""
 		}
 
@@ -344,6 +348,14 @@ components: sources: file: {
 				read it uncompressed to identify it, and then ensure it has all of
 				the data, including any written in a gap between Vector's last read
 				and the actual rotation event.
+
+				Vector notices when a file it is reading becomes shorter than the
+				position it has read up to, as happens when it is truncated in place,
+				and reports it with the `files_truncated` metric. With the
+				`device_and_inode` strategy the file is then read again from the
+				start. With the content based strategies the truncated file no longer
+				matches its fingerprint and is picked up as a new file instead, once
+				it is large enough to be fingerprinted.
 				"""
 		}
 
@@ -359,6 +371,11 @@ components: sources: file: {
 				This strategy avoids the common pitfalls of using device and inode
 				names since inode names can be reused across files. This enables
 				Vector to properly tail files across various rotation strategies.
+
+				The `device_inode_and_checksum` strategy combines both, and only
+				considers files the same if their device, inode and checksum all
+				match. This keeps files with a common content apart, and catches a
+				new file reusing the inode of a deleted one.
 				"""
 		}
 
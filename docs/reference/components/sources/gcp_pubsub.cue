@@ -0,0 +1,145 @@
+package metadata
+
+components: sources: gcp_pubsub: {
+	title:       "GCP PubSub"
+	description: components.sinks.gcp_pubsub.description
+
+	features: {
+		multiline: enabled: false
+		collect: {
+			checkpoint: enabled: false
+			from: {
+				name:     "GCP PubSub"
+				thing:    "a \(name) subscription"
+				url:      urls.gcp_pubsub
+				versions: null
+
+				interface: socket: {
+					api: {
+						title: "GCP PubSub StreamingPull API"
+						url:   urls.gcp_pubsub_streaming_pull
+					}
+					direction: "outgoing"
+					protocols: ["http"]
+					ssl: "required"
+				}
+			}
+			tls: components.sinks.gcp_pubsub.features.send.tls
+		}
+	}
+
+	classes: {
+		commonly_used: false
+		deployment_roles: ["aggregator"]
+		delivery:      "at_least_once"
+		development:   "beta"
+		egress_method: "stream"
+		service_providers: ["GCP"]
+	}
+
+	support: components.sinks.gcp_pubsub.support
+
+	configuration: {
+		ack_deadline_secs: {
+			common:      false
+			description: "How long Vector has to acknowledge pulled messages before Pub/Sub redelivers them, between 10 and 600 seconds."
+			required:    false
+			warnings: []
+			type: uint: {
+				default: 600
+				unit:    "seconds"
+			}
+		}
+		api_key:          components.sinks.gcp_pubsub.configuration.api_key
+		attributes_key: {
+			common:      false
+			description: "The log field name to use for the message attributes."
+			required:    false
+			warnings: []
+			type: string: default: "attributes"
+		}
+		credentials_path: components.sinks.gcp_pubsub.configuration.credentials_path
+		endpoint: {
+			common:      false
+			description: "The endpoint to pull messages from."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "https://pubsub.googleapis.com"
+				examples: ["https://us-central1-pubsub.googleapis.com"]
+			}
+		}
+		max_messages: {
+			common:      false
+			description: "The maximum number of messages that are pulled but not yet acknowledged. Pub/Sub pauses delivery while this many are outstanding."
+			required:    false
+			warnings: []
+			type: uint: {
+				default: 1000
+				unit:    null
+			}
+		}
+		project: {
+			description: "The project the subscription belongs to."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["vector-123456"]
+			}
+		}
+		subscription: {
+			description: "The subscription within the project to pull messages from."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["vector-logs"]
+			}
+		}
+	}
+
+	output: logs: record: {
+		description: "An individual Pub/Sub message."
+		fields: {
+			attributes: {
+				description: "The attributes of the message, if it has any."
+				required:    false
+				common:      true
+				type: object: {
+					examples: [{"origin": "frontend"}]
+					options: {}
+				}
+			}
+			message: {
+				description: "The data of the Pub/Sub message."
+				required:    true
+				type: string: examples: ["53.126.150.246 - - [01/Oct/2020:11:25:58 -0400] \"GET /disintermediate HTTP/2.0\" 401 20308"]
+			}
+			message_id: {
+				description: "The ID Pub/Sub assigned to the message."
+				required:    true
+				type: string: examples: ["2070443601311540"]
+			}
+			timestamp: {
+				description: "The time the message was published, falling back to the time the message was received."
+				required:    true
+				type: timestamp: {}
+			}
+		}
+	}
+
+	how_it_works: {
+		pulling: {
+			title: "Pulling and acknowledging"
+			body:  """
+				Vector keeps a gRPC StreamingPull stream open to the subscription,
+				over which Pub/Sub delivers messages as they are published. The ack
+				deadline of the stream is set to `ack_deadline_secs`, and each batch
+				is acknowledged over the same stream once all of its messages have
+				been handed to the pipeline. If Vector can't hand a batch over, its
+				deadline is set to zero so that Pub/Sub redelivers the messages
+				immediately. Streams closed by Pub/Sub are reopened right away, and
+				failed ones are retried with an exponential backoff.
+				"""
+		}
+	}
+}
@@ -0,0 +1,138 @@
+package metadata
+
+components: sources: gelf: {
+	_port: 12201
+
+	title:       "GELF"
+	description: "The [Graylog Extended Log Format](\(urls.gelf)) (GELF) is a structured log format used by Graylog and supported by many logging libraries and the Docker `gelf` logging driver."
+
+	classes: {
+		commonly_used: false
+		delivery:      "best_effort"
+		deployment_roles: ["aggregator"]
+		development:   "beta"
+		egress_method: "stream"
+	}
+
+	features: {
+		multiline: enabled: false
+		receive: {
+			from: {
+				name:     "GELF"
+				thing:    "a \(name) client"
+				url:      urls.gelf
+				versions: null
+
+				interface: socket: {
+					api: {
+						title: "GELF"
+						url:   urls.gelf
+					}
+					direction: "incoming"
+					port:      _port
+					protocols: ["tcp", "udp"]
+					ssl: "optional"
+				}
+			}
+
+			tls: sources.socket.features.receive.tls
+		}
+	}
+
+	support: {
+		platforms: sources.socket.support.platforms
+
+		requirements: []
+		warnings: []
+		notices: []
+	}
+
+	configuration: {
+		address: {
+			description: "The address to listen for messages on, or `systemd#N` to use the Nth socket passed by systemd socket activation. If an address is used it _must_ include a port."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["0.0.0.0:\(_port)", "systemd", "systemd#3"]
+			}
+		}
+		chunk_timeout_secs: {
+			common:      false
+			description: "How long to wait for all chunks of a chunked message to arrive before the message is discarded."
+			groups: ["udp"]
+			required: false
+			warnings: []
+			type: uint: {
+				default: 5
+				unit:    "seconds"
+			}
+		}
+		max_length: {
+			common:      true
+			description: "The maximum bytes size of incoming messages, after reassembly and decompression, before they are discarded."
+			required:    false
+			warnings: []
+			type: uint: {
+				default: 1048576
+				unit:    "bytes"
+			}
+		}
+		mode: {
+			description: "The type of socket to use."
+			required:    true
+			warnings: []
+			type: string: {
+				enum: {
+					tcp: "TCP socket, with messages delimited by a null byte."
+					udp: "UDP socket, with optional chunking and compression."
+				}
+			}
+		}
+		shutdown_timeout_secs: sources.socket.configuration.shutdown_timeout_secs
+	}
+
+	output: logs: line: {
+		description: "An individual GELF message."
+		fields: {
+			host: {
+				description: "The `host` field of the message, or the upstream address if it is missing."
+				required:    true
+				type: string: examples: ["example.org"]
+			}
+			message: {
+				description: "The `short_message` field of the message."
+				required:    true
+				type: string: examples: ["A short message that helps you identify what is going on"]
+			}
+			source_ip: {
+				description: "The upstream address the message was received from."
+				required:    true
+				type: string: examples: ["127.0.0.1"]
+			}
+			timestamp: {
+				description: "The `timestamp` field of the message, or the time it was received if it is missing."
+				required:    true
+				type: timestamp: {}
+			}
+			"*": {
+				description: "All other fields of the message. Additional fields have their leading `_` removed, unless that would overwrite another field."
+				required:    false
+				type: "*": {}
+			}
+		}
+	}
+
+	how_it_works: {
+		chunking: {
+			title: "Chunking & Compression"
+			body: """
+				Over UDP, messages may be split into up to 128 chunks. Chunks are reassembled
+				by message id and the message is discarded if not all chunks arrive within
+				`chunk_timeout_secs`. At most 1000 messages are reassembled at once, the
+				chunks of further messages are discarded. Complete messages may be gzip or
+				zlib compressed, which is detected from their leading bytes, and are discarded
+				once they grow past `max_length` while being decompressed.
+				"""
+		}
+	}
+}
@@ -42,18 +42,21 @@ components: sources: host_metrics: {
 
 	configuration: {
 		collectors: {
-			description: "The list of host metric collector services to use. Defaults to all collectors."
+			description: "The list of host metric collector services to use. Defaults to all collectors except `cgroups`, `process` and `tcp`, which must be listed explicitly."
 			common:      true
 			required:    false
 			type: array: {
 				default: ["cpu", "disk", "filesystem", "load", "memory", "network"]
 				items: type: string: enum: {
+					cgroups:    "Metrics related to the CPU, memory and I/O usage of control groups (Linux only)."
 					cpu:        "Metrics related to CPU utilization."
 					disk:       "Metrics related to disk I/O utilization."
 					filesystem: "Metrics related to filesystem space utilization."
 					load:       "Load average metrics (UNIX only)."
 					memory:     "Metrics related to memory utilization."
 					network:    "Metrics related to network utilization."
+					process:    "Metrics related to the CPU, memory and file descriptor usage of individual processes."
+					tcp:        "Metrics related to the states of TCP connections (Linux only)."
 				}
 			}
 		}
@@ -72,6 +75,64 @@ components: sources: host_metrics: {
 				unit:    "seconds"
 			}
 		}
+		cgroups: {
+			common:      false
+			description: #"Options for the "cgroups" metrics collector."#
+			required:    false
+			type: object: options: {
+				base: {
+					common:      false
+					required:    false
+					description: "The control group to start gathering metrics from, relative to the root of the hierarchy. Defaults to the root group."
+					type: string: {
+						default: null
+						examples: ["/system.slice"]
+					}
+				}
+				groups: {
+					common:      false
+					required:    false
+					description: "Lists of control group name patterns to include or exclude."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of control group name patterns for which to gather metrics.
+								Defaults to including all groups.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: examples: ["/user.slice/*", "/system.slice/docker-*"]
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of control group name patterns for which to gather metrics.
+								Defaults to excluding no groups.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: []
+								items: type: string: examples: ["/user.slice/*", "/system.slice/docker-*"]
+							}
+						}
+					}
+				}
+				levels: {
+					common:      false
+					required:    false
+					description: "The number of levels of the control group hierarchy below `base` to gather metrics for."
+					type: uint: {
+						default: 100
+						unit:    null
+					}
+				}
+			}
+		}
 		disk: {
 			common:      false
 			description: #"Options for the "disk" metrics collector."#
@@ -258,6 +319,46 @@ components: sources: host_metrics: {
 				}
 			}
 		}
+		process: {
+			common:      false
+			description: #"Options for the "process" metrics collector."#
+			required:    false
+			type: object: options: {
+				names: {
+					common:      false
+					required:    false
+					description: "Lists of process name patterns to include or exclude."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of process name patterns for which to gather metrics.
+								Defaults to including all processes.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: examples: ["vector", "postgres*"]
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of process name patterns for which to gather metrics.
+								Defaults to excluding no processes.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: []
+								items: type: string: examples: ["vector", "postgres*"]
+							}
+						}
+					}
+				}
+			}
+		}
 	}
 
 	output: metrics: {
@@ -273,6 +374,49 @@ components: sources: host_metrics: {
 			}
 		}
 
+		_cgroup_name: {
+			description: "The path of the control group, relative to the root of the hierarchy."
+			required:    true
+			examples: ["/", "/system.slice/vector.service"]
+		}
+		_cgroup_counter: {
+			type: "counter"
+			tags: _tags & {
+				collector: examples: ["cgroups"]
+				cgroup: _cgroup_name
+			}
+			relevant_when: "OS is Linux"
+		}
+		_cgroup_gauge: {
+			type: "gauge"
+			tags: _tags & {
+				collector: examples: ["cgroups"]
+				cgroup: _cgroup_name
+			}
+			relevant_when: "OS is Linux"
+		}
+		_cgroup_io: _cgroup_counter & {
+			tags: device: {
+				description: "The major and minor number of the block device."
+				required:    true
+				examples: ["8:0"]
+			}
+		}
+		cgroup_cpu_usage_seconds_total: _cgroup_counter & {description: "The number of CPU seconds used by the control group and its descendants."}
+		cgroup_cpu_seconds_total: _cgroup_counter & {
+			description: "The number of CPU seconds used by the control group and its descendants in different operating modes."
+			tags: mode: {
+				description: "Which mode the CPU was running in during the given time."
+				required:    true
+				examples: ["system", "user"]
+			}
+		}
+		cgroup_io_read_bytes_total:    _cgroup_io & {description:    "The number of bytes read from the device by the control group."}
+		cgroup_io_written_bytes_total: _cgroup_io & {description:    "The number of bytes written to the device by the control group."}
+		cgroup_memory_anon_bytes:      _cgroup_gauge & {description: "The number of bytes of anonymous memory used by the control group and its descendants."}
+		cgroup_memory_current_bytes:   _cgroup_gauge & {description: "The number of bytes of memory used by the control group and its descendants."}
+		cgroup_memory_file_bytes:      _cgroup_gauge & {description: "The number of bytes of file-backed memory used by the control group and its descendants."}
+
 		cpu_seconds_total: {
 			description: "The number of CPU seconds accumulated in different operating modes."
 			type:        "counter"
@@ -389,5 +533,60 @@ components: sources: host_metrics: {
 		network_transmit_errs_total:         _network_gauge & {description:   "The number of errors encountered during transmits on this interface."}
 		network_transmit_packets_drop_total: _network_nomac & {description:   "The number of packets dropped during transmits on this interface."}
 		network_transmit_packets_total:      _network_nomac & {description:   "The number of packets transmitted on this interface."}
+
+		_process_tags: _tags & {
+			collector: examples: ["process"]
+			name: {
+				description: "The name of the process."
+				required:    true
+				examples: ["vector"]
+			}
+			pid: {
+				description: "The ID of the process."
+				required:    true
+				examples: ["1234"]
+			}
+		}
+		process_cpu_seconds_total: {
+			description: "The number of CPU seconds used by the process in different operating modes."
+			type:        "counter"
+			tags:        _process_tags & {
+				mode: {
+					description: "Which mode the CPU was running in during the given time."
+					required:    true
+					examples: ["system", "user"]
+				}
+			}
+		}
+		process_memory_rss_bytes: {
+			description: "The number of bytes of resident memory used by the process."
+			type:        "gauge"
+			tags:        _process_tags
+		}
+		process_memory_virtual_bytes: {
+			description: "The number of bytes of virtual memory used by the process."
+			type:        "gauge"
+			tags:        _process_tags
+		}
+		process_open_fds: {
+			description:   "The number of file descriptors the process has open. Only available for processes Vector is allowed to inspect."
+			type:          "gauge"
+			tags:          _process_tags
+			relevant_when: "OS is Linux"
+		}
+
+		tcp_connections: {
+			description: "The number of TCP connections, over IPv4 and IPv6, in each state."
+			type:        "gauge"
+			tags:        _tags & {
+				collector: examples: ["tcp"]
+				state: {
+					description: "The state of the connections."
+					required:    true
+					examples: ["established", "listen", "time_wait"]
+				}
+			}
+			relevant_when: "OS is Linux"
+		}
 	}
 }
@@ -63,6 +63,31 @@ components: sources: journald: {
 			warnings: []
 			type: bool: default: true
 		}
+		exclude_matches: {
+			common:      false
+			description: "The list of journal field values to exclude from monitoring. Records with any of the listed values are excluded. Unlike the `include_matches`, these are applied by Vector, since `journalctl` has no way of excluding matches."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: [
+					{
+						"_SYSTEMD_UNIT": ["sshd.service", "ntpd.service"]
+						"_TRANSPORT": ["kernel"]
+					},
+				]
+				options: {
+					"*": {
+						common:      false
+						description: "The field name and the values to exclude."
+						required:    false
+						type: array: {
+							default: null
+							items: type: string: examples: ["sshd.service"]
+						}
+					}
+				}
+			}
+		}
 		exclude_units: {
 			common:      true
 			description: "The list of unit names to exclude from monitoring. Unit names lacking a `\".\"` will have `\".service\"` appended to make them a valid service unit name."
@@ -73,6 +98,31 @@ components: sources: journald: {
 				items: type: string: examples: ["badservice", "sysinit.target"]
 			}
 		}
+		include_matches: {
+			common:      false
+			description: "The list of journal field values to monitor. If empty or not present, all records are accepted. Records must match one of the values of every listed field. The matches are passed to `journalctl`, so that the other records aren't even read."
+			required:    false
+			warnings: []
+			type: object: {
+				examples: [
+					{
+						"_SYSTEMD_UNIT": ["sshd.service", "ntpd.service"]
+						"_TRANSPORT": ["kernel"]
+					},
+				]
+				options: {
+					"*": {
+						common:      false
+						description: "The field name and the values to include."
+						required:    false
+						type: array: {
+							default: null
+							items: type: string: examples: ["sshd.service"]
+						}
+					}
+				}
+			}
+		}
 		include_units: {
 			common:      true
 			description: "The list of unit names to monitor. If empty or not present, all units are accepted. Unit names lacking a `\".\"` will have `\".service\"` appended to make them a valid service unit name."
@@ -83,6 +133,36 @@ components: sources: journald: {
 				items: type: string: examples: ["ntpd", "sysinit.target"]
 			}
 		}
+		journal_directory: {
+			common:      false
+			description: "Read the journal files in this directory, such as the journals shipped from other hosts or containers, instead of the local journal. Cannot be combined with `journal_files` or `journal_namespace`."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["/var/log/journal/remote"]
+			}
+		}
+		journal_files: {
+			common:      false
+			description: "Read these journal files instead of the local journal. Cannot be combined with `journal_directory` or `journal_namespace`."
+			required:    false
+			warnings: []
+			type: array: {
+				default: []
+				items: type: string: examples: ["/var/log/journal/remote/remote-10.0.0.1.journal"]
+			}
+		}
+		journal_namespace: {
+			common:      false
+			description: "Read the journal of this systemd [journal namespace](\(urls.journald)) instead of the default one. Requires systemd 245 or later. Cannot be combined with `journal_directory` or `journal_files`."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["audit"]
+			}
+		}
 		journalctl_path: {
 			common:      false
 			description: "The full path of the `journalctl` executable. If not set, Vector will search the path for `journalctl`."
@@ -178,6 +258,21 @@ components: sources: journald: {
 				[issue #1473][urls.issue_1473].
 				"""
 		}
+		matches: {
+			title: "Filtering"
+			body: """
+				The `include_units` and `include_matches` options are passed
+				to `journalctl` as match expressions, so the records that
+				don't match never reach Vector. Matches on different fields
+				must all apply, while matches on the same field are
+				alternatives. The units are matched on the `_SYSTEMD_UNIT`
+				field.
+
+				`journalctl` can't exclude matches, so the `exclude_units`
+				and `exclude_matches` options are applied by Vector after
+				reading the records.
+				"""
+		}
 		non_ascii: {
 			title: "Non-ASCII Messages"
 			body: """
@@ -73,6 +73,16 @@ components: sources: kafka: {
 				examples: ["consumer-group-name"]
 			}
 		}
+		headers_key: {
+			common:      false
+			description: "The log field name to use for the headers of the Kafka message. The headers are added as a map of header names to their values. If unspecified, the headers are not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["headers"]
+			}
+		}
 		key_field: {
 			common:      true
 			description: "The log field name to use for the Kafka message key. If unspecified, the key would not be added to the log event. If the message has null key, then this field would not be added to the log event."
@@ -84,6 +94,26 @@ components: sources: kafka: {
 			}
 		}
 		librdkafka_options: components._kafka.configuration.librdkafka_options
+		offset_key: {
+			common:      false
+			description: "The log field name to use for the offset of the Kafka message. If unspecified, the offset is not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["offset"]
+			}
+		}
+		partition_key: {
+			common:      false
+			description: "The log field name to use for the partition of the Kafka message. If unspecified, the partition is not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["partition"]
+			}
+		}
 		sasl: {
 			common:      false
 			description: "Options for SASL/SCRAM authentication support."
@@ -144,6 +174,27 @@ components: sources: kafka: {
 			}
 		}
 		socket_timeout_ms: components._kafka.configuration.socket_timeout_ms
+		statistics_interval_ms: {
+			common:      false
+			description: "The interval at which librdkafka reports the statistics the consumer lag metrics are computed from. Set to `0` to disable the statistics."
+			required:    false
+			warnings: []
+			type: uint: {
+				default: 10000
+				examples: [5000, 10000]
+				unit: "milliseconds"
+			}
+		}
+		topic_key: {
+			common:      false
+			description: "The log field name to use for the topic of the Kafka message. If unspecified, the topic is not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["topic"]
+			}
+		}
 		topics: {
 			description: "The Kafka topics names to read events from. Regex is supported if the topic begins with `^`.\n"
 			required:    true
@@ -166,5 +217,20 @@ components: sources: kafka: {
 		}
 	}
 
-	how_it_works: components._kafka.how_it_works
+	how_it_works: components._kafka.how_it_works & {
+		consumer_lag: {
+			title: "Consumer Lag"
+			body: """
+				Vector reports the lag of the consumer, and the number of
+				messages and bytes in its fetch queue, as the
+				`consumer_lag`, `fetch_queue_messages` and
+				`fetch_queue_bytes` gauges, labeled by `topic_id` and
+				`partition_id`. They are updated from the statistics of
+				librdkafka, every `statistics_interval_ms`, and reset to
+				zero when their partition is revoked. The rebalances
+				of the consumer group are counted by the
+				`consumer_rebalances_total` counter.
+				"""
+		}
+	}
 }
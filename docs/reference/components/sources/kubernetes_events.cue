@@ -0,0 +1,275 @@
+package metadata
+
+components: sources: kubernetes_events: {
+	title:       "Kubernetes Events"
+	description: "Collects the [Kubernetes events](\(urls.kubernetes)) of the cluster, like scheduling decisions, image pulls, container restarts and evictions."
+
+	classes: {
+		commonly_used: false
+		delivery:      "best_effort"
+		deployment_roles: ["daemon", "service"]
+		development:   "beta"
+		egress_method: "stream"
+	}
+
+	features: {
+		collect: {
+			checkpoint: enabled: false
+			from: {
+				name:     "Kubernetes"
+				thing:    "a \(name) cluster"
+				url:      urls.kubernetes
+				versions: ">= 1.14"
+			}
+		}
+		multiline: enabled: false
+	}
+
+	support: components.sources.kubernetes_logs.support
+
+	configuration: {
+		field_selector: {
+			common:      false
+			description: "Specifies the field selector to filter `Event`s with."
+			required:    false
+			type: string: {
+				default: ""
+				examples: ["type=Warning"]
+			}
+		}
+		include_previous_events: {
+			common:      false
+			description: "Emit the events that occurred before the source started watching. By default only the new events are emitted, so that restarts don't emit the retained events again."
+			required:    false
+			type: bool: default: false
+		}
+		label_selector: {
+			common:      false
+			description: "Specifies the label selector to filter `Event`s with."
+			required:    false
+			type: string: {
+				default: ""
+				examples: ["my_custom_label!=my_value"]
+			}
+		}
+		leader_election: {
+			common:      true
+			description: "Only watch the events while holding a `Lease`, so that only one of the Vector replicas, like the ones of a `DaemonSet`, emits the events."
+			required:    false
+			type: object: {
+				examples: []
+				options: {
+					identity: {
+						common:      false
+						description: "The identity of this replica. Defaults to the `VECTOR_SELF_POD_NAME` env var, or to the hostname."
+						required:    false
+						type: string: {
+							default: null
+							examples: ["vector-agent-x7k2p"]
+						}
+					}
+					lease_duration_secs: {
+						common:      false
+						description: "How long the other replicas wait before taking over a lease that wasn't renewed."
+						required:    false
+						type: uint: {
+							default: 15
+							unit:    "seconds"
+						}
+					}
+					lease_name: {
+						common:      false
+						description: "The name of the `Lease` to compete for."
+						required:    false
+						type: string: default: "vector-kubernetes-events"
+					}
+					lease_namespace: {
+						common:      false
+						description: "The namespace of the `Lease`. Defaults to the `VECTOR_SELF_POD_NAMESPACE` env var."
+						required:    false
+						type: string: {
+							default: null
+							examples: ["vector"]
+						}
+					}
+					renew_deadline_secs: {
+						common:      false
+						description: "How long the leader keeps retrying to renew the lease before giving up the leadership."
+						required:    false
+						type: uint: {
+							default: 10
+							unit:    "seconds"
+						}
+					}
+					retry_period_secs: {
+						common:      false
+						description: "How often to try acquiring or renewing the lease."
+						required:    false
+						type: uint: {
+							default: 2
+							unit:    "seconds"
+						}
+					}
+				}
+			}
+		}
+		namespaces: {
+			common:      true
+			description: "The namespaces to watch the events in. The events of all namespaces are watched when empty."
+			required:    false
+			type: array: {
+				default: []
+				items: type: string: examples: ["default", "kube-system"]
+			}
+		}
+	}
+
+	output: logs: event: {
+		description: "An occurrence of a Kubernetes event."
+		fields: {
+			action: {
+				description: "What action was taken or failed regarding the involved object."
+				required:    false
+				common:      false
+				type: string: {
+					examples: ["Binding"]
+					default: null
+				}
+			}
+			count: {
+				description: "The number of times the event occurred. Repeated events are emitted again with an increased count."
+				required:    true
+				type: uint: {
+					examples: [1, 5]
+					unit: null
+				}
+			}
+			first_timestamp: {
+				description: "The time the event first occurred."
+				required:    false
+				common:      false
+				type: timestamp: {}
+			}
+			involved_object: {
+				description: "The object the event is about."
+				required:    true
+				type: object: {
+					examples: [{"api_version": "v1", "kind": "Pod", "name": "coredns-qwertyuiop-qwert", "namespace": "kube-system", "uid": "ba46d8c9-9541-4f6b-bbf9-d23b36f2f136"}]
+					options: {}
+				}
+			}
+			message: {
+				description: "The human-readable description of the event."
+				required:    true
+				type: string: examples: ["Back-off restarting failed container"]
+			}
+			name: {
+				description: "The name of the event object."
+				required:    true
+				type: string: examples: ["coredns-qwertyuiop-qwert.163e5d34b5fd7e5c"]
+			}
+			namespace: {
+				description: "The namespace of the event object."
+				required:    true
+				type: string: examples: ["kube-system"]
+			}
+			reason: {
+				description: "The machine-readable reason of the event."
+				required:    false
+				common:      true
+				type: string: {
+					examples: ["BackOff", "Scheduled", "Pulled"]
+					default: null
+				}
+			}
+			related: {
+				description: "A secondary object the event is about, if any."
+				required:    false
+				common:      false
+				type: object: {
+					examples: [{"kind": "Node", "name": "minikube"}]
+					options: {}
+				}
+			}
+			reporting_component: {
+				description: "The component that reported the event."
+				required:    false
+				common:      false
+				type: string: {
+					examples: ["kubelet"]
+					default: null
+				}
+			}
+			reporting_instance: {
+				description: "The instance of the component that reported the event."
+				required:    false
+				common:      false
+				type: string: {
+					examples: ["minikube"]
+					default: null
+				}
+			}
+			source_type: {
+				description: "The name of the source type."
+				required:    true
+				type: string: examples: ["kubernetes_events"]
+			}
+			timestamp: {
+				description: "The time the event last occurred."
+				required:    true
+				type: timestamp: {}
+			}
+			type: {
+				description: "The type of the event."
+				required:    false
+				common:      true
+				type: string: {
+					examples: ["Normal", "Warning"]
+					default: null
+				}
+			}
+			uid: {
+				description: "The uid of the event object."
+				required:    true
+				type: string: examples: ["1c8e0d4f-5a3e-4f7a-9d1c-2b7e0c4f8a11"]
+			}
+		}
+	}
+
+	how_it_works: {
+		connecting_to_kubernetes_api: components.sources.kubernetes_logs.how_it_works.connecting_to_kubernetes_api
+
+		deduplication: {
+			title: "Deduplication"
+			body: """
+				Kubernetes reports repeated events by updating the existing
+				event object with an increased `count`. Vector emits an event
+				for every new occurrence, and skips the updates that don't add
+				one, as well as the events replayed when the watch is restarted.
+				"""
+		}
+
+		leader_election: {
+			title: "Leader Election"
+			body: """
+				The events are cluster-wide, so when Vector runs as a
+				`DaemonSet` every replica would emit them. With
+				`leader_election` set, the replicas compete for a
+				`coordination.k8s.io/v1` `Lease`, and only the one holding it
+				watches the events. When the leader goes away, another replica
+				takes over once the lease expires, and picks up the events
+				from the lease duration before.
+				"""
+		}
+
+		permissions: {
+			title: "Permissions"
+			body: """
+				The Vector `ServiceAccount` has to be allowed to `watch` the
+				`events`, cluster-wide or in the configured `namespaces`. With
+				leader election, it also needs to `get`, `create` and `update`
+				`leases` in the lease namespace.
+				"""
+		}
+	}
+}
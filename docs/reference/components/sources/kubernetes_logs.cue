@@ -71,6 +71,30 @@ components: sources: kubernetes_logs: {
 							default: "kubernetes.container_name"
 						}
 					}
+					namespace_annotations: {
+						common:      false
+						description: "Event field for Namespace annotations. Only added when `namespace_metadata` is enabled."
+						required:    false
+						type: string: {
+							default: "kubernetes.namespace_annotations"
+						}
+					}
+					namespace_labels: {
+						common:      false
+						description: "Event field for Namespace labels. Only added when `namespace_metadata` is enabled."
+						required:    false
+						type: string: {
+							default: "kubernetes.namespace_labels"
+						}
+					}
+					node_labels: {
+						common:      false
+						description: "Event field for Node labels. Only added when `node_metadata` is enabled."
+						required:    false
+						type: string: {
+							default: "kubernetes.node_labels"
+						}
+					}
 					pod_labels: {
 						common:      false
 						description: "Event field for Pod labels."
@@ -103,6 +127,15 @@ components: sources: kubernetes_logs: {
 							default: "kubernetes.pod_node_name"
 						}
 					}
+					pod_owner: {
+						common:      false
+						description: "Event field for the Pod owner, as `Kind/name`. The owner is only added when this is set."
+						required:    false
+						type: string: {
+							default: null
+							examples: ["kubernetes.pod_owner"]
+						}
+					}
 					pod_uid: {
 						common:      false
 						description: "Event field for Pod uid."
@@ -120,6 +153,18 @@ components: sources: kubernetes_logs: {
 			required:    false
 			type: bool: default: true
 		}
+		namespace_metadata: {
+			common:      false
+			description: "Annotate the events with the labels and annotations of the Pod's `Namespace`. Requires permissions to watch `Namespace`s."
+			required:    false
+			type: bool: default: false
+		}
+		node_metadata: {
+			common:      false
+			description: "Annotate the events with the labels of the `Node` this Vector instance runs at, like its zone and instance type. Requires permissions to watch `Node`s."
+			required:    false
+			type: bool: default: false
+		}
 		self_node_name: {
 			common:      false
 			description: "The name of the Kubernetes `Node` this Vector instance runs at. Configured to use an env var by default, to be evaluated to a value provided by Kubernetes at Pod deploy time."
@@ -191,6 +236,33 @@ components: sources: kubernetes_logs: {
 					default: null
 				}
 			}
+			"kubernetes.namespace_annotations": {
+				description: "Namespace annotations, when `namespace_metadata` is enabled."
+				required:    false
+				common:      false
+				type: object: {
+					examples: [{"myannotation": "myvalue"}]
+					options: {}
+				}
+			}
+			"kubernetes.namespace_labels": {
+				description: "Namespace labels, when `namespace_metadata` is enabled."
+				required:    false
+				common:      false
+				type: object: {
+					examples: [{"team": "payments"}]
+					options: {}
+				}
+			}
+			"kubernetes.node_labels": {
+				description: "Node labels, when `node_metadata` is enabled."
+				required:    false
+				common:      false
+				type: object: {
+					examples: [{"topology.kubernetes.io/zone": "us-east-1a", "node.kubernetes.io/instance-type": "m5.large"}]
+					options: {}
+				}
+			}
 			"kubernetes.pod_labels": {
 				description: "Pod labels name."
 				required:    false
@@ -227,6 +299,15 @@ components: sources: kubernetes_logs: {
 					default: null
 				}
 			}
+			"kubernetes.pod_owner": {
+				description: "The controller of the Pod, as `Kind/name`. Pods of a `Deployment` are attributed to the `Deployment` rather than its `ReplicaSet`. Only present when `annotation_fields.pod_owner` is set."
+				required:    false
+				common:      true
+				type: string: {
+					examples: ["Deployment/coredns", "StatefulSet/postgres", "Job/backup-1603000000"]
+					default: null
+				}
+			}
 			"kubernetes.pod_uid": {
 				description: "Pod uid."
 				required:    false
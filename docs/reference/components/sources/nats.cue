@@ -0,0 +1,91 @@
+package metadata
+
+components: sources: nats: {
+	title:       "NATS"
+	description: components.sinks.nats.description
+
+	features: {
+		collect: {
+			checkpoint: enabled: false
+			tls: enabled:        false
+			from: {
+				name:     "NATS"
+				thing:    "a \(name) server"
+				url:      urls.nats
+				versions: null
+
+				interface: socket: {
+					direction: "outgoing"
+					protocols: ["tcp"]
+					ssl: "disabled"
+				}
+			}
+		}
+		multiline: enabled: false
+	}
+
+	classes: {
+		commonly_used: false
+		deployment_roles: ["aggregator"]
+		delivery:      "best_effort"
+		development:   "beta"
+		egress_method: "stream"
+	}
+
+	support: components.sinks.nats.support
+
+	configuration: {
+		headers_key: {
+			common:      false
+			description: "The log field name to insert the message headers into, as a map. If unspecified, headers are not added to the log event. Headers require NATS server 2.2 or later."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["headers"]
+			}
+		}
+		name: components.sinks.nats.configuration.name
+		queue: {
+			common:      false
+			description: "The NATS queue group to join. Subscribers in the same queue group each receive a subset of the messages published to the subject."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["vector"]
+			}
+		}
+		subject: {
+			description: "The NATS subject to subscribe to. Wildcards are supported."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["foo", "time.us.east", "time.*.east", "time.>", ">"]
+			}
+		}
+		subject_key: {
+			common:      false
+			description: "The log field name to use for the subject a message was received on. If unspecified, the subject is not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["subject"]
+			}
+		}
+		url: components.sinks.nats.configuration.url
+	}
+
+	output: logs: record: {
+		description: "An individual NATS message."
+		fields: {
+			message: {
+				description: "The payload of the NATS message."
+				required:    true
+				type: string: examples: ["53.126.150.246 - - [01/Oct/2020:11:25:58 -0400] \"GET /disintermediate HTTP/2.0\" 401 20308"]
+			}
+			timestamp: fields._current_timestamp
+		}
+	}
+}
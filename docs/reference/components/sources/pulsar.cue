@@ -0,0 +1,120 @@
+package metadata
+
+components: sources: pulsar: {
+	title:       "Apache Pulsar"
+	description: components.sinks.pulsar.description
+
+	features: {
+		collect: {
+			checkpoint: enabled: false
+			tls: enabled:        false
+			from: {
+				name:     "Apache Pulsar"
+				thing:    "an \(name) cluster"
+				url:      urls.pulsar
+				versions: null
+
+				interface: socket: {
+					direction: "outgoing"
+					protocols: ["tcp"]
+					ssl: "disabled"
+				}
+			}
+		}
+		multiline: enabled: false
+	}
+
+	classes: {
+		commonly_used: false
+		deployment_roles: ["aggregator"]
+		delivery:      "at_least_once"
+		development:   "beta"
+		egress_method: "stream"
+	}
+
+	support: components.sinks.pulsar.support
+
+	configuration: {
+		auth:     components.sinks.pulsar.configuration.auth
+		endpoint: components.sinks.pulsar.configuration.endpoint
+		consumer_name: {
+			common:      false
+			description: "The name of the consumer."
+			required:    false
+			warnings: []
+			type: string: default: "vector"
+		}
+		key_field: {
+			common:      true
+			description: "The log field name to use for the message key. If unspecified, the key is not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["message_key"]
+			}
+		}
+		properties_key: {
+			common:      false
+			description: "The log field name to insert the message properties into, as a map. If unspecified, properties are not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["properties"]
+			}
+		}
+		subscription_name: {
+			description: "The name of the subscription to consume with."
+			required:    true
+			warnings: []
+			type: string: examples: ["vector"]
+		}
+		subscription_type: {
+			common:      true
+			description: "The type of the subscription, which determines how messages are distributed between the consumers of a subscription."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "exclusive"
+				enum: {
+					exclusive:  "Only one consumer may be attached to the subscription."
+					shared:     "Messages are distributed round-robin between consumers."
+					failover:   "A single active consumer, with the others taking over when it disconnects."
+					key_shared: "Messages with the same key are delivered to the same consumer."
+				}
+			}
+		}
+		topics: {
+			description: "The Pulsar topics to read messages from."
+			required:    true
+			warnings: []
+			type: array: items: type: string: examples: ["topic-1234"]
+		}
+	}
+
+	output: logs: record: {
+		description: "An individual Pulsar message."
+		fields: {
+			message: {
+				description: "The payload of the Pulsar message."
+				required:    true
+				type: string: examples: ["53.126.150.246 - - [01/Oct/2020:11:25:58 -0400] \"GET /disintermediate HTTP/2.0\" 401 20308"]
+			}
+			timestamp: fields._current_timestamp & {
+				description: "The publish time of the message."
+			}
+		}
+	}
+
+	how_it_works: {
+		acknowledgements: {
+			title: "Acknowledgements"
+			body: """
+				Each message is acknowledged once it has been handed to the next
+				component in the pipeline. Messages that were not acknowledged, for
+				example because Vector was stopped, are redelivered by the broker.
+				"""
+		}
+	}
+}
@@ -0,0 +1,83 @@
+package metadata
+
+components: sources: redis: {
+	title:       "Redis"
+	description: components.sinks.redis.description
+
+	features: {
+		collect: {
+			checkpoint: enabled: false
+			tls: enabled:        false
+			from: {
+				name:     "Redis"
+				thing:    "a \(name) server"
+				url:      urls.redis
+				versions: null
+
+				interface: socket: {
+					direction: "outgoing"
+					protocols: ["tcp"]
+					ssl: "disabled"
+				}
+			}
+		}
+		multiline: enabled: false
+	}
+
+	classes: {
+		commonly_used: false
+		deployment_roles: ["aggregator"]
+		delivery:      "best_effort"
+		development:   "beta"
+		egress_method: "stream"
+	}
+
+	support: components.sinks.redis.support
+
+	configuration: {
+		data_type: {
+			common:      true
+			description: "The Redis data type to read messages from."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "list"
+				enum: {
+					list:    "Pop messages from a list with [`BLPOP`](\(urls.redis_blpop))."
+					channel: "Receive messages published to a channel with `SUBSCRIBE`."
+				}
+			}
+		}
+		key: {
+			description: "The name of the list or channel to read messages from."
+			required:    true
+			warnings: []
+			type: string: {
+				examples: ["vector"]
+			}
+		}
+		redis_key: {
+			common:      false
+			description: "The log field name to use for the list or channel a message was read from. If unspecified, the key is not added to the log event."
+			required:    false
+			warnings: []
+			type: string: {
+				default: null
+				examples: ["redis_key"]
+			}
+		}
+		url: components.sinks.redis.configuration.url
+	}
+
+	output: logs: record: {
+		description: "An individual Redis message."
+		fields: {
+			message: {
+				description: "The payload of the Redis message."
+				required:    true
+				type: string: examples: ["53.126.150.246 - - [01/Oct/2020:11:25:58 -0400] \"GET /disintermediate HTTP/2.0\" 401 20308"]
+			}
+			timestamp: fields._current_timestamp
+		}
+	}
+}
@@ -63,6 +63,14 @@ components: sources: statsd: {
 				examples: ["0.0.0.0:\(_port)", "systemd", "systemd#3"]
 			}
 		}
+		dogstatsd_events: {
+			common:      false
+			description: "Whether to parse [DogStatsD events and service checks](\(urls.dogstatsd_datagram)) into log events. When disabled they are rejected as invalid, so that the source only outputs metrics."
+			groups: ["tcp", "udp", "unix"]
+			required: false
+			warnings: ["Enabling this outputs logs alongside metrics, which sinks that only accept metrics can't handle."]
+			type: bool: default: false
+		}
 		mode: {
 			description: "The type of socket to use."
 			groups: ["tcp", "udp", "unix"]
@@ -76,6 +84,14 @@ components: sources: statsd: {
 				}
 			}
 		}
+		origin_detection: {
+			common:      false
+			description: "Whether to tag the metrics, and the events and service checks, with the ID of the container of the process sending them, found from its credentials. This overrides the container ID sent by the client. Only available on Linux."
+			groups: ["unix"]
+			required: false
+			warnings: []
+			type: bool: default: false
+		}
 		path: {
 			description: "The unix socket path. *This should be an absolute path*."
 			groups: ["unix"]
@@ -99,6 +115,141 @@ components: sources: statsd: {
 
 	}
 
+	output: logs: {
+		event: {
+			description: "A [DogStatsD event](\(urls.dogstatsd_datagram)), formatted as `_e{<TITLE_LENGTH>,<TEXT_LENGTH>}:<TITLE>|<TEXT>|...`. Only output with `dogstatsd_events` enabled."
+			fields: {
+				aggregation_key: {
+					description: "The key grouping the events, from the `k:` field."
+					required:    false
+					common:      false
+					type: string: {
+						default: null
+						examples: ["deployment"]
+					}
+				}
+				alert_type: {
+					description: "The alert type of the event, from the `t:` field."
+					required:    false
+					common:      false
+					type: string: {
+						default: null
+						examples: ["error", "warning", "info", "success"]
+					}
+				}
+				container_id: _container_id
+				host: _host
+				message: {
+					description: "The text of the event."
+					required:    true
+					type: string: examples: ["Deployed version 1.2.3"]
+				}
+				priority: {
+					description: "The priority of the event, from the `p:` field."
+					required:    false
+					common:      false
+					type: string: {
+						default: null
+						examples: ["normal", "low"]
+					}
+				}
+				source_type_name: {
+					description: "The source type of the event, from the `s:` field."
+					required:    false
+					common:      false
+					type: string: {
+						default: null
+						examples: ["jenkins"]
+					}
+				}
+				tags:      _tags
+				timestamp: _timestamp
+				title: {
+					description: "The title of the event."
+					required:    true
+					type: string: examples: ["Deployment"]
+				}
+				type: {
+					description: "The kind of DogStatsD message."
+					required:    true
+					type: string: enum: event: "A DogStatsD event."
+				}
+			}
+		}
+		service_check: {
+			description: "A [DogStatsD service check](\(urls.dogstatsd_datagram)), formatted as `_sc|<NAME>|<STATUS>|...`. Only output with `dogstatsd_events` enabled."
+			fields: {
+				container_id: _container_id
+				host:         _host
+				message: {
+					description: "The message of the service check, from the `m:` field."
+					required:    false
+					common:      true
+					type: string: {
+						default: null
+						examples: ["Redis connection timed out"]
+					}
+				}
+				name: {
+					description: "The name of the service check."
+					required:    true
+					type: string: examples: ["redis.can_connect"]
+				}
+				status: {
+					description: "The status of the service check."
+					required:    true
+					type: string: enum: {
+						ok:       "The `0` status."
+						warning:  "The `1` status."
+						critical: "The `2` status."
+						unknown:  "The `3` status."
+					}
+				}
+				tags:      _tags
+				timestamp: _timestamp
+				type: {
+					description: "The kind of DogStatsD message."
+					required:    true
+					type: string: enum: service_check: "A DogStatsD service check."
+				}
+			}
+		}
+	}
+
+	_container_id: {
+		description: "The ID of the container sending the message, from the `c:` field, or found with the `origin_detection` option."
+		required:    false
+		common:      false
+		type: string: {
+			default: null
+			examples: ["3726184226f5d3147c25fdeab5b60097e378e8a720503a5e19ecfdf29f869860"]
+		}
+	}
+
+	_host: {
+		description: "The host name, from the `h:` field."
+		required:    false
+		common:      true
+		type: string: {
+			default: null
+			examples: ["my.host.com"]
+		}
+	}
+
+	_tags: {
+		description: "The tags of the message."
+		required:    false
+		common:      true
+		type: object: {
+			examples: [{"env": "production", "canary": "true"}]
+			options: {}
+		}
+	}
+
+	_timestamp: fields._current_timestamp & {
+		description: "The time of the message, from the `d:` field, or the time it was received by Vector."
+	}
+
 	output: metrics: {
 		counter:      output._passthrough_counter
 		distribution: output._passthrough_distribution
@@ -107,6 +258,22 @@ components: sources: statsd: {
 	}
 
 	how_it_works: {
+		dogstatsd: {
+			title: "DogStatsD Extensions"
+			body: """
+				The [DogStatsD](\(urls.dogstatsd_datagram)) extensions of the
+				protocol are supported:
+
+				* The `#` tags, and the `c:` container ID, which is added as
+				  the `container_id` tag.
+				* The `d` distribution metrics, with a summary statistic.
+				* Several values packed in a single metric, like
+				  `latency:120:95:230|ms`.
+				* The `_e{...}` events and `_sc` service checks, which are
+				  emitted as log events.
+				"""
+		}
+
 		timestamps: {
 			title: "Timestamps"
 			body: """
@@ -115,7 +282,8 @@ components: sources: statsd: {
 				`null` timestamp, which is a special value which means "a real
 				time metric", i.e. not a historical one. Normally such `null`
 				timestamps will be substituted by current time by downstream
-				sinks or 3rd party services during sending/ingestion. See the
+				sinks or 3rd party services during sending/ingestion, unless
+				the metric comes with a DogStatsD `T` timestamp. See the
 				[metric][docs.data-model.metric] data model page for more info.
 				"""
 		}
@@ -41,7 +41,15 @@ components: sources: syslog: {
 		notices: []
 	}
 
-	configuration: sources.socket.configuration
+	configuration: sources.socket.configuration & {
+		raw_structured_data: {
+			common:      false
+			description: "Keep the raw [RFC 5424][urls.syslog_5424] structured data of the messages in the `structured_data` field, in addition to the parsed elements."
+			required:    false
+			warnings: []
+			type: bool: default: false
+		}
+	}
 
 	output: logs: line: {
 		description: "An individual Syslog event"
@@ -96,6 +104,15 @@ components: sources: syslog: {
 					examples: ["notice"]
 				}
 			}
+			structured_data: {
+				description: "The raw structured data of the RFC 5424 message, if the `raw_structured_data` option is enabled."
+				required:    false
+				common:      false
+				type: string: {
+					default: null
+					examples: ["[exampleSDID@32473 iut=\"3\" eventSource=\"Application\"]"]
+				}
+			}
 			source_ip: {
 				description: "The upstream hostname. In the case where `mode` = `\"unix\"` the socket path will be used. (`host` is also this value if `hostname` does not exist in the log.)"
 				required:    true
@@ -113,9 +130,12 @@ components: sources: syslog: {
 				}
 			}
 			"*": {
-				description: "In addition to the defined fields, any Syslog 5424 structured fields are parsed and inserted as root level fields."
+				description: "In addition to the defined fields, each Syslog 5424 structured data element is inserted as a root level map, named after its SD-ID and containing its parameters. Repeated parameters are collected into an array."
 				required:    true
-				type: string: examples: ["hello world"]
+				type: object: {
+					examples: [{"iut": "3", "eventSource": "Application"}]
+					options: {}
+				}
 			}
 		}
 	}
@@ -148,19 +168,28 @@ components: sources: syslog: {
 				appname:     _app_name
 				procid:      _procid
 				msgid:       _msgid
-				iut:         _iut
-				eventSource: _event_source
-				eventID:     _event_id
-				message:     _message
+				"exampleSDID@32473": {
+					iut:         _iut
+					eventSource: _event_source
+					eventID:     _event_id
+				}
+				message: _message
 			}
 		},
 	]
 
 	how_it_works: {
 		line_delimiters: {
-			title: "Line Delimiters"
+			title: "Framing"
 			body: """
-				Each line is read until a new line delimiter, the `0xA` byte, is found.
+				With the `tcp` and `unix` modes, Vector detects the framing of
+				each message as described in [RFC 6587][urls.syslog_6587]:
+				messages starting with a length and a space, like the ones
+				sent by rsyslog with `TCP_Framing="octet-counted"`, are read
+				for that many bytes, so they can contain new lines. Other
+				messages are read until a new line delimiter, the `0xA` byte,
+				or a null byte is found. Messages longer than `max_length`
+				are discarded.
 				"""
 		}
 
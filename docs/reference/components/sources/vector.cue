@@ -24,7 +24,7 @@ components: sources: vector: {
 
 				interface: socket: {
 					port: _port
-					protocols: ["tcp"]
+					protocols: ["tcp", "http"]
 					ssl: "optional"
 				}
 			}
@@ -72,6 +72,19 @@ components: sources: vector: {
 				unit:    "seconds"
 			}
 		}
+		version: {
+			common:      true
+			description: "The version of the protocol, which must match the version of the upstream `vector` sinks."
+			required:    false
+			warnings: []
+			type: string: {
+				default: "1"
+				enum: {
+					"1": "Events are streamed over TCP, without acknowledgements."
+					"2": "Batches of events are sent over [gRPC](\(urls.grpc)), and acknowledged once accepted by the source."
+				}
+			}
+		}
 	}
 
 	output: logs: event: {
@@ -96,16 +109,28 @@ components: sources: vector: {
 		communication_protocol: {
 			title: "Communication Protocol"
 			body: """
-				Upstream Vector instances forward data to downstream Vector
-				instances via the TCP protocol.
+				With version 1 of the protocol, upstream Vector instances stream
+				events to downstream Vector instances over a TCP connection.
+
+				With version 2, events are sent in batches over
+				[gRPC](\(urls.grpc)), as described by the
+				[Vector service](\(urls.vector_proto)). Batches can be compressed
+				with gzip, up to 100 MiB once decompressed, and the connections are
+				reestablished when lost, so the downstream instances can sit behind
+				an HTTP/2 load balancer.
 				"""
 		}
 		message_acknowledgement: {
 			title: "Message Acknowledgement"
 			body: """
-				Currently, Vector does not perform any application level message
-				acknowledgement. While rare, this means the individual message
-				could be lost.
+				Version 1 of the protocol does not perform any application level
+				message acknowledgement. While rare, this means the individual
+				message could be lost.
+
+				With version 2, the source responds to a batch once its events were
+				accepted into its pipeline, and the sink acknowledges the events to
+				its buffer only then, retrying failed batches. Combined with a
+				disk buffer, this provides at-least-once delivery.
 				"""
 		}
 
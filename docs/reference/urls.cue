@@ -1,6 +1,7 @@
 package metadata
 
 urls: {
+	amqp:                                                     "https://www.amqp.org/"
 	apache:                                                   "https://httpd.apache.org/"
 	apache_extended_status:                                   "https://httpd.apache.org/docs/current/mod/core.html#extendedstatus"
 	apache_install:                                           "https://httpd.apache.org/docs/current/install.html"
@@ -68,6 +69,7 @@ urls: {
 	clickhouse:                                               "https://clickhouse.yandex/"
 	clickhouse_http:                                          "https://clickhouse.yandex/docs/en/interfaces/http/"
 	cloudsmith:                                               "https://cloudsmith.io/~timber/repos/vector/packages/"
+	confluent_schema_registry:                                "https://docs.confluent.io/platform/current/schema-registry/index.html"
 	console:                                                  "https://en.wikipedia.org/wiki/System_console"
 	conventional_commits:                                     "https://www.conventionalcommits.org"
 	contributing:                                             "https://github.com/timberio/vector/blob/master/CONTRIBUTING.md#setup"
@@ -98,14 +100,20 @@ urls: {
 	docker_object_labels:                                     "https://docs.docker.com/config/labels-custom-metadata/"
 	docker_setup:                                             "https://docs.docker.com/get-docker/"
 	dockerfile:                                               "https://github.com/timberio/vector/blob/master/Dockerfile"
+	dogstatsd_datagram:                                       "https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/"
 	dpkg:                                                     "https://wiki.debian.org/dpkg"
 	dry_code:                                                 "https://en.wikipedia.org/wiki/Don%27t_repeat_yourself"
 	cidr:                                                     "https://en.wikipedia.org/wiki/Classless_Inter-Domain_Routing"
 	elasticsearch:                                            "https://www.elastic.co/products/elasticsearch"
 	elasticsearch_bulk:                                       "https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-bulk.html"
+	elasticsearch_data_streams:                               "https://www.elastic.co/guide/en/elasticsearch/reference/current/data-streams.html"
 	elasticsearch_id_field:                                   "https://www.elastic.co/guide/en/elasticsearch/reference/current/mapping-id-field.html"
 	elasticsearch_id_performance:                             "https://www.elastic.co/guide/en/elasticsearch/reference/master/tune-for-indexing-speed.html#_use_auto_generated_ids"
 	elasticsearch_ignore_malformed:                           "https://www.elastic.co/guide/en/elasticsearch/reference/current/ignore-malformed.html"
+	elasticsearch_point_in_time:                              "https://www.elastic.co/guide/en/elasticsearch/reference/current/point-in-time-api.html"
+	elasticsearch_query_dsl:                                  "https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl.html"
+	elasticsearch_scroll:                                     "https://www.elastic.co/guide/en/elasticsearch/reference/current/paginate-search-results.html#scroll-search-results"
+	elasticsearch_search_after:                               "https://www.elastic.co/guide/en/elasticsearch/reference/current/paginate-search-results.html#search-after"
 	endler_dev:                                               "https://endler.dev/"
 	etsy:                                                     "https://www.etsy.com"
 	event_proto:                                              "https://github.com/timberio/vector/blob/master/proto/event.proto"
@@ -121,7 +129,7 @@ urls: {
 	gcp_cloud_storage:                                        "https://cloud.google.com/storage"
 	gcp_folders:                                              "https://cloud.google.com/resource-manager/docs/creating-managing-folders"
 	gcp_pubsub:                                               "https://cloud.google.com/pubsub/"
-	gcp_pubsub_rest:                                          "https://cloud.google.com/pubsub/docs/reference/rest/"
+	gcp_pubsub_streaming_pull:                                "https://cloud.google.com/pubsub/docs/pull#streamingpull"
 	gcp_projects:                                             "https://cloud.google.com/resource-manager/docs/creating-managing-projects"
 	gcp_resources:                                            "https://cloud.google.com/monitoring/api/resources"
 	gcp_stackdriver:                                          "https://cloud.google.com/products/operations"
@@ -132,6 +140,7 @@ urls: {
 	gcs_predefined_acl:                                       "https://cloud.google.com/storage/docs/access-control/lists#predefined-acl"
 	gcs_storage_classes:                                      "https://cloud.google.com/storage/docs/storage-classes"
 	gcs_custom_metadata:                                      "https://cloud.google.com/storage/docs/metadata#custom-metadata"
+	gelf:                                                     "https://docs.graylog.org/en/latest/pages/gelf.html"
 	git:                                                      "https://git-scm.com/"
 	github_protected_branches:                                "https://help.github.com/en/github/administering-a-repository/about-protected-branches"
 	github_sign_commits:                                      "https://help.github.com/en/github/authenticating-to-github/signing-commits"
@@ -139,6 +148,7 @@ urls: {
 	grok:                                                     "https://grokdebug.herokuapp.com/"
 	grok_debugger:                                            "https://grokdebug.herokuapp.com/"
 	grok_patterns:                                            "https://github.com/daschl/grok/tree/master/patterns"
+	grpc:                                                     "https://grpc.io/"
 	gzip:                                                     "https://www.gzip.org/"
 	haproxy:                                                  "https://www.haproxy.org/"
 	heroku_http_log_drain:                                    "https://devcenter.heroku.com/articles/log-drains#https-drains"
@@ -171,9 +181,11 @@ urls: {
 	json:                                                     "https://en.wikipedia.org/wiki/JSON"
 	json_types:                                               "https://en.wikipedia.org/wiki/JSON#Data_types_and_syntax"
 	kafka:                                                    "https://kafka.apache.org/"
+	kafka_idempotence:                                        "https://kafka.apache.org/documentation/#producerconfigs_enable.idempotence"
 	kafka_partitioning_docs:                                  "https://cwiki.apache.org/confluence/display/KAFKA/A+Guide+To+The+Kafka+Protocol#AGuideToTheKafkaProtocol-Partitioningandbootstrapping"
 	kafka_protocol:                                           "https://kafka.apache.org/protocol"
 	kafka_sasl:                                               "https://docs.confluent.io/current/kafka/authentication_sasl/index.html"
+	kafka_transactions:                                       "https://kafka.apache.org/documentation/#semantics"
 	kubernetes:                                               "https://kubernetes.io/"
 	kubernetes_accessing_api_from_pod:                        "https://kubernetes.io/docs/tasks/access-application-cluster/access-cluster/#accessing-the-api-from-a-pod"
 	kubernetes_api:                                           "https://kubernetes.io/docs/concepts/overview/kubernetes-api/"
@@ -243,7 +255,10 @@ urls: {
 	nix:                                                      "https://nixos.org/nix/"
 	nixos:                                                    "https://nixos.org/"
 	nixpkgs_9682:                                             "https://github.com/NixOS/nixpkgs/issues/9682"
+	openmetrics:                                              "https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md"
+	openmetrics_exemplars:                                    "https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md#exemplars"
 	openssl:                                                  "https://www.openssl.org/"
+	parquet:                                                  "https://parquet.apache.org/"
 	papertrail:                                               "https://www.papertrail.com/"
 	papertrail_syslog:                                        "https://help.papertrailapp.com/kb/how-it-works/http-api/#submitting-log-messages"
 	perl_windows:                                             "https://www.perl.org/get.html#win32"
@@ -262,7 +277,11 @@ urls: {
 	prometheus_remote_write:                                  "https://prometheus.io/docs/prometheus/latest/configuration/configuration/#remote_write"
 	pulsar:                                                   "https://pulsar.apache.org/"
 	pulsar_protocol:                                          "https://pulsar.apache.org/docs/en/develop-binary-protocol/"
+	rabbitmq:                                                 "https://www.rabbitmq.com/"
 	rdkafka:                                                  "https://github.com/edenhill/librdkafka"
+	redis:                                                    "https://redis.io/"
+	redis_blpop:                                              "https://redis.io/commands/blpop"
+	redis_pubsub:                                             "https://redis.io/topics/pubsub"
 	regex:                                                    "https://en.wikipedia.org/wiki/Regular_expression"
 	regex_grouping_and_flags:                                 "https://docs.rs/regex/1.3.9/regex/#grouping-and-flags"
 	regex_tester:                                             "https://rustexp.lpil.uk/"
@@ -357,6 +376,7 @@ urls: {
 	vector_performance:                                       "https://vector.dev/#performance"
 	vector_privacy_policy:                                    "https://github.com/timberio/vector/blob/master/PRIVACY.md"
 	vector_programmable_transforms:                           "https://vector.dev/components/?functions%5B%5D=program"
+	vector_proto:                                             "https://github.com/timberio/vector/blob/master/proto/vector.proto"
 	vector_pull_requests:                                     "https://github.com/timberio/vector/pulls"
 	vector_receiving_sources:                                 "https://vector.dev/components/?functions%5B%5D=receive"
 	vector_regex_parser:                                      "https://vector.dev/docs/reference/transforms/regex_parser/"
@@ -4,7 +4,8 @@ use glob::glob;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    fs, io,
+    fs,
+    io::{self, Read, Write},
     path::{Path, PathBuf},
 };
 
@@ -23,11 +24,16 @@ enum State {
 
 /// A simple JSON-friendly struct of the fingerprint/position pair, since fingerprints as objects
 /// cannot be keys in a plain JSON map.
+///
+/// The path is informational only, it is the last path the file was read from and is absent in
+/// checkpoints written by older versions.
 #[derive(Debug, Serialize, Deserialize)]
-struct Checkpoint {
-    fingerprint: FileFingerprint,
-    position: FilePosition,
-    modified: DateTime<Utc>,
+pub struct Checkpoint {
+    pub fingerprint: FileFingerprint,
+    pub position: FilePosition,
+    pub modified: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
 }
 
 pub struct Checkpointer {
@@ -37,6 +43,7 @@ pub struct Checkpointer {
     glob_string: String,
     checkpoints: HashMap<FileFingerprint, FilePosition>,
     modified_times: HashMap<FileFingerprint, DateTime<Utc>>,
+    paths: HashMap<FileFingerprint, PathBuf>,
 }
 
 impl Checkpointer {
@@ -53,9 +60,17 @@ impl Checkpointer {
             stable_file_path,
             checkpoints: HashMap::new(),
             modified_times: HashMap::new(),
+            paths: HashMap::new(),
         }
     }
 
+    /// Whether checkpoints, in either the current or the legacy format, exist in `data_dir`.
+    pub fn exists(data_dir: &Path) -> bool {
+        data_dir.join(STABLE_FILE_NAME).exists()
+            || data_dir.join(TMP_FILE_NAME).exists()
+            || data_dir.join("checkpoints").is_dir()
+    }
+
     /// Encode a fingerprint to a file name, including legacy Unknown values
     ///
     /// For each of the non-legacy variants, prepend an identifier byte that falls outside of the
@@ -69,6 +84,7 @@ impl Checkpointer {
             Checksum(c) => format!("g{:x}.{}", c, pos),
             FirstLineChecksum(c) => format!("h{:x}.{}", c, pos),
             DevInode(dev, ino) => format!("i{:x}.{:x}.{}", dev, ino, pos),
+            DevInodeChecksum(dev, ino, c) => format!("j{:x}.{:x}.{:x}.{}", dev, ino, c, pos),
             Unknown(x) => format!("{:x}.{}", x, pos),
         };
         self.directory.join(path)
@@ -99,6 +115,18 @@ impl Checkpointer {
                         .unwrap();
                 (DevInode(dev, ino), pos)
             }
+            'j' => {
+                let (dev, ino, c, pos) = scan_fmt!(
+                    file_name,
+                    "j{x}.{x}.{x}.{}",
+                    [hex u64],
+                    [hex u64],
+                    [hex u64],
+                    FilePosition
+                )
+                .unwrap();
+                (DevInodeChecksum(dev, ino, c), pos)
+            }
             _ => {
                 let (c, pos) = scan_fmt!(file_name, "{x}.{}", [hex u64], FilePosition).unwrap();
                 (Unknown(c), pos)
@@ -111,15 +139,37 @@ impl Checkpointer {
         self.modified_times.insert(fng, Utc::now());
     }
 
+    pub fn update_path(&mut self, fng: FileFingerprint, path: &Path) {
+        if self.paths.get(&fng).map_or(true, |known| known != path) {
+            self.paths.insert(fng, path.to_owned());
+        }
+    }
+
     pub fn get_checkpoint(&self, fng: FileFingerprint) -> Option<FilePosition> {
         self.checkpoints.get(&fng).cloned()
     }
 
+    pub fn remove_checkpoint(&mut self, fng: FileFingerprint) -> Option<FilePosition> {
+        self.modified_times.remove(&fng);
+        self.paths.remove(&fng);
+        self.checkpoints.remove(&fng)
+    }
+
+    /// All checkpoints currently held, ordered by the path they were last read from.
+    pub fn checkpoints(&self) -> Vec<Checkpoint> {
+        let State::V1 { mut checkpoints } = self.get_state();
+        checkpoints.sort_by(|a, b| a.path.cmp(&b.path));
+        checkpoints
+    }
+
     fn load_checkpoint(&mut self, checkpoint: Checkpoint) {
         self.checkpoints
             .insert(checkpoint.fingerprint, checkpoint.position);
         self.modified_times
             .insert(checkpoint.fingerprint, checkpoint.modified);
+        if let Some(path) = checkpoint.path {
+            self.paths.insert(checkpoint.fingerprint, path);
+        }
     }
 
     fn set_state(&mut self, state: State, ignore_before: Option<DateTime<Utc>>) {
@@ -150,6 +200,7 @@ impl Checkpointer {
                         .get(&fingerprint)
                         .cloned()
                         .unwrap_or_else(Utc::now),
+                    path: self.paths.get(&fingerprint).cloned(),
                 })
                 .collect(),
         }
@@ -168,6 +219,21 @@ impl Checkpointer {
         }
     }
 
+    /// Moves the checkpoint of a file from the fingerprint an older version
+    /// computed for it, in either format, to its current fingerprint, unless
+    /// that one already has a checkpoint.
+    pub fn migrate(&mut self, old: FileFingerprint, new: FileFingerprint) {
+        if self.checkpoints.contains_key(&new) {
+            return;
+        }
+        for old in &[old, FileFingerprint::Unknown(old.to_legacy())] {
+            if let Some(pos) = self.remove_checkpoint(*old) {
+                self.update_checkpoint(new, pos);
+                return;
+            }
+        }
+    }
+
     /// Persist the current checkpoints state to disk, making our best effort to do so in an atomic
     /// way that allow for recovering the previous state in the event of a crash.
     pub fn write_checkpoints(&mut self) -> Result<usize, io::Error> {
@@ -187,6 +253,24 @@ impl Checkpointer {
         Ok(self.checkpoints.len())
     }
 
+    /// Write all checkpoints in the same versioned format as the checkpoints file, so they can be
+    /// restored with `import`.
+    pub fn export(&self, writer: impl Write) -> Result<(), io::Error> {
+        serde_json::to_writer_pretty(writer, &self.get_state())?;
+        Ok(())
+    }
+
+    /// Replace all checkpoints with ones previously written by `export`.
+    pub fn import(&mut self, reader: impl Read) -> Result<usize, io::Error> {
+        let state = serde_json::from_reader(reader)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.checkpoints.clear();
+        self.modified_times.clear();
+        self.paths.clear();
+        self.set_state(state, None);
+        Ok(self.checkpoints.len())
+    }
+
     /// Write checkpoints to disk in the legacy format. Used for compatibility testing only.
     #[cfg(test)]
     pub fn write_legacy_checkpoints(&mut self) -> Result<usize, io::Error> {
@@ -283,6 +367,7 @@ mod test {
         Checkpoint, Checkpointer, FileFingerprint, FilePosition, STABLE_FILE_NAME, TMP_FILE_NAME,
     };
     use chrono::{Duration, Utc};
+    use std::path::PathBuf;
     use tempfile::tempdir;
 
     #[test]
@@ -291,6 +376,7 @@ mod test {
             FileFingerprint::DevInode(1, 2),
             FileFingerprint::Checksum(3456),
             FileFingerprint::FirstLineChecksum(78910),
+            FileFingerprint::DevInodeChecksum(1, 2, 3456),
             FileFingerprint::Unknown(1337),
         ];
         for fingerprint in fingerprints {
@@ -338,6 +424,7 @@ mod test {
                     fingerprint: *fingerprint,
                     position,
                     modified: *modified,
+                    path: None,
                 });
                 assert_eq!(chkptr.get_checkpoint(*fingerprint), Some(position));
                 chkptr.write_checkpoints().unwrap();
@@ -362,6 +449,7 @@ mod test {
             FileFingerprint::DevInode(1, 2),
             FileFingerprint::Checksum(3456),
             FileFingerprint::FirstLineChecksum(78910),
+            FileFingerprint::DevInodeChecksum(1, 2, 3456),
             FileFingerprint::Unknown(1337),
         ];
         for fingerprint in fingerprints {
@@ -407,6 +495,25 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_checkpointer_migrates_fingerprints() {
+        let old_fingerprint = FileFingerprint::Checksum(1);
+        let new_fingerprint = FileFingerprint::Checksum(2);
+        let position: FilePosition = 1234;
+
+        let data_dir = tempdir().unwrap();
+        let mut chkptr = Checkpointer::new(&data_dir.path());
+        chkptr.update_checkpoint(old_fingerprint, position);
+        chkptr.migrate(old_fingerprint, new_fingerprint);
+        assert_eq!(chkptr.get_checkpoint(new_fingerprint), Some(position));
+        assert_eq!(chkptr.get_checkpoint(old_fingerprint), None);
+
+        // An existing checkpoint of the new fingerprint is kept.
+        chkptr.update_checkpoint(old_fingerprint, 1);
+        chkptr.migrate(old_fingerprint, new_fingerprint);
+        assert_eq!(chkptr.get_checkpoint(new_fingerprint), Some(position));
+    }
+
     #[test]
     fn test_checkpointer_file_upgrades() {
         let fingerprint = FileFingerprint::DevInode(1, 2);
@@ -449,4 +556,55 @@ mod test {
             assert_eq!(chkptr.get_checkpoint(fingerprint), Some(position));
         }
     }
+
+    #[test]
+    fn test_checkpointer_paths() {
+        let fingerprint = FileFingerprint::Checksum(3456);
+        let legacy = FileFingerprint::Checksum(78910);
+        let path = PathBuf::from("/var/log/messages");
+
+        let data_dir = tempdir().unwrap();
+        {
+            let mut chkptr = Checkpointer::new(&data_dir.path());
+            chkptr.update_checkpoint(fingerprint, 1234);
+            chkptr.update_path(fingerprint, &path);
+            chkptr.update_checkpoint(legacy, 5678);
+            chkptr.write_checkpoints().unwrap();
+        }
+        {
+            let mut chkptr = Checkpointer::new(&data_dir.path());
+            chkptr.read_checkpoints(None);
+            let checkpoints = chkptr.checkpoints();
+            assert_eq!(checkpoints.len(), 2);
+            assert_eq!(checkpoints[0].fingerprint, legacy);
+            assert_eq!(checkpoints[0].path, None);
+            assert_eq!(checkpoints[1].fingerprint, fingerprint);
+            assert_eq!(checkpoints[1].path, Some(path));
+        }
+    }
+
+    #[test]
+    fn test_checkpointer_export_import() {
+        let fingerprint = FileFingerprint::DevInode(1, 2);
+        let removed = FileFingerprint::Checksum(3456);
+
+        let mut exported = Vec::new();
+        {
+            let data_dir = tempdir().unwrap();
+            let mut chkptr = Checkpointer::new(&data_dir.path());
+            chkptr.update_checkpoint(fingerprint, 1234);
+            chkptr.export(&mut exported).unwrap();
+        }
+        {
+            let data_dir = tempdir().unwrap();
+            let mut chkptr = Checkpointer::new(&data_dir.path());
+            chkptr.update_checkpoint(removed, 5678);
+            assert_eq!(chkptr.import(&exported[..]).unwrap(), 1);
+            assert_eq!(chkptr.get_checkpoint(fingerprint), Some(1234));
+            assert_eq!(chkptr.get_checkpoint(removed), None);
+
+            assert_eq!(chkptr.remove_checkpoint(fingerprint), Some(1234));
+            assert!(chkptr.checkpoints().is_empty());
+        }
+    }
 }
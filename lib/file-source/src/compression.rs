@@ -0,0 +1,123 @@
+use bzip2::bufread::MultiBzDecoder;
+use flate2::bufread::MultiGzDecoder;
+use std::io::{self, BufRead, Read};
+
+/// The compression formats of files that are transparently decompressed
+/// while reading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Detects the compression of a file from the magic bytes at its start.
+    /// This does not rely on file names, as rotated archives are frequently
+    /// named after the original log.
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if header.len() >= 4
+            && header.starts_with(b"BZh")
+            && (b'1'..=b'9').contains(&header[3])
+        {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
+
+    /// Wraps `reader` in a stream decoder for this format. Concatenated
+    /// streams, as produced by appending to an archive, are read as one.
+    pub fn decoder<R: BufRead + 'static>(self, reader: R) -> io::Result<Box<dyn BufRead>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(io::BufReader::new(MultiGzDecoder::new(reader))),
+            Compression::Zstd => Box::new(io::BufReader::new(
+                zstd::stream::read::Decoder::with_buffer(reader)?,
+            )),
+            Compression::Bzip2 => Box::new(io::BufReader::new(MultiBzDecoder::new(reader))),
+        })
+    }
+}
+
+/// Compressed files can't be seeked into, so positions in them are reached
+/// by decompressing and discarding everything before. Returns the number of
+/// bytes skipped, which is less than `count` if the content is shorter.
+pub fn skip(reader: &mut dyn BufRead, count: u64) -> io::Result<u64> {
+    io::copy(&mut reader.take(count), &mut io::sink())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{skip, Compression};
+    use flate2::{write::GzEncoder, Compression as GzCompression};
+    use std::io::{BufRead, Cursor, Read, Write};
+
+    const CONTENT: &[u8] = b"first line\nsecond line\n";
+
+    fn compress(compression: Compression) -> Vec<u8> {
+        match compression {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), GzCompression::fast());
+                encoder.write_all(CONTENT).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zstd => zstd::stream::encode_all(CONTENT, 0).unwrap(),
+            Compression::Bzip2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+                encoder.write_all(CONTENT).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn detects_and_decompresses() {
+        for &compression in &[Compression::Gzip, Compression::Zstd, Compression::Bzip2] {
+            let data = compress(compression);
+            assert_eq!(Compression::detect(&data), Some(compression));
+
+            let mut reader = compression.decoder(Cursor::new(data)).unwrap();
+            let mut content = Vec::new();
+            reader.read_to_end(&mut content).unwrap();
+            assert_eq!(content, CONTENT, "{:?}", compression);
+        }
+    }
+
+    #[test]
+    fn reads_concatenated_streams() {
+        for &compression in &[Compression::Gzip, Compression::Zstd, Compression::Bzip2] {
+            let mut data = compress(compression);
+            data.extend(compress(compression));
+
+            let mut reader = compression.decoder(Cursor::new(data)).unwrap();
+            let mut content = Vec::new();
+            reader.read_to_end(&mut content).unwrap();
+            assert_eq!(content, [CONTENT, CONTENT].concat(), "{:?}", compression);
+        }
+    }
+
+    #[test]
+    fn ignores_plain_content() {
+        assert_eq!(Compression::detect(CONTENT), None);
+        assert_eq!(Compression::detect(b"BZh is not bzip2"), None);
+        assert_eq!(Compression::detect(b""), None);
+    }
+
+    #[test]
+    fn skips_uncompressed_bytes() {
+        let mut reader = Compression::Zstd
+            .decoder(Cursor::new(compress(Compression::Zstd)))
+            .unwrap();
+        assert_eq!(skip(reader.as_mut(), 11).unwrap(), 11);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "second line\n");
+
+        assert_eq!(skip(reader.as_mut(), 100).unwrap(), 0);
+    }
+}
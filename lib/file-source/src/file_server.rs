@@ -95,6 +95,7 @@ where
                 &mut known_small_files,
                 &self.emitter,
             ) {
+                self.migrate_checkpoint(&path, file_id, &mut checkpointer, &mut fingerprint_buffer);
                 existing_files.push((path, file_id));
             }
         }
@@ -208,6 +209,12 @@ where
                             }
                         } else {
                             // untracked file fingerprint
+                            self.migrate_checkpoint(
+                                &path,
+                                file_id,
+                                &mut checkpointer,
+                                &mut fingerprint_buffer,
+                            );
                             self.watch_new_file(path, file_id, &mut fp_map, &checkpointer, false);
                         }
                     }
@@ -252,9 +259,27 @@ where
                 }
                 stats.record("reading", start.elapsed());
 
+                if let Some(file_position) = watcher.take_truncation() {
+                    self.emitter
+                        .emit_file_truncated(&watcher.path, file_position);
+                    // With a content based fingerprint the truncated file no
+                    // longer is the file we were reading, so leave it to be
+                    // discovered again once it can be fingerprinted.
+                    match self
+                        .fingerprinter
+                        .get_fingerprint_of_file(&watcher.path, &mut fingerprint_buffer)
+                    {
+                        Ok(fingerprint) if fingerprint == file_id => {
+                            checkpointer.update_checkpoint(file_id, watcher.get_file_position());
+                        }
+                        _ => watcher.set_dead(),
+                    }
+                }
+
                 if bytes_read > 0 {
                     global_bytes_read = global_bytes_read.saturating_add(bytes_read);
                     checkpointer.update_checkpoint(file_id, watcher.get_file_position());
+                    checkpointer.update_path(file_id, &watcher.path);
                 } else {
                     // Should the file be removed
                     if let Some(grace_period) = self.remove_after {
@@ -340,6 +365,27 @@ where
         }
     }
 
+    /// Compressed files used to be fingerprinted by their compressed bytes,
+    /// their checkpoints are moved to their current fingerprint when they
+    /// are first seen.
+    fn migrate_checkpoint(
+        &self,
+        path: &PathBuf,
+        file_id: FileFingerprint,
+        checkpointer: &mut Checkpointer,
+        buffer: &mut Vec<u8>,
+    ) {
+        if checkpointer.get_checkpoint(file_id).is_some() {
+            return;
+        }
+        if let Ok(Some(legacy_id)) = self
+            .fingerprinter
+            .get_legacy_fingerprint_of_file(path, buffer)
+        {
+            checkpointer.migrate(legacy_id, file_id);
+        }
+    }
+
     fn watch_new_file(
         &self,
         path: PathBuf,
@@ -1,7 +1,9 @@
-use crate::FilePosition;
+use crate::{
+    compression::{skip, Compression},
+    FilePosition,
+};
 use bytes::{Bytes, BytesMut};
 use chrono::{DateTime, Utc};
-use flate2::bufread::MultiGzDecoder;
 use std::{
     fs::{self, File},
     io::{self, BufRead, Seek},
@@ -22,7 +24,11 @@ pub struct FileWatcher {
     pub path: PathBuf,
     findable: bool,
     reader: Box<dyn BufRead>,
+    /// The file being read, used to notice truncation. Compressed files are
+    /// not expected to be truncated and don't have it.
+    file: Option<File>,
     file_position: FilePosition,
+    truncated_at: Option<FilePosition>,
     devno: u64,
     inode: u64,
     is_dead: bool,
@@ -47,6 +53,7 @@ impl FileWatcher {
         let f = fs::File::open(&path)?;
         let (devno, ino) = (f.portable_dev()?, f.portable_ino()?);
         let metadata = f.metadata()?;
+        let file = f.try_clone()?;
         let mut reader = io::BufReader::new(f);
 
         let too_old = if let (Some(ignore_before), Ok(modified_time)) = (
@@ -58,27 +65,31 @@ impl FileWatcher {
             false
         };
 
-        let (reader, file_position): (Box<dyn BufRead>, FilePosition) = if is_gzipped(&mut reader)?
-        {
-            if file_position != 0 || too_old {
-                // We can't accurately seek into gzipped files without manually scanning through
-                // the entire thing, so for now we simply refuse to read gzipped files for which we
-                // already have a stored file position from a previous run.
+        let compression = Compression::detect(reader.fill_buf()?);
+        let (reader, file_position): (Box<dyn BufRead>, FilePosition) = match compression {
+            Some(compression) if too_old => {
+                // Finding the end of a compressed file means decompressing
+                // all of it, only to throw it away.
                 debug!(
-                    message = "Not re-reading gzipped file with existing stored offset.",
+                    message = "Not reading compressed file older than ignore_older.",
                     ?path,
-                    %file_position
+                    ?compression
                 );
                 (Box::new(null_reader()), file_position)
-            } else {
-                (Box::new(io::BufReader::new(MultiGzDecoder::new(reader))), 0)
             }
-        } else if too_old {
-            let pos = reader.seek(io::SeekFrom::End(0)).unwrap();
-            (Box::new(reader), pos)
-        } else {
-            let pos = reader.seek(io::SeekFrom::Start(file_position)).unwrap();
-            (Box::new(reader), pos)
+            Some(compression) => {
+                let mut reader = compression.decoder(reader)?;
+                let file_position = skip(reader.as_mut(), file_position)?;
+                (reader, file_position)
+            }
+            None if too_old => {
+                let pos = reader.seek(io::SeekFrom::End(0)).unwrap();
+                (Box::new(reader), pos)
+            }
+            None => {
+                let pos = reader.seek(io::SeekFrom::Start(file_position)).unwrap();
+                (Box::new(reader), pos)
+            }
         };
 
         let ts = metadata
@@ -92,7 +103,9 @@ impl FileWatcher {
             path,
             findable: true,
             reader,
+            file: compression.map_or(Some(file), |_| None),
             file_position,
+            truncated_at: None,
             devno,
             inode: ino,
             is_dead: false,
@@ -106,19 +119,23 @@ impl FileWatcher {
     pub fn update_path(&mut self, path: PathBuf) -> io::Result<()> {
         let file_handle = File::open(&path)?;
         if (file_handle.portable_dev()?, file_handle.portable_ino()?) != (self.devno, self.inode) {
-            let mut reader = io::BufReader::new(fs::File::open(&path)?);
-            let gzipped = is_gzipped(&mut reader)?;
-            let new_reader: Box<dyn BufRead> = if gzipped {
-                if self.file_position != 0 {
-                    Box::new(null_reader())
-                } else {
-                    Box::new(io::BufReader::new(MultiGzDecoder::new(reader)))
-                }
-            } else {
-                reader.seek(io::SeekFrom::Start(self.file_position))?;
-                Box::new(reader)
-            };
+            let mut reader = io::BufReader::new(file_handle.try_clone()?);
+            // A rotated file may have been compressed in the meantime, in
+            // which case the position is reached in its uncompressed content.
+            let (new_reader, file): (Box<dyn BufRead>, _) =
+                match Compression::detect(reader.fill_buf()?) {
+                    Some(compression) => {
+                        let mut reader = compression.decoder(reader)?;
+                        skip(reader.as_mut(), self.file_position)?;
+                        (reader, None)
+                    }
+                    None => {
+                        reader.seek(io::SeekFrom::Start(self.file_position))?;
+                        (Box::new(reader), Some(file_handle.try_clone()?))
+                    }
+                };
             self.reader = new_reader;
+            self.file = file;
             self.devno = file_handle.portable_dev()?;
             self.inode = file_handle.portable_ino()?;
         }
@@ -146,6 +163,31 @@ impl FileWatcher {
         self.file_position
     }
 
+    /// Returns the position at which the file was found to be truncated, if
+    /// that happened since the last call.
+    pub fn take_truncation(&mut self) -> Option<FilePosition> {
+        self.truncated_at.take()
+    }
+
+    /// Files truncated in place, as with logrotate's `copytruncate`, keep
+    /// their identity but end before the position we have read up to. They
+    /// are then read again from the start. A file that is truncated and
+    /// grows past that position again before it is next read can't be told
+    /// apart from one that was appended to.
+    fn reset_if_truncated(&mut self) -> io::Result<()> {
+        if let Some(file) = &self.file {
+            if file.metadata()?.len() < self.file_position {
+                let mut reader = io::BufReader::new(file.try_clone()?);
+                reader.seek(io::SeekFrom::Start(0))?;
+                self.reader = Box::new(reader);
+                self.truncated_at = Some(self.file_position);
+                self.file_position = 0;
+                self.buf.clear();
+            }
+        }
+        Ok(())
+    }
+
     /// Read a single line from the underlying file
     ///
     /// This function will attempt to read a new line from its file, blocking,
@@ -175,6 +217,7 @@ impl FileWatcher {
                     // give up waiting for a newline, but it's decent.
                     Ok(Some(self.buf.split().freeze()))
                 } else {
+                    self.reset_if_truncated()?;
                     Ok(None)
                 }
             }
@@ -205,11 +248,6 @@ impl FileWatcher {
     }
 }
 
-fn is_gzipped(r: &mut io::BufReader<fs::File>) -> io::Result<bool> {
-    let header_bytes = r.fill_buf()?;
-    Ok(header_bytes.starts_with(&[0x1f, 0x8b]))
-}
-
 fn null_reader() -> impl BufRead {
     io::Cursor::new(Vec::new())
 }
@@ -281,9 +319,62 @@ fn read_until_with_max_size<R: BufRead + ?Sized>(
 
 #[cfg(test)]
 mod test {
-    use super::read_until_with_max_size;
+    use super::{read_until_with_max_size, FileWatcher};
     use bytes::BytesMut;
-    use std::io::Cursor;
+    use flate2::{write::GzEncoder, Compression};
+    use std::{
+        fs,
+        io::{Cursor, Write},
+    };
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resume_compressed_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("rotated.log.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(b"first line\nsecond line\n").unwrap();
+        fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        // Positions in compressed files are offsets into their uncompressed content.
+        let mut watcher = FileWatcher::new(path, 11, None, 1000).unwrap();
+        assert_eq!(watcher.get_file_position(), 11);
+        assert_eq!(&watcher.read_line().unwrap().unwrap()[..], b"second line");
+        assert_eq!(watcher.get_file_position(), 23);
+        assert_eq!(watcher.read_line().unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_file_is_read_from_start() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("truncated.log");
+        fs::write(&path, b"first line\nsecond line\n").unwrap();
+
+        let mut watcher = FileWatcher::new(path.clone(), 0, None, 1000).unwrap();
+        assert_eq!(&watcher.read_line().unwrap().unwrap()[..], b"first line");
+        assert_eq!(&watcher.read_line().unwrap().unwrap()[..], b"second line");
+        assert_eq!(watcher.read_line().unwrap(), None);
+        assert_eq!(watcher.take_truncation(), None);
+
+        fs::write(&path, b"new\n").unwrap();
+        assert_eq!(watcher.read_line().unwrap(), None);
+        assert_eq!(watcher.take_truncation(), Some(23));
+        assert_eq!(watcher.take_truncation(), None);
+        assert_eq!(&watcher.read_line().unwrap().unwrap()[..], b"new");
+        assert_eq!(watcher.get_file_position(), 4);
+    }
+
+    #[test]
+    fn test_checkpoint_past_end_is_truncation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("truncated.log");
+        fs::write(&path, b"new\n").unwrap();
+
+        let mut watcher = FileWatcher::new(path, 100, None, 1000).unwrap();
+        assert_eq!(watcher.read_line().unwrap(), None);
+        assert_eq!(watcher.take_truncation(), Some(100));
+        assert_eq!(&watcher.read_line().unwrap().unwrap()[..], b"new");
+    }
 
     #[test]
     fn test_read_until_with_max_size() {
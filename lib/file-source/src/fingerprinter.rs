@@ -1,10 +1,16 @@
-use crate::{metadata_ext::PortableFileExt, FileSourceInternalEvents};
+use crate::{
+    compression::{skip, Compression},
+    metadata_ext::PortableFileExt,
+    FileSourceInternalEvents,
+};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashSet,
-    fs::{self, File},
-    io::{self, Read, Seek, SeekFrom, Write},
+    fmt,
+    fs::File,
+    io::{self, BufRead, Read, Seek, SeekFrom, Write},
     path::PathBuf,
+    str::FromStr,
 };
 
 #[derive(Clone)]
@@ -24,6 +30,13 @@ pub enum FingerprintStrategy {
         ignored_header_bytes: usize,
     },
     DevInode,
+    /// Combines the device and inode with a checksum of the head of the file,
+    /// so that a new file reusing the inode of a deleted one is not mistaken
+    /// for it.
+    DevInodeChecksum {
+        bytes: usize,
+        ignored_header_bytes: usize,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
@@ -31,6 +44,7 @@ pub enum FileFingerprint {
     Checksum(u64),
     FirstLineChecksum(u64),
     DevInode(u64, u64),
+    DevInodeChecksum(u64, u64, u64),
     Unknown(u64),
 }
 
@@ -47,6 +61,13 @@ impl FileFingerprint {
                 buf.write_all(&ino.to_be_bytes()).expect("writing to array");
                 crc::crc64::checksum_ecma(&buf[..])
             }
+            DevInodeChecksum(dev, ino, c) => {
+                let mut buf = Vec::with_capacity(std::mem::size_of_val(dev) * 3);
+                buf.write_all(&dev.to_be_bytes()).expect("writing to array");
+                buf.write_all(&ino.to_be_bytes()).expect("writing to array");
+                buf.write_all(&c.to_be_bytes()).expect("writing to array");
+                crc::crc64::checksum_ecma(&buf[..])
+            }
             Unknown(c) => *c,
         }
     }
@@ -58,11 +79,93 @@ impl From<u64> for FileFingerprint {
     }
 }
 
+/// A compact textual form for showing fingerprints to operators and accepting them back, e.g.
+/// `checksum:8a3f0c2e9b1d4475` or `dev_inode:2049:1835041`.
+impl fmt::Display for FileFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use FileFingerprint::*;
+
+        match self {
+            Checksum(c) => write!(f, "checksum:{:x}", c),
+            FirstLineChecksum(c) => write!(f, "first_line_checksum:{:x}", c),
+            DevInode(dev, ino) => write!(f, "dev_inode:{}:{}", dev, ino),
+            DevInodeChecksum(dev, ino, c) => {
+                write!(f, "dev_inode_checksum:{}:{}:{:x}", dev, ino, c)
+            }
+            Unknown(c) => write!(f, "unknown:{:x}", c),
+        }
+    }
+}
+
+impl FromStr for FileFingerprint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use FileFingerprint::*;
+
+        let invalid = || format!("{:?} is not a valid fingerprint", s);
+        let hex = |value: &str| u64::from_str_radix(value, 16).map_err(|_| invalid());
+        let mut parts = s.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some("checksum"), Some(c)) => hex(c).map(Checksum),
+            (Some("first_line_checksum"), Some(c)) => hex(c).map(FirstLineChecksum),
+            (Some("dev_inode"), Some(dev_ino)) => {
+                let mut parts = dev_ino.splitn(2, ':');
+                match (parts.next(), parts.next()) {
+                    (Some(dev), Some(ino)) => Ok(DevInode(
+                        dev.parse().map_err(|_| invalid())?,
+                        ino.parse().map_err(|_| invalid())?,
+                    )),
+                    _ => Err(invalid()),
+                }
+            }
+            (Some("dev_inode_checksum"), Some(dev_ino_c)) => {
+                let mut parts = dev_ino_c.splitn(3, ':');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(dev), Some(ino), Some(c)) => Ok(DevInodeChecksum(
+                        dev.parse().map_err(|_| invalid())?,
+                        ino.parse().map_err(|_| invalid())?,
+                        hex(c)?,
+                    )),
+                    _ => Err(invalid()),
+                }
+            }
+            (Some("unknown"), Some(c)) => hex(c).map(Unknown),
+            _ => Err(invalid()),
+        }
+    }
+}
+
 impl Fingerprinter {
     pub fn get_fingerprint_of_file(
         &self,
         path: &PathBuf,
         buffer: &mut Vec<u8>,
+    ) -> Result<FileFingerprint, io::Error> {
+        self.fingerprint(path, buffer, true)
+    }
+
+    /// The fingerprint of a compressed file computed from its compressed
+    /// bytes, as versions that didn't fingerprint compressed files by their
+    /// content did. `None` when the file isn't compressed, as both are then
+    /// the same.
+    pub fn get_legacy_fingerprint_of_file(
+        &self,
+        path: &PathBuf,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<FileFingerprint>, io::Error> {
+        let mut reader = io::BufReader::new(File::open(path)?);
+        if Compression::detect(reader.fill_buf()?).is_none() {
+            return Ok(None);
+        }
+        self.fingerprint(path, buffer, false).map(Some)
+    }
+
+    fn fingerprint(
+        &self,
+        path: &PathBuf,
+        buffer: &mut Vec<u8>,
+        decompress: bool,
     ) -> Result<FileFingerprint, io::Error> {
         use FileFingerprint::*;
 
@@ -73,13 +176,25 @@ impl Fingerprinter {
                 let ino = file_handle.portable_ino()?;
                 Ok(DevInode(dev, ino))
             }
+            FingerprintStrategy::DevInodeChecksum {
+                bytes,
+                ignored_header_bytes,
+            } => {
+                let file_handle = File::open(path)?;
+                let dev = file_handle.portable_dev()?;
+                let ino = file_handle.portable_ino()?;
+                buffer.resize(bytes, 0u8);
+                let mut fp = open_content(path, ignored_header_bytes, decompress)?;
+                fp.read_exact(&mut buffer[..bytes])?;
+                let fingerprint = crc::crc64::checksum_ecma(&buffer[..]);
+                Ok(DevInodeChecksum(dev, ino, fingerprint))
+            }
             FingerprintStrategy::Checksum {
                 ignored_header_bytes,
                 bytes,
             } => {
                 buffer.resize(bytes, 0u8);
-                let mut fp = fs::File::open(path)?;
-                fp.seek(io::SeekFrom::Start(ignored_header_bytes as u64))?;
+                let mut fp = open_content(path, ignored_header_bytes, decompress)?;
                 fp.read_exact(&mut buffer[..bytes])?;
                 let fingerprint = crc::crc64::checksum_ecma(&buffer[..]);
                 Ok(Checksum(fingerprint))
@@ -89,8 +204,7 @@ impl Fingerprinter {
                 ignored_header_bytes,
             } => {
                 buffer.resize(max_line_length, 0u8);
-                let mut fp = fs::File::open(path)?;
-                fp.seek(SeekFrom::Start(ignored_header_bytes as u64))?;
+                let fp = open_content(path, ignored_header_bytes, decompress)?;
                 fingerprinter_read_until(fp, b'\n', buffer)?;
                 let fingerprint = crc::crc64::checksum_ecma(&buffer[..]);
                 Ok(FirstLineChecksum(fingerprint))
@@ -126,6 +240,34 @@ impl Fingerprinter {
     }
 }
 
+/// Opens the content of `path` past its ignored header. Compressed files are
+/// fingerprinted by their uncompressed content, so that a log that has been
+/// rotated and compressed is still recognized as the same file, unless
+/// `decompress` is false.
+fn open_content(
+    path: &PathBuf,
+    ignored_header_bytes: usize,
+    decompress: bool,
+) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = io::BufReader::new(File::open(path)?);
+    let compression = if decompress {
+        Compression::detect(reader.fill_buf()?)
+    } else {
+        None
+    };
+    match compression {
+        Some(compression) => {
+            let mut reader = compression.decoder(reader)?;
+            skip(reader.as_mut(), ignored_header_bytes as u64)?;
+            Ok(reader)
+        }
+        None => {
+            reader.seek(SeekFrom::Start(ignored_header_bytes as u64))?;
+            Ok(Box::new(reader))
+        }
+    }
+}
+
 fn fingerprinter_read_until(mut r: impl Read, delim: u8, mut buf: &mut [u8]) -> io::Result<()> {
     while !buf.is_empty() {
         let read = match r.read(buf) {
@@ -149,7 +291,7 @@ fn fingerprinter_read_until(mut r: impl Read, delim: u8, mut buf: &mut [u8]) ->
 
 #[cfg(test)]
 mod test {
-    use super::{FingerprintStrategy, Fingerprinter};
+    use super::{FileFingerprint, FingerprintStrategy, Fingerprinter};
     use std::fs;
     use tempfile::tempdir;
 
@@ -303,4 +445,122 @@ mod test {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn test_inode_checksum_fingerprint() {
+        let fingerprinter = Fingerprinter {
+            strategy: FingerprintStrategy::DevInodeChecksum {
+                bytes: 256,
+                ignored_header_bytes: 0,
+            },
+            ignore_not_found: false,
+        };
+
+        let target_dir = tempdir().unwrap();
+        let path = target_dir.path().join("reused.log");
+        let duplicate_path = target_dir.path().join("duplicate.log");
+        fs::write(&path, &vec![b'x'; 256]).unwrap();
+        fs::write(&duplicate_path, &vec![b'x'; 256]).unwrap();
+
+        let mut buf = Vec::new();
+        let first = fingerprinter
+            .get_fingerprint_of_file(&path, &mut buf)
+            .unwrap();
+        assert_eq!(
+            first,
+            fingerprinter
+                .get_fingerprint_of_file(&path, &mut buf)
+                .unwrap()
+        );
+        assert_ne!(
+            first,
+            fingerprinter
+                .get_fingerprint_of_file(&duplicate_path, &mut buf)
+                .unwrap()
+        );
+
+        // Rewriting the file in place keeps its inode, as happens when an
+        // inode is reused, but the content tells the files apart.
+        fs::write(&path, &vec![b'y'; 256]).unwrap();
+        assert_ne!(
+            first,
+            fingerprinter
+                .get_fingerprint_of_file(&path, &mut buf)
+                .unwrap()
+        );
+
+        fs::write(&path, &vec![b'y'; 10]).unwrap();
+        assert!(fingerprinter
+            .get_fingerprint_of_file(&path, &mut buf)
+            .is_err());
+    }
+
+    #[test]
+    fn test_legacy_fingerprint_of_compressed_file() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let fingerprinter = Fingerprinter {
+            strategy: FingerprintStrategy::Checksum {
+                bytes: 16,
+                ignored_header_bytes: 0,
+            },
+            ignore_not_found: false,
+        };
+
+        let target_dir = tempdir().unwrap();
+        let plain_path = target_dir.path().join("app.log");
+        let compressed_path = target_dir.path().join("app.log.gz");
+        let content = vec![b'x'; 256];
+        fs::write(&plain_path, &content).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&content).unwrap();
+        let compressed = encoder.finish().unwrap();
+        fs::write(&compressed_path, &compressed).unwrap();
+
+        let mut buf = Vec::new();
+        assert_eq!(
+            fingerprinter
+                .get_legacy_fingerprint_of_file(&plain_path, &mut buf)
+                .unwrap(),
+            None
+        );
+        let legacy = fingerprinter
+            .get_legacy_fingerprint_of_file(&compressed_path, &mut buf)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            legacy,
+            FileFingerprint::Checksum(crc::crc64::checksum_ecma(&compressed[..16]))
+        );
+        assert_ne!(
+            Some(legacy),
+            fingerprinter
+                .get_fingerprint_of_file(&compressed_path, &mut buf)
+                .ok()
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_text_roundtrip() {
+        for fingerprint in &[
+            FileFingerprint::Checksum(0x8a3f_0c2e_9b1d_4475),
+            FileFingerprint::FirstLineChecksum(78910),
+            FileFingerprint::DevInode(2049, 1_835_041),
+            FileFingerprint::DevInodeChecksum(2049, 1_835_041, 0x8a3f_0c2e_9b1d_4475),
+            FileFingerprint::Unknown(1337),
+        ] {
+            let text = fingerprint.to_string();
+            assert_eq!(text.parse::<FileFingerprint>(), Ok(*fingerprint));
+        }
+
+        assert_eq!(
+            "dev_inode:2049:1835041".parse::<FileFingerprint>(),
+            Ok(FileFingerprint::DevInode(2049, 1_835_041))
+        );
+        assert!("checksum".parse::<FileFingerprint>().is_err());
+        assert!("checksum:xyz".parse::<FileFingerprint>().is_err());
+        assert!("dev_inode:2049".parse::<FileFingerprint>().is_err());
+        assert!("inode:1".parse::<FileFingerprint>().is_err());
+    }
 }
@@ -8,6 +8,8 @@ pub trait FileSourceInternalEvents: Send + 'static {
 
     fn emit_file_resumed(&self, path: &Path, file_position: u64);
 
+    fn emit_file_truncated(&self, path: &Path, file_position: u64);
+
     fn emit_file_watch_failed(&self, path: &Path, error: Error);
 
     fn emit_file_unwatched(&self, path: &Path);
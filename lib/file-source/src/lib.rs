@@ -4,6 +4,7 @@ extern crate scan_fmt;
 extern crate tracing;
 
 mod checkpointer;
+mod compression;
 mod file_server;
 mod file_watcher;
 mod fingerprinter;
@@ -11,11 +12,12 @@ mod internal_events;
 mod metadata_ext;
 pub mod paths_provider;
 
+pub use self::checkpointer::{Checkpoint, Checkpointer};
 pub use self::file_server::{FileServer, Shutdown as FileServerShutdown};
-pub use self::fingerprinter::{FingerprintStrategy, Fingerprinter};
+pub use self::fingerprinter::{FileFingerprint, FingerprintStrategy, Fingerprinter};
 pub use self::internal_events::FileSourceInternalEvents;
 
-type FilePosition = u64;
+pub type FilePosition = u64;
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,17 @@
+use lapin::{Channel, Connection, ConnectionProperties};
+use tokio_amqp::LapinTokioExt;
+
+/// Opens a connection to the broker and a channel on it. The connection is
+/// returned alongside the channel since the channel is closed once its
+/// connection is dropped.
+pub(crate) async fn connect(
+    connection_string: &str,
+) -> Result<(Connection, Channel), lapin::Error> {
+    let conn = Connection::connect(
+        connection_string,
+        ConnectionProperties::default().with_tokio(),
+    )
+    .await?;
+    let channel = conn.create_channel().await?;
+    Ok((conn, channel))
+}
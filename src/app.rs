@@ -13,6 +13,8 @@ use futures::{
 };
 use futures01::sync::mpsc;
 
+#[cfg(feature = "sources-file")]
+use crate::checkpoints;
 #[cfg(feature = "api-client")]
 use crate::top;
 #[cfg(feature = "api")]
@@ -120,6 +122,8 @@ impl Application {
                         SubCommand::Generate(g) => generate::cmd(&g),
                         #[cfg(feature = "api-client")]
                         SubCommand::Top(t) => top::cmd(&t).await,
+                        #[cfg(feature = "sources-file")]
+                        SubCommand::Checkpoints(c) => checkpoints::cmd(&c).await,
                         #[cfg(windows)]
                         SubCommand::Service(s) => service::cmd(&s),
                     };
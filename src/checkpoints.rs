@@ -0,0 +1,650 @@
+use crate::config::DataDirLock;
+#[cfg(all(unix, feature = "sources-journald"))]
+use crate::sources::journald;
+use chrono::{DateTime, Utc};
+use file_source::{Checkpointer, FileFingerprint, FilePosition};
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Opts {
+    /// The data directory of the sources, as set by the global or the source's `data_dir` option.
+    #[structopt(short, long, default_value = "/var/lib/vector/")]
+    data_dir: PathBuf,
+
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+enum Command {
+    /// List the checkpoints of all sources, or of a single one.
+    List {
+        /// The name of the source.
+        source: Option<String>,
+
+        /// Format the list in an encoding scheme.
+        #[structopt(long, default_value = "text", possible_values = &["text", "json"])]
+        format: Format,
+    },
+
+    /// Remove the checkpoints of a source, so that it reads its input again from the start.
+    Reset {
+        /// The name of the source.
+        source: String,
+
+        /// Only remove the checkpoint of the file with this fingerprint.
+        #[structopt(long)]
+        fingerprint: Option<FileFingerprint>,
+    },
+
+    /// Set the position in a file, or the journal cursor, to continue reading from.
+    Set {
+        /// The name of the source.
+        source: String,
+
+        /// The fingerprint of the file, as shown by `list`.
+        #[structopt(long, requires = "position", conflicts_with = "cursor")]
+        fingerprint: Option<FileFingerprint>,
+
+        /// The byte offset in the file. For compressed files this is an offset in the
+        /// decompressed content.
+        #[structopt(long, requires = "fingerprint")]
+        position: Option<FilePosition>,
+
+        /// The journal cursor of a `journald` source.
+        #[structopt(long, required_unless = "fingerprint")]
+        cursor: Option<String>,
+    },
+
+    /// Write the checkpoints of a source, for backup or to move them to another host.
+    Export {
+        /// The name of the source.
+        source: String,
+
+        /// Write to this file instead of the standard output.
+        #[structopt(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Replace the checkpoints of a source with ones written by `export`.
+    Import {
+        /// The name of the source.
+        source: String,
+
+        /// Read from this file instead of the standard input.
+        #[structopt(short, long)]
+        input: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            s => Err(format!(
+                "{} is not a valid option, expected `text` or `json`",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+enum Error {
+    #[snafu(display("Could not read {:?}: {}", path, source))]
+    Io { path: PathBuf, source: io::Error },
+    #[snafu(display("No checkpoints found for source {:?} in {:?}", name, data_dir))]
+    NoCheckpoints { name: String, data_dir: PathBuf },
+    #[snafu(display(
+        "Source {:?} is in use by the Vector process {}, stop it before using this command",
+        name,
+        pid
+    ))]
+    InUse { name: String, pid: u32 },
+    #[snafu(display("Could not write checkpoints of source {:?}: {}", name, source))]
+    Write { name: String, source: io::Error },
+    #[snafu(display("Source {:?} is a {} source, which {}", name, kind, reason))]
+    Unsupported {
+        name: String,
+        kind: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl Error {
+    fn exit_code(&self) -> exitcode::ExitCode {
+        match self {
+            Error::Io { .. } | Error::Write { .. } => exitcode::IOERR,
+            Error::NoCheckpoints { .. } => exitcode::NOINPUT,
+            Error::InUse { .. } => exitcode::TEMPFAIL,
+            Error::Unsupported { .. } => exitcode::USAGE,
+        }
+    }
+}
+
+pub async fn cmd(opts: &Opts) -> exitcode::ExitCode {
+    match run(opts).await {
+        Ok(()) => exitcode::OK,
+        Err(error) => {
+            error!(message = "Checkpoints command failed.", %error);
+            error.exit_code()
+        }
+    }
+}
+
+/// The checkpoints of a single source. They are only ever opened after
+/// making sure that no running Vector process is using them.
+enum SourceCheckpoints {
+    File(Checkpointer),
+    #[cfg(all(unix, feature = "sources-journald"))]
+    Journald {
+        checkpointer: journald::Checkpointer,
+        path: PathBuf,
+    },
+}
+
+impl SourceCheckpoints {
+    async fn open(data_dir: &Path, name: &str) -> Result<Self, Error> {
+        let dir = data_dir.join(name);
+        ensure_stopped(&dir, name)?;
+
+        if Checkpointer::exists(&dir) {
+            let mut checkpointer = Checkpointer::new(&dir);
+            checkpointer.read_checkpoints(None);
+            return Ok(SourceCheckpoints::File(checkpointer));
+        }
+
+        #[cfg(all(unix, feature = "sources-journald"))]
+        {
+            let path = dir.join(journald::CHECKPOINT_FILENAME);
+            if path.exists() {
+                let checkpointer = journald::Checkpointer::new(path.clone())
+                    .await
+                    .context(Io { path: &path })?;
+                return Ok(SourceCheckpoints::Journald { checkpointer, path });
+            }
+        }
+
+        NoCheckpoints { name, data_dir }.fail()
+    }
+
+    async fn listing(&mut self) -> Result<Listing, Error> {
+        Ok(match self {
+            SourceCheckpoints::File(checkpointer) => Listing::File {
+                checkpoints: checkpointer
+                    .checkpoints()
+                    .into_iter()
+                    .map(|checkpoint| FileCheckpoint {
+                        fingerprint: checkpoint.fingerprint.to_string(),
+                        position: checkpoint.position,
+                        path: checkpoint.path,
+                        modified: checkpoint.modified,
+                    })
+                    .collect(),
+            },
+            #[cfg(all(unix, feature = "sources-journald"))]
+            SourceCheckpoints::Journald { checkpointer, path } => Listing::Journald {
+                cursor: checkpointer.get().await.context(Io {
+                    path: path.as_path(),
+                })?,
+            },
+        })
+    }
+
+    async fn reset(
+        &mut self,
+        name: &str,
+        fingerprint: Option<FileFingerprint>,
+    ) -> Result<(), Error> {
+        match self {
+            SourceCheckpoints::File(checkpointer) => {
+                match fingerprint {
+                    Some(fingerprint) => {
+                        if checkpointer.remove_checkpoint(fingerprint).is_none() {
+                            warn!(message = "No checkpoint found for fingerprint.", %fingerprint);
+                        }
+                    }
+                    None => {
+                        for checkpoint in checkpointer.checkpoints() {
+                            checkpointer.remove_checkpoint(checkpoint.fingerprint);
+                        }
+                    }
+                }
+                checkpointer.write_checkpoints().context(Write { name })?;
+            }
+            #[cfg(all(unix, feature = "sources-journald"))]
+            SourceCheckpoints::Journald { path, .. } => {
+                if fingerprint.is_some() {
+                    return Unsupported {
+                        name,
+                        kind: "journald",
+                        reason: "has no file fingerprints",
+                    }
+                    .fail();
+                }
+                fs::remove_file(path).context(Write { name })?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn set(
+        &mut self,
+        name: &str,
+        fingerprint: Option<FileFingerprint>,
+        position: Option<FilePosition>,
+        cursor: Option<String>,
+    ) -> Result<(), Error> {
+        match (self, fingerprint, position, cursor) {
+            (SourceCheckpoints::File(checkpointer), Some(fingerprint), Some(position), None) => {
+                checkpointer.update_checkpoint(fingerprint, position);
+                checkpointer.write_checkpoints().context(Write { name })?;
+            }
+            (SourceCheckpoints::File(_), ..) => {
+                return Unsupported {
+                    name,
+                    kind: "file",
+                    reason: "needs a `--fingerprint` and a `--position` instead of a `--cursor`",
+                }
+                .fail();
+            }
+            #[cfg(all(unix, feature = "sources-journald"))]
+            (SourceCheckpoints::Journald { checkpointer, .. }, None, None, Some(cursor)) => {
+                checkpointer.set(&cursor).await.context(Write { name })?;
+            }
+            #[cfg(all(unix, feature = "sources-journald"))]
+            (SourceCheckpoints::Journald { .. }, ..) => {
+                return Unsupported {
+                    name,
+                    kind: "journald",
+                    reason: "needs a `--cursor` instead of a `--fingerprint` and a `--position`",
+                }
+                .fail();
+            }
+        }
+        Ok(())
+    }
+
+    async fn export(&mut self, name: &str, mut writer: impl Write) -> Result<(), Error> {
+        match self {
+            SourceCheckpoints::File(checkpointer) => {
+                checkpointer.export(&mut writer).context(Write { name })?;
+                writeln!(writer).context(Write { name })?;
+            }
+            #[cfg(all(unix, feature = "sources-journald"))]
+            SourceCheckpoints::Journald { checkpointer, .. } => {
+                if let Some(cursor) = checkpointer.get().await.context(Write { name })? {
+                    writeln!(writer, "{}", cursor).context(Write { name })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn import(&mut self, name: &str, mut reader: impl Read) -> Result<(), Error> {
+        match self {
+            SourceCheckpoints::File(checkpointer) => {
+                let count = checkpointer.import(&mut reader).context(Write { name })?;
+                checkpointer.write_checkpoints().context(Write { name })?;
+                info!(message = "Imported checkpoints.", %count);
+            }
+            #[cfg(all(unix, feature = "sources-journald"))]
+            SourceCheckpoints::Journald { checkpointer, path } => {
+                let mut contents = String::new();
+                reader
+                    .read_to_string(&mut contents)
+                    .context(Write { name })?;
+                match contents.lines().next().filter(|cursor| !cursor.is_empty()) {
+                    Some(cursor) => checkpointer.set(cursor).await.context(Write { name })?,
+                    None => fs::remove_file(path).context(Write { name })?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct SourceListing {
+    source: String,
+    #[serde(flatten)]
+    listing: Listing,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Listing {
+    File {
+        checkpoints: Vec<FileCheckpoint>,
+    },
+    #[cfg(all(unix, feature = "sources-journald"))]
+    Journald {
+        cursor: Option<String>,
+    },
+}
+
+#[derive(Serialize)]
+struct FileCheckpoint {
+    fingerprint: String,
+    position: FilePosition,
+    path: Option<PathBuf>,
+    modified: DateTime<Utc>,
+}
+
+async fn run(opts: &Opts) -> Result<(), Error> {
+    let data_dir = &opts.data_dir;
+    match &opts.command {
+        Command::List { source, format } => {
+            let names = match source {
+                Some(name) => vec![name.clone()],
+                None => source_names(data_dir)?,
+            };
+            let mut listings = Vec::new();
+            for name in names {
+                let mut checkpoints = SourceCheckpoints::open(data_dir, &name).await?;
+                let listing = checkpoints.listing().await?;
+                listings.push(SourceListing {
+                    source: name,
+                    listing,
+                });
+            }
+            print_listings(&listings, format);
+        }
+        Command::Reset {
+            source,
+            fingerprint,
+        } => {
+            let _lock = lock(data_dir, source)?;
+            SourceCheckpoints::open(data_dir, source)
+                .await?
+                .reset(source, *fingerprint)
+                .await?;
+        }
+        Command::Set {
+            source,
+            fingerprint,
+            position,
+            cursor,
+        } => {
+            let _lock = lock(data_dir, source)?;
+            SourceCheckpoints::open(data_dir, source)
+                .await?
+                .set(source, *fingerprint, *position, cursor.clone())
+                .await?;
+        }
+        Command::Export { source, output } => {
+            let mut checkpoints = SourceCheckpoints::open(data_dir, source).await?;
+            match output {
+                Some(path) => {
+                    let file = fs::File::create(path).context(Write { name: source })?;
+                    checkpoints.export(source, file).await?;
+                }
+                None => checkpoints.export(source, io::stdout()).await?,
+            }
+        }
+        Command::Import { source, input } => {
+            let _lock = lock(data_dir, source)?;
+            let mut checkpoints = SourceCheckpoints::open(data_dir, source).await?;
+            match input {
+                Some(path) => {
+                    let file = fs::File::open(path).context(Io { path })?;
+                    checkpoints.import(source, file).await?;
+                }
+                None => checkpoints.import(source, io::stdin()).await?,
+            }
+        }
+    }
+    Ok(())
+}
+
+fn ensure_stopped(dir: &Path, name: &str) -> Result<(), Error> {
+    match DataDirLock::holder(dir).context(Io { path: dir })? {
+        Some(pid) if pid != std::process::id() => InUse { name, pid }.fail(),
+        _ => Ok(()),
+    }
+}
+
+/// Keeps Vector from starting the source while its checkpoints are changed.
+fn lock(data_dir: &Path, name: &str) -> Result<Option<DataDirLock>, Error> {
+    let dir = data_dir.join(name);
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    ensure_stopped(&dir, name)?;
+    DataDirLock::acquire(&dir).map(Some).context(Write { name })
+}
+
+/// The names of the sources with checkpoints in `data_dir`, which keeps
+/// the state of each source in a subdirectory named after it.
+fn source_names(data_dir: &Path) -> Result<Vec<String>, Error> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(data_dir).context(Io { path: data_dir })? {
+        let path = entry.context(Io { path: data_dir })?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let has_checkpoints = Checkpointer::exists(&path);
+        #[cfg(all(unix, feature = "sources-journald"))]
+        let has_checkpoints = has_checkpoints || path.join(journald::CHECKPOINT_FILENAME).exists();
+        if has_checkpoints {
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                names.push(name.to_owned());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn print_listings(listings: &[SourceListing], format: &Format) {
+    match format {
+        Format::Text => {
+            for (i, source) in listings.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                match &source.listing {
+                    Listing::File { checkpoints } => {
+                        println!("{} (file):", source.source);
+                        for checkpoint in checkpoints {
+                            let path = checkpoint
+                                .path
+                                .as_ref()
+                                .map(|path| path.display().to_string())
+                                .unwrap_or_else(|| "(unknown path)".into());
+                            println!(
+                                "- {} at {} ({}, modified {})",
+                                path,
+                                checkpoint.position,
+                                checkpoint.fingerprint,
+                                checkpoint.modified.to_rfc3339()
+                            );
+                        }
+                    }
+                    #[cfg(all(unix, feature = "sources-journald"))]
+                    Listing::Journald { cursor } => {
+                        println!("{} (journald):", source.source);
+                        println!("- cursor {}", cursor.as_deref().unwrap_or("(none)"));
+                    }
+                }
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string(listings).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_checkpoints(dir: &Path, checkpoints: &[(FileFingerprint, FilePosition)]) {
+        fs::create_dir_all(dir).unwrap();
+        let mut checkpointer = Checkpointer::new(dir);
+        for &(fingerprint, position) in checkpoints {
+            checkpointer.update_checkpoint(fingerprint, position);
+        }
+        checkpointer.write_checkpoints().unwrap();
+    }
+
+    fn read_checkpoints(dir: &Path) -> Checkpointer {
+        let mut checkpointer = Checkpointer::new(dir);
+        checkpointer.read_checkpoints(None);
+        checkpointer
+    }
+
+    async fn run_command(data_dir: &Path, command: Command) -> Result<(), Error> {
+        run(&Opts {
+            data_dir: data_dir.into(),
+            command,
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn changes_file_checkpoints() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let dir = data_dir.path().join("in");
+        let first = FileFingerprint::Checksum(1);
+        let second = FileFingerprint::DevInode(2, 3);
+        write_checkpoints(&dir, &[(first, 100), (second, 200)]);
+
+        assert_eq!(source_names(data_dir.path()).unwrap(), vec!["in"]);
+
+        run_command(
+            data_dir.path(),
+            Command::Set {
+                source: "in".into(),
+                fingerprint: Some(first),
+                position: Some(50),
+                cursor: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(read_checkpoints(&dir).get_checkpoint(first), Some(50));
+
+        run_command(
+            data_dir.path(),
+            Command::Reset {
+                source: "in".into(),
+                fingerprint: Some(second),
+            },
+        )
+        .await
+        .unwrap();
+        let checkpointer = read_checkpoints(&dir);
+        assert_eq!(checkpointer.get_checkpoint(first), Some(50));
+        assert_eq!(checkpointer.get_checkpoint(second), None);
+
+        run_command(
+            data_dir.path(),
+            Command::Reset {
+                source: "in".into(),
+                fingerprint: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(read_checkpoints(&dir).checkpoints().is_empty());
+
+        // The command's own lock is released again.
+        assert_eq!(DataDirLock::holder(&dir).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn exports_and_imports_file_checkpoints() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let fingerprint = FileFingerprint::Checksum(1);
+        write_checkpoints(&data_dir.path().join("old"), &[(fingerprint, 100)]);
+        write_checkpoints(&data_dir.path().join("new"), &[]);
+
+        let export = data_dir.path().join("export.json");
+        run_command(
+            data_dir.path(),
+            Command::Export {
+                source: "old".into(),
+                output: Some(export.clone()),
+            },
+        )
+        .await
+        .unwrap();
+        run_command(
+            data_dir.path(),
+            Command::Import {
+                source: "new".into(),
+                input: Some(export),
+            },
+        )
+        .await
+        .unwrap();
+
+        let checkpointer = read_checkpoints(&data_dir.path().join("new"));
+        assert_eq!(checkpointer.get_checkpoint(fingerprint), Some(100));
+    }
+
+    #[tokio::test]
+    async fn fails_without_checkpoints() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let error = run_command(
+            data_dir.path(),
+            Command::Reset {
+                source: "missing".into(),
+                fingerprint: None,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, Error::NoCheckpoints { .. }));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn refuses_while_vector_is_running() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let dir = data_dir.path().join("in");
+        let fingerprint = FileFingerprint::Checksum(1);
+        write_checkpoints(&dir, &[(fingerprint, 100)]);
+        // Stands in for a running Vector, as locks on separate open files
+        // conflict like those of separate processes do.
+        fs::write(dir.join("vector.lock"), "1").unwrap();
+        let held = fs::File::open(dir.join("vector.lock")).unwrap();
+        nix::fcntl::flock(
+            std::os::unix::io::AsRawFd::as_raw_fd(&held),
+            nix::fcntl::FlockArg::LockExclusiveNonblock,
+        )
+        .unwrap();
+
+        let error = run_command(
+            data_dir.path(),
+            Command::Reset {
+                source: "in".into(),
+                fingerprint: None,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, Error::InUse { pid: 1, .. }));
+        assert_eq!(
+            read_checkpoints(&dir).get_checkpoint(fingerprint),
+            Some(100)
+        );
+    }
+}
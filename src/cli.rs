@@ -1,3 +1,5 @@
+#[cfg(feature = "sources-file")]
+use crate::checkpoints;
 #[cfg(feature = "api-client")]
 use crate::top;
 use crate::{generate, get_version, list, unit_test, validate};
@@ -29,17 +31,20 @@ impl Opts {
     }
 
     pub fn log_level(&self) -> &'static str {
-        let (quiet_level, verbose_level) = match self.sub_command {
+        let quiet_sub_command = match self.sub_command {
             Some(SubCommand::Validate(_))
             | Some(SubCommand::Generate(_))
-            | Some(SubCommand::List(_)) => {
-                if self.root.verbose == 0 {
-                    (self.root.quiet + 1, self.root.verbose)
-                } else {
-                    (self.root.quiet, self.root.verbose - 1)
-                }
-            }
-            _ => (self.root.quiet, self.root.verbose),
+            | Some(SubCommand::List(_)) => true,
+            #[cfg(feature = "sources-file")]
+            Some(SubCommand::Checkpoints(_)) => true,
+            _ => false,
+        };
+        let (quiet_level, verbose_level) = if !quiet_sub_command {
+            (self.root.quiet, self.root.verbose)
+        } else if self.root.verbose == 0 {
+            (self.root.quiet + 1, self.root.verbose)
+        } else {
+            (self.root.quiet, self.root.verbose - 1)
         };
         match quiet_level {
             0 => match verbose_level {
@@ -118,6 +123,10 @@ pub enum SubCommand {
     #[cfg(feature = "api-client")]
     Top(top::Opts),
 
+    /// Inspect and change the checkpoints of file and journald sources while Vector is stopped.
+    #[cfg(feature = "sources-file")]
+    Checkpoints(checkpoints::Opts),
+
     /// Manage the vector service.
     #[cfg(windows)]
     Service(service::Opts),
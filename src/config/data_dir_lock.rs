@@ -0,0 +1,259 @@
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+const LOCK_FILE_NAME: &str = "vector.lock";
+
+lazy_static! {
+    /// The lock files held by this process and how many handles share each,
+    /// so that a source rebuilt on reload can share the lock of the instance
+    /// it replaces.
+    static ref HELD: Mutex<HashMap<PathBuf, (usize, File)>> = Mutex::new(HashMap::new());
+}
+
+/// Marks a source's data directory as in use by a running Vector process, so
+/// that tools modifying its state, like `vector checkpoints`, can refuse to do
+/// so underneath it.
+///
+/// On Unix the lock file is locked with `flock` for as long as it is held, so
+/// a lock left behind by a process that died is free to be taken again.
+/// Elsewhere the lock file is created exclusively, and a stale one has to be
+/// removed by hand. The lock file holds the process id and is removed on drop.
+#[derive(Debug)]
+pub struct DataDirLock {
+    path: PathBuf,
+}
+
+impl DataDirLock {
+    pub fn acquire(data_dir: &Path) -> io::Result<Self> {
+        let path = data_dir.join(LOCK_FILE_NAME);
+        let mut held = HELD.lock().unwrap();
+        if let Some((count, _)) = held.get_mut(&path) {
+            *count += 1;
+            return Ok(DataDirLock { path });
+        }
+
+        let mut file = match lock(&path)? {
+            Some(file) => file,
+            None => {
+                let pid = read_pid(&path)?;
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "Data directory {:?} is already in use by process {}",
+                        data_dir, pid
+                    ),
+                ));
+            }
+        };
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+
+        held.insert(path.clone(), (1, file));
+        Ok(DataDirLock { path })
+    }
+
+    /// Returns the id of the process holding the lock on `data_dir`, or 0 if
+    /// that process hasn't written its id yet.
+    pub fn holder(data_dir: &Path) -> io::Result<Option<u32>> {
+        let path = data_dir.join(LOCK_FILE_NAME);
+        if is_locked(&path)? {
+            read_pid(&path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl Drop for DataDirLock {
+    fn drop(&mut self) {
+        let mut held = HELD.lock().unwrap();
+        let count = match held.get_mut(&self.path) {
+            Some((count, _)) => count,
+            None => return,
+        };
+        *count -= 1;
+        if *count > 0 {
+            return;
+        }
+        let (_, file) = held.remove(&self.path).expect("Lock was just found.");
+
+        // The file is removed while it is still locked, so it is never
+        // removed from underneath another process.
+        match is_same_file(&file, &self.path) {
+            Ok(true) => {
+                if let Err(error) = fs::remove_file(&self.path) {
+                    warn!(message = "Unable to remove data directory lock.", path = ?self.path, %error);
+                }
+            }
+            Ok(false) => {
+                warn!(message = "Data directory lock was replaced by another process.", path = ?self.path)
+            }
+            Err(error) => {
+                warn!(message = "Unable to check data directory lock.", path = ?self.path, %error)
+            }
+        }
+    }
+}
+
+fn read_pid(path: &Path) -> io::Result<u32> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error),
+    };
+    Ok(contents.trim().parse().unwrap_or(0))
+}
+
+/// Opens and locks the lock file at `path`, or returns `None` if another
+/// open lock file holds it.
+#[cfg(unix)]
+fn lock(path: &Path) -> io::Result<Option<File>> {
+    use nix::fcntl::FlockArg;
+
+    loop {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path)?;
+        if !flock(&file, FlockArg::LockExclusiveNonblock)? {
+            return Ok(None);
+        }
+        // The previous holder removes the file before unlocking it, so the
+        // file locked here may no longer be the one at `path`.
+        if is_same_file(&file, path)? {
+            return Ok(Some(file));
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_locked(path: &Path) -> io::Result<bool> {
+    use nix::fcntl::FlockArg;
+
+    match File::open(path) {
+        // Released again when the file is closed.
+        Ok(file) => flock(&file, FlockArg::LockSharedNonblock).map(|locked| !locked),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns whether the lock was taken, or `false` if it is held elsewhere.
+#[cfg(unix)]
+fn flock(file: &File, arg: nix::fcntl::FlockArg) -> io::Result<bool> {
+    use nix::errno::Errno;
+    use std::os::unix::io::AsRawFd;
+
+    match nix::fcntl::flock(file.as_raw_fd(), arg) {
+        Ok(()) => Ok(true),
+        Err(error) => match error.as_errno() {
+            Some(Errno::EWOULDBLOCK) => Ok(false),
+            Some(errno) => Err(io::Error::from_raw_os_error(errno as i32)),
+            None => Err(io::Error::new(io::ErrorKind::Other, error)),
+        },
+    }
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let opened = file.metadata()?;
+    match fs::metadata(path) {
+        Ok(current) => Ok(opened.dev() == current.dev() && opened.ino() == current.ino()),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(not(unix))]
+fn lock(path: &Path) -> io::Result<Option<File>> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(file) => Ok(Some(file)),
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(not(unix))]
+fn is_locked(path: &Path) -> io::Result<bool> {
+    Ok(path.exists())
+}
+
+/// Nothing else creates the lock file once it was created exclusively.
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &Path) -> io::Result<bool> {
+    Ok(path.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_is_held_until_dropped() {
+        let data_dir = tempfile::tempdir().unwrap();
+        assert_eq!(DataDirLock::holder(data_dir.path()).unwrap(), None);
+
+        let lock = DataDirLock::acquire(data_dir.path()).unwrap();
+        assert_eq!(
+            DataDirLock::holder(data_dir.path()).unwrap(),
+            Some(std::process::id())
+        );
+
+        // A source rebuilt on reload shares the lock of the one it replaces.
+        let shared = DataDirLock::acquire(data_dir.path()).unwrap();
+        drop(lock);
+        assert_eq!(
+            DataDirLock::holder(data_dir.path()).unwrap(),
+            Some(std::process::id())
+        );
+
+        drop(shared);
+        assert_eq!(DataDirLock::holder(data_dir.path()).unwrap(), None);
+        assert!(!data_dir.path().join(LOCK_FILE_NAME).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ignores_stale_lock() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let path = data_dir.path().join(LOCK_FILE_NAME);
+        fs::write(&path, "2147483646").unwrap();
+        assert_eq!(DataDirLock::holder(data_dir.path()).unwrap(), None);
+
+        let lock = DataDirLock::acquire(data_dir.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            std::process::id().to_string()
+        );
+        drop(lock);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_lock_held_elsewhere() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let path = data_dir.path().join(LOCK_FILE_NAME);
+        // Locks on separate open files conflict like those of separate
+        // processes do.
+        let other = lock(&path).unwrap().unwrap();
+        fs::write(&path, "2147483646").unwrap();
+
+        assert_eq!(
+            DataDirLock::holder(data_dir.path()).unwrap(),
+            Some(2_147_483_646)
+        );
+        assert!(DataDirLock::acquire(data_dir.path()).is_err());
+
+        drop(other);
+        assert_eq!(DataDirLock::holder(data_dir.path()).unwrap(), None);
+    }
+}
@@ -17,6 +17,7 @@ pub mod api;
 mod builder;
 mod compiler;
 pub mod component;
+mod data_dir_lock;
 mod diff;
 mod loading;
 mod log_schema;
@@ -26,6 +27,7 @@ mod vars;
 pub mod watcher;
 
 pub use builder::ConfigBuilder;
+pub use data_dir_lock::DataDirLock;
 pub use diff::ConfigDiff;
 pub use loading::{load_from_paths, load_from_str, process_paths, CONFIG_PATHS};
 pub use log_schema::{log_schema, LogSchema, LOG_SCHEMA};
@@ -0,0 +1,71 @@
+use crate::rusoto;
+use http::{
+    header::{HeaderName, HeaderValue},
+    Request,
+};
+use rusoto_credential::{CredentialsError, ProvideAwsCredentials};
+use rusoto_signature::SignedRequest;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+enum ElasticSearchError {
+    #[snafu(display("Could not generate AWS credentials: {:?}", source))]
+    AWSCredentialsGenerateFailed { source: CredentialsError },
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "strategy")]
+pub enum ElasticSearchAuth {
+    Basic { user: String, password: String },
+    Aws { assume_role: Option<String> },
+}
+
+impl ElasticSearchAuth {
+    pub fn apply<B>(&self, req: &mut Request<B>) {
+        if let Self::Basic { user, password } = &self {
+            use headers::{Authorization, HeaderMapExt};
+            let auth = Authorization::basic(&user, &password);
+            req.headers_mut().typed_insert(auth);
+        }
+    }
+
+    /// The value of the `Authorization` header of the basic authentication.
+    pub fn basic_authorization(&self) -> Option<String> {
+        match self {
+            Self::Basic { user, password } => {
+                let token = format!("{}:{}", user, password);
+                Some(format!("Basic {}", base64::encode(token.as_bytes())))
+            }
+            Self::Aws { .. } => None,
+        }
+    }
+}
+
+/// Signs the request with the AWS credentials and copies the signature
+/// headers to the builder.
+pub async fn finish_signer(
+    signer: &mut SignedRequest,
+    credentials_provider: &rusoto::AwsCredentialsProvider,
+    mut builder: http::request::Builder,
+) -> crate::Result<http::request::Builder> {
+    let credentials = credentials_provider
+        .credentials()
+        .await
+        .context(AWSCredentialsGenerateFailed)?;
+
+    signer.sign(&credentials);
+
+    for (name, values) in signer.headers() {
+        let header_name = name
+            .parse::<HeaderName>()
+            .expect("Could not parse header name.");
+        for value in values {
+            let header_value =
+                HeaderValue::from_bytes(value).expect("Could not parse header value.");
+            builder = builder.header(&header_name, header_value);
+        }
+    }
+
+    Ok(builder)
+}
@@ -0,0 +1,139 @@
+//! A transport for the clients generated by `tonic`, connecting the same way
+//! as `HttpClient` does.
+
+use crate::{
+    dns::Resolver,
+    tls::{tls_connector_builder, MaybeTlsSettings},
+};
+use http::{Request, Uri};
+use hyper::client::{Client, HttpConnector, ResponseFuture};
+use hyper_openssl::HttpsConnector;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tonic::body::BoxBody;
+use tower::Service;
+
+/// How long a connection is used for new requests before being replaced.
+const MAX_CONNECTION_AGE: Duration = Duration::from_secs(300);
+
+type Connector = HttpsConnector<HttpConnector<Resolver>>;
+
+pub type Channel = WithOrigin<Reconnecting>;
+
+/// Builds a channel sending the requests to `origin`, over TLS if its scheme
+/// is `https`. The connections are established lazily and reestablished
+/// when lost, or once they are older than `MAX_CONNECTION_AGE`.
+pub fn channel(origin: Uri, tls_settings: impl Into<MaybeTlsSettings>) -> crate::Result<Channel> {
+    let mut http = HttpConnector::new_with_resolver(Resolver);
+    http.enforce_http(false);
+
+    let settings = tls_settings.into();
+    let mut tls = tls_connector_builder(&settings)?;
+    // Load balancers and Google's front ends only speak gRPC when HTTP/2 was
+    // negotiated.
+    tls.set_alpn_protos(b"\x02h2")?;
+    let mut https = HttpsConnector::with_connector(http, tls)?;
+
+    let settings = settings.tls().cloned();
+    https.set_callback(move |c, _uri| {
+        if let Some(settings) = &settings {
+            settings.apply_connect_configuration(c);
+        }
+
+        Ok(())
+    });
+
+    Ok(WithOrigin {
+        inner: Reconnecting::new(https),
+        origin,
+    })
+}
+
+/// An HTTP/2 client replacing its connection once it gets old. The requests
+/// are multiplexed over a single connection, so without this a channel
+/// would keep talking to the backend of a load balancer it first reached.
+/// The requests in flight finish on the connection they were sent on.
+#[derive(Clone)]
+pub struct Reconnecting {
+    connector: Connector,
+    client: Arc<Mutex<(Client<Connector, BoxBody>, Instant)>>,
+}
+
+impl Reconnecting {
+    fn new(connector: Connector) -> Self {
+        let client = Self::client(&connector);
+        Self {
+            connector,
+            client: Arc::new(Mutex::new((client, Instant::now()))),
+        }
+    }
+
+    fn client(connector: &Connector) -> Client<Connector, BoxBody> {
+        Client::builder().http2_only(true).build(connector.clone())
+    }
+}
+
+impl fmt::Debug for Reconnecting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reconnecting")
+            .field("client", &self.client)
+            .finish()
+    }
+}
+
+impl Service<Request<BoxBody>> for Reconnecting {
+    type Response = http::Response<hyper::Body>;
+    type Error = hyper::Error;
+    type Future = ResponseFuture;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The client is always ready.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<BoxBody>) -> Self::Future {
+        let mut client = self.client.lock().unwrap();
+        if client.1.elapsed() >= MAX_CONNECTION_AGE {
+            debug!(message = "Replacing the gRPC connection.");
+            *client = (Self::client(&self.connector), Instant::now());
+        }
+        client.0.request(request)
+    }
+}
+
+/// Sets the scheme and authority of the requests, as the generated clients
+/// only set their path.
+#[derive(Clone, Debug)]
+pub struct WithOrigin<S> {
+    inner: S,
+    origin: Uri,
+}
+
+impl<S, B> Service<Request<B>> for WithOrigin<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let (mut parts, body) = request.into_parts();
+
+        let origin = self.origin.clone().into_parts();
+        let mut uri = parts.uri.into_parts();
+        uri.scheme = origin.scheme;
+        uri.authority = origin.authority;
+        parts.uri = Uri::from_parts(uri).expect("Origin with a path is a valid URI.");
+
+        self.inner.call(Request::from_parts(parts, body))
+    }
+}
@@ -0,0 +1,79 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct AmqpEventReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for AmqpEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received one event.", rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct AmqpEventFailed {
+    pub error: lapin::Error,
+}
+
+impl InternalEvent for AmqpEventFailed {
+    fn emit_logs(&self) {
+        error!(message = "Failed to read message.", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_failed_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct AmqpAckFailed {
+    pub error: lapin::Error,
+}
+
+impl InternalEvent for AmqpAckFailed {
+    fn emit_logs(&self) {
+        error!(message = "Failed to acknowledge message.", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("consumer_acknowledgements_failed_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct AmqpEventSent {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for AmqpEventSent {
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct AmqpMissingKeys<'a> {
+    pub keys: &'a [String],
+}
+
+impl<'a> InternalEvent for AmqpMissingKeys<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Keys do not exist on the event; dropping event.",
+            missing_keys = ?self.keys,
+            rate_limit_secs = 30,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("missing_keys_total", 1);
+    }
+}
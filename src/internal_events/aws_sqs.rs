@@ -0,0 +1,66 @@
+use super::InternalEvent;
+use metrics::counter;
+use rusoto_core::RusotoError;
+use rusoto_sqs::ReceiveMessageError;
+
+#[derive(Debug)]
+pub struct AwsSqsMessageReceiveSucceeded {
+    pub count: usize,
+}
+
+impl InternalEvent for AwsSqsMessageReceiveSucceeded {
+    fn emit_logs(&self) {
+        trace!(message = "Received SQS messages.", count = %self.count);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sqs_message_receive_succeeded_total", 1);
+        counter!("sqs_message_received_messages_total", self.count as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct AwsSqsMessageReceiveFailed<'a> {
+    pub error: &'a RusotoError<ReceiveMessageError>,
+}
+
+impl<'a> InternalEvent for AwsSqsMessageReceiveFailed<'a> {
+    fn emit_logs(&self) {
+        error!(message = "Failed to fetch SQS events.", error = %self.error, rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sqs_message_receive_failed_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct AwsSqsEventReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for AwsSqsEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received one event.", rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct AwsSqsMessageDeleteFailed<'a> {
+    pub error: &'a str,
+}
+
+impl<'a> InternalEvent for AwsSqsMessageDeleteFailed<'a> {
+    fn emit_logs(&self) {
+        error!(message = "Failed to delete SQS messages.", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("sqs_message_delete_failed_total", 1);
+    }
+}
@@ -0,0 +1,32 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct ClickhouseRowRejected<'a> {
+    pub error: &'a crate::sinks::clickhouse::RowError,
+}
+
+impl<'a> InternalEvent for ClickhouseRowRejected<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Event does not match the table schema; dropping event.",
+            error = %self.error,
+            rate_limit_secs = 30,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("rows_rejected_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct ClickhouseSchemaFetched {
+    pub columns: usize,
+}
+
+impl InternalEvent for ClickhouseSchemaFetched {
+    fn emit_logs(&self) {
+        debug!(message = "Fetched table schema.", columns = %self.columns);
+    }
+}
@@ -165,3 +165,58 @@ impl<'a> InternalEvent for DockerLoggingDriverUnsupported<'a> {
         counter!("logging_driver_errors_total", 1);
     }
 }
+
+#[derive(Debug)]
+pub struct DockerContainerExcluded<'a> {
+    pub container_id: &'a str,
+}
+
+impl<'a> InternalEvent for DockerContainerExcluded<'a> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Container excluded by label.",
+            container_id = %self.container_id
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct DockerContainerLabelConfigInvalid<'a> {
+    pub error: crate::Error,
+    pub container_id: &'a str,
+}
+
+impl<'a> InternalEvent for DockerContainerLabelConfigInvalid<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Invalid multiline labels, using the source settings instead.",
+            error = %self.error,
+            container_id = %self.container_id,
+            rate_limit_secs = 10
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("label_config_errors_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct DockerStatsReceived<'a> {
+    pub count: usize,
+    pub container_id: &'a str,
+}
+
+impl<'a> InternalEvent for DockerStatsReceived<'a> {
+    fn emit_logs(&self) {
+        trace!(
+            message = "Received container stats.",
+            count = %self.count,
+            container_id = %self.container_id
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", self.count as u64);
+    }
+}
@@ -36,3 +36,124 @@ impl<'a> InternalEvent for ElasticSearchMissingKeys<'a> {
         counter!("missing_keys_total", 1);
     }
 }
+
+#[derive(Debug)]
+pub struct ElasticSearchInvalidBulkAction<'a> {
+    pub action: &'a str,
+}
+
+impl<'a> InternalEvent for ElasticSearchInvalidBulkAction<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Invalid bulk action; dropping event.",
+            action = %self.action,
+            rate_limit_secs = 30,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("invalid_bulk_action_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct ElasticSearchBulkResponseMismatch {
+    pub items: usize,
+    pub results: usize,
+}
+
+impl InternalEvent for ElasticSearchBulkResponseMismatch {
+    fn emit_logs(&self) {
+        error!(
+            message = "Bulk response doesn't match the items of the request; dropping the request.",
+            items = %self.items,
+            results = %self.results,
+            rate_limit_secs = 10,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_failed_total", self.items as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct ElasticSearchItemRejected<'a> {
+    pub action: &'a str,
+    pub index: Option<&'a str>,
+    pub id: Option<&'a str>,
+    pub status: u16,
+    pub error_type: Option<&'a str>,
+    pub reason: Option<&'a str>,
+}
+
+impl<'a> InternalEvent for ElasticSearchItemRejected<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Event rejected by Elasticsearch.",
+            action = %self.action,
+            index = ?self.index,
+            id = ?self.id,
+            status = %self.status,
+            error_type = ?self.error_type,
+            reason = ?self.reason,
+            rate_limit_secs = 10,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_failed_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct ElasticSearchHitsReceived {
+    pub count: usize,
+    pub byte_size: usize,
+}
+
+impl InternalEvent for ElasticSearchHitsReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received search hits.", count = %self.count);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", self.count as u64);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct ElasticSearchSearchFailed<'a> {
+    pub error: &'a crate::Error,
+}
+
+impl<'a> InternalEvent for ElasticSearchSearchFailed<'a> {
+    fn emit_logs(&self) {
+        error!(message = "Search request failed.", error = %self.error, rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("search_failed_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct ElasticSearchCheckpointFailed<'a> {
+    pub error: std::io::Error,
+    pub path: &'a std::path::Path,
+}
+
+impl<'a> InternalEvent for ElasticSearchCheckpointFailed<'a> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Unable to access checkpoint.",
+            path = ?self.path,
+            error = %self.error,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("checkpoint_errors_total", 1);
+    }
+}
@@ -187,6 +187,29 @@ impl<'a> InternalEvent for FileResumed<'a> {
     }
 }
 
+#[derive(Debug)]
+pub struct FileTruncated<'a> {
+    pub path: &'a Path,
+    pub file_position: u64,
+}
+
+impl<'a> InternalEvent for FileTruncated<'a> {
+    fn emit_logs(&self) {
+        info!(
+            message = "File was truncated, reading it from the start.",
+            path = ?self.path,
+            file_position = %self.file_position
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "files_truncated", 1,
+            "file" => self.path.to_string_lossy().into_owned(),
+        );
+    }
+}
+
 #[derive(Debug)]
 pub struct FileAdded<'a> {
     pub path: &'a Path,
@@ -252,6 +275,13 @@ impl FileSourceInternalEvents for FileSourceInternalEventsEmitter {
         });
     }
 
+    fn emit_file_truncated(&self, path: &Path, file_position: u64) {
+        emit!(FileTruncated {
+            path,
+            file_position
+        });
+    }
+
     fn emit_file_watch_failed(&self, path: &Path, error: Error) {
         emit!(FileWatchFailed { path, error });
     }
@@ -0,0 +1,49 @@
+use super::InternalEvent;
+use crate::sources::gcp_pubsub::PubsubError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct GcpPubsubEventReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for GcpPubsubEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received one event.", rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct GcpPubsubReceiveFailed {
+    pub error: PubsubError,
+}
+
+impl InternalEvent for GcpPubsubReceiveFailed {
+    fn emit_logs(&self) {
+        error!(message = "Failed to pull messages.", error = %self.error, rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("request_errors_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct GcpPubsubAckFailed {
+    pub error: PubsubError,
+}
+
+impl InternalEvent for GcpPubsubAckFailed {
+    fn emit_logs(&self) {
+        error!(message = "Failed to acknowledge messages.", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("consumer_acknowledgements_failed_total", 1);
+    }
+}
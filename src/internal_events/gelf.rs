@@ -0,0 +1,70 @@
+use super::InternalEvent;
+use crate::sources::util::gelf::GelfError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct GelfEventReceived {
+    pub byte_size: usize,
+    pub mode: &'static str,
+}
+
+impl InternalEvent for GelfEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received one event.", byte_size = %self.byte_size, mode = self.mode);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1, "mode" => self.mode);
+        counter!("processed_bytes_total", self.byte_size as u64, "mode" => self.mode);
+    }
+}
+
+#[derive(Debug)]
+pub struct GelfParseError {
+    pub error: GelfError,
+    pub mode: &'static str,
+}
+
+impl InternalEvent for GelfParseError {
+    fn emit_logs(&self) {
+        warn!(message = "Failed to parse GELF message.", error = %self.error, mode = self.mode, rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("parse_errors_total", 1, "mode" => self.mode);
+    }
+}
+
+#[derive(Debug)]
+pub struct GelfChunksExpired {
+    pub count: usize,
+}
+
+impl InternalEvent for GelfChunksExpired {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Dropped incomplete chunked messages.",
+            count = %self.count,
+            rate_limit_secs = 10
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("chunked_messages_expired_total", self.count as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct GelfUdpReadError {
+    pub error: std::io::Error,
+}
+
+impl InternalEvent for GelfUdpReadError {
+    fn emit_logs(&self) {
+        error!(message = "Error reading datagram.", error = ?self.error, rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("connection_read_errors_total", 1, "mode" => "udp");
+    }
+}
@@ -1,5 +1,6 @@
 use super::InternalEvent;
-use metrics::counter;
+use metrics::{counter, gauge};
+use std::collections::HashSet;
 
 #[derive(Debug)]
 pub struct KafkaEventReceived {
@@ -47,6 +48,88 @@ impl InternalEvent for KafkaEventFailed {
     }
 }
 
+#[derive(Debug)]
+pub struct KafkaStatisticsReceived<'a> {
+    pub statistics: &'a rdkafka::Statistics,
+}
+
+impl InternalEvent for KafkaStatisticsReceived<'_> {
+    fn emit_metrics(&self) {
+        for (topic_id, topic) in &self.statistics.topics {
+            for (partition_id, partition) in &topic.partitions {
+                // The internal partition collects the messages not yet
+                // assigned to a partition.
+                if *partition_id < 0 {
+                    continue;
+                }
+                // The lag is only known once the consumer fetched from the
+                // partition.
+                if partition.consumer_lag >= 0 {
+                    gauge!(
+                        "consumer_lag", partition.consumer_lag as f64,
+                        "topic_id" => topic_id.clone(),
+                        "partition_id" => partition_id.to_string(),
+                    );
+                }
+                gauge!(
+                    "fetch_queue_messages", partition.fetchq_cnt as f64,
+                    "topic_id" => topic_id.clone(),
+                    "partition_id" => partition_id.to_string(),
+                );
+                gauge!(
+                    "fetch_queue_bytes", partition.fetchq_size as f64,
+                    "topic_id" => topic_id.clone(),
+                    "partition_id" => partition_id.to_string(),
+                );
+            }
+        }
+    }
+}
+
+/// Zeroes the gauges of the partitions revoked from the consumer, whose
+/// statistics are no longer updated.
+#[derive(Debug)]
+pub struct KafkaPartitionsRevoked<'a> {
+    pub partitions: &'a HashSet<(String, i32)>,
+}
+
+impl InternalEvent for KafkaPartitionsRevoked<'_> {
+    fn emit_metrics(&self) {
+        for (topic_id, partition_id) in self.partitions {
+            gauge!(
+                "consumer_lag", 0.0,
+                "topic_id" => topic_id.clone(),
+                "partition_id" => partition_id.to_string(),
+            );
+            gauge!(
+                "fetch_queue_messages", 0.0,
+                "topic_id" => topic_id.clone(),
+                "partition_id" => partition_id.to_string(),
+            );
+            gauge!(
+                "fetch_queue_bytes", 0.0,
+                "topic_id" => topic_id.clone(),
+                "partition_id" => partition_id.to_string(),
+            );
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct KafkaRebalance {
+    pub kind: &'static str,
+}
+
+impl InternalEvent for KafkaRebalance {
+    fn emit_logs(&self) {
+        debug!(message = "Consumer group rebalance.", kind = %self.kind);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("consumer_rebalances_total", 1, "kind" => self.kind);
+    }
+}
+
 #[derive(Debug)]
 pub struct KafkaKeyExtractionFailed<'a> {
     pub key_field: &'a str,
@@ -57,3 +140,37 @@ impl InternalEvent for KafkaKeyExtractionFailed<'_> {
         error!(message = "Failed to extract key.", key_field = %self.key_field);
     }
 }
+
+#[derive(Debug)]
+pub struct KafkaEncodeEventFailed {
+    pub error: crate::Error,
+}
+
+impl InternalEvent for KafkaEncodeEventFailed {
+    fn emit_logs(&self) {
+        error!(
+            message = "Failed to encode event; dropping event.",
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("encode_errors_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct KafkaTransactionCommitted {
+    pub events: usize,
+}
+
+impl InternalEvent for KafkaTransactionCommitted {
+    fn emit_logs(&self) {
+        debug!(message = "Committed transaction.", events = %self.events);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("transactions_committed_total", 1);
+    }
+}
@@ -0,0 +1,88 @@
+use super::InternalEvent;
+use metrics::{counter, gauge};
+
+#[derive(Debug)]
+pub struct KubernetesEventsEventReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for KubernetesEventsEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received one event.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct KubernetesEventsEventDeduplicated;
+
+impl InternalEvent for KubernetesEventsEventDeduplicated {
+    fn emit_logs(&self) {
+        trace!(message = "Skipped already seen event.");
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_deduplicated_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct KubernetesEventsWatchFailed<'a> {
+    pub error: &'a dyn std::error::Error,
+}
+
+impl InternalEvent for KubernetesEventsWatchFailed<'_> {
+    fn emit_logs(&self) {
+        error!(
+            message = "Watching events failed, retrying.",
+            error = %self.error,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("k8s_watch_failures_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct KubernetesEventsLeaderElectionFailed<'a> {
+    pub error: &'a dyn std::error::Error,
+}
+
+impl InternalEvent for KubernetesEventsLeaderElectionFailed<'_> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Leader election request failed.",
+            error = %self.error,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("k8s_leader_election_failures_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct KubernetesEventsLeadershipChanged {
+    pub leader: bool,
+}
+
+impl InternalEvent for KubernetesEventsLeadershipChanged {
+    fn emit_logs(&self) {
+        if self.leader {
+            info!(message = "Acquired leadership, watching events.");
+        } else {
+            info!(message = "Lost leadership, stopped watching events.");
+        }
+    }
+
+    fn emit_metrics(&self) {
+        gauge!("k8s_leader", if self.leader { 1.0 } else { 0.0 });
+    }
+}
@@ -0,0 +1,53 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct LokiOutOfOrderEventDropped;
+
+impl InternalEvent for LokiOutOfOrderEventDropped {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Received out-of-order event; dropping event.",
+            rate_limit_secs = 30
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("out_of_order_events_dropped_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct LokiOutOfOrderEventRewritten;
+
+impl InternalEvent for LokiOutOfOrderEventRewritten {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Received out-of-order event, rewriting timestamp.",
+            rate_limit_secs = 30
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("out_of_order_events_rewritten_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct LokiStreamLimitReached {
+    pub max_streams: usize,
+}
+
+impl InternalEvent for LokiStreamLimitReached {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Reached the maximum number of streams; dropping event.",
+            max_streams = %self.max_streams,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("stream_limit_events_dropped_total", 1);
+    }
+}
@@ -2,6 +2,8 @@ use std::borrow::Cow;
 
 mod add_fields;
 mod add_tags;
+#[cfg(any(feature = "sources-amqp", feature = "sinks-amqp"))]
+mod amqp;
 mod ansi_stripper;
 #[cfg(feature = "sources-apache_metrics")]
 mod apache_metrics;
@@ -18,7 +20,11 @@ mod aws_kinesis_firehose;
 mod aws_kinesis_streams;
 #[cfg(any(feature = "sources-aws_s3", feature = "sinks-aws_s3"))]
 pub(crate) mod aws_s3;
+#[cfg(feature = "sources-aws_sqs")]
+mod aws_sqs;
 mod blackhole;
+#[cfg(feature = "sinks-clickhouse")]
+mod clickhouse;
 #[cfg(feature = "transforms-coercer")]
 mod coercer;
 #[cfg(feature = "transforms-concat")]
@@ -30,6 +36,10 @@ mod dedupe;
 #[cfg(feature = "sources-docker")]
 mod docker;
 mod elasticsearch;
+#[cfg(feature = "sources-gcp_pubsub")]
+mod gcp_pubsub;
+#[cfg(feature = "sources-gelf")]
+mod gelf;
 #[cfg(feature = "sources-generator")]
 mod generator;
 #[cfg(feature = "transforms-grok_parser")]
@@ -42,10 +52,12 @@ mod http;
 mod journald;
 #[cfg(feature = "transforms-json_parser")]
 mod json_parser;
-#[cfg(feature = "sources-kafka")]
+#[cfg(any(feature = "sources-kafka", feature = "sinks-kafka"))]
 mod kafka;
 #[cfg(feature = "transforms-key_value_parser")]
 mod key_value_parser;
+#[cfg(feature = "sources-kubernetes-events")]
+mod kubernetes_events;
 #[cfg(feature = "sources-kubernetes-logs")]
 mod kubernetes_logs;
 #[cfg(feature = "transforms-log_to_metric")]
@@ -53,18 +65,25 @@ mod log_to_metric;
 #[cfg(feature = "transforms-logfmt_parser")]
 mod logfmt_parser;
 mod logplex;
+mod loki;
 #[cfg(feature = "transforms-lua")]
 mod lua;
 #[cfg(feature = "transforms-metric_to_log")]
 mod metric_to_log;
 #[cfg(feature = "sources-mongodb_metrics")]
 mod mongodb_metrics;
-#[cfg(feature = "sinks-nats")]
+#[cfg(any(feature = "sources-nats", feature = "sinks-nats"))]
 mod nats;
 mod open;
+#[cfg(feature = "parquet")]
+mod parquet;
 mod process;
 #[cfg(feature = "sources-prometheus")]
 mod prometheus;
+#[cfg(feature = "sources-pulsar")]
+mod pulsar;
+#[cfg(any(feature = "sources-redis", feature = "sinks-redis"))]
+mod redis;
 #[cfg(feature = "transforms-reduce")]
 mod reduce;
 #[cfg(feature = "transforms-regex_parser")]
@@ -106,6 +125,8 @@ pub mod kubernetes;
 
 pub use self::add_fields::*;
 pub use self::add_tags::*;
+#[cfg(any(feature = "sources-amqp", feature = "sinks-amqp"))]
+pub use self::amqp::*;
 pub use self::ansi_stripper::*;
 #[cfg(feature = "sources-apache_metrics")]
 pub use self::apache_metrics::*;
@@ -120,7 +141,11 @@ pub use self::aws_ec2_metadata::*;
 pub use self::aws_kinesis_firehose::*;
 #[cfg(feature = "sinks-aws_kinesis_streams")]
 pub use self::aws_kinesis_streams::*;
+#[cfg(feature = "sources-aws_sqs")]
+pub use self::aws_sqs::*;
 pub use self::blackhole::*;
+#[cfg(feature = "sinks-clickhouse")]
+pub use self::clickhouse::*;
 #[cfg(feature = "transforms-coercer")]
 pub(crate) use self::coercer::*;
 #[cfg(feature = "transforms-concat")]
@@ -134,6 +159,10 @@ pub use self::docker::*;
 pub use self::elasticsearch::*;
 #[cfg(any(feature = "sources-file", feature = "sources-kubernetes-logs"))]
 pub use self::file::*;
+#[cfg(feature = "sources-gcp_pubsub")]
+pub use self::gcp_pubsub::*;
+#[cfg(feature = "sources-gelf")]
+pub use self::gelf::*;
 #[cfg(feature = "sources-generator")]
 pub use self::generator::*;
 #[cfg(feature = "transforms-grok_parser")]
@@ -146,10 +175,12 @@ pub use self::http::*;
 pub(crate) use self::journald::*;
 #[cfg(feature = "transforms-json_parser")]
 pub(crate) use self::json_parser::*;
-#[cfg(feature = "sources-kafka")]
+#[cfg(any(feature = "sources-kafka", feature = "sinks-kafka"))]
 pub use self::kafka::*;
 #[cfg(feature = "transforms-key_value_parser")]
 pub(crate) use self::key_value_parser::*;
+#[cfg(feature = "sources-kubernetes-events")]
+pub use self::kubernetes_events::*;
 #[cfg(feature = "sources-kubernetes-logs")]
 pub use self::kubernetes_logs::*;
 #[cfg(feature = "transforms-log_to_metric")]
@@ -157,16 +188,23 @@ pub(crate) use self::log_to_metric::*;
 #[cfg(feature = "transforms-logfmt_parser")]
 pub use self::logfmt_parser::*;
 pub use self::logplex::*;
+pub use self::loki::*;
 #[cfg(feature = "transforms-lua")]
 pub use self::lua::*;
 #[cfg(feature = "transforms-metric_to_log")]
 pub(crate) use self::metric_to_log::*;
-#[cfg(feature = "sinks-nats")]
+#[cfg(any(feature = "sources-nats", feature = "sinks-nats"))]
 pub use self::nats::*;
 pub use self::open::*;
+#[cfg(feature = "parquet")]
+pub use self::parquet::*;
 pub use self::process::*;
 #[cfg(feature = "sources-prometheus")]
 pub use self::prometheus::*;
+#[cfg(feature = "sources-pulsar")]
+pub use self::pulsar::*;
+#[cfg(any(feature = "sources-redis", feature = "sinks-redis"))]
+pub use self::redis::*;
 #[cfg(feature = "transforms-reduce")]
 pub(crate) use self::reduce::*;
 #[cfg(feature = "transforms-regex_parser")]
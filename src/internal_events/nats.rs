@@ -2,6 +2,22 @@ use super::InternalEvent;
 use metrics::counter;
 use std::io::Error;
 
+#[derive(Debug)]
+pub struct NatsEventReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for NatsEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received one event.", rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
 #[derive(Debug)]
 pub struct NatsEventSendSuccess {
     pub byte_size: usize,
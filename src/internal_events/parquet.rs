@@ -0,0 +1,23 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct ParquetEncodingFailed {
+    pub error: ::parquet::errors::ParquetError,
+    pub count: usize,
+}
+
+impl InternalEvent for ParquetEncodingFailed {
+    fn emit_logs(&self) {
+        error!(
+            message = "Failed to write events to Parquet file; dropping them.",
+            error = %self.error,
+            count = %self.count,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("encode_errors_total", 1);
+    }
+}
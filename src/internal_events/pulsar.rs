@@ -0,0 +1,48 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct PulsarEventReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for PulsarEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received one event.", rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct PulsarEventFailed {
+    pub error: pulsar::Error,
+}
+
+impl InternalEvent for PulsarEventFailed {
+    fn emit_logs(&self) {
+        error!(message = "Failed to read message.", error = ?self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_failed_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct PulsarAcknowledgementFailed {
+    pub error: crate::Error,
+}
+
+impl InternalEvent for PulsarAcknowledgementFailed {
+    fn emit_logs(&self) {
+        error!(message = "Failed to acknowledge message.", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("consumer_acknowledgements_failed_total", 1);
+    }
+}
@@ -0,0 +1,64 @@
+use super::InternalEvent;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct RedisEventReceived {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for RedisEventReceived {
+    fn emit_logs(&self) {
+        trace!(message = "Received one event.", rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct RedisReceiveEventFailed {
+    pub error: redis::RedisError,
+}
+
+impl InternalEvent for RedisReceiveEventFailed {
+    fn emit_logs(&self) {
+        error!(message = "Failed to read message.", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("events_failed_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct RedisEventSent {
+    pub byte_size: usize,
+}
+
+impl InternalEvent for RedisEventSent {
+    fn emit_metrics(&self) {
+        counter!("events_processed_total", 1);
+        counter!("processed_bytes_total", self.byte_size as u64);
+    }
+}
+
+#[derive(Debug)]
+pub struct RedisMissingKeys<'a> {
+    pub keys: &'a [String],
+}
+
+impl<'a> InternalEvent for RedisMissingKeys<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Keys do not exist on the event; dropping event.",
+            missing_keys = ?self.keys,
+            rate_limit_secs = 30,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("missing_keys_total", 1);
+    }
+}
@@ -46,3 +46,43 @@ impl InternalEvent for SyslogUdpUtf8Error {
         counter!("utf8_convert_errors_total", 1, "mode" => "udp");
     }
 }
+
+#[derive(Debug)]
+pub struct SyslogSinkMissingKeys<'a> {
+    pub keys: &'a [String],
+}
+
+impl<'a> InternalEvent for SyslogSinkMissingKeys<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Keys do not exist on the event; dropping event.",
+            missing_keys = ?self.keys,
+            rate_limit_secs = 30,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("missing_keys_total", 1);
+    }
+}
+
+#[derive(Debug)]
+pub struct SyslogSinkInvalidPriority<'a> {
+    pub field: &'static str,
+    pub value: &'a str,
+}
+
+impl<'a> InternalEvent for SyslogSinkInvalidPriority<'a> {
+    fn emit_logs(&self) {
+        warn!(
+            message = "Invalid syslog priority; dropping event.",
+            field = %self.field,
+            value = %self.value,
+            rate_limit_secs = 30,
+        )
+    }
+
+    fn emit_metrics(&self) {
+        counter!("invalid_priority_total", 1);
+    }
+}
@@ -44,3 +44,18 @@ impl InternalEvent for VectorProtoDecodeError {
         counter!("protobuf_decode_errors_total", 1);
     }
 }
+
+#[derive(Debug)]
+pub struct VectorDecompressionError {
+    pub error: std::io::Error,
+}
+
+impl InternalEvent for VectorDecompressionError {
+    fn emit_logs(&self) {
+        error!(message = "Failed to decompress events.", error = %self.error, rate_limit_secs = 10);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("decompression_errors_total", 1);
+    }
+}
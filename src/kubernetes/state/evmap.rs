@@ -16,6 +16,7 @@ where
 {
     inner: WriteHandle<String, Value<T>>,
     debounced_flush: Option<Debounce>,
+    key: KeyFn,
 }
 
 impl<T> Writer<T>
@@ -23,10 +24,20 @@ where
     T: Metadata<Ty = ObjectMeta> + Send,
 {
     /// Take a [`WriteHandle`], initialize it and return it wrapped with
-    /// [`Self`].
+    /// [`Self`]. The objects are keyed by their `uid`.
     pub fn new(
+        inner: WriteHandle<String, Value<T>>,
+        flush_debounce_timeout: Option<Duration>,
+    ) -> Self {
+        Self::with_key(inner, flush_debounce_timeout, uid_key)
+    }
+
+    /// Like [`Self::new`], but keys the objects with `key`, e.g. with
+    /// [`name_key`] to look up cluster-wide objects by their name.
+    pub fn with_key(
         mut inner: WriteHandle<String, Value<T>>,
         flush_debounce_timeout: Option<Duration>,
+        key: KeyFn,
     ) -> Self {
         // Prepare inner.
         inner.purge();
@@ -38,6 +49,7 @@ where
         Self {
             inner,
             debounced_flush,
+            key,
         }
     }
 
@@ -65,21 +77,21 @@ where
     type Item = T;
 
     async fn add(&mut self, item: Self::Item) {
-        if let Some((key, value)) = kv(item) {
+        if let Some((key, value)) = kv(item, self.key) {
             self.inner.insert(key, value);
             self.debounced_flush();
         }
     }
 
     async fn update(&mut self, item: Self::Item) {
-        if let Some((key, value)) = kv(item) {
+        if let Some((key, value)) = kv(item, self.key) {
             self.inner.update(key, value);
             self.debounced_flush();
         }
     }
 
     async fn delete(&mut self, item: Self::Item) {
-        if let Some((key, _value)) = kv(item) {
+        if let Some((key, _value)) = kv(item, self.key) {
             self.inner.empty(key);
             self.debounced_flush();
         }
@@ -118,10 +130,24 @@ where
 /// An alias to the value used at [`evmap`].
 pub type Value<T> = Box<HashValue<T>>;
 
+/// Picks the key of an object in [`evmap`] from its metadata.
+pub type KeyFn = fn(&ObjectMeta) -> Option<&str>;
+
+/// Keys objects by their `uid`.
+pub fn uid_key(metadata: &ObjectMeta) -> Option<&str> {
+    metadata.uid.as_deref()
+}
+
+/// Keys objects by their `name`, which is only unique for cluster-wide
+/// objects, like `Namespace`s and `Node`s.
+pub fn name_key(metadata: &ObjectMeta) -> Option<&str> {
+    metadata.name.as_deref()
+}
+
 /// Build a key value pair for using in [`evmap`].
-fn kv<T: Metadata<Ty = ObjectMeta>>(object: T) -> Option<(String, Value<T>)> {
+fn kv<T: Metadata<Ty = ObjectMeta>>(object: T, key: KeyFn) -> Option<(String, Value<T>)> {
+    let key = key(object.metadata())?.to_owned();
     let value = Box::new(HashValue::new(object));
-    let key = value.uid()?.to_owned();
     Some((key, value))
 }
 
@@ -144,11 +170,22 @@ mod tests {
     #[test]
     fn test_kv() {
         let pod = make_pod("uid");
-        let (key, val) = kv(pod.clone()).unwrap();
+        let (key, val) = kv(pod.clone(), uid_key).unwrap();
         assert_eq!(key, "uid");
         assert_eq!(val, Box::new(HashValue::new(pod)));
     }
 
+    #[test]
+    fn test_kv_by_name() {
+        let mut pod = make_pod("uid");
+        assert!(kv(pod.clone(), name_key).is_none());
+
+        pod.metadata.name = Some("name".to_owned());
+        let (key, val) = kv(pod.clone(), name_key).unwrap();
+        assert_eq!(key, "name");
+        assert_eq!(val, Box::new(HashValue::new(pod)));
+    }
+
     #[tokio::test]
     async fn test_without_debounce() {
         let (state_reader, state_writer) = evmap::new();
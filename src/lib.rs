@@ -23,12 +23,20 @@ static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 #[macro_use]
 pub mod config;
+#[cfg(feature = "lapin")]
+pub mod amqp;
 pub mod buffers;
+#[cfg(feature = "sources-file")]
+pub mod checkpoints;
 pub mod cli;
 pub mod conditions;
 pub mod dns;
+#[cfg(any(feature = "sinks-elasticsearch", feature = "sources-elasticsearch"))]
+pub mod elasticsearch;
 pub mod event;
 pub mod expiring_hash_map;
+#[cfg(feature = "goauth")]
+pub mod gcp;
 pub mod generate;
 #[cfg(feature = "wasm")]
 pub mod wasm;
@@ -38,6 +46,8 @@ pub mod internal_events;
 pub mod api;
 pub mod app;
 pub mod async_read;
+#[cfg(feature = "tonic")]
+pub mod grpc;
 pub mod heartbeat;
 pub mod http;
 #[cfg(feature = "rdkafka")]
@@ -48,6 +58,10 @@ pub mod list;
 pub mod mapping;
 pub mod metrics;
 pub(crate) mod pipeline;
+#[cfg(any(feature = "sources-vector", feature = "sinks-vector"))]
+pub mod proto;
+#[cfg(feature = "pulsar")]
+pub mod pulsar;
 pub mod remap;
 #[cfg(feature = "rusoto_core")]
 pub mod rusoto;
@@ -0,0 +1,25 @@
+//! The protocols spoken between the `vector` sink and source.
+
+use serde::{Deserialize, Serialize};
+
+/// The version of the protocol, which both ends must agree on.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorVersion {
+    /// Length delimited events over TCP, without acknowledgements.
+    #[serde(rename = "1")]
+    V1,
+    /// Batches of events over gRPC, acknowledged once accepted by the source.
+    #[serde(rename = "2")]
+    V2,
+}
+
+impl Default for VectorVersion {
+    fn default() -> Self {
+        VectorVersion::V1
+    }
+}
+
+#[allow(clippy::all)]
+pub mod vector {
+    include!(concat!(env!("OUT_DIR"), "/grpc/vector.rs"));
+}
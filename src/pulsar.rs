@@ -0,0 +1,31 @@
+use pulsar::{Authentication, Pulsar, PulsarBuilder, TokioExecutor};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct PulsarAuthConfig {
+    name: String,  // "token"
+    token: String, // <jwt token>
+}
+
+impl PulsarAuthConfig {
+    pub(crate) fn apply(
+        &self,
+        builder: PulsarBuilder<TokioExecutor>,
+    ) -> PulsarBuilder<TokioExecutor> {
+        builder.with_auth(Authentication {
+            name: self.name.clone(),
+            data: self.token.as_bytes().to_vec(),
+        })
+    }
+}
+
+pub(crate) async fn connect(
+    endpoint: &str,
+    auth: &Option<PulsarAuthConfig>,
+) -> Result<Pulsar<TokioExecutor>, pulsar::Error> {
+    let mut builder = Pulsar::builder(endpoint, TokioExecutor);
+    if let Some(auth) = auth {
+        builder = auth.apply(builder);
+    }
+    builder.build().await
+}
@@ -1,20 +1,23 @@
 use crate::{
-    config::{log_schema, DataType, SinkConfig, SinkContext, SinkDescription},
+    config::{DataType, SinkConfig, SinkContext, SinkDescription},
     rusoto::{self, RegionOrEndpoint},
     serde::to_string,
     sinks::util::{
+        buffer::parquet::{encode_object_event, ObjectBuffer, ObjectEncoding, ParquetConfig},
         encoding::{EncodingConfigWithDefault, EncodingConfiguration},
         retries::RetryLogic,
         sink::Response,
-        BatchConfig, BatchSettings, Buffer, Compression, InFlightLimit, PartitionBatchSink,
+        BatchConfig, BatchSettings, Compression, InFlightLimit, PartitionBatchSink,
         PartitionBuffer, PartitionInnerBuffer, ServiceBuilderExt, TowerRequestConfig,
     },
     template::Template,
-    Event,
 };
 use bytes::Bytes;
 use chrono::Utc;
-use futures::{future::BoxFuture, stream, FutureExt, SinkExt, StreamExt};
+use futures::{
+    future::{self, BoxFuture},
+    stream, FutureExt, SinkExt, StreamExt,
+};
 use http::StatusCode;
 use lazy_static::lazy_static;
 use rusoto_core::RusotoError;
@@ -58,6 +61,11 @@ pub struct S3SinkConfig {
     pub compression: Compression,
     #[serde(default)]
     pub batch: BatchConfig,
+    #[serde(
+        skip_serializing_if = "crate::serde::skip_serializing_if_default",
+        default
+    )]
+    pub parquet: ParquetConfig,
     #[serde(default)]
     pub request: TowerRequestConfig,
     pub assume_role: Option<String>,
@@ -130,6 +138,17 @@ pub enum Encoding {
     #[derivative(Default)]
     Text,
     Ndjson,
+    Parquet,
+}
+
+impl From<Encoding> for ObjectEncoding {
+    fn from(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Text => Self::Text,
+            Encoding::Ndjson => Self::Ndjson,
+            Encoding::Parquet => Self::Parquet,
+        }
+    }
 }
 
 inventory::submit! {
@@ -174,26 +193,20 @@ impl S3SinkConfig {
     pub fn new(&self, client: S3Client, cx: SinkContext) -> crate::Result<super::VectorSink> {
         let request = self.request.unwrap_with(&REQUEST_DEFAULTS);
         let encoding = self.encoding.clone();
-
-        let compression = self.compression;
+        let parquet = *encoding.codec() == Encoding::Parquet;
+        let (compression, filename_extension, options) = self.object_settings();
         let filename_time_format = self
             .filename_time_format
             .clone()
             .unwrap_or_else(|| "%s".into());
         let filename_append_uuid = self.filename_append_uuid.unwrap_or(true);
-        let batch = BatchSettings::default()
-            .bytes(10_000_000)
-            .timeout(300)
-            .parse_config(self.batch)?;
 
         let key_prefix = self.key_prefix.as_deref().unwrap_or("date=%F/");
         let key_prefix = Template::try_from(key_prefix)?;
 
         let s3 = S3Sink { client };
 
-        let filename_extension = self.filename_extension.clone();
         let bucket = self.bucket.clone();
-        let options = self.options.clone();
 
         let svc = ServiceBuilder::new()
             .map(move |req| {
@@ -210,15 +223,46 @@ impl S3SinkConfig {
             .settings(request, S3RetryLogic)
             .service(s3);
 
-        let buffer = PartitionBuffer::new(Buffer::new(batch.size, self.compression));
+        let batch = BatchSettings::default()
+            .bytes(10_000_000)
+            .timeout(300)
+            .parse_config(self.batch)?;
+        let buffer = PartitionBuffer::new(ObjectBuffer::new(
+            batch.size,
+            self.compression,
+            if parquet {
+                Some(self.parquet.clone())
+            } else {
+                None
+            },
+        ));
 
         let sink = PartitionBatchSink::new(svc, buffer, batch.timeout, cx.acker())
-            .with_flat_map(move |e| stream::iter(encode_event(e, &key_prefix, &encoding)).map(Ok))
+            .with_flat_map(move |e| {
+                stream::iter(encode_object_event(e, &key_prefix, &encoding)).map(Ok)
+            })
             .sink_map_err(|error| error!(message = "Sink failed to flush.", %error));
 
         Ok(super::VectorSink::Sink(Box::new(sink)))
     }
 
+    /// The compression, filename extension and options of the objects, which
+    /// default differently for Parquet files.
+    fn object_settings(&self) -> (Compression, Option<String>, S3Options) {
+        let mut filename_extension = self.filename_extension.clone();
+        let mut options = self.options.clone();
+        if *self.encoding.codec() != Encoding::Parquet {
+            return (self.compression, filename_extension, options);
+        }
+
+        filename_extension = filename_extension.or_else(|| Some("parquet".into()));
+        options.content_type = options
+            .content_type
+            .or_else(|| Some("application/octet-stream".into()));
+        // Parquet files compress their columns, rather than being compressed as a whole.
+        (Compression::None, filename_extension, options)
+    }
+
     pub async fn healthcheck(self, client: S3Client) -> crate::Result<()> {
         let req = client.head_bucket(HeadBucketRequest {
             bucket: self.bucket.clone(),
@@ -260,6 +304,11 @@ impl Service<Request> for S3Sink {
     }
 
     fn call(&mut self, request: Request) -> Self::Future {
+        // Batches that couldn't be written to a file leave nothing to upload.
+        if request.body.is_empty() {
+            return Box::pin(future::ok(PutObjectOutput::default()));
+        }
+
         let options = request.options;
 
         let content_encoding = request.content_encoding;
@@ -376,67 +425,36 @@ impl RetryLogic for S3RetryLogic {
     }
 }
 
-fn encode_event(
-    mut event: Event,
-    key_prefix: &Template,
-    encoding: &EncodingConfigWithDefault<Encoding>,
-) -> Option<PartitionInnerBuffer<Vec<u8>, Bytes>> {
-    let key = key_prefix
-        .render_string(&event)
-        .map_err(|missing_keys| {
-            warn!(
-                message = "Keys do not exist on the event; dropping event.",
-                ?missing_keys,
-                rate_limit_secs = 30,
-            );
-        })
-        .ok()?;
-
-    encoding.apply_rules(&mut event);
-
-    let log = event.into_log();
-    let bytes = match encoding.codec() {
-        Encoding::Ndjson => serde_json::to_vec(&log)
-            .map(|mut b| {
-                b.push(b'\n');
-                b
-            })
-            .expect("Failed to encode event as json, this is a bug!"),
-        Encoding::Text => {
-            let mut bytes = log
-                .get(log_schema().message_key())
-                .map(|v| v.as_bytes().to_vec())
-                .unwrap_or_default();
-            bytes.push(b'\n');
-            bytes
-        }
-    };
-
-    Some(PartitionInnerBuffer::new(bytes, key.into()))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{config::log_schema, sinks::util::buffer::parquet::ObjectInput, Event};
 
     #[test]
     fn generate_config() {
         crate::test_util::test_generate_config::<S3SinkConfig>();
     }
 
+    fn encoded(input: PartitionInnerBuffer<ObjectInput, Bytes>) -> Vec<u8> {
+        match input.into_parts().0 {
+            ObjectInput::Encoded(bytes) => bytes,
+            ObjectInput::Log(_) => panic!("Expected an encoded event"),
+        }
+    }
+
     #[test]
     fn s3_encode_event_text() {
         let message = "hello world".to_string();
         let batch_time_format = Template::try_from("date=%F").unwrap();
-        let bytes = encode_event(
+        let bytes = encode_object_event(
             message.clone().into(),
             &batch_time_format,
-            &Encoding::Text.into(),
+            &EncodingConfigWithDefault::from(Encoding::Text),
         )
         .unwrap();
 
         let encoded_message = message + "\n";
-        let (bytes, _) = bytes.into_parts();
+        let bytes = encoded(bytes);
         assert_eq!(&bytes[..], encoded_message.as_bytes());
     }
 
@@ -447,9 +465,14 @@ mod tests {
         event.as_mut_log().insert("key", "value");
 
         let batch_time_format = Template::try_from("date=%F").unwrap();
-        let bytes = encode_event(event, &batch_time_format, &Encoding::Ndjson.into()).unwrap();
+        let bytes = encode_object_event(
+            event,
+            &batch_time_format,
+            &EncodingConfigWithDefault::from(Encoding::Ndjson),
+        )
+        .unwrap();
 
-        let (bytes, _) = bytes.into_parts();
+        let bytes = encoded(bytes);
         let map: BTreeMap<String, String> = serde_json::from_slice(&bytes[..]).unwrap();
 
         assert_eq!(map[&log_schema().message_key().to_string()], message);
@@ -470,9 +493,9 @@ mod tests {
             ..Default::default()
         };
 
-        let bytes = encode_event(event, &key_prefix, &encoding_config).unwrap();
+        let bytes = encode_object_event(event, &key_prefix, &encoding_config).unwrap();
 
-        let (bytes, _) = bytes.into_parts();
+        let bytes = encoded(bytes);
         let map: BTreeMap<String, String> = serde_json::from_slice(&bytes[..]).unwrap();
 
         assert_eq!(map[&log_schema().message_key().to_string()], message);
@@ -527,6 +550,34 @@ mod tests {
         );
         assert_ne!(req.key, "key/date.log.gz".to_string());
     }
+
+    #[test]
+    fn s3_build_parquet_request() {
+        let buf = PartitionInnerBuffer::new(vec![0u8; 10], Bytes::from("key/"));
+
+        let config = S3SinkConfig {
+            encoding: Encoding::Parquet.into(),
+            compression: Compression::gzip_default(),
+            ..Default::default()
+        };
+        let (compression, extension, options) = config.object_settings();
+        assert_eq!(
+            options.content_type.as_deref(),
+            Some("application/octet-stream")
+        );
+
+        let req = build_request(
+            buf,
+            "date".into(),
+            extension,
+            false,
+            compression,
+            "bucket".into(),
+            options,
+        );
+        assert_eq!(req.key, "key/date.parquet".to_string());
+        assert!(req.content_encoding.is_none());
+    }
 }
 
 #[cfg(feature = "aws-s3-integration-tests")]
@@ -536,6 +587,7 @@ mod integration_tests {
     use crate::{
         assert_downcast_matches,
         test_util::{random_lines_with_stream, random_string},
+        Event,
     };
     use bytes::{buf::BufExt, BytesMut};
     use flate2::read::GzDecoder;
@@ -1,7 +1,13 @@
+mod schema;
+
+pub use self::schema::{RowError, UnknownFields};
+
+use self::schema::TableSchema;
 use crate::{
     config::{DataType, SinkConfig, SinkContext, SinkDescription},
     event::Event,
     http::{Auth, HttpClient},
+    internal_events::{ClickhouseRowRejected, ClickhouseSchemaFetched},
     sinks::util::{
         encoding::{EncodingConfigWithDefault, EncodingConfiguration},
         http::{BatchedHttpSink, HttpRetryLogic, HttpSink},
@@ -16,7 +22,17 @@ use http::{Request, StatusCode, Uri};
 use hyper::Body;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use snafu::ResultExt;
+use snafu::{ResultExt, Snafu};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, RwLock,
+};
+
+#[derive(Debug, Snafu)]
+enum HealthcheckError {
+    #[snafu(display("Invalid table schema: {}", source))]
+    InvalidSchema { source: serde_json::Error },
+}
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 #[serde(deny_unknown_fields)]
@@ -34,6 +50,8 @@ pub struct ClickhouseConfig {
     )]
     pub encoding: EncodingConfigWithDefault<Encoding>,
     #[serde(default)]
+    pub unknown_fields: UnknownFields,
+    #[serde(default)]
     pub batch: BatchConfig,
     pub auth: Option<Auth>,
     #[serde(default)]
@@ -76,19 +94,30 @@ impl SinkConfig for ClickhouseConfig {
         let tls_settings = TlsSettings::from_options(&self.tls)?;
         let client = HttpClient::new(tls_settings)?;
 
+        let sink = ClickhouseSink {
+            config: self.clone(),
+            client: client.clone(),
+            schema: Arc::new(RwLock::new(None)),
+            schema_outdated: Arc::new(AtomicBool::new(false)),
+        };
+
+        let healthcheck = healthcheck(sink.clone()).boxed();
+        let retry_logic = ClickhouseRetryLogic {
+            inner: HttpRetryLogic,
+            schema_outdated: Arc::clone(&sink.schema_outdated),
+        };
+
         let sink = BatchedHttpSink::with_retry_logic(
-            self.clone(),
+            sink,
             Buffer::new(batch.size, self.compression),
-            ClickhouseRetryLogic::default(),
+            retry_logic,
             request,
             batch.timeout,
-            client.clone(),
+            client,
             cx.acker(),
         )
         .sink_map_err(|error| error!(message = "Fatal clickhouse sink error.", %error));
 
-        let healthcheck = healthcheck(client, self.clone()).boxed();
-
         Ok((super::VectorSink::Sink(Box::new(sink)), healthcheck))
     }
 
@@ -101,39 +130,112 @@ impl SinkConfig for ClickhouseConfig {
     }
 }
 
+impl ClickhouseConfig {
+    fn database(&self) -> &str {
+        self.database.as_deref().unwrap_or("default")
+    }
+}
+
+#[derive(Clone)]
+struct ClickhouseSink {
+    config: ClickhouseConfig,
+    client: HttpClient,
+    /// The schema of the table, fetched by the healthcheck or, when it is
+    /// disabled, before the first request.
+    schema: Arc<RwLock<Option<TableSchema>>>,
+    /// Set when an insert failed, as the table may have been altered.
+    schema_outdated: Arc<AtomicBool>,
+}
+
+impl ClickhouseSink {
+    async fn fetch_schema(&self) -> crate::Result<()> {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair(
+                "query",
+                &format!(
+                    "DESCRIBE TABLE {} FORMAT JSONEachRow",
+                    quote_table(self.config.database(), &self.config.table)
+                ),
+            )
+            .finish();
+        let uri = format!("{}/?{}", self.config.endpoint.trim_end_matches('/'), query);
+        let mut request = Request::get(uri).body(Body::empty()).unwrap();
+
+        if let Some(auth) = &self.config.auth {
+            auth.apply(&mut request);
+        }
+
+        let response = self.client.send(request).await?;
+        if response.status() != StatusCode::OK {
+            return Err(super::HealthcheckError::UnexpectedStatus {
+                status: response.status(),
+            }
+            .into());
+        }
+
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let schema = TableSchema::from_describe(&body).context(InvalidSchema)?;
+        emit!(ClickhouseSchemaFetched {
+            columns: schema.columns.len()
+        });
+        *self.schema.write().unwrap() = Some(schema);
+
+        Ok(())
+    }
+}
+
 #[async_trait::async_trait]
-impl HttpSink for ClickhouseConfig {
+impl HttpSink for ClickhouseSink {
     type Input = Vec<u8>;
     type Output = Vec<u8>;
 
     fn encode_event(&self, mut event: Event) -> Option<Self::Input> {
-        self.encoding.apply_rules(&mut event);
-
-        let mut body =
-            serde_json::to_vec(&event.as_log().all_fields()).expect("Events should be valid json!");
+        self.config.encoding.apply_rules(&mut event);
+        let log = event.as_log();
+
+        let mut body = match &*self.schema.read().unwrap() {
+            Some(schema) => match schema.encode(log, self.config.unknown_fields) {
+                Ok(row) => serde_json::to_vec(&row),
+                Err(error) => {
+                    emit!(ClickhouseRowRejected { error: &error });
+                    return None;
+                }
+            },
+            None => serde_json::to_vec(&log.all_fields()),
+        }
+        .expect("Events should be valid json!");
         body.push(b'\n');
 
         Some(body)
     }
 
     async fn build_request(&self, events: Self::Output) -> crate::Result<http::Request<Vec<u8>>> {
-        let database = if let Some(database) = &self.database {
-            database.as_str()
-        } else {
-            "default"
-        };
+        let outdated = self.schema_outdated.swap(false, Ordering::Relaxed);
+        if outdated || self.schema.read().unwrap().is_none() {
+            // The events of this batch were converted with the previous
+            // schema, if any, the following ones use the one fetched here.
+            if let Err(error) = self.fetch_schema().await {
+                warn!(message = "Failed to fetch the table schema.", %error, rate_limit_secs = 30);
+            }
+        }
 
-        let uri = encode_uri(&self.endpoint, database, &self.table).expect("Unable to encode uri");
+        let uri = encode_uri(
+            &self.config.endpoint,
+            self.config.database(),
+            &self.config.table,
+            self.config.unknown_fields,
+        )
+        .expect("Unable to encode uri");
 
         let mut builder = Request::post(&uri).header("Content-Type", "application/x-ndjson");
 
-        if let Some(ce) = self.compression.content_encoding() {
+        if let Some(ce) = self.config.compression.content_encoding() {
             builder = builder.header("Content-Encoding", ce);
         }
 
         let mut request = builder.body(events).unwrap();
 
-        if let Some(auth) = &self.auth {
+        if let Some(auth) = &self.config.auth {
             auth.apply(&mut request);
         }
 
@@ -141,35 +243,40 @@ impl HttpSink for ClickhouseConfig {
     }
 }
 
-async fn healthcheck(client: HttpClient, config: ClickhouseConfig) -> crate::Result<()> {
-    // TODO: check if table exists?
-    let uri = format!("{}/?query=SELECT%201", config.endpoint);
-    let mut request = Request::get(uri).body(Body::empty()).unwrap();
-
-    if let Some(auth) = &config.auth {
-        auth.apply(&mut request);
-    }
-
-    let response = client.send(request).await?;
+/// Checks that the table exists, by fetching its schema.
+async fn healthcheck(sink: ClickhouseSink) -> crate::Result<()> {
+    sink.fetch_schema().await
+}
 
-    match response.status() {
-        StatusCode::OK => Ok(()),
-        status => Err(super::HealthcheckError::UnexpectedStatus { status }.into()),
-    }
+fn quote_table(database: &str, table: &str) -> String {
+    format!(
+        "\"{}\".\"{}\"",
+        database.replace("\"", "\\\""),
+        table.replace("\"", "\\\"")
+    )
 }
 
-fn encode_uri(host: &str, database: &str, table: &str) -> crate::Result<Uri> {
-    let query = url::form_urlencoded::Serializer::new(String::new())
-        .append_pair(
-            "query",
-            format!(
-                "INSERT INTO \"{}\".\"{}\" FORMAT JSONEachRow",
-                database,
-                table.replace("\"", "\\\"")
-            )
-            .as_str(),
+fn encode_uri(
+    host: &str,
+    database: &str,
+    table: &str,
+    unknown_fields: UnknownFields,
+) -> crate::Result<Uri> {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    query.append_pair(
+        "query",
+        format!(
+            "INSERT INTO {} FORMAT JSONEachRow",
+            quote_table(database, table)
         )
-        .finish();
+        .as_str(),
+    );
+    // Until the schema is known, the fields without a column are left to
+    // ClickHouse to skip.
+    if unknown_fields == UnknownFields::Skip {
+        query.append_pair("input_format_skip_unknown_fields", "1");
+    }
+    let query = query.finish();
 
     let url = if host.ends_with('/') {
         format!("{}?{}", host, query)
@@ -180,9 +287,10 @@ fn encode_uri(host: &str, database: &str, table: &str) -> crate::Result<Uri> {
     Ok(url.parse::<Uri>().context(super::UriParseError)?)
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 struct ClickhouseRetryLogic {
     inner: HttpRetryLogic,
+    schema_outdated: Arc<AtomicBool>,
 }
 
 impl RetryLogic for ClickhouseRetryLogic {
@@ -194,6 +302,10 @@ impl RetryLogic for ClickhouseRetryLogic {
     }
 
     fn should_retry_response(&self, response: &Self::Response) -> RetryAction {
+        if !response.status().is_success() {
+            self.schema_outdated.store(true, Ordering::Relaxed);
+        }
+
         match response.status() {
             StatusCode::INTERNAL_SERVER_ERROR => {
                 let body = response.body();
@@ -230,16 +342,62 @@ mod tests {
 
     #[test]
     fn encode_valid() {
-        let uri = encode_uri("http://localhost:80", "my_database", "my_table").unwrap();
+        let uri = encode_uri(
+            "http://localhost:80",
+            "my_database",
+            "my_table",
+            UnknownFields::Error,
+        )
+        .unwrap();
         assert_eq!(uri, "http://localhost:80/?query=INSERT+INTO+%22my_database%22.%22my_table%22+FORMAT+JSONEachRow");
 
-        let uri = encode_uri("http://localhost:80", "my_database", "my_\"table\"").unwrap();
+        let uri = encode_uri(
+            "http://localhost:80",
+            "my_database",
+            "my_\"table\"",
+            UnknownFields::Error,
+        )
+        .unwrap();
         assert_eq!(uri, "http://localhost:80/?query=INSERT+INTO+%22my_database%22.%22my_%5C%22table%5C%22%22+FORMAT+JSONEachRow");
+
+        let uri = encode_uri(
+            "http://localhost:80",
+            "my_database",
+            "my_table",
+            UnknownFields::Skip,
+        )
+        .unwrap();
+        assert_eq!(uri, "http://localhost:80/?query=INSERT+INTO+%22my_database%22.%22my_table%22+FORMAT+JSONEachRow&input_format_skip_unknown_fields=1");
+    }
+
+    #[test]
+    fn failed_insert_outdates_schema() {
+        let logic = ClickhouseRetryLogic {
+            inner: HttpRetryLogic,
+            schema_outdated: Arc::new(AtomicBool::new(false)),
+        };
+        let response = |status| {
+            http::Response::builder()
+                .status(status)
+                .body(Bytes::from("Code: 16"))
+                .unwrap()
+        };
+
+        logic.should_retry_response(&response(StatusCode::OK));
+        assert!(!logic.schema_outdated.load(Ordering::Relaxed));
+        logic.should_retry_response(&response(StatusCode::BAD_REQUEST));
+        assert!(logic.schema_outdated.load(Ordering::Relaxed));
     }
 
     #[test]
     fn encode_invalid() {
-        encode_uri("localhost:80", "my_database", "my_table").unwrap_err();
+        encode_uri(
+            "localhost:80",
+            "my_database",
+            "my_table",
+            UnknownFields::Skip,
+        )
+        .unwrap_err();
     }
 }
 
@@ -0,0 +1,545 @@
+//! Conversion of the events to the columns of a ClickHouse table.
+//!
+//! The schema is read from `DESCRIBE TABLE`, and each event is turned into
+//! a `JSONEachRow` row holding the values of the columns converted to their
+//! type, so that ClickHouse doesn't have to guess how to coerce them.
+
+use crate::event::{LogEvent, Value};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map};
+use snafu::Snafu;
+
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize, Eq, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownFields {
+    Skip,
+    #[derivative(Default)]
+    Error,
+}
+
+#[derive(Debug, Snafu, PartialEq)]
+pub enum RowError {
+    #[snafu(display("Field {:?} does not match any column", field))]
+    UnknownField { field: String },
+    #[snafu(display("Invalid value for column {:?}: {}", column, message))]
+    InvalidValue { column: String, message: String },
+}
+
+/// The types of the columns, as far as the conversion is concerned.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnType {
+    Bool,
+    /// The width of the integers, in bits.
+    Int(u32),
+    UInt(u32),
+    Float,
+    Decimal,
+    String,
+    Date,
+    DateTime,
+    DateTime64(u32),
+    Array(Box<ColumnType>),
+    /// The type of the values of the map.
+    Map(Box<ColumnType>),
+    Tuple(Vec<ColumnType>),
+    Nullable(Box<ColumnType>),
+    LowCardinality(Box<ColumnType>),
+    /// Sent as is, for ClickHouse to parse.
+    Other,
+}
+
+impl ColumnType {
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        let (name, args) = match text.find('(') {
+            Some(open) if text.ends_with(')') => {
+                (&text[..open], split_args(&text[open + 1..text.len() - 1]))
+            }
+            _ => (text, Vec::new()),
+        };
+        let arg = |index: usize| args.get(index).map(|arg| Self::parse(arg));
+
+        match name {
+            "Bool" | "Boolean" => Self::Bool,
+            "Int8" | "Int16" | "Int32" | "Int64" | "Int128" | "Int256" => {
+                Self::Int(name["Int".len()..].parse().expect("Known integer width"))
+            }
+            "UInt8" | "UInt16" | "UInt32" | "UInt64" | "UInt128" | "UInt256" => {
+                Self::UInt(name["UInt".len()..].parse().expect("Known integer width"))
+            }
+            "Float32" | "Float64" => Self::Float,
+            "Decimal" | "Decimal32" | "Decimal64" | "Decimal128" | "Decimal256" => Self::Decimal,
+            "String" | "FixedString" | "UUID" | "IPv4" | "IPv6" | "Enum8" | "Enum16" => {
+                Self::String
+            }
+            "Date" | "Date32" => Self::Date,
+            "DateTime" => Self::DateTime,
+            "DateTime64" => Self::DateTime64(
+                args.first()
+                    .and_then(|precision| precision.parse().ok())
+                    .filter(|precision| *precision <= 9)
+                    .unwrap_or(3),
+            ),
+            "Array" => arg(0).map_or(Self::Other, |inner| Self::Array(Box::new(inner))),
+            "Map" => arg(1).map_or(Self::Other, |inner| Self::Map(Box::new(inner))),
+            "Tuple" => Self::Tuple(
+                args.iter()
+                    // The elements of the named tuples are `<name> <type>`.
+                    .map(|arg| match arg.find(' ') {
+                        Some(space) if !arg[..space].contains('(') => Self::parse(&arg[space..]),
+                        _ => Self::parse(arg),
+                    })
+                    .collect(),
+            ),
+            "Nullable" => arg(0).map_or(Self::Other, |inner| Self::Nullable(Box::new(inner))),
+            "LowCardinality" => {
+                arg(0).map_or(Self::Other, |inner| Self::LowCardinality(Box::new(inner)))
+            }
+            _ => Self::Other,
+        }
+    }
+
+    /// Converts the value to the JSON representation ClickHouse parses for
+    /// this type.
+    fn convert(&self, value: &Value) -> Result<serde_json::Value, String> {
+        match (self, value) {
+            (Self::Nullable(_), Value::Null) => Ok(serde_json::Value::Null),
+            (Self::Nullable(inner), _) | (Self::LowCardinality(inner), _) => inner.convert(value),
+            (Self::Other, _) => serde_json::to_value(value).map_err(|error| error.to_string()),
+            // ClickHouse would silently insert the default value of the type.
+            (_, Value::Null) => Err("null for a column that is not nullable".into()),
+
+            (Self::Bool, Value::Boolean(b)) => Ok(json!(b)),
+            (Self::Bool, Value::Integer(i)) => Ok(json!(*i != 0)),
+            (Self::Bool, Value::Bytes(_)) => match value.to_string_lossy().as_str() {
+                "true" | "1" => Ok(json!(true)),
+                "false" | "0" => Ok(json!(false)),
+                text => Err(format!("{:?} is not a boolean", text)),
+            },
+
+            (Self::Int(bits), _) | (Self::UInt(bits), _) => {
+                let unsigned = matches!(self, Self::UInt(_));
+                let int = match value {
+                    Value::Integer(i) => *i,
+                    Value::Float(f) if f.fract() == 0.0 => *f as i64,
+                    Value::Boolean(b) => *b as i64,
+                    Value::Timestamp(timestamp) => timestamp.timestamp(),
+                    Value::Bytes(_) => {
+                        let text = value.to_string_lossy();
+                        match text.parse::<i64>() {
+                            Ok(int) => int,
+                            // Larger than an `Int64`, left to ClickHouse.
+                            Err(_) if unsigned && *bits >= 64 && text.parse::<u64>().is_ok() => {
+                                return Ok(json!(text))
+                            }
+                            Err(_) => return Err(format!("{:?} is not an integer", text)),
+                        }
+                    }
+                    _ => return Err(mismatch(value, "an integer")),
+                };
+                check_int_range(int, *bits, unsigned)?;
+                Ok(json!(int))
+            }
+
+            (Self::Float, Value::Integer(i)) | (Self::Decimal, Value::Integer(i)) => Ok(json!(i)),
+            (Self::Float, Value::Float(f)) | (Self::Decimal, Value::Float(f)) => Ok(json!(f)),
+            (Self::Float, Value::Bytes(_)) | (Self::Decimal, Value::Bytes(_)) => {
+                let text = value.to_string_lossy();
+                match text.parse::<f64>() {
+                    // The decimals are kept as text to not lose precision.
+                    Ok(_) if *self == Self::Decimal => Ok(json!(text)),
+                    Ok(float) => Ok(json!(float)),
+                    Err(_) => Err(format!("{:?} is not a number", text)),
+                }
+            }
+
+            (Self::String, _) => Ok(json!(value.to_string_lossy())),
+
+            (Self::Date, Value::Timestamp(timestamp)) => {
+                Ok(json!(timestamp.format("%Y-%m-%d").to_string()))
+            }
+            (Self::Date, Value::Bytes(_)) => Ok(json!(value.to_string_lossy())),
+
+            // The timestamps are sent as Unix time, which doesn't depend on
+            // the time zone of the column.
+            (Self::DateTime, Value::Timestamp(timestamp)) => Ok(json!(timestamp.timestamp())),
+            (Self::DateTime, Value::Integer(i)) => Ok(json!(i)),
+            (Self::DateTime, Value::Bytes(_)) => {
+                let text = value.to_string_lossy();
+                match DateTime::parse_from_rfc3339(&text) {
+                    Ok(timestamp) => Ok(json!(timestamp.timestamp())),
+                    Err(_) => Ok(json!(text)),
+                }
+            }
+            (Self::DateTime64(precision), Value::Timestamp(timestamp)) => {
+                Ok(json!(format_datetime64(timestamp, *precision)))
+            }
+            (Self::DateTime64(_), Value::Integer(i)) => Ok(json!(i)),
+            (Self::DateTime64(precision), Value::Float(f)) => {
+                Ok(json!(format!("{:.*}", *precision as usize, f)))
+            }
+            (Self::DateTime64(precision), Value::Bytes(_)) => {
+                let text = value.to_string_lossy();
+                match DateTime::parse_from_rfc3339(&text) {
+                    Ok(timestamp) => Ok(json!(format_datetime64(
+                        &timestamp.with_timezone(&Utc),
+                        *precision
+                    ))),
+                    Err(_) => Ok(json!(text)),
+                }
+            }
+
+            (Self::Array(inner), Value::Array(items)) => items
+                .iter()
+                .map(|item| inner.convert(item))
+                .collect::<Result<Vec<_>, _>>()
+                .map(serde_json::Value::Array),
+            (Self::Map(inner), Value::Map(map)) => map
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), inner.convert(value)?)))
+                .collect::<Result<Map<_, _>, String>>()
+                .map(serde_json::Value::Object),
+            (Self::Tuple(types), Value::Array(items)) if types.len() == items.len() => types
+                .iter()
+                .zip(items)
+                .map(|(inner, item)| inner.convert(item))
+                .collect::<Result<Vec<_>, _>>()
+                .map(serde_json::Value::Array),
+
+            (Self::Array(_), _) => Err(mismatch(value, "an array")),
+            (Self::Map(_), _) => Err(mismatch(value, "a map")),
+            (Self::Tuple(types), _) => Err(mismatch(
+                value,
+                &format!("an array of {} elements", types.len()),
+            )),
+            _ => Err(mismatch(value, &format!("{:?}", self))),
+        }
+    }
+}
+
+/// Checks that the integer fits in the integer type of the given width, as
+/// ClickHouse would otherwise wrap it around.
+fn check_int_range(int: i64, bits: u32, unsigned: bool) -> Result<(), String> {
+    // Any `Int64` fits in the wider types.
+    let bits = bits.min(64);
+    let (min, max) = if unsigned {
+        (0, (1i128 << bits) - 1)
+    } else {
+        (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+    };
+    if (min..=max).contains(&(int as i128)) {
+        Ok(())
+    } else {
+        let name = if unsigned { "UInt" } else { "Int" };
+        Err(format!("{} is out of the range of {}{}", int, name, bits))
+    }
+}
+
+fn mismatch(value: &Value, expected: &str) -> String {
+    let kind = match value {
+        Value::Bytes(_) => "a string",
+        Value::Integer(_) => "an integer",
+        Value::Float(_) => "a float",
+        Value::Boolean(_) => "a boolean",
+        Value::Timestamp(_) => "a timestamp",
+        Value::Map(_) => "a map",
+        Value::Array(_) => "an array",
+        Value::Null => "null",
+    };
+    format!("expected {}, got {}", expected, kind)
+}
+
+/// Formats the timestamp as the Unix time with `precision` decimals.
+fn format_datetime64(timestamp: &DateTime<Utc>, precision: u32) -> String {
+    if precision == 0 {
+        timestamp.timestamp().to_string()
+    } else {
+        let fraction = timestamp.timestamp_subsec_nanos() / 10u32.pow(9 - precision);
+        format!(
+            "{}.{:0width$}",
+            timestamp.timestamp(),
+            fraction,
+            width = precision as usize
+        )
+    }
+}
+
+/// Splits the arguments of a type on the commas outside of parentheses
+/// and quotes.
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, c) in args.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                parts.push(args[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = args[start..].trim();
+    if !last.is_empty() {
+        parts.push(last);
+    }
+    parts
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType,
+    /// The `MATERIALIZED` and `ALIAS` columns are computed by ClickHouse.
+    pub insertable: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableSchema {
+    pub columns: Vec<Column>,
+}
+
+#[derive(Deserialize)]
+struct DescribeRow {
+    name: String,
+    #[serde(rename = "type")]
+    column_type: String,
+    #[serde(default)]
+    default_type: String,
+}
+
+impl TableSchema {
+    /// Parses the output of `DESCRIBE TABLE ... FORMAT JSONEachRow`.
+    pub fn from_describe(output: &[u8]) -> Result<Self, serde_json::Error> {
+        let columns = serde_json::Deserializer::from_slice(output)
+            .into_iter::<DescribeRow>()
+            .map(|row| {
+                row.map(|row| Column {
+                    insertable: row.default_type != "MATERIALIZED" && row.default_type != "ALIAS",
+                    column_type: ColumnType::parse(&row.column_type),
+                    name: row.name,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns })
+    }
+
+    /// Builds the row of the event, with the values of the columns that
+    /// are set in the event.
+    pub fn encode(
+        &self,
+        log: &LogEvent,
+        unknown_fields: UnknownFields,
+    ) -> Result<Map<String, serde_json::Value>, RowError> {
+        if unknown_fields == UnknownFields::Error {
+            if let Some(field) = log.keys().find(|field| !self.covers(field)) {
+                return Err(RowError::UnknownField { field });
+            }
+        }
+
+        let mut row = Map::new();
+        for column in self.columns.iter().filter(|column| column.insertable) {
+            if let Some(value) = log.get(&column.name) {
+                let value = column.column_type.convert(value).map_err(|message| {
+                    RowError::InvalidValue {
+                        column: column.name.clone(),
+                        message,
+                    }
+                })?;
+                row.insert(column.name.clone(), value);
+            }
+        }
+        Ok(row)
+    }
+
+    // The fields nested in a column, like the keys of a `Map` or the
+    // elements of an `Array`, belong to it.
+    fn covers(&self, field: &str) -> bool {
+        self.columns.iter().any(|column| {
+            field
+                .strip_prefix(column.name.as_str())
+                .map_or(false, |rest| {
+                    rest.is_empty() || rest.starts_with('.') || rest.starts_with('[')
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn parses_column_types() {
+        use ColumnType::*;
+
+        assert_eq!(ColumnType::parse("UInt8"), UInt(8));
+        assert_eq!(
+            ColumnType::parse("DateTime64(6, 'Europe/Paris')"),
+            DateTime64(6)
+        );
+        assert_eq!(ColumnType::parse("DateTime('UTC')"), DateTime);
+        assert_eq!(
+            ColumnType::parse("LowCardinality(Nullable(String))"),
+            LowCardinality(Box::new(Nullable(Box::new(String))))
+        );
+        assert_eq!(
+            ColumnType::parse("Map(String, Array(Float64))"),
+            Map(Box::new(Array(Box::new(Float))))
+        );
+        assert_eq!(
+            ColumnType::parse("Tuple(a Int32, b Decimal(9, 2))"),
+            Tuple(vec![Int(32), Decimal])
+        );
+        assert_eq!(
+            ColumnType::parse("Enum8('a, b' = 1, 'c' = 2)"),
+            ColumnType::String
+        );
+        assert_eq!(ColumnType::parse("AggregateFunction(uniq, UInt64)"), Other);
+    }
+
+    fn schema() -> TableSchema {
+        TableSchema::from_describe(
+            br#"{"name":"timestamp","type":"DateTime64(3)","default_type":"","default_expression":""}
+{"name":"date","type":"Date","default_type":"MATERIALIZED","default_expression":"toDate(timestamp)"}
+{"name":"message","type":"String","default_type":"","default_expression":""}
+{"name":"level","type":"LowCardinality(String)","default_type":"","default_expression":""}
+{"name":"status","type":"Nullable(UInt16)","default_type":"","default_expression":""}
+{"name":"tags","type":"Array(String)","default_type":"","default_expression":""}
+{"name":"labels","type":"Map(String, String)","default_type":"","default_expression":""}
+{"name":"request.duration","type":"Float64","default_type":"","default_expression":""}
+"#,
+        )
+        .unwrap()
+    }
+
+    fn event() -> LogEvent {
+        let mut log = LogEvent::default();
+        log.insert(
+            "timestamp",
+            Utc.ymd(2020, 10, 15).and_hms_nano(11, 1, 46, 499_123_000),
+        );
+        log.insert("message", "GET /");
+        log.insert("level", "info");
+        log.insert("status", "200");
+        log.insert("tags", vec!["a", "b"]);
+        log.insert("labels.app", "nginx");
+        log.insert("labels.replicas", 3i64);
+        log.insert("request.duration", 12i64);
+        log
+    }
+
+    #[test]
+    fn reads_schema() {
+        let schema = schema();
+        assert_eq!(schema.columns.len(), 8);
+        assert_eq!(schema.columns[0].column_type, ColumnType::DateTime64(3));
+        assert!(!schema.columns[1].insertable);
+    }
+
+    #[test]
+    fn converts_values() {
+        let row = schema().encode(&event(), UnknownFields::Error).unwrap();
+
+        assert_eq!(
+            serde_json::Value::Object(row),
+            json!({
+                "timestamp": "1602759706.499",
+                "message": "GET /",
+                "level": "info",
+                "status": 200,
+                "tags": ["a", "b"],
+                "labels": { "app": "nginx", "replicas": "3" },
+                "request.duration": 12,
+            })
+        );
+    }
+
+    #[test]
+    fn handles_unknown_fields() {
+        let mut log = event();
+        log.insert("extra.field", "value");
+
+        let row = schema().encode(&log, UnknownFields::Skip).unwrap();
+        assert!(!row.contains_key("extra.field"));
+        assert_eq!(
+            schema().encode(&log, UnknownFields::Error),
+            Err(RowError::UnknownField {
+                field: "extra.field".into()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let mut log = event();
+        log.insert("status", -1i64);
+        assert!(schema().encode(&log, UnknownFields::Skip).is_err());
+
+        let mut log = event();
+        log.insert("tags", "a");
+        assert!(schema().encode(&log, UnknownFields::Skip).is_err());
+
+        let mut log = event();
+        let mut labels = BTreeMap::new();
+        labels.insert("app".to_string(), Value::from("nginx"));
+        log.insert("labels", Value::Map(labels));
+        log.insert("status", Value::Null);
+        assert!(schema().encode(&log, UnknownFields::Skip).is_ok());
+
+        let mut log = event();
+        log.insert("message", Value::Null);
+        assert!(schema().encode(&log, UnknownFields::Skip).is_err());
+
+        let mut log = event();
+        log.insert("tags", vec![Value::from("a"), Value::Null]);
+        assert!(schema().encode(&log, UnknownFields::Skip).is_err());
+    }
+
+    #[test]
+    fn checks_integer_ranges() {
+        assert_eq!(
+            ColumnType::Int(8).convert(&Value::from(127i64)),
+            Ok(json!(127))
+        );
+        assert!(ColumnType::Int(8).convert(&Value::from(128i64)).is_err());
+        assert!(ColumnType::Int(8).convert(&Value::from(-129i64)).is_err());
+        assert_eq!(
+            ColumnType::UInt(16).convert(&Value::from("65535")),
+            Ok(json!(65535))
+        );
+        assert!(ColumnType::UInt(16)
+            .convert(&Value::from(65536i64))
+            .is_err());
+        assert!(ColumnType::UInt(64).convert(&Value::from(-1i64)).is_err());
+        assert_eq!(
+            ColumnType::UInt(64).convert(&Value::from("18446744073709551615")),
+            Ok(json!("18446744073709551615"))
+        );
+        assert!(ColumnType::UInt(32)
+            .convert(&Value::from("18446744073709551615"))
+            .is_err());
+        assert_eq!(
+            ColumnType::Int(64).convert(&Value::from(i64::MIN)),
+            Ok(json!(i64::MIN))
+        );
+    }
+
+    #[test]
+    fn formats_datetime64() {
+        let timestamp = Utc.timestamp(1602759706, 499_123_456);
+        assert_eq!(format_datetime64(&timestamp, 0), "1602759706");
+        assert_eq!(format_datetime64(&timestamp, 3), "1602759706.499");
+        assert_eq!(format_datetime64(&timestamp, 9), "1602759706.499123456");
+        let timestamp = Utc.timestamp(1602759706, 5_000_000);
+        assert_eq!(format_datetime64(&timestamp, 3), "1602759706.005");
+    }
+}
@@ -1,36 +1,44 @@
+pub use crate::elasticsearch::ElasticSearchAuth;
 use crate::{
-    config::{DataType, SinkConfig, SinkContext, SinkDescription},
+    config::{log_schema, DataType, SinkConfig, SinkContext, SinkDescription},
+    elasticsearch::finish_signer,
     emit,
     event::Event,
     http::HttpClient,
-    internal_events::{ElasticSearchEventReceived, ElasticSearchMissingKeys},
+    internal_events::{
+        ElasticSearchBulkResponseMismatch, ElasticSearchEventReceived,
+        ElasticSearchInvalidBulkAction, ElasticSearchItemRejected, ElasticSearchMissingKeys,
+    },
     rusoto::{self, region_from_endpoint, RegionOrEndpoint},
     sinks::util::{
+        batch::{Batch, BatchError, PushResult},
+        buffer::GZIP_FAST,
         encoding::{EncodingConfigWithDefault, EncodingConfiguration},
-        http::{BatchedHttpSink, HttpSink},
+        http::{HttpBatchService, HttpSink},
         retries::{RetryAction, RetryLogic},
-        BatchConfig, BatchSettings, Buffer, Compression, TowerRequestConfig,
+        BatchConfig, BatchSettings, BatchSize, Compression, TowerRequestConfig, VecBuffer,
     },
     template::{Template, TemplateError},
     tls::{TlsOptions, TlsSettings},
 };
 use bytes::Bytes;
-use futures::{FutureExt, SinkExt};
-use http::{
-    header::{HeaderName, HeaderValue},
-    uri::InvalidUri,
-    Request, StatusCode, Uri,
-};
+use flate2::write::GzEncoder;
+use futures::{future::BoxFuture, stream, FutureExt, SinkExt, StreamExt};
+use http::{uri::InvalidUri, Request, StatusCode, Uri};
 use hyper::Body;
 use lazy_static::lazy_static;
 use rusoto_core::Region;
-use rusoto_credential::{CredentialsError, ProvideAwsCredentials};
 use rusoto_signature::{SignedRequest, SignedRequestPayload};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use snafu::{ResultExt, Snafu};
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tower::Service;
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 #[serde(deny_unknown_fields)]
@@ -42,6 +50,10 @@ pub struct ElasticSearchConfig {
     pub doc_type: Option<String>,
     pub id_key: Option<String>,
     pub pipeline: Option<String>,
+    pub bulk_action: Option<String>,
+    #[serde(default)]
+    pub mode: ElasticSearchMode,
+    pub data_stream: Option<DataStreamConfig>,
 
     #[serde(default)]
     pub compression: Compression,
@@ -77,19 +89,83 @@ pub enum Encoding {
     Default,
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
-#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "strategy")]
-pub enum ElasticSearchAuth {
-    Basic { user: String, password: String },
-    Aws { assume_role: Option<String> },
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone, Copy, Derivative)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum ElasticSearchMode {
+    #[derivative(Default)]
+    Normal,
+    DataStream,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Derivative)]
+#[serde(deny_unknown_fields)]
+#[derivative(Default)]
+pub struct DataStreamConfig {
+    #[serde(rename = "type", default = "DataStreamConfig::default_type")]
+    #[derivative(Default(value = "DataStreamConfig::default_type()"))]
+    pub dtype: String,
+    #[serde(default = "DataStreamConfig::default_dataset")]
+    #[derivative(Default(value = "DataStreamConfig::default_dataset()"))]
+    pub dataset: String,
+    #[serde(default = "DataStreamConfig::default_namespace")]
+    #[derivative(Default(value = "DataStreamConfig::default_namespace()"))]
+    pub namespace: String,
+    #[serde(default = "crate::serde::default_true")]
+    #[derivative(Default(value = "true"))]
+    pub auto_routing: bool,
+    #[serde(default = "crate::serde::default_true")]
+    #[derivative(Default(value = "true"))]
+    pub sync_fields: bool,
+}
+
+impl DataStreamConfig {
+    fn default_type() -> String {
+        "logs".into()
+    }
+
+    fn default_dataset() -> String {
+        "generic".into()
+    }
+
+    fn default_namespace() -> String {
+        "default".into()
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum BulkAction {
+    Index,
+    Create,
+    Update,
+    Delete,
+}
+
+impl BulkAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Index => "index",
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+
+    fn needs_id(self) -> bool {
+        matches!(self, Self::Update | Self::Delete)
+    }
 }
 
-impl ElasticSearchAuth {
-    pub fn apply<B>(&self, req: &mut Request<B>) {
-        if let Self::Basic { user, password } = &self {
-            use headers::{Authorization, HeaderMapExt};
-            let auth = Authorization::basic(&user, &password);
-            req.headers_mut().typed_insert(auth);
+impl FromStr for BulkAction {
+    type Err = String;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        match action {
+            "index" => Ok(Self::Index),
+            "create" => Ok(Self::Create),
+            "update" => Ok(Self::Update),
+            "delete" => Ok(Self::Delete),
+            _ => Err(format!("Invalid bulk action: {:?}", action)),
         }
     }
 }
@@ -112,24 +188,24 @@ impl SinkConfig for ElasticSearchConfig {
 
         let healthcheck = healthcheck(client.clone(), common).boxed();
 
-        let common = ElasticSearchCommon::parse_config(&self)?;
-        let compression = common.compression;
+        let common = Arc::new(ElasticSearchCommon::parse_config(&self)?);
         let batch = BatchSettings::default()
             .bytes(bytesize::mib(10u64))
             .timeout(1)
             .parse_config(self.batch)?;
         let request = self.request.unwrap_with(&REQUEST_DEFAULTS);
 
-        let sink = BatchedHttpSink::with_retry_logic(
-            common,
-            Buffer::new(batch.size, compression),
-            ElasticSearchRetryLogic,
-            request,
-            batch.timeout,
-            client,
-            cx.acker(),
-        )
-        .sink_map_err(|error| error!(message = "Fatal elasticsearch sink error.", %error));
+        let service = ElasticSearchService::new(client, Arc::clone(&common));
+        let sink = request
+            .batch_sink(
+                ElasticSearchRetryLogic,
+                service,
+                BulkBuffer::new(batch.size),
+                batch.timeout,
+                cx.acker(),
+            )
+            .sink_map_err(|error| error!(message = "Fatal elasticsearch sink error.", %error))
+            .with_flat_map(move |event| stream::iter(common.encode_event(event)).map(Ok));
 
         Ok((super::VectorSink::Sink(Box::new(sink)), healthcheck))
     }
@@ -150,6 +226,8 @@ pub struct ElasticSearchCommon {
     authorization: Option<String>,
     credentials: Option<rusoto::AwsCredentialsProvider>,
     index: Template,
+    data_stream: Option<DataStreamTemplates>,
+    bulk_action: Template,
     doc_type: String,
     tls_settings: TlsSettings,
     config: ElasticSearchConfig,
@@ -164,20 +242,72 @@ enum ParseError {
     InvalidHost { host: String, source: InvalidUri },
     #[snafu(display("Host {:?} must include hostname", host))]
     HostMustIncludeHostname { host: String },
-    #[snafu(display("Could not generate AWS credentials: {:?}", source))]
-    AWSCredentialsGenerateFailed { source: CredentialsError },
     #[snafu(display("Index template parse error: {}", source))]
     IndexTemplate { source: TemplateError },
+    #[snafu(display("Bulk action template parse error: {}", source))]
+    BulkActionTemplate { source: TemplateError },
+    #[snafu(display("Data stream template parse error: {}", source))]
+    DataStreamTemplate { source: TemplateError },
+    #[snafu(display("{}", message))]
+    InvalidBulkAction { message: String },
+    #[snafu(display("The {:?} bulk action requires the `id_key` option", action))]
+    BulkActionWithoutId { action: &'static str },
+    #[snafu(display("Data streams only accept the \"create\" bulk action"))]
+    DataStreamBulkAction,
+}
+
+#[derive(Debug)]
+struct DataStreamTemplates {
+    dtype: Template,
+    dataset: Template,
+    namespace: Template,
+    auto_routing: bool,
+    sync_fields: bool,
+}
+
+impl DataStreamTemplates {
+    fn new(config: &DataStreamConfig) -> Result<Self, ParseError> {
+        Ok(Self {
+            dtype: Template::try_from(config.dtype.as_str()).context(DataStreamTemplate)?,
+            dataset: Template::try_from(config.dataset.as_str()).context(DataStreamTemplate)?,
+            namespace: Template::try_from(config.namespace.as_str()).context(DataStreamTemplate)?,
+            auto_routing: config.auto_routing,
+            sync_fields: config.sync_fields,
+        })
+    }
+
+    // The `type-dataset-namespace` name of the data stream, taken from the
+    // `data_stream` fields of the event with `auto_routing`.
+    fn render(&self, event: &Event) -> Result<(String, String, String), Vec<String>> {
+        let part = |name: &str, template: &Template| {
+            let field = format!("data_stream.{}", name);
+            match event.as_log().get(&field) {
+                Some(value) if self.auto_routing => Ok(value.to_string_lossy()),
+                _ => template.render_string(event),
+            }
+        };
+        let dtype = part("type", &self.dtype);
+        let dataset = part("dataset", &self.dataset);
+        let namespace = part("namespace", &self.namespace);
+        match (dtype, dataset, namespace) {
+            (Ok(dtype), Ok(dataset), Ok(namespace)) => Ok((dtype, dataset, namespace)),
+            (dtype, dataset, namespace) => Err(vec![dtype.err(), dataset.err(), namespace.err()]
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect()),
+        }
+    }
 }
 
 #[async_trait::async_trait]
 impl HttpSink for ElasticSearchCommon {
-    type Input = Vec<u8>;
-    type Output = Vec<u8>;
+    type Input = Bytes;
+    type Output = BulkRequest;
 
     fn encode_event(&self, mut event: Event) -> Option<Self::Input> {
-        let index = self
-            .index
+        let action = self
+            .bulk_action
             .render_string(&event)
             .map_err(|missing_keys| {
                 emit!(ElasticSearchMissingKeys {
@@ -185,36 +315,80 @@ impl HttpSink for ElasticSearchCommon {
                 });
             })
             .ok()?;
+        let action = action
+            .parse::<BulkAction>()
+            .map_err(|_| emit!(ElasticSearchInvalidBulkAction { action: &action }))
+            .ok()?;
 
-        let mut action = json!({
-            "index": {
-                "_index": index,
-                "_type": self.doc_type,
+        let index = match &self.data_stream {
+            Some(data_stream) => {
+                let (dtype, dataset, namespace) = data_stream
+                    .render(&event)
+                    .map_err(|missing_keys| {
+                        emit!(ElasticSearchMissingKeys {
+                            keys: &missing_keys
+                        });
+                    })
+                    .ok()?;
+                let index = format!("{}-{}-{}", dtype, dataset, namespace);
+                prepare_data_stream_event(
+                    &mut event,
+                    data_stream.sync_fields,
+                    dtype,
+                    dataset,
+                    namespace,
+                );
+                index
             }
-        });
-        maybe_set_id(
-            self.config.id_key.as_ref(),
-            action.pointer_mut("/index").unwrap(),
-            &mut event,
-        );
+            None => self
+                .index
+                .render_string(&event)
+                .map_err(|missing_keys| {
+                    emit!(ElasticSearchMissingKeys {
+                        keys: &missing_keys
+                    });
+                })
+                .ok()?,
+        };
+
+        let mut metadata = json!({ "_index": index });
+        if self.data_stream.is_none() {
+            metadata["_type"] = json!(self.doc_type);
+        }
+        maybe_set_id(self.config.id_key.as_ref(), &mut metadata, &mut event);
+        if action.needs_id() && metadata.get("_id").is_none() {
+            emit!(ElasticSearchMissingKeys {
+                keys: &[self.config.id_key.clone().unwrap_or_default()]
+            });
+            return None;
+        }
 
-        let mut body = serde_json::to_vec(&action).unwrap();
+        let mut body = serde_json::to_vec(&json!({ action.as_str(): metadata })).unwrap();
         body.push(b'\n');
 
-        self.config.encoding.apply_rules(&mut event);
+        if action != BulkAction::Delete {
+            self.config.encoding.apply_rules(&mut event);
 
-        serde_json::to_writer(&mut body, &event.into_log()).unwrap();
-        body.push(b'\n');
+            let log = event.into_log();
+            if action == BulkAction::Update {
+                let doc = json!({ "doc": log, "doc_as_upsert": true });
+                serde_json::to_writer(&mut body, &doc).unwrap();
+            } else {
+                serde_json::to_writer(&mut body, &log).unwrap();
+            }
+            body.push(b'\n');
+        }
 
         emit!(ElasticSearchEventReceived {
             byte_size: body.len(),
             index
         });
 
-        Some(body)
+        Some(body.into())
     }
 
     async fn build_request(&self, events: Self::Output) -> crate::Result<http::Request<Vec<u8>>> {
+        let events = self.compress(events.body());
         let mut builder = Request::post(&self.bulk_uri);
 
         if let Some(credentials_provider) = &self.credentials {
@@ -264,19 +438,157 @@ impl HttpSink for ElasticSearchCommon {
     }
 }
 
+/// Batches the bulk items separately, so that a retry can resend only the
+/// ones that failed.
+struct BulkBuffer(VecBuffer<Bytes>);
+
+impl BulkBuffer {
+    fn new(settings: BatchSize<Self>) -> Self {
+        Self(VecBuffer::new(settings.into()))
+    }
+}
+
+impl Batch for BulkBuffer {
+    type Input = Bytes;
+    type Output = BulkRequest;
+
+    fn get_settings_defaults(
+        config: BatchConfig,
+        defaults: BatchSettings<Self>,
+    ) -> Result<BatchSettings<Self>, BatchError> {
+        VecBuffer::<Bytes>::get_settings_defaults(config.use_size_as_bytes()?, defaults.into())
+            .map(BatchSettings::into)
+    }
+
+    fn push(&mut self, item: Self::Input) -> PushResult<Self::Input> {
+        self.0.push(item)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn fresh(&self) -> Self {
+        Self(self.0.fresh())
+    }
+
+    fn finish(self) -> Self::Output {
+        BulkRequest(Arc::new(Mutex::new(self.0.finish())))
+    }
+
+    fn num_items(&self) -> usize {
+        self.0.num_items()
+    }
+}
+
+/// The items of a bulk request. They are shared with the retries of the
+/// request, which only resend the items the previous attempt failed to write.
+///
+/// The retries rely on `ElasticSearchService::call` removing the items that
+/// were written from the shared request once the response arrives, since the
+/// retry policy clones the request before it is sent.
+#[derive(Clone, Debug)]
+pub struct BulkRequest(Arc<Mutex<Vec<Bytes>>>);
+
+#[derive(Debug, Snafu)]
+enum BulkResponseError {
+    #[snafu(display("Bulk response has {} results for {} items", results, items))]
+    ItemCountMismatch { items: usize, results: usize },
+}
+
+impl BulkRequest {
+    fn body(&self) -> Vec<u8> {
+        self.0.lock().unwrap().concat()
+    }
+
+    /// Keeps the items that failed with a retriable status. When the results
+    /// can't be matched to the items, none of them is known to be unwritten,
+    /// so the request fails rather than resending them all.
+    fn retain_retriable(&self, response: &http::Response<Bytes>) -> Result<(), BulkResponseError> {
+        if !response.status().is_success() {
+            return Ok(());
+        }
+        let response = match serde_json::from_slice::<ESResultResponse>(response.body()) {
+            Ok(response) => response,
+            Err(_) => return Ok(()),
+        };
+
+        let mut items = self.0.lock().unwrap();
+        if response.items.len() != items.len() {
+            emit!(ElasticSearchBulkResponseMismatch {
+                items: items.len(),
+                results: response.items.len(),
+            });
+            return Err(BulkResponseError::ItemCountMismatch {
+                items: items.len(),
+                results: response.items.len(),
+            });
+        }
+        let mut results = response.items.iter();
+        items.retain(|_| {
+            results
+                .next()
+                .and_then(|result| result.values().next())
+                .map_or(false, |item| is_retriable(item.status))
+        });
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct ElasticSearchService {
+    inner: HttpBatchService<BoxFuture<'static, crate::Result<http::Request<Vec<u8>>>>, BulkRequest>,
+}
+
+impl ElasticSearchService {
+    fn new(client: HttpClient, common: Arc<ElasticSearchCommon>) -> Self {
+        let request_builder =
+            move |request| -> BoxFuture<'static, crate::Result<http::Request<Vec<u8>>>> {
+                let common = Arc::clone(&common);
+                Box::pin(async move { common.build_request(request).await })
+            };
+
+        Self {
+            inner: HttpBatchService::new(client, request_builder),
+        }
+    }
+}
+
+impl Service<BulkRequest> for ElasticSearchService {
+    type Response = http::Response<Bytes>;
+    type Error = crate::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: BulkRequest) -> Self::Future {
+        let response = self.inner.call(request.clone());
+
+        Box::pin(async move {
+            let response = response.await?;
+            request.retain_retriable(&response)?;
+            Ok(response)
+        })
+    }
+}
+
 #[derive(Clone)]
 struct ElasticSearchRetryLogic;
 
 #[derive(Deserialize, Debug)]
 struct ESResultResponse {
-    items: Vec<ESResultItem>,
+    // Each item is keyed by its bulk action.
+    items: Vec<HashMap<String, ESItemResult>>,
 }
 #[derive(Deserialize, Debug)]
-struct ESResultItem {
-    index: ESIndexResult,
-}
-#[derive(Deserialize, Debug)]
-struct ESIndexResult {
+struct ESItemResult {
+    #[serde(rename = "_index")]
+    index: Option<String>,
+    #[serde(rename = "_id")]
+    id: Option<String>,
+    status: u16,
     error: Option<ESErrorDetails>,
 }
 #[derive(Deserialize, Debug)]
@@ -315,7 +627,7 @@ impl RetryLogic for ElasticSearchRetryLogic {
                 let body = String::from_utf8_lossy(response.body());
 
                 if body.contains("\"errors\":true") {
-                    RetryAction::DontRetry(get_error_reason(&body))
+                    bulk_retry_action(&body)
                 } else {
                     RetryAction::Successful
                 }
@@ -325,28 +637,58 @@ impl RetryLogic for ElasticSearchRetryLogic {
     }
 }
 
-fn get_error_reason(body: &str) -> String {
-    match serde_json::from_str::<ESResultResponse>(&body) {
-        Err(json_error) => format!(
-            "some messages failed, could not parse response, error: {}",
-            json_error
-        ),
-        Ok(resp) => match resp.items.into_iter().find_map(|item| item.index.error) {
-            Some(error) => format!("error type: {}, reason: {}", error.err_type, error.reason),
-            None => format!("error response: {}", body),
-        },
+// The items of a bulk request are handled independently. The ones rejected
+// because of their content, like mapping errors, are reported one by one,
+// while the ones that can succeed later get retried. The retry only resends
+// those, see `BulkRequest`, so the written items aren't duplicated.
+fn bulk_retry_action(body: &str) -> RetryAction {
+    let response = match serde_json::from_str::<ESResultResponse>(&body) {
+        Ok(response) => response,
+        Err(json_error) => {
+            return RetryAction::DontRetry(format!(
+                "some messages failed, could not parse response, error: {}",
+                json_error
+            ))
+        }
+    };
+
+    let mut retriable = 0;
+    for (action, item) in response.items.iter().flatten() {
+        match item.status {
+            200..=299 => {}
+            409 if action == "create" => {}
+            status if is_retriable(status) => retriable += 1,
+            status => emit!(ElasticSearchItemRejected {
+                action,
+                index: item.index.as_deref(),
+                id: item.id.as_deref(),
+                status,
+                error_type: item.error.as_ref().map(|error| error.err_type.as_str()),
+                reason: item.error.as_ref().map(|error| error.reason.as_str()),
+            }),
+        }
+    }
+
+    if retriable > 0 {
+        RetryAction::Retry(format!(
+            "{} items failed with a retriable status",
+            retriable
+        ))
+    } else {
+        RetryAction::Successful
     }
 }
 
+fn is_retriable(status: u16) -> bool {
+    matches!(status, 429 | 500..=599)
+}
+
 impl ElasticSearchCommon {
     pub fn parse_config(config: &ElasticSearchConfig) -> crate::Result<Self> {
-        let authorization = match &config.auth {
-            Some(ElasticSearchAuth::Basic { user, password }) => {
-                let token = format!("{}:{}", user, password);
-                Some(format!("Basic {}", base64::encode(token.as_bytes())))
-            }
-            _ => None,
-        };
+        let authorization = config
+            .auth
+            .as_ref()
+            .and_then(ElasticSearchAuth::basic_authorization);
 
         let base_url = config.endpoint.clone();
         let region = match &config.aws {
@@ -377,6 +719,34 @@ impl ElasticSearchCommon {
         let index = config.index.as_deref().unwrap_or("vector-%Y.%m.%d");
         let index = Template::try_from(index).context(IndexTemplate)?;
 
+        let data_stream = match config.mode {
+            ElasticSearchMode::Normal => None,
+            ElasticSearchMode::DataStream => Some(DataStreamTemplates::new(
+                &config.data_stream.clone().unwrap_or_default(),
+            )?),
+        };
+        let default_action = match config.mode {
+            ElasticSearchMode::Normal => "index",
+            ElasticSearchMode::DataStream => "create",
+        };
+        let bulk_action = config.bulk_action.as_deref().unwrap_or(default_action);
+        let bulk_action = Template::try_from(bulk_action).context(BulkActionTemplate)?;
+        if !bulk_action.is_dynamic() {
+            let action = bulk_action
+                .get_ref()
+                .parse::<BulkAction>()
+                .map_err(|message| ParseError::InvalidBulkAction { message })?;
+            if data_stream.is_some() && action != BulkAction::Create {
+                return Err(ParseError::DataStreamBulkAction.into());
+            }
+            if action.needs_id() && config.id_key.is_none() {
+                return Err(ParseError::BulkActionWithoutId {
+                    action: action.as_str(),
+                }
+                .into());
+            }
+        }
+
         let doc_type = config.doc_type.clone().unwrap_or_else(|| "_doc".into());
 
         let request = config.request.unwrap_with(&REQUEST_DEFAULTS);
@@ -404,6 +774,8 @@ impl ElasticSearchCommon {
             authorization,
             credentials,
             index,
+            data_stream,
+            bulk_action,
             doc_type,
             tls_settings,
             config,
@@ -413,6 +785,21 @@ impl ElasticSearchCommon {
         })
     }
 
+    fn compress(&self, body: Vec<u8>) -> Vec<u8> {
+        match self.compression {
+            Compression::None => body,
+            Compression::Gzip(level) => {
+                let level = level.unwrap_or(GZIP_FAST);
+                let mut encoder =
+                    GzEncoder::new(Vec::new(), flate2::Compression::new(level as u32));
+                encoder
+                    .write_all(&body)
+                    .and_then(|_| encoder.finish())
+                    .expect("This can't fail because the inner writer is a Vec")
+            }
+        }
+    }
+
     fn signed_request(&self, method: &str, uri: &Uri, use_params: bool) -> SignedRequest {
         let mut request = SignedRequest::new(method, "es", &self.region, uri.path());
         if use_params {
@@ -447,30 +834,26 @@ async fn healthcheck(client: HttpClient, common: ElasticSearchCommon) -> crate::
     }
 }
 
-async fn finish_signer(
-    signer: &mut SignedRequest,
-    credentials_provider: &rusoto::AwsCredentialsProvider,
-    mut builder: http::request::Builder,
-) -> crate::Result<http::request::Builder> {
-    let credentials = credentials_provider
-        .credentials()
-        .await
-        .context(AWSCredentialsGenerateFailed)?;
-
-    signer.sign(&credentials);
-
-    for (name, values) in signer.headers() {
-        let header_name = name
-            .parse::<HeaderName>()
-            .expect("Could not parse header name.");
-        for value in values {
-            let header_value =
-                HeaderValue::from_bytes(value).expect("Could not parse header value.");
-            builder = builder.header(&header_name, header_value);
+// Data streams require a `@timestamp` field, and the `data_stream` fields
+// of their events are kept in sync with their name.
+fn prepare_data_stream_event(
+    event: &mut Event,
+    sync_fields: bool,
+    dtype: String,
+    dataset: String,
+    namespace: String,
+) {
+    let log = event.as_mut_log();
+    if log.get_flat("@timestamp").is_none() {
+        if let Some(timestamp) = log.remove(log_schema().timestamp_key()) {
+            log.insert_flat("@timestamp", timestamp);
         }
     }
-
-    Ok(builder)
+    if sync_fields {
+        log.insert("data_stream.type", dtype);
+        log.insert("data_stream.dataset", dataset);
+        log.insert("data_stream.namespace", namespace);
+    }
 }
 
 fn maybe_set_id(key: Option<impl AsRef<str>>, doc: &mut serde_json::Value, event: &mut Event) {
@@ -486,8 +869,12 @@ fn maybe_set_id(key: Option<impl AsRef<str>>, doc: &mut serde_json::Value, event
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{sinks::util::retries::RetryAction, Event};
+    use crate::{sinks::util::retries::RetryAction, test_util::next_addr, Event};
     use http::{Response, StatusCode};
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Server,
+    };
     use pretty_assertions::assert_eq;
     use serde_json::json;
 
@@ -541,6 +928,132 @@ mod tests {
             .body(Bytes::from(json))
             .unwrap();
         let logic = ElasticSearchRetryLogic;
+        assert!(matches!(
+            logic.should_retry_response(&response),
+            RetryAction::Successful
+        ));
+    }
+
+    fn bulk_response(items: &[(&str, u16)]) -> Response<Bytes> {
+        let items = items
+            .iter()
+            .map(|(action, status)| json!({ *action: { "_index": "test", "status": status } }))
+            .collect::<Vec<_>>();
+        let body = json!({ "took": 3, "errors": true, "items": items });
+        Response::builder()
+            .status(StatusCode::OK)
+            .body(Bytes::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[test]
+    fn retries_partial_failures() {
+        let logic = ElasticSearchRetryLogic;
+        assert!(matches!(
+            logic.should_retry_response(&bulk_response(&[("index", 201), ("index", 429)])),
+            RetryAction::Retry(_)
+        ));
+        assert!(matches!(
+            logic.should_retry_response(&bulk_response(&[("create", 503), ("create", 400)])),
+            RetryAction::Retry(_)
+        ));
+        assert!(matches!(
+            logic.should_retry_response(&bulk_response(&[("create", 201), ("create", 409)])),
+            RetryAction::Successful
+        ));
+        assert!(matches!(
+            logic.should_retry_response(&bulk_response(&[("index", 409), ("delete", 404)])),
+            RetryAction::Successful
+        ));
+    }
+
+    #[test]
+    fn fails_mismatched_bulk_response() {
+        let request = BulkRequest(Arc::new(Mutex::new(vec![
+            Bytes::from("first"),
+            Bytes::from("second"),
+        ])));
+        let response = bulk_response(&[("index", 429)]);
+
+        assert!(matches!(
+            request.retain_retriable(&response),
+            Err(BulkResponseError::ItemCountMismatch {
+                items: 2,
+                results: 1
+            })
+        ));
+        assert_eq!(request.body(), b"firstsecond".to_vec());
+    }
+
+    #[tokio::test]
+    async fn resends_only_failed_items() {
+        let addr = next_addr();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let responses = Arc::new(Mutex::new(vec![
+            bulk_response(&[("index", 201)]),
+            bulk_response(&[("index", 201), ("index", 429)]),
+        ]));
+
+        let received = Arc::clone(&requests);
+        let service = make_service_fn(move |_| {
+            let received = Arc::clone(&received);
+            let responses = Arc::clone(&responses);
+            async move {
+                Ok::<_, crate::Error>(service_fn(move |request: Request<Body>| {
+                    let received = Arc::clone(&received);
+                    let response = responses.lock().unwrap().pop().unwrap();
+                    async move {
+                        let body = hyper::body::to_bytes(request.into_body()).await?;
+                        received.lock().unwrap().push(body);
+                        Ok::<_, crate::Error>(response.map(Body::from))
+                    }
+                }))
+            }
+        });
+        tokio::spawn(Server::bind(&addr).serve(service));
+
+        let config = ElasticSearchConfig {
+            endpoint: format!("http://{}", addr),
+            index: Some("test".into()),
+            batch: BatchConfig {
+                max_events: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (sink, _) = config.build(SinkContext::new_test()).await.unwrap();
+        sink.run(stream::iter(vec![
+            Event::from("first"),
+            Event::from("second"),
+        ]))
+        .await
+        .unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let documents = |body: &Bytes| {
+            String::from_utf8_lossy(body)
+                .lines()
+                .filter(|line| line.contains("message"))
+                .map(|line| {
+                    serde_json::from_str::<serde_json::Value>(line).unwrap()["message"].clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            documents(&requests[0]),
+            vec![json!("first"), json!("second")]
+        );
+        assert_eq!(documents(&requests[1]), vec![json!("second")]);
+    }
+
+    #[test]
+    fn rejects_unparsable_error_response() {
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .body(Bytes::from("{\"errors\":true,\"items\":\"nope\"}"))
+            .unwrap();
+        let logic = ElasticSearchRetryLogic;
         assert!(matches!(
             logic.should_retry_response(&response),
             RetryAction::DontRetry(_)
@@ -571,6 +1084,130 @@ mod tests {
 "#;
         assert_eq!(std::str::from_utf8(&encoded).unwrap(), &expected[..]);
     }
+
+    fn encode(config: ElasticSearchConfig, event: Event) -> Option<String> {
+        let es = ElasticSearchCommon::parse_config(&ElasticSearchConfig {
+            endpoint: String::from("https://example.com"),
+            ..config
+        })
+        .unwrap();
+        es.encode_event(event)
+            .map(|encoded| String::from_utf8(encoded.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn encodes_bulk_actions() {
+        let config = ElasticSearchConfig {
+            index: Some(String::from("vector")),
+            id_key: Some(String::from("id")),
+            bulk_action: Some(String::from("{{ action }}")),
+            ..Default::default()
+        };
+        let event = |action: &str| {
+            let mut event = Event::from("hello there");
+            event.as_mut_log().insert("id", "42");
+            event.as_mut_log().insert("action", action);
+            event.as_mut_log().remove("timestamp");
+            event
+        };
+
+        assert_eq!(
+            encode(config.clone(), event("create")).unwrap(),
+            r#"{"create":{"_id":"42","_index":"vector","_type":"_doc"}}
+{"action":"create","message":"hello there"}
+"#
+        );
+        assert_eq!(
+            encode(config.clone(), event("update")).unwrap(),
+            r#"{"update":{"_id":"42","_index":"vector","_type":"_doc"}}
+{"doc":{"action":"update","message":"hello there"},"doc_as_upsert":true}
+"#
+        );
+        assert_eq!(
+            encode(config.clone(), event("delete")).unwrap(),
+            r#"{"delete":{"_id":"42","_index":"vector","_type":"_doc"}}
+"#
+        );
+        assert_eq!(encode(config.clone(), event("upsert")), None);
+
+        let mut event = event("delete");
+        event.as_mut_log().remove("id");
+        assert_eq!(encode(config, event), None);
+    }
+
+    #[test]
+    fn validates_bulk_actions() {
+        let parse = |config: ElasticSearchConfig| {
+            ElasticSearchCommon::parse_config(&ElasticSearchConfig {
+                endpoint: String::from("https://example.com"),
+                ..config
+            })
+        };
+
+        assert!(parse(ElasticSearchConfig {
+            bulk_action: Some(String::from("upsert")),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(parse(ElasticSearchConfig {
+            bulk_action: Some(String::from("delete")),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(parse(ElasticSearchConfig {
+            bulk_action: Some(String::from("index")),
+            mode: ElasticSearchMode::DataStream,
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn encodes_data_streams() {
+        let config = ElasticSearchConfig {
+            mode: ElasticSearchMode::DataStream,
+            data_stream: Some(DataStreamConfig {
+                dataset: String::from("{{ app }}"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut event = Event::from("hello there");
+        event.as_mut_log().insert("app", "nginx");
+        let timestamp = event.as_log()[log_schema().timestamp_key()].clone();
+        let encoded = encode(config.clone(), event).unwrap();
+        let mut lines = encoded.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            r#"{"create":{"_index":"logs-nginx-default"}}"#
+        );
+        let doc = serde_json::from_str::<serde_json::Value>(lines.next().unwrap()).unwrap();
+        assert_eq!(
+            doc,
+            json!({
+                "@timestamp": timestamp,
+                "app": "nginx",
+                "data_stream": {
+                    "type": "logs",
+                    "dataset": "nginx",
+                    "namespace": "default",
+                },
+                "message": "hello there",
+            })
+        );
+
+        // The `data_stream` fields of the events take precedence.
+        let mut event = Event::from("hello there");
+        event.as_mut_log().insert("app", "nginx");
+        event
+            .as_mut_log()
+            .insert("data_stream.namespace", "production");
+        let encoded = encode(config.clone(), event).unwrap();
+        assert!(encoded.starts_with(r#"{"create":{"_index":"logs-nginx-production"}}"#));
+
+        assert_eq!(encode(config, Event::from("hello there")), None);
+    }
 }
 
 #[cfg(test)]
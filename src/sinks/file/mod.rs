@@ -25,9 +25,13 @@ use tokio::{
     io::AsyncWriteExt,
 };
 mod bytes_path;
+mod rotation;
 use bytes_path::BytesPath;
+use rotation::{RotatedPaths, Rotation};
 use std::convert::TryFrom;
 
+pub use rotation::RotationConfig;
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct FileSinkConfig {
@@ -43,6 +47,7 @@ pub struct FileSinkConfig {
         skip_serializing_if = "crate::serde::skip_serializing_if_default"
     )]
     pub compression: Compression,
+    pub rotation: Option<RotationConfig>,
 }
 
 inventory::submit! {
@@ -56,6 +61,7 @@ impl GenerateConfig for FileSinkConfig {
             idle_timeout_secs: None,
             encoding: Default::default(),
             compression: Default::default(),
+            rotation: None,
         })
         .unwrap()
     }
@@ -129,6 +135,23 @@ impl OutFile {
     }
 }
 
+struct OpenFile {
+    file: OutFile,
+    /// Set when the files are rotated.
+    paths: Option<RotatedPaths>,
+    opened_at: Instant,
+    /// The number of bytes written, before compression.
+    written: u64,
+}
+
+impl OpenFile {
+    async fn write_all(&mut self, src: &[u8]) -> Result<(), std::io::Error> {
+        self.file.write_all(src).await?;
+        self.written += src.len() as u64;
+        Ok(())
+    }
+}
+
 #[async_trait::async_trait]
 #[typetag::serde(name = "file")]
 impl SinkConfig for FileSinkConfig {
@@ -158,8 +181,9 @@ pub struct FileSink {
     path: Template,
     encoding: EncodingConfigWithDefault<Encoding>,
     idle_timeout: Duration,
-    files: ExpiringHashMap<Bytes, OutFile>,
+    files: ExpiringHashMap<Bytes, OpenFile>,
     compression: Compression,
+    rotation: Option<Rotation>,
 }
 
 impl FileSink {
@@ -171,6 +195,7 @@ impl FileSink {
             idle_timeout: Duration::from_secs(config.idle_timeout_secs.unwrap_or(30)),
             files: ExpiringHashMap::default(),
             compression: config.compression,
+            rotation: config.rotation.as_ref().map(Rotation::new),
         }
     }
 
@@ -191,10 +216,15 @@ impl FileSink {
         Some(bytes)
     }
 
-    fn deadline_at(&self) -> Instant {
-        Instant::now()
+    /// Files are closed once idle, or once too old when they're rotated.
+    fn deadline_at(&self, opened_at: Instant) -> Instant {
+        let idle_deadline = Instant::now()
             .checked_add(self.idle_timeout)
-            .expect("unable to compute next deadline")
+            .expect("unable to compute next deadline");
+        self.rotation
+            .as_ref()
+            .and_then(|rotation| rotation.deadline(opened_at))
+            .map_or(idle_deadline, |deadline| deadline.min(idle_deadline))
     }
 
     async fn run(&mut self, mut input: BoxStream<'_, Event>) -> crate::Result<()> {
@@ -213,7 +243,7 @@ impl FileSink {
                             // Close all the open files.
                             debug!(message = "Closing all the open files.");
                             for (path, file) in self.files.iter_mut() {
-                                if let Err(error) = file.close().await {
+                                if let Err(error) = close_file(path, file, self.rotation.as_mut()).await {
                                     error!(message = "Failed to close file.", path = ?path, %error);
                                 } else{
                                     trace!(message = "Successfully closed file.", path = ?path);
@@ -232,7 +262,7 @@ impl FileSink {
                         Some(Ok((mut expired_file, path))) => {
                             // We got an expired file. All we really want is to
                             // flush and close it.
-                            if let Err(error) = expired_file.close().await {
+                            if let Err(error) = close_file(path.get_ref(), &mut expired_file, self.rotation.as_mut()).await {
                                 error!(message = "Failed to close file.", path = ?path, %error);
                             }
                             drop(expired_file); // ignore close error
@@ -260,7 +290,28 @@ impl FileSink {
             }
         };
 
-        let next_deadline = self.deadline_at();
+        let mut buf = encode_event(&self.encoding, event);
+        buf.push(b'\n');
+
+        let rotate = match (&self.rotation, self.files.get(&path)) {
+            (Some(rotation), Some(file)) => {
+                rotation.should_rotate(file.opened_at, file.written, buf.len())
+            }
+            _ => false,
+        };
+        if rotate {
+            trace!(message = "Rotating file.", path = ?path);
+            let (mut file, _) = self.files.remove(&path).expect("File was checked.");
+            if let Err(error) = close_file(&path, &mut file, self.rotation.as_mut()).await {
+                error!(message = "Failed to close file.", path = ?path, %error);
+            }
+        }
+
+        let opened_at = self
+            .files
+            .get(&path)
+            .map_or_else(Instant::now, |file| file.opened_at);
+        let next_deadline = self.deadline_at(opened_at);
         trace!(message = "Computed next deadline.", next_deadline = ?next_deadline, path = ?path);
 
         let file = if let Some(file) = self.files.reset_at(&path, next_deadline) {
@@ -268,7 +319,19 @@ impl FileSink {
             file
         } else {
             trace!(message = "Opening new file.", ?path);
-            let file = match open_file(BytesPath::new(path.clone())).await {
+            let paths = match self.rotation.as_mut() {
+                Some(rotation) => Some(
+                    rotation
+                        .paths(&path, BytesPath::new(path.clone()).as_ref())
+                        .await,
+                ),
+                None => None,
+            };
+            let opened = match &paths {
+                Some(paths) => open_file(&paths.temporary).await,
+                None => open_file(BytesPath::new(path.clone())).await,
+            };
+            let file = match opened {
                 Ok(file) => file,
                 Err(error) => {
                     // We couldn't open the file for this event.
@@ -279,19 +342,37 @@ impl FileSink {
                 }
             };
 
-            let outfile = OutFile::new(file, self.compression);
+            let outfile = OpenFile {
+                file: OutFile::new(file, self.compression),
+                paths,
+                opened_at,
+                written: 0,
+            };
 
             self.files.insert_at(path.clone(), outfile, next_deadline);
             self.files.get_mut(&path).unwrap()
         };
 
         trace!(message = "Writing an event to file.", path = ?path);
-        if let Err(error) = write_event_to_file(file, event, &self.encoding).await {
+        if let Err(error) = file.write_all(&buf).await {
             error!(message = "Failed to write file.", path = ?path, %error);
         }
     }
 }
 
+/// Closes the file, and moves it to its final path when it's rotated.
+async fn close_file(
+    path: &Bytes,
+    file: &mut OpenFile,
+    rotation: Option<&mut Rotation>,
+) -> Result<(), std::io::Error> {
+    file.file.close().await?;
+    match (rotation, &file.paths) {
+        (Some(rotation), Some(paths)) => rotation.finalize(path, paths).await,
+        _ => Ok(()),
+    }
+}
+
 async fn open_file(path: impl AsRef<std::path::Path>) -> std::io::Result<File> {
     let parent = path.as_ref().parent();
 
@@ -320,16 +401,6 @@ pub fn encode_event(encoding: &EncodingConfigWithDefault<Encoding>, mut event: E
     }
 }
 
-async fn write_event_to_file(
-    file: &mut OutFile,
-    event: Event,
-    encoding: &EncodingConfigWithDefault<Encoding>,
-) -> Result<(), std::io::Error> {
-    let mut buf = encode_event(encoding, event);
-    buf.push(b'\n');
-    file.write_all(&buf[..]).await
-}
-
 #[async_trait]
 impl StreamSink for FileSink {
     async fn run(&mut self, input: BoxStream<'_, Event>) -> Result<(), ()> {
@@ -364,6 +435,7 @@ mod tests {
             idle_timeout_secs: None,
             encoding: Encoding::Text.into(),
             compression: Compression::None,
+            rotation: None,
         };
 
         let mut sink = FileSink::new(&config, Acker::Null);
@@ -389,6 +461,7 @@ mod tests {
             idle_timeout_secs: None,
             encoding: Encoding::Text.into(),
             compression: Compression::Gzip,
+            rotation: None,
         };
 
         let mut sink = FileSink::new(&config, Acker::Null);
@@ -419,6 +492,7 @@ mod tests {
             idle_timeout_secs: None,
             encoding: Encoding::Text.into(),
             compression: Compression::None,
+            rotation: None,
         };
 
         let mut sink = FileSink::new(&config, Acker::Null);
@@ -487,6 +561,46 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn rotation() {
+        trace_init();
+
+        let directory = temp_dir();
+
+        let config = FileSinkConfig {
+            path: directory.join("app.log").try_into().unwrap(),
+            idle_timeout_secs: None,
+            encoding: Encoding::Text.into(),
+            compression: Compression::None,
+            rotation: Some(RotationConfig {
+                // Two lines per file.
+                max_bytes: Some(80),
+                max_age_secs: None,
+                max_files: Some(2),
+            }),
+        };
+
+        let mut sink = FileSink::new(&config, Acker::Null);
+        let (input, _events) = random_lines_with_stream(39, 10);
+
+        let events = Box::pin(stream::iter(input.clone().into_iter().map(Event::from)));
+        sink.run(events).await.unwrap();
+
+        let mut paths = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths.len(), 2);
+        for path in &paths {
+            let name = path.file_name().unwrap().to_string_lossy();
+            assert!(name.starts_with("app-") && name.ends_with(".log"));
+        }
+
+        let output = paths.iter().flat_map(lines_from_file).collect::<Vec<_>>();
+        assert_eq!(output, input[6..].to_vec());
+    }
+
     #[tokio::test]
     async fn reopening() {
         use pretty_assertions::assert_eq;
@@ -500,6 +614,7 @@ mod tests {
             idle_timeout_secs: Some(1),
             encoding: Encoding::Text.into(),
             compression: Compression::None,
+            rotation: None,
         };
 
         let mut sink = FileSink::new(&config, Acker::Null);
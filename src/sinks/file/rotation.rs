@@ -0,0 +1,321 @@
+//! Rotation of the files: they are written to a temporary path, and renamed
+//! to their final path once closed, so readers never see partial files.
+//! The files rotated by a previous run are found on the first use of their
+//! path, counting towards `max_files`, and the temporary files it left are
+//! renamed to their final path.
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::fs;
+
+const TEMPORARY_EXTENSION: &str = "tmp";
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RotationConfig {
+    /// The size of the file, before compression, past which it's rotated.
+    pub max_bytes: Option<u64>,
+    pub max_age_secs: Option<u64>,
+    /// The number of finalized files to keep for each path.
+    pub max_files: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Rotation {
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    max_files: Option<usize>,
+    /// The files finalized for each path, oldest first. A path is only
+    /// present once its directory was scanned.
+    finalized: HashMap<Bytes, VecDeque<PathBuf>>,
+    last_suffix: u64,
+}
+
+/// The paths of a rotated file.
+#[derive(Debug)]
+pub struct RotatedPaths {
+    pub temporary: PathBuf,
+    pub final_path: PathBuf,
+}
+
+impl Rotation {
+    pub fn new(config: &RotationConfig) -> Self {
+        Self {
+            max_bytes: config.max_bytes,
+            max_age: config.max_age_secs.map(Duration::from_secs),
+            max_files: config.max_files,
+            finalized: HashMap::new(),
+            last_suffix: 0,
+        }
+    }
+
+    /// The instant the file opened at `opened_at` must be closed at, at the
+    /// latest.
+    pub fn deadline(&self, opened_at: Instant) -> Option<Instant> {
+        self.max_age
+            .and_then(|max_age| opened_at.checked_add(max_age))
+    }
+
+    /// Whether the file must be rotated before writing `len` more bytes to
+    /// it. Files are never left empty, so events larger than `max_bytes` get
+    /// a file of their own.
+    pub fn should_rotate(&self, opened_at: Instant, written: u64, len: usize) -> bool {
+        let too_large = self.max_bytes.map_or(false, |max_bytes| {
+            written > 0 && written + len as u64 > max_bytes
+        });
+        let too_old = self
+            .max_age
+            .map_or(false, |max_age| opened_at.elapsed() >= max_age);
+        too_large || too_old
+    }
+
+    /// Picks unique paths for a new file of `path`, rendered from `key`. The
+    /// final path has the time the file was opened, in milliseconds,
+    /// appended to its stem, so `/var/log/app.log` becomes
+    /// `/var/log/app-1603118400000.log`.
+    pub async fn paths(&mut self, key: &Bytes, path: &Path) -> RotatedPaths {
+        if !self.finalized.contains_key(key) {
+            let finalized = self.recover(path).await;
+            self.finalized.insert(key.clone(), finalized);
+            self.remove_oldest(key).await;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default();
+        // Files rotated within the same millisecond still get distinct names.
+        let suffix = now.max(self.last_suffix + 1);
+        self.last_suffix = suffix;
+
+        let final_path = suffixed_path(path, suffix);
+        let mut temporary = final_path.clone().into_os_string();
+        temporary.push(".");
+        temporary.push(TEMPORARY_EXTENSION);
+
+        RotatedPaths {
+            temporary: temporary.into(),
+            final_path,
+        }
+    }
+
+    /// Renames the closed file of `key` to its final path, and removes the
+    /// oldest files of `key` past `max_files`.
+    pub async fn finalize(&mut self, key: &Bytes, paths: &RotatedPaths) -> std::io::Result<()> {
+        fs::rename(&paths.temporary, &paths.final_path).await?;
+
+        self.finalized
+            .entry(key.clone())
+            .or_default()
+            .push_back(paths.final_path.clone());
+        self.remove_oldest(key).await;
+
+        Ok(())
+    }
+
+    /// Removes the oldest files of `key` past `max_files`.
+    async fn remove_oldest(&mut self, key: &Bytes) {
+        let (max_files, finalized) = match (self.max_files, self.finalized.get_mut(key)) {
+            (Some(max_files), Some(finalized)) => (max_files, finalized),
+            _ => return,
+        };
+        while finalized.len() > max_files {
+            let oldest = finalized.pop_front().expect("Files were checked.");
+            if let Err(error) = fs::remove_file(&oldest).await {
+                error!(message = "Failed to remove rotated file.", path = ?oldest, %error);
+            }
+        }
+    }
+
+    /// Finds the files of `path` rotated by a previous run, oldest first,
+    /// renaming the temporary ones it didn't get to close.
+    async fn recover(&mut self, path: &Path) -> VecDeque<PathBuf> {
+        let directory = match path.parent() {
+            Some(directory) if directory != Path::new("") => directory,
+            _ => Path::new("."),
+        };
+        let mut entries = match fs::read_dir(directory).await {
+            Ok(entries) => entries,
+            Err(error) => {
+                if error.kind() != std::io::ErrorKind::NotFound {
+                    error!(message = "Failed to scan for rotated files.", ?directory, %error);
+                }
+                return VecDeque::new();
+            }
+        };
+
+        let mut found = Vec::new();
+        loop {
+            let entry = match entries.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(error) => {
+                    error!(message = "Failed to scan for rotated files.", ?directory, %error);
+                    break;
+                }
+            };
+            let name = entry.file_name();
+            let name = match name.to_str() {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let temporary = name
+                .strip_suffix(TEMPORARY_EXTENSION)
+                .and_then(|name| name.strip_suffix('.'));
+            let suffix = match rotation_suffix(path, temporary.unwrap_or(name)) {
+                Some(suffix) => suffix,
+                None => continue,
+            };
+            let final_path = suffixed_path(path, suffix);
+            if temporary.is_some() {
+                if let Err(error) = fs::rename(entry.path(), &final_path).await {
+                    error!(message = "Failed to recover temporary file.", path = ?entry.path(), %error);
+                    continue;
+                }
+                info!(message = "Recovered temporary file.", path = ?final_path);
+            }
+            found.push((suffix, final_path));
+        }
+
+        found.sort();
+        found.dedup();
+        if let Some((suffix, _)) = found.last() {
+            self.last_suffix = self.last_suffix.max(*suffix);
+        }
+        found.into_iter().map(|(_, path)| path).collect()
+    }
+}
+
+fn suffixed_path(path: &Path, suffix: u64) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("-{}", suffix));
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// The suffix of `name` if it's the name of a file rotated from `path`.
+fn rotation_suffix(path: &Path, name: &str) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    let name = match path.extension() {
+        Some(extension) => name.strip_suffix(extension.to_str()?)?.strip_suffix('.')?,
+        None => name,
+    };
+    let suffix = name.strip_prefix(stem)?.strip_prefix('-')?;
+    if suffix.is_empty() || !suffix.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    suffix.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn suffixes_paths() {
+        assert_eq!(
+            suffixed_path(Path::new("/var/log/app.log"), 42),
+            PathBuf::from("/var/log/app-42.log")
+        );
+        assert_eq!(
+            suffixed_path(Path::new("/var/log/app.log.gz"), 42),
+            PathBuf::from("/var/log/app.log-42.gz")
+        );
+        assert_eq!(
+            suffixed_path(Path::new("/var/log/app"), 42),
+            PathBuf::from("/var/log/app-42")
+        );
+    }
+
+    #[test]
+    fn parses_suffixes() {
+        let path = Path::new("/var/log/app.log");
+        assert_eq!(rotation_suffix(path, "app-42.log"), Some(42));
+        assert_eq!(rotation_suffix(path, "app.log"), None);
+        assert_eq!(rotation_suffix(path, "app-.log"), None);
+        assert_eq!(rotation_suffix(path, "app-42.log.gz"), None);
+        assert_eq!(rotation_suffix(path, "app-other-42.log"), None);
+        assert_eq!(
+            rotation_suffix(Path::new("/var/log/app"), "app-42"),
+            Some(42)
+        );
+    }
+
+    #[tokio::test]
+    async fn picks_unique_paths() {
+        let mut rotation = Rotation::new(&RotationConfig::default());
+        let path = temp_dir().join("app.log");
+        let key = Bytes::from(path.to_str().unwrap().to_owned());
+
+        let first = rotation.paths(&key, &path).await;
+        let second = rotation.paths(&key, &path).await;
+        assert_ne!(first.final_path, second.final_path);
+
+        let mut temporary = first.final_path.into_os_string();
+        temporary.push(".tmp");
+        assert_eq!(first.temporary, PathBuf::from(temporary));
+    }
+
+    #[tokio::test]
+    async fn recovers_previous_files() {
+        let directory = temp_dir();
+        std::fs::create_dir_all(&directory).unwrap();
+        for name in &[
+            "app-1.log",
+            "app-2.log",
+            "app-3.log.tmp",
+            "other-4.log",
+            "app.log",
+        ] {
+            std::fs::write(directory.join(name), name).unwrap();
+        }
+
+        let mut rotation = Rotation::new(&RotationConfig {
+            max_bytes: None,
+            max_age_secs: None,
+            max_files: Some(2),
+        });
+        let path = directory.join("app.log");
+        let key = Bytes::from(path.to_str().unwrap().to_owned());
+        rotation.paths(&key, &path).await;
+
+        let mut names = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["app-2.log", "app-3.log", "app.log", "other-4.log"]
+        );
+        assert_eq!(
+            std::fs::read_to_string(directory.join("app-3.log")).unwrap(),
+            "app-3.log.tmp"
+        );
+    }
+
+    #[test]
+    fn rotates_large_and_old_files() {
+        let rotation = Rotation::new(&RotationConfig {
+            max_bytes: Some(100),
+            max_age_secs: Some(60),
+            max_files: None,
+        });
+        let now = Instant::now();
+
+        assert!(!rotation.should_rotate(now, 0, 200));
+        assert!(!rotation.should_rotate(now, 50, 50));
+        assert!(rotation.should_rotate(now, 50, 51));
+        assert!(rotation.should_rotate(now - Duration::from_secs(60), 0, 1));
+    }
+}
@@ -5,20 +5,20 @@ use crate::{
     serde::to_string,
     sinks::{
         util::{
+            buffer::parquet::{encode_object_event, ObjectBuffer, ObjectEncoding, ParquetConfig},
             encoding::{EncodingConfig, EncodingConfiguration},
             retries::{RetryAction, RetryLogic},
-            BatchConfig, BatchSettings, Buffer, Compression, InFlightLimit, PartitionBatchSink,
+            BatchConfig, BatchSettings, Compression, InFlightLimit, PartitionBatchSink,
             PartitionBuffer, PartitionInnerBuffer, ServiceBuilderExt, TowerRequestConfig,
         },
         Healthcheck, VectorSink,
     },
     template::{Template, TemplateError},
     tls::{TlsOptions, TlsSettings},
-    Event,
 };
 use bytes::Bytes;
 use chrono::Utc;
-use futures::{stream, FutureExt, SinkExt, StreamExt};
+use futures::{future, stream, FutureExt, SinkExt, StreamExt};
 use http::{StatusCode, Uri};
 use hyper::{
     header::{HeaderName, HeaderValue},
@@ -65,6 +65,11 @@ pub struct GcsSinkConfig {
     compression: Compression,
     #[serde(default)]
     batch: BatchConfig,
+    #[serde(
+        skip_serializing_if = "crate::serde::skip_serializing_if_default",
+        default
+    )]
+    parquet: ParquetConfig,
     #[serde(default)]
     request: TowerRequestConfig,
     #[serde(flatten)]
@@ -86,6 +91,7 @@ fn default_config(e: Encoding) -> GcsSinkConfig {
         encoding: e.into(),
         compression: Compression::gzip_default(),
         batch: Default::default(),
+        parquet: Default::default(),
         request: Default::default(),
         auth: Default::default(),
         tls: Default::default(),
@@ -129,6 +135,17 @@ lazy_static! {
 enum Encoding {
     Text,
     Ndjson,
+    Parquet,
+}
+
+impl From<Encoding> for ObjectEncoding {
+    fn from(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Text => Self::Text,
+            Encoding::Ndjson => Self::Ndjson,
+            Encoding::Parquet => Self::Parquet,
+        }
+    }
 }
 
 impl Encoding {
@@ -136,6 +153,7 @@ impl Encoding {
         match self {
             Self::Text => "text/plain",
             Self::Ndjson => "application/x-ndjson",
+            Self::Parquet => "application/octet-stream",
         }
     }
 }
@@ -209,11 +227,6 @@ impl GcsSink {
         let request = config.request.unwrap_with(&REQUEST_DEFAULTS);
         let encoding = config.encoding.clone();
 
-        let batch = BatchSettings::default()
-            .bytes(bytesize::mib(10u64))
-            .timeout(300)
-            .parse_config(config.batch)?;
-
         let key_prefix = config.key_prefix.as_deref().unwrap_or("date=%F/");
         let key_prefix = Template::try_from(key_prefix).context(KeyPrefixTemplate)?;
 
@@ -224,11 +237,22 @@ impl GcsSink {
             .settings(request, GcsRetryLogic)
             .service(self);
 
-        let buffer = PartitionBuffer::new(Buffer::new(batch.size, config.compression));
+        let batch = BatchSettings::default()
+            .bytes(bytesize::mib(10u64))
+            .timeout(300)
+            .parse_config(config.batch)?;
+        let parquet = match encoding.codec() {
+            Encoding::Parquet => Some(config.parquet.clone()),
+            _ => None,
+        };
+        let buffer =
+            PartitionBuffer::new(ObjectBuffer::new(batch.size, config.compression, parquet));
 
         let sink = PartitionBatchSink::new(svc, buffer, batch.timeout, cx.acker())
             .sink_map_err(|error| error!(message = "Fatal gcp_cloud_storage error.", %error))
-            .with_flat_map(move |e| stream::iter(encode_event(e, &key_prefix, &encoding)).map(Ok));
+            .with_flat_map(move |e| {
+                stream::iter(encode_object_event(e, &key_prefix, &encoding)).map(Ok)
+            });
 
         Ok(VectorSink::Sink(Box::new(sink)))
     }
@@ -259,6 +283,11 @@ impl Service<RequestWrapper> for GcsSink {
     }
 
     fn call(&mut self, request: RequestWrapper) -> Self::Future {
+        // Batches that couldn't be written to a file leave nothing to upload.
+        if request.body.is_empty() {
+            return Box::pin(future::ok(Response::new(Body::empty())));
+        }
+
         let settings = request.settings;
 
         let uri = format!("{}{}", self.base_url, request.key)
@@ -350,8 +379,14 @@ impl RequestSettings {
             .acl
             .map(|acl| HeaderValue::from_str(&to_string(acl)).unwrap());
         let content_type = HeaderValue::from_str(config.encoding.codec().content_type()).unwrap();
-        let content_encoding = config
-            .compression
+        // Parquet files compress their columns, rather than being compressed as a whole.
+        let parquet = *config.encoding.codec() == Encoding::Parquet;
+        let compression = if parquet {
+            Compression::None
+        } else {
+            config.compression
+        };
+        let content_encoding = compression
             .content_encoding()
             .map(|ce| HeaderValue::from_str(&to_string(ce)).unwrap());
         let storage_class = config.storage_class.unwrap_or_default();
@@ -366,10 +401,13 @@ impl RequestSettings {
                     .collect::<Result<Vec<_>, _>>()
             })
             .unwrap_or_else(|| Ok(vec![]))?;
-        let extension = config
-            .filename_extension
-            .clone()
-            .unwrap_or_else(|| config.compression.extension().into());
+        let extension = config.filename_extension.clone().unwrap_or_else(|| {
+            if parquet {
+                "parquet".into()
+            } else {
+                compression.extension().into()
+            }
+        });
         let time_format = config
             .filename_time_format
             .clone()
@@ -396,92 +434,36 @@ fn make_header((name, value): (&String, &String)) -> crate::Result<(HeaderName,
     ))
 }
 
-fn encode_event(
-    mut event: Event,
-    key_prefix: &Template,
-    encoding: &EncodingConfig<Encoding>,
-) -> Option<PartitionInnerBuffer<Vec<u8>, Bytes>> {
-    let key = key_prefix
-        .render_string(&event)
-        .map_err(|missing_keys| {
-            warn!(
-                message = "Keys do not exist on the event; dropping event.",
-                ?missing_keys,
-                rate_limit_secs = 30,
-            );
-        })
-        .ok()?;
-    encoding.apply_rules(&mut event);
-    let log = event.into_log();
-    let bytes = match encoding.codec() {
-        Encoding::Ndjson => serde_json::to_vec(&log)
-            .map(|mut b| {
-                b.push(b'\n');
-                b
-            })
-            .expect("Failed to encode event as json, this is a bug!"),
-        Encoding::Text => {
-            let mut bytes = log
-                .get(crate::config::log_schema().message_key())
-                .map(|v| v.as_bytes().to_vec())
-                .unwrap_or_default();
-            bytes.push(b'\n');
-            bytes
-        }
-    };
-
-    Some(PartitionInnerBuffer::new(bytes, key.into()))
-}
-
-#[derive(Clone)]
-struct GcsRetryLogic;
-
-// This is a clone of HttpRetryLogic for the Body type, should get merged
-impl RetryLogic for GcsRetryLogic {
-    type Error = hyper::Error;
-    type Response = Response<Body>;
-
-    fn is_retriable_error(&self, _error: &Self::Error) -> bool {
-        true
-    }
-
-    fn should_retry_response(&self, response: &Self::Response) -> RetryAction {
-        let status = response.status();
-
-        match status {
-            StatusCode::TOO_MANY_REQUESTS => RetryAction::Retry("too many requests".into()),
-            StatusCode::NOT_IMPLEMENTED => {
-                RetryAction::DontRetry("endpoint not implemented".into())
-            }
-            _ if status.is_server_error() => RetryAction::Retry(format!("{}", status)),
-            _ if status.is_success() => RetryAction::Successful,
-            _ => RetryAction::DontRetry(format!("response status: {}", status)),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{sinks::util::buffer::parquet::ObjectInput, Event};
 
     #[test]
     fn generate_config() {
         crate::test_util::test_generate_config::<GcsSinkConfig>();
     }
 
+    fn encoded(input: PartitionInnerBuffer<ObjectInput, Bytes>) -> Vec<u8> {
+        match input.into_parts().0 {
+            ObjectInput::Encoded(bytes) => bytes,
+            ObjectInput::Log(_) => panic!("Expected an encoded event"),
+        }
+    }
+
     #[test]
     fn gcs_encode_event_text() {
         let message = "hello world".to_string();
         let batch_time_format = Template::try_from("date=%F").unwrap();
-        let bytes = encode_event(
+        let bytes = encode_object_event(
             message.clone().into(),
             &batch_time_format,
-            &Encoding::Text.into(),
+            &EncodingConfig::from(Encoding::Text),
         )
         .unwrap();
 
         let encoded_message = message + "\n";
-        let (bytes, _) = bytes.into_parts();
+        let bytes = encoded(bytes);
         assert_eq!(&bytes[..], encoded_message.as_bytes());
     }
 
@@ -492,9 +474,14 @@ mod tests {
         event.as_mut_log().insert("key", "value");
 
         let batch_time_format = Template::try_from("date=%F").unwrap();
-        let bytes = encode_event(event, &batch_time_format, &Encoding::Ndjson.into()).unwrap();
+        let bytes = encode_object_event(
+            event,
+            &batch_time_format,
+            &EncodingConfig::from(Encoding::Ndjson),
+        )
+        .unwrap();
 
-        let (bytes, _) = bytes.into_parts();
+        let bytes = encoded(bytes);
         let map: HashMap<String, String> = serde_json::from_slice(&bytes[..]).unwrap();
 
         assert_eq!(
@@ -513,7 +500,8 @@ mod tests {
         event.as_mut_log().insert("key", "value");
 
         let key_format = Template::try_from("key: {{ key }}").unwrap();
-        let bytes = encode_event(event, &key_format, &Encoding::Text.into()).unwrap();
+        let bytes =
+            encode_object_event(event, &key_format, &EncodingConfig::from(Encoding::Text)).unwrap();
 
         let (_, key) = bytes.into_parts();
         assert_eq!(key, "key: value");
@@ -523,6 +511,15 @@ mod tests {
         extension: Option<&str>,
         uuid: bool,
         compression: Compression,
+    ) -> RequestSettings {
+        encoding_request_settings(Encoding::Ndjson, extension, uuid, compression)
+    }
+
+    fn encoding_request_settings(
+        encoding: Encoding,
+        extension: Option<&str>,
+        uuid: bool,
+        compression: Compression,
     ) -> RequestSettings {
         RequestSettings::new(&GcsSinkConfig {
             key_prefix: Some("key/".into()),
@@ -530,7 +527,7 @@ mod tests {
             filename_extension: extension.map(Into::into),
             filename_append_uuid: Some(uuid),
             compression,
-            ..default_config(Encoding::Ndjson)
+            ..default_config(encoding)
         })
         .expect("Could not create request settings")
     }
@@ -563,4 +560,17 @@ mod tests {
         );
         assert_ne!(req.key, "key/date.log.gz".to_string());
     }
+
+    #[test]
+    fn gcs_build_parquet_request() {
+        let buf = PartitionInnerBuffer::new(vec![0u8; 10], Bytes::from("key/"));
+
+        let settings =
+            encoding_request_settings(Encoding::Parquet, None, false, Compression::gzip_default());
+        assert_eq!(settings.content_type, "application/octet-stream");
+        assert!(settings.content_encoding.is_none());
+
+        let req = RequestWrapper::new(buf, settings);
+        assert_eq!(req.key, "key/date.parquet".to_string());
+    }
 }
@@ -0,0 +1,224 @@
+//! Conversion of the events to the Avro values of a schema.
+
+use crate::event::Value;
+use avro_rs::{types::Value as AvroValue, Schema};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::{collections::HashMap, convert::TryFrom};
+
+/// Encodes the fields of the event as an Avro datum of the schema.
+pub fn encode(schema: &Schema, value: &Value) -> Result<Vec<u8>, String> {
+    let value = convert(schema, Some(value))?;
+    avro_rs::to_avro_datum(schema, value).map_err(|error| error.to_string())
+}
+
+fn convert(schema: &Schema, value: Option<&Value>) -> Result<AvroValue, String> {
+    let value = match (schema, value) {
+        (Schema::Union(union), _) => return convert_union(union.variants(), value),
+        (Schema::Null, None) | (Schema::Null, Some(Value::Null)) => return Ok(AvroValue::Null),
+        (_, None) | (_, Some(Value::Null)) => return Err("missing value".into()),
+        (_, Some(value)) => value,
+    };
+
+    match (schema, value) {
+        (Schema::Boolean, Value::Boolean(b)) => Ok(AvroValue::Boolean(*b)),
+        (Schema::Boolean, Value::Bytes(_)) => match value.to_string_lossy().as_str() {
+            "true" => Ok(AvroValue::Boolean(true)),
+            "false" => Ok(AvroValue::Boolean(false)),
+            text => Err(format!("{:?} is not a boolean", text)),
+        },
+
+        (Schema::Int, _) => to_i64(value).and_then(|int| {
+            i32::try_from(int)
+                .map(AvroValue::Int)
+                .map_err(|_| format!("{} is out of range for an int", int))
+        }),
+        (Schema::Long, _) => to_i64(value).map(AvroValue::Long),
+        (Schema::Float, _) => to_f64(value).map(|float| AvroValue::Float(float as f32)),
+        (Schema::Double, _) => to_f64(value).map(AvroValue::Double),
+        (Schema::Bytes, Value::Bytes(bytes)) => Ok(AvroValue::Bytes(bytes.to_vec())),
+        (Schema::String, _) => Ok(AvroValue::String(value.to_string_lossy())),
+
+        (Schema::Date, _) => {
+            let epoch = NaiveDate::from_ymd(1970, 1, 1);
+            to_timestamp(value).map(|timestamp| {
+                AvroValue::Date((timestamp.naive_utc().date() - epoch).num_days() as i32)
+            })
+        }
+        (Schema::TimestampMillis, _) => to_timestamp(value)
+            .map(|timestamp| AvroValue::TimestampMillis(timestamp.timestamp_millis())),
+        (Schema::TimestampMicros, _) => to_timestamp(value).map(|timestamp| {
+            AvroValue::TimestampMicros(
+                timestamp.timestamp() * 1_000_000 + timestamp.timestamp_subsec_micros() as i64,
+            )
+        }),
+
+        (Schema::Enum { symbols, .. }, Value::Bytes(_)) => {
+            let symbol = value.to_string_lossy();
+            symbols
+                .iter()
+                .position(|candidate| *candidate == symbol)
+                .map(|index| AvroValue::Enum(index as i32, symbol.clone()))
+                .ok_or_else(|| format!("{:?} is not a symbol of the enum", symbol))
+        }
+        (Schema::Fixed { size, .. }, Value::Bytes(bytes)) if bytes.len() == *size => {
+            Ok(AvroValue::Fixed(*size, bytes.to_vec()))
+        }
+
+        (Schema::Array(inner), Value::Array(items)) => items
+            .iter()
+            .map(|item| convert(inner, Some(item)))
+            .collect::<Result<_, _>>()
+            .map(AvroValue::Array),
+        (Schema::Map(inner), Value::Map(map)) => map
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), convert(inner, Some(value))?)))
+            .collect::<Result<HashMap<_, _>, String>>()
+            .map(AvroValue::Map),
+        (Schema::Record { fields, .. }, Value::Map(map)) => fields
+            .iter()
+            .map(|field| {
+                let default = field.default.clone().map(Value::from);
+                let value = map.get(&field.name).or_else(|| default.as_ref());
+                convert(&field.schema, value)
+                    .map(|value| (field.name.clone(), value))
+                    .map_err(|message| format!("{}: {}", field.name, message))
+            })
+            .collect::<Result<_, _>>()
+            .map(AvroValue::Record),
+
+        _ => Err(format!("can't convert {:?} to {:?}", value, schema)),
+    }
+}
+
+/// Uses the first variant of the union the value can be converted to.
+fn convert_union(variants: &[Schema], value: Option<&Value>) -> Result<AvroValue, String> {
+    variants
+        .iter()
+        .find_map(|variant| convert(variant, value).ok())
+        .map(|value| AvroValue::Union(Box::new(value)))
+        .ok_or_else(|| format!("{:?} doesn't match any variant of the union", value))
+}
+
+fn to_i64(value: &Value) -> Result<i64, String> {
+    match value {
+        Value::Integer(i) => Ok(*i),
+        Value::Float(f) if f.fract() == 0.0 => Ok(*f as i64),
+        Value::Bytes(_) => {
+            let text = value.to_string_lossy();
+            text.parse()
+                .map_err(|_| format!("{:?} is not an integer", text))
+        }
+        _ => Err(format!("{:?} is not an integer", value)),
+    }
+}
+
+fn to_f64(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Integer(i) => Ok(*i as f64),
+        Value::Float(f) => Ok(*f),
+        Value::Bytes(_) => {
+            let text = value.to_string_lossy();
+            text.parse()
+                .map_err(|_| format!("{:?} is not a number", text))
+        }
+        _ => Err(format!("{:?} is not a number", value)),
+    }
+}
+
+fn to_timestamp(value: &Value) -> Result<DateTime<Utc>, String> {
+    match value {
+        Value::Timestamp(timestamp) => Ok(*timestamp),
+        Value::Bytes(_) => {
+            let text = value.to_string_lossy();
+            DateTime::parse_from_rfc3339(&text)
+                .map(|timestamp| timestamp.with_timezone(&Utc))
+                .map_err(|_| format!("{:?} is not a timestamp", text))
+        }
+        _ => Err(format!("{:?} is not a timestamp", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "Request",
+        "fields": [
+            {"name": "message", "type": "string"},
+            {"name": "status", "type": "int"},
+            {"name": "timestamp", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "tags", "type": {"type": "array", "items": "string"}},
+            {"name": "user", "type": ["null", "string"], "default": null},
+            {"name": "level", "type": {"type": "enum", "name": "Level", "symbols": ["info", "error"]}, "default": "info"}
+        ]
+    }"#;
+
+    fn event() -> Value {
+        let mut map = BTreeMap::new();
+        map.insert("message".to_string(), Value::from("GET /"));
+        map.insert("status".to_string(), Value::from("200"));
+        map.insert(
+            "timestamp".to_string(),
+            Value::from(Utc.timestamp_millis(1_602_759_706_499)),
+        );
+        map.insert("tags".to_string(), Value::from(vec!["a", "b"]));
+        map.insert("host".to_string(), Value::from("example.com"));
+        Value::Map(map)
+    }
+
+    #[test]
+    fn converts_records() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+
+        assert_eq!(
+            convert(&schema, Some(&event())).unwrap(),
+            AvroValue::Record(vec![
+                ("message".into(), AvroValue::String("GET /".into())),
+                ("status".into(), AvroValue::Int(200)),
+                (
+                    "timestamp".into(),
+                    AvroValue::TimestampMillis(1_602_759_706_499)
+                ),
+                (
+                    "tags".into(),
+                    AvroValue::Array(vec![
+                        AvroValue::String("a".into()),
+                        AvroValue::String("b".into())
+                    ])
+                ),
+                ("user".into(), AvroValue::Union(Box::new(AvroValue::Null))),
+                ("level".into(), AvroValue::Enum(0, "info".into())),
+            ])
+        );
+    }
+
+    #[test]
+    fn encodes_records() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let encoded = encode(&schema, &event()).unwrap();
+
+        let decoded = avro_rs::from_avro_datum(&schema, &mut &encoded[..], None).unwrap();
+        assert_eq!(decoded, convert(&schema, Some(&event())).unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+
+        let mut missing = event();
+        if let Value::Map(map) = &mut missing {
+            map.remove("message");
+        }
+        assert!(encode(&schema, &missing).is_err());
+
+        let mut invalid = event();
+        if let Value::Map(map) = &mut invalid {
+            map.insert("status".into(), Value::from("OK"));
+        }
+        assert!(encode(&schema, &invalid).is_err());
+    }
+}
@@ -0,0 +1,1244 @@
+mod avro;
+mod protobuf;
+mod schema_registry;
+mod transaction;
+
+pub use self::{
+    protobuf::ProtobufConfig,
+    schema_registry::{SchemaRegistryConfig, SchemaRegistryError},
+};
+
+use self::{
+    protobuf::ProtobufEncoder, schema_registry::SchemaRegistry, transaction::TransactionError,
+};
+use crate::{
+    buffers::Acker,
+    config::{log_schema, DataType, GenerateConfig, SinkConfig, SinkContext, SinkDescription},
+    event::{Event, Value},
+    internal_events::{KafkaEncodeEventFailed, KafkaTransactionCommitted},
+    kafka::{KafkaAuthConfig, KafkaCompression},
+    serde::to_string,
+    sinks::util::encoding::{EncodingConfig, EncodingConfigWithDefault, EncodingConfiguration},
+    template::{Template, TemplateError},
+};
+use futures::{
+    channel::oneshot::Canceled,
+    future::{self, BoxFuture},
+    ready,
+    stream::{FuturesOrdered, FuturesUnordered},
+    FutureExt, Sink, Stream,
+};
+use rdkafka::{
+    consumer::{BaseConsumer, Consumer},
+    error::{KafkaError, RDKafkaError},
+    message::OwnedHeaders,
+    producer::{DeliveryFuture, FutureProducer, FutureRecord},
+    ClientConfig,
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::time::{delay_for, Delay, Duration};
+use tokio_retry::strategy::ExponentialBackoff;
+
+// Maximum number of events being encoded before [send_result](https://docs.rs/rdkafka/0.24.0/rdkafka/producer/future_producer/struct.FutureProducer.html#method.send_result)
+const SEND_RESULT_LIMIT: usize = 5;
+
+const TRANSACTION_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("creating kafka producer failed: {}", source))]
+    KafkaCreateFailed { source: KafkaError },
+    #[snafu(display("invalid topic template: {}", source))]
+    TopicTemplate { source: TemplateError },
+    #[snafu(display("invalid subject template: {}", source))]
+    SubjectTemplate { source: TemplateError },
+    #[snafu(display("the {:?} codec requires the `schema_registry` option", codec))]
+    MissingSchemaRegistry { codec: Encoding },
+    #[snafu(display("the protobuf codec requires the `protobuf` option"))]
+    MissingProtobuf,
+    #[snafu(display("the transactional producer requires the `transaction` option"))]
+    MissingTransaction,
+    #[snafu(display("initializing transactions failed: {}", source))]
+    TransactionInitFailed { source: TransactionError },
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct KafkaSinkConfig {
+    bootstrap_servers: String,
+    topic: String,
+    key_field: Option<String>,
+    encoding: EncodingConfigWithDefault<Encoding>,
+    #[serde(default)]
+    compression: KafkaCompression,
+    #[serde(flatten)]
+    auth: KafkaAuthConfig,
+    #[serde(default = "default_socket_timeout_ms")]
+    socket_timeout_ms: u64,
+    #[serde(default = "default_message_timeout_ms")]
+    message_timeout_ms: u64,
+    librdkafka_options: Option<HashMap<String, String>>,
+    /// The record headers, by name, with the field of the event they are
+    /// taken from.
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    producer_mode: ProducerMode,
+    transaction: Option<TransactionConfig>,
+    schema_registry: Option<SchemaRegistryConfig>,
+    protobuf: Option<ProtobufConfig>,
+}
+
+fn default_socket_timeout_ms() -> u64 {
+    60000 // default in librdkafka
+}
+
+fn default_message_timeout_ms() -> u64 {
+    300000 // default in librdkafka
+}
+
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize, Eq, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[derivative(Default)]
+    Text,
+    Json,
+    Avro,
+    Protobuf,
+}
+
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize, Eq, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProducerMode {
+    #[derivative(Default)]
+    Default,
+    /// Retries can't duplicate or reorder the messages.
+    Idempotent,
+    /// The events are produced in transactions, which the consumers reading
+    /// committed messages see atomically.
+    Transactional,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionConfig {
+    id: String,
+    #[serde(default = "default_transaction_max_events")]
+    max_events: usize,
+    #[serde(default = "default_transaction_timeout_ms")]
+    timeout_ms: u64,
+}
+
+fn default_transaction_max_events() -> usize {
+    1000
+}
+
+fn default_transaction_timeout_ms() -> u64 {
+    60000 // default in librdkafka
+}
+
+pub struct KafkaSink {
+    producer: Arc<FutureProducer>,
+    topic: Template,
+    key_field: Option<String>,
+    headers: BTreeMap<String, String>,
+    encoding: EncodingConfig<Encoding>,
+    schema_registry: Option<Arc<SchemaRegistry>>,
+    subject: Option<Template>,
+    transaction: Option<TransactionState>,
+    /// The records being encoded, which are sent in the order of their
+    /// events. `None` for the events that were dropped.
+    encode_fut: FuturesOrdered<BoxFuture<'static, (usize, Option<Record>)>>,
+    /// The next record to send, held back while the queue of the producer
+    /// is full.
+    queue_full: Option<(usize, Record, Delay)>,
+    in_flight: FuturesUnordered<
+        BoxFuture<
+            'static,
+            (
+                usize,
+                Result<Result<Option<(i32, i64)>, KafkaError>, Canceled>,
+            ),
+        >,
+    >,
+
+    acker: Acker,
+    seq_head: usize,
+    seq_tail: usize,
+    pending_acks: HashSet<usize>,
+}
+
+/// An encoded event, ready to be produced.
+#[derive(Clone, Debug)]
+struct Record {
+    topic: String,
+    key: Vec<u8>,
+    payload: Vec<u8>,
+    timestamp: Option<i64>,
+    headers: Vec<(String, Vec<u8>)>,
+}
+
+impl Record {
+    fn send(&self, producer: &FutureProducer) -> Result<DeliveryFuture, KafkaError> {
+        let mut record = FutureRecord::to(&self.topic)
+            .key(&self.key)
+            .payload(&self.payload);
+        if let Some(timestamp) = self.timestamp {
+            record = record.timestamp(timestamp);
+        }
+        if !self.headers.is_empty() {
+            let headers = self
+                .headers
+                .iter()
+                .fold(OwnedHeaders::new(), |owned, (name, value)| {
+                    owned.add(name.as_str(), &value[..])
+                });
+            record = record.headers(headers);
+        }
+
+        debug!(message = "Sending event.", count = 1);
+        producer
+            .send_result(record)
+            .map_err(|(error, _record)| error)
+    }
+}
+
+/// The transaction the events are currently produced in. The events are
+/// acknowledged once it is committed.
+struct TransactionState {
+    max_events: usize,
+    timeout: Duration,
+    /// The number of events sent since the transaction began.
+    events: usize,
+    /// The records sent in the transaction, to send again if it has to be
+    /// aborted.
+    records: Vec<Record>,
+    failed: bool,
+    finishing: Option<BoxFuture<'static, Result<(), ()>>>,
+}
+
+inventory::submit! {
+    SinkDescription::new::<KafkaSinkConfig>("kafka")
+}
+
+impl GenerateConfig for KafkaSinkConfig {
+    fn generate_config() -> toml::Value {
+        toml::from_str(
+            r#"bootstrap_servers = "10.14.22.123:9092,10.14.23.332:9092"
+            key_field = "user_id"
+            topic = "topic-1234"
+            encoding.codec = "json""#,
+        )
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "kafka")]
+impl SinkConfig for KafkaSinkConfig {
+    async fn build(
+        &self,
+        cx: SinkContext,
+    ) -> crate::Result<(super::VectorSink, super::Healthcheck)> {
+        let sink = KafkaSink::new(self.clone(), cx.acker())?;
+        sink.init_transactions().await?;
+        let hc = healthcheck(self.clone()).boxed();
+        Ok((super::VectorSink::Sink(Box::new(sink)), hc))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "kafka"
+    }
+}
+
+impl KafkaSinkConfig {
+    fn to_rdkafka(&self) -> crate::Result<ClientConfig> {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &self.bootstrap_servers)
+            .set("compression.codec", &to_string(self.compression))
+            .set("socket.timeout.ms", &self.socket_timeout_ms.to_string())
+            .set("message.timeout.ms", &self.message_timeout_ms.to_string());
+
+        self.auth.apply(&mut client_config)?;
+
+        if let Some(ref librdkafka_options) = self.librdkafka_options {
+            for (key, value) in librdkafka_options.iter() {
+                client_config.set(key.as_str(), value.as_str());
+            }
+        }
+
+        Ok(client_config)
+    }
+
+    fn to_rdkafka_producer(&self) -> crate::Result<ClientConfig> {
+        let mut client_config = self.to_rdkafka()?;
+
+        match self.producer_mode {
+            ProducerMode::Default => {}
+            ProducerMode::Idempotent => {
+                client_config.set("enable.idempotence", "true");
+            }
+            ProducerMode::Transactional => {
+                let transaction = self
+                    .transaction
+                    .as_ref()
+                    .ok_or(BuildError::MissingTransaction)?;
+                client_config
+                    .set("enable.idempotence", "true")
+                    .set("transactional.id", &transaction.id)
+                    .set(
+                        "transaction.timeout.ms",
+                        &transaction.timeout_ms.to_string(),
+                    );
+            }
+        }
+
+        Ok(client_config)
+    }
+}
+
+impl KafkaSink {
+    fn new(config: KafkaSinkConfig, acker: Acker) -> crate::Result<Self> {
+        let producer = config
+            .to_rdkafka_producer()?
+            .create()
+            .context(KafkaCreateFailed)?;
+
+        let codec = *config.encoding.codec();
+        let schema_registry = match codec {
+            Encoding::Text | Encoding::Json => None,
+            Encoding::Avro | Encoding::Protobuf => {
+                let registry = config
+                    .schema_registry
+                    .as_ref()
+                    .ok_or(BuildError::MissingSchemaRegistry { codec })?;
+                let protobuf = match codec {
+                    Encoding::Protobuf => Some(ProtobufEncoder::load(
+                        config
+                            .protobuf
+                            .as_ref()
+                            .ok_or(BuildError::MissingProtobuf)?,
+                    )?),
+                    _ => None,
+                };
+                Some(Arc::new(SchemaRegistry::new(registry, codec, protobuf)?))
+            }
+        };
+        let subject = match config
+            .schema_registry
+            .as_ref()
+            .and_then(|registry| registry.subject.as_ref())
+        {
+            Some(subject) => Some(Template::try_from(subject.as_str()).context(SubjectTemplate)?),
+            None => None,
+        };
+
+        let transaction = match config.producer_mode {
+            ProducerMode::Transactional => {
+                config
+                    .transaction
+                    .as_ref()
+                    .map(|transaction| TransactionState {
+                        max_events: transaction.max_events,
+                        timeout: Duration::from_millis(transaction.timeout_ms),
+                        events: 0,
+                        records: Vec::new(),
+                        failed: false,
+                        finishing: None,
+                    })
+            }
+            _ => None,
+        };
+
+        Ok(KafkaSink {
+            producer: Arc::new(producer),
+            topic: Template::try_from(config.topic).context(TopicTemplate)?,
+            key_field: config.key_field,
+            headers: config.headers,
+            encoding: config.encoding.into(),
+            schema_registry,
+            subject,
+            transaction,
+            encode_fut: FuturesOrdered::new(),
+            queue_full: None,
+            in_flight: FuturesUnordered::new(),
+            acker,
+            seq_head: 0,
+            seq_tail: 0,
+            pending_acks: HashSet::new(),
+        })
+    }
+
+    async fn init_transactions(&self) -> crate::Result<()> {
+        if let Some(state) = &self.transaction {
+            let producer = Arc::clone(&self.producer);
+            let timeout = state.timeout;
+            tokio::task::spawn_blocking(move || transaction::init(&producer, timeout))
+                .await?
+                .context(TransactionInitFailed)?;
+        }
+        Ok(())
+    }
+
+    /// Sends the encoded records in the order of their events. Returns
+    /// `Pending` while some are still being encoded, or can't be queued.
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        loop {
+            let (seqno, record) = match self.queue_full.take() {
+                Some((seqno, record, mut delay)) => {
+                    if delay.poll_unpin(cx).is_pending() {
+                        self.queue_full = Some((seqno, record, delay));
+                        return Poll::Pending;
+                    }
+                    (seqno, record)
+                }
+                None => match ready!(Pin::new(&mut self.encode_fut).poll_next(cx)) {
+                    Some((seqno, Some(record))) => (seqno, record),
+                    Some((seqno, None)) => {
+                        self.in_flight
+                            .push(Box::pin(future::ready((seqno, Ok(Ok(None))))));
+                        continue;
+                    }
+                    None => return Poll::Ready(()),
+                },
+            };
+
+            match record.send(&self.producer) {
+                Ok(delivery) => self.in_flight.push(Box::pin(delivery.map(move |result| {
+                    let result = result
+                        .map(|result| result.map(Some).map_err(|(error, _owned_message)| error));
+                    (seqno, result)
+                }))),
+                // Try again if queue is full.
+                // See item 4 on GitHub: https://github.com/timberio/vector/pull/101#issue-257150924
+                // https://docs.rs/rdkafka/0.24.0/src/rdkafka/producer/future_producer.rs.html#296
+                Err(error) if error == KafkaError::MessageProduction(RDKafkaError::QueueFull) => {
+                    debug!(message = "The rdkafka queue full.", %error, %seqno, rate_limit_secs = 1);
+                    let delay = delay_for(Duration::from_millis(10));
+                    self.queue_full = Some((seqno, record, delay));
+                    continue;
+                }
+                Err(error) => self
+                    .in_flight
+                    .push(Box::pin(future::ready((seqno, Ok(Err(error)))))),
+            }
+            if let Some(state) = &mut self.transaction {
+                state.records.push(record);
+            }
+        }
+    }
+
+    fn ack_pending(&mut self) {
+        let mut num_to_ack = 0;
+        while self.pending_acks.remove(&self.seq_tail) {
+            num_to_ack += 1;
+            self.seq_tail += 1
+        }
+        self.acker.ack(num_to_ack);
+    }
+}
+
+impl TransactionState {
+    /// Commits the transaction, see `finish_transaction`.
+    fn poll_finish(
+        &mut self,
+        producer: &Arc<FutureProducer>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ()>> {
+        if self.events == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let producer = Arc::clone(producer);
+        let timeout = self.timeout;
+        let failed = self.failed;
+        let records = &mut self.records;
+        let finishing = self.finishing.get_or_insert_with(|| {
+            let records = std::mem::take(records);
+            Box::pin(finish_transaction(producer, timeout, records, failed))
+        });
+
+        let result = ready!(finishing.poll_unpin(cx));
+        if result.is_ok() {
+            emit!(KafkaTransactionCommitted {
+                events: self.events
+            });
+        }
+        self.events = 0;
+        self.failed = false;
+        self.finishing = None;
+
+        Poll::Ready(result)
+    }
+}
+
+/// Commits the transaction. If one of its messages could not be delivered,
+/// or the commit requires it, the transaction is aborted and its records
+/// are sent again in a new one. Only the fatal errors of the producer fail
+/// it, and the events of the transaction are then not acknowledged.
+async fn finish_transaction(
+    producer: Arc<FutureProducer>,
+    timeout: Duration,
+    records: Vec<Record>,
+    mut failed: bool,
+) -> Result<(), ()> {
+    loop {
+        if failed {
+            warn!(message = "Aborting transaction after failing to deliver messages.");
+        } else {
+            match run_blocking(&producer, transaction::commit, timeout).await? {
+                Ok(()) => return Ok(()),
+                Err(error) if error.is_fatal() => {
+                    error!(message = "Failed to commit transaction.", %error);
+                    return Err(());
+                }
+                Err(error) if error.requires_abort() => {
+                    warn!(message = "Failed to commit transaction; aborting.", %error);
+                }
+                Err(error) => {
+                    warn!(message = "Failed to commit transaction; retrying.", %error);
+                    delay_for(TRANSACTION_RETRY_DELAY).await;
+                    continue;
+                }
+            }
+        }
+
+        loop {
+            match run_blocking(&producer, transaction::abort, timeout).await? {
+                Ok(()) => break,
+                Err(error) if error.is_fatal() => {
+                    error!(message = "Failed to abort transaction.", %error);
+                    return Err(());
+                }
+                Err(error) => {
+                    warn!(message = "Failed to abort transaction; retrying.", %error);
+                    delay_for(TRANSACTION_RETRY_DELAY).await;
+                }
+            }
+        }
+
+        delay_for(TRANSACTION_RETRY_DELAY).await;
+        transaction::begin(&producer).map_err(|error| {
+            error!(message = "Failed to begin transaction.", %error);
+        })?;
+        debug!(message = "Sending the events of the aborted transaction again.", count = %records.len());
+        failed = !resend(&producer, &records).await;
+    }
+}
+
+/// Runs a blocking call of the transactional API, which fails fatally if
+/// its task panicked.
+async fn run_blocking(
+    producer: &Arc<FutureProducer>,
+    call: fn(&FutureProducer, Duration) -> Result<(), TransactionError>,
+    timeout: Duration,
+) -> Result<Result<(), TransactionError>, ()> {
+    let producer = Arc::clone(producer);
+    tokio::task::spawn_blocking(move || call(&producer, timeout))
+        .await
+        .map_err(|error| error!(message = "Transaction task failed.", %error))
+}
+
+/// Sends the records in order and waits for their delivery, returning
+/// whether they were all delivered.
+async fn resend(producer: &FutureProducer, records: &[Record]) -> bool {
+    let mut deliveries = Vec::with_capacity(records.len());
+    for record in records {
+        loop {
+            match record.send(producer) {
+                Ok(delivery) => {
+                    deliveries.push(delivery);
+                    break;
+                }
+                Err(error) if error == KafkaError::MessageProduction(RDKafkaError::QueueFull) => {
+                    delay_for(Duration::from_millis(10)).await
+                }
+                Err(error) => {
+                    error!(message = "Kafka error.", %error);
+                    return false;
+                }
+            }
+        }
+    }
+
+    let mut delivered = true;
+    for delivery in deliveries {
+        match delivery.await {
+            Ok(Ok(_)) => {}
+            Ok(Err((error, _owned_message))) => {
+                error!(message = "Kafka error.", %error);
+                delivered = false;
+            }
+            Err(Canceled) => {
+                error!(message = "Request canceled.");
+                delivered = false;
+            }
+        }
+    }
+    delivered
+}
+
+/// Encodes the event with the schema of its subject. The events following
+/// it are held back to keep their order, so fetching the schema is retried
+/// until it succeeds. The event is dropped if the schema can't encode it.
+async fn encode_with_registry(
+    registry: &SchemaRegistry,
+    subject: &str,
+    event: Event,
+) -> Option<Vec<u8>> {
+    let mut backoff = ExponentialBackoff::from_millis(2)
+        .factor(250)
+        .max_delay(Duration::from_secs(60));
+    loop {
+        match registry.encode(subject, &event).await {
+            Ok(body) => return Some(body),
+            Err(error @ SchemaRegistryError::EncodeFailed { .. }) => {
+                emit!(KafkaEncodeEventFailed {
+                    error: error.into()
+                });
+                return None;
+            }
+            Err(error) => {
+                warn!(message = "Failed to fetch schema; retrying.", %error, rate_limit_secs = 30);
+                delay_for(backoff.next().unwrap()).await;
+            }
+        }
+    }
+}
+
+impl Sink<Event> for KafkaSink {
+    type Error = ();
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let transaction_full = self
+            .transaction
+            .as_ref()
+            .map_or(false, |state| state.events >= state.max_events);
+        if transaction_full {
+            ready!(self.as_mut().poll_flush(cx))?;
+        }
+
+        match self.poll_send(cx) {
+            Poll::Pending if self.encode_fut.len() >= SEND_RESULT_LIMIT => Poll::Pending,
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Event) -> Result<(), Self::Error> {
+        assert!(
+            self.encode_fut.len() < SEND_RESULT_LIMIT,
+            "Expected `poll_ready` to be called first."
+        );
+
+        let topic = self.topic.render_string(&item).map_err(|missing_keys| {
+            error!(message = "Missing keys for topic.", missing_keys = ?missing_keys);
+        })?;
+        let subject = match &self.subject {
+            Some(subject) => subject.render_string(&item).map_err(|missing_keys| {
+                error!(message = "Missing keys for subject.", missing_keys = ?missing_keys);
+            })?,
+            None => format!("{}-value", topic),
+        };
+        let headers = encode_headers(&item, &self.headers);
+        let timestamp = match item.as_log().get(log_schema().timestamp_key()) {
+            Some(Value::Timestamp(timestamp)) => Some(timestamp.timestamp_millis()),
+            _ => None,
+        };
+        let (key, payload) = encode_event(item, &self.key_field, &self.encoding);
+
+        let producer = Arc::clone(&self.producer);
+        if let Some(state) = &mut self.transaction {
+            if state.events == 0 {
+                transaction::begin(&producer).map_err(|error| {
+                    error!(message = "Failed to begin transaction.", %error);
+                })?;
+            }
+            state.events += 1;
+        }
+
+        let seqno = self.seq_head;
+        self.seq_head += 1;
+
+        let schema_registry = self.schema_registry.clone();
+        self.encode_fut.push(Box::pin(async move {
+            let payload = match payload {
+                Payload::Encoded(body) => body,
+                Payload::Registry(event) => {
+                    let registry =
+                        schema_registry.expect("Registry codecs require a schema registry.");
+                    match encode_with_registry(&registry, &subject, event).await {
+                        Some(body) => body,
+                        None => return (seqno, None),
+                    }
+                }
+            };
+
+            let record = Record {
+                topic,
+                key,
+                payload,
+                timestamp,
+                headers,
+            };
+            (seqno, Some(record))
+        }));
+
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.poll_send(cx));
+
+        let this = Pin::into_inner(self);
+        while !this.in_flight.is_empty() {
+            match ready!(Pin::new(&mut this.in_flight).poll_next(cx)) {
+                Some((seqno, Ok(result))) => {
+                    match result {
+                        Ok(Some((partition, offset))) => {
+                            trace!(message = "Produced message.", ?partition, ?offset)
+                        }
+                        Ok(None) => {}
+                        Err(error) => {
+                            error!(message = "Kafka error.", %error);
+                            if let Some(state) = &mut this.transaction {
+                                state.failed = true;
+                            }
+                        }
+                    };
+
+                    this.pending_acks.insert(seqno);
+                    if this.transaction.is_none() {
+                        this.ack_pending();
+                    }
+                }
+                Some((_, Err(Canceled))) => {
+                    error!(message = "Request canceled.");
+                    return Poll::Ready(Err(()));
+                }
+                None => break,
+            }
+        }
+
+        if let Some(state) = &mut this.transaction {
+            ready!(state.poll_finish(&this.producer, cx))?;
+            this.ack_pending();
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
+async fn healthcheck(config: KafkaSinkConfig) -> crate::Result<()> {
+    let client = config.to_rdkafka().unwrap();
+    let topic = match Template::try_from(config.topic)
+        .context(TopicTemplate)?
+        .render_string(&Event::from(""))
+    {
+        Ok(topic) => Some(topic),
+        Err(missing_keys) => {
+            warn!(
+                message = "Could not generate topic for healthcheck.",
+                ?missing_keys
+            );
+            None
+        }
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let consumer: BaseConsumer = client.create().unwrap();
+        let topic = topic.as_ref().map(|topic| &topic[..]);
+
+        consumer
+            .fetch_metadata(topic, Duration::from_secs(3))
+            .map(|_| ())
+    })
+    .await??;
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum Payload {
+    Encoded(Vec<u8>),
+    /// Encoded with the schema of the registry, once it is fetched.
+    Registry(Event),
+}
+
+fn encode_event(
+    mut event: Event,
+    key_field: &Option<String>,
+    encoding: &EncodingConfig<Encoding>,
+) -> (Vec<u8>, Payload) {
+    let key = key_field
+        .as_ref()
+        .and_then(|f| event.as_log().get(f))
+        .map(|v| v.as_bytes().to_vec())
+        .unwrap_or_default();
+
+    encoding.apply_rules(&mut event);
+
+    let payload = match encoding.codec() {
+        Encoding::Json => Payload::Encoded(serde_json::to_vec(&event.as_log()).unwrap()),
+        Encoding::Text => Payload::Encoded(
+            event
+                .as_log()
+                .get(log_schema().message_key())
+                .map(|v| v.as_bytes().to_vec())
+                .unwrap_or_default(),
+        ),
+        Encoding::Avro | Encoding::Protobuf => Payload::Registry(event),
+    };
+
+    (key, payload)
+}
+
+/// The headers whose field is set in the event.
+fn encode_headers(event: &Event, headers: &BTreeMap<String, String>) -> Vec<(String, Vec<u8>)> {
+    headers
+        .iter()
+        .filter_map(|(name, field)| {
+            event
+                .as_log()
+                .get(field)
+                .map(|value| (name.clone(), value.as_bytes().to_vec()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Event;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<KafkaSinkConfig>();
+    }
+
+    #[test]
+    fn kafka_encode_event_text() {
+        let key = "";
+        let message = "hello world".to_string();
+        let (key_bytes, payload) = encode_event(
+            message.clone().into(),
+            &None,
+            &EncodingConfig::from(Encoding::Text),
+        );
+
+        assert_eq!(&key_bytes[..], key.as_bytes());
+        assert_eq!(payload, Payload::Encoded(message.into_bytes()));
+    }
+
+    #[test]
+    fn kafka_encode_event_json() {
+        let message = "hello world".to_string();
+        let mut event = Event::from(message.clone());
+        event.as_mut_log().insert("key", "value");
+        event.as_mut_log().insert("foo", "bar");
+
+        let (key, payload) = encode_event(
+            event,
+            &Some("key".into()),
+            &EncodingConfig::from(Encoding::Json),
+        );
+
+        let map: BTreeMap<String, String> = serde_json::from_slice(&encoded(payload)).unwrap();
+
+        assert_eq!(&key[..], b"value");
+        assert_eq!(map[&log_schema().message_key().to_string()], message);
+        assert_eq!(map["key"], "value".to_string());
+        assert_eq!(map["foo"], "bar".to_string());
+    }
+
+    #[test]
+    fn kafka_encode_event_apply_rules() {
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("key", "value");
+
+        let (key, payload) = encode_event(
+            event,
+            &Some("key".into()),
+            &EncodingConfigWithDefault {
+                codec: Encoding::Json,
+                except_fields: Some(vec!["key".into()]),
+                ..Default::default()
+            }
+            .into(),
+        );
+
+        let map: BTreeMap<String, String> = serde_json::from_slice(&encoded(payload)).unwrap();
+
+        assert_eq!(&key[..], b"value");
+        assert!(!map.contains_key("key"));
+    }
+
+    #[test]
+    fn kafka_encode_event_registry() {
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("key", "value");
+
+        let (key, payload) = encode_event(
+            event,
+            &Some("key".into()),
+            &EncodingConfigWithDefault {
+                codec: Encoding::Avro,
+                except_fields: Some(vec!["key".into()]),
+                ..Default::default()
+            }
+            .into(),
+        );
+
+        assert_eq!(&key[..], b"value");
+        match payload {
+            Payload::Registry(event) => assert!(!event.as_log().contains("key")),
+            Payload::Encoded(_) => panic!("Expected the event to be encoded with the registry."),
+        }
+    }
+
+    #[test]
+    fn kafka_encode_headers() {
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("trace.id", "abc");
+        event.as_mut_log().insert("tenant", 12);
+
+        let mut headers = BTreeMap::new();
+        headers.insert("trace-id".to_string(), "trace.id".to_string());
+        headers.insert("tenant".to_string(), "tenant".to_string());
+        headers.insert("user".to_string(), "user_id".to_string());
+
+        assert_eq!(
+            encode_headers(&event, &headers),
+            vec![
+                ("tenant".to_string(), b"12".to_vec()),
+                ("trace-id".to_string(), b"abc".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn kafka_producer_modes() {
+        let config: KafkaSinkConfig = toml::from_str(
+            r#"
+            bootstrap_servers = "localhost:9092"
+            topic = "logs"
+            encoding.codec = "json"
+            producer_mode = "transactional"
+            "#,
+        )
+        .unwrap();
+        assert!(config.to_rdkafka_producer().is_err());
+
+        let config = KafkaSinkConfig {
+            transaction: Some(TransactionConfig {
+                id: "vector".into(),
+                max_events: default_transaction_max_events(),
+                timeout_ms: default_transaction_timeout_ms(),
+            }),
+            ..config
+        };
+        let client_config = config.to_rdkafka_producer().unwrap();
+        assert_eq!(client_config.get("transactional.id"), Some("vector"));
+        assert_eq!(client_config.get("enable.idempotence"), Some("true"));
+    }
+
+    #[test]
+    fn kafka_registry_codecs_require_registry() {
+        let config: KafkaSinkConfig = toml::from_str(
+            r#"
+            bootstrap_servers = "localhost:9092"
+            topic = "logs"
+            encoding.codec = "avro"
+            "#,
+        )
+        .unwrap();
+        let (acker, _) = Acker::new_for_testing();
+        assert!(KafkaSink::new(config, acker).is_err());
+    }
+
+    fn encoded(payload: Payload) -> Vec<u8> {
+        match payload {
+            Payload::Encoded(bytes) => bytes,
+            Payload::Registry(_) => panic!("Expected the event to be encoded."),
+        }
+    }
+}
+
+#[cfg(feature = "kafka-integration-tests")]
+#[cfg(test)]
+mod integration_test {
+    use super::*;
+    use crate::{
+        buffers::Acker,
+        kafka::{KafkaAuthConfig, KafkaSaslConfig, KafkaTlsConfig},
+        test_util::{random_lines_with_stream, random_string, wait_for},
+        tls::TlsOptions,
+    };
+    use futures::{future, StreamExt};
+    use rdkafka::{
+        consumer::{BaseConsumer, Consumer},
+        Message, Offset, TopicPartitionList,
+    };
+    use std::{thread, time::Duration};
+
+    const TEST_CA: &str = "tests/data/Vector_CA.crt";
+    const TEST_CRT: &str = "tests/data/localhost.crt";
+    const TEST_KEY: &str = "tests/data/localhost.key";
+
+    #[tokio::test]
+    async fn healthcheck() {
+        let topic = format!("test-{}", random_string(10));
+
+        let config = KafkaSinkConfig {
+            bootstrap_servers: "localhost:9091".into(),
+            topic: topic.clone(),
+            compression: KafkaCompression::None,
+            encoding: EncodingConfigWithDefault::from(Encoding::Text),
+            key_field: None,
+            socket_timeout_ms: 60000,
+            message_timeout_ms: 300000,
+            ..Default::default()
+        };
+
+        super::healthcheck(config).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_plaintext() {
+        kafka_happy_path("localhost:9091", None, None, KafkaCompression::None).await;
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_gzip() {
+        kafka_happy_path("localhost:9091", None, None, KafkaCompression::Gzip).await;
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_lz4() {
+        kafka_happy_path("localhost:9091", None, None, KafkaCompression::Lz4).await;
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_snappy() {
+        kafka_happy_path("localhost:9091", None, None, KafkaCompression::Snappy).await;
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_zstd() {
+        kafka_happy_path("localhost:9091", None, None, KafkaCompression::Zstd).await;
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_tls() {
+        kafka_happy_path(
+            "localhost:9092",
+            None,
+            Some(KafkaTlsConfig {
+                enabled: Some(true),
+                options: TlsOptions {
+                    ca_file: Some(TEST_CA.into()),
+                    ..Default::default()
+                },
+            }),
+            KafkaCompression::None,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_tls_with_key() {
+        kafka_happy_path(
+            "localhost:9092",
+            None,
+            Some(KafkaTlsConfig {
+                enabled: Some(true),
+                options: TlsOptions {
+                    ca_file: Some(TEST_CA.into()),
+                    // Dummy key, not actually checked by server
+                    crt_file: Some(TEST_CRT.into()),
+                    key_file: Some(TEST_KEY.into()),
+                    ..Default::default()
+                },
+            }),
+            KafkaCompression::None,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_sasl() {
+        kafka_happy_path(
+            "localhost:9093",
+            Some(KafkaSaslConfig {
+                enabled: Some(true),
+                username: Some("admin".to_owned()),
+                password: Some("admin".to_owned()),
+                mechanism: Some("PLAIN".to_owned()),
+            }),
+            None,
+            KafkaCompression::None,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn kafka_happy_path_transactional() {
+        let topic = format!("test-{}", random_string(10));
+        let config = KafkaSinkConfig {
+            bootstrap_servers: "localhost:9091".into(),
+            topic: topic.clone(),
+            encoding: EncodingConfigWithDefault::from(Encoding::Text),
+            producer_mode: ProducerMode::Transactional,
+            transaction: Some(TransactionConfig {
+                id: format!("vector-{}", random_string(10)),
+                max_events: 100,
+                timeout_ms: default_transaction_timeout_ms(),
+            }),
+            socket_timeout_ms: 60000,
+            message_timeout_ms: 300000,
+            ..Default::default()
+        };
+        let (acker, ack_counter) = Acker::new_for_testing();
+        let sink = KafkaSink::new(config, acker).unwrap();
+        sink.init_transactions().await.unwrap();
+
+        let num_events = 1000;
+        let (input, events) = random_lines_with_stream(100, num_events);
+        events.map(Ok).forward(sink).await.unwrap();
+
+        // Only the messages of committed transactions are read.
+        let mut client_config = rdkafka::ClientConfig::new();
+        client_config.set("bootstrap.servers", "localhost:9091");
+        client_config.set("group.id", &random_string(10));
+        client_config.set("enable.partition.eof", "true");
+        client_config.set("isolation.level", "read_committed");
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition(&topic, 0).set_offset(Offset::Beginning);
+
+        let consumer: BaseConsumer = client_config.create().unwrap();
+        consumer.assign(&tpl).unwrap();
+
+        let mut failures = 0;
+        let mut out = Vec::new();
+        while failures < 100 {
+            match consumer.poll(Duration::from_secs(3)) {
+                Some(Ok(msg)) => {
+                    let s: &str = msg.payload_view().unwrap().unwrap();
+                    out.push(s.to_owned());
+                }
+                None if out.len() >= input.len() => break,
+                _ => {
+                    failures += 1;
+                    thread::sleep(Duration::from_millis(50));
+                }
+            }
+        }
+
+        assert_eq!(out, input);
+        assert_eq!(
+            ack_counter.load(std::sync::atomic::Ordering::Relaxed),
+            num_events
+        );
+    }
+
+    async fn kafka_happy_path(
+        server: &str,
+        sasl: Option<KafkaSaslConfig>,
+        tls: Option<KafkaTlsConfig>,
+        compression: KafkaCompression,
+    ) {
+        let topic = format!("test-{}", random_string(10));
+
+        let kafka_auth = KafkaAuthConfig { sasl, tls };
+        let config = KafkaSinkConfig {
+            bootstrap_servers: server.to_string(),
+            topic: format!("{}-%Y%m%d", topic),
+            compression,
+            encoding: EncodingConfigWithDefault::from(Encoding::Text),
+            key_field: None,
+            auth: kafka_auth.clone(),
+            socket_timeout_ms: 60000,
+            message_timeout_ms: 300000,
+            ..Default::default()
+        };
+        let topic = format!("{}-{}", topic, chrono::Utc::now().format("%Y%m%d"));
+        let (acker, ack_counter) = Acker::new_for_testing();
+        let sink = KafkaSink::new(config, acker).unwrap();
+
+        let num_events = 1000;
+        let (input, events) = random_lines_with_stream(100, num_events);
+        events.map(Ok).forward(sink).await.unwrap();
+
+        // read back everything from the beginning
+        let mut client_config = rdkafka::ClientConfig::new();
+        client_config.set("bootstrap.servers", server);
+        client_config.set("group.id", &random_string(10));
+        client_config.set("enable.partition.eof", "true");
+        let _ = kafka_auth.apply(&mut client_config).unwrap();
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition(&topic, 0).set_offset(Offset::Beginning);
+
+        let consumer: BaseConsumer = client_config.create().unwrap();
+        consumer.assign(&tpl).unwrap();
+
+        // wait for messages to show up
+        wait_for(|| {
+            let (_low, high) = consumer
+                .fetch_watermarks(&topic, 0, Duration::from_secs(3))
+                .unwrap();
+            future::ready(high > 0)
+        })
+        .await;
+
+        // check we have the expected number of messages in the topic
+        let (low, high) = consumer
+            .fetch_watermarks(&topic, 0, Duration::from_secs(3))
+            .unwrap();
+        assert_eq!((0, num_events as i64), (low, high));
+
+        // loop instead of iter so we can set a timeout
+        let mut failures = 0;
+        let mut out = Vec::new();
+        while failures < 100 {
+            match consumer.poll(Duration::from_secs(3)) {
+                Some(Ok(msg)) => {
+                    let s: &str = msg.payload_view().unwrap().unwrap();
+                    out.push(s.to_owned());
+                }
+                None if out.len() >= input.len() => break,
+                _ => {
+                    failures += 1;
+                    thread::sleep(Duration::from_millis(50));
+                }
+            }
+        }
+
+        assert_eq!(out.len(), input.len());
+        assert_eq!(out, input);
+
+        assert_eq!(
+            ack_counter.load(std::sync::atomic::Ordering::Relaxed),
+            num_events
+        );
+    }
+}
@@ -0,0 +1,518 @@
+//! Encoding of the events as Protobuf messages described by a compiled
+//! descriptor set, generated with `protoc --descriptor_set_out`.
+
+use crate::event::Value;
+use prost::Message;
+use prost_types::{
+    field_descriptor_proto::{Label, Type},
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorSet,
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{collections::HashMap, path::PathBuf};
+
+const TIMESTAMP: &str = ".google.protobuf.Timestamp";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtobufConfig {
+    pub descriptor_set_file: PathBuf,
+    pub message_type: String,
+}
+
+#[derive(Debug, Snafu)]
+pub enum DescriptorError {
+    #[snafu(display("Could not read {:?}: {}", path, source))]
+    ReadDescriptorSet {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Invalid descriptor set {:?}: {}", path, source))]
+    DecodeDescriptorSet {
+        path: PathBuf,
+        source: prost::DecodeError,
+    },
+    #[snafu(display("Message type {:?} is not in the descriptor set", message_type))]
+    UnknownMessageType { message_type: String },
+}
+
+#[derive(Debug)]
+pub struct ProtobufEncoder {
+    messages: HashMap<String, DescriptorProto>,
+    enums: HashMap<String, EnumDescriptorProto>,
+    message_type: String,
+    /// The path of the message in its file, as used by the wire format of
+    /// the schema registry.
+    indexes: Vec<i32>,
+}
+
+impl ProtobufEncoder {
+    pub fn load(config: &ProtobufConfig) -> Result<Self, DescriptorError> {
+        let path = &config.descriptor_set_file;
+        let bytes = std::fs::read(path).context(ReadDescriptorSet { path })?;
+        let descriptors =
+            FileDescriptorSet::decode(&bytes[..]).context(DecodeDescriptorSet { path })?;
+        Self::new(descriptors, &config.message_type)
+    }
+
+    pub fn new(
+        descriptors: FileDescriptorSet,
+        message_type: &str,
+    ) -> Result<Self, DescriptorError> {
+        let message_type = format!(".{}", message_type.trim_start_matches('.'));
+        let mut encoder = Self {
+            messages: HashMap::new(),
+            enums: HashMap::new(),
+            message_type,
+            indexes: Vec::new(),
+        };
+
+        for file in descriptors.file {
+            let prefix = match &file.package {
+                Some(package) => format!(".{}", package),
+                None => String::new(),
+            };
+            encoder.index(&prefix, &[], file.message_type, file.enum_type);
+        }
+
+        if encoder.messages.contains_key(&encoder.message_type) {
+            Ok(encoder)
+        } else {
+            Err(DescriptorError::UnknownMessageType {
+                message_type: encoder.message_type,
+            })
+        }
+    }
+
+    fn index(
+        &mut self,
+        prefix: &str,
+        path: &[i32],
+        messages: Vec<DescriptorProto>,
+        enums: Vec<EnumDescriptorProto>,
+    ) {
+        for descriptor in enums {
+            let name = format!("{}.{}", prefix, descriptor.name());
+            self.enums.insert(name, descriptor);
+        }
+        for (index, mut descriptor) in messages.into_iter().enumerate() {
+            let name = format!("{}.{}", prefix, descriptor.name());
+            let mut path = path.to_vec();
+            path.push(index as i32);
+            if name == self.message_type {
+                self.indexes = path.clone();
+            }
+
+            let nested = std::mem::replace(&mut descriptor.nested_type, Vec::new());
+            let enums = std::mem::replace(&mut descriptor.enum_type, Vec::new());
+            self.index(&name, &path, nested, enums);
+            self.messages.insert(name, descriptor);
+        }
+    }
+
+    /// The message indexes of the wire format, as zigzag encoded varints.
+    /// The first message of the file, by far the most common, is written as
+    /// a single `0`.
+    pub fn message_indexes(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        if self.indexes == [0] {
+            buf.push(0);
+        } else {
+            encode_varint(zigzag(self.indexes.len() as i64), &mut buf);
+            for index in &self.indexes {
+                encode_varint(zigzag(*index as i64), &mut buf);
+            }
+        }
+        buf
+    }
+
+    pub fn encode(&self, value: &Value) -> Result<Vec<u8>, String> {
+        let mut buf = Vec::new();
+        self.encode_message(&self.message_type, value, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn encode_message(&self, name: &str, value: &Value, buf: &mut Vec<u8>) -> Result<(), String> {
+        if name == TIMESTAMP {
+            if let Value::Timestamp(timestamp) = value {
+                encode_key(1, WIRE_VARINT, buf);
+                encode_varint(timestamp.timestamp() as u64, buf);
+                encode_key(2, WIRE_VARINT, buf);
+                encode_varint(timestamp.timestamp_subsec_nanos() as u64, buf);
+                return Ok(());
+            }
+        }
+
+        let descriptor = self
+            .messages
+            .get(name)
+            .ok_or_else(|| format!("message type {:?} is not in the descriptor set", name))?;
+        let map = match value {
+            Value::Map(map) => map,
+            _ => return Err(format!("expected a map for {:?}", name)),
+        };
+
+        for field in &descriptor.field {
+            let value = match map.get(field.name()) {
+                None | Some(Value::Null) => continue,
+                Some(value) => value,
+            };
+            self.encode_field(field, value, buf)
+                .map_err(|message| format!("{}: {}", field.name(), message))?;
+        }
+        Ok(())
+    }
+
+    fn encode_field(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        if field.label() != Label::Repeated {
+            return self.encode_value(field, value, buf);
+        }
+
+        let entry = self
+            .messages
+            .get(field.type_name())
+            .filter(|message| message.options.as_ref().and_then(|o| o.map_entry) == Some(true));
+        match (entry, value) {
+            // The maps are repeated entries with a key and a value field.
+            (Some(entry), Value::Map(map)) => {
+                for (key, value) in map {
+                    let mut message = Vec::new();
+                    for entry_field in &entry.field {
+                        let value = match entry_field.number() {
+                            1 => Value::from(key.as_str()),
+                            _ => value.clone(),
+                        };
+                        self.encode_value(entry_field, &value, &mut message)?;
+                    }
+                    encode_key(field.number() as u32, WIRE_LENGTH_DELIMITED, buf);
+                    encode_varint(message.len() as u64, buf);
+                    buf.extend(message);
+                }
+                Ok(())
+            }
+            (Some(_), _) => Err("expected a map".into()),
+            (None, Value::Array(items)) => items
+                .iter()
+                .try_for_each(|item| self.encode_value(field, item, buf)),
+            (None, _) => Err("expected an array".into()),
+        }
+    }
+
+    fn encode_value(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        let number = field.number() as u32;
+        match field.r#type() {
+            Type::Int32 | Type::Int64 | Type::Uint32 | Type::Uint64 => {
+                encode_key(number, WIRE_VARINT, buf);
+                encode_varint(to_i64(value)? as u64, buf);
+            }
+            Type::Sint32 | Type::Sint64 => {
+                encode_key(number, WIRE_VARINT, buf);
+                encode_varint(zigzag(to_i64(value)?), buf);
+            }
+            Type::Bool => {
+                let b = match value {
+                    Value::Boolean(b) => *b,
+                    _ => match value.to_string_lossy().as_str() {
+                        "true" => true,
+                        "false" => false,
+                        text => return Err(format!("{:?} is not a boolean", text)),
+                    },
+                };
+                encode_key(number, WIRE_VARINT, buf);
+                encode_varint(b as u64, buf);
+            }
+            Type::Enum => {
+                let number_value = match value {
+                    Value::Bytes(_) => {
+                        let symbol = value.to_string_lossy();
+                        self.enums
+                            .get(field.type_name())
+                            .and_then(|descriptor| {
+                                descriptor
+                                    .value
+                                    .iter()
+                                    .find(|candidate| candidate.name() == symbol)
+                            })
+                            .map(|candidate| candidate.number() as i64)
+                            .ok_or_else(|| format!("{:?} is not a value of the enum", symbol))?
+                    }
+                    _ => to_i64(value)?,
+                };
+                encode_key(number, WIRE_VARINT, buf);
+                encode_varint(number_value as u64, buf);
+            }
+            Type::Fixed64 | Type::Sfixed64 => {
+                encode_key(number, WIRE_FIXED64, buf);
+                buf.extend(&to_i64(value)?.to_le_bytes());
+            }
+            Type::Fixed32 | Type::Sfixed32 => {
+                encode_key(number, WIRE_FIXED32, buf);
+                buf.extend(&(to_i64(value)? as i32).to_le_bytes());
+            }
+            Type::Double => {
+                encode_key(number, WIRE_FIXED64, buf);
+                buf.extend(&to_f64(value)?.to_le_bytes());
+            }
+            Type::Float => {
+                encode_key(number, WIRE_FIXED32, buf);
+                buf.extend(&(to_f64(value)? as f32).to_le_bytes());
+            }
+            Type::String => {
+                let text = value.to_string_lossy();
+                encode_key(number, WIRE_LENGTH_DELIMITED, buf);
+                encode_varint(text.len() as u64, buf);
+                buf.extend(text.as_bytes());
+            }
+            Type::Bytes => {
+                let bytes = value.as_bytes();
+                encode_key(number, WIRE_LENGTH_DELIMITED, buf);
+                encode_varint(bytes.len() as u64, buf);
+                buf.extend(&bytes[..]);
+            }
+            Type::Message => {
+                let mut message = Vec::new();
+                self.encode_message(field.type_name(), value, &mut message)?;
+                encode_key(number, WIRE_LENGTH_DELIMITED, buf);
+                encode_varint(message.len() as u64, buf);
+                buf.extend(message);
+            }
+            Type::Group => return Err("groups are not supported".into()),
+        }
+        Ok(())
+    }
+}
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_FIXED64: u32 = 1;
+const WIRE_LENGTH_DELIMITED: u32 = 2;
+const WIRE_FIXED32: u32 = 5;
+
+fn encode_key(number: u32, wire_type: u32, buf: &mut Vec<u8>) {
+    encode_varint(u64::from(number << 3 | wire_type), buf);
+}
+
+fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn to_i64(value: &Value) -> Result<i64, String> {
+    match value {
+        Value::Integer(i) => Ok(*i),
+        Value::Float(f) if f.fract() == 0.0 => Ok(*f as i64),
+        Value::Boolean(b) => Ok(*b as i64),
+        Value::Timestamp(timestamp) => Ok(timestamp.timestamp()),
+        Value::Bytes(_) => {
+            let text = value.to_string_lossy();
+            text.parse()
+                .map_err(|_| format!("{:?} is not an integer", text))
+        }
+        _ => Err(format!("{:?} is not an integer", value)),
+    }
+}
+
+fn to_f64(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Integer(i) => Ok(*i as f64),
+        Value::Float(f) => Ok(*f),
+        Value::Bytes(_) => {
+            let text = value.to_string_lossy();
+            text.parse()
+                .map_err(|_| format!("{:?} is not a number", text))
+        }
+        _ => Err(format!("{:?} is not a number", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use prost_types::{FileDescriptorProto, MessageOptions};
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Request {
+        #[prost(string, tag = "1")]
+        message: String,
+        #[prost(sint64, tag = "2")]
+        status: i64,
+        #[prost(string, repeated, tag = "3")]
+        tags: Vec<String>,
+        #[prost(map = "string, string", tag = "4")]
+        labels: HashMap<String, String>,
+        #[prost(message, optional, tag = "5")]
+        timestamp: Option<prost_types::Timestamp>,
+        #[prost(enumeration = "Level", tag = "6")]
+        level: i32,
+        #[prost(double, tag = "7")]
+        duration: f64,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+    #[repr(i32)]
+    enum Level {
+        Info = 0,
+        Error = 1,
+    }
+
+    fn field(name: &str, number: i32, r#type: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.into()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(r#type as i32),
+            ..Default::default()
+        }
+    }
+
+    fn typed(mut field: FieldDescriptorProto, type_name: &str) -> FieldDescriptorProto {
+        field.type_name = Some(type_name.into());
+        field
+    }
+
+    fn descriptors() -> FileDescriptorSet {
+        let entry = DescriptorProto {
+            name: Some("LabelsEntry".into()),
+            field: vec![
+                field("key", 1, Type::String, Label::Optional),
+                field("value", 2, Type::String, Label::Optional),
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let level = EnumDescriptorProto {
+            name: Some("Level".into()),
+            value: vec![
+                prost_types::EnumValueDescriptorProto {
+                    name: Some("INFO".into()),
+                    number: Some(0),
+                    ..Default::default()
+                },
+                prost_types::EnumValueDescriptorProto {
+                    name: Some("ERROR".into()),
+                    number: Some(1),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let request = DescriptorProto {
+            name: Some("Request".into()),
+            field: vec![
+                field("message", 1, Type::String, Label::Optional),
+                field("status", 2, Type::Sint64, Label::Optional),
+                field("tags", 3, Type::String, Label::Repeated),
+                typed(
+                    field("labels", 4, Type::Message, Label::Repeated),
+                    ".logs.Request.LabelsEntry",
+                ),
+                typed(
+                    field("timestamp", 5, Type::Message, Label::Optional),
+                    TIMESTAMP,
+                ),
+                typed(
+                    field("level", 6, Type::Enum, Label::Optional),
+                    ".logs.Level",
+                ),
+                field("duration", 7, Type::Double, Label::Optional),
+            ],
+            nested_type: vec![entry],
+            ..Default::default()
+        };
+
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("logs.proto".into()),
+                package: Some("logs".into()),
+                message_type: vec![
+                    DescriptorProto {
+                        name: Some("Unused".into()),
+                        ..Default::default()
+                    },
+                    request,
+                ],
+                enum_type: vec![level],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn encodes_messages() {
+        let encoder = ProtobufEncoder::new(descriptors(), "logs.Request").unwrap();
+
+        let mut labels = BTreeMap::new();
+        labels.insert("app".to_string(), Value::from("nginx"));
+        let mut map = BTreeMap::new();
+        map.insert("message".to_string(), Value::from("GET /"));
+        map.insert("status".to_string(), Value::from(-1i64));
+        map.insert("tags".to_string(), Value::from(vec!["a", "b"]));
+        map.insert("labels".to_string(), Value::Map(labels));
+        map.insert(
+            "timestamp".to_string(),
+            Value::from(Utc.timestamp(1_602_759_706, 499)),
+        );
+        map.insert("level".to_string(), Value::from("ERROR"));
+        map.insert("duration".to_string(), Value::from(0.5));
+        map.insert("host".to_string(), Value::from("example.com"));
+
+        let encoded = encoder.encode(&Value::Map(map)).unwrap();
+        let decoded = Request::decode(&encoded[..]).unwrap();
+
+        let mut labels = HashMap::new();
+        labels.insert("app".to_string(), "nginx".to_string());
+        assert_eq!(
+            decoded,
+            Request {
+                message: "GET /".into(),
+                status: -1,
+                tags: vec!["a".into(), "b".into()],
+                labels,
+                timestamp: Some(prost_types::Timestamp {
+                    seconds: 1_602_759_706,
+                    nanos: 499
+                }),
+                level: Level::Error as i32,
+                duration: 0.5,
+            }
+        );
+    }
+
+    #[test]
+    fn encodes_message_indexes() {
+        let encoder = ProtobufEncoder::new(descriptors(), "logs.Request").unwrap();
+        assert_eq!(encoder.message_indexes(), vec![2, 2]);
+
+        let encoder = ProtobufEncoder::new(descriptors(), ".logs.Unused").unwrap();
+        assert_eq!(encoder.message_indexes(), vec![0]);
+
+        let encoder = ProtobufEncoder::new(descriptors(), "logs.Request.LabelsEntry").unwrap();
+        assert_eq!(encoder.message_indexes(), vec![4, 2, 0]);
+    }
+
+    #[test]
+    fn rejects_unknown_message_types() {
+        assert!(ProtobufEncoder::new(descriptors(), "logs.Response").is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//! Encoding of the events in the wire format of the Confluent Schema
+//! Registry: a magic byte, the ID of the schema in the registry, and the
+//! Avro or Protobuf encoded event.
+
+use super::{avro, protobuf::ProtobufEncoder, Encoding};
+use crate::{
+    event::{Event, Value},
+    http::{Auth, HttpClient, HttpError},
+    tls::{TlsOptions, TlsSettings},
+};
+use http::{Request, StatusCode};
+use hyper::Body;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use url::Url;
+
+const MAGIC_BYTE: u8 = 0;
+
+/// How long a schema is used before an event it can't encode gets it
+/// fetched again, in case the subject evolved.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SchemaRegistryConfig {
+    pub url: String,
+    /// Defaults to the topic name strategy, `<topic>-value`.
+    pub subject: Option<String>,
+    pub auth: Option<Auth>,
+    pub tls: Option<TlsOptions>,
+}
+
+/// All but `EncodeFailed` are about fetching the schema, and may succeed
+/// when retried.
+#[derive(Debug, Snafu)]
+pub enum SchemaRegistryError {
+    #[snafu(display("Invalid schema registry URL {:?}", url))]
+    InvalidUrl { url: String },
+    #[snafu(display("Failed to fetch the schema of subject {:?}: {}", subject, source))]
+    FetchFailed { subject: String, source: HttpError },
+    #[snafu(display("Failed to read the schema of subject {:?}: {}", subject, source))]
+    ReadFailed {
+        subject: String,
+        source: hyper::Error,
+    },
+    #[snafu(display(
+        "Unexpected status fetching the schema of subject {:?}: {}",
+        subject,
+        status
+    ))]
+    UnexpectedStatus { subject: String, status: StatusCode },
+    #[snafu(display("Invalid schema for subject {:?}: {}", subject, message))]
+    InvalidSchema { subject: String, message: String },
+    #[snafu(display(
+        "Could not encode event with the schema of subject {:?}: {}",
+        subject,
+        message
+    ))]
+    EncodeFailed { subject: String, message: String },
+}
+
+#[derive(Deserialize)]
+struct SubjectVersion {
+    id: u32,
+    schema: String,
+    #[serde(rename = "schemaType")]
+    schema_type: Option<String>,
+}
+
+enum Schema {
+    Avro(avro_rs::Schema),
+    Protobuf,
+}
+
+struct RegisteredSchema {
+    id: u32,
+    schema: Schema,
+    fetched: Instant,
+}
+
+pub struct SchemaRegistry {
+    client: HttpClient,
+    url: Url,
+    auth: Option<Auth>,
+    codec: Encoding,
+    protobuf: Option<ProtobufEncoder>,
+    /// The latest version of the subjects, fetched on their first use. The
+    /// lock is held while fetching, so that the concurrent uses of a subject
+    /// fetch it once.
+    schemas: Mutex<HashMap<String, Arc<RegisteredSchema>>>,
+}
+
+impl SchemaRegistry {
+    pub fn new(
+        config: &SchemaRegistryConfig,
+        codec: Encoding,
+        protobuf: Option<ProtobufEncoder>,
+    ) -> crate::Result<Self> {
+        let url = Url::parse(&config.url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or_else(|| SchemaRegistryError::InvalidUrl {
+                url: config.url.clone(),
+            })?;
+        let tls_settings = TlsSettings::from_options(&config.tls)?;
+
+        Ok(Self {
+            client: HttpClient::new(tls_settings)?,
+            url,
+            auth: config.auth.clone(),
+            codec,
+            protobuf,
+            schemas: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn encode(
+        &self,
+        subject: &str,
+        event: &Event,
+    ) -> Result<Vec<u8>, SchemaRegistryError> {
+        let fields: BTreeMap<String, Value> = event.as_log().clone().into();
+        let value = Value::Map(fields);
+
+        let registered = self.schema(subject, None).await?;
+        match self.encode_value(subject, &registered, &value) {
+            Err(SchemaRegistryError::EncodeFailed { .. })
+                if registered.fetched.elapsed() >= REFRESH_INTERVAL =>
+            {
+                let registered = self.schema(subject, Some(&registered)).await?;
+                self.encode_value(subject, &registered, &value)
+            }
+            result => result,
+        }
+    }
+
+    fn encode_value(
+        &self,
+        subject: &str,
+        registered: &RegisteredSchema,
+        value: &Value,
+    ) -> Result<Vec<u8>, SchemaRegistryError> {
+        let mut buf = vec![MAGIC_BYTE];
+        buf.extend(&registered.id.to_be_bytes());
+        let encoded = match &registered.schema {
+            Schema::Avro(schema) => avro::encode(schema, value),
+            Schema::Protobuf => {
+                let protobuf = self
+                    .protobuf
+                    .as_ref()
+                    .expect("Protobuf schemas require a descriptor set.");
+                buf.extend(protobuf.message_indexes());
+                protobuf.encode(value)
+            }
+        };
+        buf.extend(
+            encoded.map_err(|message| SchemaRegistryError::EncodeFailed {
+                subject: subject.into(),
+                message,
+            })?,
+        );
+
+        Ok(buf)
+    }
+
+    /// Returns the cached schema of the subject, unless it is `outdated`.
+    async fn schema(
+        &self,
+        subject: &str,
+        outdated: Option<&Arc<RegisteredSchema>>,
+    ) -> Result<Arc<RegisteredSchema>, SchemaRegistryError> {
+        let mut schemas = self.schemas.lock().await;
+        if let Some(registered) = schemas.get(subject) {
+            if !outdated.map_or(false, |outdated| Arc::ptr_eq(outdated, registered)) {
+                return Ok(Arc::clone(registered));
+            }
+        }
+
+        let registered = Arc::new(self.fetch(subject).await?);
+        debug!(message = "Fetched schema.", %subject, id = %registered.id);
+        schemas.insert(subject.into(), Arc::clone(&registered));
+        Ok(registered)
+    }
+
+    async fn fetch(&self, subject: &str) -> Result<RegisteredSchema, SchemaRegistryError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .expect("URL was checked to be a base.")
+            .pop_if_empty()
+            .extend(&["subjects", subject, "versions", "latest"]);
+
+        let mut request = Request::get(url.as_str())
+            .header("Accept", "application/vnd.schemaregistry.v1+json")
+            .body(Body::empty())
+            .unwrap();
+        if let Some(auth) = &self.auth {
+            auth.apply(&mut request);
+        }
+
+        let response = self
+            .client
+            .send(request)
+            .await
+            .context(FetchFailed { subject })?;
+        let status = response.status();
+        if status != StatusCode::OK {
+            return Err(SchemaRegistryError::UnexpectedStatus {
+                subject: subject.into(),
+                status,
+            });
+        }
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .context(ReadFailed { subject })?;
+
+        let invalid = |message: String| SchemaRegistryError::InvalidSchema {
+            subject: subject.into(),
+            message,
+        };
+        let version: SubjectVersion =
+            serde_json::from_slice(&body).map_err(|error| invalid(error.to_string()))?;
+
+        let schema_type = version.schema_type.as_deref().unwrap_or("AVRO");
+        let schema = match (self.codec, schema_type) {
+            (Encoding::Avro, "AVRO") => avro_rs::Schema::parse_str(&version.schema)
+                .map(Schema::Avro)
+                .map_err(|error| invalid(error.to_string()))?,
+            (Encoding::Protobuf, "PROTOBUF") => Schema::Protobuf,
+            (codec, schema_type) => {
+                return Err(invalid(format!(
+                    "{} schema can't be used with the {:?} codec",
+                    schema_type, codec
+                )))
+            }
+        };
+
+        Ok(RegisteredSchema {
+            id: version.id,
+            schema,
+            fetched: Instant::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::next_addr, Error};
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Response, Server,
+    };
+    use serde_json::json;
+    use std::{
+        net::SocketAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "Log",
+        "fields": [
+            {"name": "message", "type": "string"},
+            {"name": "host", "type": ["null", "string"], "default": null}
+        ]
+    }"#;
+
+    /// Serves the latest version of the `logs-value` subject, and counts
+    /// the requests.
+    fn mock_registry(version: serde_json::Value) -> (SocketAddr, Arc<AtomicUsize>) {
+        let addr = next_addr();
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&requests);
+        let body = version.to_string();
+        let service = make_service_fn(move |_| {
+            let counter = Arc::clone(&counter);
+            let body = body.clone();
+            async move {
+                Ok::<_, Error>(service_fn(move |request: Request<Body>| {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    let response = match request.uri().path() {
+                        "/subjects/logs-value/versions/latest" => {
+                            Response::new(Body::from(body.clone()))
+                        }
+                        _ => Response::builder()
+                            .status(StatusCode::NOT_FOUND)
+                            .body(Body::from(r#"{"error_code":40401}"#))
+                            .unwrap(),
+                    };
+                    async move { Ok::<_, Error>(response) }
+                }))
+            }
+        });
+        tokio::spawn(Server::bind(&addr).serve(service));
+
+        (addr, requests)
+    }
+
+    fn registry(
+        addr: SocketAddr,
+        codec: Encoding,
+        protobuf: Option<ProtobufEncoder>,
+    ) -> SchemaRegistry {
+        let config = SchemaRegistryConfig {
+            url: format!("http://{}/", addr),
+            subject: None,
+            auth: None,
+            tls: None,
+        };
+        SchemaRegistry::new(&config, codec, protobuf).unwrap()
+    }
+
+    fn event() -> Event {
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("host", "example.com");
+        event
+    }
+
+    #[tokio::test]
+    async fn encodes_avro() {
+        let (addr, requests) = mock_registry(json!({
+            "subject": "logs-value",
+            "version": 3,
+            "id": 42,
+            "schema": SCHEMA,
+        }));
+        let registry = registry(addr, Encoding::Avro, None);
+
+        let encoded = registry.encode("logs-value", &event()).await.unwrap();
+        assert_eq!(&encoded[..5], &[0, 0, 0, 0, 42]);
+
+        let schema = avro_rs::Schema::parse_str(SCHEMA).unwrap();
+        let decoded = avro_rs::from_avro_datum(&schema, &mut &encoded[5..], None).unwrap();
+        assert_eq!(
+            decoded,
+            avro_rs::types::Value::Record(vec![
+                (
+                    "message".into(),
+                    avro_rs::types::Value::String("hello".into())
+                ),
+                (
+                    "host".into(),
+                    avro_rs::types::Value::Union(Box::new(avro_rs::types::Value::String(
+                        "example.com".into()
+                    )))
+                ),
+            ])
+        );
+
+        // The schema is cached.
+        registry.encode("logs-value", &event()).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn fetches_subjects_once() {
+        let (addr, requests) = mock_registry(json!({
+            "subject": "logs-value",
+            "version": 3,
+            "id": 42,
+            "schema": SCHEMA,
+        }));
+        let registry = registry(addr, Encoding::Avro, None);
+
+        let event = event();
+        let (first, second) = futures::join!(
+            registry.encode("logs-value", &event),
+            registry.encode("logs-value", &event)
+        );
+        assert_eq!(first.unwrap(), second.unwrap());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn encodes_protobuf() {
+        use prost_types::{
+            field_descriptor_proto::{Label, Type},
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        };
+
+        let (addr, _) = mock_registry(json!({
+            "subject": "logs-value",
+            "version": 1,
+            "id": 7,
+            "schemaType": "PROTOBUF",
+            "schema": "syntax = \"proto3\"; message Log { string message = 1; }",
+        }));
+        let descriptors = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                message_type: vec![DescriptorProto {
+                    name: Some("Log".into()),
+                    field: vec![FieldDescriptorProto {
+                        name: Some("message".into()),
+                        number: Some(1),
+                        label: Some(Label::Optional as i32),
+                        r#type: Some(Type::String as i32),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let protobuf = ProtobufEncoder::new(descriptors, "Log").unwrap();
+        let registry = registry(addr, Encoding::Protobuf, Some(protobuf));
+
+        let encoded = registry.encode("logs-value", &event()).await.unwrap();
+        assert_eq!(
+            encoded,
+            vec![0, 0, 0, 0, 7, 0, 0x0a, 5, b'h', b'e', b'l', b'l', b'o']
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_schemas() {
+        let (addr, _) = mock_registry(json!({
+            "subject": "logs-value",
+            "version": 1,
+            "id": 1,
+            "schema": SCHEMA,
+        }));
+        let registry = registry(addr, Encoding::Protobuf, None);
+
+        assert!(matches!(
+            registry.encode("logs-value", &event()).await,
+            Err(SchemaRegistryError::InvalidSchema { .. })
+        ));
+        assert!(matches!(
+            registry.encode("metrics-value", &event()).await,
+            Err(SchemaRegistryError::UnexpectedStatus {
+                status: StatusCode::NOT_FOUND,
+                ..
+            })
+        ));
+    }
+}
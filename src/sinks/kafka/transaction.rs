@@ -0,0 +1,80 @@
+//! The transactional API of librdkafka, which `rdkafka` doesn't wrap yet.
+
+use rdkafka::{
+    bindings as rdsys,
+    producer::{FutureProducer, Producer},
+};
+use snafu::Snafu;
+use std::{ffi::CStr, time::Duration};
+
+#[derive(Debug, Snafu)]
+pub enum TransactionError {
+    #[snafu(display("{}", message))]
+    Failed {
+        message: String,
+        requires_abort: bool,
+        fatal: bool,
+    },
+}
+
+impl TransactionError {
+    /// The transaction can't be committed anymore, and must be aborted.
+    pub fn requires_abort(&self) -> bool {
+        match self {
+            Self::Failed { requires_abort, .. } => *requires_abort,
+        }
+    }
+
+    /// The producer can't be used anymore.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            Self::Failed { fatal, .. } => *fatal,
+        }
+    }
+}
+
+/// Registers the transactional ID and fences the previous producers using
+/// it. Blocks up to `timeout`.
+pub fn init(producer: &FutureProducer, timeout: Duration) -> Result<(), TransactionError> {
+    let client = producer.client().native_ptr();
+    check(unsafe { rdsys::rd_kafka_init_transactions(client, timeout.as_millis() as i32) })
+}
+
+pub fn begin(producer: &FutureProducer) -> Result<(), TransactionError> {
+    let client = producer.client().native_ptr();
+    check(unsafe { rdsys::rd_kafka_begin_transaction(client) })
+}
+
+/// Flushes the messages of the transaction and commits it. Blocks up to
+/// `timeout`.
+pub fn commit(producer: &FutureProducer, timeout: Duration) -> Result<(), TransactionError> {
+    let client = producer.client().native_ptr();
+    check(unsafe { rdsys::rd_kafka_commit_transaction(client, timeout.as_millis() as i32) })
+}
+
+/// Blocks up to `timeout`.
+pub fn abort(producer: &FutureProducer, timeout: Duration) -> Result<(), TransactionError> {
+    let client = producer.client().native_ptr();
+    check(unsafe { rdsys::rd_kafka_abort_transaction(client, timeout.as_millis() as i32) })
+}
+
+fn check(error: *mut rdsys::rd_kafka_error_t) -> Result<(), TransactionError> {
+    if error.is_null() {
+        return Ok(());
+    }
+
+    unsafe {
+        let message = CStr::from_ptr(rdsys::rd_kafka_error_string(error))
+            .to_string_lossy()
+            .into_owned();
+        let requires_abort = rdsys::rd_kafka_error_txn_requires_abort(error) != 0;
+        let fatal = rdsys::rd_kafka_error_is_fatal(error) != 0;
+        rdsys::rd_kafka_error_destroy(error);
+
+        Err(TransactionError::Failed {
+            message,
+            requires_abort,
+            fatal,
+        })
+    }
+}
@@ -11,16 +11,22 @@
 //!
 //! If an event produces no labels, this can happen if the template
 //! does not match, we will add a default label `{agent="vector"}`.
+//!
+//! Older versions of Loki reject the entries of a stream that are older
+//! than the latest one they accepted, so the buffer keeps track of the
+//! latest timestamp of each stream. Unless the out of order entries are
+//! accepted, the `drop` and `rewrite_timestamp` actions also default to
+//! sending the requests one at a time, so they reach Loki in order.
 
 use crate::{
     config::{log_schema, DataType, GenerateConfig, SinkConfig, SinkContext, SinkDescription},
     event::{self, Event, Value},
     http::{Auth, HttpClient},
     sinks::util::{
-        buffer::loki::{LokiBuffer, LokiEvent, LokiRecord},
+        buffer::loki::{GlobalTimestamps, LokiBuffer, LokiEvent, LokiRecord, OutOfOrderAction},
         encoding::{EncodingConfigWithDefault, EncodingConfiguration},
         http::{BatchedHttpSink, HttpSink},
-        BatchConfig, BatchSettings, TowerRequestConfig, UriSerde,
+        BatchConfig, BatchSettings, InFlightLimit, TowerRequestConfig, UriSerde,
     },
     template::Template,
     tls::{TlsOptions, TlsSettings},
@@ -45,6 +51,11 @@ pub struct LokiConfig {
     #[serde(default = "crate::serde::default_true")]
     remove_timestamp: bool,
 
+    #[serde(default)]
+    out_of_order_action: OutOfOrderAction,
+    /// The maximum number of active label sets sent by the sink.
+    max_streams: Option<usize>,
+
     auth: Option<Auth>,
 
     #[serde(default)]
@@ -89,8 +100,16 @@ impl SinkConfig for LokiConfig {
         if self.labels.is_empty() {
             return Err("`labels` must include at least one label.".into());
         }
+        if self.max_streams == Some(0) {
+            return Err("`max_streams` must be at least 1.".into());
+        }
 
-        let request_settings = self.request.unwrap_with(&TowerRequestConfig::default());
+        let mut request_defaults = TowerRequestConfig::default();
+        if self.out_of_order_action != OutOfOrderAction::Accept {
+            // Concurrent requests could reach Loki out of order.
+            request_defaults.in_flight_limit = InFlightLimit::Fixed(1);
+        }
+        let request_settings = self.request.unwrap_with(&request_defaults);
         let batch_settings = BatchSettings::default()
             .bytes(102_400)
             .events(100_000)
@@ -101,7 +120,12 @@ impl SinkConfig for LokiConfig {
 
         let sink = BatchedHttpSink::new(
             self.clone(),
-            LokiBuffer::new(batch_settings.size),
+            LokiBuffer::new(
+                batch_settings.size,
+                GlobalTimestamps::default(),
+                self.out_of_order_action,
+                self.max_streams,
+            ),
             request_settings,
             batch_settings.timeout,
             client.clone(),
@@ -5,6 +5,8 @@ use std::fmt;
 
 pub mod util;
 
+#[cfg(feature = "sinks-amqp")]
+pub mod amqp;
 #[cfg(feature = "sinks-aws_cloudwatch_logs")]
 pub mod aws_cloudwatch_logs;
 #[cfg(feature = "sinks-aws_cloudwatch_metrics")]
@@ -55,6 +57,8 @@ pub mod papertrail;
 pub mod prometheus;
 #[cfg(feature = "sinks-pulsar")]
 pub mod pulsar;
+#[cfg(feature = "sinks-redis")]
+pub mod redis;
 #[cfg(feature = "sinks-sematext")]
 pub mod sematext;
 #[cfg(feature = "sinks-socket")]
@@ -63,6 +67,8 @@ pub mod socket;
 pub mod splunk_hec;
 #[cfg(feature = "sinks-statsd")]
 pub mod statsd;
+#[cfg(feature = "sinks-syslog")]
+pub mod syslog;
 #[cfg(feature = "sinks-vector")]
 pub mod vector;
 
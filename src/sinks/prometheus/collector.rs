@@ -7,6 +7,7 @@ use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write as _;
 
 const METRIC_NAME_LABEL: &str = "__name__";
+const COUNTER_SUFFIX: &str = "_total";
 
 pub(super) trait MetricCollector {
     fn new() -> Self;
@@ -185,14 +186,43 @@ pub(super) trait MetricCollector {
     }
 }
 
+/// The text formats the metrics are exposed in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum ExpositionFormat {
+    Prometheus,
+    OpenMetrics,
+}
+
+impl ExpositionFormat {
+    pub(super) fn content_type(self) -> &'static str {
+        match self {
+            Self::Prometheus => "text/plain; version=0.0.4",
+            Self::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
+}
+
+/// A sample of a series linked to its context, like the trace it was
+/// measured in. Only exposed in the OpenMetrics format.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Exemplar {
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+    /// In seconds since the Unix epoch.
+    pub timestamp: Option<f64>,
+}
+
 pub(super) struct StringCollector {
     pub result: String,
+    format: ExpositionFormat,
+    /// The exemplar of the metric being encoded, until it's written.
+    exemplar: Option<Exemplar>,
+    counter: bool,
 }
 
 impl MetricCollector for StringCollector {
     fn new() -> Self {
-        let result = String::new();
-        Self { result }
+        Self::with_format(ExpositionFormat::Prometheus)
     }
 
     fn emit(
@@ -204,14 +234,96 @@ impl MetricCollector for StringCollector {
         tags: &Option<BTreeMap<String, String>>,
         extra: Option<(&str, String)>,
     ) {
+        if self.format == ExpositionFormat::OpenMetrics {
+            // OpenMetrics summaries only have quantiles, a sum and a count.
+            if let "_min" | "_max" | "_avg" = suffix {
+                return;
+            }
+        }
+
+        let exemplar = self.take_exemplar(suffix, &extra);
         self.result.push_str(name);
         self.result.push_str(suffix);
+        if self.counter
+            && self.format == ExpositionFormat::OpenMetrics
+            && !name.ends_with(COUNTER_SUFFIX)
+        {
+            self.result.push_str(COUNTER_SUFFIX);
+        }
         self.encode_tags(tags, extra);
-        writeln!(&mut self.result, " {}", value).ok();
+        write!(&mut self.result, " {}", value).ok();
+        if let Some(exemplar) = exemplar {
+            self.result.push_str(" # ");
+            self.encode_tags(&Some(exemplar.labels), None);
+            write!(&mut self.result, " {}", exemplar.value).ok();
+            if let Some(timestamp) = exemplar.timestamp {
+                write!(&mut self.result, " {}", timestamp).ok();
+            }
+        }
+        self.result.push('\n');
     }
 }
 
 impl StringCollector {
+    pub(super) fn with_format(format: ExpositionFormat) -> Self {
+        Self {
+            result: String::new(),
+            format,
+            exemplar: None,
+            counter: false,
+        }
+    }
+
+    /// Encodes the metric, with its exemplar in the OpenMetrics format.
+    pub(super) fn encode_series(
+        &mut self,
+        default_namespace: Option<&str>,
+        buckets: &[f64],
+        quantiles: &[f64],
+        expired: bool,
+        metric: &Metric,
+        exemplar: Option<&Exemplar>,
+    ) {
+        self.counter = matches!(metric.value, MetricValue::Counter { .. });
+        self.exemplar = match self.format {
+            ExpositionFormat::Prometheus => None,
+            ExpositionFormat::OpenMetrics => exemplar.cloned(),
+        };
+        self.encode_metric(default_namespace, buckets, quantiles, expired, metric);
+        self.counter = false;
+        self.exemplar = None;
+    }
+
+    /// Terminates the exposition.
+    pub(super) fn finish(mut self) -> String {
+        if self.format == ExpositionFormat::OpenMetrics {
+            self.result.push_str("# EOF\n");
+        }
+        self.result
+    }
+
+    /// Exemplars are attached to the samples of counters, and to the first
+    /// bucket of histograms containing their value.
+    fn take_exemplar(&mut self, suffix: &str, extra: &Option<(&str, String)>) -> Option<Exemplar> {
+        let exemplar = self.exemplar.as_ref()?;
+        let attached = match (suffix, extra) {
+            ("", None) => self.counter,
+            ("_bucket", Some(("le", bound))) => {
+                let bound = match bound.as_str() {
+                    "+Inf" => f64::INFINITY,
+                    bound => bound.parse().unwrap_or(f64::NAN),
+                };
+                exemplar.value <= bound
+            }
+            _ => false,
+        };
+        if attached {
+            self.exemplar.take()
+        } else {
+            None
+        }
+    }
+
     fn encode_tags(
         &mut self,
         tags: &Option<BTreeMap<String, String>>,
@@ -258,6 +370,16 @@ impl StringCollector {
             MetricValue::AggregatedSummary { .. } => "summary",
         };
 
+        // OpenMetrics counter families are named without the suffix of
+        // their samples.
+        let fullname = match (self.format, &metric.value) {
+            (ExpositionFormat::OpenMetrics, MetricValue::Counter { .. }) => fullname
+                .strip_suffix(COUNTER_SUFFIX)
+                .map(Into::into)
+                .unwrap_or(fullname),
+            _ => fullname,
+        };
+
         writeln!(&mut self.result, "# HELP {} {}", fullname, name).ok();
         writeln!(&mut self.result, "# TYPE {} {}", fullname, r#type).ok();
     }
@@ -562,4 +684,104 @@ vector_requests_count 6
         );
         assert_eq!(frame, "ns_requests{code=\"200\",quantile=\"0.5\"} 2\nns_requests{code=\"200\",quantile=\"0.75\"} 2\nns_requests{code=\"200\",quantile=\"0.9\"} 3\nns_requests{code=\"200\",quantile=\"0.95\"} 3\nns_requests{code=\"200\",quantile=\"0.99\"} 3\nns_requests_sum{code=\"200\"} 15\nns_requests_count{code=\"200\"} 8\nns_requests_min{code=\"200\"} 1\nns_requests_max{code=\"200\"} 3\nns_requests_avg{code=\"200\"} 1.875\n".to_owned());
     }
+
+    fn exemplar(value: f64) -> Exemplar {
+        Exemplar {
+            labels: vec![("trace_id".to_owned(), "abc".to_owned())]
+                .into_iter()
+                .collect(),
+            value,
+            timestamp: Some(1603118400.5),
+        }
+    }
+
+    fn encode_open_metrics(metric: &Metric, buckets: &[f64], exemplar: &Exemplar) -> String {
+        let mut s = StringCollector::with_format(ExpositionFormat::OpenMetrics);
+        s.encode_header(Some("vector"), metric);
+        s.encode_series(Some("vector"), buckets, &[], false, metric, Some(exemplar));
+        s.finish()
+    }
+
+    #[test]
+    fn test_encode_open_metrics_counter() {
+        let metric = Metric {
+            name: "hits_total".to_owned(),
+            namespace: None,
+            timestamp: None,
+            tags: Some(tags()),
+            kind: MetricKind::Absolute,
+            value: MetricValue::Counter { value: 10.0 },
+        };
+
+        assert_eq!(
+            encode_open_metrics(&metric, &[], &exemplar(1.0)),
+            r#"# HELP vector_hits hits_total
+# TYPE vector_hits counter
+vector_hits_total{code="200"} 10 # {trace_id="abc"} 1 1603118400.5
+# EOF
+"#
+        );
+    }
+
+    #[test]
+    fn test_encode_open_metrics_histogram() {
+        let metric = Metric {
+            name: "requests".to_owned(),
+            namespace: None,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+            value: MetricValue::Distribution {
+                values: vec![1.0, 2.0, 3.0],
+                sample_rates: vec![3, 3, 2],
+                statistic: StatisticKind::Histogram,
+            },
+        };
+
+        assert_eq!(
+            encode_open_metrics(&metric, &[0.0, 2.5, 5.0], &exemplar(3.0)),
+            r#"# HELP vector_requests requests
+# TYPE vector_requests histogram
+vector_requests_bucket{le="0"} 0
+vector_requests_bucket{le="2.5"} 6
+vector_requests_bucket{le="5"} 8 # {trace_id="abc"} 3 1603118400.5
+vector_requests_bucket{le="+Inf"} 8
+vector_requests_sum 15
+vector_requests_count 8
+# EOF
+"#
+        );
+    }
+
+    #[test]
+    fn test_encode_open_metrics_distribution_summary() {
+        let metric = Metric {
+            name: "requests".to_owned(),
+            namespace: None,
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+            value: MetricValue::Distribution {
+                values: vec![1.0, 2.0, 3.0],
+                sample_rates: vec![3, 3, 2],
+                statistic: StatisticKind::Summary,
+            },
+        };
+
+        let mut s = StringCollector::with_format(ExpositionFormat::OpenMetrics);
+        s.encode_series(
+            Some("ns"),
+            &[],
+            &[0.5],
+            false,
+            &metric,
+            Some(&exemplar(1.0)),
+        );
+
+        // Summaries don't have exemplars.
+        assert_eq!(
+            s.finish(),
+            "ns_requests{quantile=\"0.5\"} 2\nns_requests_sum 15\nns_requests_count 8\n# EOF\n"
+        );
+    }
 }
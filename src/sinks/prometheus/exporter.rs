@@ -1,40 +1,47 @@
 use crate::{
     buffers::Acker,
     config::{DataType, GenerateConfig, Resource, SinkConfig, SinkContext, SinkDescription},
-    event::metric::MetricKind,
+    event::metric::{Metric, MetricKind, MetricValue},
+    http::Auth,
     sinks::{
         util::{statistic::validate_quantiles, MetricEntry, StreamSink},
         Healthcheck, VectorSink,
     },
+    tls::{MaybeTlsListener, MaybeTlsSettings, TlsConfig},
     Event,
 };
 use async_trait::async_trait;
 use chrono::Utc;
-use futures::{future, stream::BoxStream, FutureExt, StreamExt, TryFutureExt};
+use futures::{future, stream, stream::BoxStream, FutureExt, StreamExt};
 use hyper::{
-    header::HeaderValue,
+    header::{HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE},
+    server::accept,
     service::{make_service_fn, service_fn},
-    Body, Method, Request, Response, Server, StatusCode,
+    Body, HeaderMap, Method, Request, Response, Server, StatusCode,
 };
-use indexmap::IndexSet;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     convert::Infallible,
     net::SocketAddr,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 use stream_cancel::{Trigger, Tripwire};
 
-use super::collector::{self, MetricCollector as _};
+use super::collector::{self, Exemplar, ExpositionFormat, MetricCollector as _};
 
 const MIN_FLUSH_PERIOD_SECS: u64 = 1;
+const MIN_EXPIRE_METRICS_SECS: u64 = 1;
 
 #[derive(Debug, Snafu)]
 enum BuildError {
     #[snafu(display("Flush period for sets must be greater or equal to {} secs", min))]
     FlushPeriodTooShort { min: u64 },
+    #[snafu(display("Expiration of metrics must be greater or equal to {} secs", min))]
+    ExpirationTooShort { min: u64 },
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -49,6 +56,13 @@ pub struct PrometheusExporterConfig {
     pub quantiles: Vec<f64>,
     #[serde(default = "default_flush_period_secs")]
     pub flush_period_secs: u64,
+    /// Series which didn't receive any event for this long are removed.
+    pub expire_metrics_secs: Option<u64>,
+    /// The tags moved from the series to their exemplars.
+    #[serde(default)]
+    pub exemplar_tags: Vec<String>,
+    pub auth: Option<Auth>,
+    pub tls: Option<TlsConfig>,
 }
 
 impl std::default::Default for PrometheusExporterConfig {
@@ -59,6 +73,10 @@ impl std::default::Default for PrometheusExporterConfig {
             buckets: super::default_histogram_buckets(),
             quantiles: super::default_summary_quantiles(),
             flush_period_secs: default_flush_period_secs(),
+            expire_metrics_secs: None,
+            exemplar_tags: Vec::new(),
+            auth: None,
+            tls: None,
         }
     }
 }
@@ -97,9 +115,21 @@ impl SinkConfig for PrometheusExporterConfig {
             }));
         }
 
+        if self
+            .expire_metrics_secs
+            .map_or(false, |secs| secs < MIN_EXPIRE_METRICS_SECS)
+        {
+            return Err(Box::new(BuildError::ExpirationTooShort {
+                min: MIN_EXPIRE_METRICS_SECS,
+            }));
+        }
+
         validate_quantiles(&self.quantiles)?;
 
-        let sink = PrometheusExporter::new(self.clone(), cx.acker());
+        let tls = MaybeTlsSettings::from_config(&self.tls, true)?;
+        // Bound here so that an address in use fails the startup.
+        let listener = tls.bind(&self.address).await?;
+        let sink = PrometheusExporter::new(self.clone(), Some(listener), cx.acker());
         let healthcheck = future::ok(()).boxed();
 
         Ok((VectorSink::Stream(Box::new(sink)), healthcheck))
@@ -146,89 +176,209 @@ impl SinkConfig for PrometheusCompatConfig {
     }
 }
 
+/// The state of an exported series.
+#[derive(Clone, Debug)]
+struct Series {
+    last_seen: Instant,
+    exemplar: Option<Exemplar>,
+}
+
+type Metrics = IndexMap<MetricEntry, Series>;
+
 struct PrometheusExporter {
     server_shutdown_trigger: Option<Trigger>,
     config: PrometheusExporterConfig,
-    metrics: Arc<RwLock<IndexSet<MetricEntry>>>,
+    /// The listener of the server, until the server is started.
+    listener: Option<MaybeTlsListener>,
+    metrics: Arc<RwLock<Metrics>>,
     last_flush_timestamp: Arc<RwLock<i64>>,
     acker: Acker,
 }
 
-fn handle(
-    req: Request<Body>,
-    default_namespace: Option<&str>,
-    buckets: &[f64],
-    quantiles: &[f64],
-    expired: bool,
-    metrics: &IndexSet<MetricEntry>,
-) -> Response<Body> {
-    let mut response = Response::new(Body::empty());
-
-    match (req.method(), req.uri().path()) {
-        (&Method::GET, "/metrics") => {
-            let mut s = collector::StringCollector::new();
-
-            // output headers only once
-            let mut processed_headers = HashSet::new();
-
-            for metric in metrics {
-                let name = &metric.0.name;
-                if !processed_headers.contains(&name) {
-                    s.encode_header(default_namespace, &metric.0);
-                    processed_headers.insert(name);
-                };
-
-                s.encode_metric(default_namespace, &buckets, quantiles, expired, &metric.0);
-            }
+/// The settings of the scrape handler.
+#[derive(Clone)]
+struct Handler {
+    default_namespace: Option<String>,
+    buckets: Vec<f64>,
+    quantiles: Vec<f64>,
+    expire_after: Option<Duration>,
+    /// The expected value of the `Authorization` header.
+    authorization: Option<HeaderValue>,
+}
 
-            *response.body_mut() = s.result.into();
+impl Handler {
+    fn new(config: &PrometheusExporterConfig) -> Self {
+        let authorization = config.auth.as_ref().and_then(|auth| {
+            let mut request = Request::new(());
+            auth.apply(&mut request);
+            request.headers_mut().remove(AUTHORIZATION)
+        });
 
-            response.headers_mut().insert(
-                "Content-Type",
-                HeaderValue::from_static("text/plain; version=0.0.4"),
-            );
+        Self {
+            default_namespace: config.default_namespace.clone(),
+            buckets: config.buckets.clone(),
+            quantiles: config.quantiles.clone(),
+            expire_after: config.expire_metrics_secs.map(Duration::from_secs),
+            authorization,
         }
-        _ => {
-            *response.status_mut() = StatusCode::NOT_FOUND;
+    }
+
+    fn handle(&self, req: Request<Body>, expired: bool, metrics: &Metrics) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+
+        if let Some(authorization) = &self.authorization {
+            if req.headers().get(AUTHORIZATION) != Some(authorization) {
+                *response.status_mut() = StatusCode::UNAUTHORIZED;
+                response.headers_mut().insert(
+                    WWW_AUTHENTICATE,
+                    HeaderValue::from_static("Basic realm=\"vector\""),
+                );
+                return response;
+            }
         }
+
+        match (req.method(), req.uri().path()) {
+            (&Method::GET, "/metrics") => {
+                let format = negotiate_format(req.headers());
+                let mut s = collector::StringCollector::with_format(format);
+
+                // output headers only once
+                let mut processed_headers = HashSet::new();
+
+                // The samples of a metric must be grouped together.
+                let mut series = metrics
+                    .iter()
+                    .filter(|(_, series)| !self.is_expired(series))
+                    .collect::<Vec<_>>();
+                series.sort_by(|(a, _), (b, _)| a.0.name.cmp(&b.0.name));
+
+                for (metric, series) in series {
+                    let name = &metric.0.name;
+                    if !processed_headers.contains(&name) {
+                        s.encode_header(self.default_namespace.as_deref(), &metric.0);
+                        processed_headers.insert(name);
+                    };
+
+                    s.encode_series(
+                        self.default_namespace.as_deref(),
+                        &self.buckets,
+                        &self.quantiles,
+                        expired,
+                        &metric.0,
+                        series.exemplar.as_ref(),
+                    );
+                }
+
+                *response.body_mut() = s.finish().into();
+
+                response.headers_mut().insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static(format.content_type()),
+                );
+            }
+            _ => {
+                *response.status_mut() = StatusCode::NOT_FOUND;
+            }
+        }
+
+        info!(
+            message = "Request complete.",
+            response_code = ?response.status()
+        );
+
+        response
+    }
+
+    fn is_expired(&self, series: &Series) -> bool {
+        self.expire_after.map_or(false, |expire_after| {
+            series.last_seen.elapsed() >= expire_after
+        })
+    }
+}
+
+/// Uses the OpenMetrics format when the scraper accepts it.
+fn negotiate_format(headers: &HeaderMap) -> ExpositionFormat {
+    let open_metrics = headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_range| {
+            let mut params = media_range.split(';').map(str::trim);
+            params.next() == Some("application/openmetrics-text")
+                && !params.any(|param| {
+                    param
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f64>().ok())
+                        .map_or(false, |q| q <= 0.0)
+                })
+        });
+
+    if open_metrics {
+        ExpositionFormat::OpenMetrics
+    } else {
+        ExpositionFormat::Prometheus
+    }
+}
+
+/// Moves the exemplar tags of the metric to its exemplar.
+fn extract_exemplar(metric: &mut Metric, exemplar_tags: &[String]) -> Option<Exemplar> {
+    let tags = metric.tags.as_mut()?;
+    let labels = exemplar_tags
+        .iter()
+        .filter_map(|tag| tags.remove_entry(tag))
+        .collect::<BTreeMap<_, _>>();
+    if labels.is_empty() {
+        return None;
+    }
+    if tags.is_empty() {
+        metric.tags = None;
     }
 
-    info!(
-        message = "Request complete.",
-        response_code = ?response.status()
-    );
+    let value = match &metric.value {
+        MetricValue::Counter { value } | MetricValue::Gauge { value } => *value,
+        MetricValue::Distribution { values, .. } => *values.last()?,
+        _ => return None,
+    };
+    let timestamp = metric.timestamp.unwrap_or_else(Utc::now);
 
-    response
+    Some(Exemplar {
+        labels,
+        value,
+        timestamp: Some(timestamp.timestamp_millis() as f64 / 1000.0),
+    })
 }
 
 impl PrometheusExporter {
-    fn new(config: PrometheusExporterConfig, acker: Acker) -> Self {
+    fn new(
+        config: PrometheusExporterConfig,
+        listener: Option<MaybeTlsListener>,
+        acker: Acker,
+    ) -> Self {
         Self {
             server_shutdown_trigger: None,
             config,
-            metrics: Arc::new(RwLock::new(IndexSet::new())),
+            listener,
+            metrics: Arc::new(RwLock::new(IndexMap::new())),
             last_flush_timestamp: Arc::new(RwLock::new(Utc::now().timestamp())),
             acker,
         }
     }
 
     fn start_server_if_needed(&mut self) {
-        if self.server_shutdown_trigger.is_some() {
-            return;
-        }
+        let listener = match self.listener.take() {
+            Some(listener) => listener,
+            None => return,
+        };
 
         let metrics = Arc::clone(&self.metrics);
-        let default_namespace = self.config.default_namespace.clone();
-        let buckets = self.config.buckets.clone();
-        let quantiles = self.config.quantiles.clone();
+        let handler = Handler::new(&self.config);
         let last_flush_timestamp = Arc::clone(&self.last_flush_timestamp);
         let flush_period_secs = self.config.flush_period_secs;
 
         let new_service = make_service_fn(move |_| {
             let metrics = Arc::clone(&metrics);
-            let default_namespace = default_namespace.clone();
-            let buckets = buckets.clone();
-            let quantiles = quantiles.clone();
+            let handler = handler.clone();
             let last_flush_timestamp = Arc::clone(&last_flush_timestamp);
             let flush_period_secs = flush_period_secs;
 
@@ -244,16 +394,7 @@ impl PrometheusExporter {
                         method = ?req.method(),
                         path = ?req.uri().path(),
                     )
-                    .in_scope(|| {
-                        handle(
-                            req,
-                            default_namespace.as_deref(),
-                            &buckets,
-                            &quantiles,
-                            expired,
-                            &metrics,
-                        )
-                    });
+                    .in_scope(|| handler.handle(req, expired, &metrics));
 
                     future::ok::<_, Infallible>(response)
                 }))
@@ -262,51 +403,98 @@ impl PrometheusExporter {
 
         let (trigger, tripwire) = Tripwire::new();
 
-        let server = Server::bind(&self.config.address)
-            .serve(new_service)
-            .with_graceful_shutdown(tripwire.then(crate::stream::tripwire_handler))
-            .map_err(|error| eprintln!("Server error: {}", error));
-
-        tokio::spawn(server);
+        tokio::spawn(async move {
+            if let Err(error) = Server::builder(accept::from_stream(listener.accept_stream()))
+                .serve(new_service)
+                .with_graceful_shutdown(tripwire.then(crate::stream::tripwire_handler))
+                .await
+            {
+                error!(message = "Server error.", %error);
+            }
+        });
         self.server_shutdown_trigger = Some(trigger);
     }
+
+    fn handle_event(&mut self, event: Event) {
+        let mut item = event.into_metric();
+        let exemplar = extract_exemplar(&mut item, &self.config.exemplar_tags);
+        let mut metrics = self.metrics.write().unwrap();
+
+        let (entry, series) = match item.kind {
+            MetricKind::Incremental => {
+                let new = MetricEntry(item.to_absolute());
+                if let Some((_, MetricEntry(mut existing), series)) = metrics.swap_remove_full(&new)
+                {
+                    if item.value.is_set() {
+                        // sets need to be expired from time to time
+                        // because otherwise they could grow infinitelly
+                        let now = Utc::now().timestamp();
+                        let interval = now - *self.last_flush_timestamp.read().unwrap();
+                        if interval > self.config.flush_period_secs as i64 {
+                            *self.last_flush_timestamp.write().unwrap() = now;
+                            existing.reset();
+                        }
+                    }
+                    existing.add(&item);
+                    (MetricEntry(existing), Some(series))
+                } else {
+                    (new, None)
+                }
+            }
+            MetricKind::Absolute => {
+                let new = MetricEntry(item);
+                let series = metrics.swap_remove(&new);
+                (new, series)
+            }
+        };
+
+        let series = Series {
+            last_seen: Instant::now(),
+            exemplar: exemplar.or_else(|| series.and_then(|series| series.exemplar)),
+        };
+        metrics.insert(entry, series);
+    }
+
+    /// Removes the series which didn't receive any event for
+    /// `expire_metrics_secs`.
+    fn expire_metrics(&mut self, expire_after: Duration) {
+        let mut metrics = self.metrics.write().unwrap();
+        let before = metrics.len();
+        metrics.retain(|_, series| series.last_seen.elapsed() < expire_after);
+
+        let expired = before - metrics.len();
+        if expired > 0 {
+            debug!(message = "Expired metrics.", count = %expired);
+        }
+    }
 }
 
 #[async_trait]
 impl StreamSink for PrometheusExporter {
     async fn run(&mut self, mut input: BoxStream<'_, Event>) -> Result<(), ()> {
         self.start_server_if_needed();
-        while let Some(event) = input.next().await {
-            let item = event.into_metric();
-            let mut metrics = self.metrics.write().unwrap();
-
-            match item.kind {
-                MetricKind::Incremental => {
-                    let new = MetricEntry(item.to_absolute());
-                    if let Some(MetricEntry(mut existing)) = metrics.take(&new) {
-                        if item.value.is_set() {
-                            // sets need to be expired from time to time
-                            // because otherwise they could grow infinitelly
-                            let now = Utc::now().timestamp();
-                            let interval = now - *self.last_flush_timestamp.read().unwrap();
-                            if interval > self.config.flush_period_secs as i64 {
-                                *self.last_flush_timestamp.write().unwrap() = now;
-                                existing.reset();
-                            }
-                        }
-                        existing.add(&item);
-                        metrics.insert(MetricEntry(existing));
-                    } else {
-                        metrics.insert(new);
-                    };
-                }
-                MetricKind::Absolute => {
-                    let new = MetricEntry(item);
-                    metrics.replace(new);
-                }
-            };
 
-            self.acker.ack(1);
+        let expire_after = self.config.expire_metrics_secs.map(Duration::from_secs);
+        let mut expirations = match expire_after {
+            Some(expire_after) => tokio::time::interval(expire_after).map(|_| ()).boxed(),
+            None => stream::pending().boxed(),
+        };
+
+        loop {
+            tokio::select! {
+                event = input.next() => match event {
+                    Some(event) => {
+                        self.handle_event(event);
+                        self.acker.ack(1);
+                    }
+                    None => break,
+                },
+                Some(()) = expirations.next() => {
+                    if let Some(expire_after) = expire_after {
+                        self.expire_metrics(expire_after);
+                    }
+                }
+            }
         }
         Ok(())
     }
@@ -320,6 +508,160 @@ mod tests {
     fn generate_config() {
         crate::test_util::test_generate_config::<PrometheusExporterConfig>();
     }
+
+    fn counter(tags: &[(&str, &str)]) -> Event {
+        Metric {
+            name: "hits".into(),
+            namespace: None,
+            timestamp: None,
+            tags: Some(
+                tags.iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            )
+            .filter(|tags: &BTreeMap<_, _>| !tags.is_empty()),
+            kind: MetricKind::Incremental,
+            value: MetricValue::Counter { value: 1.0 },
+        }
+        .into()
+    }
+
+    fn exporter(config: PrometheusExporterConfig) -> PrometheusExporter {
+        PrometheusExporter::new(config, None, Acker::Null)
+    }
+
+    async fn scrape(
+        exporter: &PrometheusExporter,
+        request: Request<Body>,
+    ) -> (StatusCode, HeaderMap, String) {
+        let handler = Handler::new(&exporter.config);
+        let response = handler.handle(request, false, &exporter.metrics.read().unwrap());
+        let (parts, body) = response.into_parts();
+        let body = hyper::body::to_bytes(body).await.unwrap();
+        (
+            parts.status,
+            parts.headers,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    fn get_metrics() -> http::request::Builder {
+        Request::get("/metrics")
+    }
+
+    #[test]
+    fn negotiates_format() {
+        let format = |accept: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, accept.parse().unwrap());
+            negotiate_format(&headers)
+        };
+
+        assert_eq!(
+            negotiate_format(&HeaderMap::new()),
+            ExpositionFormat::Prometheus
+        );
+        assert_eq!(
+            format("application/openmetrics-text;version=1.0.0,application/openmetrics-text;version=0.0.1;q=0.75,text/plain;version=0.0.4;q=0.5,*/*;q=0.1"),
+            ExpositionFormat::OpenMetrics
+        );
+        assert_eq!(
+            format("text/plain;version=0.0.4"),
+            ExpositionFormat::Prometheus
+        );
+        assert_eq!(
+            format("application/openmetrics-text; q=0"),
+            ExpositionFormat::Prometheus
+        );
+    }
+
+    #[tokio::test]
+    async fn exposes_exemplars_in_open_metrics() {
+        let mut exporter = exporter(PrometheusExporterConfig {
+            exemplar_tags: vec!["trace_id".into()],
+            ..Default::default()
+        });
+        exporter.handle_event(counter(&[("code", "200"), ("trace_id", "abc")]));
+        exporter.handle_event(counter(&[("code", "200"), ("trace_id", "def")]));
+
+        // The exemplar tags aren't part of the series.
+        assert_eq!(exporter.metrics.read().unwrap().len(), 1);
+
+        let (_, headers, body) =
+            scrape(&exporter, get_metrics().body(Body::empty()).unwrap()).await;
+        assert_eq!(headers[CONTENT_TYPE], "text/plain; version=0.0.4");
+        assert_eq!(
+            body,
+            "# HELP hits hits\n# TYPE hits counter\nhits{code=\"200\"} 2\n"
+        );
+
+        let request = get_metrics()
+            .header(ACCEPT, "application/openmetrics-text; version=1.0.0")
+            .body(Body::empty())
+            .unwrap();
+        let (_, headers, body) = scrape(&exporter, request).await;
+        assert_eq!(
+            headers[CONTENT_TYPE],
+            "application/openmetrics-text; version=1.0.0; charset=utf-8"
+        );
+        assert!(body.starts_with(
+            "# HELP hits hits\n# TYPE hits counter\nhits_total{code=\"200\"} 2 # {trace_id=\"def\"} 1 "
+        ));
+        assert!(body.ends_with("\n# EOF\n"));
+    }
+
+    #[tokio::test]
+    async fn expires_metrics() {
+        let mut exporter = exporter(PrometheusExporterConfig {
+            expire_metrics_secs: Some(1),
+            ..Default::default()
+        });
+        exporter.handle_event(counter(&[("pod", "a")]));
+        exporter.handle_event(counter(&[("pod", "b")]));
+
+        tokio::time::delay_for(Duration::from_millis(1100)).await;
+        exporter.handle_event(counter(&[("pod", "b")]));
+
+        // Expired series aren't exposed, even before they're removed.
+        let (_, _, body) = scrape(&exporter, get_metrics().body(Body::empty()).unwrap()).await;
+        assert_eq!(
+            body,
+            "# HELP hits hits\n# TYPE hits counter\nhits{pod=\"b\"} 2\n"
+        );
+
+        exporter.expire_metrics(Duration::from_secs(1));
+        assert_eq!(exporter.metrics.read().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn requires_authorization() {
+        let mut exporter = exporter(PrometheusExporterConfig {
+            auth: Some(Auth::Basic {
+                user: "user".into(),
+                password: "secret".into(),
+            }),
+            ..Default::default()
+        });
+        exporter.handle_event(counter(&[]));
+
+        let (status, _, _) = scrape(&exporter, get_metrics().body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let mut request = get_metrics().body(Body::empty()).unwrap();
+        Auth::Basic {
+            user: "user".into(),
+            password: "wrong".into(),
+        }
+        .apply(&mut request);
+        let (status, _, _) = scrape(&exporter, request).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let mut request = get_metrics().body(Body::empty()).unwrap();
+        exporter.config.auth.as_ref().unwrap().apply(&mut request);
+        let (status, _, body) = scrape(&exporter, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "# HELP hits hits\n# TYPE hits counter\nhits 1\n");
+    }
 }
 
 #[cfg(all(test, feature = "prometheus-integration-tests"))]
@@ -2,12 +2,12 @@ use crate::{
     buffers::Acker,
     config::{log_schema, DataType, GenerateConfig, SinkConfig, SinkContext, SinkDescription},
     event::Event,
+    pulsar::{connect, PulsarAuthConfig},
     sinks::util::encoding::{EncodingConfig, EncodingConfigWithDefault, EncodingConfiguration},
 };
 use futures::{future::BoxFuture, ready, stream::FuturesUnordered, FutureExt, Sink, Stream};
 use pulsar::{
-    producer::SendFuture, proto::CommandSendReceipt, Authentication, Error as PulsarError,
-    Producer, Pulsar, TokioExecutor,
+    producer::SendFuture, proto::CommandSendReceipt, Error as PulsarError, Producer, TokioExecutor,
 };
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
@@ -30,13 +30,7 @@ pub struct PulsarSinkConfig {
     endpoint: String,
     topic: String,
     encoding: EncodingConfigWithDefault<Encoding>,
-    auth: Option<AuthConfig>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct AuthConfig {
-    name: String,  // "token"
-    token: String, // <jwt token>
+    auth: Option<PulsarAuthConfig>,
 }
 
 #[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize, Eq, PartialEq)]
@@ -118,14 +112,7 @@ impl SinkConfig for PulsarSinkConfig {
 
 impl PulsarSinkConfig {
     async fn create_pulsar_producer(&self) -> Result<PulsarProducer, PulsarError> {
-        let mut builder = Pulsar::builder(&self.endpoint, TokioExecutor);
-        if let Some(auth) = &self.auth {
-            builder = builder.with_auth(Authentication {
-                name: auth.name.clone(),
-                data: auth.token.as_bytes().to_vec(),
-            });
-        }
-        let pulsar = builder.build().await?;
+        let pulsar = connect(&self.endpoint, &self.auth).await?;
         pulsar.producer().with_topic(&self.topic).build().await
     }
 }
@@ -310,7 +297,7 @@ mod integration_tests {
     use super::*;
     use crate::test_util::{random_lines_with_stream, random_string, trace_init};
     use futures::StreamExt;
-    use pulsar::SubType;
+    use pulsar::{Pulsar, SubType};
 
     #[tokio::test]
     async fn pulsar_happy() {
@@ -0,0 +1,402 @@
+use crate::{
+    config::{log_schema, DataType, GenerateConfig, SinkConfig, SinkContext, SinkDescription},
+    event::Event,
+    internal_events::{RedisEventSent, RedisMissingKeys},
+    sinks::util::{
+        encoding::{EncodingConfig, EncodingConfiguration},
+        retries::RetryLogic,
+        BatchConfig, BatchSettings, EncodedLength, TowerRequestConfig, VecBuffer,
+    },
+    template::Template,
+};
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream, FutureExt, Sink, SinkExt, StreamExt};
+use lazy_static::lazy_static;
+use redis::{aio::MultiplexedConnection, RedisError, RedisResult};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::Mutex;
+use tower::Service;
+use tracing_futures::Instrument;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid redis url: {}", source))]
+    InvalidUrl { source: RedisError },
+    #[snafu(display("Failed to connect to redis: {}", source))]
+    Connect { source: RedisError },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RedisSinkConfig {
+    pub url: String,
+    /// The list or channel events are written to.
+    pub key: Template,
+    #[serde(default)]
+    pub data_type: DataTypeConfig,
+    pub encoding: EncodingConfig<Encoding>,
+    #[serde(default)]
+    pub batch: BatchConfig,
+    #[serde(default)]
+    pub request: TowerRequestConfig,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Derivative, Eq, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DataTypeConfig {
+    /// Append events to a list with `RPUSH`.
+    #[derivative(Default)]
+    List,
+    /// Publish events to a channel with `PUBLISH`.
+    Channel,
+}
+
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone, Derivative)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    Text,
+    Json,
+}
+
+lazy_static! {
+    static ref REQUEST_DEFAULTS: TowerRequestConfig = TowerRequestConfig {
+        timeout_secs: Some(30),
+        ..Default::default()
+    };
+}
+
+inventory::submit! {
+    SinkDescription::new::<RedisSinkConfig>("redis")
+}
+
+impl GenerateConfig for RedisSinkConfig {
+    fn generate_config() -> toml::Value {
+        toml::from_str(
+            r#"url = "redis://127.0.0.1:6379/0"
+            key = "vector"
+            encoding.codec = "json""#,
+        )
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "redis")]
+impl SinkConfig for RedisSinkConfig {
+    async fn build(
+        &self,
+        cx: SinkContext,
+    ) -> crate::Result<(super::VectorSink, super::Healthcheck)> {
+        let client = self.client()?;
+        let conn = client
+            .get_multiplexed_tokio_connection()
+            .await
+            .context(Connect)?;
+        let healthcheck = healthcheck(conn.clone()).boxed();
+        let conn = SharedConnection {
+            client,
+            conn: Arc::new(Mutex::new((0, Some(conn)))),
+        };
+        let sink = RedisSink::new(self.clone(), conn, cx)?;
+        Ok((super::VectorSink::Sink(Box::new(sink)), healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "redis"
+    }
+}
+
+impl RedisSinkConfig {
+    fn client(&self) -> crate::Result<redis::Client> {
+        Ok(redis::Client::open(self.url.as_str()).context(InvalidUrl)?)
+    }
+
+    #[cfg(all(test, feature = "redis-integration-tests"))]
+    async fn connect(&self) -> crate::Result<MultiplexedConnection> {
+        let conn = self
+            .client()?
+            .get_multiplexed_tokio_connection()
+            .await
+            .context(Connect)?;
+        Ok(conn)
+    }
+}
+
+async fn healthcheck(mut conn: MultiplexedConnection) -> crate::Result<()> {
+    redis::cmd("PING")
+        .query_async::<_, String>(&mut conn)
+        .await
+        .map(|_| ())
+        .map_err(Into::into)
+}
+
+#[derive(Clone)]
+struct RedisSink {
+    conn: SharedConnection,
+    data_type: DataTypeConfig,
+}
+
+/// The connection shared by the requests, reestablished after it's lost.
+#[derive(Clone)]
+struct SharedConnection {
+    client: redis::Client,
+    /// The connection, and the number of times it was reestablished.
+    conn: Arc<Mutex<(usize, Option<MultiplexedConnection>)>>,
+}
+
+impl SharedConnection {
+    async fn get(&self) -> RedisResult<(usize, MultiplexedConnection)> {
+        let mut conn = self.conn.lock().await;
+        let (generation, conn) = &mut *conn;
+        let conn = match conn {
+            Some(conn) => conn.clone(),
+            None => {
+                let new = self.client.get_multiplexed_tokio_connection().await?;
+                *generation += 1;
+                debug!(message = "Reconnected to redis.");
+                conn.get_or_insert(new).clone()
+            }
+        };
+        Ok((*generation, conn))
+    }
+
+    /// Drops the connection of `generation`, which failed, unless another
+    /// request already replaced it.
+    async fn reset(&self, generation: usize) {
+        let mut conn = self.conn.lock().await;
+        if conn.0 == generation {
+            conn.1 = None;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RedisKvEntry {
+    key: String,
+    value: Bytes,
+}
+
+impl RedisSink {
+    fn new(
+        config: RedisSinkConfig,
+        conn: SharedConnection,
+        cx: SinkContext,
+    ) -> crate::Result<impl Sink<Event, Error = ()>> {
+        let batch = BatchSettings::default()
+            .bytes(1_000_000)
+            .events(100)
+            .timeout(1)
+            .parse_config(config.batch)?;
+        let request = config.request.unwrap_with(&REQUEST_DEFAULTS);
+        let encoding = config.encoding;
+        let key = config.key;
+
+        let redis = RedisSink {
+            conn,
+            data_type: config.data_type,
+        };
+
+        let sink = request
+            .batch_sink(
+                RedisRetryLogic,
+                redis,
+                VecBuffer::new(batch.size),
+                batch.timeout,
+                cx.acker(),
+            )
+            .sink_map_err(|error| error!(message = "Fatal redis sink error.", %error))
+            .with_flat_map(move |e| stream::iter(encode_event(e, &key, &encoding)).map(Ok));
+
+        Ok(sink)
+    }
+}
+
+impl Service<Vec<RedisKvEntry>> for RedisSink {
+    type Response = ();
+    type Error = RedisError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, entries: Vec<RedisKvEntry>) -> Self::Future {
+        debug!(
+            message = "Sending events.",
+            events = %entries.len(),
+        );
+
+        let mut pipe = redis::pipe();
+        for entry in &entries {
+            match self.data_type {
+                DataTypeConfig::List => pipe.rpush(&entry.key, entry.value.as_ref()),
+                DataTypeConfig::Channel => pipe.publish(&entry.key, entry.value.as_ref()),
+            };
+        }
+        let sizes: Vec<usize> = entries.iter().map(|entry| entry.value.len()).collect();
+
+        let shared = self.conn.clone();
+        Box::pin(async move {
+            let (generation, mut conn) = shared.get().await?;
+            let result: RedisResult<()> = pipe
+                .query_async(&mut conn)
+                .instrument(info_span!("request"))
+                .await;
+            match &result {
+                Ok(()) => {
+                    for byte_size in sizes {
+                        emit!(RedisEventSent { byte_size });
+                    }
+                }
+                Err(error) if error.is_io_error() || error.is_connection_dropped() => {
+                    shared.reset(generation).await
+                }
+                Err(_) => {}
+            }
+            result
+        })
+    }
+}
+
+impl EncodedLength for RedisKvEntry {
+    fn encoded_length(&self) -> usize {
+        self.key.len() + self.value.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RedisRetryLogic;
+
+impl RetryLogic for RedisRetryLogic {
+    type Error = RedisError;
+    type Response = ();
+
+    fn is_retriable_error(&self, error: &Self::Error) -> bool {
+        error.is_io_error() || error.is_timeout() || error.is_connection_dropped()
+    }
+}
+
+fn encode_event(
+    mut event: Event,
+    key: &Template,
+    encoding: &EncodingConfig<Encoding>,
+) -> Option<RedisKvEntry> {
+    let key = key
+        .render_string(&event)
+        .map_err(|missing_keys| {
+            emit!(RedisMissingKeys {
+                keys: &missing_keys
+            });
+        })
+        .ok()?;
+
+    encoding.apply_rules(&mut event);
+
+    let log = event.into_log();
+    let value = match encoding.codec() {
+        Encoding::Json => serde_json::to_vec(&log).expect("Error encoding event as json."),
+        Encoding::Text => log
+            .get(log_schema().message_key())
+            .map(|v| v.as_bytes().to_vec())
+            .unwrap_or_default(),
+    };
+
+    Some(RedisKvEntry {
+        key,
+        value: Bytes::from(value),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<RedisSinkConfig>();
+    }
+
+    #[test]
+    fn redis_encode_event_templated_key() {
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("app", "web");
+
+        let key = Template::try_from("logs-{{ app }}").unwrap();
+        let entry = encode_event(event, &key, &Encoding::Text.into()).unwrap();
+
+        assert_eq!(entry.key, "logs-web");
+        assert_eq!(entry.value, Bytes::from("hello"));
+    }
+
+    #[test]
+    fn redis_encode_event_missing_key_drops() {
+        let key = Template::try_from("logs-{{ app }}").unwrap();
+        assert!(encode_event(Event::from("hello"), &key, &Encoding::Json.into()).is_none());
+    }
+
+    #[test]
+    fn redis_encode_event_json() {
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("key", "value");
+
+        let key = Template::try_from("logs").unwrap();
+        let entry = encode_event(event, &key, &Encoding::Json.into()).unwrap();
+        let map: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_slice(&entry.value).unwrap();
+
+        assert_eq!(map[&log_schema().message_key().to_string()], "hello");
+        assert_eq!(map["key"], "value");
+    }
+}
+
+#[cfg(feature = "redis-integration-tests")]
+#[cfg(test)]
+mod integration_tests {
+    use super::*;
+    use crate::test_util::{random_lines_with_stream, random_string, trace_init};
+    use std::convert::TryFrom;
+
+    const REDIS_URL: &str = "redis://127.0.0.1:6379/0";
+
+    #[tokio::test]
+    async fn redis_sink_list() {
+        trace_init();
+
+        let key = format!("test-{}", random_string(10));
+        let config = RedisSinkConfig {
+            url: REDIS_URL.to_owned(),
+            key: Template::try_from(key.as_str()).unwrap(),
+            data_type: DataTypeConfig::List,
+            encoding: Encoding::Text.into(),
+            batch: BatchConfig::default(),
+            request: TowerRequestConfig::default(),
+        };
+
+        let (sink, healthcheck) = config.build(SinkContext::new_test()).await.unwrap();
+        healthcheck.await.unwrap();
+
+        let (lines, events) = random_lines_with_stream(100, 10);
+        sink.run(events).await.unwrap();
+
+        let mut conn = config.connect().await.unwrap();
+        let stored: Vec<String> = redis::cmd("LRANGE")
+            .arg(&key)
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(stored, lines);
+    }
+}
@@ -0,0 +1,618 @@
+#[cfg(unix)]
+use crate::sinks::util::unix::UnixSinkConfig;
+use crate::{
+    config::{log_schema, DataType, GenerateConfig, SinkConfig, SinkContext, SinkDescription},
+    event::{Event, Value},
+    internal_events::{SyslogSinkInvalidPriority, SyslogSinkMissingKeys},
+    sinks::util::{
+        encoding::{EncodingConfig, EncodingConfiguration},
+        tcp::TcpSinkConfig,
+        udp::UdpSinkConfig,
+        Encoding,
+    },
+    template::Template,
+};
+use bytes::Bytes;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+#[derive(Deserialize, Serialize, Debug)]
+// TODO: add back when serde-rs/serde#1358 is addressed
+// #[serde(deny_unknown_fields)]
+pub struct SyslogSinkConfig {
+    #[serde(flatten)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub format: Format,
+    #[serde(default)]
+    pub framing: Framing,
+    pub facility: Option<Template>,
+    pub severity: Option<Template>,
+    pub app_name: Option<Template>,
+    pub proc_id: Option<Template>,
+    pub msg_id: Option<Template>,
+    /// Fields holding maps to send as RFC 5424 structured data elements,
+    /// named after the fields.
+    #[serde(default)]
+    pub structured_data: Vec<String>,
+    pub encoding: EncodingConfig<Encoding>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Mode {
+    Tcp(TcpSinkConfig),
+    Udp(UdpSinkConfig),
+    #[cfg(unix)]
+    Unix(UnixSinkConfig),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Rfc5424,
+    Rfc3164,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self::Rfc5424
+    }
+}
+
+/// How the messages are delimited on the `tcp` and `unix` modes, as described
+/// in https://tools.ietf.org/html/rfc6587. Each `udp` datagram holds a single
+/// message, so they aren't framed.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Framing {
+    NewlineDelimited,
+    OctetCounting,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self::NewlineDelimited
+    }
+}
+
+inventory::submit! {
+    SinkDescription::new::<SyslogSinkConfig>("syslog")
+}
+
+impl GenerateConfig for SyslogSinkConfig {
+    fn generate_config() -> toml::Value {
+        toml::from_str(
+            r#"address = "92.12.333.224:514"
+            mode = "tcp"
+            encoding.codec = "text""#,
+        )
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "syslog")]
+impl SinkConfig for SyslogSinkConfig {
+    async fn build(
+        &self,
+        cx: SinkContext,
+    ) -> crate::Result<(super::VectorSink, super::Healthcheck)> {
+        let framing = match self.mode {
+            Mode::Udp(_) => None,
+            _ => Some(self.framing),
+        };
+        let encoder = SyslogEncoder::new(self, framing)?;
+        let encode_event = move |event| encoder.encode_event(event);
+        match &self.mode {
+            Mode::Tcp(config) => config.build(cx, encode_event),
+            Mode::Udp(config) => config.build(cx, encode_event),
+            #[cfg(unix)]
+            Mode::Unix(config) => config.build(cx, encode_event),
+        }
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "syslog"
+    }
+}
+
+struct SyslogEncoder {
+    format: Format,
+    framing: Option<Framing>,
+    facility: Template,
+    severity: Template,
+    app_name: Template,
+    proc_id: Option<Template>,
+    msg_id: Option<Template>,
+    structured_data: Vec<String>,
+    encoding: EncodingConfig<Encoding>,
+    /// Used for the events without a host.
+    hostname: Option<String>,
+}
+
+impl SyslogEncoder {
+    fn new(config: &SyslogSinkConfig, framing: Option<Framing>) -> crate::Result<Self> {
+        let template = |template: &Option<Template>, default: &str| match template {
+            Some(template) => Ok(template.clone()),
+            None => Template::try_from(default),
+        };
+        Ok(Self {
+            format: config.format,
+            framing,
+            facility: template(&config.facility, "user")?,
+            severity: template(&config.severity, "info")?,
+            app_name: template(&config.app_name, "vector")?,
+            proc_id: config.proc_id.clone(),
+            msg_id: config.msg_id.clone(),
+            structured_data: config.structured_data.clone(),
+            encoding: config.encoding.clone(),
+            hostname: crate::get_hostname().ok(),
+        })
+    }
+
+    fn encode_event(&self, event: Event) -> Option<Bytes> {
+        let facility = self.render(&self.facility, &event)?;
+        let facility = parse_facility(&facility).or_else(|| {
+            emit!(SyslogSinkInvalidPriority {
+                field: "facility",
+                value: &facility
+            });
+            None
+        })?;
+        let severity = self.render(&self.severity, &event)?;
+        let severity = parse_severity(&severity).or_else(|| {
+            emit!(SyslogSinkInvalidPriority {
+                field: "severity",
+                value: &severity
+            });
+            None
+        })?;
+        let app_name = self.render(&self.app_name, &event)?;
+        let proc_id = match &self.proc_id {
+            Some(proc_id) => Some(self.render(proc_id, &event)?),
+            None => None,
+        };
+        let msg_id = match &self.msg_id {
+            Some(msg_id) => Some(self.render(msg_id, &event)?),
+            None => None,
+        };
+
+        let header = Header {
+            priority: facility * 8 + severity,
+            timestamp: match event.as_log().get(log_schema().timestamp_key()) {
+                Some(Value::Timestamp(timestamp)) => *timestamp,
+                _ => Utc::now(),
+            },
+            hostname: event
+                .as_log()
+                .get(log_schema().host_key())
+                .map(|host| host.to_string_lossy())
+                .or_else(|| self.hostname.clone()),
+            app_name,
+            proc_id,
+        };
+
+        let message = match self.format {
+            Format::Rfc5424 => {
+                let structured_data = self.structured_data(&event);
+                let message = self.message(event)?;
+                header.rfc5424(msg_id.as_deref(), &structured_data, &message)
+            }
+            Format::Rfc3164 => {
+                let message = self.message(event)?;
+                header.rfc3164(&message)
+            }
+        };
+
+        let bytes = match self.framing {
+            Some(Framing::NewlineDelimited) => format!("{}\n", message),
+            Some(Framing::OctetCounting) => format!("{} {}", message.len(), message),
+            None => message,
+        };
+        Some(Bytes::from(bytes))
+    }
+
+    fn render(&self, template: &Template, event: &Event) -> Option<String> {
+        template
+            .render_string(event)
+            .map_err(|missing_keys| {
+                emit!(SyslogSinkMissingKeys {
+                    keys: &missing_keys
+                });
+            })
+            .ok()
+    }
+
+    fn message(&self, mut event: Event) -> Option<String> {
+        self.encoding.apply_rules(&mut event);
+        let log = event.into_log();
+        match self.encoding.codec() {
+            Encoding::Json => serde_json::to_string(&log)
+                .map_err(|error| error!(message = "Unable to encode.", %error))
+                .ok(),
+            Encoding::Text => Some(
+                log.get(log_schema().message_key())
+                    .map(|v| v.to_string_lossy())
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+
+    /// Renders the `structured_data` fields as STRUCTURED-DATA elements.
+    fn structured_data(&self, event: &Event) -> String {
+        let mut structured_data = String::new();
+        for id in &self.structured_data {
+            // The field names are SD-IDs, which commonly contain dots.
+            let params = match event.as_log().get_flat(id) {
+                Some(Value::Map(params)) => params,
+                _ => continue,
+            };
+            structured_data.push('[');
+            structured_data.push_str(&sd_name(id));
+            for (name, value) in params {
+                let values: Vec<&Value> = match value {
+                    Value::Array(values) => values.iter().collect(),
+                    value => vec![value],
+                };
+                for value in values {
+                    structured_data.push(' ');
+                    structured_data.push_str(&sd_name(name));
+                    structured_data.push_str("=\"");
+                    structured_data.push_str(&sd_param_value(&value.to_string_lossy()));
+                    structured_data.push('"');
+                }
+            }
+            structured_data.push(']');
+        }
+        structured_data
+    }
+}
+
+struct Header {
+    priority: u8,
+    timestamp: DateTime<Utc>,
+    hostname: Option<String>,
+    app_name: String,
+    proc_id: Option<String>,
+}
+
+impl Header {
+    /// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`
+    fn rfc5424(&self, msg_id: Option<&str>, structured_data: &str, message: &str) -> String {
+        let mut line = format!(
+            "<{}>1 {} {} {} {} {} {}",
+            self.priority,
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+            header_field(self.hostname.as_deref(), 255),
+            header_field(Some(self.app_name.as_str()), 48),
+            header_field(self.proc_id.as_deref(), 128),
+            header_field(msg_id, 32),
+            if structured_data.is_empty() {
+                "-"
+            } else {
+                structured_data
+            },
+        );
+        if !message.is_empty() {
+            line.push(' ');
+            line.push_str(message);
+        }
+        line
+    }
+
+    /// `<PRI>Mmm dd hh:mm:ss HOSTNAME TAG[PID]: MSG`
+    fn rfc3164(&self, message: &str) -> String {
+        let tag = sanitize(&self.app_name, 32, |c| c != ':' && c != '[' && c != ']');
+        let mut line = format!(
+            "<{}>{} {} {}",
+            self.priority,
+            self.timestamp.format("%b %e %H:%M:%S"),
+            header_field(self.hostname.as_deref(), 255),
+            if tag.is_empty() {
+                "vector"
+            } else {
+                tag.as_str()
+            },
+        );
+        if let Some(proc_id) = &self.proc_id {
+            line.push('[');
+            line.push_str(&sanitize(proc_id, 128, |c| c != ']'));
+            line.push(']');
+        }
+        line.push_str(": ");
+        line.push_str(message);
+        line
+    }
+}
+
+/// Keeps the printable ASCII characters, other than space, allowed by
+/// `allowed`, up to `max_len` of them.
+fn sanitize(value: &str, max_len: usize, allowed: impl Fn(char) -> bool) -> String {
+    value
+        .chars()
+        .filter(|&c| c.is_ascii_graphic() && allowed(c))
+        .take(max_len)
+        .collect()
+}
+
+/// Header fields are printable ASCII, or `-` when they are missing.
+fn header_field(value: Option<&str>, max_len: usize) -> String {
+    let value = value
+        .map(|value| sanitize(value, max_len, |_| true))
+        .unwrap_or_default();
+    if value.is_empty() {
+        "-".to_owned()
+    } else {
+        value
+    }
+}
+
+/// SD-IDs and PARAM-NAMEs can't contain `=`, `]` and `"`.
+fn sd_name(name: &str) -> String {
+    sanitize(name, 32, |c| c != '=' && c != ']' && c != '"')
+}
+
+/// `"`, `\` and `]` have to be escaped in PARAM-VALUEs.
+fn sd_param_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '"' || c == '\\' || c == ']' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// Parses a facility from its keyword or code.
+fn parse_facility(value: &str) -> Option<u8> {
+    parse_code(value, &FACILITIES)
+}
+
+/// Parses a severity from its keyword, a common alias, or its code.
+fn parse_severity(value: &str) -> Option<u8> {
+    let value = value.trim().to_ascii_lowercase();
+    let value = match value.as_str() {
+        "emergency" | "panic" => "emerg",
+        "critical" => "crit",
+        "error" => "err",
+        "warn" => "warning",
+        "informational" => "info",
+        value => value,
+    };
+    parse_code(value, &SEVERITIES)
+}
+
+fn parse_code(value: &str, keywords: &[&str]) -> Option<u8> {
+    let value = value.trim().to_ascii_lowercase();
+    match value.parse::<u8>() {
+        Ok(code) if (code as usize) < keywords.len() => Some(code),
+        Ok(_) => None,
+        Err(_) => keywords
+            .iter()
+            .position(|&keyword| keyword == value)
+            .map(|code| code as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{next_addr, random_lines_with_stream, trace_init, CountReceiver};
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<SyslogSinkConfig>();
+    }
+
+    fn config(extra: &str) -> SyslogSinkConfig {
+        toml::from_str(&format!(
+            r#"
+            mode = "tcp"
+            address = "127.0.0.1:514"
+            encoding.codec = "text"
+            {}
+            "#,
+            extra
+        ))
+        .unwrap()
+    }
+
+    fn encode(config: &SyslogSinkConfig, event: Event) -> Option<String> {
+        SyslogEncoder::new(config, Some(config.framing))
+            .unwrap()
+            .encode_event(event)
+            .map(|bytes| String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    fn event() -> Event {
+        let mut event = Event::from("hello world");
+        let log = event.as_mut_log();
+        log.insert(
+            log_schema().timestamp_key(),
+            Utc.ymd(2020, 3, 5).and_hms_micro(8, 30, 15, 123_456),
+        );
+        log.insert(log_schema().host_key(), "web-1");
+        log.insert("app", "nginx");
+        log.insert("level", "warn");
+        log.insert("pid", 8710);
+        event
+    }
+
+    #[test]
+    fn encodes_rfc5424() {
+        let config = config(
+            r#"
+            facility = "local3"
+            severity = "{{ level }}"
+            app_name = "{{ app }}"
+            proc_id = "{{ pid }}"
+            msg_id = "ID47"
+            "#,
+        );
+        assert_eq!(
+            encode(&config, event()).unwrap(),
+            "<156>1 2020-03-05T08:30:15.123456Z web-1 nginx 8710 ID47 - hello world\n"
+        );
+    }
+
+    #[test]
+    fn encodes_rfc5424_defaults() {
+        let mut event = event();
+        event.as_mut_log().insert(log_schema().message_key(), "");
+        assert_eq!(
+            encode(&config(""), event).unwrap(),
+            "<14>1 2020-03-05T08:30:15.123456Z web-1 vector - - -\n"
+        );
+    }
+
+    #[test]
+    fn encodes_structured_data() {
+        let config = config(r#"structured_data = ["exampleSDID@32473", "missing", "app"]"#);
+        let mut params = BTreeMap::new();
+        params.insert("iut".to_owned(), Value::from("3"));
+        params.insert("note".to_owned(), Value::from(r#"a "quoted" \ ] value"#));
+        params.insert("tag".to_owned(), Value::Array(vec!["a".into(), "b".into()]));
+        let mut event = event();
+        event
+            .as_mut_log()
+            .insert_flat("exampleSDID@32473", Value::Map(params));
+        assert_eq!(
+            encode(&config, event).unwrap(),
+            concat!(
+                "<14>1 2020-03-05T08:30:15.123456Z web-1 vector - - ",
+                r#"[exampleSDID@32473 iut="3" note="a \"quoted\" \\ \] value" tag="a" tag="b"] "#,
+                "hello world\n"
+            )
+        );
+    }
+
+    #[test]
+    fn encodes_rfc3164() {
+        let config = config(
+            r#"
+            format = "rfc3164"
+            severity = "{{ level }}"
+            app_name = "{{ app }}"
+            proc_id = "{{ pid }}"
+            "#,
+        );
+        assert_eq!(
+            encode(&config, event()).unwrap(),
+            "<12>Mar  5 08:30:15 web-1 nginx[8710]: hello world\n"
+        );
+    }
+
+    #[test]
+    fn encodes_octet_counting() {
+        let config = config(r#"framing = "octet_counting""#);
+        let message = "<14>1 2020-03-05T08:30:15.123456Z web-1 vector - - - hello world";
+        assert_eq!(
+            encode(&config, event()).unwrap(),
+            format!("{} {}", message.len(), message)
+        );
+    }
+
+    #[test]
+    fn sanitizes_header_fields() {
+        let config = config(r#"app_name = "my app""#);
+        let mut event = event();
+        event.as_mut_log().insert(log_schema().host_key(), "");
+        let encoded = encode(&config, event).unwrap();
+        assert!(encoded.starts_with("<14>1 2020-03-05T08:30:15.123456Z - myapp - - -"));
+    }
+
+    #[test]
+    fn drops_invalid_priorities() {
+        let config = config(r#"severity = "{{ level }}""#);
+        let mut event = event();
+        event.as_mut_log().insert("level", "loud");
+        assert_eq!(encode(&config, event), None);
+
+        assert_eq!(encode(&config, Event::from("hello world")), None);
+    }
+
+    #[test]
+    fn parses_priorities() {
+        assert_eq!(parse_facility("kern"), Some(0));
+        assert_eq!(parse_facility("LOCAL7"), Some(23));
+        assert_eq!(parse_facility("16"), Some(16));
+        assert_eq!(parse_facility("24"), None);
+        assert_eq!(parse_facility("local8"), None);
+        assert_eq!(parse_severity("error"), Some(3));
+        assert_eq!(parse_severity("Warning"), Some(4));
+        assert_eq!(parse_severity("7"), Some(7));
+        assert_eq!(parse_severity("8"), None);
+    }
+
+    #[tokio::test]
+    async fn tcp_stream() {
+        trace_init();
+
+        let addr = next_addr();
+        let config = SyslogSinkConfig {
+            mode: Mode::Tcp(TcpSinkConfig {
+                address: addr.to_string(),
+                tls: None,
+            }),
+            ..config("")
+        };
+
+        let context = SinkContext::new_test();
+        let (sink, _healthcheck) = config.build(context).await.unwrap();
+
+        let mut receiver = CountReceiver::receive_lines(addr);
+
+        let (lines, events) = random_lines_with_stream(10, 100);
+        sink.run(events).await.unwrap();
+
+        // Wait for output to connect
+        receiver.connected().await;
+
+        let output = receiver.await;
+        assert_eq!(lines.len(), output.len());
+        for (source, received) in lines.iter().zip(output) {
+            assert!(received.starts_with("<14>1 "));
+            assert!(received.ends_with(&format!(" vector - - - {}", source)));
+        }
+    }
+}
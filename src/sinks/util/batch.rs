@@ -96,6 +96,16 @@ pub struct BatchSize<B> {
     _type_marker: PhantomData<B>,
 }
 
+impl<B> BatchSize<B> {
+    pub const fn into<B2>(self) -> BatchSize<B2> {
+        BatchSize {
+            bytes: self.bytes,
+            events: self.events,
+            _type_marker: PhantomData,
+        }
+    }
+}
+
 #[derive(Debug, Derivative)]
 #[derivative(Clone(bound = ""))]
 #[derivative(Copy(bound = ""))]
@@ -142,11 +152,7 @@ impl<B> BatchSettings<B> {
     // "conflicting implementations of trait"
     pub const fn into<B2>(self) -> BatchSettings<B2> {
         BatchSettings {
-            size: BatchSize {
-                bytes: self.size.bytes,
-                events: self.size.events,
-                _type_marker: PhantomData,
-            },
+            size: self.size.into(),
             timeout: self.timeout,
         }
     }
@@ -3,18 +3,36 @@
 //! This buffer handles stream merging -- when a record is inserted into
 //! the buffer, all records having the same stream label set are grouped
 //! together for more efficient output.
+//!
+//! Older versions of Loki reject the entries of a stream older than the
+//! latest one they accepted, so the latest timestamp of each stream is shared
+//! by the successive batches, and the records arriving out of order are
+//! handled according to the `OutOfOrderAction`.
 
 use super::{
     err_event_too_large, json::BoxedRawValue, Batch, BatchConfig, BatchError, BatchSettings,
     BatchSize, PushResult,
 };
+use crate::internal_events::{
+    LokiOutOfOrderEventDropped, LokiOutOfOrderEventRewritten, LokiStreamLimitReached,
+};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, value::to_raw_value};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 const WRAPPER_OVERHEAD: usize = r#"{"streams":[]}"#.len();
 const STREAM_OVERHEAD: usize = r#"{"stream":{},"values":[]}"#.len();
 const LABEL_OVERHEAD: usize = r#""":"""#.len();
 
+/// Loki flushes the streams that didn't receive entries for its
+/// `chunk_idle_period`, 30 minutes by default, and forgets their latest
+/// timestamp, so the sink forgets them too.
+const STREAM_EXPIRATION: Duration = Duration::from_secs(30 * 60);
+
 pub type Labels = Vec<(String, String)>;
 
 #[derive(Clone, Debug)]
@@ -29,6 +47,52 @@ pub struct LokiRecord {
     pub event: LokiEvent,
 }
 
+#[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize, Eq, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutOfOrderAction {
+    Drop,
+    RewriteTimestamp,
+    #[derivative(Default)]
+    Accept,
+}
+
+/// The latest timestamp sent for each active stream, and when it was sent,
+/// shared by the successive batches of a sink.
+#[derive(Clone, Debug)]
+pub struct GlobalTimestamps {
+    map: Arc<Mutex<HashMap<Labels, (i64, Instant)>>>,
+    expiration: Duration,
+}
+
+impl Default for GlobalTimestamps {
+    fn default() -> Self {
+        Self::new(STREAM_EXPIRATION)
+    }
+}
+
+impl GlobalTimestamps {
+    fn new(expiration: Duration) -> Self {
+        Self {
+            map: Default::default(),
+            expiration,
+        }
+    }
+
+    fn update(&self, streams: &HashMap<Labels, Vec<LokiEncodedEvent>>) {
+        let now = Instant::now();
+        let mut map = self.map.lock().unwrap();
+        map.retain(|_, (_, sent)| now.duration_since(*sent) < self.expiration);
+        for (labels, events) in streams {
+            if let Some(latest) = events.iter().map(|event| event.timestamp).max() {
+                let (timestamp, sent) = map.entry(labels.clone()).or_insert((latest, now));
+                *timestamp = latest.max(*timestamp);
+                *sent = now;
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct LokiEncodedEvent {
     pub timestamp: i64,
@@ -53,15 +117,78 @@ pub struct LokiBuffer {
     num_items: usize,
     streams: HashMap<Labels, Vec<LokiEncodedEvent>>,
     settings: BatchSize<Self>,
+    latest_timestamps: GlobalTimestamps,
+    out_of_order_action: OutOfOrderAction,
+    max_streams: Option<usize>,
 }
 
 impl LokiBuffer {
-    pub fn new(settings: BatchSize<Self>) -> Self {
+    pub fn new(
+        settings: BatchSize<Self>,
+        latest_timestamps: GlobalTimestamps,
+        out_of_order_action: OutOfOrderAction,
+        max_streams: Option<usize>,
+    ) -> Self {
         Self {
             num_bytes: WRAPPER_OVERHEAD,
             num_items: 0,
             streams: HashMap::default(),
             settings,
+            latest_timestamps,
+            out_of_order_action,
+            max_streams,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.num_bytes >= self.settings.bytes || self.num_items >= self.settings.events
+    }
+
+    /// Checks the record against the streams sent by the previous batches,
+    /// returning whether it can be added to the batch.
+    fn admit(&self, item: &mut LokiRecord) -> bool {
+        let expiration = self.latest_timestamps.expiration;
+        let mut latest_timestamps = self.latest_timestamps.map.lock().unwrap();
+        let latest = latest_timestamps
+            .get(&item.labels)
+            .filter(|(_, sent)| sent.elapsed() < expiration)
+            .map(|(latest, _)| *latest);
+        let latest = match latest {
+            Some(latest) => latest,
+            None => {
+                match self.max_streams {
+                    Some(max_streams) if !self.streams.contains_key(&item.labels) => {
+                        latest_timestamps.retain(|_, (_, sent)| sent.elapsed() < expiration);
+                        let new_streams = self
+                            .streams
+                            .keys()
+                            .filter(|labels| !latest_timestamps.contains_key(*labels))
+                            .count();
+                        if latest_timestamps.len() + new_streams >= max_streams {
+                            emit!(LokiStreamLimitReached { max_streams });
+                            return false;
+                        }
+                    }
+                    _ => {}
+                }
+                return true;
+            }
+        };
+
+        if item.event.timestamp >= latest {
+            return true;
+        }
+        match self.out_of_order_action {
+            OutOfOrderAction::Drop => {
+                emit!(LokiOutOfOrderEventDropped);
+                false
+            }
+            OutOfOrderAction::RewriteTimestamp => {
+                emit!(LokiOutOfOrderEventRewritten);
+                item.event.timestamp = latest;
+                true
+            }
+            OutOfOrderAction::Accept => true,
         }
     }
 }
@@ -80,6 +207,13 @@ impl Batch for LokiBuffer {
     }
 
     fn push(&mut self, mut item: Self::Input) -> PushResult<Self::Input> {
+        // We must sort the stream labels here to ensure they hash to
+        // the same stream if the label set matches.
+        item.labels.sort();
+        if !self.admit(&mut item) {
+            return PushResult::Ok(self.is_full());
+        }
+
         let labels_len = item
             .labels
             .iter()
@@ -96,9 +230,6 @@ impl Batch for LokiBuffer {
         {
             PushResult::Overflow(item)
         } else {
-            // We must sort the stream labels here to ensure they hash to
-            // the same stream if the label set matches.
-            item.labels.sort();
             let new_bytes = match self.streams.get_mut(&item.labels) {
                 // Label exists, and we checked the size, just add it
                 Some(stream) => {
@@ -119,9 +250,7 @@ impl Batch for LokiBuffer {
             };
             self.num_bytes += new_bytes;
             self.num_items += 1;
-            PushResult::Ok(
-                self.num_bytes >= self.settings.bytes || self.num_items >= self.settings.events,
-            )
+            PushResult::Ok(self.is_full())
         }
     }
 
@@ -130,10 +259,17 @@ impl Batch for LokiBuffer {
     }
 
     fn fresh(&self) -> Self {
-        Self::new(self.settings)
+        Self::new(
+            self.settings,
+            self.latest_timestamps.clone(),
+            self.out_of_order_action,
+            self.max_streams,
+        )
     }
 
     fn finish(self) -> Self::Output {
+        self.latest_timestamps.update(&self.streams);
+
         let streams_json = self
             .streams
             .into_iter()
@@ -199,7 +335,12 @@ mod tests {
 
     #[test]
     fn insert_single() {
-        let mut buffer = LokiBuffer::new(BatchSettings::default().size);
+        let mut buffer = LokiBuffer::new(
+            BatchSettings::default().size,
+            GlobalTimestamps::default(),
+            OutOfOrderAction::Drop,
+            None,
+        );
         assert!(matches!(
             buffer.push(LokiRecord {
                 labels: vec![("label1".into(), "value1".into())],
@@ -221,7 +362,12 @@ mod tests {
 
     #[test]
     fn insert_multiple_streams() {
-        let mut buffer = LokiBuffer::new(BatchSettings::default().size);
+        let mut buffer = LokiBuffer::new(
+            BatchSettings::default().size,
+            GlobalTimestamps::default(),
+            OutOfOrderAction::Drop,
+            None,
+        );
         for n in 1..4 {
             assert!(matches!(
                 buffer.push(LokiRecord {
@@ -245,7 +391,12 @@ mod tests {
 
     #[test]
     fn insert_multiple_one_stream() {
-        let mut buffer = LokiBuffer::new(BatchSettings::default().size);
+        let mut buffer = LokiBuffer::new(
+            BatchSettings::default().size,
+            GlobalTimestamps::default(),
+            OutOfOrderAction::Drop,
+            None,
+        );
         for n in 1..4 {
             assert!(matches!(
                 buffer.push(LokiRecord {
@@ -266,4 +417,156 @@ mod tests {
             r#"{"streams":[{"stream":{"asdf":"value1"},"values":[["123456781","event #1"],["123456782","event #2"],["123456783","event #3"]]}]}"#,
         );
     }
+
+    fn record(stream: &str, timestamp: i64) -> LokiRecord {
+        LokiRecord {
+            labels: vec![("stream".into(), stream.into())],
+            event: LokiEvent {
+                timestamp,
+                event: format!("event at {}", timestamp),
+            },
+        }
+    }
+
+    fn push(buffer: &mut LokiBuffer, stream: &str, timestamp: i64) {
+        assert!(matches!(
+            buffer.push(record(stream, timestamp)),
+            PushResult::Ok(false)
+        ));
+    }
+
+    fn buffer(action: OutOfOrderAction, max_streams: Option<usize>) -> LokiBuffer {
+        LokiBuffer::new(
+            BatchSettings::default().size,
+            GlobalTimestamps::default(),
+            action,
+            max_streams,
+        )
+    }
+
+    #[test]
+    fn sorts_stream_within_batch() {
+        let mut buffer = buffer(OutOfOrderAction::Drop, None);
+        for timestamp in &[3, 1, 2] {
+            assert!(matches!(
+                buffer.push(record("a", *timestamp)),
+                PushResult::Ok(false)
+            ));
+        }
+
+        assert_eq!(buffer.num_items, 3);
+        test_finish(
+            buffer,
+            r#"{"streams":[{"stream":{"stream":"a"},"values":[["1","event at 1"],["2","event at 2"],["3","event at 3"]]}]}"#,
+        );
+    }
+
+    #[test]
+    fn drops_out_of_order_events() {
+        let mut buffer = buffer(OutOfOrderAction::Drop, None);
+        push(&mut buffer, "a", 10);
+        push(&mut buffer, "b", 10);
+        let mut buffer = {
+            let fresh = buffer.fresh();
+            buffer.finish();
+            fresh
+        };
+
+        assert!(matches!(buffer.push(record("a", 5)), PushResult::Ok(false)));
+        assert!(buffer.is_empty());
+        push(&mut buffer, "a", 10);
+        push(&mut buffer, "b", 12);
+        push(&mut buffer, "c", 5);
+
+        assert_eq!(buffer.num_items, 3);
+        test_finish(
+            buffer,
+            r#"{"streams":[{"stream":{"stream":"a"},"values":[["10","event at 10"]]},{"stream":{"stream":"b"},"values":[["12","event at 12"]]},{"stream":{"stream":"c"},"values":[["5","event at 5"]]}]}"#,
+        );
+    }
+
+    #[test]
+    fn rewrites_out_of_order_timestamps() {
+        let mut buffer = buffer(OutOfOrderAction::RewriteTimestamp, None);
+        push(&mut buffer, "a", 10);
+        let mut buffer = {
+            let fresh = buffer.fresh();
+            buffer.finish();
+            fresh
+        };
+
+        push(&mut buffer, "a", 5);
+        test_finish(
+            buffer,
+            r#"{"streams":[{"stream":{"stream":"a"},"values":[["10","event at 5"]]}]}"#,
+        );
+    }
+
+    #[test]
+    fn accepts_out_of_order_events() {
+        let mut buffer = buffer(OutOfOrderAction::Accept, None);
+        push(&mut buffer, "a", 10);
+        let mut buffer = {
+            let fresh = buffer.fresh();
+            buffer.finish();
+            fresh
+        };
+
+        push(&mut buffer, "a", 5);
+        test_finish(
+            buffer,
+            r#"{"streams":[{"stream":{"stream":"a"},"values":[["5","event at 5"]]}]}"#,
+        );
+    }
+
+    #[test]
+    fn forgets_expired_streams() {
+        let latest_timestamps = GlobalTimestamps::new(Duration::from_secs(0));
+        let buffer = |max_streams| {
+            LokiBuffer::new(
+                BatchSettings::default().size,
+                latest_timestamps.clone(),
+                OutOfOrderAction::Drop,
+                max_streams,
+            )
+        };
+
+        let mut first = buffer(Some(1));
+        push(&mut first, "a", 10);
+        first.finish();
+
+        let mut buffer = buffer(Some(1));
+        push(&mut buffer, "b", 5);
+        push(&mut buffer, "c", 5);
+        assert_eq!(buffer.num_items, 1);
+        test_finish(
+            buffer,
+            r#"{"streams":[{"stream":{"stream":"b"},"values":[["5","event at 5"]]}]}"#,
+        );
+        assert_eq!(latest_timestamps.map.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn limits_streams() {
+        let mut buffer = buffer(OutOfOrderAction::Drop, Some(2));
+        push(&mut buffer, "a", 1);
+        push(&mut buffer, "b", 1);
+        push(&mut buffer, "c", 1);
+        push(&mut buffer, "a", 2);
+        assert_eq!(buffer.num_items, 3);
+        assert_eq!(buffer.streams.len(), 2);
+
+        let mut buffer = {
+            let fresh = buffer.fresh();
+            buffer.finish();
+            fresh
+        };
+        push(&mut buffer, "d", 3);
+        push(&mut buffer, "b", 3);
+        assert_eq!(buffer.num_items, 1);
+        test_finish(
+            buffer,
+            r#"{"streams":[{"stream":{"stream":"b"},"values":[["3","event at 3"]]}]}"#,
+        );
+    }
 }
@@ -8,6 +8,8 @@ pub mod compression;
 pub mod json;
 pub mod loki;
 pub mod metrics;
+#[cfg(feature = "parquet")]
+pub mod parquet;
 pub mod partition;
 pub mod vec;
 
@@ -0,0 +1,646 @@
+use super::super::batch::{
+    err_event_too_large, Batch, BatchConfig, BatchError, BatchSettings, BatchSize, PushResult,
+};
+use super::super::encoding::EncodingConfiguration;
+use super::{Buffer, Compression, PartitionInnerBuffer};
+use crate::{
+    config::log_schema,
+    event::{Event, LogEvent, Value},
+    internal_events::ParquetEncodingFailed,
+    template::Template,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use parquet::{
+    basic::{Compression as ParquetCompression, LogicalType, Repetition, Type as PhysicalType},
+    column::writer::{ColumnWriter, ColumnWriterImpl},
+    data_type::{ByteArray, DataType},
+    errors::ParquetError,
+    file::{
+        properties::WriterProperties,
+        writer::{FileWriter, InMemoryWriteableCursor, RowGroupWriter, SerializedFileWriter},
+    },
+    schema::types::Type,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, rc::Rc, sync::Arc};
+
+#[derive(Clone, Debug, Derivative, Deserialize, Serialize, PartialEq)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct ParquetConfig {
+    /// The columns of the files, from the top level fields of the events.
+    /// Inferred from each batch when not set.
+    #[serde(default)]
+    pub schema: Option<BTreeMap<String, ParquetType>>,
+    #[serde(default = "default_row_group_size")]
+    #[derivative(Default(value = "default_row_group_size()"))]
+    pub row_group_size: usize,
+}
+
+fn default_row_group_size() -> usize {
+    10_000
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParquetType {
+    Boolean,
+    Double,
+    Int64,
+    String,
+    Timestamp,
+}
+
+impl ParquetType {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Boolean(_) => Some(Self::Boolean),
+            Value::Float(_) => Some(Self::Double),
+            Value::Integer(_) => Some(Self::Int64),
+            Value::Timestamp(_) => Some(Self::Timestamp),
+            Value::Bytes(_) | Value::Map(_) | Value::Array(_) => Some(Self::String),
+            Value::Null => None,
+        }
+    }
+
+    // The type of a column holding values of both types.
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::Int64, Self::Double) | (Self::Double, Self::Int64) => Self::Double,
+            _ => Self::String,
+        }
+    }
+
+    fn field(self, name: &str) -> Type {
+        let (physical, logical) = match self {
+            Self::Boolean => (PhysicalType::BOOLEAN, LogicalType::NONE),
+            Self::Double => (PhysicalType::DOUBLE, LogicalType::NONE),
+            Self::Int64 => (PhysicalType::INT64, LogicalType::INT_64),
+            Self::String => (PhysicalType::BYTE_ARRAY, LogicalType::UTF8),
+            Self::Timestamp => (PhysicalType::INT64, LogicalType::TIMESTAMP_MILLIS),
+        };
+        Type::primitive_type_builder(name, physical)
+            .with_logical_type(logical)
+            .with_repetition(Repetition::OPTIONAL)
+            .build()
+            .expect("Primitive types without length or precision are always valid")
+    }
+}
+
+/// Buffers the events of a batch, to write them as a Parquet file with one
+/// column per field. Since the file is only written once the batch is
+/// complete, the size of the batch is estimated from the size of the values.
+#[derive(Debug)]
+pub struct ParquetBuffer {
+    rows: Vec<BTreeMap<String, Value>>,
+    num_bytes: usize,
+    settings: BatchSize<Self>,
+    compression: Compression,
+    config: Arc<ParquetConfig>,
+}
+
+impl ParquetBuffer {
+    pub fn new(settings: BatchSize<Self>, compression: Compression, config: ParquetConfig) -> Self {
+        Self::with_config(settings, compression, Arc::new(config))
+    }
+
+    fn with_config(
+        settings: BatchSize<Self>,
+        compression: Compression,
+        config: Arc<ParquetConfig>,
+    ) -> Self {
+        Self {
+            rows: Vec::new(),
+            num_bytes: 0,
+            settings,
+            compression,
+            config,
+        }
+    }
+
+    fn schema(&self) -> BTreeMap<String, ParquetType> {
+        if let Some(schema) = &self.config.schema {
+            return schema.clone();
+        }
+
+        let mut schema = BTreeMap::new();
+        for row in &self.rows {
+            for (name, value) in row {
+                if let Some(kind) = ParquetType::of(value) {
+                    schema
+                        .entry(name.clone())
+                        .and_modify(|existing: &mut ParquetType| *existing = existing.merge(kind))
+                        .or_insert(kind);
+                }
+            }
+        }
+        schema
+    }
+
+    fn write(
+        &self,
+        schema: &BTreeMap<String, ParquetType>,
+        rows: &[BTreeMap<String, Value>],
+    ) -> Result<Vec<u8>, ParquetError> {
+        let mut fields = schema
+            .iter()
+            .map(|(name, kind)| Rc::new(kind.field(name)))
+            .collect::<Vec<_>>();
+        let schema = Type::group_type_builder("vector")
+            .with_fields(&mut fields)
+            .build()?;
+
+        let compression = match self.compression {
+            Compression::None => ParquetCompression::UNCOMPRESSED,
+            Compression::Gzip(_) => ParquetCompression::GZIP,
+        };
+        let properties = WriterProperties::builder()
+            .set_compression(compression)
+            .build();
+
+        let cursor = InMemoryWriteableCursor::default();
+        let mut writer =
+            SerializedFileWriter::new(cursor.clone(), Rc::new(schema), Rc::new(properties))?;
+        for rows in rows.chunks(self.config.row_group_size.max(1)) {
+            let mut row_group = writer.next_row_group()?;
+            for (name, kind) in schema {
+                let mut column = row_group
+                    .next_column()?
+                    .expect("There is a column writer for each field of the schema");
+                write_column(&mut column, rows, name, *kind)?;
+                row_group.close_column(column)?;
+            }
+            writer.close_row_group(row_group)?;
+        }
+        writer.close()?;
+
+        Ok(cursor.data())
+    }
+}
+
+impl Batch for ParquetBuffer {
+    type Input = LogEvent;
+    type Output = Vec<u8>;
+
+    fn get_settings_defaults(
+        config: BatchConfig,
+        defaults: BatchSettings<Self>,
+    ) -> Result<BatchSettings<Self>, BatchError> {
+        Ok(config
+            .use_size_as_bytes()?
+            .get_settings_or_default(defaults))
+    }
+
+    fn push(&mut self, item: Self::Input) -> PushResult<Self::Input> {
+        let row: BTreeMap<String, Value> = item.into();
+        let size = row
+            .iter()
+            .map(|(name, value)| name.len() + value_size(value))
+            .sum::<usize>();
+        let new_bytes = self.num_bytes + size;
+        if self.is_empty() && size > self.settings.bytes {
+            err_event_too_large(size)
+        } else if self.rows.len() >= self.settings.events || new_bytes > self.settings.bytes {
+            PushResult::Overflow(row.into())
+        } else {
+            self.rows.push(row);
+            self.num_bytes = new_bytes;
+            PushResult::Ok(
+                self.rows.len() >= self.settings.events || new_bytes >= self.settings.bytes,
+            )
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    fn fresh(&self) -> Self {
+        Self::with_config(self.settings, self.compression, Arc::clone(&self.config))
+    }
+
+    /// When the batch can't be written as a whole, the rows are tried one at
+    /// a time, and only the ones that can't be written on their own are
+    /// dropped. A batch left without rows gives an empty file that the sinks
+    /// don't upload.
+    fn finish(mut self) -> Self::Output {
+        let schema = self.schema();
+        let error = match self.write(&schema, &self.rows) {
+            Ok(bytes) => return bytes,
+            Err(error) => error,
+        };
+
+        let (rows, dropped): (Vec<_>, Vec<_>) = std::mem::take(&mut self.rows)
+            .into_iter()
+            .partition(|row| self.write(&schema, std::slice::from_ref(row)).is_ok());
+        if !dropped.is_empty() {
+            emit!(ParquetEncodingFailed {
+                error,
+                count: dropped.len(),
+            });
+        }
+        if rows.is_empty() {
+            return Vec::new();
+        }
+        self.write(&schema, &rows).unwrap_or_else(|error| {
+            emit!(ParquetEncodingFailed {
+                error,
+                count: rows.len(),
+            });
+            Vec::new()
+        })
+    }
+
+    fn num_items(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+/// The buffer of the sinks writing objects, either from the encoded events or
+/// as Parquet files.
+#[derive(Debug)]
+pub enum ObjectBuffer {
+    Encoded(Buffer),
+    Parquet(ParquetBuffer),
+}
+
+/// An event for an [`ObjectBuffer`], encoded unless written to a Parquet file.
+#[derive(Debug)]
+pub enum ObjectInput {
+    Encoded(Vec<u8>),
+    Log(LogEvent),
+}
+
+/// The encodings of the sinks writing objects.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ObjectEncoding {
+    Text,
+    Ndjson,
+    Parquet,
+}
+
+/// Encodes an event for an [`ObjectBuffer`], partitioned by the key prefix
+/// rendered from it. Events missing the fields of the key prefix are dropped.
+pub fn encode_object_event<E>(
+    mut event: Event,
+    key_prefix: &Template,
+    encoding: &impl EncodingConfiguration<E>,
+) -> Option<PartitionInnerBuffer<ObjectInput, Bytes>>
+where
+    E: Clone + Into<ObjectEncoding>,
+{
+    let key = key_prefix
+        .render_string(&event)
+        .map_err(|missing_keys| {
+            warn!(
+                message = "Keys do not exist on the event; dropping event.",
+                ?missing_keys,
+                rate_limit_secs = 30,
+            );
+        })
+        .ok()?;
+
+    encoding.apply_rules(&mut event);
+    let log = event.into_log();
+
+    let input = match encoding.codec().clone().into() {
+        ObjectEncoding::Ndjson => ObjectInput::Encoded(
+            serde_json::to_vec(&log)
+                .map(|mut b| {
+                    b.push(b'\n');
+                    b
+                })
+                .expect("Failed to encode event as json, this is a bug!"),
+        ),
+        ObjectEncoding::Text => {
+            let mut bytes = log
+                .get(log_schema().message_key())
+                .map(|v| v.as_bytes().to_vec())
+                .unwrap_or_default();
+            bytes.push(b'\n');
+            ObjectInput::Encoded(bytes)
+        }
+        // Parquet files are written from the logs of the whole batch.
+        ObjectEncoding::Parquet => ObjectInput::Log(log),
+    };
+
+    Some(PartitionInnerBuffer::new(input, key.into()))
+}
+
+impl ObjectBuffer {
+    pub fn new(
+        settings: BatchSize<Self>,
+        compression: Compression,
+        parquet: Option<ParquetConfig>,
+    ) -> Self {
+        match parquet {
+            Some(config) => Self::Parquet(ParquetBuffer::new(settings.into(), compression, config)),
+            None => Self::Encoded(Buffer::new(settings.into(), compression)),
+        }
+    }
+}
+
+impl Batch for ObjectBuffer {
+    type Input = ObjectInput;
+    type Output = Vec<u8>;
+
+    fn get_settings_defaults(
+        config: BatchConfig,
+        defaults: BatchSettings<Self>,
+    ) -> Result<BatchSettings<Self>, BatchError> {
+        Ok(config
+            .use_size_as_bytes()?
+            .get_settings_or_default(defaults))
+    }
+
+    fn push(&mut self, item: Self::Input) -> PushResult<Self::Input> {
+        match (self, item) {
+            (Self::Encoded(buffer), ObjectInput::Encoded(bytes)) => match buffer.push(bytes) {
+                PushResult::Ok(full) => PushResult::Ok(full),
+                PushResult::Overflow(bytes) => PushResult::Overflow(ObjectInput::Encoded(bytes)),
+            },
+            (Self::Parquet(buffer), ObjectInput::Log(log)) => match buffer.push(log) {
+                PushResult::Ok(full) => PushResult::Ok(full),
+                PushResult::Overflow(log) => PushResult::Overflow(ObjectInput::Log(log)),
+            },
+            _ => unreachable!("Events are encoded for the buffer they are pushed to"),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Encoded(buffer) => buffer.is_empty(),
+            Self::Parquet(buffer) => buffer.is_empty(),
+        }
+    }
+
+    fn fresh(&self) -> Self {
+        match self {
+            Self::Encoded(buffer) => Self::Encoded(buffer.fresh()),
+            Self::Parquet(buffer) => Self::Parquet(buffer.fresh()),
+        }
+    }
+
+    fn finish(self) -> Self::Output {
+        match self {
+            Self::Encoded(buffer) => buffer.finish(),
+            Self::Parquet(buffer) => buffer.finish(),
+        }
+    }
+
+    fn num_items(&self) -> usize {
+        match self {
+            Self::Encoded(buffer) => buffer.num_items(),
+            Self::Parquet(buffer) => buffer.num_items(),
+        }
+    }
+}
+
+fn write_column(
+    column: &mut ColumnWriter,
+    rows: &[BTreeMap<String, Value>],
+    name: &str,
+    kind: ParquetType,
+) -> Result<(), ParquetError> {
+    match (column, kind) {
+        (ColumnWriter::BoolColumnWriter(writer), ParquetType::Boolean) => {
+            write_values(writer, rows, name, to_boolean)
+        }
+        (ColumnWriter::DoubleColumnWriter(writer), ParquetType::Double) => {
+            write_values(writer, rows, name, to_double)
+        }
+        (ColumnWriter::Int64ColumnWriter(writer), ParquetType::Int64) => {
+            write_values(writer, rows, name, to_int64)
+        }
+        (ColumnWriter::Int64ColumnWriter(writer), ParquetType::Timestamp) => {
+            write_values(writer, rows, name, to_timestamp)
+        }
+        (ColumnWriter::ByteArrayColumnWriter(writer), ParquetType::String) => {
+            write_values(writer, rows, name, to_string)
+        }
+        _ => Err(ParquetError::General(format!(
+            "Unexpected column writer for field {:?}",
+            name
+        ))),
+    }
+}
+
+// All the columns are optional, so the missing values, and the ones that
+// can't be converted to the type of the column, are written as nulls.
+fn write_values<T: DataType>(
+    writer: &mut ColumnWriterImpl<T>,
+    rows: &[BTreeMap<String, Value>],
+    name: &str,
+    convert: impl Fn(&Value) -> Option<T::T>,
+) -> Result<(), ParquetError> {
+    let mut values = Vec::with_capacity(rows.len());
+    let mut definition_levels = Vec::with_capacity(rows.len());
+    for row in rows {
+        match row.get(name).and_then(|value| convert(value)) {
+            Some(value) => {
+                values.push(value);
+                definition_levels.push(1);
+            }
+            None => definition_levels.push(0),
+        }
+    }
+    writer.write_batch(&values, Some(&definition_levels), None)?;
+    Ok(())
+}
+
+fn to_boolean(value: &Value) -> Option<bool> {
+    match value {
+        Value::Boolean(boolean) => Some(*boolean),
+        Value::Bytes(bytes) => std::str::from_utf8(bytes).ok()?.parse().ok(),
+        _ => None,
+    }
+}
+
+fn to_double(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float(float) => Some(*float),
+        Value::Integer(integer) => Some(*integer as f64),
+        Value::Bytes(bytes) => std::str::from_utf8(bytes).ok()?.parse().ok(),
+        _ => None,
+    }
+}
+
+fn to_int64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Integer(integer) => Some(*integer),
+        Value::Bytes(bytes) => std::str::from_utf8(bytes).ok()?.parse().ok(),
+        _ => None,
+    }
+}
+
+fn to_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::Timestamp(timestamp) => Some(timestamp.timestamp_millis()),
+        Value::Bytes(bytes) => std::str::from_utf8(bytes)
+            .ok()?
+            .parse::<DateTime<Utc>>()
+            .ok()
+            .map(|timestamp| timestamp.timestamp_millis()),
+        _ => None,
+    }
+}
+
+fn to_string(value: &Value) -> Option<ByteArray> {
+    match value {
+        Value::Null => None,
+        Value::Bytes(bytes) => Some(bytes.to_vec().into()),
+        value => Some(value.to_string_lossy().into_bytes().into()),
+    }
+}
+
+fn value_size(value: &Value) -> usize {
+    match value {
+        Value::Bytes(bytes) => bytes.len(),
+        Value::Map(map) => map
+            .iter()
+            .map(|(name, value)| name.len() + value_size(value))
+            .sum(),
+        Value::Array(array) => array.iter().map(value_size).sum(),
+        Value::Boolean(_) => 1,
+        Value::Null => 0,
+        Value::Integer(_) | Value::Float(_) | Value::Timestamp(_) => 8,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::RowAccessor,
+        util::cursor::SliceableCursor,
+    };
+
+    fn event(fields: Vec<(&str, Value)>) -> LogEvent {
+        fields.into_iter().collect()
+    }
+
+    fn buffer(config: ParquetConfig) -> ParquetBuffer {
+        let settings = BatchSettings::default().bytes(100_000).events(100).size;
+        ParquetBuffer::new(settings, Compression::gzip_default(), config)
+    }
+
+    fn read(bytes: Vec<u8>) -> SerializedFileReader<SliceableCursor> {
+        SerializedFileReader::new(SliceableCursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn infers_schema() {
+        let mut buffer = buffer(ParquetConfig::default());
+        let timestamp = Utc.ymd(2020, 10, 1).and_hms(12, 0, 0);
+        let _ = buffer.push(event(vec![
+            ("message", "first".into()),
+            ("count", 1i64.into()),
+            ("ratio", 1i64.into()),
+            ("timestamp", timestamp.into()),
+        ]));
+        let _ = buffer.push(event(vec![
+            ("message", "second".into()),
+            ("ratio", 0.5.into()),
+            ("enabled", true.into()),
+        ]));
+
+        let reader = read(buffer.finish());
+        let schema = reader.metadata().file_metadata().schema_descr();
+        let columns = schema
+            .columns()
+            .iter()
+            .map(|column| (column.name().to_owned(), column.physical_type()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            vec![
+                ("count".to_owned(), PhysicalType::INT64),
+                ("enabled".to_owned(), PhysicalType::BOOLEAN),
+                ("message".to_owned(), PhysicalType::BYTE_ARRAY),
+                ("ratio".to_owned(), PhysicalType::DOUBLE),
+                ("timestamp".to_owned(), PhysicalType::INT64),
+            ]
+        );
+
+        let rows = reader.get_row_iter(None).unwrap().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get_long(0).unwrap(), 1);
+        assert!(rows[1].get_long(0).is_err());
+        assert_eq!(rows[1].get_bool(1).unwrap(), true);
+        assert_eq!(rows[0].get_string(2).unwrap(), "first");
+        assert_eq!(rows[0].get_double(3).unwrap(), 1.0);
+        assert_eq!(rows[1].get_double(3).unwrap(), 0.5);
+        assert_eq!(
+            rows[0].get_timestamp_millis(4).unwrap(),
+            timestamp.timestamp_millis() as u64
+        );
+    }
+
+    #[test]
+    fn uses_declared_schema() {
+        let schema = vec![
+            ("message".to_owned(), ParquetType::String),
+            ("status".to_owned(), ParquetType::Int64),
+        ];
+        let mut buffer = buffer(ParquetConfig {
+            schema: Some(schema.into_iter().collect()),
+            ..Default::default()
+        });
+        let _ = buffer.push(event(vec![
+            ("message", "hello".into()),
+            ("status", "404".into()),
+            ("ignored", true.into()),
+        ]));
+        let _ = buffer.push(event(vec![("status", "not a number".into())]));
+
+        let reader = read(buffer.finish());
+        assert_eq!(
+            reader
+                .metadata()
+                .file_metadata()
+                .schema_descr()
+                .num_columns(),
+            2
+        );
+        let rows = reader.get_row_iter(None).unwrap().collect::<Vec<_>>();
+        assert_eq!(rows[0].get_string(0).unwrap(), "hello");
+        assert_eq!(rows[0].get_long(1).unwrap(), 404);
+        assert!(rows[1].get_string(0).is_err());
+        assert!(rows[1].get_long(1).is_err());
+    }
+
+    #[test]
+    fn splits_row_groups() {
+        let mut buffer = buffer(ParquetConfig {
+            row_group_size: 2,
+            ..Default::default()
+        });
+        for i in 0..5i64 {
+            let _ = buffer.push(event(vec![("count", i.into())]));
+        }
+
+        let reader = read(buffer.finish());
+        assert_eq!(reader.metadata().num_row_groups(), 3);
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 5);
+    }
+
+    #[test]
+    fn limits_batch_size() {
+        let settings = BatchSettings::default().bytes(20).events(100).size;
+        let mut buffer = ParquetBuffer::new(settings, Compression::None, ParquetConfig::default());
+
+        assert_eq!(
+            buffer.push(event(vec![("message", "0123456789".into())])),
+            PushResult::Ok(false)
+        );
+        assert!(matches!(
+            buffer.push(event(vec![("message", "0123456789".into())])),
+            PushResult::Overflow(_)
+        ));
+        assert_eq!(buffer.num_items(), 1);
+    }
+}
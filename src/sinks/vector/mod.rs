@@ -0,0 +1,120 @@
+use crate::{
+    config::{DataType, GenerateConfig, SinkConfig, SinkContext, SinkDescription},
+    proto::VectorVersion,
+    sinks::util::{BatchConfig, Compression, TowerRequestConfig},
+    tls::TlsConfig,
+};
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+mod v1;
+mod v2;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct VectorSinkConfig {
+    #[serde(default)]
+    pub version: VectorVersion,
+    pub address: String,
+    pub tls: Option<TlsConfig>,
+    /// Only supported by version 2, which batches the events.
+    pub compression: Option<Compression>,
+    pub batch: Option<BatchConfig>,
+    pub request: Option<TowerRequestConfig>,
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Missing host in address field"))]
+    MissingHost,
+    #[snafu(display("Missing port in address field"))]
+    MissingPort,
+    #[snafu(display("Invalid address: {}", source))]
+    InvalidAddress { source: http::uri::InvalidUri },
+    #[snafu(display("The `{}` option is only supported by version 2", option))]
+    UnsupportedOption { option: &'static str },
+}
+
+inventory::submit! {
+    SinkDescription::new::<VectorSinkConfig>("vector")
+}
+
+impl GenerateConfig for VectorSinkConfig {
+    fn generate_config() -> toml::Value {
+        toml::from_str(r#"address = "127.0.0.1:5000""#).unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "vector")]
+impl SinkConfig for VectorSinkConfig {
+    async fn build(
+        &self,
+        cx: SinkContext,
+    ) -> crate::Result<(super::VectorSink, super::Healthcheck)> {
+        match self.version {
+            VectorVersion::V1 => {
+                let unsupported = [
+                    ("compression", self.compression.is_some()),
+                    ("batch", self.batch.is_some()),
+                    ("request", self.request.is_some()),
+                ];
+                if let Some((option, _)) = unsupported.iter().find(|(_, set)| *set) {
+                    return Err(BuildError::UnsupportedOption { option: *option }.into());
+                }
+                v1::build(self, cx)
+            }
+            VectorVersion::V2 => v2::build(self, cx),
+        }
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Any
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "vector"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<VectorSinkConfig>();
+    }
+
+    #[test]
+    fn parses_version() {
+        let config: VectorSinkConfig = toml::from_str(
+            r#"
+            address = "127.0.0.1:5000"
+            version = "2"
+            compression = "gzip"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.version, VectorVersion::V2);
+
+        let config: VectorSinkConfig = toml::from_str(r#"address = "127.0.0.1:5000""#).unwrap();
+        assert_eq!(config.version, VectorVersion::V1);
+    }
+
+    #[tokio::test]
+    async fn rejects_batching_options_for_version_1() {
+        let config: VectorSinkConfig = toml::from_str(
+            r#"
+            address = "127.0.0.1:5000"
+            compression = "gzip"
+            "#,
+        )
+        .unwrap();
+        let error = config.build(SinkContext::new_test()).await.err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "The `compression` option is only supported by version 2"
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use super::VectorSinkConfig;
+use crate::{
+    config::SinkContext,
+    event::proto,
+    internal_events::VectorEventSent,
+    sinks::{util::tcp::TcpSinkConfig, Healthcheck, VectorSink},
+    Event,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use prost::Message;
+use snafu::Snafu;
+
+pub(super) fn build(
+    config: &VectorSinkConfig,
+    cx: SinkContext,
+) -> crate::Result<(VectorSink, Healthcheck)> {
+    let sink_config = TcpSinkConfig::new(config.address.clone(), config.tls.clone());
+    sink_config.build(cx, encode_event)
+}
+
+#[derive(Debug, Snafu)]
+enum HealthcheckError {
+    #[snafu(display("Connect error: {}", source))]
+    ConnectError { source: std::io::Error },
+}
+
+fn encode_event(event: Event) -> Option<Bytes> {
+    let event = proto::EventWrapper::from(event);
+    let event_len = event.encoded_len();
+    let full_len = event_len + 4;
+
+    emit!(VectorEventSent {
+        byte_size: full_len
+    });
+
+    let mut out = BytesMut::with_capacity(full_len);
+    out.put_u32(event_len as u32);
+    event.encode(&mut out).unwrap();
+
+    Some(out.into())
+}
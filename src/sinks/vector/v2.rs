@@ -0,0 +1,267 @@
+use super::{BuildError, InvalidAddress, VectorSinkConfig};
+use crate::{
+    config::SinkContext,
+    event::proto::EventWrapper,
+    grpc,
+    internal_events::VectorEventSent,
+    proto::vector::{
+        self as proto, vector_client::VectorClient, EventBatch, HealthCheckRequest,
+        PushEventsRequest, PushEventsResponse, ServingStatus,
+    },
+    sinks::{
+        util::{
+            buffer::compression::GZIP_DEFAULT, retries::RetryLogic, sink::Response, BatchSettings,
+            Compression, EncodedLength, InFlightLimit, TowerRequestConfig, VecBuffer,
+        },
+        Healthcheck, VectorSink,
+    },
+    tls::MaybeTlsSettings,
+    Event,
+};
+use flate2::write::GzEncoder;
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt, SinkExt, TryFutureExt,
+};
+use http::{uri::Scheme, Uri};
+use lazy_static::lazy_static;
+use prost::Message;
+use snafu::{ResultExt, Snafu};
+use std::{
+    io::Write,
+    task::{Context, Poll},
+};
+use tonic::{Code, Status};
+use tower::Service;
+use tracing_futures::Instrument;
+
+lazy_static! {
+    static ref REQUEST_DEFAULTS: TowerRequestConfig = TowerRequestConfig {
+        in_flight_limit: InFlightLimit::Fixed(10),
+        timeout_secs: Some(30),
+        rate_limit_num: Some(u64::max_value()),
+        ..Default::default()
+    };
+}
+
+type Client = VectorClient<grpc::Channel>;
+
+#[derive(Debug, Snafu)]
+enum HealthcheckError {
+    #[snafu(display("Source is not serving"))]
+    NotServing,
+}
+
+pub(super) fn build(
+    config: &VectorSinkConfig,
+    cx: SinkContext,
+) -> crate::Result<(VectorSink, Healthcheck)> {
+    let tls = MaybeTlsSettings::from_config(&config.tls, false)?;
+    let origin = origin(&config.address, &tls)?;
+    let client = VectorClient::new(grpc::channel(origin, tls)?);
+
+    let batch = BatchSettings::default()
+        .bytes(4_000_000)
+        .events(1000)
+        .timeout(1)
+        .parse_config(config.batch.unwrap_or_default())?;
+    let request = config
+        .request
+        .unwrap_or_default()
+        .unwrap_with(&REQUEST_DEFAULTS);
+
+    let healthcheck = healthcheck(client.clone()).boxed();
+    let service = VectorService {
+        client,
+        compression: config.compression.unwrap_or_default(),
+    };
+
+    // Events are only acknowledged once the source accepted the batch they
+    // were sent in.
+    let sink = request
+        .batch_sink(
+            VectorRetryLogic,
+            service,
+            VecBuffer::new(batch.size),
+            batch.timeout,
+            cx.acker(),
+        )
+        .sink_map_err(|error| error!(message = "Fatal vector sink error.", %error))
+        .with(|event: Event| future::ok::<_, ()>(EventWrapper::from(event)));
+
+    Ok((VectorSink::Sink(Box::new(sink)), healthcheck))
+}
+
+/// The scheme and authority the requests are sent to.
+fn origin(address: &str, tls: &MaybeTlsSettings) -> crate::Result<Uri> {
+    let uri = address.parse::<Uri>().context(InvalidAddress)?;
+    let authority = uri.authority().ok_or(BuildError::MissingHost)?;
+    if authority.port_u16().is_none() {
+        return Err(BuildError::MissingPort.into());
+    }
+
+    let scheme = if tls.is_tls() {
+        Scheme::HTTPS
+    } else {
+        Scheme::HTTP
+    };
+    Ok(Uri::builder()
+        .scheme(scheme)
+        .authority(authority.clone())
+        .path_and_query("/")
+        .build()?)
+}
+
+async fn healthcheck(mut client: Client) -> crate::Result<()> {
+    let response = client.health_check(HealthCheckRequest {}).await?;
+
+    match response.into_inner().status() {
+        ServingStatus::Serving => Ok(()),
+        ServingStatus::NotServing => Err(HealthcheckError::NotServing.into()),
+    }
+}
+
+#[derive(Clone, Debug)]
+struct VectorService {
+    client: Client,
+    compression: Compression,
+}
+
+impl Service<Vec<EventWrapper>> for VectorService {
+    type Response = PushEventsResponse;
+    type Error = Status;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, events: Vec<EventWrapper>) -> Self::Future {
+        debug!(
+            message = "Sending events.",
+            events = %events.len(),
+        );
+
+        let sizes: Vec<usize> = events.iter().map(|event| event.encoded_len()).collect();
+        let request = encode_request(events, self.compression);
+
+        let mut client = self.client.clone();
+        Box::pin(
+            async move { client.push_events(request).await }
+                .map_ok(|response| {
+                    for byte_size in sizes {
+                        emit!(VectorEventSent { byte_size });
+                    }
+                    response.into_inner()
+                })
+                .instrument(info_span!("request")),
+        )
+    }
+}
+
+fn encode_request(events: Vec<EventWrapper>, compression: Compression) -> PushEventsRequest {
+    let batch = EventBatch { events };
+    let mut encoded = Vec::with_capacity(batch.encoded_len());
+    batch
+        .encode(&mut encoded)
+        .expect("Vec has the capacity of the batch.");
+
+    match compression {
+        Compression::None => PushEventsRequest {
+            compression: proto::Compression::None as i32,
+            events: encoded,
+        },
+        Compression::Gzip(level) => {
+            let level = level.unwrap_or(GZIP_DEFAULT);
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::new(level as u32));
+            let events = encoder
+                .write_all(&encoded)
+                .and_then(|_| encoder.finish())
+                .expect("Writing to a Vec can't fail.");
+            PushEventsRequest {
+                compression: proto::Compression::Gzip as i32,
+                events,
+            }
+        }
+    }
+}
+
+impl EncodedLength for EventWrapper {
+    fn encoded_length(&self) -> usize {
+        self.encoded_len()
+    }
+}
+
+impl Response for PushEventsResponse {}
+
+#[derive(Debug, Clone)]
+struct VectorRetryLogic;
+
+impl RetryLogic for VectorRetryLogic {
+    type Error = Status;
+    type Response = PushEventsResponse;
+
+    fn is_retriable_error(&self, error: &Self::Error) -> bool {
+        // Transport errors, such as refused connections, are reported as
+        // `Unknown`.
+        matches!(
+            error.code(),
+            Code::Unavailable
+                | Code::ResourceExhausted
+                | Code::DeadlineExceeded
+                | Code::Aborted
+                | Code::Internal
+                | Code::Unknown
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn decode_batch(request: PushEventsRequest) -> Vec<Event> {
+        let events = match request.compression() {
+            proto::Compression::None => request.events,
+            proto::Compression::Gzip => {
+                let mut events = Vec::new();
+                GzDecoder::new(&request.events[..])
+                    .read_to_end(&mut events)
+                    .unwrap();
+                events
+            }
+        };
+        EventBatch::decode(&events[..])
+            .unwrap()
+            .events
+            .into_iter()
+            .map(Event::from)
+            .collect()
+    }
+
+    #[test]
+    fn encodes_compressed_batches() {
+        let events = vec![Event::from("first"), Event::from("second")];
+        let wrappers = || events.iter().cloned().map(EventWrapper::from).collect();
+
+        let request = encode_request(wrappers(), Compression::None);
+        assert_eq!(request.compression(), proto::Compression::None);
+        assert_eq!(decode_batch(request), events);
+
+        let request = encode_request(wrappers(), Compression::gzip_default());
+        assert_eq!(request.compression(), proto::Compression::Gzip);
+        assert_eq!(decode_batch(request), events);
+    }
+
+    #[test]
+    fn rejects_addresses_without_port() {
+        let tls = MaybeTlsSettings::Raw(());
+        assert!(origin("localhost", &tls).is_err());
+        assert_eq!(
+            origin("localhost:6000", &tls).unwrap(),
+            "http://localhost:6000/".parse::<Uri>().unwrap()
+        );
+    }
+}
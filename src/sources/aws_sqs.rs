@@ -0,0 +1,436 @@
+use crate::{
+    config::{DataType, GenerateConfig, GlobalOptions, SourceConfig, SourceDescription},
+    event::Event,
+    internal_events::{
+        AwsSqsEventReceived, AwsSqsMessageDeleteFailed, AwsSqsMessageReceiveFailed,
+        AwsSqsMessageReceiveSucceeded,
+    },
+    rusoto::{self, RegionOrEndpoint},
+    shutdown::ShutdownSignal,
+    sources::util::message::{decode_message, fresh_backoff},
+    Pipeline,
+};
+use chrono::{TimeZone, Utc};
+use futures::{compat::Future01CompatExt, FutureExt, TryFutureExt};
+use futures01::Sink;
+use rusoto_core::RusotoError;
+use rusoto_sqs::{
+    ChangeMessageVisibilityBatchRequest, ChangeMessageVisibilityBatchRequestEntry,
+    DeleteMessageBatchRequest, DeleteMessageBatchRequestEntry, Message, ReceiveMessageError,
+    ReceiveMessageRequest, Sqs, SqsClient,
+};
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::convert::TryInto;
+use tokio::time::delay_for;
+
+/// SQS caps long polling at 20 seconds.
+const MAX_POLL_SECS: u32 = 20;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("poll_secs must be at most {}, got {}", MAX_POLL_SECS, poll_secs))]
+    InvalidPollSecs { poll_secs: u32 },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AwsSqsConfig {
+    #[serde(flatten)]
+    pub region: RegionOrEndpoint,
+    pub queue_url: String,
+    /// How long a receive waits for messages to arrive on an empty queue.
+    #[serde(default = "default_poll_secs")]
+    pub poll_secs: u32,
+    /// How long received messages are hidden from other consumers.
+    #[serde(default = "default_visibility_timeout_secs")]
+    pub visibility_timeout_secs: u32,
+    #[serde(default = "default_true")]
+    pub delete_message: bool,
+    pub assume_role: Option<String>,
+}
+
+const fn default_poll_secs() -> u32 {
+    15
+}
+
+const fn default_visibility_timeout_secs() -> u32 {
+    300
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+inventory::submit! {
+    SourceDescription::new::<AwsSqsConfig>("aws_sqs")
+}
+
+impl GenerateConfig for AwsSqsConfig {
+    fn generate_config() -> toml::Value {
+        toml::from_str(
+            r#"
+            region = "us-east-1"
+            queue_url = "https://sqs.us-east-1.amazonaws.com/123456789012/MyQueue"
+            "#,
+        )
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "aws_sqs")]
+impl SourceConfig for AwsSqsConfig {
+    async fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<super::Source> {
+        if self.poll_secs > MAX_POLL_SECS {
+            return Err(BuildError::InvalidPollSecs {
+                poll_secs: self.poll_secs,
+            }
+            .into());
+        }
+
+        let source = SqsSource {
+            client: self.create_client()?,
+            queue_url: self.queue_url.clone(),
+            poll_secs: self.poll_secs.into(),
+            visibility_timeout_secs: self.visibility_timeout_secs.into(),
+            delete_message: self.delete_message,
+        };
+
+        Ok(Box::new(source.run(shutdown, out).boxed().compat()))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "aws_sqs"
+    }
+}
+
+impl AwsSqsConfig {
+    fn create_client(&self) -> crate::Result<SqsClient> {
+        let region = (&self.region).try_into()?;
+        let client = rusoto::client()?;
+        let creds = rusoto::AwsCredentialsProvider::new(&region, self.assume_role.clone())?;
+        Ok(SqsClient::new_with(client, creds, region))
+    }
+}
+
+struct SqsSource {
+    client: SqsClient,
+    queue_url: String,
+    poll_secs: i64,
+    visibility_timeout_secs: i64,
+    delete_message: bool,
+}
+
+impl SqsSource {
+    async fn run(self, mut shutdown: ShutdownSignal, out: Pipeline) -> Result<(), ()> {
+        let mut out = out.sink_map_err(|error| error!(message = "Error sending event.", %error));
+
+        let mut backoff = fresh_backoff();
+        loop {
+            let messages = tokio::select! {
+                messages = self.receive_messages() => messages,
+                _ = &mut shutdown => break,
+            };
+            let messages = match messages {
+                Ok(messages) => {
+                    backoff = fresh_backoff();
+                    messages
+                }
+                Err(error) => {
+                    emit!(AwsSqsMessageReceiveFailed { error: &error });
+                    // Don't hammer SQS while it's unreachable or rejecting
+                    // our credentials.
+                    let delay = backoff.next().expect("Backoff never ends.");
+                    tokio::select! {
+                        _ = delay_for(delay) => continue,
+                        _ = &mut shutdown => break,
+                    }
+                }
+            };
+            emit!(AwsSqsMessageReceiveSucceeded {
+                count: messages.len()
+            });
+
+            let mut sent = 0;
+            for message in &messages {
+                match out.send(message_to_event(message)).compat().await {
+                    Ok(sink) => out = sink,
+                    Err(()) => {
+                        // Make the messages that weren't processed visible
+                        // again instead of waiting out the visibility timeout.
+                        self.release_messages(&messages[sent..]).await;
+                        return Err(());
+                    }
+                }
+                sent += 1;
+            }
+
+            if self.delete_message {
+                self.delete_messages(&messages).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn receive_messages(&self) -> Result<Vec<Message>, RusotoError<ReceiveMessageError>> {
+        self.client
+            .receive_message(ReceiveMessageRequest {
+                queue_url: self.queue_url.clone(),
+                max_number_of_messages: Some(10),
+                visibility_timeout: Some(self.visibility_timeout_secs),
+                wait_time_seconds: Some(self.poll_secs),
+                attribute_names: Some(vec!["SentTimestamp".to_owned()]),
+                ..Default::default()
+            })
+            .map_ok(|res| res.messages.unwrap_or_default())
+            .await
+    }
+
+    async fn delete_messages(&self, messages: &[Message]) {
+        let entries: Vec<_> = messages
+            .iter()
+            .enumerate()
+            .filter_map(|(id, message)| {
+                message.receipt_handle.clone().map(|receipt_handle| {
+                    DeleteMessageBatchRequestEntry {
+                        id: id.to_string(),
+                        receipt_handle,
+                    }
+                })
+            })
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
+
+        let result = self
+            .client
+            .delete_message_batch(DeleteMessageBatchRequest {
+                queue_url: self.queue_url.clone(),
+                entries,
+            })
+            .await;
+        match result {
+            Ok(result) if !result.failed.is_empty() => {
+                emit!(AwsSqsMessageDeleteFailed {
+                    error: &format!("{} of {} messages", result.failed.len(), messages.len())
+                });
+            }
+            Ok(_) => (),
+            Err(error) => {
+                emit!(AwsSqsMessageDeleteFailed {
+                    error: &error.to_string()
+                });
+            }
+        }
+    }
+
+    async fn release_messages(&self, messages: &[Message]) {
+        let entries: Vec<_> = messages
+            .iter()
+            .enumerate()
+            .filter_map(|(id, message)| {
+                message.receipt_handle.clone().map(|receipt_handle| {
+                    ChangeMessageVisibilityBatchRequestEntry {
+                        id: id.to_string(),
+                        receipt_handle,
+                        visibility_timeout: Some(0),
+                    }
+                })
+            })
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
+
+        if let Err(error) = self
+            .client
+            .change_message_visibility_batch(ChangeMessageVisibilityBatchRequest {
+                queue_url: self.queue_url.clone(),
+                entries,
+            })
+            .await
+        {
+            warn!(message = "Failed to release unprocessed SQS messages.", %error);
+        }
+    }
+}
+
+fn message_to_event(message: &Message) -> Event {
+    let body = message.body.as_deref().unwrap_or_default();
+    emit!(AwsSqsEventReceived {
+        byte_size: body.len()
+    });
+
+    let timestamp = message
+        .attributes
+        .as_ref()
+        .and_then(|attributes| attributes.get("SentTimestamp"))
+        .and_then(|timestamp| timestamp.parse::<i64>().ok())
+        .and_then(|timestamp| Utc.timestamp_millis_opt(timestamp).latest());
+    let mut event = decode_message("aws_sqs", body.as_bytes(), timestamp);
+    if let Some(message_id) = &message.message_id {
+        event.as_mut_log().insert("message_id", message_id.clone());
+    }
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::log_schema;
+    use std::collections::HashMap;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<AwsSqsConfig>();
+    }
+
+    #[tokio::test]
+    async fn rejects_long_poll_secs() {
+        let config: AwsSqsConfig = toml::from_str(
+            r#"
+            region = "us-east-1"
+            queue_url = "https://sqs.us-east-1.amazonaws.com/123456789012/MyQueue"
+            poll_secs = 30
+            "#,
+        )
+        .unwrap();
+        let result = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                Pipeline::new_test().0,
+            )
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn sqs_message_to_event() {
+        let mut attributes = HashMap::new();
+        attributes.insert("SentTimestamp".to_owned(), "1600000000000".to_owned());
+        let event = message_to_event(&Message {
+            body: Some("hello".to_owned()),
+            message_id: Some("id-1".to_owned()),
+            attributes: Some(attributes),
+            ..Default::default()
+        });
+        let log = event.as_log();
+
+        assert_eq!(log[log_schema().message_key()], "hello".into());
+        assert_eq!(log["message_id"], "id-1".into());
+        assert_eq!(
+            log[log_schema().timestamp_key()],
+            Utc.timestamp(1_600_000_000, 0).into()
+        );
+        assert_eq!(log[log_schema().source_type_key()], "aws_sqs".into());
+    }
+}
+
+#[cfg(feature = "aws-sqs-integration-tests")]
+#[cfg(test)]
+mod integration_tests {
+    use super::*;
+    use crate::{
+        config::log_schema,
+        test_util::{collect_n, random_lines, trace_init},
+    };
+    use rusoto_core::Region;
+    use rusoto_sqs::{CreateQueueRequest, GetQueueAttributesRequest, SendMessageRequest};
+
+    fn sqs_client() -> SqsClient {
+        SqsClient::new(Region::Custom {
+            name: "us-east-1".to_owned(),
+            endpoint: "http://localhost:4566".to_owned(),
+        })
+    }
+
+    #[tokio::test]
+    async fn sqs_receive_and_delete() {
+        trace_init();
+
+        let client = sqs_client();
+        let queue_url = client
+            .create_queue(CreateQueueRequest {
+                queue_name: uuid::Uuid::new_v4().to_string(),
+                ..Default::default()
+            })
+            .await
+            .expect("Could not create queue")
+            .queue_url
+            .expect("no queue url");
+
+        let lines: Vec<String> = random_lines(100).take(10).collect();
+        for line in &lines {
+            client
+                .send_message(SendMessageRequest {
+                    queue_url: queue_url.clone(),
+                    message_body: line.clone(),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        let config = AwsSqsConfig {
+            region: RegionOrEndpoint::with_endpoint("http://localhost:4566".to_owned()),
+            queue_url: queue_url.clone(),
+            poll_secs: 1,
+            visibility_timeout_secs: default_visibility_timeout_secs(),
+            delete_message: true,
+            assume_role: None,
+        };
+        let (tx, rx) = Pipeline::new_test();
+        let source = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .await
+            .unwrap();
+        tokio::spawn(source.compat());
+
+        let events = collect_n(rx, lines.len()).await.unwrap();
+        let mut messages: Vec<String> = events
+            .iter()
+            .map(|event| event.as_log()[log_schema().message_key()].to_string_lossy())
+            .collect();
+        messages.sort();
+        let mut expected = lines.clone();
+        expected.sort();
+        assert_eq!(messages, expected);
+
+        // Give the source a moment to delete the batch.
+        tokio::time::delay_for(std::time::Duration::from_secs(2)).await;
+        let attributes = client
+            .get_queue_attributes(GetQueueAttributesRequest {
+                queue_url,
+                attribute_names: Some(vec![
+                    "ApproximateNumberOfMessages".to_owned(),
+                    "ApproximateNumberOfMessagesNotVisible".to_owned(),
+                ]),
+            })
+            .await
+            .unwrap()
+            .attributes
+            .unwrap();
+        assert_eq!(attributes["ApproximateNumberOfMessages"], "0");
+        assert_eq!(attributes["ApproximateNumberOfMessagesNotVisible"], "0");
+    }
+}
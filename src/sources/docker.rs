@@ -2,18 +2,21 @@ use super::util::MultilineConfig;
 use crate::{
     config::{log_schema, DataType, GlobalOptions, SourceConfig, SourceDescription},
     event::merge_state::LogEventMergeState,
-    event::{self, Event, LogEvent, Value},
+    event::{self, Event, LogEvent, Metric, MetricKind, MetricValue, Value},
     internal_events::{
-        DockerCommunicationError, DockerContainerEventReceived, DockerContainerMetadataFetchFailed,
+        DockerCommunicationError, DockerContainerEventReceived, DockerContainerExcluded,
+        DockerContainerLabelConfigInvalid, DockerContainerMetadataFetchFailed,
         DockerContainerUnwatch, DockerContainerWatch, DockerEventReceived,
-        DockerLoggingDriverUnsupported, DockerTimestampParseFailed,
+        DockerLoggingDriverUnsupported, DockerStatsReceived, DockerTimestampParseFailed,
     },
     line_agg::{self, LineAgg},
     shutdown::ShutdownSignal,
     Pipeline,
 };
 use bollard::{
-    container::{InspectContainerOptions, ListContainersOptions, LogOutput, LogsOptions},
+    container::{
+        InspectContainerOptions, ListContainersOptions, LogOutput, LogsOptions, Stats, StatsOptions,
+    },
     errors::Error as DockerError,
     service::{ContainerInspectResponse, SystemEventsResponse},
     system::EventsOptions,
@@ -29,7 +32,11 @@ use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
-use std::{collections::HashMap, convert::TryFrom, env};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    env,
+};
 
 use tokio::sync::mpsc;
 
@@ -41,6 +48,12 @@ const NAME: &str = "container_name";
 const STREAM: &str = "stream";
 const CONTAINER: &str = "container_id";
 
+/// Containers with this label set to `true` are ignored.
+const EXCLUDE_LABEL: &str = "vector.exclude";
+/// Labels with this prefix override the `multiline` settings for the
+/// container, e.g. `vector.multiline.start_pattern`.
+const MULTILINE_LABEL_PREFIX: &str = "vector.multiline.";
+
 lazy_static! {
     static ref STDERR: Bytes = "stderr".into();
     static ref STDOUT: Bytes = "stdout".into();
@@ -56,6 +69,24 @@ pub struct DockerConfig {
     auto_partial_merge: bool,
     multiline: Option<MultilineConfig>,
     retry_backoff_secs: u64,
+    stats: StatsConfig,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields, default)]
+pub struct StatsConfig {
+    /// Collect the container resource usage metrics.
+    enabled: bool,
+    namespace: String,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            namespace: "docker".to_owned(),
+        }
+    }
 }
 
 impl Default for DockerConfig {
@@ -68,6 +99,7 @@ impl Default for DockerConfig {
             auto_partial_merge: true,
             multiline: None,
             retry_backoff_secs: 2,
+            stats: StatsConfig::default(),
         }
     }
 }
@@ -154,7 +186,11 @@ impl SourceConfig for DockerConfig {
     }
 
     fn output_type(&self) -> DataType {
-        DataType::Log
+        if self.stats.enabled {
+            DataType::Any
+        } else {
+            DataType::Log
+        }
     }
 
     fn source_type(&self) -> &'static str {
@@ -372,6 +408,7 @@ impl DockerSource {
                 }
 
                 let id = ContainerId::new(id);
+                self.esb.start_stats(id.clone());
                 self.containers.insert(id.clone(), self.esb.start(id, None));
             });
 
@@ -434,6 +471,12 @@ impl DockerSource {
                                 }
                                 "start" | "unpause" => {
                                     if let Some(state) = self.containers.get_mut(&id) {
+                                        // The stats stream ends when the
+                                        // container dies, but lasts through
+                                        // the pauses.
+                                        if action == "start" {
+                                            self.esb.start_stats(id.clone());
+                                        }
                                         state.running();
                                         self.esb.restart(state);
                                     } else {
@@ -449,6 +492,7 @@ impl DockerSource {
                                         );
 
                                         if include_name && self_check {
+                                            self.esb.start_stats(id.clone());
                                             self.containers.insert(id.clone(), self.esb.start(id, None));
                                         }
                                     }
@@ -519,7 +563,20 @@ impl EventStreamBuilder {
                 .await
             {
                 Ok(details) => match ContainerMetadata::from_details(details) {
-                    Ok(metadata) => {
+                    Ok(mut metadata) => {
+                        if metadata.excluded {
+                            emit!(DockerContainerExcluded {
+                                container_id: id.as_str()
+                            });
+                            return;
+                        }
+                        match metadata.line_agg_config(this.core.config.multiline.as_ref()) {
+                            Ok(line_agg_config) => metadata.line_agg_config = line_agg_config,
+                            Err(error) => emit!(DockerContainerLabelConfigInvalid {
+                                error,
+                                container_id: id.as_str()
+                            }),
+                        }
                         let info = ContainerLogInfo::new(id, metadata, this.core.now_timestamp);
                         this.run_event_stream(info).await;
                         return;
@@ -543,6 +600,80 @@ impl EventStreamBuilder {
         ContainerState::new_running()
     }
 
+    /// Spawn a task to stream the container stats until the container dies,
+    /// or until shutdown, if stats are enabled.
+    fn start_stats(&self, id: ContainerId) {
+        if !self.core.config.stats.enabled {
+            return;
+        }
+
+        let this = self.clone();
+        tokio::spawn(async move {
+            let details = match this
+                .core
+                .docker
+                .inspect_container(id.as_str(), None::<InspectContainerOptions>)
+                .await
+            {
+                Ok(details) => details,
+                Err(error) => {
+                    emit!(DockerContainerMetadataFetchFailed {
+                        error,
+                        container_id: id.as_str()
+                    });
+                    return;
+                }
+            };
+            match ContainerMetadata::from_details(details) {
+                Ok(metadata) if metadata.excluded => {}
+                Ok(metadata) => this.run_stats_stream(id, metadata).await,
+                Err(error) => emit!(DockerTimestampParseFailed {
+                    error,
+                    container_id: id.as_str()
+                }),
+            }
+        });
+    }
+
+    async fn run_stats_stream(&self, id: ContainerId, metadata: ContainerMetadata) {
+        let namespace = &self.core.config.stats.namespace;
+        let tags = metadata.tags(&id);
+
+        let stream = self
+            .core
+            .docker
+            .stats(id.as_str(), Some(StatsOptions { stream: true }));
+
+        let result = stream
+            .map(|value| match value {
+                Ok(stats) => Ok(stats),
+                Err(error) => {
+                    emit!(DockerCommunicationError {
+                        error,
+                        container_id: Some(id.as_str())
+                    });
+                    Err(())
+                }
+            })
+            .take_while(|v| future::ready(v.is_ok()))
+            .flat_map(|stats| {
+                let metrics = stats_to_metrics(&stats.unwrap(), namespace, &tags);
+                emit!(DockerStatsReceived {
+                    count: metrics.len(),
+                    container_id: id.as_str()
+                });
+                futures::stream::iter(metrics.into_iter().map(Event::Metric))
+            })
+            .take_until(self.shutdown.clone())
+            .map(Ok)
+            .forward(self.out.clone().sink_compat().sink_map_err(|_| ()))
+            .await;
+
+        if result.is_err() {
+            error!(message = "Unable to send container stats.", container_id = %id.as_str());
+        }
+    }
+
     /// If info is present, restarts event stream which will run until shutdown.
     fn restart(&self, container: &mut ContainerState) {
         if let Some(info) = container.take_info() {
@@ -570,6 +701,13 @@ impl EventStreamBuilder {
         // Create event streamer
         let mut partial_event_merge_state = None;
 
+        // The container labels take precedence over the source config.
+        let line_agg_config = info
+            .metadata
+            .line_agg_config
+            .clone()
+            .or_else(|| self.core.line_agg_config.clone());
+
         let events_stream = stream
             .map(|value| {
                 match value {
@@ -605,10 +743,10 @@ impl EventStreamBuilder {
             .take_until(self.shutdown.clone());
 
         let events_stream: Box<dyn Stream<Item = Event> + Unpin + Send> =
-            if let Some(ref line_agg_config) = self.core.line_agg_config {
+            if let Some(line_agg_config) = line_agg_config {
                 Box::new(line_agg_adapter(
                     events_stream,
-                    line_agg::Logic::new(line_agg_config.clone()),
+                    line_agg::Logic::new(line_agg_config),
                 ))
             } else {
                 Box::new(events_stream)
@@ -918,6 +1056,12 @@ struct ContainerMetadata {
     image: Value,
     /// created_at
     created_at: DateTime<Utc>,
+    /// True if the container has the `vector.exclude` label set.
+    excluded: bool,
+    /// `vector.multiline.*` labels, with the prefix removed.
+    multiline_labels: HashMap<String, String>,
+    /// Aggregation config from the container labels, if any.
+    line_agg_config: Option<line_agg::Config>,
 }
 
 impl ContainerMetadata {
@@ -938,13 +1082,253 @@ impl ContainerMetadata {
             })
             .unwrap_or_default();
 
+        let excluded = config
+            .labels
+            .as_ref()
+            .and_then(|map| map.get(EXCLUDE_LABEL))
+            .map_or(false, |value| value == "true");
+
+        let multiline_labels = config
+            .labels
+            .as_ref()
+            .map(|map| {
+                map.iter()
+                    .filter_map(|(key, value)| {
+                        key.strip_prefix(MULTILINE_LABEL_PREFIX)
+                            .map(|key| (key.to_owned(), value.to_owned()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Ok(ContainerMetadata {
             labels,
             name: name.as_str().trim_start_matches('/').to_owned().into(),
             image: config.image.unwrap().into(),
             created_at: DateTime::parse_from_rfc3339(created.as_str())?.with_timezone(&Utc),
+            excluded,
+            multiline_labels,
+            line_agg_config: None,
         })
     }
+
+    /// Builds the aggregation config from the `vector.multiline.*` labels,
+    /// using `default` for the settings that aren't overridden. Returns `None`
+    /// if the container has no such labels.
+    fn line_agg_config(
+        &self,
+        default: Option<&MultilineConfig>,
+    ) -> crate::Result<Option<line_agg::Config>> {
+        if self.multiline_labels.is_empty() {
+            return Ok(None);
+        }
+        let config = multiline_config_from_labels(&self.multiline_labels, default)?;
+        Ok(Some(line_agg::Config::try_from(&config)?))
+    }
+
+    /// Tags for the container metrics.
+    fn tags(&self, id: &ContainerId) -> BTreeMap<String, String> {
+        let mut tags = BTreeMap::new();
+        tags.insert(CONTAINER.to_owned(), id.as_str().to_owned());
+        tags.insert(NAME.to_owned(), self.name.to_string_lossy());
+        tags.insert(IMAGE.to_owned(), self.image.to_string_lossy());
+        for (key, value) in self.labels.iter() {
+            tags.insert(key.clone(), value.to_string_lossy());
+        }
+        tags
+    }
+}
+
+fn multiline_config_from_labels(
+    labels: &HashMap<String, String>,
+    default: Option<&MultilineConfig>,
+) -> crate::Result<MultilineConfig> {
+    let required = |key: &str, default: Option<String>| -> crate::Result<String> {
+        labels
+            .get(key)
+            .cloned()
+            .or(default)
+            .ok_or_else(|| format!("missing {}{} label", MULTILINE_LABEL_PREFIX, key).into())
+    };
+
+    let start_pattern = required(
+        "start_pattern",
+        default.map(|default| default.start_pattern.clone()),
+    )?;
+    let condition_pattern = required(
+        "condition_pattern",
+        default.map(|default| default.condition_pattern.clone()),
+    )?;
+    let mode = match labels.get("mode") {
+        Some(mode) => serde_json::from_value(serde_json::Value::String(mode.clone()))
+            .map_err(|_| format!("invalid multiline mode {:?}", mode))?,
+        None => default
+            .map(|default| default.mode.clone())
+            .ok_or_else(|| format!("missing {}mode label", MULTILINE_LABEL_PREFIX))?,
+    };
+    let timeout_ms = match labels.get("timeout_ms") {
+        Some(timeout_ms) => timeout_ms
+            .parse()
+            .map_err(|_| format!("invalid multiline timeout_ms {:?}", timeout_ms))?,
+        None => default.map_or(1000, |default| default.timeout_ms),
+    };
+
+    Ok(MultilineConfig {
+        start_pattern,
+        condition_pattern,
+        mode,
+        timeout_ms,
+    })
+}
+
+/// Converts the container stats to CPU, memory, network and block IO
+/// metrics.
+fn stats_to_metrics(
+    stats: &Stats,
+    namespace: &str,
+    tags: &BTreeMap<String, String>,
+) -> Vec<Metric> {
+    let timestamp = stats.read;
+    let metric = |name: &str, value: MetricValue, tags: BTreeMap<String, String>| Metric {
+        name: name.into(),
+        namespace: Some(namespace.to_owned()),
+        timestamp: Some(timestamp),
+        tags: Some(tags),
+        kind: MetricKind::Absolute,
+        value,
+    };
+    let counter = |name: &str, value: u64| {
+        metric(
+            name,
+            MetricValue::Counter {
+                value: value as f64,
+            },
+            tags.clone(),
+        )
+    };
+    let gauge = |name: &str, value: u64| {
+        metric(
+            name,
+            MetricValue::Gauge {
+                value: value as f64,
+            },
+            tags.clone(),
+        )
+    };
+
+    let mut metrics = Vec::new();
+
+    // CPU times are reported in nanoseconds.
+    let cpu = &stats.cpu_stats;
+    let seconds = |nanos: u64| nanos as f64 / 1_000_000_000.0;
+    for (name, nanos) in &[
+        ("cpu_usage_seconds_total", cpu.cpu_usage.total_usage),
+        ("cpu_user_seconds_total", cpu.cpu_usage.usage_in_usermode),
+        (
+            "cpu_system_seconds_total",
+            cpu.cpu_usage.usage_in_kernelmode,
+        ),
+        (
+            "cpu_throttled_seconds_total",
+            cpu.throttling_data.throttled_time,
+        ),
+    ] {
+        metrics.push(metric(
+            name,
+            MetricValue::Counter {
+                value: seconds(*nanos),
+            },
+            tags.clone(),
+        ));
+    }
+    metrics.push(counter(
+        "cpu_throttled_periods_total",
+        cpu.throttling_data.throttled_periods,
+    ));
+
+    // The usage relative to a single CPU since the previous sample, the same
+    // as `docker stats` reports it.
+    let precpu = &stats.precpu_stats;
+    if let (Some(system), Some(presystem)) = (cpu.system_cpu_usage, precpu.system_cpu_usage) {
+        let cpu_delta = cpu
+            .cpu_usage
+            .total_usage
+            .saturating_sub(precpu.cpu_usage.total_usage);
+        let system_delta = system.saturating_sub(presystem);
+        let online_cpus = cpu.online_cpus.unwrap_or(1);
+        if precpu.cpu_usage.total_usage > 0 && system_delta > 0 {
+            let value = cpu_delta as f64 / system_delta as f64 * online_cpus as f64 * 100.0;
+            metrics.push(metric(
+                "cpu_usage_percent",
+                MetricValue::Gauge { value },
+                tags.clone(),
+            ));
+        }
+    }
+
+    let memory = &stats.memory_stats;
+    for (name, value) in &[
+        ("memory_usage_bytes", memory.usage),
+        ("memory_max_usage_bytes", memory.max_usage),
+        ("memory_limit_bytes", memory.limit),
+    ] {
+        if let Some(value) = value {
+            metrics.push(gauge(name, *value));
+        }
+    }
+    if let Some(failcnt) = memory.failcnt {
+        metrics.push(counter("memory_failures_total", failcnt));
+    }
+
+    for (interface, network) in stats.networks.iter().flatten() {
+        let mut tags = tags.clone();
+        tags.insert("interface".to_owned(), interface.clone());
+        for (name, value) in &[
+            ("network_receive_bytes_total", network.rx_bytes),
+            ("network_receive_packets_total", network.rx_packets),
+            ("network_receive_errors_total", network.rx_errors),
+            ("network_receive_dropped_total", network.rx_dropped),
+            ("network_transmit_bytes_total", network.tx_bytes),
+            ("network_transmit_packets_total", network.tx_packets),
+            ("network_transmit_errors_total", network.tx_errors),
+            ("network_transmit_dropped_total", network.tx_dropped),
+        ] {
+            metrics.push(metric(
+                name,
+                MetricValue::Counter {
+                    value: *value as f64,
+                },
+                tags.clone(),
+            ));
+        }
+    }
+
+    let blkio = &stats.blkio_stats;
+    for (name, entries) in &[
+        (
+            "blkio_service_bytes_total",
+            &blkio.io_service_bytes_recursive,
+        ),
+        ("blkio_serviced_total", &blkio.io_serviced_recursive),
+    ] {
+        for entry in entries.iter().flatten() {
+            let mut tags = tags.clone();
+            tags.insert(
+                "device".to_owned(),
+                format!("{}:{}", entry.major, entry.minor),
+            );
+            tags.insert("op".to_owned(), entry.op.to_lowercase());
+            metrics.push(metric(
+                name,
+                MetricValue::Counter {
+                    value: entry.value as f64,
+                },
+                tags,
+            ));
+        }
+    }
+
+    metrics
 }
 
 fn docker() -> Result<Docker, DockerError> {
@@ -993,6 +1377,161 @@ mod tests {
     fn generate_config() {
         crate::test_util::test_generate_config::<DockerConfig>();
     }
+
+    fn labels(labels: &[(&str, &str)]) -> HashMap<String, String> {
+        labels
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn multiline_config_from_labels_only() {
+        let config = multiline_config_from_labels(
+            &labels(&[
+                ("start_pattern", "^[^\\s]"),
+                ("condition_pattern", "^[\\s]+"),
+                ("mode", "continue_through"),
+            ]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            MultilineConfig {
+                start_pattern: "^[^\\s]".to_owned(),
+                condition_pattern: "^[\\s]+".to_owned(),
+                mode: line_agg::Mode::ContinueThrough,
+                timeout_ms: 1000,
+            }
+        );
+    }
+
+    #[test]
+    fn multiline_config_from_labels_overrides_default() {
+        let default = MultilineConfig {
+            start_pattern: "^[^\\s]".to_owned(),
+            condition_pattern: "^[\\s]+".to_owned(),
+            mode: line_agg::Mode::ContinueThrough,
+            timeout_ms: 500,
+        };
+        let config = multiline_config_from_labels(
+            &labels(&[("start_pattern", "^Traceback"), ("timeout_ms", "2000")]),
+            Some(&default),
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            MultilineConfig {
+                start_pattern: "^Traceback".to_owned(),
+                timeout_ms: 2000,
+                ..default
+            }
+        );
+    }
+
+    #[test]
+    fn multiline_config_from_labels_invalid() {
+        assert!(multiline_config_from_labels(&labels(&[("start_pattern", "^a")]), None).is_err());
+        assert!(multiline_config_from_labels(
+            &labels(&[
+                ("start_pattern", "^a"),
+                ("condition_pattern", "^b"),
+                ("mode", "sideways"),
+            ]),
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn stats_to_metrics_converts() {
+        let stats: Stats = serde_json::from_str(
+            r#"{
+                "read": "2020-10-15T11:01:46.499555308Z",
+                "preread": "2020-10-15T11:01:45.498521306Z",
+                "num_procs": 0,
+                "pids_stats": {"current": 3},
+                "networks": {
+                    "eth0": {
+                        "rx_bytes": 1000, "rx_packets": 10, "rx_errors": 0, "rx_dropped": 0,
+                        "tx_bytes": 2000, "tx_packets": 20, "tx_errors": 0, "tx_dropped": 0
+                    }
+                },
+                "memory_stats": {"usage": 6537216, "max_usage": 6651904, "limit": 67108864, "stats": {}},
+                "blkio_stats": {
+                    "io_service_bytes_recursive": [
+                        {"major": 8, "minor": 0, "op": "Read", "value": 4096},
+                        {"major": 8, "minor": 0, "op": "Write", "value": 8192}
+                    ]
+                },
+                "cpu_stats": {
+                    "cpu_usage": {
+                        "total_usage": 3000000000,
+                        "usage_in_kernelmode": 1000000000,
+                        "usage_in_usermode": 2000000000
+                    },
+                    "system_cpu_usage": 20000000000,
+                    "online_cpus": 2,
+                    "throttling_data": {"periods": 0, "throttled_periods": 0, "throttled_time": 0}
+                },
+                "precpu_stats": {
+                    "cpu_usage": {
+                        "total_usage": 2000000000,
+                        "usage_in_kernelmode": 500000000,
+                        "usage_in_usermode": 1500000000
+                    },
+                    "system_cpu_usage": 10000000000,
+                    "online_cpus": 2,
+                    "throttling_data": {"periods": 0, "throttled_periods": 0, "throttled_time": 0}
+                },
+                "storage_stats": {},
+                "name": "/vector_test",
+                "id": "b5ab3e6f3c30"
+            }"#,
+        )
+        .unwrap();
+        let mut tags = BTreeMap::new();
+        tags.insert(NAME.to_owned(), "vector_test".to_owned());
+
+        let metrics = stats_to_metrics(&stats, "docker", &tags);
+        let find = |name: &str, tag: Option<(&str, &str)>| {
+            metrics
+                .iter()
+                .find(|metric| {
+                    metric.name == name
+                        && tag.map_or(true, |(key, value)| {
+                            metric.tags.as_ref().unwrap().get(key).map(String::as_str)
+                                == Some(value)
+                        })
+                })
+                .unwrap_or_else(|| panic!("missing metric {}", name))
+        };
+
+        let metric = find("cpu_usage_seconds_total", None);
+        assert_eq!(metric.namespace.as_deref(), Some("docker"));
+        assert_eq!(metric.value, MetricValue::Counter { value: 3.0 });
+        assert_eq!(
+            metric.tags.as_ref().unwrap().get(NAME).map(String::as_str),
+            Some("vector_test")
+        );
+        assert_eq!(
+            find("cpu_usage_percent", None).value,
+            MetricValue::Gauge { value: 20.0 }
+        );
+        assert_eq!(
+            find("memory_usage_bytes", None).value,
+            MetricValue::Gauge { value: 6537216.0 }
+        );
+        assert_eq!(
+            find("network_transmit_bytes_total", Some(("interface", "eth0"))).value,
+            MetricValue::Counter { value: 2000.0 }
+        );
+        assert_eq!(
+            find("blkio_service_bytes_total", Some(("op", "write"))).value,
+            MetricValue::Counter { value: 8192.0 }
+        );
+    }
 }
 
 #[cfg(all(test, feature = "docker-integration-tests"))]
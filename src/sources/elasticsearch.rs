@@ -0,0 +1,791 @@
+use crate::{
+    config::{
+        log_schema, DataDirLock, DataType, GenerateConfig, GlobalOptions, SourceConfig,
+        SourceDescription,
+    },
+    elasticsearch::{finish_signer, ElasticSearchAuth},
+    event::{Event, LogEvent, Value},
+    http::HttpClient,
+    internal_events::{
+        ElasticSearchCheckpointFailed, ElasticSearchHitsReceived, ElasticSearchSearchFailed,
+    },
+    rusoto::{self, region_from_endpoint, RegionOrEndpoint},
+    shutdown::ShutdownSignal,
+    tls::{TlsOptions, TlsSettings},
+    Pipeline,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::{compat::Future01CompatExt, FutureExt, TryFutureExt};
+use futures01::Sink;
+use http::{uri::InvalidUri, Method, Request, StatusCode, Uri};
+use hyper::Body;
+use rusoto_core::Region;
+use rusoto_signature::{SignedRequest, SignedRequestPayload};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{fs, time::delay_for};
+
+const CHECKPOINT_FILENAME: &str = "checkpoint.json";
+const RETRY_SECS: u64 = 5;
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid endpoint {:?}: {}", endpoint, source))]
+    InvalidEndpoint {
+        endpoint: String,
+        source: InvalidUri,
+    },
+    #[snafu(display("Invalid query: {}", source))]
+    InvalidQuery { source: serde_json::Error },
+    #[snafu(display(
+        "Invalid sort {:?}, expected \"<field>\" or \"<field>:<asc|desc>\"",
+        sort
+    ))]
+    InvalidSort { sort: String },
+    #[snafu(display("The \"search_after\" pagination requires the `sort` option"))]
+    SearchAfterWithoutSort,
+}
+
+#[derive(Debug, Snafu)]
+enum SearchError {
+    #[snafu(display("Unexpected status {}: {}", status, body))]
+    UnexpectedStatus { status: StatusCode, body: String },
+    #[snafu(display("Could not parse response: {}", source))]
+    ParseResponse { source: serde_json::Error },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ElasticSearchSourceConfig {
+    pub endpoint: String,
+    pub index: String,
+    /// The query, in the JSON query DSL.
+    pub query: Option<String>,
+    #[serde(default)]
+    pub sort: Vec<String>,
+    #[serde(default)]
+    pub pagination: Pagination,
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+    /// How long the point in time or the scroll context is kept between pages.
+    #[serde(default = "default_keep_alive_secs")]
+    pub keep_alive_secs: u64,
+    pub id_key: Option<String>,
+    pub index_key: Option<String>,
+    pub data_dir: Option<PathBuf>,
+
+    pub auth: Option<ElasticSearchAuth>,
+    pub aws: Option<RegionOrEndpoint>,
+    pub tls: Option<TlsOptions>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone, Copy, Derivative)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum Pagination {
+    #[derivative(Default)]
+    PointInTime,
+    SearchAfter,
+    Scroll,
+}
+
+const fn default_page_size() -> usize {
+    1000
+}
+
+const fn default_keep_alive_secs() -> u64 {
+    300
+}
+
+inventory::submit! {
+    SourceDescription::new::<ElasticSearchSourceConfig>("elasticsearch")
+}
+
+impl GenerateConfig for ElasticSearchSourceConfig {
+    fn generate_config() -> toml::Value {
+        toml::from_str(
+            r#"
+            endpoint = "http://localhost:9200"
+            index = "vector-*"
+            "#,
+        )
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "elasticsearch")]
+impl SourceConfig for ElasticSearchSourceConfig {
+    async fn build(
+        &self,
+        name: &str,
+        globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<super::Source> {
+        let source = ElasticSearchSource::new(self)?;
+
+        let data_dir = globals.resolve_and_make_data_subdir(self.data_dir.as_ref(), name)?;
+        let lock = DataDirLock::acquire(&data_dir)?;
+        let checkpoint_path = data_dir.join(CHECKPOINT_FILENAME);
+
+        Ok(Box::new(
+            async move {
+                // Held until the final checkpoint has been saved.
+                let _lock = lock;
+                source.run(checkpoint_path, shutdown, out).await
+            }
+            .boxed()
+            .compat(),
+        ))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "elasticsearch"
+    }
+}
+
+/// The position of the export, saved after each page has been sent.
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq)]
+struct Checkpoint {
+    /// The search the checkpoint belongs to, so that changing the
+    /// configuration starts a new export.
+    search: serde_json::Value,
+    #[serde(default)]
+    pit_id: Option<String>,
+    #[serde(default)]
+    scroll_id: Option<String>,
+    #[serde(default)]
+    search_after: Option<serde_json::Value>,
+    #[serde(default)]
+    finished: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchResponse {
+    #[serde(rename = "_scroll_id")]
+    scroll_id: Option<String>,
+    pit_id: Option<String>,
+    hits: SearchHits,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchHits {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchHit {
+    #[serde(rename = "_index")]
+    index: String,
+    #[serde(rename = "_id")]
+    id: String,
+    #[serde(rename = "_source", default)]
+    source: serde_json::Map<String, serde_json::Value>,
+    sort: Option<serde_json::Value>,
+}
+
+struct ElasticSearchSource {
+    client: HttpClient,
+    endpoint: String,
+    index: String,
+    query: serde_json::Value,
+    sort: Vec<serde_json::Value>,
+    pagination: Pagination,
+    page_size: usize,
+    keep_alive: String,
+    id_key: Option<String>,
+    index_key: Option<String>,
+    authorization: Option<String>,
+    credentials: Option<rusoto::AwsCredentialsProvider>,
+    region: Region,
+}
+
+impl ElasticSearchSource {
+    fn new(config: &ElasticSearchSourceConfig) -> crate::Result<Self> {
+        let endpoint = config.endpoint.trim_end_matches('/').to_owned();
+        endpoint.parse::<Uri>().with_context(|| InvalidEndpoint {
+            endpoint: endpoint.clone(),
+        })?;
+
+        let query = match &config.query {
+            Some(query) => serde_json::from_str(query).context(InvalidQuery)?,
+            None => json!({ "match_all": {} }),
+        };
+        let sort = config
+            .sort
+            .iter()
+            .map(|sort| parse_sort(sort))
+            .collect::<Result<Vec<_>, _>>()?;
+        if config.pagination == Pagination::SearchAfter && sort.is_empty() {
+            return Err(BuildError::SearchAfterWithoutSort.into());
+        }
+
+        let region = match &config.aws {
+            Some(region) => Region::try_from(region)?,
+            None => region_from_endpoint(&config.endpoint)?,
+        };
+        let credentials = match &config.auth {
+            Some(ElasticSearchAuth::Basic { .. }) | None => None,
+            Some(ElasticSearchAuth::Aws { assume_role }) => Some(
+                rusoto::AwsCredentialsProvider::new(&region, assume_role.clone())?,
+            ),
+        };
+        let authorization = config
+            .auth
+            .as_ref()
+            .and_then(ElasticSearchAuth::basic_authorization);
+
+        let tls_settings = TlsSettings::from_options(&config.tls)?;
+
+        Ok(Self {
+            client: HttpClient::new(tls_settings)?,
+            endpoint,
+            index: config.index.clone(),
+            query,
+            sort,
+            pagination: config.pagination,
+            page_size: config.page_size,
+            keep_alive: format!("{}s", config.keep_alive_secs),
+            id_key: config.id_key.clone(),
+            index_key: config.index_key.clone(),
+            authorization,
+            credentials,
+            region,
+        })
+    }
+
+    async fn run(
+        self,
+        checkpoint_path: PathBuf,
+        mut shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> Result<(), ()> {
+        let mut out = out.sink_map_err(|error| error!(message = "Error sending event.", %error));
+
+        let mut checkpoint = self.load_checkpoint(&checkpoint_path).await;
+        if checkpoint.finished {
+            info!(
+                message = "The search has already been exported, remove the checkpoint to export it again.",
+                path = ?checkpoint_path,
+            );
+            return Ok(());
+        }
+
+        loop {
+            let hits = tokio::select! {
+                hits = self.next_page(&mut checkpoint) => hits,
+                _ = &mut shutdown => return Ok(()),
+            };
+            let hits = match hits {
+                Ok(hits) => hits,
+                Err(error) => {
+                    emit!(ElasticSearchSearchFailed { error: &error });
+                    if self.expire_context(&error, &mut checkpoint) {
+                        continue;
+                    }
+                    tokio::select! {
+                        _ = delay_for(Duration::from_secs(RETRY_SECS)) => continue,
+                        _ = &mut shutdown => return Ok(()),
+                    }
+                }
+            };
+
+            if hits.is_empty() {
+                self.close_context(&checkpoint).await;
+                checkpoint.finished = true;
+                save_checkpoint(&checkpoint_path, &checkpoint).await;
+                info!(message = "Finished exporting the search.");
+                return Ok(());
+            }
+
+            if let Some(search_after) = hits.last().and_then(|hit| hit.sort.clone()) {
+                checkpoint.search_after = Some(search_after);
+            }
+            for hit in hits {
+                let event = self.hit_to_event(hit);
+                out = out.send(event).compat().await?;
+            }
+            save_checkpoint(&checkpoint_path, &checkpoint).await;
+        }
+    }
+
+    /// The configuration of the search, as recorded in the checkpoints.
+    fn search(&self) -> serde_json::Value {
+        json!({
+            "index": self.index,
+            "query": self.query,
+            "sort": self.sort,
+            "pagination": self.pagination,
+        })
+    }
+
+    async fn load_checkpoint(&self, path: &Path) -> Checkpoint {
+        let search = self.search();
+        let checkpoint = match fs::read(path).await {
+            Ok(data) => serde_json::from_slice::<Checkpoint>(&data)
+                .map_err(|error| {
+                    warn!(message = "Unable to parse checkpoint, starting over.", ?path, %error)
+                })
+                .ok(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => {
+                emit!(ElasticSearchCheckpointFailed { error, path });
+                None
+            }
+        };
+
+        match checkpoint {
+            Some(checkpoint) if checkpoint.search == search => checkpoint,
+            Some(_) => {
+                warn!(
+                    message =
+                        "The search has changed since the checkpoint was saved, starting over."
+                );
+                Checkpoint {
+                    search,
+                    ..Default::default()
+                }
+            }
+            None => Checkpoint {
+                search,
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn next_page(&self, checkpoint: &mut Checkpoint) -> crate::Result<Vec<SearchHit>> {
+        let response = match self.pagination {
+            Pagination::PointInTime => {
+                let pit_id = match checkpoint.pit_id.take() {
+                    Some(pit_id) => pit_id,
+                    None => {
+                        let pit_id = self.open_point_in_time().await?;
+                        // The default sort only orders the hits within the
+                        // point in time they were read from.
+                        if self.sort.is_empty() && checkpoint.search_after.take().is_some() {
+                            warn!(
+                                message =
+                                    "Restarting the search from the start in a new point in time."
+                            );
+                        }
+                        pit_id
+                    }
+                };
+                checkpoint.pit_id = Some(pit_id.clone());
+                // The point in time sorts the hits by their shard and
+                // document when no other sort is set.
+                let sort = if self.sort.is_empty() {
+                    vec![json!({ "_shard_doc": "asc" })]
+                } else {
+                    self.sort.clone()
+                };
+                let mut body = json!({
+                    "size": self.page_size,
+                    "query": self.query,
+                    "sort": sort,
+                    "pit": { "id": pit_id, "keep_alive": self.keep_alive },
+                    "track_total_hits": false,
+                });
+                if let Some(search_after) = &checkpoint.search_after {
+                    body["search_after"] = search_after.clone();
+                }
+                self.search_request(Method::POST, "/_search", &[], body)
+                    .await?
+            }
+            Pagination::SearchAfter => {
+                let mut body = json!({
+                    "size": self.page_size,
+                    "query": self.query,
+                    "sort": self.sort,
+                    "track_total_hits": false,
+                });
+                if let Some(search_after) = &checkpoint.search_after {
+                    body["search_after"] = search_after.clone();
+                }
+                let path = format!("/{}/_search", self.index);
+                self.search_request(Method::POST, &path, &[], body).await?
+            }
+            Pagination::Scroll => match &checkpoint.scroll_id {
+                Some(scroll_id) => {
+                    let body = json!({ "scroll": self.keep_alive, "scroll_id": scroll_id });
+                    self.search_request(Method::POST, "/_search/scroll", &[], body)
+                        .await?
+                }
+                None => {
+                    let sort = if self.sort.is_empty() {
+                        vec![json!("_doc")]
+                    } else {
+                        self.sort.clone()
+                    };
+                    let body = json!({
+                        "size": self.page_size,
+                        "query": self.query,
+                        "sort": sort,
+                    });
+                    let path = format!("/{}/_search", self.index);
+                    let params = [("scroll", self.keep_alive.as_str())];
+                    self.search_request(Method::POST, &path, &params, body)
+                        .await?
+                }
+            },
+        };
+
+        // The IDs of the contexts may change from a page to another.
+        if response.pit_id.is_some() {
+            checkpoint.pit_id = response.pit_id;
+        }
+        if response.scroll_id.is_some() {
+            checkpoint.scroll_id = response.scroll_id;
+        }
+        Ok(response.hits.hits)
+    }
+
+    /// Forgets the point in time or the scroll context once it has expired,
+    /// returning whether the search should be retried right away.
+    fn expire_context(&self, error: &crate::Error, checkpoint: &mut Checkpoint) -> bool {
+        let expired = matches!(
+            error.downcast_ref::<SearchError>(),
+            Some(SearchError::UnexpectedStatus { status, .. }) if *status == StatusCode::NOT_FOUND
+        );
+        if !expired {
+            return false;
+        }
+
+        if checkpoint.pit_id.take().is_some() {
+            warn!(message = "Point in time expired, opening a new one.");
+            true
+        } else if checkpoint.scroll_id.take().is_some() {
+            // The scroll contexts can't be recreated at a given position, and
+            // the scroll API doesn't take a `search_after` position either.
+            warn!(message = "Scroll context expired, restarting the search from the start.");
+            true
+        } else {
+            false
+        }
+    }
+
+    async fn open_point_in_time(&self) -> crate::Result<String> {
+        #[derive(Deserialize)]
+        struct PointInTime {
+            id: String,
+        }
+
+        let path = format!("/{}/_pit", self.index);
+        let params = [("keep_alive", self.keep_alive.as_str())];
+        let body = self
+            .request(Method::POST, &path, &params, Bytes::new())
+            .await?;
+        let pit = serde_json::from_slice::<PointInTime>(&body).context(ParseResponse)?;
+        Ok(pit.id)
+    }
+
+    async fn close_context(&self, checkpoint: &Checkpoint) {
+        let result = if let Some(pit_id) = &checkpoint.pit_id {
+            let body = json!({ "id": pit_id }).to_string();
+            self.request(Method::DELETE, "/_pit", &[], body.into())
+                .await
+        } else if let Some(scroll_id) = &checkpoint.scroll_id {
+            let body = json!({ "scroll_id": scroll_id }).to_string();
+            self.request(Method::DELETE, "/_search/scroll", &[], body.into())
+                .await
+        } else {
+            return;
+        };
+        // The contexts expire on their own anyway.
+        if let Err(error) = result {
+            debug!(message = "Unable to close search context.", %error);
+        }
+    }
+
+    async fn search_request(
+        &self,
+        method: Method,
+        path: &str,
+        params: &[(&str, &str)],
+        body: serde_json::Value,
+    ) -> crate::Result<SearchResponse> {
+        let body = self
+            .request(method, path, params, body.to_string().into())
+            .await?;
+        let response = serde_json::from_slice::<SearchResponse>(&body).context(ParseResponse)?;
+        emit!(ElasticSearchHitsReceived {
+            count: response.hits.hits.len(),
+            byte_size: body.len(),
+        });
+        Ok(response)
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        params: &[(&str, &str)],
+        body: Bytes,
+    ) -> crate::Result<Bytes> {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        for (key, value) in params {
+            query.append_pair(key, value);
+        }
+        let query = query.finish();
+        let uri = if query.is_empty() {
+            format!("{}{}", self.endpoint, path)
+        } else {
+            format!("{}{}?{}", self.endpoint, path, query)
+        };
+        let uri = uri.parse::<Uri>()?;
+
+        let mut builder = Request::builder()
+            .method(method.clone())
+            .uri(&uri)
+            .header("Content-Type", "application/json");
+        let body = if let Some(credentials_provider) = &self.credentials {
+            let mut request = SignedRequest::new(method.as_str(), "es", &self.region, uri.path());
+            for (key, value) in params {
+                request.add_param(*key, *value);
+            }
+            request.add_header("Content-Type", "application/json");
+            request.set_payload(Some(body));
+            builder = finish_signer(&mut request, credentials_provider, builder).await?;
+
+            match request.payload.take() {
+                Some(SignedRequestPayload::Buffer(body)) => body,
+                _ => unreachable!(),
+            }
+        } else {
+            if let Some(authorization) = &self.authorization {
+                builder = builder.header("Authorization", authorization.as_str());
+            }
+            body
+        };
+
+        let request = builder.body(Body::from(body))?;
+        let response = self.client.send(request).await?;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        if status.is_success() {
+            Ok(body)
+        } else {
+            Err(SearchError::UnexpectedStatus {
+                status,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            }
+            .into())
+        }
+    }
+
+    fn hit_to_event(&self, hit: SearchHit) -> Event {
+        let SearchHit {
+            index,
+            id,
+            mut source,
+            ..
+        } = hit;
+
+        let timestamp = source.remove("@timestamp");
+        let fields = source
+            .into_iter()
+            .map(|(key, value)| (key, Value::from(value)))
+            .collect::<BTreeMap<_, _>>();
+        let mut log = LogEvent::from(fields);
+
+        // The documents of the data streams and the Elastic integrations
+        // are timestamped by their `@timestamp` field.
+        match timestamp {
+            Some(serde_json::Value::String(text)) => match DateTime::parse_from_rfc3339(&text) {
+                Ok(timestamp) => {
+                    log.insert(log_schema().timestamp_key(), timestamp.with_timezone(&Utc));
+                }
+                Err(_) => log.insert_flat("@timestamp", text),
+            },
+            Some(value) => log.insert_flat("@timestamp", Value::from(value)),
+            None => {}
+        }
+        if !log.contains(log_schema().timestamp_key()) {
+            log.insert(log_schema().timestamp_key(), Utc::now());
+        }
+
+        if let Some(id_key) = &self.id_key {
+            log.insert(id_key, id);
+        }
+        if let Some(index_key) = &self.index_key {
+            log.insert(index_key, index);
+        }
+        log.insert(log_schema().source_type_key(), Bytes::from("elasticsearch"));
+
+        Event::Log(log)
+    }
+}
+
+/// Parses a sort written as `<field>` or `<field>:<asc|desc>`.
+fn parse_sort(sort: &str) -> Result<serde_json::Value, BuildError> {
+    let mut parts = sort.rsplitn(2, ':');
+    let (field, order) = match (parts.next(), parts.next()) {
+        (Some(order), Some(field)) if order == "asc" || order == "desc" => (field, order),
+        (Some(field), None) => (field, "asc"),
+        _ => return Err(BuildError::InvalidSort { sort: sort.into() }),
+    };
+    if field.is_empty() {
+        return Err(BuildError::InvalidSort { sort: sort.into() });
+    }
+    Ok(json!({ field: order }))
+}
+
+/// Replaces the checkpoint file, so that it is never left half written.
+async fn save_checkpoint(path: &Path, checkpoint: &Checkpoint) {
+    let data = serde_json::to_vec(checkpoint).expect("Checkpoints are serializable.");
+    let tmp_path = path.with_extension("json.tmp");
+    let result = async {
+        fs::write(&tmp_path, &data).await?;
+        fs::rename(&tmp_path, path).await
+    }
+    .await;
+    if let Err(error) = result {
+        emit!(ElasticSearchCheckpointFailed { error, path });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<ElasticSearchSourceConfig>();
+    }
+
+    fn config() -> ElasticSearchSourceConfig {
+        toml::from_str(
+            r#"
+            endpoint = "http://localhost:9200/"
+            index = "logs-*"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_sorts() {
+        assert_eq!(
+            parse_sort("timestamp").unwrap(),
+            json!({ "timestamp": "asc" })
+        );
+        assert_eq!(
+            parse_sort("@timestamp:desc").unwrap(),
+            json!({ "@timestamp": "desc" })
+        );
+        assert_eq!(parse_sort("a:b:asc").unwrap(), json!({ "a:b": "asc" }));
+        assert!(parse_sort("timestamp:up").is_err());
+        assert!(parse_sort(":asc").is_err());
+    }
+
+    #[test]
+    fn validates_config() {
+        assert!(ElasticSearchSource::new(&ElasticSearchSourceConfig {
+            query: Some("{\"match\":".into()),
+            ..config()
+        })
+        .is_err());
+        assert!(ElasticSearchSource::new(&ElasticSearchSourceConfig {
+            pagination: Pagination::SearchAfter,
+            ..config()
+        })
+        .is_err());
+        assert!(ElasticSearchSource::new(&ElasticSearchSourceConfig {
+            pagination: Pagination::SearchAfter,
+            sort: vec!["@timestamp".into(), "event.id:desc".into()],
+            ..config()
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn converts_hits() {
+        let source = ElasticSearchSource::new(&ElasticSearchSourceConfig {
+            id_key: Some("_id".into()),
+            index_key: Some("_index".into()),
+            ..config()
+        })
+        .unwrap();
+        let response = serde_json::from_str::<SearchResponse>(
+            r#"{
+                "pit_id": "46ToAwMDaWR5BXV1aWQy",
+                "hits": {
+                    "hits": [{
+                        "_index": "logs-nginx-default",
+                        "_id": "aHR0cHM6Ly93d3",
+                        "_source": {
+                            "@timestamp": "2020-10-15T11:01:46.499Z",
+                            "message": "GET /",
+                            "http": { "status": 200 }
+                        },
+                        "sort": [1602759706499, 42]
+                    }]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(response.pit_id.as_deref(), Some("46ToAwMDaWR5BXV1aWQy"));
+
+        let hit = response.hits.hits.into_iter().next().unwrap();
+        assert_eq!(hit.sort, Some(json!([1602759706499i64, 42])));
+        let event = source.hit_to_event(hit);
+        let log = event.as_log();
+
+        assert_eq!(log["message"], "GET /".into());
+        assert_eq!(log["http.status"], Value::Integer(200));
+        assert_eq!(log["_id"], "aHR0cHM6Ly93d3".into());
+        assert_eq!(log["_index"], "logs-nginx-default".into());
+        assert_eq!(
+            log[log_schema().timestamp_key()],
+            "2020-10-15T11:01:46.499Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+                .into()
+        );
+        assert!(log.get_flat("@timestamp").is_none());
+        assert_eq!(log[log_schema().source_type_key()], "elasticsearch".into());
+    }
+
+    #[tokio::test]
+    async fn resumes_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CHECKPOINT_FILENAME);
+        let source = ElasticSearchSource::new(&config()).unwrap();
+
+        let checkpoint = source.load_checkpoint(&path).await;
+        assert_eq!(checkpoint.search, source.search());
+        assert_eq!(checkpoint.search_after, None);
+
+        let checkpoint = Checkpoint {
+            pit_id: Some("46ToAwMDaWR5BXV1aWQy".into()),
+            search_after: Some(json!([1602759706499i64, 42])),
+            ..checkpoint
+        };
+        save_checkpoint(&path, &checkpoint).await;
+        assert_eq!(source.load_checkpoint(&path).await, checkpoint);
+
+        // Another search starts over.
+        let other = ElasticSearchSource::new(&ElasticSearchSourceConfig {
+            query: Some(r#"{"term":{"service":"nginx"}}"#.into()),
+            ..config()
+        })
+        .unwrap();
+        let restarted = other.load_checkpoint(&path).await;
+        assert_eq!(restarted.search_after, None);
+        assert_eq!(restarted.pit_id, None);
+    }
+}
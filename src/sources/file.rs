@@ -1,6 +1,6 @@
 use super::util::MultilineConfig;
 use crate::{
-    config::{log_schema, DataType, GlobalOptions, SourceConfig, SourceDescription},
+    config::{log_schema, DataDirLock, DataType, GlobalOptions, SourceConfig, SourceDescription},
     event::Event,
     internal_events::{FileEventReceived, FileSourceInternalEventsEmitter},
     line_agg::{self, LineAgg},
@@ -92,6 +92,11 @@ pub enum FingerprintConfig {
     },
     #[serde(rename = "device_and_inode")]
     DevInode,
+    #[serde(rename = "device_inode_and_checksum")]
+    DevInodeChecksum {
+        bytes: usize,
+        ignored_header_bytes: usize,
+    },
 }
 
 impl From<FingerprintConfig> for FingerprintStrategy {
@@ -105,6 +110,13 @@ impl From<FingerprintConfig> for FingerprintStrategy {
                 ignored_header_bytes,
             },
             FingerprintConfig::DevInode => FingerprintStrategy::DevInode,
+            FingerprintConfig::DevInodeChecksum {
+                bytes,
+                ignored_header_bytes,
+            } => FingerprintStrategy::DevInodeChecksum {
+                bytes,
+                ignored_header_bytes,
+            },
         }
     }
 }
@@ -174,7 +186,7 @@ impl SourceConfig for FileConfig {
             }
         }
 
-        Ok(file_source(self, data_dir, shutdown, out))
+        file_source(self, data_dir, shutdown, out)
     }
 
     fn output_type(&self) -> DataType {
@@ -191,12 +203,14 @@ pub fn file_source(
     data_dir: PathBuf,
     shutdown: ShutdownSignal,
     out: Pipeline,
-) -> super::Source {
+) -> crate::Result<super::Source> {
     let ignore_before = config
         .ignore_older
         .map(|secs| Utc::now() - chrono::Duration::seconds(secs as i64));
     let glob_minimum_cooldown = Duration::from_millis(config.glob_minimum_cooldown);
 
+    let lock = DataDirLock::acquire(&data_dir)?;
+
     let paths_provider = Glob::new(&config.include, &config.exclude, MatchOptions::default())
         .expect("invalid glob patterns");
 
@@ -230,7 +244,7 @@ pub fn file_source(
     let message_start_indicator = config.message_start_indicator.clone();
     let multi_line_timeout = config.multi_line_timeout;
 
-    Box::new(futures01::future::lazy(move || {
+    Ok(Box::new(futures01::future::lazy(move || {
         info!(message = "Starting file server.", include = ?include, exclude = ?exclude);
 
         // sizing here is just a guess
@@ -276,6 +290,8 @@ pub fn file_source(
         let span = info_span!("file_server");
         spawn_blocking(move || {
             let _enter = span.enter();
+            // Held until the file server has written its final checkpoints.
+            let _lock = lock;
             let result = file_server.run(tx, shutdown);
             // Panic if we encounter any error originating from the file server.
             // We're at the `spawn_blocking` call, the panic will be caught and
@@ -285,7 +301,7 @@ pub fn file_source(
         .boxed()
         .compat()
         .map_err(|error| error!(message="File server unexpectedly stopped.", %error))
-    }))
+    })))
 }
 
 fn wrap_with_line_agg(
@@ -421,6 +437,23 @@ mod tests {
                 ignored_header_bytes: 512,
             }
         );
+
+        let config: FileConfig = toml::from_str(
+            r#"
+        [fingerprint]
+        strategy = "device_inode_and_checksum"
+        bytes = 128
+        ignored_header_bytes = 512
+        "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.fingerprint,
+            FingerprintConfig::DevInodeChecksum {
+                bytes: 128,
+                ignored_header_bytes: 512,
+            }
+        );
     }
 
     #[test]
@@ -472,7 +505,8 @@ mod tests {
             ..test_default_file_config(&dir)
         };
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let path1 = dir.path().join("file1");
@@ -529,7 +563,8 @@ mod tests {
             include: vec![dir.path().join("*")],
             ..test_default_file_config(&dir)
         };
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let path = dir.path().join("file");
@@ -583,6 +618,49 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn file_copytruncate_with_device_and_inode() {
+        let (tx, rx) = Pipeline::new_test();
+        let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
+
+        let dir = tempdir().unwrap();
+        let config = file::FileConfig {
+            include: vec![dir.path().join("*")],
+            fingerprint: FingerprintConfig::DevInode,
+            ..test_default_file_config(&dir)
+        };
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
+        tokio::spawn(source.compat());
+
+        let path = dir.path().join("file");
+        let mut file = File::create(&path).unwrap();
+
+        sleep_500_millis().await;
+
+        writeln!(&mut file, "a long line before the truncation").unwrap();
+
+        sleep_500_millis().await;
+
+        // The file keeps its inode, and the new content ends before the
+        // position read up to, so it would be missed without noticing the
+        // truncation.
+        file.set_len(0).unwrap();
+        file.seek(std::io::SeekFrom::Start(0)).unwrap();
+        writeln!(&mut file, "short").unwrap();
+
+        sleep_500_millis().await;
+
+        drop(trigger_shutdown);
+
+        let received = wait_with_timeout(rx.collect().compat()).await;
+        let lines = received
+            .into_iter()
+            .map(|event| event.as_log()[log_schema().message_key()].to_string_lossy())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec!["a long line before the truncation", "short"]);
+    }
+
     #[tokio::test]
     async fn file_rotate() {
         let n = 5;
@@ -594,7 +672,8 @@ mod tests {
             include: vec![dir.path().join("*")],
             ..test_default_file_config(&dir)
         };
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let path = dir.path().join("file");
@@ -662,7 +741,8 @@ mod tests {
             ..test_default_file_config(&dir)
         };
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let path1 = dir.path().join("a.txt");
@@ -718,7 +798,8 @@ mod tests {
                 ..test_default_file_config(&dir)
             };
 
-            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            let source =
+                file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
             tokio::spawn(source.compat());
 
             let path = dir.path().join("file");
@@ -755,7 +836,8 @@ mod tests {
                 ..test_default_file_config(&dir)
             };
 
-            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            let source =
+                file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
             tokio::spawn(source.compat());
 
             let path = dir.path().join("file");
@@ -792,7 +874,8 @@ mod tests {
                 ..test_default_file_config(&dir)
             };
 
-            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            let source =
+                file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
             tokio::spawn(source.compat());
 
             let path = dir.path().join("file");
@@ -843,7 +926,8 @@ mod tests {
             let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
 
             let (tx, rx) = Pipeline::new_test();
-            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            let source =
+                file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
             tokio::spawn(source.compat());
 
             sleep_500_millis().await;
@@ -864,7 +948,8 @@ mod tests {
             let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
 
             let (tx, rx) = Pipeline::new_test();
-            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            let source =
+                file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
             tokio::spawn(source.compat());
 
             sleep_500_millis().await;
@@ -890,7 +975,8 @@ mod tests {
                 ..test_default_file_config(&dir)
             };
             let (tx, rx) = Pipeline::new_test();
-            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            let source =
+                file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
             tokio::spawn(source.compat());
 
             sleep_500_millis().await;
@@ -926,7 +1012,8 @@ mod tests {
             let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
 
             let (tx, rx) = Pipeline::new_test();
-            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            let source =
+                file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
             tokio::spawn(source.compat());
 
             let mut file = File::create(&path).unwrap();
@@ -951,7 +1038,8 @@ mod tests {
             let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
 
             let (tx, rx) = Pipeline::new_test();
-            let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+            let source =
+                file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
             tokio::spawn(source.compat());
 
             let mut file = File::create(&path).unwrap();
@@ -986,7 +1074,8 @@ mod tests {
             ..test_default_file_config(&dir)
         };
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let before_path = dir.path().join("before");
@@ -1061,7 +1150,8 @@ mod tests {
             ..test_default_file_config(&dir)
         };
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let path = dir.path().join("file");
@@ -1120,7 +1210,8 @@ mod tests {
             ..test_default_file_config(&dir)
         };
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let path = dir.path().join("file");
@@ -1195,7 +1286,8 @@ mod tests {
             ..test_default_file_config(&dir)
         };
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let path = dir.path().join("file");
@@ -1285,7 +1377,8 @@ mod tests {
 
         sleep_500_millis().await;
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         sleep_500_millis().await;
@@ -1350,7 +1443,8 @@ mod tests {
 
         sleep_500_millis().await;
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         sleep_500_millis().await;
@@ -1403,7 +1497,8 @@ mod tests {
 
         sleep_500_millis().await;
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         sleep_500_millis().await;
@@ -1452,7 +1547,8 @@ mod tests {
             ..test_default_file_config(&dir)
         };
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         sleep_500_millis().await;
@@ -1503,7 +1599,8 @@ mod tests {
             ..test_default_file_config(&dir)
         };
 
-        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+        let source =
+            file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx).unwrap();
         tokio::spawn(source.compat());
 
         let path = dir.path().join("file");
@@ -0,0 +1,503 @@
+use crate::{
+    config::{DataType, GenerateConfig, GlobalOptions, SourceConfig, SourceDescription},
+    event::Event,
+    gcp::{GcpAuthConfig, GcpCredentials, Scope},
+    grpc,
+    internal_events::{GcpPubsubAckFailed, GcpPubsubEventReceived, GcpPubsubReceiveFailed},
+    shutdown::ShutdownSignal,
+    sources::util::message::{decode_message, fresh_backoff, insert_headers},
+    tls::{TlsOptions, TlsSettings},
+    Pipeline,
+};
+use chrono::{TimeZone, Utc};
+use futures::{
+    channel::mpsc::{self, UnboundedSender},
+    compat::Future01CompatExt,
+    FutureExt, StreamExt, TryFutureExt,
+};
+use futures01::Sink;
+use http::Uri;
+use proto::{
+    subscriber_client::SubscriberClient, PubsubMessage, StreamingPullRequest, StreamingPullResponse,
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use tokio::time::delay_for;
+use tokio_retry::strategy::ExponentialBackoff;
+use tonic::{codec::Streaming, metadata::MetadataValue, Status};
+
+#[allow(clippy::all)]
+mod proto {
+    include!(concat!(env!("OUT_DIR"), "/grpc/google.pubsub.v1.rs"));
+}
+
+#[derive(Debug, Snafu)]
+pub enum PubsubError {
+    #[snafu(display("Invalid Pub/Sub endpoint: {}", source))]
+    InvalidUri { source: http::uri::InvalidUri },
+    #[snafu(display("Pub/Sub endpoint {:?} is missing a scheme or host", endpoint))]
+    IncompleteEndpoint { endpoint: String },
+    #[snafu(display(
+        "ack_deadline_secs must be between {} and {} seconds, got {}",
+        MIN_ACK_DEADLINE_SECS,
+        MAX_ACK_DEADLINE_SECS,
+        ack_deadline_secs
+    ))]
+    InvalidAckDeadline { ack_deadline_secs: u16 },
+    #[snafu(display("Invalid credentials: {}", source))]
+    InvalidCredentials {
+        source: tonic::metadata::errors::InvalidMetadataValue,
+    },
+    #[snafu(display("Pub/Sub request failed: {}", source))]
+    Pull { source: Status },
+    #[snafu(display("The stream to Pub/Sub is closed"))]
+    StreamClosed,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PubsubConfig {
+    pub project: String,
+    pub subscription: String,
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub skip_authentication: bool,
+    #[serde(flatten)]
+    pub auth: GcpAuthConfig,
+    /// How long the source has to acknowledge a pulled message before
+    /// Pub/Sub redelivers it.
+    #[serde(default = "default_ack_deadline_secs")]
+    pub ack_deadline_secs: u16,
+    #[serde(default = "default_max_messages")]
+    pub max_messages: u32,
+    /// The field the message attributes are written to, as a map.
+    #[serde(default = "default_attributes_key")]
+    pub attributes_key: String,
+    pub tls: Option<TlsOptions>,
+}
+
+/// The range of acknowledgement deadlines accepted by Pub/Sub.
+const MIN_ACK_DEADLINE_SECS: u16 = 10;
+const MAX_ACK_DEADLINE_SECS: u16 = 600;
+
+fn default_ack_deadline_secs() -> u16 {
+    MAX_ACK_DEADLINE_SECS
+}
+
+fn default_max_messages() -> u32 {
+    1000
+}
+
+fn default_attributes_key() -> String {
+    String::from("attributes")
+}
+
+inventory::submit! {
+    SourceDescription::new::<PubsubConfig>("gcp_pubsub")
+}
+
+impl GenerateConfig for PubsubConfig {
+    fn generate_config() -> toml::Value {
+        toml::from_str(
+            r#"
+            project = "my-project"
+            subscription = "my-subscription"
+            "#,
+        )
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "gcp_pubsub")]
+impl SourceConfig for PubsubConfig {
+    async fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<super::Source> {
+        if !(MIN_ACK_DEADLINE_SECS..=MAX_ACK_DEADLINE_SECS).contains(&self.ack_deadline_secs) {
+            return Err(PubsubError::InvalidAckDeadline {
+                ack_deadline_secs: self.ack_deadline_secs,
+            }
+            .into());
+        }
+
+        // We only need to load the credentials if we are not targeting an emulator.
+        let creds = if self.skip_authentication {
+            None
+        } else {
+            self.auth.make_credentials(Scope::PubSub).await?
+        };
+        if let Some(creds) = &creds {
+            creds.spawn_regenerate_token();
+        }
+
+        let endpoint = self
+            .endpoint
+            .as_deref()
+            .unwrap_or("https://pubsub.googleapis.com");
+        let origin = endpoint.parse::<Uri>().context(InvalidUri)?;
+        if origin.scheme().is_none() || origin.authority().is_none() {
+            return Err(PubsubError::IncompleteEndpoint {
+                endpoint: endpoint.into(),
+            }
+            .into());
+        }
+        let tls_settings = TlsSettings::from_options(&self.tls)?;
+        let client = SubscriberClient::new(grpc::channel(origin, tls_settings)?);
+
+        let source = PubsubSource {
+            client,
+            creds,
+            api_key: self.auth.api_key.clone(),
+            subscription: format!(
+                "projects/{}/subscriptions/{}",
+                self.project, self.subscription
+            ),
+            ack_deadline_secs: self.ack_deadline_secs,
+            max_messages: self.max_messages,
+            attributes_key: self.attributes_key.clone(),
+        };
+
+        Ok(Box::new(source.run(shutdown, out).boxed().compat()))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "gcp_pubsub"
+    }
+}
+
+struct PubsubSource {
+    client: SubscriberClient<grpc::Channel>,
+    creds: Option<GcpCredentials>,
+    api_key: Option<String>,
+    subscription: String,
+    ack_deadline_secs: u16,
+    max_messages: u32,
+    attributes_key: String,
+}
+
+impl PubsubSource {
+    async fn run(self, mut shutdown: ShutdownSignal, out: Pipeline) -> Result<(), ()> {
+        let mut backoff = fresh_backoff();
+
+        loop {
+            // Pub/Sub closes streams now and then, they are reopened right
+            // away unless they failed.
+            let result = tokio::select! {
+                result = self.consume(&out, &mut backoff) => result?,
+                _ = &mut shutdown => break,
+            };
+            if let Err(error) = result {
+                emit!(GcpPubsubReceiveFailed { error });
+                let delay = backoff.next().expect("Backoff never ends.");
+                tokio::select! {
+                    _ = delay_for(delay) => {}
+                    _ = &mut shutdown => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Opens a stream and forwards the messages it delivers until it ends.
+    /// Fails with `Err(())` if the pipeline is closed.
+    async fn consume(
+        &self,
+        out: &Pipeline,
+        backoff: &mut ExponentialBackoff,
+    ) -> Result<Result<(), PubsubError>, ()> {
+        let (requests, mut responses) = match self.streaming_pull().await {
+            Ok(stream) => stream,
+            Err(error) => return Ok(Err(error)),
+        };
+
+        while let Some(response) = responses.next().await {
+            let response = match response.context(Pull) {
+                Ok(response) => response,
+                Err(error) => return Ok(Err(error)),
+            };
+            *backoff = fresh_backoff();
+            self.forward(response, out, &requests).await?;
+        }
+
+        Ok(Ok(()))
+    }
+
+    async fn streaming_pull(
+        &self,
+    ) -> Result<
+        (
+            UnboundedSender<StreamingPullRequest>,
+            Streaming<StreamingPullResponse>,
+        ),
+        PubsubError,
+    > {
+        let (requests, receiver) = mpsc::unbounded();
+        // The ack deadline applies to all the messages delivered over the
+        // stream, and Pub/Sub stops delivering while too many are
+        // outstanding.
+        requests
+            .unbounded_send(StreamingPullRequest {
+                subscription: self.subscription.clone(),
+                stream_ack_deadline_seconds: self.ack_deadline_secs.into(),
+                max_outstanding_messages: self.max_messages.into(),
+                ..Default::default()
+            })
+            .expect("The receiver was just created.");
+
+        let mut request = tonic::Request::new(receiver);
+        if let Some(creds) = &self.creds {
+            let token = MetadataValue::from_str(&creds.make_token()).context(InvalidCredentials)?;
+            request.metadata_mut().insert("authorization", token);
+        }
+        if let Some(api_key) = &self.api_key {
+            let api_key = MetadataValue::from_str(api_key).context(InvalidCredentials)?;
+            request.metadata_mut().insert("x-goog-api-key", api_key);
+        }
+
+        let response = self
+            .client
+            .clone()
+            .streaming_pull(request)
+            .await
+            .context(Pull)?;
+        Ok((requests, response.into_inner()))
+    }
+
+    /// Sends the messages to the pipeline, and acknowledges them once they
+    /// are all in it.
+    async fn forward(
+        &self,
+        response: StreamingPullResponse,
+        out: &Pipeline,
+        requests: &UnboundedSender<StreamingPullRequest>,
+    ) -> Result<(), ()> {
+        let mut ack_ids = Vec::with_capacity(response.received_messages.len());
+        let mut events = Vec::with_capacity(response.received_messages.len());
+        for received in response.received_messages {
+            ack_ids.push(received.ack_id);
+            if let Some(message) = received.message {
+                events.push(message_to_event(message, &self.attributes_key));
+            }
+        }
+
+        match out
+            .clone()
+            .send_all(futures01::stream::iter_ok(events))
+            .compat()
+            .await
+        {
+            Ok(_) => {
+                let request = StreamingPullRequest {
+                    ack_ids,
+                    ..Default::default()
+                };
+                if requests.unbounded_send(request).is_err() {
+                    emit!(GcpPubsubAckFailed {
+                        error: PubsubError::StreamClosed
+                    });
+                }
+                Ok(())
+            }
+            Err(error) => {
+                error!(message = "Error sending event.", %error);
+                // Hand the messages back so another consumer can pick them up
+                // right away, if the stream is still open to send this.
+                let request = StreamingPullRequest {
+                    modify_deadline_seconds: vec![0; ack_ids.len()],
+                    modify_deadline_ack_ids: ack_ids,
+                    ..Default::default()
+                };
+                let _ = requests.unbounded_send(request);
+                Err(())
+            }
+        }
+    }
+}
+
+fn message_to_event(message: PubsubMessage, attributes_key: &str) -> Event {
+    emit!(GcpPubsubEventReceived {
+        byte_size: message.data.len()
+    });
+
+    let timestamp = message
+        .publish_time
+        .and_then(|time| Utc.timestamp_opt(time.seconds, time.nanos as u32).single());
+    let mut event = decode_message("gcp_pubsub", &message.data, timestamp);
+    event.as_mut_log().insert("message_id", message.message_id);
+    if !message.attributes.is_empty() {
+        insert_headers(&mut event, attributes_key, message.attributes);
+    }
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::log_schema;
+    use std::collections::HashMap;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<PubsubConfig>();
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_ack_deadline() {
+        let config = PubsubConfig {
+            ack_deadline_secs: 5,
+            skip_authentication: true,
+            ..Default::default()
+        };
+        let error = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                Pipeline::new_test().0,
+            )
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "ack_deadline_secs must be between 10 and 600 seconds, got 5"
+        );
+    }
+
+    #[test]
+    fn message_to_event_maps_attributes() {
+        let mut attributes = HashMap::new();
+        attributes.insert("team".to_owned(), "core".to_owned());
+        let event = message_to_event(
+            PubsubMessage {
+                data: b"hello".to_vec(),
+                attributes,
+                message_id: "1".into(),
+                publish_time: Some(prost_types::Timestamp {
+                    seconds: 1_604_232_000,
+                    nanos: 123_000_000,
+                }),
+                ordering_key: String::new(),
+            },
+            &default_attributes_key(),
+        );
+        let log = event.as_log();
+
+        assert_eq!(log[log_schema().message_key()], "hello".into());
+        assert_eq!(log["message_id"], "1".into());
+        assert_eq!(log["attributes.team"], "core".into());
+        assert_eq!(log[log_schema().source_type_key()], "gcp_pubsub".into());
+        assert_eq!(
+            log[log_schema().timestamp_key()],
+            Utc.timestamp(1_604_232_000, 123_000_000).into()
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "gcp-pubsub-integration-tests")]
+mod integration_tests {
+    use super::*;
+    use crate::{
+        config::log_schema,
+        test_util::{collect_n, random_string, trace_init},
+    };
+    use reqwest::{Client, Method};
+    use serde_json::Value;
+    use std::time::Duration;
+
+    const EMULATOR_HOST: &str = "http://localhost:8681";
+    const PROJECT: &str = "testproject";
+
+    #[tokio::test]
+    async fn gcp_pubsub_source_pulls_and_acks() {
+        trace_init();
+
+        let (topic, subscription) = create_topic_subscription().await;
+        let config = PubsubConfig {
+            project: PROJECT.into(),
+            subscription: subscription.clone(),
+            endpoint: Some(EMULATOR_HOST.into()),
+            skip_authentication: true,
+            ack_deadline_secs: default_ack_deadline_secs(),
+            max_messages: default_max_messages(),
+            attributes_key: default_attributes_key(),
+            ..Default::default()
+        };
+
+        let (tx, rx) = Pipeline::new_test();
+        let source = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .await
+            .unwrap();
+        tokio::spawn(source.compat());
+
+        request(
+            Method::POST,
+            &format!("topics/{}:publish", topic),
+            json!({
+                "messages": [{
+                    "data": base64::encode("hello"),
+                    "attributes": {"team": "core"},
+                }]
+            }),
+        )
+        .await;
+
+        let events = collect_n(rx, 1).await.unwrap();
+        let log = events[0].as_log();
+        assert_eq!(log[log_schema().message_key()], "hello".into());
+        assert_eq!(log["attributes.team"], "core".into());
+
+        // Acknowledged messages are not redelivered.
+        tokio::time::delay_for(Duration::from_secs(1)).await;
+        let response = request(
+            Method::POST,
+            &format!("subscriptions/{}:pull", subscription),
+            json!({ "returnImmediately": true, "maxMessages": 10 }),
+        )
+        .await;
+        assert!(response.get("receivedMessages").is_none());
+    }
+
+    async fn create_topic_subscription() -> (String, String) {
+        let topic = format!("topic-{}", random_string(10));
+        let subscription = format!("subscription-{}", random_string(10));
+        request(Method::PUT, &format!("topics/{}", topic), json!({})).await;
+        request(
+            Method::PUT,
+            &format!("subscriptions/{}", subscription),
+            json!({ "topic": format!("projects/{}/topics/{}", PROJECT, topic) }),
+        )
+        .await;
+        (topic, subscription)
+    }
+
+    async fn request(method: Method, path: &str, json: Value) -> Value {
+        let url = format!("{}/v1/projects/{}/{}", EMULATOR_HOST, PROJECT, path);
+        Client::new()
+            .request(method.clone(), &url)
+            .json(&json)
+            .send()
+            .await
+            .unwrap_or_else(|_| panic!("Sending {} request to {} failed", method, url))
+            .json::<Value>()
+            .await
+            .unwrap_or_else(|_| panic!("Parsing {} response from {} failed", method, url))
+    }
+}
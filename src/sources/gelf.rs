@@ -0,0 +1,385 @@
+use super::util::{
+    gelf::{self, ChunkAssembler},
+    SocketListenAddr, TcpSource,
+};
+use crate::{
+    config::{
+        log_schema, DataType, GenerateConfig, GlobalOptions, Resource, SourceConfig,
+        SourceDescription,
+    },
+    event::Event,
+    internal_events::{GelfChunksExpired, GelfEventReceived, GelfParseError, GelfUdpReadError},
+    shutdown::ShutdownSignal,
+    tls::{MaybeTlsSettings, TlsConfig},
+    Pipeline,
+};
+use bytes::Bytes;
+use codec::BytesDelimitedCodec;
+use futures::{compat::Future01CompatExt, FutureExt, TryFutureExt};
+use futures01::Sink;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::{net::SocketAddr, time::Duration};
+use tokio::net::UdpSocket;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+// TODO: add back when serde-rs/serde#1358 is addressed
+// #[serde(deny_unknown_fields)]
+pub struct GelfConfig {
+    #[serde(flatten)]
+    pub mode: Mode,
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+    /// How long to wait for the remaining chunks of a chunked UDP message.
+    #[serde(default = "default_chunk_timeout_secs")]
+    pub chunk_timeout_secs: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Mode {
+    Tcp {
+        address: SocketListenAddr,
+        tls: Option<TlsConfig>,
+        #[serde(default = "default_shutdown_timeout_secs")]
+        shutdown_timeout_secs: u64,
+    },
+    Udp {
+        address: SocketAddr,
+    },
+}
+
+fn default_max_length() -> usize {
+    gelf::DEFAULT_MAX_LENGTH
+}
+
+fn default_chunk_timeout_secs() -> u64 {
+    5
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("chunk_timeout_secs must be greater than zero"))]
+    ZeroChunkTimeout,
+}
+
+impl GelfConfig {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            max_length: default_max_length(),
+            chunk_timeout_secs: default_chunk_timeout_secs(),
+        }
+    }
+}
+
+inventory::submit! {
+    SourceDescription::new::<GelfConfig>("gelf")
+}
+
+impl GenerateConfig for GelfConfig {
+    fn generate_config() -> toml::Value {
+        toml::Value::try_from(Self::new(Mode::Udp {
+            address: "0.0.0.0:12201".parse().unwrap(),
+        }))
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "gelf")]
+impl SourceConfig for GelfConfig {
+    async fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<super::Source> {
+        match self.mode.clone() {
+            Mode::Tcp {
+                address,
+                tls,
+                shutdown_timeout_secs,
+            } => {
+                let source = GelfTcpSource {
+                    max_length: self.max_length,
+                };
+                let tls = MaybeTlsSettings::from_config(&tls, true)?;
+                source.run(address, shutdown_timeout_secs, tls, shutdown, out)
+            }
+            Mode::Udp { .. } if self.chunk_timeout_secs == 0 => {
+                Err(BuildError::ZeroChunkTimeout.into())
+            }
+            Mode::Udp { address } => Ok(udp(
+                address,
+                self.max_length,
+                Duration::from_secs(self.chunk_timeout_secs),
+                shutdown,
+                out,
+            )),
+        }
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "gelf"
+    }
+
+    fn resources(&self) -> Vec<Resource> {
+        match self.mode.clone() {
+            Mode::Tcp { address, .. } => vec![address.into()],
+            Mode::Udp { address } => vec![address.into()],
+        }
+    }
+}
+
+/// GELF over TCP is uncompressed, unchunked and delimited by a null byte.
+#[derive(Debug, Clone)]
+struct GelfTcpSource {
+    max_length: usize,
+}
+
+impl TcpSource for GelfTcpSource {
+    type Error = std::io::Error;
+    type Decoder = BytesDelimitedCodec;
+
+    fn decoder(&self) -> Self::Decoder {
+        BytesDelimitedCodec::new_with_max_length(b'\0', self.max_length)
+    }
+
+    fn build_event(&self, frame: Bytes, host: Bytes) -> Option<Event> {
+        // Some clients terminate frames with a newline in addition to the null byte.
+        let frame = match frame.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(start) => frame.slice(start..),
+            None => return None,
+        };
+        event_from_payload(frame, host, self.max_length, "tcp")
+    }
+}
+
+/// The largest payload of a UDP datagram.
+const MAX_DATAGRAM_LENGTH: usize = 65_535;
+
+fn udp(
+    address: SocketAddr,
+    max_length: usize,
+    chunk_timeout: Duration,
+    mut shutdown: ShutdownSignal,
+    out: Pipeline,
+) -> super::Source {
+    let mut out = out.sink_map_err(|error| error!(message = "Error sending event.", %error));
+
+    Box::new(
+        async move {
+            let mut socket = UdpSocket::bind(&address)
+                .await
+                .expect("Failed to bind to UDP listener socket");
+            info!(message = "Listening.", address = %address, r#type = "udp");
+
+            let mut assembler = ChunkAssembler::new(chunk_timeout);
+            let mut expire = tokio::time::interval(chunk_timeout);
+            let mut buf = vec![0; MAX_DATAGRAM_LENGTH];
+            loop {
+                tokio::select! {
+                    recv = socket.recv_from(&mut buf) => {
+                        let (byte_size, received_from) = match recv {
+                            Ok(recv) => recv,
+                            Err(error) => {
+                                emit!(GelfUdpReadError { error });
+                                continue;
+                            }
+                        };
+
+                        // Pending chunks are kept until their message is
+                        // complete, so they must not share the receive buffer.
+                        let datagram = Bytes::copy_from_slice(&buf[..byte_size]);
+                        let payload = match assembler.push(datagram) {
+                            Ok(Some(payload)) => payload,
+                            Ok(None) => continue,
+                            Err(error) => {
+                                emit!(GelfParseError { error, mode: "udp" });
+                                continue;
+                            }
+                        };
+
+                        let host = received_from.ip().to_string().into();
+                        if let Some(event) = event_from_payload(payload, host, max_length, "udp") {
+                            tokio::select! {
+                                result = out.send(event).compat() => {
+                                    out = result?;
+                                }
+                                _ = &mut shutdown => return Ok(()),
+                            }
+                        }
+                    }
+                    _ = expire.tick() => {
+                        let count = assembler.expire();
+                        if count > 0 {
+                            emit!(GelfChunksExpired { count });
+                        }
+                    }
+                    _ = &mut shutdown => return Ok(()),
+                }
+            }
+        }
+        .boxed()
+        .compat(),
+    )
+}
+
+fn event_from_payload(
+    payload: Bytes,
+    source_ip: Bytes,
+    max_length: usize,
+    mode: &'static str,
+) -> Option<Event> {
+    let byte_size = payload.len();
+    match gelf::parse(payload, max_length) {
+        Ok(mut event) => {
+            let log = event.as_mut_log();
+            log.insert(log_schema().source_type_key(), Bytes::from("gelf"));
+            log.insert("source_ip", source_ip.clone());
+            log.try_insert(log_schema().host_key(), source_ip);
+
+            emit!(GelfEventReceived { byte_size, mode });
+            Some(event)
+        }
+        Err(error) => {
+            emit!(GelfParseError { error, mode });
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GelfConfig, Mode};
+    use crate::{
+        config::{log_schema, GlobalOptions, SourceConfig},
+        shutdown::ShutdownSignal,
+        test_util::{collect_n, next_addr, send_lines, wait_for_tcp},
+        Pipeline,
+    };
+    use flate2::{write::GzEncoder, Compression};
+    use futures::compat::Future01CompatExt;
+    use std::{io::Write, net::UdpSocket};
+
+    const MESSAGE: &str =
+        r#"{"version":"1.1","host":"example.org","short_message":"hello","_app":"web"}"#;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<GelfConfig>();
+    }
+
+    #[test]
+    fn config_tcp() {
+        let config: GelfConfig = toml::from_str(
+            r#"
+            mode = "tcp"
+            address = "127.0.0.1:12201"
+            chunk_timeout_secs = 10
+          "#,
+        )
+        .unwrap();
+        assert!(matches!(config.mode, Mode::Tcp { shutdown_timeout_secs: 30, .. }));
+        assert_eq!(config.chunk_timeout_secs, 10);
+    }
+
+    #[tokio::test]
+    async fn rejects_zero_chunk_timeout() {
+        let mut config = GelfConfig::new(Mode::Udp {
+            address: next_addr(),
+        });
+        config.chunk_timeout_secs = 0;
+        let (tx, _rx) = Pipeline::new_test();
+        let result = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn gelf_udp_chunked_and_compressed() {
+        let (tx, rx) = Pipeline::new_test();
+        let address = next_addr();
+        let server = GelfConfig::new(Mode::Udp { address })
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .await
+            .unwrap();
+        tokio::spawn(server.compat());
+        tokio::time::delay_for(std::time::Duration::from_millis(100)).await;
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(MESSAGE.as_bytes()).unwrap();
+        let payload = gzip.finish().unwrap();
+        let (first, second) = payload.split_at(payload.len() / 2);
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for (number, data) in [first, second].iter().enumerate() {
+            let mut chunk = vec![0x1e, 0x0f, 0, 0, 0, 0, 0, 0, 0, 1, number as u8, 2];
+            chunk.extend_from_slice(data);
+            socket.send_to(&chunk, address).unwrap();
+        }
+
+        let events = collect_n(rx, 1).await.unwrap();
+        let log = events[0].as_log();
+        assert_eq!(log[log_schema().message_key()], "hello".into());
+        assert_eq!(log[log_schema().host_key()], "example.org".into());
+        assert_eq!(log[log_schema().source_type_key()], "gelf".into());
+        assert_eq!(log["app"], "web".into());
+        assert_eq!(log["source_ip"], "127.0.0.1".into());
+    }
+
+    #[tokio::test]
+    async fn gelf_tcp_null_delimited() {
+        let (tx, rx) = Pipeline::new_test();
+        let address = next_addr();
+        let server = GelfConfig::new(Mode::Tcp {
+            address: address.into(),
+            tls: None,
+            shutdown_timeout_secs: 30,
+        })
+        .build(
+            "default",
+            &GlobalOptions::default(),
+            ShutdownSignal::noop(),
+            tx,
+        )
+        .await
+        .unwrap();
+        tokio::spawn(server.compat());
+        wait_for_tcp(address).await;
+
+        let line = format!("{}\0{}\0", MESSAGE, MESSAGE.replace("hello", "world"));
+        send_lines(address, vec![line]).await.unwrap();
+
+        let events = collect_n(rx, 2).await.unwrap();
+        assert_eq!(
+            events[0].as_log()[log_schema().message_key()],
+            "hello".into()
+        );
+        assert_eq!(
+            events[1].as_log()[log_schema().message_key()],
+            "world".into()
+        );
+    }
+}
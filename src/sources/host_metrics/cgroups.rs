@@ -0,0 +1,527 @@
+use super::{FilterList, HostMetricsConfig};
+use crate::event::metric::Metric;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const MICROSECONDS: f64 = 1_000_000.0;
+const NANOSECONDS: f64 = 1_000_000_000.0;
+/// `cpuacct.stat` reports times in USER_HZ, which the kernel fixes at 100
+/// for everything it exposes to userspace.
+const USER_HZ: f64 = 100.0;
+
+#[derive(Clone, Debug, Derivative, Deserialize, Serialize)]
+#[derivative(Default)]
+pub(super) struct CgroupsConfig {
+    /// How many levels of the hierarchy below `base` to descend into.
+    #[derivative(Default(value = "default_levels()"))]
+    #[serde(default = "default_levels")]
+    levels: usize,
+    /// The cgroup to start from, relative to the root of the hierarchy.
+    base: Option<PathBuf>,
+    #[serde(default)]
+    groups: FilterList,
+}
+
+const fn default_levels() -> usize {
+    100
+}
+
+/// Per device `(read, written)` byte counts.
+type IoStats = BTreeMap<String, (u64, u64)>;
+
+impl HostMetricsConfig {
+    pub async fn cgroups_metrics(&self) -> Vec<Metric> {
+        // Without cgroups there is no hierarchy to walk, so this finds
+        // nothing rather than failing.
+        self.cgroups_metrics_at(Path::new(CGROUP_ROOT)).await
+    }
+
+    async fn cgroups_metrics_at(&self, root: &Path) -> Vec<Metric> {
+        // The unified (v2) hierarchy lists the available controllers at its
+        // root, while each v1 controller is mounted as a hierarchy of its own.
+        if tokio::fs::metadata(root.join("cgroup.controllers"))
+            .await
+            .is_ok()
+        {
+            self.cgroups_v2_metrics(root).await
+        } else {
+            self.cgroups_v1_metrics(root).await
+        }
+    }
+
+    async fn cgroups_v2_metrics(&self, root: &Path) -> Vec<Metric> {
+        let mut metrics = Vec::new();
+        for (dir, name) in self.cgroups_in(root).await {
+            let timestamp = Utc::now();
+            let tags = tags!["cgroup" => name];
+
+            if let Some(stat) = read_keyed(&dir.join("cpu.stat")).await {
+                self.push_cpu_metrics(
+                    &mut metrics,
+                    timestamp,
+                    &tags,
+                    stat.get("usage_usec")
+                        .map(|usec| *usec as f64 / MICROSECONDS),
+                    stat.get("user_usec")
+                        .map(|usec| *usec as f64 / MICROSECONDS),
+                    stat.get("system_usec")
+                        .map(|usec| *usec as f64 / MICROSECONDS),
+                );
+            }
+            if let Some(current) = read_value(&dir.join("memory.current")).await {
+                metrics.push(self.gauge(
+                    "cgroup_memory_current_bytes",
+                    timestamp,
+                    current as f64,
+                    tags.clone(),
+                ));
+            }
+            if let Some(stat) = read_keyed(&dir.join("memory.stat")).await {
+                self.push_memory_stat_metrics(
+                    &mut metrics,
+                    timestamp,
+                    &tags,
+                    stat.get("anon"),
+                    stat.get("file"),
+                );
+            }
+            if let Ok(stat) = tokio::fs::read_to_string(dir.join("io.stat")).await {
+                self.push_io_metrics(&mut metrics, timestamp, &tags, parse_io_stat(&stat));
+            }
+        }
+        metrics
+    }
+
+    async fn cgroups_v1_metrics(&self, root: &Path) -> Vec<Metric> {
+        let mut metrics = Vec::new();
+
+        for (dir, name) in self.cgroups_in(&root.join("cpuacct")).await {
+            let timestamp = Utc::now();
+            let tags = tags!["cgroup" => name];
+            let usage = read_value(&dir.join("cpuacct.usage")).await;
+            let stat = read_keyed(&dir.join("cpuacct.stat"))
+                .await
+                .unwrap_or_default();
+            self.push_cpu_metrics(
+                &mut metrics,
+                timestamp,
+                &tags,
+                usage.map(|nsec| nsec as f64 / NANOSECONDS),
+                stat.get("user").map(|ticks| *ticks as f64 / USER_HZ),
+                stat.get("system").map(|ticks| *ticks as f64 / USER_HZ),
+            );
+        }
+
+        for (dir, name) in self.cgroups_in(&root.join("memory")).await {
+            let timestamp = Utc::now();
+            let tags = tags!["cgroup" => name];
+            if let Some(usage) = read_value(&dir.join("memory.usage_in_bytes")).await {
+                metrics.push(self.gauge(
+                    "cgroup_memory_current_bytes",
+                    timestamp,
+                    usage as f64,
+                    tags.clone(),
+                ));
+            }
+            // The `total_` values include descendants, like the v2 ones do.
+            if let Some(stat) = read_keyed(&dir.join("memory.stat")).await {
+                self.push_memory_stat_metrics(
+                    &mut metrics,
+                    timestamp,
+                    &tags,
+                    stat.get("total_rss"),
+                    stat.get("total_cache"),
+                );
+            }
+        }
+
+        for (dir, name) in self.cgroups_in(&root.join("blkio")).await {
+            let timestamp = Utc::now();
+            let tags = tags!["cgroup" => name];
+            if let Ok(stat) =
+                tokio::fs::read_to_string(dir.join("blkio.throttle.io_service_bytes")).await
+            {
+                self.push_io_metrics(&mut metrics, timestamp, &tags, parse_blkio_stat(&stat));
+            }
+        }
+
+        metrics
+    }
+
+    /// Lists the cgroups of a hierarchy that pass the configured filter, as
+    /// their directory along with their name relative to the hierarchy root.
+    async fn cgroups_in(&self, hierarchy: &Path) -> Vec<(PathBuf, String)> {
+        let base = match &self.cgroups.base {
+            Some(base) => base.strip_prefix("/").unwrap_or(base).to_path_buf(),
+            None => PathBuf::new(),
+        };
+        if !tokio::fs::metadata(hierarchy.join(&base))
+            .await
+            .map(|metadata| metadata.is_dir())
+            .unwrap_or(false)
+        {
+            return vec![];
+        }
+
+        let mut groups = Vec::new();
+        let mut level = vec![base];
+        for depth in 0..=self.cgroups.levels {
+            let mut next = Vec::new();
+            for group in level {
+                let dir = hierarchy.join(&group);
+                if depth < self.cgroups.levels {
+                    if let Ok(mut entries) = tokio::fs::read_dir(&dir).await {
+                        while let Ok(Some(entry)) = entries.next_entry().await {
+                            if entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false) {
+                                next.push(group.join(entry.file_name()));
+                            }
+                        }
+                    }
+                }
+
+                let name = Path::new("/").join(&group);
+                if self.cgroups.groups.contains_path(&name) {
+                    groups.push((dir, name.to_string_lossy().into_owned()));
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            level = next;
+        }
+        groups
+    }
+
+    fn push_cpu_metrics(
+        &self,
+        metrics: &mut Vec<Metric>,
+        timestamp: DateTime<Utc>,
+        tags: &BTreeMap<String, String>,
+        usage: Option<f64>,
+        user: Option<f64>,
+        system: Option<f64>,
+    ) {
+        if let Some(usage) = usage {
+            metrics.push(self.counter(
+                "cgroup_cpu_usage_seconds_total",
+                timestamp,
+                usage,
+                tags.clone(),
+            ));
+        }
+        for (mode, value) in &[("user", user), ("system", system)] {
+            if let Some(value) = value {
+                metrics.push(self.counter(
+                    "cgroup_cpu_seconds_total",
+                    timestamp,
+                    *value,
+                    with_tag(tags, "mode", mode),
+                ));
+            }
+        }
+    }
+
+    fn push_memory_stat_metrics(
+        &self,
+        metrics: &mut Vec<Metric>,
+        timestamp: DateTime<Utc>,
+        tags: &BTreeMap<String, String>,
+        anon: Option<&u64>,
+        file: Option<&u64>,
+    ) {
+        if let Some(anon) = anon {
+            metrics.push(self.gauge(
+                "cgroup_memory_anon_bytes",
+                timestamp,
+                *anon as f64,
+                tags.clone(),
+            ));
+        }
+        if let Some(file) = file {
+            metrics.push(self.gauge(
+                "cgroup_memory_file_bytes",
+                timestamp,
+                *file as f64,
+                tags.clone(),
+            ));
+        }
+    }
+
+    fn push_io_metrics(
+        &self,
+        metrics: &mut Vec<Metric>,
+        timestamp: DateTime<Utc>,
+        tags: &BTreeMap<String, String>,
+        stats: IoStats,
+    ) {
+        for (device, (read, written)) in stats {
+            let tags = with_tag(tags, "device", &device);
+            metrics.push(self.counter(
+                "cgroup_io_read_bytes_total",
+                timestamp,
+                read as f64,
+                tags.clone(),
+            ));
+            metrics.push(self.counter(
+                "cgroup_io_written_bytes_total",
+                timestamp,
+                written as f64,
+                tags,
+            ));
+        }
+    }
+}
+
+fn with_tag(tags: &BTreeMap<String, String>, key: &str, value: &str) -> BTreeMap<String, String> {
+    let mut tags = tags.clone();
+    tags.insert(key.into(), value.into());
+    tags
+}
+
+async fn read_value(path: &Path) -> Option<u64> {
+    tokio::fs::read_to_string(path)
+        .await
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+async fn read_keyed(path: &Path) -> Option<BTreeMap<String, u64>> {
+    let contents = tokio::fs::read_to_string(path).await.ok()?;
+    Some(parse_keyed(&contents))
+}
+
+/// Parses the `key value` lines of files like `cpu.stat` and `memory.stat`.
+fn parse_keyed(contents: &str) -> BTreeMap<String, u64> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let key = parts.next()?;
+            let value = parts.next()?.parse().ok()?;
+            Some((key.to_owned(), value))
+        })
+        .collect()
+}
+
+/// Parses the v2 `io.stat`, which has one line per device like
+/// `8:0 rbytes=1024 wbytes=4096 rios=1 wios=2 dbytes=0 dios=0`.
+fn parse_io_stat(contents: &str) -> IoStats {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let device = parts.next()?;
+            let (mut read, mut written) = (0, 0);
+            for part in parts {
+                let mut pair = part.splitn(2, '=');
+                match (pair.next(), pair.next().and_then(|v| v.parse().ok())) {
+                    (Some("rbytes"), Some(value)) => read = value,
+                    (Some("wbytes"), Some(value)) => written = value,
+                    _ => (),
+                }
+            }
+            Some((device.to_owned(), (read, written)))
+        })
+        .collect()
+}
+
+/// Parses the v1 `blkio.throttle.io_service_bytes`, which has one line per
+/// device and operation like `8:0 Read 1024`, followed by a `Total` line.
+fn parse_blkio_stat(contents: &str) -> IoStats {
+    let mut stats = IoStats::new();
+    for line in contents.lines() {
+        let parts: Vec<_> = line.split_whitespace().collect();
+        if let [device, op, value] = parts[..] {
+            if let Ok(value) = value.parse() {
+                let entry = stats.entry(device.to_owned()).or_default();
+                match op {
+                    "Read" => entry.0 = value,
+                    "Write" => entry.1 = value,
+                    _ => (),
+                }
+            }
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PatternWrapper;
+    use super::*;
+    use crate::event::metric::MetricValue;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write(dir: &Path, file: &str, contents: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(file), contents).unwrap();
+    }
+
+    fn find<'a>(metrics: &'a [Metric], name: &str, cgroup: &str) -> Vec<&'a Metric> {
+        metrics
+            .iter()
+            .filter(|metric| {
+                metric.name == name && metric.tags.as_ref().unwrap()["cgroup"] == cgroup
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn generates_cgroups_v2_metrics() {
+        let root = tempdir().unwrap();
+        write(root.path(), "cgroup.controllers", "cpu io memory\n");
+        write(
+            root.path(),
+            "cpu.stat",
+            "usage_usec 2000000\nuser_usec 1500000\nsystem_usec 500000\n",
+        );
+        let service = root.path().join("system.slice/vector.service");
+        write(&service, "cpu.stat", "usage_usec 1000000\n");
+        write(&service, "memory.current", "4096\n");
+        write(&service, "memory.stat", "anon 1024\nfile 2048\n");
+        write(
+            &service,
+            "io.stat",
+            "8:0 rbytes=100 wbytes=200 rios=1 wios=2 dbytes=0 dios=0\n",
+        );
+
+        let metrics = HostMetricsConfig::default()
+            .cgroups_metrics_at(root.path())
+            .await;
+
+        assert_eq!(
+            find(&metrics, "cgroup_cpu_usage_seconds_total", "/").len(),
+            1
+        );
+        assert_eq!(find(&metrics, "cgroup_cpu_seconds_total", "/").len(), 2);
+
+        let name = "/system.slice/vector.service";
+        assert_eq!(
+            find(&metrics, "cgroup_cpu_usage_seconds_total", name)[0].value,
+            MetricValue::Counter { value: 1.0 }
+        );
+        assert_eq!(
+            find(&metrics, "cgroup_memory_current_bytes", name)[0].value,
+            MetricValue::Gauge { value: 4096.0 }
+        );
+        assert_eq!(
+            find(&metrics, "cgroup_memory_anon_bytes", name)[0].value,
+            MetricValue::Gauge { value: 1024.0 }
+        );
+        let written = find(&metrics, "cgroup_io_written_bytes_total", name);
+        assert_eq!(written[0].value, MetricValue::Counter { value: 200.0 });
+        assert_eq!(written[0].tags.as_ref().unwrap()["device"], "8:0");
+
+        // The intermediate slice has no stats files of its own.
+        assert!(find(&metrics, "cgroup_cpu_usage_seconds_total", "/system.slice").is_empty());
+    }
+
+    #[tokio::test]
+    async fn generates_cgroups_v1_metrics() {
+        let root = tempdir().unwrap();
+        let cpuacct = root.path().join("cpuacct/docker");
+        write(&cpuacct, "cpuacct.usage", "3000000000\n");
+        write(&cpuacct, "cpuacct.stat", "user 200\nsystem 100\n");
+        let memory = root.path().join("memory/docker");
+        write(&memory, "memory.usage_in_bytes", "8192\n");
+        write(
+            &memory,
+            "memory.stat",
+            "rss 1\ncache 2\ntotal_rss 1024\ntotal_cache 2048\n",
+        );
+        let blkio = root.path().join("blkio/docker");
+        write(
+            &blkio,
+            "blkio.throttle.io_service_bytes",
+            "8:0 Read 100\n8:0 Write 200\n8:0 Sync 300\n8:0 Async 0\n8:0 Total 300\nTotal 300\n",
+        );
+
+        let metrics = HostMetricsConfig::default()
+            .cgroups_metrics_at(root.path())
+            .await;
+
+        assert_eq!(
+            find(&metrics, "cgroup_cpu_usage_seconds_total", "/docker")[0].value,
+            MetricValue::Counter { value: 3.0 }
+        );
+        let cpu = find(&metrics, "cgroup_cpu_seconds_total", "/docker");
+        assert_eq!(cpu.len(), 2);
+        assert!(cpu
+            .iter()
+            .any(|metric| metric.value == MetricValue::Counter { value: 2.0 }
+                && metric.tags.as_ref().unwrap()["mode"] == "user"));
+        assert_eq!(
+            find(&metrics, "cgroup_memory_current_bytes", "/docker")[0].value,
+            MetricValue::Gauge { value: 8192.0 }
+        );
+        assert_eq!(
+            find(&metrics, "cgroup_memory_file_bytes", "/docker")[0].value,
+            MetricValue::Gauge { value: 2048.0 }
+        );
+        assert_eq!(
+            find(&metrics, "cgroup_io_read_bytes_total", "/docker")[0].value,
+            MetricValue::Counter { value: 100.0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn cgroups_honors_base_levels_and_filters() {
+        let root = tempdir().unwrap();
+        write(root.path(), "cgroup.controllers", "cpu\n");
+        for group in &["a", "a/b", "a/b/c", "d"] {
+            write(&root.path().join(group), "cpu.stat", "usage_usec 1\n");
+        }
+
+        let config = HostMetricsConfig {
+            cgroups: CgroupsConfig {
+                base: Some("/a".into()),
+                levels: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(cgroup_names(config, root.path()).await, vec!["/a", "/a/b"]);
+
+        let config = HostMetricsConfig {
+            cgroups: CgroupsConfig {
+                groups: FilterList {
+                    includes: Some(vec![PatternWrapper::new("/a*").unwrap()]),
+                    excludes: Some(vec![PatternWrapper::new("/a/b/c").unwrap()]),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(cgroup_names(config, root.path()).await, vec!["/a", "/a/b"]);
+    }
+
+    async fn cgroup_names(config: HostMetricsConfig, root: &Path) -> Vec<String> {
+        let mut names: Vec<_> = config
+            .cgroups_metrics_at(root)
+            .await
+            .into_iter()
+            .map(|metric| metric.tags.unwrap()["cgroup"].clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn parses_io_stats() {
+        let stats = parse_io_stat("8:0 rbytes=1 wbytes=2 rios=3 wios=4\n253:1 rbytes=5 wbytes=6\n");
+        assert_eq!(stats["8:0"], (1, 2));
+        assert_eq!(stats["253:1"], (5, 6));
+
+        let stats = parse_blkio_stat("8:0 Read 1\n8:0 Write 2\n8:0 Total 3\nTotal 3\n");
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats["8:0"], (1, 2));
+    }
+}
@@ -42,15 +42,42 @@ use std::path::Path;
 use std::time::Duration;
 use tokio::{select, time};
 
+macro_rules! tags {
+    ( $( $key:expr => $value:expr ),* ) => {{
+        #[allow(unused_mut)]
+        let mut result = std::collections::BTreeMap::default();
+        $( result.insert($key.to_string(), $value.to_string()); )*
+            result
+    }}
+}
+
+mod cgroups;
+mod process;
+mod tcp;
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 enum Collector {
+    Cgroups,
     Cpu,
     Disk,
     Filesystem,
     Load,
     Memory,
     Network,
+    Process,
+    Tcp,
+}
+
+impl Collector {
+    /// The collectors added after the original set, such as the per-process
+    /// metrics which can be numerous, are only used when listed explicitly.
+    fn is_default(self) -> bool {
+        !matches!(
+            self,
+            Collector::Cgroups | Collector::Process | Collector::Tcp
+        )
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -100,12 +127,16 @@ pub struct HostMetricsConfig {
     #[serde(default)]
     namespace: Namespace,
 
+    #[serde(default)]
+    cgroups: cgroups::CgroupsConfig,
     #[serde(default)]
     disk: DiskConfig,
     #[serde(default)]
     filesystem: FilesystemConfig,
     #[serde(default)]
     network: NetworkConfig,
+    #[serde(default)]
+    process: process::ProcessConfig,
 }
 
 const fn default_scrape_interval() -> u64 {
@@ -143,15 +174,6 @@ impl SourceConfig for HostMetricsConfig {
     }
 }
 
-macro_rules! tags {
-    ( $( $key:expr => $value:expr ),* ) => {{
-        #[allow(unused_mut)]
-        let mut result = std::collections::BTreeMap::default();
-        $( result.insert($key.to_string(), $value.to_string()); )*
-            result
-    }}
-}
-
 impl HostMetricsConfig {
     async fn run(self, mut out: Pipeline, mut shutdown: ShutdownSignal) -> Result<(), ()> {
         let interval = Duration::from_secs(self.scrape_interval_secs);
@@ -179,7 +201,7 @@ impl HostMetricsConfig {
 
     fn has_collector(&self, collector: Collector) -> bool {
         match &self.collectors {
-            None => true,
+            None => collector.is_default(),
             Some(collectors) => collectors.iter().any(|&c| c == collector),
         }
     }
@@ -187,6 +209,9 @@ impl HostMetricsConfig {
     async fn capture_metrics(&self) -> impl Iterator<Item = Event> {
         let hostname = crate::get_hostname();
         let mut metrics = Vec::new();
+        if self.has_collector(Collector::Cgroups) {
+            metrics.extend(add_collector("cgroups", self.cgroups_metrics().await));
+        }
         if self.has_collector(Collector::Cpu) {
             metrics.extend(add_collector("cpu", self.cpu_metrics().await));
         }
@@ -206,6 +231,12 @@ impl HostMetricsConfig {
         if self.has_collector(Collector::Network) {
             metrics.extend(add_collector("network", self.network_metrics().await));
         }
+        if self.has_collector(Collector::Process) {
+            metrics.extend(add_collector("process", self.process_metrics().await));
+        }
+        if self.has_collector(Collector::Tcp) {
+            metrics.extend(add_collector("tcp", self.tcp_metrics().await));
+        }
         if let Ok(hostname) = &hostname {
             for metric in &mut metrics {
                 (metric.tags.as_mut().unwrap()).insert("host".into(), hostname.into());
@@ -886,6 +917,15 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn opt_in_collectors_are_not_default() {
+        let mut metrics = HostMetricsConfig::default().capture_metrics().await;
+        assert!(!metrics.any(|event| {
+            let collector = &event.into_metric().tags.unwrap()["collector"];
+            collector == "cgroups" || collector == "process" || collector == "tcp"
+        }));
+    }
+
     #[tokio::test]
     async fn are_taged_with_hostname() {
         let mut metrics = HostMetricsConfig::default().capture_metrics().await;
@@ -1092,6 +1132,40 @@ mod tests {
             .any(|metric| !metric.name.starts_with("load")));
     }
 
+    #[tokio::test]
+    async fn generates_process_metrics() {
+        let metrics = HostMetricsConfig::default().process_metrics().await;
+        assert!(!metrics.is_empty());
+        assert!(!metrics
+            .iter()
+            .any(|metric| !metric.name.starts_with("process_")));
+        assert_eq!(count_tag(&metrics, "name"), metrics.len());
+        assert_eq!(count_tag(&metrics, "pid"), metrics.len());
+
+        // Our own process is among them
+        let pid = std::process::id().to_string();
+        let name = metrics
+            .iter()
+            .map(|metric| metric.tags.as_ref().unwrap())
+            .find(|tags| tags["pid"] == pid)
+            .map(|tags| tags["name"].clone())
+            .expect("Missing metrics for the current process");
+
+        let filtered = HostMetricsConfig {
+            process: process::ProcessConfig {
+                names: FilterList {
+                    includes: Some(vec![PatternWrapper::new(Pattern::escape(&name)).unwrap()]),
+                    excludes: None,
+                },
+            },
+            ..Default::default()
+        }
+        .process_metrics()
+        .await;
+        assert!(all_tags_match(&filtered, "name", |s| s == name));
+        assert!(collect_tag_values(&filtered, "pid").contains(&pid));
+    }
+
     fn all_counters(metrics: &[Metric]) -> bool {
         !metrics
             .iter()
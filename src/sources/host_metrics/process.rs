@@ -0,0 +1,101 @@
+use super::{FilterList, HostMetricsConfig};
+use crate::event::metric::Metric;
+use chrono::Utc;
+use futures::{pin_mut, StreamExt};
+use heim::{
+    process::{Pid, Process},
+    units::{information::byte, time::second},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub(super) struct ProcessConfig {
+    #[serde(default)]
+    pub(super) names: FilterList,
+}
+
+impl HostMetricsConfig {
+    pub async fn process_metrics(&self) -> Vec<Metric> {
+        match heim::process::processes().await {
+            Ok(processes) => {
+                pin_mut!(processes);
+                let mut metrics = Vec::new();
+                while let Some(process) = processes.next().await {
+                    // Processes routinely exit while they are being
+                    // inspected, so failures for a single process are
+                    // expected and not worth reporting.
+                    if let Ok(process) = process {
+                        if let Some(process_metrics) = self.single_process_metrics(process).await {
+                            metrics.extend(process_metrics);
+                        }
+                    }
+                }
+                metrics
+            }
+            Err(error) => {
+                error!(message = "Failed to load process list.", %error, rate_limit_secs = 60);
+                vec![]
+            }
+        }
+    }
+
+    async fn single_process_metrics(&self, process: Process) -> Option<Vec<Metric>> {
+        let name = process.name().await.ok()?;
+        if !self.process.names.contains_str(&name) {
+            return None;
+        }
+        let cpu_time = process.cpu_time().await.ok()?;
+        let memory = process.memory().await.ok()?;
+
+        let timestamp = Utc::now();
+        let tags = tags!["name" => name, "pid" => process.pid()];
+        let mode_tags = |mode: &str| {
+            let mut tags = tags.clone();
+            tags.insert("mode".into(), mode.into());
+            tags
+        };
+        let mut metrics = vec![
+            self.counter(
+                "process_cpu_seconds_total",
+                timestamp,
+                cpu_time.user().get::<second>(),
+                mode_tags("user"),
+            ),
+            self.counter(
+                "process_cpu_seconds_total",
+                timestamp,
+                cpu_time.system().get::<second>(),
+                mode_tags("system"),
+            ),
+            self.gauge(
+                "process_memory_rss_bytes",
+                timestamp,
+                memory.rss().get::<byte>() as f64,
+                tags.clone(),
+            ),
+            self.gauge(
+                "process_memory_virtual_bytes",
+                timestamp,
+                memory.vms().get::<byte>() as f64,
+                tags.clone(),
+            ),
+        ];
+        if let Some(open_fds) = open_fds(process.pid()).await {
+            metrics.push(self.gauge("process_open_fds", timestamp, open_fds as f64, tags));
+        }
+        Some(metrics)
+    }
+}
+
+/// Counts the entries of `/proc/<pid>/fd`. This is only available on
+/// Linux, and only for processes the current user may inspect.
+async fn open_fds(pid: Pid) -> Option<usize> {
+    let mut entries = tokio::fs::read_dir(format!("/proc/{}/fd", pid))
+        .await
+        .ok()?;
+    let mut count = 0;
+    while entries.next_entry().await.ok()?.is_some() {
+        count += 1;
+    }
+    Some(count)
+}
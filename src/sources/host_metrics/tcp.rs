@@ -0,0 +1,108 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use chrono::Utc;
+use std::io;
+
+/// The connection states in the order of their numeric values in
+/// `/proc/net/tcp`, starting at 1.
+const TCP_STATES: [&str; 11] = [
+    "established",
+    "syn_sent",
+    "syn_recv",
+    "fin_wait1",
+    "fin_wait2",
+    "time_wait",
+    "close",
+    "close_wait",
+    "last_ack",
+    "listen",
+    "closing",
+];
+
+const PROC_NET_TCP: [&str; 2] = ["/proc/net/tcp", "/proc/net/tcp6"];
+
+impl HostMetricsConfig {
+    pub async fn tcp_metrics(&self) -> Vec<Metric> {
+        let mut counts = [0usize; TCP_STATES.len()];
+        let mut found = false;
+        for path in &PROC_NET_TCP {
+            match tokio::fs::read_to_string(path).await {
+                Ok(contents) => {
+                    found = true;
+                    count_tcp_states(&contents, &mut counts);
+                }
+                // The connection tables only exist on Linux, and the IPv6 one
+                // only when IPv6 is enabled.
+                Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+                Err(error) => {
+                    error!(message = "Failed to load TCP connection info.", %path, %error, rate_limit_secs = 60);
+                }
+            }
+        }
+        if !found {
+            return vec![];
+        }
+
+        let timestamp = Utc::now();
+        TCP_STATES
+            .iter()
+            .zip(counts.iter())
+            .map(|(state, count)| {
+                self.gauge(
+                    "tcp_connections",
+                    timestamp,
+                    *count as f64,
+                    tags!["state" => state],
+                )
+            })
+            .collect()
+    }
+}
+
+/// Tallies the connections listed in a `/proc/net/tcp` style table by state.
+fn count_tcp_states(contents: &str, counts: &mut [usize; TCP_STATES.len()]) {
+    for line in contents.lines().skip(1) {
+        let state = line
+            .split_whitespace()
+            .nth(3)
+            .and_then(|state| u8::from_str_radix(state, 16).ok());
+        if let Some(state @ 1..=11) = state {
+            counts[state as usize - 1] += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_tcp_states() {
+        let contents = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 20520 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 19043 1 0000000000000000 100 0 0 10 0
+   2: 0F02000A:A5B8 2A1F1F8C:01BB 01 00000000:00000000 02:000007A5 00000000  1000        0 54231 2 0000000000000000 20 4 30 10 -1
+   3: 0F02000A:C2F0 5D2B0E8E:01BB 06 00000000:00000000 03:00001477 00000000     0        0 0 3 0000000000000000
+";
+        let mut counts = [0; TCP_STATES.len()];
+        count_tcp_states(contents, &mut counts);
+
+        assert_eq!(counts[0], 1, "established");
+        assert_eq!(counts[5], 1, "time_wait");
+        assert_eq!(counts[9], 2, "listen");
+        assert_eq!(counts.iter().sum::<usize>(), 4);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn generates_tcp_metrics() {
+        let metrics = HostMetricsConfig::default().tcp_metrics().await;
+        assert_eq!(metrics.len(), TCP_STATES.len());
+        assert!(metrics
+            .iter()
+            .all(|metric| metric.name == "tcp_connections"));
+        assert!(metrics
+            .iter()
+            .all(|metric| metric.tags.as_ref().unwrap().contains_key("state")));
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     },
     event::{Event, Value},
     shutdown::ShutdownSignal,
-    sources::util::{add_query_parameters, ErrorMessage, HttpSource, HttpSourceAuthConfig},
+    sources::util::{add_query_parameters, gelf, ErrorMessage, HttpSource, HttpSourceAuthConfig},
     tls::TlsConfig,
     Pipeline,
 };
@@ -65,6 +65,7 @@ pub enum Encoding {
     Text,
     Ndjson,
     Json,
+    Gelf,
 }
 
 impl HttpSource for SimpleHttpSource {
@@ -177,6 +178,11 @@ fn decode_body(body: Bytes, enc: Encoding) -> Result<Vec<Event>, ErrorMessage> {
                 .map_err(|error| json_error(format!("Error parsing Json: {:?}", error)))?;
             json_parse_array_of_object(parsed_json)
         }
+        Encoding::Gelf => gelf::parse(body, gelf::DEFAULT_MAX_LENGTH)
+            .map(|event| vec![event])
+            .map_err(|error| {
+                ErrorMessage::new(StatusCode::BAD_REQUEST, format!("Bad GELF: {}", error))
+            }),
     }
 }
 
@@ -425,6 +431,30 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn http_gelf() {
+        trace_init();
+
+        let (rx, addr) = source(Encoding::Gelf, vec![], vec![]).await;
+
+        assert_eq!(
+            200,
+            send(
+                addr,
+                r#"{"version":"1.1","host":"example.org","short_message":"hello","_team":"core"}"#
+            )
+            .await
+        );
+        assert_eq!(400, send(addr, r#"{"version":"1.1"}"#).await);
+
+        let events = collect_n(rx, 1).await.unwrap();
+        let log = events[0].as_log();
+        assert_eq!(log[log_schema().message_key()], "hello".into());
+        assert_eq!(log[log_schema().host_key()], "example.org".into());
+        assert_eq!(log["team"], "core".into());
+        assert_eq!(log[log_schema().source_type_key()], "http".into());
+    }
+
     #[tokio::test]
     async fn http_json_dotted_keys() {
         trace_init();
@@ -1,5 +1,5 @@
 use crate::{
-    config::{log_schema, DataType, GlobalOptions, SourceConfig, SourceDescription},
+    config::{log_schema, DataDirLock, DataType, GlobalOptions, SourceConfig, SourceDescription},
     event::{Event, LogEvent, Value},
     internal_events::{JournaldEventReceived, JournaldInvalidRecord},
     shutdown::ShutdownSignal,
@@ -24,6 +24,7 @@ use serde_json::{Error as JsonError, Value as JsonValue};
 use snafu::{ResultExt, Snafu};
 use std::{
     collections::{HashMap, HashSet},
+    ffi::OsString,
     io::SeekFrom,
     iter::FromIterator,
     path::PathBuf,
@@ -43,7 +44,7 @@ use tracing_futures::Instrument;
 
 const DEFAULT_BATCH_SIZE: usize = 16;
 
-const CHECKPOINT_FILENAME: &str = "checkpoint.txt";
+pub(crate) const CHECKPOINT_FILENAME: &str = "checkpoint.txt";
 const CURSOR: &str = "__CURSOR";
 const HOSTNAME: &str = "_HOSTNAME";
 const MESSAGE: &str = "MESSAGE";
@@ -68,6 +69,16 @@ enum BuildError {
         unit
     ))]
     DuplicatedUnit { unit: String },
+    #[snafu(display(
+        "The value {:?} of the field {:?} is duplicated in both include_matches and exclude_matches",
+        value,
+        field
+    ))]
+    DuplicatedMatch { field: String, value: String },
+    #[snafu(display(
+        "Only one of `journal_directory`, `journal_files` and `journal_namespace` can be set"
+    ))]
+    ConflictingJournals,
 }
 
 #[derive(Deserialize, Serialize, Debug, Default)]
@@ -80,6 +91,11 @@ pub struct JournaldConfig {
     pub data_dir: Option<PathBuf>,
     pub batch_size: Option<usize>,
     pub journalctl_path: Option<PathBuf>,
+    pub journal_directory: Option<PathBuf>,
+    pub journal_files: Vec<PathBuf>,
+    pub journal_namespace: Option<String>,
+    pub include_matches: Matches,
+    pub exclude_matches: Matches,
     #[serde(default)]
     pub remap_priority: bool,
 }
@@ -92,6 +108,9 @@ impl_generate_config_from_default!(JournaldConfig);
 
 type Record = HashMap<String, String>;
 
+/// Journal field names mapped to the accepted values.
+type Matches = HashMap<String, HashSet<String>>;
+
 #[async_trait::async_trait]
 #[typetag::serde(name = "journald")]
 impl SourceConfig for JournaldConfig {
@@ -124,30 +143,56 @@ impl SourceConfig for JournaldConfig {
             return Err(BuildError::DuplicatedUnit { unit }.into());
         }
 
+        let include_matches = merge_units(&self.include_matches, include_units);
+        let exclude_matches = merge_units(&self.exclude_matches, exclude_units);
+        if let Some((field, value)) = find_duplicate_match(&include_matches, &exclude_matches) {
+            return Err(BuildError::DuplicatedMatch { field, value }.into());
+        }
+
+        let journals = [
+            self.journal_directory.is_some(),
+            !self.journal_files.is_empty(),
+            self.journal_namespace.is_some(),
+        ];
+        if journals.iter().filter(|&&set| set).count() > 1 {
+            return Err(BuildError::ConflictingJournals.into());
+        }
+
+        let lock = DataDirLock::acquire(&data_dir)?;
         let mut checkpoint_path = data_dir;
         checkpoint_path.push(CHECKPOINT_FILENAME);
 
-        let journalctl_path = self
-            .journalctl_path
-            .clone()
-            .unwrap_or_else(|| JOURNALCTL.clone());
+        let starter = StartJournalctl {
+            path: self
+                .journalctl_path
+                .clone()
+                .unwrap_or_else(|| JOURNALCTL.clone()),
+            journal_directory: self.journal_directory.clone(),
+            journal_files: self.journal_files.clone(),
+            journal_namespace: self.journal_namespace.clone(),
+            current_boot_only: self.current_boot_only.unwrap_or(true),
+            include_matches: include_matches.clone(),
+        };
 
         let batch_size = self.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
-        let current_boot_only = self.current_boot_only.unwrap_or(true);
 
-        let start: StartJournalctlFn =
-            Box::new(move |cursor| start_journalctl(&journalctl_path, current_boot_only, cursor));
+        let start: StartJournalctlFn = Box::new(move |cursor| starter.start(cursor));
+
+        let source = JournaldSource {
+            include_matches,
+            exclude_matches,
+            checkpoint_path,
+            batch_size,
+            remap_priority: self.remap_priority,
+            out: out.sink_compat(),
+        };
 
         Ok(Box::new(
-            JournaldSource {
-                include_units,
-                exclude_units,
-                checkpoint_path,
-                batch_size,
-                remap_priority: self.remap_priority,
-                out: out.sink_compat(),
+            async move {
+                // Held until the final checkpoint has been saved.
+                let _lock = lock;
+                source.run_shutdown(shutdown, start).await
             }
-            .run_shutdown(shutdown, start)
             .instrument(info_span!("journald-server"))
             .boxed()
             .compat(),
@@ -164,8 +209,8 @@ impl SourceConfig for JournaldConfig {
 }
 
 struct JournaldSource {
-    include_units: HashSet<String>,
-    exclude_units: HashSet<String>,
+    include_matches: Matches,
+    exclude_matches: Matches,
     checkpoint_path: PathBuf,
     batch_size: usize,
     remap_priority: bool,
@@ -275,7 +320,7 @@ impl JournaldSource {
                     }
                 };
 
-                let mut record = match decode_record(&bytes, self.remap_priority) {
+                let mut record = match decode_record(&bytes) {
                     Ok(record) => record,
                     Err(error) => {
                         emit!(JournaldInvalidRecord {
@@ -291,11 +336,18 @@ impl JournaldSource {
 
                 saw_record = true;
 
-                let unit = record.get(&*SYSTEMD_UNIT);
-                if filter_unit(unit, &self.include_units, &self.exclude_units) {
+                // `journalctl` already applies the include matches, but
+                // there is no match expression for the exclusions.
+                if filter_matches(&record, &self.include_matches, &self.exclude_matches) {
                     continue;
                 }
 
+                if self.remap_priority {
+                    if let Some(priority) = record.get_mut("PRIORITY") {
+                        remap_priority(priority);
+                    }
+                }
+
                 emit!(JournaldEventReceived {
                     byte_size: bytes.len()
                 });
@@ -344,43 +396,91 @@ type StartJournalctlFn = Box<
 
 type StopJournalctlFn = Box<dyn FnOnce() + Send>;
 
-fn start_journalctl(
-    path: &PathBuf,
+/// Everything needed to spawn `journalctl`, except the cursor.
+struct StartJournalctl {
+    path: PathBuf,
+    journal_directory: Option<PathBuf>,
+    journal_files: Vec<PathBuf>,
+    journal_namespace: Option<String>,
     current_boot_only: bool,
-    cursor: &Option<String>,
-) -> crate::Result<(BoxStream<'static, io::Result<Bytes>>, StopJournalctlFn)> {
-    let mut command = Command::new(path);
-    command.stdout(Stdio::piped());
-    command.arg("--follow");
-    command.arg("--all");
-    command.arg("--show-cursor");
-    command.arg("--output=json");
+    include_matches: Matches,
+}
 
-    if current_boot_only {
-        command.arg("--boot");
-    }
+impl StartJournalctl {
+    fn args(&self, cursor: &Option<String>) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec![
+            "--follow".into(),
+            "--all".into(),
+            "--show-cursor".into(),
+            "--output=json".into(),
+        ];
+
+        if let Some(dir) = &self.journal_directory {
+            let mut arg = OsString::from("--directory=");
+            arg.push(dir);
+            args.push(arg);
+        }
 
-    if let Some(cursor) = cursor {
-        command.arg(format!("--after-cursor={}", cursor));
-    } else {
-        // journalctl --follow only outputs a few lines without a starting point
-        command.arg("--since=2000-01-01");
-    }
+        for file in &self.journal_files {
+            let mut arg = OsString::from("--file=");
+            arg.push(file);
+            args.push(arg);
+        }
 
-    let mut child = command.spawn().context(JournalctlSpawn)?;
+        if let Some(namespace) = &self.journal_namespace {
+            args.push(format!("--namespace={}", namespace).into());
+        }
 
-    let stream = FramedRead::new(
-        child.stdout.take().unwrap(),
-        BytesDelimitedCodec::new(b'\n'),
-    )
-    .boxed();
+        if self.current_boot_only {
+            args.push("--boot".into());
+        }
 
-    let pid = Pid::from_raw(child.id() as i32);
-    let stop = Box::new(move || {
-        let _ = kill(pid, Signal::SIGTERM);
-    });
+        if let Some(cursor) = cursor {
+            args.push(format!("--after-cursor={}", cursor).into());
+        } else {
+            // journalctl --follow only outputs a few lines without a starting point
+            args.push("--since=2000-01-01".into());
+        }
+
+        // Matches on different fields must all apply, while the ones on the
+        // same field are alternatives, which is exactly the `journalctl`
+        // semantic. Sorted to keep the command line stable.
+        let mut fields = self.include_matches.iter().collect::<Vec<_>>();
+        fields.sort_by_key(|(field, _)| *field);
+        for (field, values) in fields {
+            let mut values = values.iter().collect::<Vec<_>>();
+            values.sort();
+            for value in values {
+                args.push(format!("{}={}", field, value).into());
+            }
+        }
+
+        args
+    }
 
-    Ok((stream, stop))
+    fn start(
+        &self,
+        cursor: &Option<String>,
+    ) -> crate::Result<(BoxStream<'static, io::Result<Bytes>>, StopJournalctlFn)> {
+        let mut command = Command::new(&self.path);
+        command.stdout(Stdio::piped());
+        command.args(self.args(cursor));
+
+        let mut child = command.spawn().context(JournalctlSpawn)?;
+
+        let stream = FramedRead::new(
+            child.stdout.take().unwrap(),
+            BytesDelimitedCodec::new(b'\n'),
+        )
+        .boxed();
+
+        let pid = Pid::from_raw(child.id() as i32);
+        let stop = Box::new(move || {
+            let _ = kill(pid, Signal::SIGTERM);
+        });
+
+        Ok((stream, stop))
+    }
 }
 
 fn create_event(record: Record) -> Event {
@@ -423,7 +523,7 @@ fn fixup_unit(unit: &str) -> String {
     }
 }
 
-fn decode_record(line: &[u8], remap: bool) -> Result<Record, JsonError> {
+fn decode_record(line: &[u8]) -> Result<Record, JsonError> {
     let mut record = serde_json::from_str::<JsonValue>(&String::from_utf8_lossy(line))?;
     // journalctl will output non-ASCII values using an array
     // of integers. Look for those values and re-parse them.
@@ -434,9 +534,6 @@ fn decode_record(line: &[u8], remap: bool) -> Result<Record, JsonError> {
             }
         }
     }
-    if remap {
-        record.get_mut("PRIORITY").map(remap_priority);
-    }
     serde_json::from_value(record)
 }
 
@@ -456,8 +553,8 @@ fn decode_array(array: &[JsonValue]) -> Option<JsonValue> {
         .map(|array| String::from_utf8_lossy(&array).into())
 }
 
-fn remap_priority(priority: &mut JsonValue) {
-    if let Some(num) = priority.as_str().and_then(|s| usize::from_str(s).ok()) {
+fn remap_priority(priority: &mut String) {
+    if let Ok(num) = usize::from_str(priority) {
         let text = match num {
             0 => "EMERG",
             1 => "ALERT",
@@ -469,32 +566,57 @@ fn remap_priority(priority: &mut JsonValue) {
             7 => "DEBUG",
             _ => "UNKNOWN",
         };
-        *priority = JsonValue::String(text.into());
+        *priority = text.into();
     }
 }
 
-/// Should the given unit name be filtered (excluded)?
-fn filter_unit(
-    unit: Option<&String>,
-    includes: &HashSet<String>,
-    excludes: &HashSet<String>,
-) -> bool {
-    match (unit, includes.is_empty(), excludes.is_empty()) {
-        (None, empty, _) => !empty,
-        (Some(_), true, true) => false,
-        (Some(unit), false, true) => !includes.contains(unit),
-        (Some(unit), true, false) => excludes.contains(unit),
-        (Some(unit), false, false) => !includes.contains(unit) || excludes.contains(unit),
+/// Adds the units to the matches on the `_SYSTEMD_UNIT` field.
+fn merge_units(matches: &Matches, units: HashSet<String>) -> Matches {
+    let mut matches = matches.clone();
+    if !units.is_empty() {
+        matches
+            .entry(SYSTEMD_UNIT.into())
+            .or_default()
+            .extend(units);
     }
+    matches
+}
+
+fn find_duplicate_match(includes: &Matches, excludes: &Matches) -> Option<(String, String)> {
+    includes.iter().find_map(|(field, values)| {
+        let excluded = excludes.get(field)?;
+        values
+            .iter()
+            .find(|value| excluded.contains(*value))
+            .map(|value| (field.clone(), value.clone()))
+    })
 }
 
-struct Checkpointer {
+/// Should the given record be filtered (excluded)?
+///
+/// A record is included if, for every included field, it has one of the
+/// included values, and excluded if it has any of the excluded values.
+fn filter_matches(record: &Record, includes: &Matches, excludes: &Matches) -> bool {
+    let included = includes.iter().all(|(field, values)| {
+        record
+            .get(field)
+            .map_or(false, |value| values.contains(value))
+    });
+    let excluded = excludes.iter().any(|(field, values)| {
+        record
+            .get(field)
+            .map_or(false, |value| values.contains(value))
+    });
+    !included || excluded
+}
+
+pub(crate) struct Checkpointer {
     file: File,
     filename: PathBuf,
 }
 
 impl Checkpointer {
-    async fn new(filename: PathBuf) -> Result<Self, io::Error> {
+    pub(crate) async fn new(filename: PathBuf) -> Result<Self, io::Error> {
         let file = OpenOptions::new()
             .read(true)
             .write(true)
@@ -504,7 +626,7 @@ impl Checkpointer {
         Ok(Checkpointer { file, filename })
     }
 
-    async fn set(&mut self, token: &str) -> Result<(), io::Error> {
+    pub(crate) async fn set(&mut self, token: &str) -> Result<(), io::Error> {
         self.file.seek(SeekFrom::Start(0)).await?;
         self.file
             .write_all(format!("{}\n", token).as_bytes())
@@ -512,7 +634,7 @@ impl Checkpointer {
         Ok(())
     }
 
-    async fn get(&mut self) -> Result<Option<String>, io::Error> {
+    pub(crate) async fn get(&mut self) -> Result<Option<String>, io::Error> {
         let mut buf = Vec::<u8>::new();
         self.file.seek(SeekFrom::Start(0)).await?;
         self.file.read_to_end(&mut buf).await?;
@@ -646,6 +768,16 @@ mod tests {
     }
 
     async fn run_journal(iunits: &[&str], xunits: &[&str], cursor: Option<&str>) -> Vec<Event> {
+        let include_matches = merge_units(&Matches::new(), set(iunits));
+        let exclude_matches = merge_units(&Matches::new(), set(xunits));
+        run_journal_matches(include_matches, exclude_matches, cursor).await
+    }
+
+    async fn run_journal_matches(
+        include_matches: Matches,
+        exclude_matches: Matches,
+        cursor: Option<&str>,
+    ) -> Vec<Event> {
         let (tx, rx) = Pipeline::new_test();
         let (trigger, shutdown, _) = ShutdownSignal::new_wired();
 
@@ -664,12 +796,9 @@ mod tests {
                 .expect("Could not set checkpoint");
         }
 
-        let include_units = HashSet::<String>::from_iter(iunits.iter().map(|&s| s.into()));
-        let exclude_units = HashSet::<String>::from_iter(xunits.iter().map(|&s| s.into()));
-
         let source = JournaldSource {
-            include_units,
-            exclude_units,
+            include_matches,
+            exclude_matches,
             checkpoint_path,
             batch_size: DEFAULT_BATCH_SIZE,
             remap_priority: true,
@@ -765,26 +894,162 @@ mod tests {
         assert_eq!(timestamp(&received[1]), value_ts(1578529839, 140005000));
     }
 
+    #[tokio::test]
+    async fn includes_matches() {
+        let matches = create_matches(vec![("PRIORITY", "7"), ("PRIORITY", "5")]);
+        let received = run_journal_matches(matches, Matches::new(), None).await;
+        assert_eq!(received.len(), 2);
+        assert_eq!(message(&received[0]), Value::Bytes("unit message".into()));
+        assert_eq!(priority(&received[0]), Value::Bytes("DEBUG".into()));
+        assert_eq!(message(&received[1]), Value::Bytes("¿Hello?".into()));
+    }
+
+    #[tokio::test]
+    async fn excludes_matches() {
+        let matches = create_matches(vec![("PRIORITY", "3"), ("_SYSTEMD_UNIT", "unit.service")]);
+        let received = run_journal_matches(Matches::new(), matches, None).await;
+        assert_eq!(received.len(), 3);
+        assert_eq!(
+            message(&received[0]),
+            Value::Bytes("System Initialization".into())
+        );
+        assert_eq!(message(&received[1]), Value::Bytes("¿Hello?".into()));
+        assert_eq!(
+            message(&received[2]),
+            Value::Bytes("Missing timestamp".into())
+        );
+    }
+
+    #[test]
+    fn filter_matches_works_correctly() {
+        let empty = Matches::new();
+        let includes = create_matches(vec![("one", "1"), ("two", "2")]);
+        let excludes = create_matches(vec![("foo", "bar"), ("foo", "baz")]);
+
+        let zero = create_record(vec![("zero", "0")]);
+        assert_eq!(filter_matches(&zero, &empty, &empty), false);
+        assert_eq!(filter_matches(&zero, &includes, &empty), true);
+        assert_eq!(filter_matches(&zero, &empty, &excludes), false);
+        assert_eq!(filter_matches(&zero, &includes, &excludes), true);
+        let one = create_record(vec![("one", "1")]);
+        assert_eq!(filter_matches(&one, &empty, &empty), false);
+        assert_eq!(filter_matches(&one, &includes, &empty), true);
+        assert_eq!(filter_matches(&one, &empty, &excludes), false);
+        assert_eq!(filter_matches(&one, &includes, &excludes), true);
+        let both = create_record(vec![("one", "1"), ("two", "2")]);
+        assert_eq!(filter_matches(&both, &empty, &empty), false);
+        assert_eq!(filter_matches(&both, &includes, &empty), false);
+        assert_eq!(filter_matches(&both, &empty, &excludes), false);
+        assert_eq!(filter_matches(&both, &includes, &excludes), false);
+        let bar = create_record(vec![("one", "1"), ("two", "2"), ("foo", "bar")]);
+        assert_eq!(filter_matches(&bar, &empty, &empty), false);
+        assert_eq!(filter_matches(&bar, &includes, &empty), false);
+        assert_eq!(filter_matches(&bar, &empty, &excludes), true);
+        assert_eq!(filter_matches(&bar, &includes, &excludes), true);
+    }
+
+    #[test]
+    fn merges_units_into_matches() {
+        let matches = create_matches(vec![("_SYSTEMD_UNIT", "one.service"), ("PRIORITY", "3")]);
+        let merged = merge_units(&matches, set(&["two.service"]));
+        assert_eq!(
+            merged,
+            create_matches(vec![
+                ("_SYSTEMD_UNIT", "one.service"),
+                ("_SYSTEMD_UNIT", "two.service"),
+                ("PRIORITY", "3")
+            ])
+        );
+        assert_eq!(merge_units(&matches, HashSet::new()), matches);
+    }
+
+    #[test]
+    fn finds_duplicate_matches() {
+        let includes = create_matches(vec![("PRIORITY", "3"), ("PRIORITY", "4")]);
+        let excludes = create_matches(vec![("PRIORITY", "4"), ("_SYSTEMD_UNIT", "3")]);
+        assert_eq!(
+            find_duplicate_match(&includes, &excludes),
+            Some(("PRIORITY".into(), "4".into()))
+        );
+        assert_eq!(find_duplicate_match(&includes, &Matches::new()), None);
+    }
+
     #[test]
-    fn filter_unit_works_correctly() {
-        let empty: HashSet<String> = vec![].into_iter().collect();
-        let includes: HashSet<String> = vec!["one", "two"].into_iter().map(Into::into).collect();
-        let excludes: HashSet<String> = vec!["foo", "bar"].into_iter().map(Into::into).collect();
-
-        assert_eq!(filter_unit(None, &empty, &empty), false);
-        assert_eq!(filter_unit(None, &includes, &empty), true);
-        assert_eq!(filter_unit(None, &empty, &excludes), false);
-        assert_eq!(filter_unit(None, &includes, &excludes), true);
-        let one = String::from("one");
-        assert_eq!(filter_unit(Some(&one), &empty, &empty), false);
-        assert_eq!(filter_unit(Some(&one), &includes, &empty), false);
-        assert_eq!(filter_unit(Some(&one), &empty, &excludes), false);
-        assert_eq!(filter_unit(Some(&one), &includes, &excludes), false);
-        let two = String::from("bar");
-        assert_eq!(filter_unit(Some(&two), &empty, &empty), false);
-        assert_eq!(filter_unit(Some(&two), &includes, &empty), true);
-        assert_eq!(filter_unit(Some(&two), &empty, &excludes), true);
-        assert_eq!(filter_unit(Some(&two), &includes, &excludes), true);
+    fn journalctl_args() {
+        let mut starter = StartJournalctl {
+            path: JOURNALCTL.clone(),
+            journal_directory: Some("/var/log/remote".into()),
+            journal_files: vec![],
+            journal_namespace: None,
+            current_boot_only: false,
+            include_matches: create_matches(vec![
+                ("_SYSTEMD_UNIT", "two.service"),
+                ("_SYSTEMD_UNIT", "one.service"),
+                ("PRIORITY", "3"),
+            ]),
+        };
+        assert_eq!(
+            starter.args(&Some("cursor".into())),
+            os_strings(&[
+                "--follow",
+                "--all",
+                "--show-cursor",
+                "--output=json",
+                "--directory=/var/log/remote",
+                "--after-cursor=cursor",
+                "PRIORITY=3",
+                "_SYSTEMD_UNIT=one.service",
+                "_SYSTEMD_UNIT=two.service",
+            ])
+        );
+
+        starter.journal_directory = None;
+        starter.journal_files = vec!["a.journal".into(), "b.journal".into()];
+        starter.current_boot_only = true;
+        starter.include_matches = Matches::new();
+        assert_eq!(
+            starter.args(&None),
+            os_strings(&[
+                "--follow",
+                "--all",
+                "--show-cursor",
+                "--output=json",
+                "--file=a.journal",
+                "--file=b.journal",
+                "--boot",
+                "--since=2000-01-01",
+            ])
+        );
+
+        starter.journal_files = vec![];
+        starter.journal_namespace = Some("audit".into());
+        assert!(starter.args(&None).contains(&"--namespace=audit".into()));
+    }
+
+    fn set(values: &[&str]) -> HashSet<String> {
+        HashSet::<String>::from_iter(values.iter().map(|&s| s.into()))
+    }
+
+    fn create_matches(pairs: Vec<(&str, &str)>) -> Matches {
+        let mut matches = Matches::new();
+        for (field, value) in pairs {
+            matches
+                .entry(field.into())
+                .or_insert_with(HashSet::new)
+                .insert(value.into());
+        }
+        matches
+    }
+
+    fn create_record(pairs: Vec<(&str, &str)>) -> Record {
+        pairs
+            .into_iter()
+            .map(|(field, value)| (field.into(), value.into()))
+            .collect()
+    }
+
+    fn os_strings(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(Into::into).collect()
     }
 
     fn message(event: &Event) -> Value {
@@ -1,9 +1,13 @@
 use crate::{
-    config::{log_schema, DataType, GlobalOptions, SourceConfig, SourceDescription},
+    config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
     event::{Event, Value},
-    internal_events::{KafkaEventFailed, KafkaEventReceived, KafkaOffsetUpdateFailed},
+    internal_events::{
+        KafkaEventFailed, KafkaEventReceived, KafkaOffsetUpdateFailed, KafkaPartitionsRevoked,
+        KafkaRebalance, KafkaStatisticsReceived,
+    },
     kafka::KafkaAuthConfig,
     shutdown::ShutdownSignal,
+    sources::util::message::decode_message,
     Pipeline,
 };
 use bytes::Bytes;
@@ -15,12 +19,16 @@ use futures::{
 use futures01::Sink;
 use rdkafka::{
     config::ClientConfig,
-    consumer::{Consumer, StreamConsumer},
-    message::Message,
+    consumer::{Consumer, ConsumerContext, Rebalance, StreamConsumer},
+    message::{Headers, Message},
+    ClientContext, Statistics,
 };
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
 
 #[derive(Debug, Snafu)]
 enum BuildError {
@@ -47,6 +55,12 @@ pub struct KafkaSourceConfig {
     #[serde(default = "default_commit_interval_ms")]
     commit_interval_ms: u64,
     key_field: Option<String>,
+    topic_key: Option<String>,
+    partition_key: Option<String>,
+    offset_key: Option<String>,
+    headers_key: Option<String>,
+    #[serde(default = "default_statistics_interval_ms")]
+    statistics_interval_ms: u64,
     librdkafka_options: Option<HashMap<String, String>>,
     #[serde(flatten)]
     auth: KafkaAuthConfig,
@@ -72,6 +86,10 @@ fn default_auto_offset_reset() -> String {
     "largest".into() // default in librdkafka
 }
 
+fn default_statistics_interval_ms() -> u64 {
+    10000
+}
+
 inventory::submit! {
     SourceDescription::new::<KafkaSourceConfig>("kafka")
 }
@@ -105,7 +123,7 @@ fn kafka_source(
     shutdown: ShutdownSignal,
     out: Pipeline,
 ) -> crate::Result<super::Source> {
-    let key_field = config.key_field.clone();
+    let keys = Keys::from(config);
     let consumer = Arc::new(create_consumer(config)?);
 
     let fut = async move {
@@ -113,7 +131,7 @@ fn kafka_source(
             .start()
             .take_until(shutdown.clone())
             .then(move |message| {
-                let key_field = key_field.clone();
+                let keys = keys.clone();
                 let consumer = Arc::clone(&consumer);
 
                 async move {
@@ -127,40 +145,8 @@ fn kafka_source(
                                 byte_size: msg.payload_len()
                             });
 
-                            let payload = match msg.payload() {
-                                None => return Err(()), // skip messages with empty payload
-                                Some(payload) => payload,
-                            };
-                            let mut event = Event::new_empty_log();
-                            let log = event.as_mut_log();
-
-                            log.insert(
-                                log_schema().message_key(),
-                                Value::from(Bytes::from(payload.to_owned())),
-                            );
-
-                            // Extract timestamp from kafka message
-                            let timestamp = msg
-                                .timestamp()
-                                .to_millis()
-                                .and_then(|millis| Utc.timestamp_millis_opt(millis).latest())
-                                .unwrap_or_else(Utc::now);
-                            log.insert(log_schema().timestamp_key(), timestamp);
-
-                            // Add source type
-                            log.insert(log_schema().source_type_key(), Bytes::from("kafka"));
-
-                            if let Some(key_field) = &key_field {
-                                match msg.key() {
-                                    None => (),
-                                    Some(key) => {
-                                        log.insert(
-                                            key_field,
-                                            Value::from(String::from_utf8_lossy(key).to_string()),
-                                        );
-                                    }
-                                }
-                            }
+                            // Skip messages with empty payload.
+                            let event = message_to_event(&msg, &keys).ok_or(())?;
 
                             consumer.store_offset(&msg).map_err(|error| {
                                 emit!(KafkaOffsetUpdateFailed { error });
@@ -194,7 +180,125 @@ fn kafka_source(
     Ok(Box::new(Compat::new(fut.boxed())))
 }
 
-fn create_consumer(config: &KafkaSourceConfig) -> crate::Result<StreamConsumer> {
+/// The fields the metadata of the messages are written to.
+#[derive(Clone, Debug, Default)]
+struct Keys {
+    key_field: Option<String>,
+    topic_key: Option<String>,
+    partition_key: Option<String>,
+    offset_key: Option<String>,
+    headers_key: Option<String>,
+}
+
+impl From<&KafkaSourceConfig> for Keys {
+    fn from(config: &KafkaSourceConfig) -> Self {
+        Self {
+            key_field: config.key_field.clone(),
+            topic_key: config.topic_key.clone(),
+            partition_key: config.partition_key.clone(),
+            offset_key: config.offset_key.clone(),
+            headers_key: config.headers_key.clone(),
+        }
+    }
+}
+
+fn message_to_event<M: Message>(msg: &M, keys: &Keys) -> Option<Event> {
+    let payload = msg.payload()?;
+    // Extract timestamp from kafka message
+    let timestamp = msg
+        .timestamp()
+        .to_millis()
+        .and_then(|millis| Utc.timestamp_millis_opt(millis).latest());
+    let mut event = decode_message("kafka", payload, timestamp);
+    let log = event.as_mut_log();
+
+    if let Some(key_field) = &keys.key_field {
+        match msg.key() {
+            None => (),
+            Some(key) => {
+                log.insert(
+                    key_field,
+                    Value::from(String::from_utf8_lossy(key).to_string()),
+                );
+            }
+        }
+    }
+    if let Some(topic_key) = &keys.topic_key {
+        log.insert(topic_key, msg.topic());
+    }
+    if let Some(partition_key) = &keys.partition_key {
+        log.insert(partition_key, msg.partition());
+    }
+    if let Some(offset_key) = &keys.offset_key {
+        log.insert(offset_key, msg.offset());
+    }
+    if let Some(headers_key) = &keys.headers_key {
+        let mut headers = BTreeMap::new();
+        if let Some(borrowed) = msg.headers() {
+            for index in 0..borrowed.count() {
+                if let Some((name, value)) = borrowed.get(index) {
+                    headers.insert(name.to_string(), Value::from(Bytes::copy_from_slice(value)));
+                }
+            }
+        }
+        log.insert(headers_key, Value::Map(headers));
+    }
+
+    Some(event)
+}
+
+/// Turns the statistics and the rebalances of the consumer into internal
+/// metrics.
+#[derive(Default)]
+struct KafkaSourceContext {
+    /// The partitions reported in the statistics, whose gauges are zeroed
+    /// when they are revoked.
+    partitions: Mutex<HashSet<(String, i32)>>,
+}
+
+impl ClientContext for KafkaSourceContext {
+    fn stats(&self, statistics: Statistics) {
+        emit!(KafkaStatisticsReceived {
+            statistics: &statistics
+        });
+
+        let mut partitions = self.partitions.lock().unwrap();
+        for (topic_id, topic) in &statistics.topics {
+            for (partition_id, partition) in &topic.partitions {
+                if *partition_id >= 0 {
+                    partitions.insert((topic_id.clone(), *partition_id));
+                }
+            }
+        }
+    }
+}
+
+impl ConsumerContext for KafkaSourceContext {
+    fn pre_rebalance(&self, rebalance: &Rebalance) {
+        emit!(KafkaRebalance {
+            kind: match rebalance {
+                Rebalance::Assign(_) => "assign",
+                Rebalance::Error(_) => "error",
+                _ => "revoke",
+            }
+        });
+
+        // The rebalances are eager, all the partitions are revoked before
+        // the new assignment.
+        if let Rebalance::Assign(_) | Rebalance::Error(_) = rebalance {
+            return;
+        }
+        let mut partitions = self.partitions.lock().unwrap();
+        emit!(KafkaPartitionsRevoked {
+            partitions: &partitions
+        });
+        partitions.clear();
+    }
+}
+
+fn create_consumer(
+    config: &KafkaSourceConfig,
+) -> crate::Result<StreamConsumer<KafkaSourceContext>> {
     let mut client_config = ClientConfig::new();
     client_config
         .set("group.id", &config.group_id)
@@ -210,6 +314,10 @@ fn create_consumer(config: &KafkaSourceConfig) -> crate::Result<StreamConsumer>
             &config.commit_interval_ms.to_string(),
         )
         .set("enable.auto.offset.store", "false")
+        .set(
+            "statistics.interval.ms",
+            &config.statistics_interval_ms.to_string(),
+        )
         .set("client.id", "vector");
 
     config.auth.apply(&mut client_config)?;
@@ -220,7 +328,9 @@ fn create_consumer(config: &KafkaSourceConfig) -> crate::Result<StreamConsumer>
         }
     }
 
-    let consumer: StreamConsumer = client_config.create().context(KafkaCreateError)?;
+    let consumer: StreamConsumer<KafkaSourceContext> = client_config
+        .create_with_context(KafkaSourceContext::default())
+        .context(KafkaCreateError)?;
     let topics: Vec<&str> = config.topics.iter().map(|s| s.as_str()).collect();
     consumer.subscribe(&topics).context(KafkaSubscribeError)?;
 
@@ -229,8 +339,11 @@ fn create_consumer(config: &KafkaSourceConfig) -> crate::Result<StreamConsumer>
 
 #[cfg(test)]
 mod test {
-    use super::{kafka_source, KafkaSourceConfig};
-    use crate::{shutdown::ShutdownSignal, Pipeline};
+    use super::{kafka_source, message_to_event, KafkaSourceConfig, Keys};
+    use crate::{config::log_schema, event::Value, shutdown::ShutdownSignal, Pipeline};
+    use bytes::Bytes;
+    use rdkafka::message::{OwnedHeaders, OwnedMessage, Timestamp};
+    use std::collections::BTreeMap;
 
     #[test]
     fn generate_config() {
@@ -258,6 +371,82 @@ mod test {
         assert!(kafka_source(&config, ShutdownSignal::noop(), Pipeline::new_test().0).is_ok());
     }
 
+    fn message() -> OwnedMessage {
+        OwnedMessage::new(
+            Some(b"my message".to_vec()),
+            Some(b"my key".to_vec()),
+            "my-topic".into(),
+            Timestamp::CreateTime(1_602_759_706_499),
+            3,
+            42,
+            Some(
+                OwnedHeaders::new()
+                    .add("trace-id", "abc")
+                    .add("tenant", &b"\x01\x02"[..]),
+            ),
+        )
+    }
+
+    #[test]
+    fn kafka_message_to_event() {
+        let event = message_to_event(
+            &message(),
+            &Keys {
+                key_field: Some("message_key".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let log = event.as_log();
+
+        assert_eq!(log[log_schema().message_key()], "my message".into());
+        assert_eq!(log["message_key"], "my key".into());
+        assert!(!log.contains("kafka.topic"));
+        assert!(!log.contains("headers"));
+    }
+
+    #[test]
+    fn kafka_message_to_event_metadata() {
+        let event = message_to_event(
+            &message(),
+            &Keys {
+                topic_key: Some("kafka.topic".into()),
+                partition_key: Some("kafka.partition".into()),
+                offset_key: Some("kafka.offset".into()),
+                headers_key: Some("headers".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let log = event.as_log();
+
+        assert_eq!(log["kafka.topic"], "my-topic".into());
+        assert_eq!(log["kafka.partition"], 3.into());
+        assert_eq!(log["kafka.offset"], 42.into());
+
+        let mut headers = BTreeMap::new();
+        headers.insert("trace-id".to_string(), Value::from("abc"));
+        headers.insert(
+            "tenant".to_string(),
+            Value::from(Bytes::from_static(b"\x01\x02")),
+        );
+        assert_eq!(log["headers"], Value::Map(headers));
+    }
+
+    #[test]
+    fn kafka_message_without_payload() {
+        let message = OwnedMessage::new(
+            None,
+            None,
+            "my-topic".into(),
+            Timestamp::NotAvailable,
+            0,
+            0,
+            None,
+        );
+        assert!(message_to_event(&message, &Keys::default()).is_none());
+    }
+
     #[test]
     fn kafka_source_create_incorrect_auto_offset_reset() {
         let config = KafkaSourceConfig {
@@ -273,6 +462,7 @@ mod test {
 mod integration_test {
     use super::*;
     use crate::{
+        config::log_schema,
         shutdown::ShutdownSignal,
         test_util::{collect_n, random_string},
         Pipeline,
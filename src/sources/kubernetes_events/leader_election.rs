@@ -0,0 +1,451 @@
+//! Leader election on top of a `coordination.k8s.io/v1` `Lease`.
+//!
+//! Follows the same protocol as the `client-go` leader election: the leader
+//! keeps renewing the lease, and the other candidates take it over once it
+//! hasn't been renewed for the lease duration.
+//!
+//! Like `client-go`, the expiry is measured on the local clock, from when a
+//! candidate first observed the current version of the lease, rather than
+//! from the `renewTime` written by the leader, so that clock skew between
+//! the nodes can't make a candidate take over a lease that is still held.
+
+#![deny(missing_docs)]
+
+use crate::{
+    http::HttpError, internal_events::KubernetesEventsLeaderElectionFailed, kubernetes as k8s,
+};
+use chrono::{DateTime, Utc};
+use http::{Request, StatusCode};
+use k8s_openapi::{
+    api::coordination::v1::{Lease, LeaseSpec, ReadNamespacedLeaseOptional},
+    apimachinery::pkg::apis::meta::v1::{MicroTime, ObjectMeta},
+    CreateOptional, ReplaceOptional, RequestError,
+};
+use snafu::{ResultExt, Snafu};
+use std::time::{Duration, Instant};
+use tokio::time::delay_for;
+
+/// Errors that can occur while talking to the Kubernetes API.
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// Returned when the lease request couldn't be built.
+    #[snafu(display("failed to prepare the lease request: {}", source))]
+    RequestPreparation {
+        /// The underlying error.
+        source: RequestError,
+    },
+
+    /// Returned when the lease request failed.
+    #[snafu(display("lease request failed: {}", source))]
+    Http {
+        /// The underlying error.
+        source: HttpError,
+    },
+
+    /// Returned when the lease response body couldn't be read.
+    #[snafu(display("failed to read the lease response: {}", source))]
+    ReadBody {
+        /// The underlying error.
+        source: hyper::Error,
+    },
+
+    /// Returned when the lease response body is not a valid `Lease`.
+    #[snafu(display("failed to parse the lease response: {}", source))]
+    Parse {
+        /// The underlying error.
+        source: serde_json::Error,
+    },
+
+    /// Returned when the API responds with an unexpected status.
+    #[snafu(display("unexpected lease response status: {}", status))]
+    BadStatus {
+        /// The response status.
+        status: StatusCode,
+    },
+}
+
+/// Competes for a `Lease` with the other candidates using the same lease.
+pub struct LeaderElector {
+    client: k8s::client::Client,
+    lease_name: String,
+    lease_namespace: String,
+    identity: String,
+    lease_duration: Duration,
+    renew_deadline: Duration,
+    retry_period: Duration,
+    observed: Option<Observation>,
+}
+
+/// The version of the lease last read by this candidate, and when it was
+/// first read.
+struct Observation {
+    resource_version: Option<String>,
+    renew_time: Option<MicroTime>,
+    at: Instant,
+}
+
+impl LeaderElector {
+    /// Create a new [`LeaderElector`].
+    pub fn new(
+        client: k8s::client::Client,
+        lease_name: String,
+        lease_namespace: String,
+        identity: String,
+        lease_duration: Duration,
+        renew_deadline: Duration,
+        retry_period: Duration,
+    ) -> Self {
+        Self {
+            client,
+            lease_name,
+            lease_namespace,
+            identity,
+            lease_duration,
+            renew_deadline,
+            retry_period,
+            observed: None,
+        }
+    }
+
+    /// How long the lease is valid for without renewals.
+    pub fn lease_duration(&self) -> Duration {
+        self.lease_duration
+    }
+
+    /// Resolves once this candidate holds the lease.
+    pub async fn acquire(&mut self) {
+        loop {
+            match self.try_acquire_or_renew().await {
+                Ok(true) => return,
+                Ok(false) => {}
+                Err(error) => emit!(KubernetesEventsLeaderElectionFailed { error: &error }),
+            }
+            delay_for(self.retry_period).await;
+        }
+    }
+
+    /// Keeps renewing the lease, and resolves once the leadership is lost,
+    /// either to another candidate or because the lease couldn't be renewed
+    /// within the renew deadline.
+    pub async fn hold(&mut self) {
+        let mut renewed_at = Instant::now();
+        loop {
+            delay_for(self.retry_period).await;
+            match self.try_acquire_or_renew().await {
+                Ok(true) => renewed_at = Instant::now(),
+                Ok(false) => return,
+                Err(error) => {
+                    emit!(KubernetesEventsLeaderElectionFailed { error: &error });
+                    if renewed_at.elapsed() >= self.renew_deadline {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gives up the lease, so that another candidate can take over without
+    /// waiting for it to expire.
+    pub async fn release(&mut self) {
+        let result = async {
+            let lease = match self.read().await? {
+                Some(lease) => lease,
+                None => return Ok(()),
+            };
+            let spec = lease.spec.clone().unwrap_or_default();
+            if spec.holder_identity.as_deref() != Some(self.identity.as_str()) {
+                return Ok(());
+            }
+            let spec = LeaseSpec {
+                holder_identity: None,
+                renew_time: Some(MicroTime(Utc::now())),
+                ..spec
+            };
+            self.replace(lease.metadata, spec).await.map(|_| ())
+        }
+        .await;
+        if let Err(error) = result {
+            emit!(KubernetesEventsLeaderElectionFailed { error: &error });
+        }
+    }
+
+    /// Acquires the lease if it's free or expired, or renews it if it's
+    /// ours. Returns whether we hold the lease.
+    async fn try_acquire_or_renew(&mut self) -> Result<bool, Error> {
+        let now = Utc::now();
+        let lease = self.read().await?;
+        let unchanged_for = self.observe(lease.as_ref());
+        let current = lease.as_ref().and_then(|lease| lease.spec.as_ref());
+        let spec = match next_spec(
+            current,
+            &self.identity,
+            now,
+            self.lease_duration,
+            unchanged_for,
+        ) {
+            Some(spec) => spec,
+            None => return Ok(false),
+        };
+        match lease {
+            Some(lease) => self.replace(lease.metadata, spec).await,
+            None => self.create(spec).await,
+        }
+    }
+
+    /// Records the version of `lease`, and returns for how long this
+    /// candidate has been observing it unchanged.
+    fn observe(&mut self, lease: Option<&Lease>) -> Duration {
+        let resource_version = lease.and_then(|lease| lease.metadata.resource_version.clone());
+        let renew_time = lease
+            .and_then(|lease| lease.spec.as_ref())
+            .and_then(|spec| spec.renew_time.clone());
+        match &self.observed {
+            Some(observed)
+                if observed.resource_version == resource_version
+                    && observed.renew_time == renew_time =>
+            {
+                observed.at.elapsed()
+            }
+            _ => {
+                self.observed = Some(Observation {
+                    resource_version,
+                    renew_time,
+                    at: Instant::now(),
+                });
+                Duration::from_secs(0)
+            }
+        }
+    }
+
+    async fn read(&mut self) -> Result<Option<Lease>, Error> {
+        let (request, _) = Lease::read_namespaced_lease(
+            &self.lease_name,
+            &self.lease_namespace,
+            ReadNamespacedLeaseOptional::default(),
+        )
+        .context(RequestPreparation)?;
+        match self.send(request).await? {
+            (_, Some(lease)) => Ok(Some(lease)),
+            (StatusCode::NOT_FOUND, None) => Ok(None),
+            (status, None) => Err(Error::BadStatus { status }),
+        }
+    }
+
+    async fn create(&mut self, spec: LeaseSpec) -> Result<bool, Error> {
+        let lease = Lease {
+            metadata: ObjectMeta {
+                name: Some(self.lease_name.clone()),
+                namespace: Some(self.lease_namespace.clone()),
+                ..ObjectMeta::default()
+            },
+            spec: Some(spec),
+        };
+        let (request, _) = Lease::create_namespaced_lease(
+            &self.lease_namespace,
+            &lease,
+            CreateOptional::default(),
+        )
+        .context(RequestPreparation)?;
+        self.send_update(request).await
+    }
+
+    /// Replaces the lease, using the `resourceVersion` in `metadata` to make
+    /// sure nobody else updated it in the meantime.
+    async fn replace(&mut self, metadata: ObjectMeta, spec: LeaseSpec) -> Result<bool, Error> {
+        let lease = Lease {
+            metadata,
+            spec: Some(spec),
+        };
+        let (request, _) = Lease::replace_namespaced_lease(
+            &self.lease_name,
+            &self.lease_namespace,
+            &lease,
+            ReplaceOptional::default(),
+        )
+        .context(RequestPreparation)?;
+        self.send_update(request).await
+    }
+
+    /// Sends a lease update. Returns `false` if we lost the race to another
+    /// candidate.
+    async fn send_update(&mut self, request: Request<Vec<u8>>) -> Result<bool, Error> {
+        match self.send(request).await? {
+            (_, Some(_)) => Ok(true),
+            (StatusCode::CONFLICT, None) => Ok(false),
+            (status, None) => Err(Error::BadStatus { status }),
+        }
+    }
+
+    /// Sends a request, and parses the `Lease` from successful responses.
+    async fn send(
+        &mut self,
+        request: Request<Vec<u8>>,
+    ) -> Result<(StatusCode, Option<Lease>), Error> {
+        let response = self.client.send(request).await.context(Http)?;
+        let status = response.status();
+        if !status.is_success() {
+            return Ok((status, None));
+        }
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .context(ReadBody)?;
+        let lease = serde_json::from_slice(&body).context(Parse)?;
+        Ok((status, Some(lease)))
+    }
+}
+
+/// Computes the lease spec to write to acquire or renew the lease, or `None`
+/// if the lease is held by another candidate. The lease held by another
+/// candidate expires once it has been observed unchanged for
+/// `lease_duration`.
+fn next_spec(
+    current: Option<&LeaseSpec>,
+    identity: &str,
+    now: DateTime<Utc>,
+    lease_duration: Duration,
+    unchanged_for: Duration,
+) -> Option<LeaseSpec> {
+    let current = current.cloned().unwrap_or_default();
+    let holder = current.holder_identity.clone();
+    let renew_time = Some(MicroTime(now));
+    let lease_duration_seconds = Some(lease_duration.as_secs() as i32);
+
+    match holder.as_deref() {
+        Some(holder) if holder == identity => Some(LeaseSpec {
+            renew_time,
+            lease_duration_seconds,
+            ..current
+        }),
+        Some(holder) if !holder.is_empty() && unchanged_for < lease_duration => None,
+        _ => Some(LeaseSpec {
+            holder_identity: Some(identity.to_owned()),
+            acquire_time: Some(MicroTime(now)),
+            renew_time,
+            lease_duration_seconds,
+            lease_transitions: Some(current.lease_transitions.unwrap_or(0) + 1),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const LEASE_DURATION: Duration = Duration::from_secs(15);
+
+    fn spec(holder: &str, renewed_secs_ago: i64, transitions: i32) -> LeaseSpec {
+        let now = Utc.timestamp(1_600_000_000, 0);
+        LeaseSpec {
+            holder_identity: Some(holder.to_owned()),
+            acquire_time: Some(MicroTime(Utc.timestamp(1_500_000_000, 0))),
+            renew_time: Some(MicroTime(now - chrono::Duration::seconds(renewed_secs_ago))),
+            lease_duration_seconds: Some(LEASE_DURATION.as_secs() as i32),
+            lease_transitions: Some(transitions),
+        }
+    }
+
+    #[test]
+    fn acquires_missing_lease() {
+        let now = Utc.timestamp(1_600_000_000, 0);
+        let spec = next_spec(
+            None,
+            "vector-0",
+            now,
+            LEASE_DURATION,
+            Duration::from_secs(0),
+        )
+        .unwrap();
+        assert_eq!(spec.holder_identity.as_deref(), Some("vector-0"));
+        assert_eq!(spec.acquire_time, Some(MicroTime(now)));
+        assert_eq!(spec.renew_time, Some(MicroTime(now)));
+        assert_eq!(spec.lease_duration_seconds, Some(15));
+        assert_eq!(spec.lease_transitions, Some(1));
+    }
+
+    #[test]
+    fn respects_held_lease() {
+        let now = Utc.timestamp(1_600_000_000, 0);
+        let current = spec("vector-1", 5, 3);
+        assert_eq!(
+            next_spec(
+                Some(&current),
+                "vector-0",
+                now,
+                LEASE_DURATION,
+                Duration::from_secs(5)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn respects_newly_observed_lease_with_old_renew_time() {
+        let now = Utc.timestamp(1_600_000_000, 0);
+        let current = spec("vector-1", 60, 3);
+        assert_eq!(
+            next_spec(
+                Some(&current),
+                "vector-0",
+                now,
+                LEASE_DURATION,
+                Duration::from_secs(5)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn takes_over_expired_lease() {
+        let now = Utc.timestamp(1_600_000_000, 0);
+        let current = spec("vector-1", 20, 3);
+        let spec = next_spec(
+            Some(&current),
+            "vector-0",
+            now,
+            LEASE_DURATION,
+            Duration::from_secs(20),
+        )
+        .unwrap();
+        assert_eq!(spec.holder_identity.as_deref(), Some("vector-0"));
+        assert_eq!(spec.acquire_time, Some(MicroTime(now)));
+        assert_eq!(spec.lease_transitions, Some(4));
+    }
+
+    #[test]
+    fn takes_over_released_lease() {
+        let now = Utc.timestamp(1_600_000_000, 0);
+        let current = LeaseSpec {
+            holder_identity: None,
+            ..spec("vector-1", 1, 3)
+        };
+        let spec = next_spec(
+            Some(&current),
+            "vector-0",
+            now,
+            LEASE_DURATION,
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        assert_eq!(spec.holder_identity.as_deref(), Some("vector-0"));
+        assert_eq!(spec.lease_transitions, Some(4));
+    }
+
+    #[test]
+    fn renews_own_lease() {
+        let now = Utc.timestamp(1_600_000_000, 0);
+        let current = spec("vector-0", 5, 3);
+        let spec = next_spec(
+            Some(&current),
+            "vector-0",
+            now,
+            LEASE_DURATION,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(spec.holder_identity.as_deref(), Some("vector-0"));
+        assert_eq!(spec.acquire_time, current.acquire_time);
+        assert_eq!(spec.renew_time, Some(MicroTime(now)));
+        assert_eq!(spec.lease_transitions, Some(3));
+    }
+}
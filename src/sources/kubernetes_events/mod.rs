@@ -0,0 +1,756 @@
+//! This mod implements `kubernetes_events` source.
+//! The source watches the `v1/Event` objects at the Kubernetes API, and
+//! emits a log event for every new occurrence of a Kubernetes event.
+//! When running as a `DaemonSet`, leader election makes only one of the
+//! replicas watch the events.
+
+#![deny(missing_docs)]
+
+use crate::event::{Event, LogEvent};
+use crate::internal_events::{
+    KubernetesEventsEventDeduplicated, KubernetesEventsEventReceived,
+    KubernetesEventsLeadershipChanged, KubernetesEventsWatchFailed,
+};
+use crate::kubernetes as k8s;
+use crate::{
+    config::{DataType, GenerateConfig, GlobalOptions, SourceConfig, SourceDescription},
+    shutdown::ShutdownSignal,
+    sources, Pipeline,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{
+    channel::mpsc,
+    compat::Future01CompatExt,
+    future::{select, BoxFuture, Either, FutureExt, TryFutureExt},
+    SinkExt, StreamExt,
+};
+use futures01::Sink;
+use k8s_openapi::{
+    api::core::v1::{Event as KubeEvent, ObjectReference},
+    http::Request,
+    RequestError, WatchOptional,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::time::delay_for;
+
+mod leader_election;
+
+use leader_election::LeaderElector;
+
+/// The `identity` value env var key.
+const SELF_POD_NAME_ENV_KEY: &str = "VECTOR_SELF_POD_NAME";
+
+/// The `lease_namespace` value env var key.
+const SELF_POD_NAMESPACE_ENV_KEY: &str = "VECTOR_SELF_POD_NAMESPACE";
+
+/// How long the Kubernetes events are remembered for deduplication after
+/// their last occurrence. The API server deletes them after an hour by
+/// default, which we also learn about from the watch.
+const DEDUPE_RETENTION_SECS: i64 = 2 * 60 * 60;
+
+/// Configuration for the `kubernetes_events` source.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields, default)]
+pub struct Config {
+    /// The namespaces to watch the events in. All namespaces are watched
+    /// when empty.
+    namespaces: Vec<String>,
+
+    /// Specifies the field selector to filter `Event`s with.
+    field_selector: String,
+
+    /// Specifies the label selector to filter `Event`s with.
+    label_selector: String,
+
+    /// Emit the events that occurred before the source started watching.
+    include_previous_events: bool,
+
+    /// Only watch the events when holding the lease. Allows running the
+    /// source in multiple replicas, with only one of them emitting events.
+    leader_election: Option<LeaderElectionConfig>,
+}
+
+/// Configuration for the leader election.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields, default)]
+pub struct LeaderElectionConfig {
+    /// The name of the `Lease` to compete for.
+    lease_name: String,
+
+    /// The namespace of the `Lease`. Defaults to the namespace of the Vector
+    /// `Pod`.
+    lease_namespace: String,
+
+    /// The identity of this replica. Defaults to the name of the Vector
+    /// `Pod`.
+    identity: String,
+
+    /// How long the other replicas wait before taking over an unrenewed
+    /// lease.
+    lease_duration_secs: u64,
+
+    /// How long the leader keeps retrying to renew the lease before giving
+    /// up the leadership.
+    renew_deadline_secs: u64,
+
+    /// How often to try acquiring or renewing the lease.
+    retry_period_secs: u64,
+}
+
+impl Default for LeaderElectionConfig {
+    fn default() -> Self {
+        Self {
+            lease_name: "vector-kubernetes-events".to_owned(),
+            lease_namespace: String::new(),
+            identity: String::new(),
+            lease_duration_secs: 15,
+            renew_deadline_secs: 10,
+            retry_period_secs: 2,
+        }
+    }
+}
+
+inventory::submit! {
+    SourceDescription::new::<Config>(COMPONENT_NAME)
+}
+
+impl GenerateConfig for Config {
+    fn generate_config() -> toml::Value {
+        toml::Value::try_from(&Self::default()).unwrap()
+    }
+}
+
+const COMPONENT_NAME: &str = "kubernetes_events";
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "kubernetes_events")]
+impl SourceConfig for Config {
+    async fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<sources::Source> {
+        let source = Source::new(self)?;
+        Ok(Box::new(source.run(out, shutdown).boxed().compat()))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        COMPONENT_NAME
+    }
+}
+
+struct Source {
+    client: k8s::client::Client,
+    namespaces: Vec<Option<String>>,
+    field_selector: Option<String>,
+    label_selector: Option<String>,
+    include_previous_events: bool,
+    leader_elector: Option<LeaderElector>,
+}
+
+impl Source {
+    fn new(config: &Config) -> crate::Result<Self> {
+        let k8s_config = k8s::client::config::Config::in_cluster()?;
+        let client = k8s::client::Client::new(k8s_config)?;
+
+        let namespaces = if config.namespaces.is_empty() {
+            vec![None]
+        } else {
+            config.namespaces.iter().cloned().map(Some).collect()
+        };
+
+        let leader_elector = match &config.leader_election {
+            Some(leader_election) => Some(LeaderElector::new(
+                client.clone(),
+                leader_election.lease_name.clone(),
+                resolve_lease_namespace(leader_election)?,
+                resolve_identity(leader_election)?,
+                Duration::from_secs(leader_election.lease_duration_secs),
+                Duration::from_secs(leader_election.renew_deadline_secs),
+                Duration::from_secs(leader_election.retry_period_secs),
+            )),
+            None => None,
+        };
+
+        Ok(Self {
+            client,
+            namespaces,
+            field_selector: non_empty(&config.field_selector),
+            label_selector: non_empty(&config.label_selector),
+            include_previous_events: config.include_previous_events,
+            leader_elector,
+        })
+    }
+
+    async fn run(self, out: Pipeline, shutdown: ShutdownSignal) -> Result<(), ()> {
+        let (changes_tx, changes_rx) = mpsc::channel(1024);
+
+        let watch = Box::pin(self.watch(changes_tx, shutdown));
+        let forward = Box::pin(forward_events(changes_rx, out));
+
+        // The watch stops on shutdown, dropping its sender, so the forwarding
+        // completes once the pending changes are processed.
+        match select(watch, forward).await {
+            Either::Left(((), forward)) => forward.await,
+            Either::Right((result, _)) => result,
+        }
+    }
+
+    /// Watches the events, only while holding the lease if leader election
+    /// is enabled, until shutdown.
+    async fn watch(self, changes_tx: mpsc::Sender<Change>, mut shutdown: ShutdownSignal) {
+        let Self {
+            client,
+            namespaces,
+            field_selector,
+            label_selector,
+            include_previous_events,
+            leader_elector,
+        } = self;
+
+        let mut leader_elector = match leader_elector {
+            Some(leader_elector) => leader_elector,
+            None => {
+                let since = if include_previous_events {
+                    None
+                } else {
+                    Some(Utc::now())
+                };
+                let watch = watch_all(
+                    &client,
+                    &namespaces,
+                    &field_selector,
+                    &label_selector,
+                    &changes_tx,
+                    since,
+                );
+                tokio::select! {
+                    _ = watch => {}
+                    _ = &mut shutdown => {}
+                }
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = leader_elector.acquire() => {}
+                _ = &mut shutdown => return,
+            }
+            emit!(KubernetesEventsLeadershipChanged { leader: true });
+
+            // The previous leader might have died without emitting the
+            // events since it last renewed the lease, so we pick up from
+            // there. Anything older was handled by the previous leader.
+            let since = if include_previous_events {
+                None
+            } else {
+                chrono::Duration::from_std(leader_elector.lease_duration())
+                    .ok()
+                    .map(|lease_duration| Utc::now() - lease_duration)
+            };
+
+            let watch = watch_all(
+                &client,
+                &namespaces,
+                &field_selector,
+                &label_selector,
+                &changes_tx,
+                since,
+            );
+            let lost = tokio::select! {
+                _ = leader_elector.hold() => true,
+                _ = watch => false,
+                _ = &mut shutdown => false,
+            };
+            emit!(KubernetesEventsLeadershipChanged { leader: false });
+            if !lost {
+                leader_elector.release().await;
+                return;
+            }
+        }
+    }
+}
+
+/// Watches the events at all the configured namespaces, sending the changes
+/// to `changes_tx`. Never completes.
+async fn watch_all(
+    client: &k8s::client::Client,
+    namespaces: &[Option<String>],
+    field_selector: &Option<String>,
+    label_selector: &Option<String>,
+    changes_tx: &mpsc::Sender<Change>,
+    since: Option<DateTime<Utc>>,
+) {
+    let watches = namespaces.iter().map(|namespace| {
+        let watcher = k8s::api_watcher::ApiWatcher::new(
+            client.clone(),
+            EventsRequestBuilder {
+                namespace: namespace.clone(),
+            },
+        );
+        let watcher = k8s::instrumenting_watcher::InstrumentingWatcher::new(watcher);
+        let state_writer = ChangesWriter {
+            changes_tx: changes_tx.clone(),
+            since,
+        };
+        let mut reflector = k8s::reflector::Reflector::new(
+            watcher,
+            state_writer,
+            field_selector.clone(),
+            label_selector.clone(),
+            Duration::from_secs(1),
+        );
+        let fut: BoxFuture<'static, ()> = Box::pin(async move {
+            loop {
+                match reflector.run().await {
+                    Ok(never) => match never {},
+                    Err(error) => {
+                        emit!(KubernetesEventsWatchFailed { error: &error });
+                        delay_for(Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        });
+        fut
+    });
+    futures::future::join_all(watches).await;
+}
+
+/// Builds the watch requests for the events at a namespace, or at all the
+/// namespaces.
+struct EventsRequestBuilder {
+    namespace: Option<String>,
+}
+
+impl k8s::WatchRequestBuilder for EventsRequestBuilder {
+    type Object = KubeEvent;
+
+    fn build<'a>(
+        &self,
+        watch_optional: WatchOptional<'a>,
+    ) -> Result<Request<Vec<u8>>, RequestError> {
+        let (request, _) = match &self.namespace {
+            Some(namespace) => KubeEvent::watch_namespaced_event(namespace, watch_optional)?,
+            None => KubeEvent::watch_event_for_all_namespaces(watch_optional)?,
+        };
+        Ok(request)
+    }
+}
+
+/// A change to the Kubernetes events observed by the watch.
+#[derive(Debug)]
+enum Change {
+    Applied(KubeEvent),
+    Deleted(KubeEvent),
+}
+
+/// A state writer that passes the changes on instead of keeping them.
+struct ChangesWriter {
+    changes_tx: mpsc::Sender<Change>,
+    /// Events that last occurred before this are skipped.
+    since: Option<DateTime<Utc>>,
+}
+
+impl ChangesWriter {
+    async fn apply(&mut self, item: KubeEvent) {
+        if let (Some(since), Some(timestamp)) = (self.since, last_occurrence(&item)) {
+            if timestamp < since {
+                return;
+            }
+        }
+        // The receiver only goes away on shutdown.
+        let _ = self.changes_tx.send(Change::Applied(item)).await;
+    }
+}
+
+#[async_trait]
+impl k8s::state::Write for ChangesWriter {
+    type Item = KubeEvent;
+
+    async fn add(&mut self, item: Self::Item) {
+        self.apply(item).await;
+    }
+
+    async fn update(&mut self, item: Self::Item) {
+        self.apply(item).await;
+    }
+
+    async fn delete(&mut self, item: Self::Item) {
+        let _ = self.changes_tx.send(Change::Deleted(item)).await;
+    }
+
+    async fn resync(&mut self) {
+        // The events are replayed after the resync, and deduplicated
+        // downstream.
+    }
+}
+
+#[async_trait]
+impl k8s::state::MaintainedWrite for ChangesWriter {
+    fn maintenance_request(&mut self) -> Option<BoxFuture<'_, ()>> {
+        None
+    }
+
+    async fn perform_maintenance(&mut self) {}
+}
+
+/// Deduplicates the events, and sends them out.
+async fn forward_events(mut changes_rx: mpsc::Receiver<Change>, out: Pipeline) -> Result<(), ()> {
+    let mut out = out.sink_map_err(|error| error!(message = "Error sending event.", %error));
+    let mut deduper = Deduper::default();
+
+    while let Some(change) = changes_rx.next().await {
+        match change {
+            Change::Applied(event) => {
+                if !deduper.observe(&event) {
+                    emit!(KubernetesEventsEventDeduplicated);
+                    continue;
+                }
+                let event = create_event(event);
+                emit!(KubernetesEventsEventReceived {
+                    byte_size: event
+                        .as_log()
+                        .get(crate::config::log_schema().message_key())
+                        .map_or(0, |message| message.as_bytes().len()),
+                });
+                out = out.send(event).compat().await?;
+            }
+            Change::Deleted(event) => deduper.forget(&event),
+        }
+    }
+
+    Ok(())
+}
+
+/// Tracks the occurrences of the events that were already emitted, so the
+/// replays on restarted watches and updates that don't add an occurrence are
+/// skipped.
+#[derive(Default)]
+struct Deduper {
+    seen: HashMap<String, Seen>,
+    pruned_at: Option<Instant>,
+}
+
+struct Seen {
+    count: i32,
+    last_occurrence: Option<DateTime<Utc>>,
+}
+
+impl Deduper {
+    /// Records the event, and returns whether it's a new occurrence.
+    fn observe(&mut self, event: &KubeEvent) -> bool {
+        self.prune();
+
+        let uid = match &event.metadata.uid {
+            Some(uid) => uid,
+            None => return true,
+        };
+        let count = occurrences(event);
+        if let Some(seen) = self.seen.get(uid) {
+            if seen.count >= count {
+                return false;
+            }
+        }
+        self.seen.insert(
+            uid.clone(),
+            Seen {
+                count,
+                last_occurrence: last_occurrence(event),
+            },
+        );
+        true
+    }
+
+    fn forget(&mut self, event: &KubeEvent) {
+        if let Some(uid) = &event.metadata.uid {
+            self.seen.remove(uid);
+        }
+    }
+
+    /// Drops the events we missed the deletion of, like during a resync.
+    fn prune(&mut self) {
+        if matches!(self.pruned_at, Some(pruned_at) if pruned_at.elapsed() < Duration::from_secs(60))
+        {
+            return;
+        }
+        self.pruned_at = Some(Instant::now());
+
+        let cutoff = Utc::now() - chrono::Duration::seconds(DEDUPE_RETENTION_SECS);
+        self.seen.retain(|_, seen| {
+            seen.last_occurrence
+                .map_or(true, |last_occurrence| last_occurrence >= cutoff)
+        });
+    }
+}
+
+/// The number of times the event occurred. Repeated events are reported as
+/// updates to the same object, with an increased count.
+fn occurrences(event: &KubeEvent) -> i32 {
+    event
+        .series
+        .as_ref()
+        .and_then(|series| series.count)
+        .or(event.count)
+        .unwrap_or(1)
+}
+
+fn last_occurrence(event: &KubeEvent) -> Option<DateTime<Utc>> {
+    event
+        .series
+        .as_ref()
+        .and_then(|series| series.last_observed_time.as_ref())
+        .map(|time| time.0)
+        .or_else(|| event.last_timestamp.as_ref().map(|time| time.0))
+        .or_else(|| event.event_time.as_ref().map(|time| time.0))
+        .or_else(|| event.first_timestamp.as_ref().map(|time| time.0))
+        .or_else(|| {
+            event
+                .metadata
+                .creation_timestamp
+                .as_ref()
+                .map(|time| time.0)
+        })
+}
+
+fn create_event(event: KubeEvent) -> Event {
+    let mut log = LogEvent::default();
+    let schema = crate::config::log_schema();
+
+    log.insert(schema.source_type_key(), COMPONENT_NAME);
+    log.insert(
+        schema.timestamp_key(),
+        last_occurrence(&event).unwrap_or_else(Utc::now),
+    );
+    log.insert("count", occurrences(&event) as i64);
+
+    for (key, val) in [
+        (schema.message_key(), event.message),
+        ("reason", event.reason),
+        ("type", event.type_),
+        ("action", event.action),
+        ("name", event.metadata.name),
+        ("namespace", event.metadata.namespace),
+        ("uid", event.metadata.uid),
+    ]
+    .iter_mut()
+    {
+        if let Some(val) = val.take() {
+            log.insert(*key, val);
+        }
+    }
+
+    if let Some(first_timestamp) = event.first_timestamp {
+        log.insert("first_timestamp", first_timestamp.0);
+    }
+
+    let (component, host) = event
+        .source
+        .map_or((None, None), |source| (source.component, source.host));
+    if let Some(component) = event.reporting_component.or(component) {
+        log.insert("reporting_component", component);
+    }
+    if let Some(instance) = event.reporting_instance.or(host) {
+        log.insert("reporting_instance", instance);
+    }
+
+    insert_object_reference(&mut log, "involved_object", event.involved_object);
+    if let Some(related) = event.related {
+        insert_object_reference(&mut log, "related", related);
+    }
+
+    Event::Log(log)
+}
+
+fn insert_object_reference(log: &mut LogEvent, prefix: &str, reference: ObjectReference) {
+    for (key, val) in [
+        ("api_version", reference.api_version),
+        ("kind", reference.kind),
+        ("name", reference.name),
+        ("namespace", reference.namespace),
+        ("uid", reference.uid),
+        ("field_path", reference.field_path),
+        ("resource_version", reference.resource_version),
+    ]
+    .iter_mut()
+    {
+        if let Some(val) = val.take() {
+            log.insert(format!("{}.{}", prefix, key), val);
+        }
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_owned())
+    }
+}
+
+/// Resolves the namespace of the lease from the configuration or the
+/// environment.
+fn resolve_lease_namespace(config: &LeaderElectionConfig) -> crate::Result<String> {
+    if !config.lease_namespace.is_empty() {
+        return Ok(config.lease_namespace.clone());
+    }
+    std::env::var(SELF_POD_NAMESPACE_ENV_KEY).map_err(|_| {
+        format!(
+            "leader_election.lease_namespace config value or {} env var is not set",
+            SELF_POD_NAMESPACE_ENV_KEY
+        )
+        .into()
+    })
+}
+
+/// Resolves the identity of this replica from the configuration or the
+/// environment, falling back to the hostname, which is the `Pod` name
+/// in Kubernetes.
+fn resolve_identity(config: &LeaderElectionConfig) -> crate::Result<String> {
+    if !config.identity.is_empty() {
+        return Ok(config.identity.clone());
+    }
+    match std::env::var(SELF_POD_NAME_ENV_KEY) {
+        Ok(identity) => Ok(identity),
+        Err(_) => Ok(crate::get_hostname()?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+
+    fn make_event(uid: &str, count: i32, last_timestamp: i64) -> KubeEvent {
+        KubeEvent {
+            metadata: ObjectMeta {
+                name: Some("sandbox0-pod.163e5d34b5fd7e5c".to_owned()),
+                namespace: Some("sandbox0-ns".to_owned()),
+                uid: Some(uid.to_owned()),
+                ..ObjectMeta::default()
+            },
+            involved_object: ObjectReference {
+                api_version: Some("v1".to_owned()),
+                kind: Some("Pod".to_owned()),
+                name: Some("sandbox0-pod".to_owned()),
+                namespace: Some("sandbox0-ns".to_owned()),
+                uid: Some("sandbox0-pod-uid".to_owned()),
+                ..ObjectReference::default()
+            },
+            reason: Some("BackOff".to_owned()),
+            message: Some("Back-off restarting failed container".to_owned()),
+            type_: Some("Warning".to_owned()),
+            count: Some(count),
+            first_timestamp: Some(Time(Utc.timestamp(1_600_000_000, 0))),
+            last_timestamp: Some(Time(Utc.timestamp(last_timestamp, 0))),
+            ..KubeEvent::default()
+        }
+    }
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<Config>();
+    }
+
+    #[test]
+    fn parse_leader_election_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+            namespaces = ["default"]
+            [leader_election]
+            "#,
+        )
+        .unwrap();
+        let leader_election = config.leader_election.unwrap();
+        assert_eq!(leader_election.lease_name, "vector-kubernetes-events");
+        assert_eq!(leader_election.lease_duration_secs, 15);
+        assert_eq!(config.namespaces, vec!["default".to_owned()]);
+    }
+
+    #[test]
+    fn dedupes_repeated_occurrences() {
+        let mut deduper = Deduper::default();
+        let now = Utc::now().timestamp();
+
+        assert!(deduper.observe(&make_event("uid0", 1, now)));
+        // Replayed after a resync.
+        assert!(!deduper.observe(&make_event("uid0", 1, now)));
+        // Repeated.
+        assert!(deduper.observe(&make_event("uid0", 2, now)));
+        assert!(!deduper.observe(&make_event("uid0", 2, now)));
+        assert!(deduper.observe(&make_event("uid1", 1, now)));
+
+        deduper.forget(&make_event("uid0", 2, now));
+        assert!(deduper.observe(&make_event("uid0", 2, now)));
+    }
+
+    #[test]
+    fn prunes_old_events() {
+        let mut deduper = Deduper::default();
+        let old = Utc::now().timestamp() - DEDUPE_RETENTION_SECS - 60;
+
+        assert!(deduper.observe(&make_event("uid0", 1, old)));
+        deduper.pruned_at = None;
+        deduper.prune();
+        assert!(deduper.seen.is_empty());
+    }
+
+    #[test]
+    fn creates_log_event() {
+        let event = create_event(make_event("uid0", 3, 1_600_000_060));
+        let log = event.as_log();
+
+        assert_eq!(
+            log["message"],
+            "Back-off restarting failed container".into()
+        );
+        assert_eq!(log["source_type"], "kubernetes_events".into());
+        assert_eq!(log["timestamp"], Utc.timestamp(1_600_000_060, 0).into());
+        assert_eq!(
+            log["first_timestamp"],
+            Utc.timestamp(1_600_000_000, 0).into()
+        );
+        assert_eq!(log["count"], 3.into());
+        assert_eq!(log["reason"], "BackOff".into());
+        assert_eq!(log["type"], "Warning".into());
+        assert_eq!(log["namespace"], "sandbox0-ns".into());
+        assert_eq!(log["uid"], "uid0".into());
+        assert_eq!(log["involved_object.kind"], "Pod".into());
+        assert_eq!(log["involved_object.name"], "sandbox0-pod".into());
+        assert_eq!(log["involved_object.uid"], "sandbox0-pod-uid".into());
+        assert!(log.get("related").is_none());
+        assert!(log.get("action").is_none());
+    }
+
+    #[tokio::test]
+    async fn skips_events_before_since() {
+        use k8s::state::Write;
+
+        let (changes_tx, mut changes_rx) = mpsc::channel(10);
+        let mut writer = ChangesWriter {
+            changes_tx,
+            since: Some(Utc.timestamp(1_600_000_030, 0)),
+        };
+
+        writer.add(make_event("uid0", 1, 1_600_000_000)).await;
+        writer.add(make_event("uid1", 1, 1_600_000_060)).await;
+        drop(writer);
+
+        let changes = changes_rx.by_ref().collect::<Vec<_>>().await;
+        assert_eq!(changes.len(), 1);
+        match &changes[0] {
+            Change::Applied(event) => assert_eq!(event.metadata.uid.as_deref(), Some("uid1")),
+            change => panic!("unexpected change: {:?}", change),
+        }
+    }
+}
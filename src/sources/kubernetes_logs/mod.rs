@@ -20,7 +20,8 @@ use crate::{
 };
 use bytes::Bytes;
 use file_source::{FileServer, FileServerShutdown, FingerprintStrategy, Fingerprinter};
-use k8s_openapi::api::core::v1::Pod;
+use k8s::WatchRequestBuilder;
+use k8s_openapi::api::core::v1::{Namespace, Node, Pod};
 use serde::{Deserialize, Serialize};
 use std::convert::TryInto;
 use std::path::PathBuf;
@@ -74,6 +75,14 @@ pub struct Config {
     /// Specifies the field names for metadata annotation.
     annotation_fields: pod_metadata_annotator::FieldsSpec,
 
+    /// Annotate the events with the labels and annotations of the pod's
+    /// `Namespace`. Requires permissions to watch `Namespace`s.
+    namespace_metadata: bool,
+
+    /// Annotate the events with the labels of the `Node` Vector runs at.
+    /// Requires permissions to watch `Node`s.
+    node_metadata: bool,
+
     /// A list of glob patterns to exclude from reading the files.
     exclude_paths_glob_patterns: Vec<PathBuf>,
 
@@ -156,6 +165,8 @@ struct Source {
     fields_spec: pod_metadata_annotator::FieldsSpec,
     field_selector: String,
     label_selector: String,
+    namespace_metadata: bool,
+    node_field_selector: Option<String>,
     exclude_paths: Vec<glob::Pattern>,
     max_read_bytes: usize,
     glob_minimum_cooldown: Duration,
@@ -163,8 +174,14 @@ struct Source {
 
 impl Source {
     fn new(config: &Config, globals: &GlobalOptions, name: &str) -> crate::Result<Self> {
-        let field_selector = prepare_field_selector(config)?;
+        let self_node_name = resolve_self_node_name(config)?;
+        let field_selector = prepare_field_selector(config, &self_node_name);
         let label_selector = prepare_label_selector(config);
+        let node_field_selector = if config.node_metadata {
+            Some(format!("metadata.name={}", self_node_name))
+        } else {
+            None
+        };
 
         let k8s_config = k8s::client::config::Config::in_cluster()?;
         let client = k8s::client::Client::new(k8s_config)?;
@@ -194,6 +211,8 @@ impl Source {
             fields_spec: config.annotation_fields.clone(),
             field_selector,
             label_selector,
+            namespace_metadata: config.namespace_metadata,
+            node_field_selector,
             exclude_paths,
             max_read_bytes: config.max_read_bytes,
             glob_minimum_cooldown,
@@ -212,31 +231,60 @@ impl Source {
             fields_spec,
             field_selector,
             label_selector,
+            namespace_metadata,
+            node_field_selector,
             exclude_paths,
             max_read_bytes,
             glob_minimum_cooldown,
         } = self;
 
-        let watcher = k8s::api_watcher::ApiWatcher::new(client, Pod::watch_pod_for_all_namespaces);
-        let watcher = k8s::instrumenting_watcher::InstrumentingWatcher::new(watcher);
-        let (state_reader, state_writer) = evmap::new();
-        let state_writer =
-            k8s::state::evmap::Writer::new(state_writer, Some(Duration::from_millis(10)));
-        let state_writer = k8s::state::instrumenting::Writer::new(state_writer);
-        let state_writer =
-            k8s::state::delayed_delete::Writer::new(state_writer, Duration::from_secs(60));
-
-        let mut reflector = k8s::reflector::Reflector::new(
-            watcher,
-            state_writer,
+        let mut lifecycle = Lifecycle::new();
+
+        // `Namespace`s and `Node`s are looked up by name when annotating, so
+        // their states are keyed by name rather than by uid.
+        let namespaces_state_reader = if namespace_metadata {
+            Some(spawn_reflector(
+                &mut lifecycle,
+                client.clone(),
+                Namespace::watch_namespace,
+                k8s::state::evmap::name_key,
+                None,
+                None,
+                "namespace",
+            ))
+        } else {
+            None
+        };
+
+        let nodes_state_reader = node_field_selector.map(|node_field_selector| {
+            spawn_reflector(
+                &mut lifecycle,
+                client.clone(),
+                Node::watch_node,
+                k8s::state::evmap::name_key,
+                Some(node_field_selector),
+                None,
+                "node",
+            )
+        });
+
+        let state_reader = spawn_reflector(
+            &mut lifecycle,
+            client,
+            Pod::watch_pod_for_all_namespaces,
+            k8s::state::evmap::uid_key,
             Some(field_selector),
             Some(label_selector),
-            Duration::from_secs(1),
+            "pod",
         );
-        let reflector_process = reflector.run();
 
         let paths_provider = K8sPathsProvider::new(state_reader.clone(), exclude_paths);
-        let annotator = PodMetadataAnnotator::new(state_reader, fields_spec);
+        let annotator = PodMetadataAnnotator::new(
+            state_reader,
+            namespaces_state_reader,
+            nodes_state_reader,
+            fields_spec,
+        );
 
         // TODO: maybe more of the parameters have to be configurable.
 
@@ -325,18 +373,6 @@ impl Source {
             Box::new(events.map(Ok).compat())
         ).map_err(|_| unreachable!("These errors should only happen if our futures compat layer is wrong. If you meet this, please report it.")).compat().forward(out);
 
-        let mut lifecycle = Lifecycle::new();
-        {
-            let (slot, shutdown) = lifecycle.add();
-            let fut =
-                util::cancel_on_signal(reflector_process, shutdown).map(|result| match result {
-                    Ok(()) => info!(message = "Reflector process completed gracefully."),
-                    Err(error) => {
-                        error!(message = "Reflector process exited with an error.", %error)
-                    }
-                });
-            slot.bind(Box::pin(fut));
-        }
         {
             let (slot, shutdown) = lifecycle.add();
             let fut = util::run_file_server(file_server, file_source_tx, shutdown).map(|result| {
@@ -376,6 +412,53 @@ impl Source {
     }
 }
 
+/// Runs a reflector mirroring the objects watched with `request_builder`
+/// into an `evmap` keyed with `key` as part of `lifecycle`, and returns the
+/// reader of that state.
+fn spawn_reflector<B>(
+    lifecycle: &mut Lifecycle<'_>,
+    client: k8s::client::Client,
+    request_builder: B,
+    key: k8s::state::evmap::KeyFn,
+    field_selector: Option<String>,
+    label_selector: Option<String>,
+    name: &'static str,
+) -> evmap::ReadHandle<String, k8s::state::evmap::Value<B::Object>>
+where
+    B: 'static + WatchRequestBuilder + Send + Sync,
+    B::Object: Send + Sync + Unpin + std::fmt::Debug,
+{
+    let watcher = k8s::api_watcher::ApiWatcher::new(client, request_builder);
+    let watcher = k8s::instrumenting_watcher::InstrumentingWatcher::new(watcher);
+    let (state_reader, state_writer) = evmap::new();
+    let state_writer =
+        k8s::state::evmap::Writer::with_key(state_writer, Some(Duration::from_millis(10)), key);
+    let state_writer = k8s::state::instrumenting::Writer::new(state_writer);
+    let state_writer =
+        k8s::state::delayed_delete::Writer::new(state_writer, Duration::from_secs(60));
+
+    let mut reflector = k8s::reflector::Reflector::new(
+        watcher,
+        state_writer,
+        field_selector,
+        label_selector,
+        Duration::from_secs(1),
+    );
+    let (slot, shutdown) = lifecycle.add();
+    let fut = async move { reflector.run().await };
+    let fut = util::cancel_on_signal(fut, shutdown).map(move |result| match result {
+        Ok(()) => info!(
+            message = "Reflector process completed gracefully.",
+            reflector = name
+        ),
+        Err(error) => {
+            error!(message = "Reflector process exited with an error.", reflector = name, %error)
+        }
+    });
+    slot.bind(Box::pin(fut));
+    state_reader
+}
+
 fn create_event(line: Bytes, file: &str) -> Event {
     let mut event = Event::from(line);
 
@@ -405,9 +488,9 @@ fn default_glob_minimum_cooldown_ms() -> usize {
     60000
 }
 
-/// This function construct the effective field selector to use, based on
-/// the specified configuration.
-fn prepare_field_selector(config: &Config) -> crate::Result<String> {
+/// This function resolves the name of the `Node` Vector runs at, from the
+/// configuration or the environment.
+fn resolve_self_node_name(config: &Config) -> crate::Result<String> {
     let self_node_name = if config.self_node_name.is_empty()
         || config.self_node_name == default_self_node_name_env_template()
     {
@@ -424,17 +507,19 @@ fn prepare_field_selector(config: &Config) -> crate::Result<String> {
         message = "Obtained Kubernetes Node name to collect logs for (self).",
         ?self_node_name
     );
+    Ok(self_node_name)
+}
 
+/// This function construct the effective field selector to use, based on
+/// the specified configuration.
+fn prepare_field_selector(config: &Config, self_node_name: &str) -> String {
     let field_selector = format!("spec.nodeName={}", self_node_name);
 
     if config.extra_field_selector.is_empty() {
-        return Ok(field_selector);
+        return field_selector;
     }
 
-    Ok(format!(
-        "{},{}",
-        field_selector, config.extra_field_selector
-    ))
+    format!("{},{}", field_selector, config.extra_field_selector)
 }
 
 /// This function construct the effective label selector to use, based on
@@ -489,7 +574,8 @@ mod tests {
         ];
 
         for (input, expected) in cases {
-            let output = super::prepare_field_selector(&input).unwrap();
+            let self_node_name = super::resolve_self_node_name(&input).unwrap();
+            let output = super::prepare_field_selector(&input, &self_node_name);
             assert_eq!(expected, output, "expected left, actual right");
         }
     }
@@ -9,10 +9,11 @@ use crate::{
 };
 use evmap::ReadHandle;
 use k8s_openapi::{
-    api::core::v1::{Container, Pod, PodSpec, PodStatus},
+    api::core::v1::{Container, Namespace, Node, Pod, PodSpec, PodStatus},
     apimachinery::pkg::apis::meta::v1::ObjectMeta,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields, default)]
@@ -24,8 +25,12 @@ pub struct FieldsSpec {
     pub pod_ips: String,
     pub pod_labels: String,
     pub pod_node_name: String,
+    pub pod_owner: Option<String>,
     pub container_name: String,
     pub container_image: String,
+    pub namespace_labels: String,
+    pub namespace_annotations: String,
+    pub node_labels: String,
 }
 
 impl Default for FieldsSpec {
@@ -38,8 +43,12 @@ impl Default for FieldsSpec {
             pod_ips: "kubernetes.pod_ips".to_owned(),
             pod_labels: "kubernetes.pod_labels".to_owned(),
             pod_node_name: "kubernetes.pod_node_name".to_owned(),
+            pod_owner: None,
             container_name: "kubernetes.container_name".to_owned(),
             container_image: "kubernetes.container_image".to_owned(),
+            namespace_labels: "kubernetes.namespace_labels".to_owned(),
+            namespace_annotations: "kubernetes.namespace_annotations".to_owned(),
+            node_labels: "kubernetes.node_labels".to_owned(),
         }
     }
 }
@@ -47,17 +56,27 @@ impl Default for FieldsSpec {
 /// Annotate the event with pod metadata.
 pub struct PodMetadataAnnotator {
     pods_state_reader: ReadHandle<String, k8s::state::evmap::Value<Pod>>,
+    namespaces_state_reader: Option<ReadHandle<String, k8s::state::evmap::Value<Namespace>>>,
+    nodes_state_reader: Option<ReadHandle<String, k8s::state::evmap::Value<Node>>>,
     fields_spec: FieldsSpec,
 }
 
 impl PodMetadataAnnotator {
     /// Create a new [`PodMetadataAnnotator`].
+    ///
+    /// The `Namespace` and `Node` states are optional, and have to be keyed
+    /// by the object name. When present, the pod's namespace and node
+    /// metadata is added to the events too.
     pub fn new(
         pods_state_reader: ReadHandle<String, k8s::state::evmap::Value<Pod>>,
+        namespaces_state_reader: Option<ReadHandle<String, k8s::state::evmap::Value<Namespace>>>,
+        nodes_state_reader: Option<ReadHandle<String, k8s::state::evmap::Value<Node>>>,
         fields_spec: FieldsSpec,
     ) -> Self {
         Self {
             pods_state_reader,
+            namespaces_state_reader,
+            nodes_state_reader,
             fields_spec,
         }
     }
@@ -92,6 +111,25 @@ impl PodMetadataAnnotator {
         if let Some(ref pod_status) = pod.status {
             annotate_from_pod_status(log, &self.fields_spec, pod_status);
         }
+
+        // The namespace and the node might not be reflected yet, or at all,
+        // so their absence doesn't fail the annotation.
+        if let (Some(reader), Some(name)) = (&self.namespaces_state_reader, &pod.metadata.namespace)
+        {
+            if let Some(entry) = reader.get_one(name.as_str()) {
+                let namespace: &Namespace = entry.as_ref();
+                annotate_from_namespace(log, &self.fields_spec, &namespace.metadata);
+            }
+        }
+
+        let node_name = pod.spec.as_ref().and_then(|spec| spec.node_name.as_ref());
+        if let (Some(reader), Some(name)) = (&self.nodes_state_reader, node_name) {
+            if let Some(entry) = reader.get_one(name.as_str()) {
+                let node: &Node = entry.as_ref();
+                annotate_from_node(log, &self.fields_spec, &node.metadata);
+            }
+        }
+
         Some(())
     }
 }
@@ -121,14 +159,69 @@ fn annotate_from_metadata(log: &mut LogEvent, fields_spec: &FieldsSpec, metadata
     }
 
     if let Some(labels) = &metadata.labels {
-        // Calculate and cache the prefix path.
-        let prefix_path = PathIter::new(fields_spec.pod_labels.as_ref()).collect::<Vec<_>>();
-        for (key, val) in labels.iter() {
-            let mut path = prefix_path.clone();
-            path.push(PathComponent::Key(key.clone()));
-            log.insert_path(path, val.to_owned());
+        insert_map(log, &fields_spec.pod_labels, labels);
+    }
+
+    if let Some(key) = &fields_spec.pod_owner {
+        if let Some(owner) = resolve_owner(metadata) {
+            log.insert(key, owner);
+        }
+    }
+}
+
+/// Resolves the pod's controller to `Kind/name`.
+///
+/// Pods of `Deployment`s are controlled by a `ReplicaSet` named after the
+/// `Deployment` and the pod template hash, so we report the `Deployment`
+/// instead. Other controllers, like `StatefulSet`s, `DaemonSet`s and `Job`s,
+/// are reported as is.
+fn resolve_owner(metadata: &ObjectMeta) -> Option<String> {
+    let owner = metadata
+        .owner_references
+        .as_ref()?
+        .iter()
+        .find(|owner| owner.controller == Some(true))?;
+
+    if owner.kind == "ReplicaSet" {
+        let deployment = metadata
+            .labels
+            .as_ref()
+            .and_then(|labels| labels.get("pod-template-hash"))
+            .and_then(|hash| owner.name.strip_suffix(hash.as_str()))
+            .and_then(|name| name.strip_suffix('-'));
+        if let Some(deployment) = deployment {
+            return Some(format!("Deployment/{}", deployment));
         }
     }
+
+    Some(format!("{}/{}", owner.kind, owner.name))
+}
+
+fn annotate_from_namespace(log: &mut LogEvent, fields_spec: &FieldsSpec, metadata: &ObjectMeta) {
+    if let Some(labels) = &metadata.labels {
+        insert_map(log, &fields_spec.namespace_labels, labels);
+    }
+    if let Some(annotations) = &metadata.annotations {
+        insert_map(log, &fields_spec.namespace_annotations, annotations);
+    }
+}
+
+fn annotate_from_node(log: &mut LogEvent, fields_spec: &FieldsSpec, metadata: &ObjectMeta) {
+    if let Some(labels) = &metadata.labels {
+        insert_map(log, &fields_spec.node_labels, labels);
+    }
+}
+
+/// Inserts the map entries under `prefix`, keeping keys with `.` as flat
+/// fields.
+fn insert_map(log: &mut LogEvent, prefix: &str, map: &BTreeMap<String, String>) {
+    // Calculate and cache the prefix path.
+    let prefix_path = PathIter::new(prefix).collect::<Vec<_>>();
+    for (key, val) in map.iter() {
+        let mut path = prefix_path.clone();
+        path.push(PathComponent::Key(key.clone()));
+        log.insert_path(path, val.to_owned());
+    }
 }
 
 fn annotate_from_pod_spec(log: &mut LogEvent, fields_spec: &FieldsSpec, pod_spec: &PodSpec) {
@@ -168,7 +261,7 @@ fn annotate_from_container(log: &mut LogEvent, fields_spec: &FieldsSpec, contain
 #[cfg(test)]
 mod tests {
     use super::*;
-    use k8s_openapi::api::core::v1::PodIP;
+    use k8s_openapi::{api::core::v1::PodIP, apimachinery::pkg::apis::meta::v1::OwnerReference};
 
     #[test]
     fn test_annotate_from_metadata() {
@@ -267,6 +360,37 @@ mod tests {
                     log
                 },
             ),
+            (
+                FieldsSpec {
+                    pod_owner: Some("kubernetes.pod_owner".to_owned()),
+                    ..Default::default()
+                },
+                ObjectMeta {
+                    owner_references: Some(vec![owner_reference(
+                        "StatefulSet",
+                        "sandbox0-sts",
+                        Some(true),
+                    )]),
+                    ..ObjectMeta::default()
+                },
+                {
+                    let mut log = LogEvent::default();
+                    log.insert("kubernetes.pod_owner", "StatefulSet/sandbox0-sts");
+                    log
+                },
+            ),
+            (
+                FieldsSpec::default(),
+                ObjectMeta {
+                    owner_references: Some(vec![owner_reference(
+                        "StatefulSet",
+                        "sandbox0-sts",
+                        Some(true),
+                    )]),
+                    ..ObjectMeta::default()
+                },
+                LogEvent::default(),
+            ),
         ];
 
         for (fields_spec, metadata, expected) in cases.into_iter() {
@@ -494,4 +618,206 @@ mod tests {
             assert_eq!(log, expected);
         }
     }
+
+    fn owner_reference(kind: &str, name: &str, controller: Option<bool>) -> OwnerReference {
+        OwnerReference {
+            api_version: "apps/v1".to_owned(),
+            kind: kind.to_owned(),
+            name: name.to_owned(),
+            uid: format!("{}-uid", name),
+            controller,
+            block_owner_deletion: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_owner() {
+        let cases = vec![
+            (ObjectMeta::default(), None),
+            (
+                ObjectMeta {
+                    owner_references: Some(vec![owner_reference(
+                        "StatefulSet",
+                        "sandbox0-sts",
+                        None,
+                    )]),
+                    ..ObjectMeta::default()
+                },
+                None,
+            ),
+            (
+                ObjectMeta {
+                    owner_references: Some(vec![owner_reference(
+                        "StatefulSet",
+                        "sandbox0-sts",
+                        Some(true),
+                    )]),
+                    ..ObjectMeta::default()
+                },
+                Some("StatefulSet/sandbox0-sts"),
+            ),
+            (
+                ObjectMeta {
+                    owner_references: Some(vec![owner_reference(
+                        "Job",
+                        "sandbox0-job-1603000000",
+                        Some(true),
+                    )]),
+                    ..ObjectMeta::default()
+                },
+                Some("Job/sandbox0-job-1603000000"),
+            ),
+            (
+                ObjectMeta {
+                    labels: Some(
+                        vec![("pod-template-hash".to_owned(), "5d8f9c7b6".to_owned())]
+                            .into_iter()
+                            .collect(),
+                    ),
+                    owner_references: Some(vec![owner_reference(
+                        "ReplicaSet",
+                        "sandbox0-deploy-5d8f9c7b6",
+                        Some(true),
+                    )]),
+                    ..ObjectMeta::default()
+                },
+                Some("Deployment/sandbox0-deploy"),
+            ),
+            // A `ReplicaSet` not created by a `Deployment`.
+            (
+                ObjectMeta {
+                    owner_references: Some(vec![owner_reference(
+                        "ReplicaSet",
+                        "sandbox0-rs",
+                        Some(true),
+                    )]),
+                    ..ObjectMeta::default()
+                },
+                Some("ReplicaSet/sandbox0-rs"),
+            ),
+        ];
+
+        for (metadata, expected) in cases.into_iter() {
+            assert_eq!(resolve_owner(&metadata).as_deref(), expected);
+        }
+    }
+
+    #[test]
+    fn test_annotate_from_namespace() {
+        let cases = vec![
+            (
+                FieldsSpec::default(),
+                ObjectMeta::default(),
+                LogEvent::default(),
+            ),
+            (
+                FieldsSpec::default(),
+                ObjectMeta {
+                    name: Some("sandbox0-ns".to_owned()),
+                    labels: Some(
+                        vec![("team".to_owned(), "sandbox0-team".to_owned())]
+                            .into_iter()
+                            .collect(),
+                    ),
+                    annotations: Some(
+                        vec![("example.com/owner".to_owned(), "sandbox0".to_owned())]
+                            .into_iter()
+                            .collect(),
+                    ),
+                    ..ObjectMeta::default()
+                },
+                {
+                    let mut log = LogEvent::default();
+                    log.insert("kubernetes.namespace_labels.team", "sandbox0-team");
+                    log.insert(
+                        "kubernetes.namespace_annotations.example\\.com/owner",
+                        "sandbox0",
+                    );
+                    log
+                },
+            ),
+            (
+                FieldsSpec {
+                    namespace_labels: "ns_labels".to_owned(),
+                    namespace_annotations: "ns_annotations".to_owned(),
+                    ..Default::default()
+                },
+                ObjectMeta {
+                    labels: Some(
+                        vec![("team".to_owned(), "sandbox0-team".to_owned())]
+                            .into_iter()
+                            .collect(),
+                    ),
+                    annotations: Some(
+                        vec![("owner".to_owned(), "sandbox0".to_owned())]
+                            .into_iter()
+                            .collect(),
+                    ),
+                    ..ObjectMeta::default()
+                },
+                {
+                    let mut log = LogEvent::default();
+                    log.insert("ns_labels.team", "sandbox0-team");
+                    log.insert("ns_annotations.owner", "sandbox0");
+                    log
+                },
+            ),
+        ];
+
+        for (fields_spec, metadata, expected) in cases.into_iter() {
+            let mut log = LogEvent::default();
+            annotate_from_namespace(&mut log, &fields_spec, &metadata);
+            assert_eq!(log, expected);
+        }
+    }
+
+    #[test]
+    fn test_annotate_from_node() {
+        let cases = vec![
+            (
+                FieldsSpec::default(),
+                ObjectMeta::default(),
+                LogEvent::default(),
+            ),
+            (
+                FieldsSpec::default(),
+                ObjectMeta {
+                    name: Some("sandbox0-node".to_owned()),
+                    labels: Some(
+                        vec![
+                            (
+                                "topology.kubernetes.io/zone".to_owned(),
+                                "us-east-1a".to_owned(),
+                            ),
+                            (
+                                "node.kubernetes.io/instance-type".to_owned(),
+                                "m5.large".to_owned(),
+                            ),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    ),
+                    ..ObjectMeta::default()
+                },
+                {
+                    let mut log = LogEvent::default();
+                    log.insert(
+                        "kubernetes.node_labels.topology\\.kubernetes\\.io/zone",
+                        "us-east-1a",
+                    );
+                    log.insert(
+                        "kubernetes.node_labels.node\\.kubernetes\\.io/instance-type",
+                        "m5.large",
+                    );
+                    log
+                },
+            ),
+        ];
+
+        for (fields_spec, metadata, expected) in cases.into_iter() {
+            let mut log = LogEvent::default();
+            annotate_from_node(&mut log, &fields_spec, &metadata);
+            assert_eq!(log, expected);
+        }
+    }
 }
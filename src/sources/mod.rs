@@ -1,16 +1,26 @@
 use futures01::Future;
 use snafu::Snafu;
 
+#[cfg(feature = "sources-amqp")]
+pub mod amqp;
 #[cfg(feature = "sources-apache_metrics")]
 pub mod apache_metrics;
 #[cfg(feature = "sources-aws_kinesis_firehose")]
 pub mod aws_kinesis_firehose;
 #[cfg(feature = "sources-aws_s3")]
 pub mod aws_s3;
+#[cfg(feature = "sources-aws_sqs")]
+pub mod aws_sqs;
 #[cfg(feature = "sources-docker")]
 pub mod docker;
+#[cfg(feature = "sources-elasticsearch")]
+pub mod elasticsearch;
 #[cfg(feature = "sources-file")]
 pub mod file;
+#[cfg(feature = "sources-gcp_pubsub")]
+pub mod gcp_pubsub;
+#[cfg(feature = "sources-gelf")]
+pub mod gelf;
 #[cfg(feature = "sources-generator")]
 pub mod generator;
 #[cfg(feature = "sources-host_metrics")]
@@ -23,14 +33,22 @@ pub mod internal_metrics;
 pub mod journald;
 #[cfg(all(feature = "sources-kafka", feature = "rdkafka"))]
 pub mod kafka;
+#[cfg(feature = "sources-kubernetes-events")]
+pub mod kubernetes_events;
 #[cfg(feature = "sources-kubernetes-logs")]
 pub mod kubernetes_logs;
 #[cfg(feature = "sources-logplex")]
 pub mod logplex;
 #[cfg(feature = "sources-mongodb_metrics")]
 pub mod mongodb_metrics;
+#[cfg(feature = "sources-nats")]
+pub mod nats;
 #[cfg(feature = "sources-prometheus")]
 pub mod prometheus;
+#[cfg(feature = "sources-pulsar")]
+pub mod pulsar;
+#[cfg(feature = "sources-redis")]
+pub mod redis;
 #[cfg(feature = "sources-socket")]
 pub mod socket;
 #[cfg(feature = "sources-splunk_hec")]
@@ -44,7 +62,7 @@ pub mod syslog;
 #[cfg(feature = "sources-vector")]
 pub mod vector;
 
-mod util;
+pub(crate) mod util;
 
 pub type Source = Box<dyn Future<Item = (), Error = ()> + Send>;
 
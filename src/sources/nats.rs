@@ -0,0 +1,222 @@
+use crate::{
+    config::{DataType, GenerateConfig, GlobalOptions, SourceConfig, SourceDescription},
+    internal_events::NatsEventReceived,
+    shutdown::ShutdownSignal,
+    sources::util::message::{decode_message, insert_headers},
+    Pipeline,
+};
+use futures::{compat::Future01CompatExt, FutureExt, StreamExt, TryFutureExt};
+use futures01::Sink;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("NATS Connect Error: {}", source))]
+    Connect { source: std::io::Error },
+    #[snafu(display("NATS Subscribe Error: {}", source))]
+    Subscribe { source: std::io::Error },
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NatsSourceConfig {
+    url: String,
+    #[serde(default = "default_name")]
+    name: String,
+    subject: String,
+    /// Subscribers sharing a queue group each receive a subset of the messages.
+    queue: Option<String>,
+    /// The field the subject each message was received on is written to.
+    subject_key: Option<String>,
+    /// The field the message headers are written to, as a map.
+    headers_key: Option<String>,
+}
+
+fn default_name() -> String {
+    String::from("vector")
+}
+
+inventory::submit! {
+    SourceDescription::new::<NatsSourceConfig>("nats")
+}
+
+impl GenerateConfig for NatsSourceConfig {
+    fn generate_config() -> toml::Value {
+        toml::from_str(
+            r#"
+            url = "nats://127.0.0.1:4222"
+            name = "vector"
+            subject = "from.vector"
+            "#,
+        )
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "nats")]
+impl SourceConfig for NatsSourceConfig {
+    async fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<super::Source> {
+        let subscription = self.subscribe().await?;
+        Ok(Box::new(
+            nats_source(
+                subscription,
+                self.subject_key.clone(),
+                self.headers_key.clone(),
+                shutdown,
+                out,
+            )
+            .boxed()
+            .compat(),
+        ))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "nats"
+    }
+}
+
+impl NatsSourceConfig {
+    fn to_nats_options(&self) -> nats::Options {
+        nats::Options::new().with_name(&self.name)
+    }
+
+    async fn connect(&self) -> crate::Result<nats::asynk::Connection> {
+        self.to_nats_options()
+            .connect_async(&self.url)
+            .await
+            .context(Connect)
+            .map_err(Into::into)
+    }
+
+    async fn subscribe(&self) -> crate::Result<nats::asynk::Subscription> {
+        let connection = self.connect().await?;
+        let subscription = match &self.queue {
+            None => connection.subscribe(&self.subject).await,
+            Some(queue) => connection.queue_subscribe(&self.subject, queue).await,
+        };
+        subscription.context(Subscribe).map_err(Into::into)
+    }
+}
+
+async fn nats_source(
+    subscription: nats::asynk::Subscription,
+    subject_key: Option<String>,
+    headers_key: Option<String>,
+    shutdown: ShutdownSignal,
+    out: Pipeline,
+) -> Result<(), ()> {
+    let mut out = out.sink_map_err(|error| error!(message = "Error sending event.", %error));
+    // Dropping the subscription unsubscribes from the subject.
+    let mut messages = subscription.take_until(shutdown);
+
+    while let Some(msg) = messages.next().await {
+        emit!(NatsEventReceived {
+            byte_size: msg.data.len()
+        });
+
+        let mut event = decode_message("nats", &msg.data, None);
+        if let Some(subject_key) = &subject_key {
+            event.as_mut_log().insert(subject_key, msg.subject.clone());
+        }
+        if let Some(headers_key) = &headers_key {
+            // Headers may repeat, the last value of each is kept.
+            let headers = msg.headers.iter().flat_map(|headers| {
+                headers.iter().flat_map(|(name, values)| {
+                    values.iter().map(move |value| (name.clone(), value))
+                })
+            });
+            insert_headers(&mut event, headers_key, headers);
+        }
+
+        out = out.send(event).compat().await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NatsSourceConfig;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<NatsSourceConfig>();
+    }
+
+    #[test]
+    fn config_queue_group() {
+        let config: NatsSourceConfig = toml::from_str(
+            r#"
+            url = "nats://127.0.0.1:4222"
+            subject = "logs.>"
+            queue = "vector"
+            subject_key = "subject"
+            headers_key = "headers"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.name, "vector");
+        assert_eq!(config.queue.as_deref(), Some("vector"));
+        assert_eq!(config.headers_key.as_deref(), Some("headers"));
+    }
+}
+
+#[cfg(feature = "nats-integration-tests")]
+#[cfg(test)]
+mod integration_tests {
+    use super::*;
+    use crate::{
+        config::log_schema,
+        test_util::{collect_n, random_string, trace_init},
+    };
+
+    #[tokio::test]
+    async fn nats_source_receives_messages() {
+        trace_init();
+
+        let subject = format!("test-{}", random_string(10));
+        let config = NatsSourceConfig {
+            url: "nats://127.0.0.1:4222".to_owned(),
+            subject: subject.clone(),
+            subject_key: Some("subject".to_owned()),
+            ..Default::default()
+        };
+
+        let (tx, rx) = Pipeline::new_test();
+        let source = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .await
+            .unwrap();
+        tokio::spawn(source.compat());
+
+        let publisher = config.connect().await.unwrap();
+        for line in &["one", "two"] {
+            publisher.publish(&subject, line).await.unwrap();
+        }
+
+        let events = collect_n(rx, 2).await.unwrap();
+        for (event, line) in events.iter().zip(&["one", "two"]) {
+            let log = event.as_log();
+            assert_eq!(log[log_schema().message_key()], (*line).into());
+            assert_eq!(log["subject"], subject.clone().into());
+            assert_eq!(log[log_schema().source_type_key()], "nats".into());
+        }
+    }
+}
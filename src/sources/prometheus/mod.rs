@@ -277,6 +277,7 @@ mod test {
                 buckets: vec![1.0, 2.0, 4.0],
                 quantiles: vec![],
                 flush_period_secs: 1,
+                ..PrometheusExporterConfig::default()
             },
         );
 
@@ -0,0 +1,300 @@
+use crate::{
+    config::{DataType, GenerateConfig, GlobalOptions, SourceConfig, SourceDescription},
+    event::Event,
+    internal_events::{PulsarAcknowledgementFailed, PulsarEventFailed, PulsarEventReceived},
+    pulsar::{connect, PulsarAuthConfig},
+    shutdown::ShutdownSignal,
+    sources::util::message::{decode_message, insert_headers},
+    Pipeline,
+};
+use chrono::{TimeZone, Utc};
+use futures::{compat::Future01CompatExt, FutureExt, StreamExt, TryFutureExt};
+use futures01::Sink;
+use pulsar::{
+    consumer::{Consumer, Message},
+    Error as PulsarError, SubType, TokioExecutor,
+};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("creating pulsar consumer failed: {}", source))]
+    CreatePulsarConsumer { source: PulsarError },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PulsarSourceConfig {
+    endpoint: String,
+    topics: Vec<String>,
+    subscription_name: String,
+    #[serde(default)]
+    subscription_type: SubscriptionType,
+    #[serde(default = "default_consumer_name")]
+    consumer_name: String,
+    auth: Option<PulsarAuthConfig>,
+    /// The field the message key is written to.
+    key_field: Option<String>,
+    /// The field the message properties are written to, as a map.
+    properties_key: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Derivative, Eq, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionType {
+    #[derivative(Default)]
+    Exclusive,
+    Shared,
+    Failover,
+    KeyShared,
+}
+
+impl From<SubscriptionType> for SubType {
+    fn from(subscription_type: SubscriptionType) -> Self {
+        match subscription_type {
+            SubscriptionType::Exclusive => SubType::Exclusive,
+            SubscriptionType::Shared => SubType::Shared,
+            SubscriptionType::Failover => SubType::Failover,
+            SubscriptionType::KeyShared => SubType::KeyShared,
+        }
+    }
+}
+
+fn default_consumer_name() -> String {
+    String::from("vector")
+}
+
+type PulsarConsumer = Consumer<Vec<u8>, TokioExecutor>;
+
+inventory::submit! {
+    SourceDescription::new::<PulsarSourceConfig>("pulsar")
+}
+
+impl GenerateConfig for PulsarSourceConfig {
+    fn generate_config() -> toml::Value {
+        toml::Value::try_from(Self {
+            endpoint: "pulsar://127.0.0.1:6650".to_string(),
+            topics: vec!["topic-1234".to_string()],
+            subscription_name: "vector".to_string(),
+            subscription_type: SubscriptionType::default(),
+            consumer_name: default_consumer_name(),
+            auth: None,
+            key_field: None,
+            properties_key: None,
+        })
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "pulsar")]
+impl SourceConfig for PulsarSourceConfig {
+    async fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<super::Source> {
+        let consumer = self
+            .create_pulsar_consumer()
+            .await
+            .context(CreatePulsarConsumer)?;
+
+        Ok(Box::new(
+            pulsar_source(
+                consumer,
+                self.key_field.clone(),
+                self.properties_key.clone(),
+                shutdown,
+                out,
+            )
+            .boxed()
+            .compat(),
+        ))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "pulsar"
+    }
+}
+
+impl PulsarSourceConfig {
+    async fn create_pulsar_consumer(&self) -> Result<PulsarConsumer, PulsarError> {
+        let pulsar = connect(&self.endpoint, &self.auth).await?;
+        pulsar
+            .consumer()
+            .with_topics(&self.topics)
+            .with_consumer_name(&self.consumer_name)
+            .with_subscription_type(self.subscription_type.into())
+            .with_subscription(&self.subscription_name)
+            .build()
+            .await
+    }
+}
+
+async fn pulsar_source(
+    mut consumer: PulsarConsumer,
+    key_field: Option<String>,
+    properties_key: Option<String>,
+    mut shutdown: ShutdownSignal,
+    out: Pipeline,
+) -> Result<(), ()> {
+    let mut out = out.sink_map_err(|error| error!(message = "Error sending event.", %error));
+
+    loop {
+        let msg = tokio::select! {
+            msg = consumer.next() => match msg {
+                Some(Ok(msg)) => msg,
+                Some(Err(error)) => {
+                    emit!(PulsarEventFailed { error });
+                    continue;
+                }
+                None => break,
+            },
+            _ = &mut shutdown => break,
+        };
+
+        emit!(PulsarEventReceived {
+            byte_size: msg.payload.data.len()
+        });
+
+        let event = message_to_event(&msg, key_field.as_deref(), properties_key.as_deref());
+        out = out.send(event).compat().await?;
+
+        // Only acknowledge once the event has been handed to the pipeline, so
+        // that unacknowledged messages are redelivered after a restart.
+        if let Err(error) = consumer.ack(&msg).await {
+            emit!(PulsarAcknowledgementFailed {
+                error: error.into()
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn message_to_event(
+    msg: &Message<Vec<u8>>,
+    key_field: Option<&str>,
+    properties_key: Option<&str>,
+) -> Event {
+    let metadata = &msg.payload.metadata;
+    let timestamp = Utc
+        .timestamp_millis_opt(metadata.publish_time as i64)
+        .latest();
+    let mut event = decode_message("pulsar", &msg.payload.data, timestamp);
+
+    if let (Some(key_field), Some(key)) = (key_field, &metadata.partition_key) {
+        event.as_mut_log().insert(key_field, key.clone());
+    }
+    if let Some(properties_key) = properties_key {
+        insert_headers(
+            &mut event,
+            properties_key,
+            metadata
+                .properties
+                .iter()
+                .map(|property| (property.key.clone(), &property.value)),
+        );
+    }
+
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PulsarSourceConfig, SubscriptionType};
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<PulsarSourceConfig>();
+    }
+
+    #[test]
+    fn config_subscription_type() {
+        let config: PulsarSourceConfig = toml::from_str(
+            r#"
+            endpoint = "pulsar://127.0.0.1:6650"
+            topics = ["logs"]
+            subscription_name = "vector"
+            subscription_type = "key_shared"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.subscription_type, SubscriptionType::KeyShared);
+        assert_eq!(config.consumer_name, "vector");
+    }
+}
+
+#[cfg(feature = "pulsar-integration-tests")]
+#[cfg(test)]
+mod integration_tests {
+    use super::*;
+    use crate::{
+        config::log_schema,
+        test_util::{collect_n, random_string, trace_init},
+    };
+    use pulsar::{producer, Pulsar};
+
+    #[tokio::test]
+    async fn pulsar_source_receives_messages() {
+        trace_init();
+
+        let topic = format!("test-{}", random_string(10));
+        let config = PulsarSourceConfig {
+            endpoint: "pulsar://127.0.0.1:6650".to_owned(),
+            topics: vec![topic.clone()],
+            subscription_name: "vector".to_owned(),
+            subscription_type: SubscriptionType::Shared,
+            consumer_name: default_consumer_name(),
+            auth: None,
+            key_field: Some("key".to_owned()),
+            properties_key: Some("properties".to_owned()),
+        };
+
+        let (tx, rx) = Pipeline::new_test();
+        let source = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .await
+            .unwrap();
+        tokio::spawn(source.compat());
+
+        let pulsar = Pulsar::<TokioExecutor>::builder(&config.endpoint, TokioExecutor)
+            .build()
+            .await
+            .unwrap();
+        let mut producer = pulsar.producer().with_topic(&topic).build().await.unwrap();
+        let mut properties = std::collections::HashMap::new();
+        properties.insert("team".to_owned(), "core".to_owned());
+        producer
+            .send(producer::Message {
+                payload: b"hello".to_vec(),
+                properties,
+                partition_key: Some("my-key".to_owned()),
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+
+        let events = collect_n(rx, 1).await.unwrap();
+        let log = events[0].as_log();
+        assert_eq!(log[log_schema().message_key()], "hello".into());
+        assert_eq!(log["key"], "my-key".into());
+        assert_eq!(log["properties.team"], "core".into());
+        assert_eq!(log[log_schema().source_type_key()], "pulsar".into());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{
+    config::{DataType, GenerateConfig, GlobalOptions, SourceConfig, SourceDescription},
+    event::Event,
+    internal_events::{RedisEventReceived, RedisReceiveEventFailed},
+    shutdown::ShutdownSignal,
+    sources::util::message::decode_message,
+    Pipeline,
+};
+use futures::{compat::Future01CompatExt, FutureExt, StreamExt, TryFutureExt};
+use futures01::Sink;
+use redis::{aio::Connection, RedisError};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid redis url: {}", source))]
+    InvalidUrl { source: RedisError },
+    #[snafu(display("Failed to connect to redis: {}", source))]
+    Connect { source: RedisError },
+    #[snafu(display("Failed to subscribe to channel {:?}: {}", channel, source))]
+    Subscribe { channel: String, source: RedisError },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RedisSourceConfig {
+    url: String,
+    /// The list popped from, or the channel subscribed to.
+    key: String,
+    #[serde(default)]
+    data_type: DataTypeConfig,
+    /// The field the list or channel name each message was read from is written to.
+    redis_key: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Derivative, Eq, PartialEq)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DataTypeConfig {
+    /// Pop messages from a list with `BLPOP`.
+    #[derivative(Default)]
+    List,
+    /// Receive messages published to a channel with `SUBSCRIBE`.
+    Channel,
+}
+
+inventory::submit! {
+    SourceDescription::new::<RedisSourceConfig>("redis")
+}
+
+impl GenerateConfig for RedisSourceConfig {
+    fn generate_config() -> toml::Value {
+        toml::Value::try_from(Self {
+            url: "redis://127.0.0.1:6379/0".to_owned(),
+            key: "vector".to_owned(),
+            data_type: DataTypeConfig::default(),
+            redis_key: None,
+        })
+        .unwrap()
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "redis")]
+impl SourceConfig for RedisSourceConfig {
+    async fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: Pipeline,
+    ) -> crate::Result<super::Source> {
+        let conn = self.connect().await?;
+        let redis_key = self.redis_key.clone();
+        let source = match self.data_type {
+            DataTypeConfig::List => {
+                list_source(conn, self.key.clone(), redis_key, shutdown, out).boxed()
+            }
+            DataTypeConfig::Channel => {
+                let mut pubsub = conn.into_pubsub();
+                pubsub.subscribe(&self.key).await.context(Subscribe {
+                    channel: self.key.clone(),
+                })?;
+                channel_source(pubsub, redis_key, shutdown, out).boxed()
+            }
+        };
+
+        Ok(Box::new(source.compat()))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "redis"
+    }
+}
+
+impl RedisSourceConfig {
+    async fn connect(&self) -> crate::Result<Connection> {
+        let client = redis::Client::open(self.url.as_str()).context(InvalidUrl)?;
+        let conn = client.get_async_connection().await.context(Connect)?;
+        Ok(conn)
+    }
+}
+
+/// How long `BLPOP` waits for a message.
+const BLPOP_TIMEOUT_SECS: usize = 1;
+
+async fn list_source(
+    mut conn: Connection,
+    key: String,
+    redis_key: Option<String>,
+    mut shutdown: ShutdownSignal,
+    out: Pipeline,
+) -> Result<(), ()> {
+    let mut out = out.sink_map_err(|error| error!(message = "Error sending event.", %error));
+
+    // The command is never cancelled, as a popped message would be lost.
+    // Instead it times out every `BLPOP_TIMEOUT_SECS`, to check for shutdown.
+    while (&mut shutdown).now_or_never().is_none() {
+        let popped = redis::cmd("BLPOP")
+            .arg(&key)
+            .arg(BLPOP_TIMEOUT_SECS)
+            .query_async::<_, Option<(String, Vec<u8>)>>(&mut conn)
+            .await;
+
+        match popped {
+            Ok(Some((list, payload))) => {
+                let event = message_to_event(&payload, &list, redis_key.as_deref());
+                out = out.send(event).compat().await?;
+            }
+            Ok(None) => {}
+            Err(error) => {
+                let fatal = error.is_io_error() || error.is_connection_dropped();
+                emit!(RedisReceiveEventFailed { error });
+                if fatal {
+                    return Err(());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn channel_source(
+    mut pubsub: redis::aio::PubSub,
+    redis_key: Option<String>,
+    shutdown: ShutdownSignal,
+    out: Pipeline,
+) -> Result<(), ()> {
+    let mut out = out.sink_map_err(|error| error!(message = "Error sending event.", %error));
+    let mut messages = pubsub.on_message().take_until(shutdown);
+
+    while let Some(msg) = messages.next().await {
+        let event = message_to_event(
+            msg.get_payload_bytes(),
+            msg.get_channel_name(),
+            redis_key.as_deref(),
+        );
+        out = out.send(event).compat().await?;
+    }
+
+    Ok(())
+}
+
+fn message_to_event(payload: &[u8], key: &str, redis_key: Option<&str>) -> Event {
+    emit!(RedisEventReceived {
+        byte_size: payload.len()
+    });
+
+    let mut event = decode_message("redis", payload, None);
+    if let Some(redis_key) = redis_key {
+        event.as_mut_log().insert(redis_key, key.to_owned());
+    }
+    event
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::log_schema;
+
+    #[test]
+    fn generate_config() {
+        crate::test_util::test_generate_config::<RedisSourceConfig>();
+    }
+
+    #[test]
+    fn config_channel() {
+        let config: RedisSourceConfig = toml::from_str(
+            r#"
+            url = "redis://127.0.0.1:6379/0"
+            key = "logs"
+            data_type = "channel"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.data_type, DataTypeConfig::Channel);
+        assert_eq!(config.redis_key, None);
+    }
+
+    #[test]
+    fn redis_message_to_event() {
+        let event = message_to_event(b"hello", "logs", Some("redis_key"));
+        let log = event.as_log();
+
+        assert_eq!(log[log_schema().message_key()], "hello".into());
+        assert_eq!(log["redis_key"], "logs".into());
+        assert_eq!(log[log_schema().source_type_key()], "redis".into());
+    }
+}
+
+#[cfg(feature = "redis-integration-tests")]
+#[cfg(test)]
+mod integration_tests {
+    use super::*;
+    use crate::{
+        config::log_schema,
+        test_util::{collect_n, random_string, trace_init},
+    };
+
+    const REDIS_URL: &str = "redis://127.0.0.1:6379/0";
+
+    async fn run_source(config: &RedisSourceConfig) -> futures01::sync::mpsc::Receiver<Event> {
+        let (tx, rx) = Pipeline::new_test();
+        let source = config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .await
+            .unwrap();
+        tokio::spawn(source.compat());
+        rx
+    }
+
+    #[tokio::test]
+    async fn redis_source_list() {
+        trace_init();
+
+        let key = format!("test-{}", random_string(10));
+        let config = RedisSourceConfig {
+            url: REDIS_URL.to_owned(),
+            key: key.clone(),
+            data_type: DataTypeConfig::List,
+            redis_key: Some("redis_key".to_owned()),
+        };
+
+        let mut conn = config.connect().await.unwrap();
+        redis::cmd("RPUSH")
+            .arg(&key)
+            .arg("one")
+            .arg("two")
+            .query_async::<_, i64>(&mut conn)
+            .await
+            .unwrap();
+
+        let rx = run_source(&config).await;
+        let events = collect_n(rx, 2).await.unwrap();
+        for (event, line) in events.iter().zip(&["one", "two"]) {
+            let log = event.as_log();
+            assert_eq!(log[log_schema().message_key()], (*line).into());
+            assert_eq!(log["redis_key"], key.clone().into());
+        }
+    }
+
+    #[tokio::test]
+    async fn redis_source_channel() {
+        trace_init();
+
+        let key = format!("test-{}", random_string(10));
+        let config = RedisSourceConfig {
+            url: REDIS_URL.to_owned(),
+            key: key.clone(),
+            data_type: DataTypeConfig::Channel,
+            redis_key: None,
+        };
+
+        let rx = run_source(&config).await;
+
+        let mut conn = config.connect().await.unwrap();
+        redis::cmd("PUBLISH")
+            .arg(&key)
+            .arg("hello")
+            .query_async::<_, i64>(&mut conn)
+            .await
+            .unwrap();
+
+        let events = collect_n(rx, 1).await.unwrap();
+        assert_eq!(
+            events[0].as_log()[log_schema().message_key()],
+            "hello".into()
+        );
+    }
+}
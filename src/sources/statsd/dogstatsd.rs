@@ -0,0 +1,229 @@
+use super::parser::{parse_tags, parse_timestamp, ParseError, CONTAINER_ID_TAG};
+use crate::{
+    config::log_schema,
+    event::{LogEvent, Value},
+};
+use chrono::Utc;
+use std::collections::BTreeMap;
+
+/// Parses a DogStatsD event, formatted as
+/// `_e{<TITLE_LENGTH>,<TEXT_LENGTH>}:<TITLE>|<TEXT>|d:<TIMESTAMP>|h:<HOSTNAME>|...`
+pub fn parse_event(packet: &str) -> Result<LogEvent, ParseError> {
+    // https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/#events
+    let body = packet
+        .strip_prefix("_e{")
+        .ok_or_else(|| ParseError::Malformed("expected '_e{' event prefix"))?;
+    let lengths_end = body
+        .find("}:")
+        .ok_or_else(|| ParseError::Malformed("expected '}:' after event lengths"))?;
+    let mut lengths = body[..lengths_end].splitn(2, ',');
+    let title_len: usize = lengths.next().unwrap_or_default().parse()?;
+    let text_len: usize = lengths
+        .next()
+        .ok_or_else(|| ParseError::Malformed("expected title and text lengths"))?
+        .parse()?;
+
+    let rest = &body[lengths_end + 2..];
+    let title = rest
+        .get(..title_len)
+        .ok_or_else(|| ParseError::Malformed("event title is shorter than its length"))?;
+    let rest = rest[title_len..]
+        .strip_prefix('|')
+        .ok_or_else(|| ParseError::Malformed("expected '|' after event title"))?;
+    let text = rest
+        .get(..text_len)
+        .ok_or_else(|| ParseError::Malformed("event text is shorter than its length"))?;
+    let rest = &rest[text_len..];
+
+    let mut log = LogEvent::default();
+    log.insert("type", "event");
+    log.insert("title", unescape(title));
+    log.insert(log_schema().message_key(), unescape(text));
+
+    let mut tags = None;
+    let mut timestamp = None;
+    for part in rest.split('|').skip(1) {
+        if part.starts_with('#') {
+            tags = Some(parse_tags(part)?);
+        } else if let Some(ts) = part.strip_prefix("d:") {
+            timestamp = Some(parse_timestamp(ts)?);
+        } else if let Some(host) = part.strip_prefix("h:") {
+            log.insert(log_schema().host_key(), host);
+        } else if let Some(key) = part.strip_prefix("k:") {
+            log.insert("aggregation_key", key);
+        } else if let Some(priority) = part.strip_prefix("p:") {
+            log.insert("priority", priority);
+        } else if let Some(source) = part.strip_prefix("s:") {
+            log.insert("source_type_name", source);
+        } else if let Some(alert_type) = part.strip_prefix("t:") {
+            log.insert("alert_type", alert_type);
+        } else if let Some(id) = part.strip_prefix("c:") {
+            insert_container_id(&mut log, id);
+        }
+    }
+
+    finish(log, tags, timestamp)
+}
+
+/// Parses a DogStatsD service check, formatted as
+/// `_sc|<NAME>|<STATUS>|d:<TIMESTAMP>|h:<HOSTNAME>|#<TAGS>|m:<MESSAGE>`
+pub fn parse_service_check(packet: &str) -> Result<LogEvent, ParseError> {
+    // https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/#service-checks
+    let body = packet
+        .strip_prefix("_sc|")
+        .ok_or_else(|| ParseError::Malformed("expected '_sc|' service check prefix"))?;
+    // the message is the last part, and may contain pipes
+    let (body, message) = match body.find("|m:") {
+        Some(index) => (&body[..index], Some(&body[index + 3..])),
+        None => (body, None),
+    };
+
+    let mut parts = body.split('|');
+    let name = parts
+        .next()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| ParseError::Malformed("expected service check name"))?;
+    let status = match parts.next() {
+        Some("0") => "ok",
+        Some("1") => "warning",
+        Some("2") => "critical",
+        Some("3") => "unknown",
+        _ => return Err(ParseError::Malformed("expected service check status")),
+    };
+
+    let mut log = LogEvent::default();
+    log.insert("type", "service_check");
+    log.insert("name", name);
+    log.insert("status", status);
+    if let Some(message) = message {
+        log.insert(log_schema().message_key(), unescape(message));
+    }
+
+    let mut tags = None;
+    let mut timestamp = None;
+    for part in parts {
+        if part.starts_with('#') {
+            tags = Some(parse_tags(part)?);
+        } else if let Some(ts) = part.strip_prefix("d:") {
+            timestamp = Some(parse_timestamp(ts)?);
+        } else if let Some(host) = part.strip_prefix("h:") {
+            log.insert(log_schema().host_key(), host);
+        } else if let Some(id) = part.strip_prefix("c:") {
+            insert_container_id(&mut log, id);
+        }
+    }
+
+    finish(log, tags, timestamp)
+}
+
+/// Sets the ID of the container an event originates from.
+pub fn insert_container_id(log: &mut LogEvent, id: &str) {
+    if !id.is_empty() {
+        log.insert(CONTAINER_ID_TAG, id);
+    }
+}
+
+fn finish(
+    mut log: LogEvent,
+    tags: Option<BTreeMap<String, String>>,
+    timestamp: Option<chrono::DateTime<Utc>>,
+) -> Result<LogEvent, ParseError> {
+    if let Some(tags) = tags {
+        let tags = tags
+            .into_iter()
+            .map(|(key, value)| (key, Value::from(value)))
+            .collect::<BTreeMap<_, _>>();
+        log.insert("tags", Value::Map(tags));
+    }
+    log.insert(
+        log_schema().timestamp_key(),
+        timestamp.unwrap_or_else(Utc::now),
+    );
+    log.insert(log_schema().source_type_key(), "statsd");
+    Ok(log)
+}
+
+// New lines are escaped by the clients.
+fn unescape(text: &str) -> String {
+    text.replace("\\n", "\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_event, parse_service_check};
+    use crate::{config::log_schema, event::Value};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn parses_events() {
+        let log = parse_event(
+            "_e{5,10}:Ti|le|Text\\nhere|d:1602151296|h:host|k:key|p:low|s:app|t:warning|#env:prod,canary|c:83c1f4f1",
+        )
+        .unwrap();
+
+        assert_eq!(log["type"], "event".into());
+        assert_eq!(log["title"], "Ti|le".into());
+        assert_eq!(log[log_schema().message_key()], "Text\nhere".into());
+        assert_eq!(
+            log[log_schema().timestamp_key()],
+            Utc.timestamp(1602151296, 0).into()
+        );
+        assert_eq!(log[log_schema().host_key()], "host".into());
+        assert_eq!(log["aggregation_key"], "key".into());
+        assert_eq!(log["priority"], "low".into());
+        assert_eq!(log["source_type_name"], "app".into());
+        assert_eq!(log["alert_type"], "warning".into());
+        assert_eq!(log["container_id"], "83c1f4f1".into());
+        assert_eq!(log["tags.env"], "prod".into());
+        assert_eq!(log["tags.canary"], "true".into());
+        assert_eq!(log[log_schema().source_type_key()], "statsd".into());
+    }
+
+    #[test]
+    fn parses_minimal_events() {
+        let log = parse_event("_e{5,4}:title|text").unwrap();
+
+        assert_eq!(log["title"], "title".into());
+        assert_eq!(log[log_schema().message_key()], "text".into());
+        assert!(log.get(log_schema().host_key()).is_none());
+        assert!(matches!(
+            log[log_schema().timestamp_key()],
+            Value::Timestamp(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_events() {
+        assert!(parse_event("_e{5,4}:titletext").is_err());
+        assert!(parse_event("_e{50,4}:title|text").is_err());
+        assert!(parse_event("_e{5}:title|text").is_err());
+        assert!(parse_event("_e{5,4}:title|text|d:yesterday").is_err());
+    }
+
+    #[test]
+    fn parses_service_checks() {
+        let log = parse_service_check(
+            "_sc|my.check|2|d:1602151296|h:host|#env:prod|c:83c1f4f1|m:Down | for now",
+        )
+        .unwrap();
+
+        assert_eq!(log["type"], "service_check".into());
+        assert_eq!(log["name"], "my.check".into());
+        assert_eq!(log["status"], "critical".into());
+        assert_eq!(log[log_schema().message_key()], "Down | for now".into());
+        assert_eq!(
+            log[log_schema().timestamp_key()],
+            Utc.timestamp(1602151296, 0).into()
+        );
+        assert_eq!(log[log_schema().host_key()], "host".into());
+        assert_eq!(log["container_id"], "83c1f4f1".into());
+        assert_eq!(log["tags.env"], "prod".into());
+    }
+
+    #[test]
+    fn rejects_malformed_service_checks() {
+        assert!(parse_service_check("_sc|my.check").is_err());
+        assert!(parse_service_check("_sc|my.check|4").is_err());
+        assert!(parse_service_check("_sc||0").is_err());
+    }
+}
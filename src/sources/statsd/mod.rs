@@ -14,11 +14,12 @@ use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use tokio::net::UdpSocket;
 use tokio_util::{codec::BytesCodec, udp::UdpFramed};
 
+mod dogstatsd;
 pub mod parser;
 #[cfg(unix)]
 mod unix;
 
-use parser::parse;
+use parser::{parse, ParseError};
 #[cfg(unix)]
 use unix::{statsd_unix, UnixConfig};
 
@@ -34,6 +35,8 @@ enum StatsdConfig {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct UdpConfig {
     pub address: SocketAddr,
+    #[serde(default)]
+    pub dogstatsd_events: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -43,6 +46,8 @@ struct TcpConfig {
     tls: Option<TlsConfig>,
     #[serde(default = "default_shutdown_timeout_secs")]
     pub shutdown_timeout_secs: u64,
+    #[serde(default)]
+    dogstatsd_events: bool,
 }
 
 fn default_shutdown_timeout_secs() -> u64 {
@@ -57,6 +62,7 @@ impl GenerateConfig for StatsdConfig {
     fn generate_config() -> toml::Value {
         toml::Value::try_from(Self::Udp(UdpConfig {
             address: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 8125)),
+            dogstatsd_events: false,
         }))
         .unwrap()
     }
@@ -78,7 +84,10 @@ impl SourceConfig for StatsdConfig {
             )),
             StatsdConfig::Tcp(config) => {
                 let tls = MaybeTlsSettings::from_config(&config.tls, true)?;
-                StatsdTcpSource.run(
+                let source = StatsdTcpSource {
+                    dogstatsd_events: config.dogstatsd_events,
+                };
+                source.run(
                     config.address,
                     config.shutdown_timeout_secs,
                     tls,
@@ -92,7 +101,11 @@ impl SourceConfig for StatsdConfig {
     }
 
     fn output_type(&self) -> crate::config::DataType {
-        config::DataType::Metric
+        if self.dogstatsd_events() {
+            config::DataType::Any
+        } else {
+            config::DataType::Metric
+        }
     }
 
     fn source_type(&self) -> &'static str {
@@ -109,13 +122,26 @@ impl SourceConfig for StatsdConfig {
     }
 }
 
-pub(self) fn parse_event(line: &str) -> Option<Event> {
-    match parse(line) {
-        Ok(metric) => {
+impl StatsdConfig {
+    /// DogStatsD events and service checks are turned into logs, so they
+    /// are only parsed when asked for to keep the output metrics only.
+    fn dogstatsd_events(&self) -> bool {
+        match self {
+            Self::Tcp(tcp) => tcp.dogstatsd_events,
+            Self::Udp(udp) => udp.dogstatsd_events,
+            #[cfg(unix)]
+            Self::Unix(unix) => unix.dogstatsd_events,
+        }
+    }
+}
+
+pub(self) fn parse_event(line: &str, dogstatsd_events: bool) -> Option<Event> {
+    match parse_line(line, dogstatsd_events) {
+        Ok(event) => {
             emit!(StatsdEventReceived {
                 byte_size: line.len()
             });
-            Some(Event::Metric(metric))
+            Some(event)
         }
         Err(error) => {
             emit!(StatsdInvalidRecord { error, text: line });
@@ -124,6 +150,31 @@ pub(self) fn parse_event(line: &str) -> Option<Event> {
     }
 }
 
+fn parse_line(line: &str, dogstatsd_events: bool) -> Result<Event, ParseError> {
+    if dogstatsd_events && line.starts_with("_e{") {
+        dogstatsd::parse_event(line).map(Event::Log)
+    } else if dogstatsd_events && line.starts_with("_sc|") {
+        dogstatsd::parse_service_check(line).map(Event::Log)
+    } else {
+        parse(line).map(Event::Metric)
+    }
+}
+
+/// Sets the ID of the container the event originates from, overriding the
+/// one sent by the client.
+#[cfg(unix)]
+pub(self) fn set_container_id(event: &mut Event, id: &str) {
+    match event {
+        Event::Log(log) => dogstatsd::insert_container_id(log, id),
+        Event::Metric(metric) => {
+            metric
+                .tags
+                .get_or_insert_with(Default::default)
+                .insert(parser::CONTAINER_ID_TAG.to_owned(), id.to_owned());
+        }
+    }
+}
+
 async fn statsd_udp(config: UdpConfig, shutdown: ShutdownSignal, out: Pipeline) -> Result<(), ()> {
     let socket = UdpSocket::bind(&config.address)
         .map_err(|error| emit!(StatsdSocketError::bind(error)))
@@ -135,13 +186,17 @@ async fn statsd_udp(config: UdpConfig, shutdown: ShutdownSignal, out: Pipeline)
         r#type = "udp"
     );
 
+    let dogstatsd_events = config.dogstatsd_events;
     let mut stream = UdpFramed::new(socket, BytesCodec::new()).take_until(shutdown);
     let mut out = out.sink_compat();
     while let Some(frame) = stream.next().await {
         match frame {
             Ok((bytes, _sock)) => {
                 let packet = String::from_utf8_lossy(bytes.as_ref());
-                let metrics = packet.lines().filter_map(parse_event).map(Ok);
+                let metrics = packet
+                    .lines()
+                    .filter_map(|line| parse_event(line, dogstatsd_events))
+                    .map(Ok);
 
                 // Need `boxed` to resolve a lifetime issue
                 // https://github.com/rust-lang/rust/issues/64552#issuecomment-669728225
@@ -161,7 +216,9 @@ async fn statsd_udp(config: UdpConfig, shutdown: ShutdownSignal, out: Pipeline)
 }
 
 #[derive(Clone)]
-struct StatsdTcpSource;
+struct StatsdTcpSource {
+    dogstatsd_events: bool,
+}
 
 impl TcpSource for StatsdTcpSource {
     type Error = std::io::Error;
@@ -173,7 +230,7 @@ impl TcpSource for StatsdTcpSource {
 
     fn build_event(&self, line: Bytes, _host: Bytes) -> Option<Event> {
         let line = String::from_utf8_lossy(line.as_ref());
-        parse_event(&line)
+        parse_event(&line, self.dogstatsd_events)
     }
 }
 
@@ -197,6 +254,39 @@ mod test {
         crate::test_util::test_generate_config::<StatsdConfig>();
     }
 
+    #[test]
+    fn dogstatsd_events_are_opt_in() {
+        // Events and service checks would be logs, which metric sinks
+        // can't handle.
+        let config = config::load_from_str(
+            r#"
+            [sources.in]
+            type = "statsd"
+            mode = "udp"
+            address = "127.0.0.1:8125"
+
+            [sinks.out]
+            type = "prometheus_exporter"
+            inputs = ["in"]
+            address = "127.0.0.1:9598"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.sources["in"].output_type(), config::DataType::Metric);
+        assert!(parse_line("_e{5,4}:title|text", false).is_err());
+        assert!(parse_line("_sc|check|0", false).is_err());
+
+        let config = StatsdConfig::Udp(UdpConfig {
+            address: next_addr(),
+            dogstatsd_events: true,
+        });
+        assert_eq!(config.output_type(), config::DataType::Any);
+        assert!(matches!(
+            parse_line("_e{5,4}:title|text", true),
+            Ok(Event::Log(_))
+        ));
+    }
+
     fn parse_count(lines: &[&str], prefix: &str) -> usize {
         lines
             .iter()
@@ -210,7 +300,10 @@ mod test {
     #[tokio::test]
     async fn test_statsd_udp() {
         let in_addr = next_addr();
-        let config = StatsdConfig::Udp(UdpConfig { address: in_addr });
+        let config = StatsdConfig::Udp(UdpConfig {
+            address: in_addr,
+            dogstatsd_events: false,
+        });
         let sender = {
             let (sender, mut receiver) = mpsc::channel(200);
             let addr = in_addr;
@@ -234,6 +327,7 @@ mod test {
             address: in_addr.into(),
             tls: None,
             shutdown_timeout_secs: 30,
+            dogstatsd_events: false,
         });
         let sender = {
             let (sender, mut receiver) = mpsc::channel(200);
@@ -259,6 +353,8 @@ mod test {
         let in_path = tempfile::tempdir().unwrap().into_path().join("unix_test");
         let config = StatsdConfig::Unix(UnixConfig {
             path: in_path.clone(),
+            origin_detection: false,
+            dogstatsd_events: false,
         });
         let sender = {
             let (sender, mut receiver) = mpsc::channel(200);
@@ -297,6 +393,7 @@ mod test {
                 buckets: vec![1.0, 2.0, 4.0],
                 quantiles: vec![],
                 flush_period_secs: 1,
+                ..PrometheusExporterConfig::default()
             },
         );
 
@@ -307,7 +404,7 @@ mod test {
 
         for _ in 0..100 {
             sender.send(
-                b"foo:1|c|#a,b:b\nbar:42|g\nfoo:1|c|#a,b:c\nglork:3|h|@0.1\nmilliglork:3000|ms|@0.1\nset:0|s\nset:1|s\n"
+                b"foo:1|c|#a,b:b\nbar:42|g\nfoo:1|c|#a,b:c\nglork:3|h|@0.1\nmilliglork:3000|ms|@0.1\nset:0|s\nset:1|s\n_e{5,4}:title|text\n_sc|check|0\n"
             ).await.unwrap();
             // Space things out slightly to try to avoid dropped packets
             delay_for(Duration::from_millis(10)).await;
@@ -1,4 +1,5 @@
 use crate::event::metric::{Metric, MetricKind, MetricValue, StatisticKind};
+use chrono::{DateTime, TimeZone, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::{
@@ -12,6 +13,9 @@ lazy_static! {
     static ref NONALPHANUM: Regex = Regex::new(r"[^a-zA-Z_\-0-9\.]").unwrap();
 }
 
+/// The tag holding the ID of the container the metrics originate from.
+pub const CONTAINER_ID_TAG: &str = "container_id";
+
 pub fn parse(packet: &str) -> Result<Metric, ParseError> {
     // https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/#datagram-format
     let key_and_body = packet.splitn(2, ':').collect::<Vec<_>>();
@@ -31,35 +35,40 @@ pub fn parse(packet: &str) -> Result<Metric, ParseError> {
 
     let name = sanitize_key(key);
     let metric_type = parts[1];
-
-    // sampling part is optional and comes after metric type part
-    let sampling = parts.get(2).filter(|s| s.starts_with('@'));
-    let sample_rate = if let Some(s) = sampling {
-        1.0 / sanitize_sampling(parse_sampling(s)?)
-    } else {
-        1.0
-    };
-
-    // tags are optional and could be found either after sampling of after metric type part
-    let tags = if sampling.is_none() {
-        parts.get(2)
-    } else {
-        parts.get(3)
-    };
-    let tags = tags.filter(|s| s.starts_with('#'));
-    let tags = if let Some(t) = tags {
-        Some(parse_tags(t)?)
-    } else {
-        None
-    };
+    // several values can be packed in a single message, separated by ':'
+    let values = parts[0].split(':').collect::<Vec<_>>();
+
+    // sampling, tags, container ID and timestamp are optional and come after metric type part
+    let mut sample_rate = 1.0;
+    let mut tags = None;
+    let mut container_id = None;
+    let mut timestamp = None;
+    for part in &parts[2..] {
+        if part.starts_with('@') {
+            sample_rate = 1.0 / sanitize_sampling(parse_sampling(part)?);
+        } else if part.starts_with('#') {
+            tags = Some(parse_tags(part)?);
+        } else if let Some(id) = part.strip_prefix("c:") {
+            container_id = Some(id);
+        } else if let Some(ts) = part.strip_prefix('T') {
+            timestamp = Some(parse_timestamp(ts)?);
+        }
+    }
+    if let Some(id) = container_id.filter(|id| !id.is_empty()) {
+        tags.get_or_insert_with(BTreeMap::new)
+            .insert(CONTAINER_ID_TAG.to_owned(), id.to_owned());
+    }
 
     let metric = match metric_type {
         "c" => {
-            let val: f64 = parts[0].parse()?;
+            let mut val = 0.0;
+            for value in values {
+                val += value.parse::<f64>()?;
+            }
             Metric {
                 name,
                 namespace: None,
-                timestamp: None,
+                timestamp,
                 tags,
                 kind: MetricKind::Incremental,
                 value: MetricValue::Counter {
@@ -68,61 +77,58 @@ pub fn parse(packet: &str) -> Result<Metric, ParseError> {
             }
         }
         unit @ "h" | unit @ "ms" | unit @ "d" => {
-            let val: f64 = parts[0].parse()?;
+            let values = values
+                .into_iter()
+                .map(|value| Ok(convert_to_base_units(unit, value.parse()?)))
+                .collect::<Result<Vec<f64>, ParseError>>()?;
             Metric {
                 name,
                 namespace: None,
-                timestamp: None,
+                timestamp,
                 tags,
                 kind: MetricKind::Incremental,
                 value: MetricValue::Distribution {
-                    values: vec![convert_to_base_units(unit, val)],
-                    sample_rates: vec![sample_rate as u32],
+                    sample_rates: vec![sample_rate as u32; values.len()],
+                    values,
                     statistic: convert_to_statistic(unit),
                 },
             }
         }
         "g" => {
-            let value = if parts[0]
-                .chars()
-                .next()
-                .map(|c| c.is_ascii_digit())
-                .ok_or_else(|| ParseError::Malformed("empty first body component"))?
-            {
-                parts[0].parse()?
-            } else {
-                parts[0][1..].parse()?
-            };
-
-            match parse_direction(parts[0])? {
-                None => Metric {
-                    name,
-                    namespace: None,
-                    timestamp: None,
-                    tags,
-                    kind: MetricKind::Absolute,
-                    value: MetricValue::Gauge { value },
-                },
-                Some(sign) => Metric {
-                    name,
-                    namespace: None,
-                    timestamp: None,
-                    tags,
-                    kind: MetricKind::Incremental,
-                    value: MetricValue::Gauge {
-                        value: value * sign,
-                    },
+            // an absolute value resets the gauge, the signed ones following it are added up
+            let mut absolute = false;
+            let mut val = 0.0;
+            for value in values {
+                match parse_direction(value)? {
+                    None => {
+                        absolute = true;
+                        val = value.parse()?;
+                    }
+                    Some(sign) => val += value[1..].parse::<f64>()? * sign,
+                }
+            }
+
+            Metric {
+                name,
+                namespace: None,
+                timestamp,
+                tags,
+                kind: if absolute {
+                    MetricKind::Absolute
+                } else {
+                    MetricKind::Incremental
                 },
+                value: MetricValue::Gauge { value: val },
             }
         }
         "s" => Metric {
             name,
             namespace: None,
-            timestamp: None,
+            timestamp,
             tags,
             kind: MetricKind::Incremental,
             value: MetricValue::Set {
-                values: vec![parts[0].into()].into_iter().collect(),
+                values: values.into_iter().map(Into::into).collect(),
             },
         },
         other => return Err(ParseError::UnknownMetricType(other.into())),
@@ -145,7 +151,14 @@ fn parse_sampling(input: &str) -> Result<f64, ParseError> {
     }
 }
 
-fn parse_tags(input: &str) -> Result<BTreeMap<String, String>, ParseError> {
+pub(super) fn parse_timestamp(input: &str) -> Result<DateTime<Utc>, ParseError> {
+    let secs: i64 = input.parse()?;
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| ParseError::Malformed("timestamp out of range"))
+}
+
+pub(super) fn parse_tags(input: &str) -> Result<BTreeMap<String, String>, ParseError> {
     if !input.starts_with('#') || input.len() < 2 {
         return Err(ParseError::Malformed(
             "expected non empty '#'-prefixed tags component",
@@ -240,8 +253,9 @@ impl From<ParseFloatError> for ParseError {
 
 #[cfg(test)]
 mod test {
-    use super::{parse, sanitize_key, sanitize_sampling};
+    use super::{parse, sanitize_key, sanitize_sampling, ParseError};
     use crate::event::metric::{Metric, MetricKind, MetricValue, StatisticKind};
+    use chrono::{TimeZone, Utc};
 
     #[test]
     fn basic_counter() {
@@ -441,6 +455,88 @@ mod test {
         );
     }
 
+    #[test]
+    fn packed_values() {
+        assert_eq!(
+            parse("glork:320:160|d|@0.5"),
+            Ok(Metric {
+                name: "glork".into(),
+                namespace: None,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Incremental,
+                value: MetricValue::Distribution {
+                    values: vec![320.0, 160.0],
+                    sample_rates: vec![2, 2],
+                    statistic: StatisticKind::Summary
+                },
+            }),
+        );
+        assert_eq!(
+            parse("foo:1:2|c"),
+            Ok(Metric {
+                name: "foo".into(),
+                namespace: None,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Incremental,
+                value: MetricValue::Counter { value: 3.0 },
+            }),
+        );
+        assert_eq!(
+            parse("gaugor:+1:10:-4|g"),
+            Ok(Metric {
+                name: "gaugor".into(),
+                namespace: None,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Absolute,
+                value: MetricValue::Gauge { value: 6.0 },
+            }),
+        );
+        assert_eq!(
+            parse("uniques:765:766|s"),
+            Ok(Metric {
+                name: "uniques".into(),
+                namespace: None,
+                timestamp: None,
+                tags: None,
+                kind: MetricKind::Incremental,
+                value: MetricValue::Set {
+                    values: vec!["765".into(), "766".into()].into_iter().collect()
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn container_id_and_timestamp() {
+        assert_eq!(
+            parse("foo:1|c|#tag1|c:83c1f4f1|T1602151296"),
+            Ok(Metric {
+                name: "foo".into(),
+                namespace: None,
+                timestamp: Some(Utc.timestamp(1602151296, 0)),
+                tags: Some(
+                    vec![
+                        ("container_id".to_owned(), "83c1f4f1".to_owned()),
+                        ("tag1".to_owned(), "true".to_owned()),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ),
+                kind: MetricKind::Incremental,
+                value: MetricValue::Counter { value: 1.0 },
+            }),
+        );
+        assert_eq!(
+            parse("foo:1|c|Tnope"),
+            Err(ParseError::InvalidInteger(
+                "nope".parse::<i64>().unwrap_err()
+            ))
+        );
+    }
+
     #[test]
     fn sanitizing_keys() {
         assert_eq!("foo-bar-baz", sanitize_key("foo/bar/baz"));
@@ -1,27 +1,111 @@
 use crate::{
-    shutdown::ShutdownSignal, sources::util::build_unix_source, sources::Source, Event, Pipeline,
+    shutdown::ShutdownSignal, sources::util::build_unix_connection_source, sources::Source,
+    Pipeline,
 };
 use bytes::Bytes;
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use tokio::net::UnixStream;
 use tokio_util::codec::LinesCodec;
 
+lazy_static! {
+    static ref CONTAINER_ID: Regex = Regex::new(r"[0-9a-f]{64}").unwrap();
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct UnixConfig {
     pub path: PathBuf,
-}
-
-fn build_event(_: &str, _: Option<Bytes>, line: &str) -> Option<Event> {
-    super::parse_event(line)
+    #[serde(default)]
+    pub origin_detection: bool,
+    #[serde(default)]
+    pub dogstatsd_events: bool,
 }
 
 pub fn statsd_unix(config: UnixConfig, shutdown: ShutdownSignal, out: Pipeline) -> Source {
-    build_unix_source(
+    let origin_detection = config.origin_detection;
+    let dogstatsd_events = config.dogstatsd_events;
+    build_unix_connection_source(
         config.path,
         LinesCodec::new(),
         String::new(),
         shutdown,
         out,
-        build_event,
+        move |socket| {
+            let container_id = if origin_detection {
+                peer_container_id(socket)
+            } else {
+                None
+            };
+
+            move |_: &str, _: Option<Bytes>, line: &str| {
+                let mut event = super::parse_event(line, dogstatsd_events)?;
+                if let Some(id) = &container_id {
+                    super::set_container_id(&mut event, id);
+                }
+                Some(event)
+            }
+        },
     )
 }
+
+/// Finds the container of the process on the other end of the socket, from
+/// its credentials and its control groups.
+#[cfg(target_os = "linux")]
+fn peer_container_id(socket: &UnixStream) -> Option<String> {
+    use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+    use std::os::unix::io::AsRawFd;
+
+    let credentials = getsockopt(socket.as_raw_fd(), PeerCredentials)
+        .map_err(|error| debug!(message = "Unable to get peer credentials.", %error))
+        .ok()?;
+    let path = format!("/proc/{}/cgroup", credentials.pid());
+    let cgroup = std::fs::read_to_string(&path)
+        .map_err(|error| debug!(message = "Unable to read peer control groups.", %path, %error))
+        .ok()?;
+    container_id_from_cgroup(&cgroup)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peer_container_id(_socket: &UnixStream) -> Option<String> {
+    None
+}
+
+// The container runtimes name the control groups of the containers after
+// their IDs, like `/docker/<ID>` or `/kubepods/.../cri-containerd-<ID>.scope`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn container_id_from_cgroup(cgroup: &str) -> Option<String> {
+    cgroup
+        .lines()
+        .filter_map(|line| line.splitn(3, ':').nth(2))
+        .find_map(|path| CONTAINER_ID.find_iter(path).last())
+        .map(|id| id.as_str().to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::container_id_from_cgroup;
+
+    const ID: &str = "3726184226f5d3147c25fdeab5b60097e378e8a720503a5e19ecfdf29f869860";
+
+    #[test]
+    fn finds_container_id() {
+        let docker = format!(
+            "12:pids:/docker/{}\n11:cpuset:/docker/{}\n0::/system.slice/containerd.service\n",
+            ID, ID
+        );
+        assert_eq!(container_id_from_cgroup(&docker), Some(ID.to_owned()));
+
+        let kubernetes = format!(
+            "0::/kubepods.slice/kubepods-besteffort.slice/kubepods-besteffort-pod2c48913c.slice/cri-containerd-{}.scope\n",
+            ID
+        );
+        assert_eq!(container_id_from_cgroup(&kubernetes), Some(ID.to_owned()));
+
+        assert_eq!(
+            container_id_from_cgroup("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
         log_schema, DataType, GenerateConfig, GlobalOptions, Resource, SourceConfig,
         SourceDescription,
     },
-    event::{Event, Value},
+    event::{Event, PathComponent, Value},
     internal_events::{SyslogEventReceived, SyslogUdpReadError, SyslogUdpUtf8Error},
     shutdown::ShutdownSignal,
     tls::{MaybeTlsSettings, TlsConfig},
@@ -18,6 +18,7 @@ use derive_is_enum_variant::is_enum_variant;
 use futures::{compat::Sink01CompatExt, FutureExt, StreamExt, TryFutureExt};
 use futures01::Sink;
 use serde::{Deserialize, Serialize};
+use std::collections::{btree_map::Entry, BTreeMap};
 use std::io;
 use std::net::SocketAddr;
 #[cfg(unix)]
@@ -25,7 +26,7 @@ use std::path::PathBuf;
 use syslog_loose::{IncompleteDate, Message, ProcId, Protocol};
 use tokio::net::UdpSocket;
 use tokio_util::{
-    codec::{BytesCodec, Decoder, LinesCodec, LinesCodecError},
+    codec::{BytesCodec, Decoder, LinesCodecError},
     udp::UdpFramed,
 };
 
@@ -39,6 +40,9 @@ pub struct SyslogConfig {
     pub max_length: usize,
     /// The host key of the log. (This differs from `hostname`)
     pub host_key: Option<String>,
+    /// Keep the raw RFC 5424 structured data in the `structured_data` field.
+    #[serde(default)]
+    pub raw_structured_data: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, is_enum_variant)]
@@ -67,6 +71,7 @@ impl SyslogConfig {
             mode,
             host_key: None,
             max_length: default_max_length(),
+            raw_structured_data: false,
         }
     }
 }
@@ -84,6 +89,7 @@ impl GenerateConfig for SyslogConfig {
             },
             host_key: None,
             max_length: default_max_length(),
+            raw_structured_data: false,
         })
         .unwrap()
     }
@@ -103,18 +109,27 @@ impl SourceConfig for SyslogConfig {
             .host_key
             .clone()
             .unwrap_or_else(|| log_schema().host_key().to_string());
+        let raw_structured_data = self.raw_structured_data;
 
         match self.mode.clone() {
             Mode::Tcp { address, tls } => {
                 let source = SyslogTcpSource {
                     max_length: self.max_length,
                     host_key,
+                    raw_structured_data,
                 };
                 let shutdown_secs = 30;
                 let tls = MaybeTlsSettings::from_config(&tls, true)?;
                 source.run(address, shutdown_secs, tls, shutdown, out)
             }
-            Mode::Udp { address } => Ok(udp(address, self.max_length, host_key, shutdown, out)),
+            Mode::Udp { address } => Ok(udp(
+                address,
+                self.max_length,
+                host_key,
+                raw_structured_data,
+                shutdown,
+                out,
+            )),
             #[cfg(unix)]
             Mode::Unix { path } => Ok(build_unix_source(
                 path,
@@ -122,7 +137,9 @@ impl SourceConfig for SyslogConfig {
                 host_key,
                 shutdown,
                 out,
-                event_from_str,
+                move |host_key, default_host, line| {
+                    event_from_str(host_key, default_host, line, raw_structured_data)
+                },
             )),
         }
     }
@@ -149,6 +166,7 @@ impl SourceConfig for SyslogConfig {
 struct SyslogTcpSource {
     max_length: usize,
     host_key: String,
+    raw_structured_data: bool,
 }
 
 impl TcpSource for SyslogTcpSource {
@@ -160,108 +178,198 @@ impl TcpSource for SyslogTcpSource {
     }
 
     fn build_event(&self, frame: String, host: Bytes) -> Option<Event> {
-        event_from_str(&self.host_key, Some(host), &frame)
+        event_from_str(&self.host_key, Some(host), &frame, self.raw_structured_data)
     }
 }
 
-/// Decodes according to `Octet Counting` in https://tools.ietf.org/html/rfc6587
+/// Decodes the `Octet Counting` and the newline delimited
+/// `Non-Transparent-Framing` of https://tools.ietf.org/html/rfc6587.
+///
+/// The framing is detected for every frame, so that senders switching between
+/// them, or terminating the octet counted frames with a newline, are handled.
 #[derive(Clone, Debug)]
 struct SyslogDecoder {
-    other: LinesCodec,
+    max_length: usize,
+    discard: Discard,
 }
 
+/// The remainder of an oversized frame, which is being skipped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Discard {
+    Nothing,
+    Bytes(usize),
+    UntilDelimiter,
+}
+
+/// Longest octet count we look for, longer prefixes can't be a valid count.
+const MAX_OCTET_COUNT_DIGITS: usize = 10;
+
 impl SyslogDecoder {
     fn new(max_length: usize) -> Self {
         Self {
-            other: LinesCodec::new_with_max_length(max_length),
+            max_length,
+            discard: Discard::Nothing,
+        }
+    }
+
+    fn is_delimiter(byte: &u8) -> bool {
+        // Some senders terminate the messages with a null byte instead.
+        *byte == b'\n' || *byte == b'\0'
+    }
+
+    /// Skips what is left of an oversized frame. Returns `false` if more
+    /// data is needed.
+    fn skip_discarded(&mut self, src: &mut BytesMut) -> bool {
+        match self.discard {
+            Discard::Nothing => true,
+            Discard::Bytes(len) => {
+                let skipped = len.min(src.len());
+                src.advance(skipped);
+                self.discard = if skipped == len {
+                    Discard::Nothing
+                } else {
+                    Discard::Bytes(len - skipped)
+                };
+                self.discard == Discard::Nothing
+            }
+            Discard::UntilDelimiter => match src.iter().position(Self::is_delimiter) {
+                Some(i) => {
+                    src.advance(i + 1);
+                    self.discard = Discard::Nothing;
+                    true
+                }
+                None => {
+                    src.clear();
+                    false
+                }
+            },
         }
     }
 
-    fn octet_decode(&self, src: &mut BytesMut) -> Result<Option<String>, LinesCodecError> {
-        // Encoding scheme:
-        //
-        // len ' ' data
-        // |    |  | len number of bytes that contain syslog message
-        // |    |
-        // |    | Separating whitespace
-        // |
-        // | ASCII decimal number of unknown length
-
-        if let Some(i) = src.iter().position(|&b| b == b' ') {
-            let len: usize = std::str::from_utf8(&src[..i])
-                .map_err(|_| ())
-                .and_then(|num| num.parse().map_err(|_| ()))
-                .map_err(|_| {
-                    LinesCodecError::Io(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "Unable to decode message len as number",
-                    ))
-                })?;
-
-            let from = i + 1;
-            let to = from + len;
-
-            if let Some(msg) = src.get(from..to) {
-                let s = std::str::from_utf8(msg)
-                    .map_err(|_| {
-                        LinesCodecError::Io(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "Unable to decode message as UTF8",
-                        ))
-                    })?
-                    .to_string();
-                src.advance(to);
-                Ok(Some(s))
-            } else {
-                Ok(None)
+    /// Returns the length of the octet count and the count, if the frame
+    /// starts with one. `None` if more data is needed to tell.
+    fn octet_count(src: &[u8]) -> Option<Option<(usize, usize)>> {
+        // A newline delimited frame starts with `<` of the PRI, while the
+        // octet count is a non zero decimal number followed by a space.
+        match src.first() {
+            None => return None,
+            Some(b'1'..=b'9') => {}
+            Some(_) => return Some(None),
+        }
+        let digits = src.iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits > MAX_OCTET_COUNT_DIGITS {
+            return Some(None);
+        }
+        match src.get(digits) {
+            None => None,
+            Some(b' ') => {
+                let count = std::str::from_utf8(&src[..digits])
+                    .ok()
+                    .and_then(|count| count.parse().ok());
+                Some(count.map(|count| (digits, count)))
             }
-        } else if src.len() < self.other.max_length() {
-            Ok(None)
-        } else {
-            // This is certainly malformed, and there is no recovering from this.
-            Err(LinesCodecError::Io(io::Error::new(
-                io::ErrorKind::Other,
-                "Frame length limit exceeded",
-            )))
+            Some(_) => Some(None),
         }
     }
 
-    /// None if this is not octet counting encoded
-    fn checked_decode(
-        &self,
+    fn octet_decode(
+        &mut self,
         src: &mut BytesMut,
-    ) -> Option<Result<Option<String>, LinesCodecError>> {
-        if let Some(&first_byte) = src.get(0) {
-            if 49 <= first_byte && first_byte <= 57 {
-                // First character is non zero number so we can assume that
-                // octet count framing is used.
-                trace!("Octet counting encoded event detected.");
-                return Some(self.octet_decode(src));
+        digits: usize,
+        count: usize,
+    ) -> Result<Option<String>, LinesCodecError> {
+        let len = digits + 1 + count;
+        if count > self.max_length {
+            self.discard = Discard::Bytes(len);
+            self.skip_discarded(src);
+            return Err(LinesCodecError::MaxLineLengthExceeded);
+        }
+        if src.len() < len {
+            src.reserve(len - src.len());
+            return Ok(None);
+        }
+        let frame = src.split_to(len);
+        decode_utf8(&frame[digits + 1..]).map(Some)
+    }
+
+    fn newline_decode(&mut self, src: &mut BytesMut) -> Result<Option<String>, LinesCodecError> {
+        let end = src
+            .iter()
+            .take(self.max_length + 1)
+            .position(Self::is_delimiter);
+        match end {
+            Some(end) => {
+                let frame = src.split_to(end + 1);
+                decode_utf8(&frame[..end]).map(Some)
             }
+            None if src.len() > self.max_length => {
+                self.discard = Discard::UntilDelimiter;
+                self.skip_discarded(src);
+                Err(LinesCodecError::MaxLineLengthExceeded)
+            }
+            None => Ok(None),
         }
-        None
     }
 }
 
+fn decode_utf8(frame: &[u8]) -> Result<String, LinesCodecError> {
+    let frame = match frame.split_last() {
+        Some((b'\r', frame)) => frame,
+        _ => frame,
+    };
+    std::str::from_utf8(frame).map(Into::into).map_err(|_| {
+        LinesCodecError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Unable to decode message as UTF8",
+        ))
+    })
+}
+
 impl Decoder for SyslogDecoder {
     type Item = String;
     type Error = LinesCodecError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if let Some(ret) = self.checked_decode(src) {
-            ret
-        } else {
-            // Octet counting isn't used so fallback to newline codec.
-            self.other.decode(src)
+        if !self.skip_discarded(src) {
+            return Ok(None);
+        }
+
+        // Skip the delimiters following the octet counted frames, and the
+        // empty lines.
+        let delimiters = src
+            .iter()
+            .take_while(|&byte| Self::is_delimiter(byte) || *byte == b'\r')
+            .count();
+        src.advance(delimiters);
+
+        match Self::octet_count(src) {
+            None => Ok(None),
+            Some(Some((digits, count))) => {
+                trace!("Octet counting encoded event detected.");
+                self.octet_decode(src, digits, count)
+            }
+            Some(None) => self.newline_decode(src),
         }
     }
 
     fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if let Some(ret) = self.checked_decode(buf) {
-            ret
-        } else {
-            // Octet counting isn't used so fallback to newline codec.
-            self.other.decode_eof(buf)
+        match self.decode(buf)? {
+            Some(frame) => Ok(Some(frame)),
+            None if buf.is_empty() || self.discard != Discard::Nothing => Ok(None),
+            None => match Self::octet_count(buf) {
+                Some(Some(_)) => {
+                    buf.clear();
+                    Err(LinesCodecError::Io(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Octet counted frame is truncated",
+                    )))
+                }
+                // The last line doesn't need a delimiter.
+                _ => {
+                    let frame = buf.split_to(buf.len());
+                    decode_utf8(&frame).map(Some)
+                }
+            },
         }
     }
 }
@@ -270,6 +378,7 @@ pub fn udp(
     addr: SocketAddr,
     _max_length: usize,
     host_key: String,
+    raw_structured_data: bool,
     shutdown: ShutdownSignal,
     out: Pipeline,
 ) -> super::Source {
@@ -299,7 +408,13 @@ pub fn udp(
                                     .map_err(|error| emit!(SyslogUdpUtf8Error { error }))
                                     .ok()
                                     .and_then(|s| {
-                                        event_from_str(&host_key, Some(received_from), s).map(Ok)
+                                        event_from_str(
+                                            &host_key,
+                                            Some(received_from),
+                                            s,
+                                            raw_structured_data,
+                                        )
+                                        .map(Ok)
                                     })
                             }
                             Err(error) => {
@@ -336,10 +451,12 @@ fn resolve_year((month, _date, _hour, _min, _sec): IncompleteDate) -> i32 {
 * Function to pass to build_unix_source, specific to the Unix mode of the syslog source.
 * Handles the logic of parsing and decoding the syslog message format.
 **/
-// TODO: many more cases to handle:
-// octet framing (i.e. num bytes as ascii string prefix) with and without delimiters
-// null byte delimiter in place of newline
-fn event_from_str(host_key: &str, default_host: Option<Bytes>, line: &str) -> Option<Event> {
+fn event_from_str(
+    host_key: &str,
+    default_host: Option<Bytes>,
+    line: &str,
+    raw_structured_data: bool,
+) -> Option<Event> {
     let line = line.trim();
     let parsed = syslog_loose::parse_message_with_year(line, resolve_year);
     let mut event = Event::from(&parsed.msg[..]);
@@ -366,6 +483,14 @@ fn event_from_str(host_key: &str, default_host: Option<Bytes>, line: &str) -> Op
         .as_mut_log()
         .insert(log_schema().timestamp_key(), timestamp);
 
+    if raw_structured_data && matches!(parsed.protocol, Protocol::RFC5424(_)) {
+        if let Some(structured_data) = find_structured_data(line) {
+            event
+                .as_mut_log()
+                .insert("structured_data", structured_data.to_owned());
+        }
+    }
+
     insert_fields_from_syslog(&mut event, parsed);
 
     emit!(SyslogEventReceived {
@@ -409,19 +534,84 @@ fn insert_fields_from_syslog(event: &mut Event, parsed: Message<&str>) {
         log.insert("procid", value);
     }
 
+    // The SD-IDs and the parameter names can contain dots, so they are
+    // inserted as is rather than as paths.
     for element in parsed.structured_data.into_iter() {
+        let mut params = BTreeMap::new();
         for (name, value) in element.params.into_iter() {
-            let key = format!("{}.{}", element.id, name);
-            log.insert(key, value.to_string());
+            let value = Value::from(value.to_string());
+            match params.entry(name.to_string()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+                // Repeated parameters are collected into an array.
+                Entry::Occupied(mut entry) => match entry.get_mut() {
+                    Value::Array(values) => values.push(value),
+                    previous => *previous = Value::Array(vec![previous.clone(), value]),
+                },
+            }
+        }
+        if !params.is_empty() {
+            log.insert_path(
+                vec![PathComponent::Key(element.id.to_string())],
+                Value::Map(params),
+            );
         }
     }
 }
 
+/// Finds the raw STRUCTURED-DATA of an RFC 5424 message, which follows the
+/// six header fields, none of which contain spaces.
+fn find_structured_data(line: &str) -> Option<&str> {
+    let rest = line.splitn(7, ' ').nth(6)?;
+    if !rest.starts_with('[') {
+        return None;
+    }
+
+    let mut end = None;
+    let mut in_value = false;
+    let mut escaped = false;
+    for (i, byte) in rest.bytes().enumerate() {
+        match byte {
+            _ if escaped => escaped = false,
+            b'\\' if in_value => escaped = true,
+            b'"' => in_value = !in_value,
+            b']' if !in_value => {
+                end = Some(i + 1);
+                // Elements follow each other without any separator.
+                if rest.as_bytes().get(i + 1) != Some(&b'[') {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    end.map(|end| &rest[..end])
+}
+
 #[cfg(test)]
 mod test {
-    use super::{event_from_str, SyslogConfig};
-    use crate::{config::log_schema, event::Event};
+    use super::{event_from_str, find_structured_data, SyslogConfig, SyslogDecoder};
+    use crate::{
+        config::log_schema,
+        event::{Event, Value},
+    };
+    use bytes::BytesMut;
     use chrono::prelude::*;
+    use tokio_util::codec::{Decoder, LinesCodecError};
+
+    fn decode_all(decoder: &mut SyslogDecoder, input: &[u8]) -> Vec<Result<String, String>> {
+        let mut buf = BytesMut::from(input);
+        let mut frames = Vec::new();
+        loop {
+            match decoder.decode_eof(&mut buf) {
+                Ok(Some(frame)) => frames.push(Ok(frame)),
+                Ok(None) => break,
+                Err(error) => frames.push(Err(error.to_string())),
+            }
+        }
+        frames
+    }
 
     #[test]
     fn generate_config() {
@@ -503,7 +693,7 @@ mod test {
         }
 
         assert_eq!(
-            event_from_str(&"host".to_string(), None, &raw).unwrap(),
+            event_from_str(&"host".to_string(), None, &raw, false).unwrap(),
             expected
         );
     }
@@ -533,7 +723,7 @@ mod test {
             expected.insert("procid", 8449);
         }
 
-        let event = event_from_str(&"host".to_string(), None, &raw);
+        let event = event_from_str(&"host".to_string(), None, &raw, false);
         assert_eq!(event, Some(expected.clone()));
 
         let raw = format!(
@@ -541,7 +731,7 @@ mod test {
             r#"[incorrect x=]"#, msg
         );
 
-        let event = event_from_str(&"host".to_string(), None, &raw);
+        let event = event_from_str(&"host".to_string(), None, &raw, false);
         assert_eq!(event, Some(expected));
     }
 
@@ -560,7 +750,7 @@ mod test {
             r#"[empty]"#
         );
 
-        let event = event_from_str(&"host".to_string(), None, &msg).unwrap();
+        let event = event_from_str(&"host".to_string(), None, &msg, false).unwrap();
         assert!(there_is_map_called_empty(event));
 
         let msg = format!(
@@ -568,7 +758,7 @@ mod test {
             r#"[non_empty x="1"][empty]"#
         );
 
-        let event = event_from_str(&"host".to_string(), None, &msg).unwrap();
+        let event = event_from_str(&"host".to_string(), None, &msg, false).unwrap();
         assert!(there_is_map_called_empty(event));
 
         let msg = format!(
@@ -576,7 +766,7 @@ mod test {
             r#"[empty][non_empty x="1"]"#
         );
 
-        let event = event_from_str(&"host".to_string(), None, &msg).unwrap();
+        let event = event_from_str(&"host".to_string(), None, &msg, false).unwrap();
         assert!(there_is_map_called_empty(event));
 
         let msg = format!(
@@ -584,7 +774,7 @@ mod test {
             r#"[empty not_really="testing the test"]"#
         );
 
-        let event = event_from_str(&"host".to_string(), None, &msg).unwrap();
+        let event = event_from_str(&"host".to_string(), None, &msg, false).unwrap();
         assert!(!there_is_map_called_empty(event));
     }
 
@@ -597,8 +787,8 @@ mod test {
         let cleaned = r#"<13>1 2019-02-13T19:48:34+00:00 74794bfb6795 root 8449 - [meta sequenceId="1"] i am foobar"#;
 
         assert_eq!(
-            event_from_str(&"host".to_string(), None, raw).unwrap(),
-            event_from_str(&"host".to_string(), None, cleaned).unwrap()
+            event_from_str(&"host".to_string(), None, raw, false).unwrap(),
+            event_from_str(&"host".to_string(), None, cleaned, false).unwrap()
         );
     }
 
@@ -623,7 +813,7 @@ mod test {
         }
 
         assert_eq!(
-            event_from_str(&"host".to_string(), None, &raw).unwrap(),
+            event_from_str(&"host".to_string(), None, &raw, false).unwrap(),
             expected
         );
     }
@@ -655,7 +845,7 @@ mod test {
         }
 
         assert_eq!(
-            event_from_str(&"host".to_string(), None, &raw).unwrap(),
+            event_from_str(&"host".to_string(), None, &raw, false).unwrap(),
             expected
         );
     }
@@ -690,8 +880,131 @@ mod test {
         }
 
         assert_eq!(
-            event_from_str(&"host".to_string(), None, &raw).unwrap(),
+            event_from_str(&"host".to_string(), None, &raw, false).unwrap(),
             expected
         );
     }
+
+    #[test]
+    fn decodes_mixed_framing() {
+        let mut decoder = SyslogDecoder::new(100);
+        let frames = decode_all(
+            &mut decoder,
+            b"<13>one\n12 <13>two\nline\n<13>three\r\n13 <13>four line\n\n<13>five\0<13>six",
+        );
+        assert_eq!(
+            frames,
+            vec![
+                Ok("<13>one".to_owned()),
+                Ok("<13>two\nline".to_owned()),
+                Ok("<13>three".to_owned()),
+                Ok("<13>four line".to_owned()),
+                Ok("<13>five".to_owned()),
+                Ok("<13>six".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_partial_octet_frames() {
+        let mut decoder = SyslogDecoder::new(100);
+        let mut buf = BytesMut::from(&b"1"[..]);
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"0 <13>h");
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"ello!9 <13>world");
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap(),
+            Some("<13>hello!".to_owned())
+        );
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap(),
+            Some("<13>world".to_owned())
+        );
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+    }
+
+    #[test]
+    fn discards_oversized_frames() {
+        let mut decoder = SyslogDecoder::new(10);
+        let mut buf = BytesMut::from(&b"20 <13>0123456"[..]);
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(LinesCodecError::MaxLineLengthExceeded)
+        ));
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"789abcdef<13>short\n<13>much too long");
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap(),
+            Some("<13>short".to_owned())
+        );
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(LinesCodecError::MaxLineLengthExceeded)
+        ));
+        buf.extend_from_slice(b" still\n8 <13>next");
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap(),
+            Some("<13>next".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_octet_frame() {
+        let mut decoder = SyslogDecoder::new(100);
+        let frames = decode_all(&mut decoder, b"<13>one\n20 <13>two");
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], Ok("<13>one".to_owned()));
+        assert!(frames[1].is_err());
+    }
+
+    #[test]
+    fn nests_structured_data() {
+        let raw = r#"<13>1 2019-02-13T19:48:34+00:00 74794bfb6795 root 8449 - [exampleSDID@32473 iut="3" event.source="app"][tags tag="a" tag="b"] qwerty"#;
+        let event = event_from_str(&"host".to_string(), None, raw, false).unwrap();
+        let log = event.as_log();
+
+        let mut element = std::collections::BTreeMap::new();
+        element.insert("iut".to_owned(), Value::from("3"));
+        element.insert("event.source".to_owned(), Value::from("app"));
+        assert_eq!(
+            log.get_flat("exampleSDID@32473"),
+            Some(&Value::Map(element))
+        );
+        assert_eq!(log["tags.tag"], Value::Array(vec!["a".into(), "b".into()]));
+        assert!(log.get("structured_data").is_none());
+    }
+
+    #[test]
+    fn keeps_raw_structured_data() {
+        let sd = r#"[meta sequenceId="1"][origin ip="192.168.0.1"]"#;
+        let raw = format!(
+            r#"<13>1 2019-02-13T19:48:34+00:00 74794bfb6795 root 8449 - {} i am foobar"#,
+            sd
+        );
+        let event = event_from_str(&"host".to_string(), None, &raw, true).unwrap();
+        assert_eq!(event.as_log()["structured_data"], sd.into());
+        assert_eq!(event.as_log()["origin.ip"], "192.168.0.1".into());
+
+        let raw = "<13>1 2019-02-13T19:48:34+00:00 74794bfb6795 root 8449 - - i am foobar";
+        let event = event_from_str(&"host".to_string(), None, raw, true).unwrap();
+        assert!(event.as_log().get("structured_data").is_none());
+    }
+
+    #[test]
+    fn finds_structured_data() {
+        assert_eq!(
+            find_structured_data(r#"<13>1 - host app 1 ID [a x="]"][b] msg [c]"#),
+            Some(r#"[a x="]"][b]"#)
+        );
+        assert_eq!(
+            find_structured_data(r#"<13>1 - host app 1 ID [a x="\"]"] msg"#),
+            Some(r#"[a x="\"]"]"#)
+        );
+        assert_eq!(
+            find_structured_data("<13>1 - host app 1 ID - msg [c]"),
+            None
+        );
+        assert_eq!(find_structured_data("<13>1 - host app 1 ID"), None);
+    }
 }
@@ -0,0 +1,423 @@
+//! Decoding of [GELF](https://docs.graylog.org/en/latest/pages/gelf.html) payloads.
+//!
+//! This handles the three layers a GELF message can be wrapped in: UDP
+//! chunking, gzip/zlib compression and the JSON document itself.
+
+use crate::{
+    config::log_schema,
+    event::{Event, LogEvent, Value},
+};
+use bytes::Bytes;
+use chrono::{TimeZone, Utc};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde_json::Value as JsonValue;
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::HashMap,
+    io::Read,
+    time::{Duration, Instant},
+};
+
+/// Magic bytes prefixing every chunk of a chunked GELF message.
+const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+/// Magic bytes, message id, sequence number and sequence count.
+const CHUNK_HEADER_LENGTH: usize = 12;
+/// The GELF specification limits messages to 128 chunks.
+const MAX_CHUNKS: u8 = 128;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The default limit on the size of a message, once decompressed.
+pub const DEFAULT_MAX_LENGTH: usize = 1024 * 1024;
+/// Chunks of new messages are dropped while this many messages are incomplete.
+const MAX_PENDING_MESSAGES: usize = 1000;
+
+#[derive(Debug, Snafu)]
+pub enum GelfError {
+    #[snafu(display("Chunk is too short to contain a header"))]
+    ChunkTooShort,
+    #[snafu(display("Invalid chunk sequence number {} of {}", number, count))]
+    InvalidChunkSequence { number: u8, count: u8 },
+    #[snafu(display("Too many incomplete chunked messages"))]
+    TooManyPendingMessages,
+    #[snafu(display("Unable to decompress payload: {}", source))]
+    Decompress { source: std::io::Error },
+    #[snafu(display("Message is larger than {} bytes", max_length))]
+    TooLarge { max_length: usize },
+    #[snafu(display("Invalid JSON: {}", source))]
+    InvalidJson { source: serde_json::Error },
+    #[snafu(display("Expected a JSON object, got {}", kind))]
+    NotAnObject { kind: &'static str },
+    #[snafu(display("Missing required field {:?}", field))]
+    MissingField { field: &'static str },
+}
+
+/// Reassembles chunked GELF messages received over UDP.
+///
+/// Incomplete messages are discarded once `timeout` has passed since their
+/// first chunk arrived, and at most `MAX_PENDING_MESSAGES` are kept.
+#[derive(Debug)]
+pub struct ChunkAssembler {
+    timeout: Duration,
+    pending: HashMap<u64, PendingMessage>,
+}
+
+#[derive(Debug)]
+struct PendingMessage {
+    first_seen: Instant,
+    chunks: Vec<Option<Bytes>>,
+    received: usize,
+}
+
+impl ChunkAssembler {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Feeds a datagram into the assembler. Returns the complete payload
+    /// once all chunks of a message have been seen. Datagrams without the
+    /// chunk magic are returned as is.
+    pub fn push(&mut self, datagram: Bytes) -> Result<Option<Bytes>, GelfError> {
+        if !datagram.starts_with(&CHUNK_MAGIC) {
+            return Ok(Some(datagram));
+        }
+        if datagram.len() < CHUNK_HEADER_LENGTH {
+            return Err(GelfError::ChunkTooShort);
+        }
+
+        let mut id = [0u8; 8];
+        id.copy_from_slice(&datagram[2..10]);
+        let id = u64::from_be_bytes(id);
+        let number = datagram[10];
+        let count = datagram[11];
+        if count == 0 || count > MAX_CHUNKS || number >= count {
+            return Err(GelfError::InvalidChunkSequence { number, count });
+        }
+
+        if self.pending.len() >= MAX_PENDING_MESSAGES && !self.pending.contains_key(&id) {
+            return Err(GelfError::TooManyPendingMessages);
+        }
+
+        let message = self.pending.entry(id).or_insert_with(|| PendingMessage {
+            first_seen: Instant::now(),
+            chunks: vec![None; count as usize],
+            received: 0,
+        });
+        if message.chunks.len() != count as usize {
+            self.pending.remove(&id);
+            return Err(GelfError::InvalidChunkSequence { number, count });
+        }
+
+        let slot = &mut message.chunks[number as usize];
+        if slot.is_none() {
+            *slot = Some(datagram.slice(CHUNK_HEADER_LENGTH..));
+            message.received += 1;
+        }
+
+        if message.received < count as usize {
+            return Ok(None);
+        }
+
+        let message = self.pending.remove(&id).expect("message was just inserted");
+        let mut payload = Vec::new();
+        for chunk in message.chunks.into_iter().flatten() {
+            payload.extend_from_slice(&chunk);
+        }
+        Ok(Some(payload.into()))
+    }
+
+    /// Drops messages whose chunks didn't all arrive in time, returning how
+    /// many were dropped.
+    pub fn expire(&mut self) -> usize {
+        let timeout = self.timeout;
+        let before = self.pending.len();
+        self.pending
+            .retain(|_, message| message.first_seen.elapsed() < timeout);
+        before - self.pending.len()
+    }
+}
+
+/// Inflates gzip or zlib compressed payloads, detected by their magic bytes.
+/// Uncompressed payloads are returned unchanged. Payloads larger than
+/// `max_length` once decompressed are rejected, without inflating more than
+/// that.
+pub fn decompress(payload: Bytes, max_length: usize) -> Result<Bytes, GelfError> {
+    let limit = max_length as u64 + 1;
+    let mut decompressed = Vec::new();
+    if payload.starts_with(&GZIP_MAGIC) {
+        GzDecoder::new(&payload[..])
+            .take(limit)
+            .read_to_end(&mut decompressed)
+            .context(Decompress)?;
+    } else if is_zlib(&payload) {
+        ZlibDecoder::new(&payload[..])
+            .take(limit)
+            .read_to_end(&mut decompressed)
+            .context(Decompress)?;
+    } else {
+        decompressed = payload.into();
+    }
+
+    if decompressed.len() > max_length {
+        return Err(GelfError::TooLarge { max_length });
+    }
+    Ok(decompressed.into())
+}
+
+/// A zlib header starts with the `deflate` method and its first two bytes,
+/// read as a big endian number, are a multiple of 31.
+fn is_zlib(payload: &[u8]) -> bool {
+    payload.len() >= 2
+        && payload[0] & 0x0f == 8
+        && ((u16::from(payload[0]) << 8) | u16::from(payload[1])) % 31 == 0
+}
+
+/// Decompresses and parses a complete GELF message into a log event.
+///
+/// `short_message`, `host` and `timestamp` are mapped to the configured
+/// `log_schema` keys. Additional fields have their leading underscore
+/// removed, unless that would overwrite one of the standard fields.
+pub fn parse(payload: Bytes, max_length: usize) -> Result<Event, GelfError> {
+    let payload = decompress(payload, max_length)?;
+    let json: JsonValue = serde_json::from_slice(&payload).context(InvalidJson)?;
+    let object = match json {
+        JsonValue::Object(object) => object,
+        other => {
+            return Err(GelfError::NotAnObject {
+                kind: json_kind(&other),
+            })
+        }
+    };
+
+    let mut event = Event::new_empty_log();
+    let log = event.as_mut_log();
+    let mut additional = Vec::new();
+    let mut has_message = false;
+
+    for (key, value) in object {
+        match key.as_str() {
+            "short_message" => {
+                has_message = true;
+                log.insert(log_schema().message_key(), Value::from(value));
+            }
+            "host" => {
+                log.insert(log_schema().host_key(), Value::from(value));
+            }
+            "timestamp" => {
+                let timestamp = value.as_f64().and_then(|secs| {
+                    let nanos = (secs.fract() * 1e9).round() as u32;
+                    Utc.timestamp_opt(secs.trunc() as i64, nanos).single()
+                });
+                match timestamp {
+                    Some(timestamp) => {
+                        log.insert(log_schema().timestamp_key(), timestamp);
+                    }
+                    None => log.insert_flat("timestamp", Value::from(value)),
+                }
+            }
+            // `_id` is reserved by Graylog and must not be sent.
+            "_id" => {}
+            _ if key.starts_with('_') && key.len() > 1 => additional.push((key, value)),
+            _ => log.insert_flat(key, Value::from(value)),
+        }
+    }
+
+    if !has_message {
+        return Err(GelfError::MissingField {
+            field: "short_message",
+        });
+    }
+
+    insert_additional_fields(log, additional);
+    if !log.contains(log_schema().timestamp_key()) {
+        log.insert(log_schema().timestamp_key(), Utc::now());
+    }
+
+    Ok(event)
+}
+
+fn insert_additional_fields(log: &mut LogEvent, fields: Vec<(String, JsonValue)>) {
+    for (key, value) in fields {
+        let stripped = &key[1..];
+        if log.get_flat(stripped).is_some() {
+            log.insert_flat(key, Value::from(value));
+        } else {
+            log.insert_flat(stripped.to_string(), Value::from(value));
+        }
+    }
+}
+
+fn json_kind(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Object(_) => "object",
+        JsonValue::Array(_) => "array",
+        JsonValue::String(_) => "string",
+        JsonValue::Number(_) => "number",
+        JsonValue::Bool(_) => "bool",
+        JsonValue::Null => "null",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{
+        write::{GzEncoder, ZlibEncoder},
+        Compression,
+    };
+    use std::io::Write;
+
+    const MESSAGE: &str = r#"{
+        "version": "1.1",
+        "host": "example.org",
+        "short_message": "A short message",
+        "full_message": "Backtrace here\n\nmore stuff",
+        "timestamp": 1385053862.3072,
+        "level": 1,
+        "_user_id": 9001,
+        "_level": "ignored",
+        "_id": "dropped"
+    }"#;
+
+    fn chunk(id: u64, number: u8, count: u8, data: &[u8]) -> Bytes {
+        let mut chunk = CHUNK_MAGIC.to_vec();
+        chunk.extend_from_slice(&id.to_be_bytes());
+        chunk.push(number);
+        chunk.push(count);
+        chunk.extend_from_slice(data);
+        chunk.into()
+    }
+
+    #[test]
+    fn gelf_parses_standard_fields() {
+        let event = parse(MESSAGE.into(), DEFAULT_MAX_LENGTH).unwrap();
+        let log = event.as_log();
+
+        assert_eq!(log[log_schema().message_key()], "A short message".into());
+        assert_eq!(log[log_schema().host_key()], "example.org".into());
+        assert_eq!(
+            log[log_schema().timestamp_key()],
+            Utc.timestamp(1385053862, 307_200_000).into()
+        );
+        assert_eq!(log["full_message"], "Backtrace here\n\nmore stuff".into());
+        assert_eq!(log["level"], 1.into());
+        assert_eq!(log["version"], "1.1".into());
+    }
+
+    #[test]
+    fn gelf_maps_additional_fields() {
+        let event = parse(MESSAGE.into(), DEFAULT_MAX_LENGTH).unwrap();
+        let log = event.as_log();
+
+        assert_eq!(log["user_id"], 9001.into());
+        assert_eq!(log.get_flat("_level"), Some(&"ignored".into()));
+        assert!(log.get("_id").is_none());
+        assert!(log.get("id").is_none());
+    }
+
+    #[test]
+    fn gelf_requires_short_message() {
+        let error = parse(
+            r#"{"version":"1.1","host":"example.org"}"#.into(),
+            DEFAULT_MAX_LENGTH,
+        )
+        .unwrap_err();
+        assert!(matches!(error, GelfError::MissingField { .. }));
+
+        let error = parse("[1, 2]".into(), DEFAULT_MAX_LENGTH).unwrap_err();
+        assert!(matches!(error, GelfError::NotAnObject { kind: "array" }));
+    }
+
+    #[test]
+    fn gelf_decompresses_payloads() {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(MESSAGE.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        assert_eq!(
+            decompress(gzip.into(), DEFAULT_MAX_LENGTH).unwrap(),
+            MESSAGE
+        );
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(MESSAGE.as_bytes()).unwrap();
+        let zlib = zlib.finish().unwrap();
+        assert_eq!(
+            decompress(zlib.into(), DEFAULT_MAX_LENGTH).unwrap(),
+            MESSAGE
+        );
+
+        assert_eq!(
+            decompress(MESSAGE.into(), DEFAULT_MAX_LENGTH).unwrap(),
+            MESSAGE
+        );
+    }
+
+    #[test]
+    fn gelf_limits_decompressed_size() {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::best());
+        gzip.write_all(&vec![b' '; 10 * DEFAULT_MAX_LENGTH])
+            .unwrap();
+        let gzip = gzip.finish().unwrap();
+        let error = decompress(gzip.into(), DEFAULT_MAX_LENGTH).unwrap_err();
+        assert!(matches!(error, GelfError::TooLarge { .. }));
+
+        let error = decompress(MESSAGE.into(), 10).unwrap_err();
+        assert!(matches!(error, GelfError::TooLarge { max_length: 10 }));
+    }
+
+    #[test]
+    fn gelf_reassembles_chunks_in_any_order() {
+        let mut assembler = ChunkAssembler::new(Duration::from_secs(5));
+        let (first, second) = MESSAGE.as_bytes().split_at(40);
+
+        assert_eq!(assembler.push(chunk(42, 1, 2, second)).unwrap(), None);
+        assert_eq!(assembler.push(chunk(7, 0, 2, first)).unwrap(), None);
+        assert_eq!(
+            assembler.push(chunk(42, 0, 2, first)).unwrap(),
+            Some(MESSAGE.into())
+        );
+        assert_eq!(assembler.pending.len(), 1);
+    }
+
+    #[test]
+    fn gelf_passes_unchunked_datagrams_through() {
+        let mut assembler = ChunkAssembler::new(Duration::from_secs(5));
+        assert_eq!(
+            assembler.push(MESSAGE.into()).unwrap(),
+            Some(MESSAGE.into())
+        );
+    }
+
+    #[test]
+    fn gelf_rejects_invalid_chunks() {
+        let mut assembler = ChunkAssembler::new(Duration::from_secs(5));
+        assert!(assembler.push(Bytes::from(&[0x1e, 0x0f, 1][..])).is_err());
+        assert!(assembler.push(chunk(1, 2, 2, b"data")).is_err());
+        assert!(assembler.push(chunk(1, 0, 129, b"data")).is_err());
+    }
+
+    #[test]
+    fn gelf_limits_incomplete_messages() {
+        let mut assembler = ChunkAssembler::new(Duration::from_secs(5));
+        for id in 0..MAX_PENDING_MESSAGES as u64 {
+            assert_eq!(assembler.push(chunk(id, 0, 2, b"data")).unwrap(), None);
+        }
+        let error = assembler
+            .push(chunk(MAX_PENDING_MESSAGES as u64, 0, 2, b"data"))
+            .unwrap_err();
+        assert!(matches!(error, GelfError::TooManyPendingMessages));
+        // Messages already pending can still complete.
+        assert!(assembler.push(chunk(0, 1, 2, b"data")).unwrap().is_some());
+    }
+
+    #[test]
+    fn gelf_expires_incomplete_messages() {
+        let mut assembler = ChunkAssembler::new(Duration::from_secs(0));
+        assert_eq!(assembler.push(chunk(1, 0, 2, b"data")).unwrap(), None);
+        assert_eq!(assembler.expire(), 1);
+        assert_eq!(assembler.expire(), 0);
+    }
+}
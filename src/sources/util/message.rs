@@ -0,0 +1,88 @@
+//! Event construction shared by the sources consuming from message queues, so
+//! that a message looks the same regardless of the queue it was read from,
+//! and the backoff between their failed receives.
+
+use crate::{
+    config::log_schema,
+    event::{Event, Value},
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use std::{collections::BTreeMap, time::Duration};
+use tokio_retry::strategy::ExponentialBackoff;
+
+/// The delays between failed attempts to receive messages, starting over
+/// once a receive succeeds.
+pub fn fresh_backoff() -> ExponentialBackoff {
+    ExponentialBackoff::from_millis(2)
+        .factor(250)
+        .max_delay(Duration::from_secs(60))
+}
+
+/// Builds a log event with the payload as the message. The timestamp falls
+/// back to the current time when the broker doesn't provide one.
+pub fn decode_message(
+    source_type: &'static str,
+    payload: &[u8],
+    timestamp: Option<DateTime<Utc>>,
+) -> Event {
+    let mut event = Event::new_empty_log();
+    let log = event.as_mut_log();
+
+    log.insert(
+        log_schema().message_key(),
+        Value::from(Bytes::copy_from_slice(payload)),
+    );
+    log.insert(
+        log_schema().timestamp_key(),
+        timestamp.unwrap_or_else(Utc::now),
+    );
+    log.insert(log_schema().source_type_key(), Bytes::from(source_type));
+
+    event
+}
+
+/// Inserts message headers or properties as a map under `key`.
+pub fn insert_headers<K, V>(event: &mut Event, key: &str, headers: impl IntoIterator<Item = (K, V)>)
+where
+    K: Into<String>,
+    V: AsRef<[u8]>,
+{
+    let headers = headers
+        .into_iter()
+        .map(|(name, value)| {
+            (
+                name.into(),
+                Value::from(Bytes::copy_from_slice(value.as_ref())),
+            )
+        })
+        .collect::<BTreeMap<String, Value>>();
+    event.as_mut_log().insert(key, headers);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn decode_message_fields() {
+        let timestamp = Utc.timestamp(1_600_000_000, 0);
+        let event = decode_message("nats", b"hello", Some(timestamp));
+        let log = event.as_log();
+
+        assert_eq!(log[log_schema().message_key()], "hello".into());
+        assert_eq!(log[log_schema().timestamp_key()], timestamp.into());
+        assert_eq!(log[log_schema().source_type_key()], "nats".into());
+    }
+
+    #[test]
+    fn insert_headers_as_map() {
+        let mut event = decode_message("pulsar", b"hello", None);
+        insert_headers(&mut event, "properties", vec![("a", "1"), ("b", "2")]);
+        let log = event.as_log();
+
+        assert_eq!(log["properties.a"], "1".into());
+        assert_eq!(log["properties.b"], "2".into());
+    }
+}
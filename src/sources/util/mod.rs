@@ -1,5 +1,17 @@
+#[cfg(feature = "sources-utils-gelf")]
+pub mod gelf;
 #[cfg(feature = "sources-utils-http")]
 mod http;
+#[cfg(any(
+    feature = "sources-amqp",
+    feature = "sources-aws_sqs",
+    feature = "sources-gcp_pubsub",
+    feature = "sources-kafka",
+    feature = "sources-nats",
+    feature = "sources-pulsar",
+    feature = "sources-redis"
+))]
+pub mod message;
 pub mod multiline_config;
 #[cfg(all(feature = "tls", feature = "listenfd"))]
 mod tcp;
@@ -9,7 +21,9 @@ mod unix;
 #[cfg(feature = "sources-utils-http")]
 pub use self::http::{add_query_parameters, ErrorMessage, HttpSource, HttpSourceAuthConfig};
 pub use multiline_config::MultilineConfig;
+#[cfg(feature = "sources-vector")]
+pub(crate) use tcp::make_listener;
 #[cfg(all(feature = "tls", feature = "listenfd"))]
 pub use tcp::{SocketListenAddr, TcpSource};
 #[cfg(all(unix, feature = "sources-utils-unix",))]
-pub use unix::build_unix_source;
+pub use unix::{build_unix_connection_source, build_unix_source};
@@ -22,7 +22,7 @@ use tokio::{
 use tokio_util::codec::{Decoder, FramedRead};
 use tracing_futures::Instrument;
 
-async fn make_listener(
+pub(crate) async fn make_listener(
     addr: SocketListenAddr,
     mut listenfd: ListenFd,
     tls: &MaybeTlsSettings,
@@ -32,6 +32,29 @@ pub fn build_unix_source<D, E>(
 where
     D: Decoder<Item = String, Error = E> + Clone + Send + 'static,
     E: From<std::io::Error> + std::fmt::Debug + std::fmt::Display,
+{
+    build_unix_connection_source(listen_path, decoder, host_key, shutdown, out, move |_| {
+        build_event.clone()
+    })
+}
+
+/**
+* Same as `build_unix_source`, but the events of each connection are built by the function
+* returned by `connection`, which can inspect the accepted socket (to get the peer credentials
+* for instance).
+**/
+pub fn build_unix_connection_source<D, E, B>(
+    listen_path: PathBuf,
+    decoder: D,
+    host_key: String,
+    shutdown: ShutdownSignal,
+    out: Pipeline,
+    connection: impl Fn(&UnixStream) -> B + Send + Sync + 'static,
+) -> Source
+where
+    D: Decoder<Item = String, Error = E> + Clone + Send + 'static,
+    E: From<std::io::Error> + std::fmt::Debug + std::fmt::Display,
+    B: Fn(&str, Option<Bytes>, &str) -> Option<Event> + Send + 'static,
 {
     let out = out.sink_map_err(|error| error!(message = "Error sending line.", %error));
 
@@ -66,7 +89,7 @@ where
                 None
             };
 
-            let build_event = build_event.clone();
+            let build_event = connection(&socket);
             let received_from: Option<Bytes> =
                 path.map(|p| p.to_string_lossy().into_owned().into());
 
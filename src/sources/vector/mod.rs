@@ -1,20 +1,21 @@
-use super::util::{SocketListenAddr, TcpSource};
+use super::util::SocketListenAddr;
 use crate::{
     config::{DataType, GenerateConfig, GlobalOptions, Resource, SourceConfig, SourceDescription},
-    event::proto,
-    internal_events::{VectorEventReceived, VectorProtoDecodeError},
+    proto::VectorVersion,
     shutdown::ShutdownSignal,
-    tls::{MaybeTlsSettings, TlsConfig},
-    Event, Pipeline,
+    tls::TlsConfig,
+    Pipeline,
 };
-use bytes::{Bytes, BytesMut};
-use prost::Message;
 use serde::{Deserialize, Serialize};
-use tokio_util::codec::LengthDelimitedCodec;
+
+mod v1;
+mod v2;
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct VectorConfig {
+    #[serde(default)]
+    pub version: VectorVersion,
     pub address: SocketListenAddr,
     #[serde(default = "default_shutdown_timeout_secs")]
     pub shutdown_timeout_secs: u64,
@@ -27,8 +28,9 @@ fn default_shutdown_timeout_secs() -> u64 {
 
 #[cfg(test)]
 impl VectorConfig {
-    pub fn new(address: SocketListenAddr, tls: Option<TlsConfig>) -> Self {
+    pub fn new(version: VectorVersion, address: SocketListenAddr, tls: Option<TlsConfig>) -> Self {
         Self {
+            version,
             address,
             shutdown_timeout_secs: default_shutdown_timeout_secs(),
             tls,
@@ -43,6 +45,7 @@ inventory::submit! {
 impl GenerateConfig for VectorConfig {
     fn generate_config() -> toml::Value {
         toml::Value::try_from(Self {
+            version: VectorVersion::default(),
             address: SocketListenAddr::SocketAddr("0.0.0.0:9000".parse().unwrap()),
             shutdown_timeout_secs: default_shutdown_timeout_secs(),
             tls: None,
@@ -61,9 +64,10 @@ impl SourceConfig for VectorConfig {
         shutdown: ShutdownSignal,
         out: Pipeline,
     ) -> crate::Result<super::Source> {
-        let vector = VectorSource;
-        let tls = MaybeTlsSettings::from_config(&self.tls, true)?;
-        vector.run(self.address, self.shutdown_timeout_secs, tls, shutdown, out)
+        match self.version {
+            VectorVersion::V1 => v1::build(self, shutdown, out),
+            VectorVersion::V2 => v2::build(self, shutdown, out),
+        }
     }
 
     fn output_type(&self) -> DataType {
@@ -79,32 +83,6 @@ impl SourceConfig for VectorConfig {
     }
 }
 
-#[derive(Debug, Clone)]
-struct VectorSource;
-
-impl TcpSource for VectorSource {
-    type Error = std::io::Error;
-    type Decoder = LengthDelimitedCodec;
-
-    fn decoder(&self) -> Self::Decoder {
-        LengthDelimitedCodec::new()
-    }
-
-    fn build_event(&self, frame: BytesMut, _host: Bytes) -> Option<Event> {
-        let byte_size = frame.len();
-        match proto::EventWrapper::decode(frame).map(Event::from) {
-            Ok(event) => {
-                emit!(VectorEventReceived { byte_size });
-                Some(event)
-            }
-            Err(error) => {
-                emit!(VectorProtoDecodeError { error });
-                None
-            }
-        }
-    }
-}
-
 #[cfg(feature = "sinks-vector")]
 #[cfg(test)]
 mod test {
@@ -116,7 +94,8 @@ mod test {
             metric::{MetricKind, MetricValue},
             Metric,
         },
-        sinks::vector::VectorSinkConfig,
+        proto::VectorVersion,
+        sinks::{util::Compression, vector::VectorSinkConfig},
         test_util::{collect_ready, next_addr, wait_for_tcp},
         tls::{TlsConfig, TlsOptions},
         Event, Pipeline,
@@ -176,16 +155,45 @@ mod test {
         assert_eq!(events, output);
     }
 
+    fn sink_config(version: VectorVersion, addr: SocketAddr, tls: bool) -> VectorSinkConfig {
+        VectorSinkConfig {
+            version,
+            address: format!("localhost:{}", addr.port()),
+            tls: if tls { Some(client_tls()) } else { None },
+            compression: None,
+            batch: None,
+            request: None,
+        }
+    }
+
+    fn client_tls() -> TlsConfig {
+        TlsConfig {
+            enabled: Some(true),
+            options: TlsOptions {
+                verify_certificate: Some(false),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn server_tls() -> TlsConfig {
+        TlsConfig {
+            enabled: Some(true),
+            options: TlsOptions {
+                crt_file: Some("tests/data/localhost.crt".into()),
+                key_file: Some("tests/data/localhost.key".into()),
+                ..Default::default()
+            },
+        }
+    }
+
     #[tokio::test]
     async fn it_works_with_vector_sink() {
         let addr = next_addr();
         stream_test(
             addr,
-            VectorConfig::new(addr.into(), None),
-            VectorSinkConfig {
-                address: format!("localhost:{}", addr.port()),
-                tls: None,
-            },
+            VectorConfig::new(VectorVersion::V1, addr.into(), None),
+            sink_config(VectorVersion::V1, addr, false),
         )
         .await;
     }
@@ -195,28 +203,69 @@ mod test {
         let addr = next_addr();
         stream_test(
             addr,
-            VectorConfig::new(
-                addr.into(),
-                Some(TlsConfig {
-                    enabled: Some(true),
-                    options: TlsOptions {
-                        crt_file: Some("tests/data/localhost.crt".into()),
-                        key_file: Some("tests/data/localhost.key".into()),
-                        ..Default::default()
-                    },
-                }),
-            ),
+            VectorConfig::new(VectorVersion::V1, addr.into(), Some(server_tls())),
+            sink_config(VectorVersion::V1, addr, true),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn it_works_with_vector_sink_v2() {
+        let addr = next_addr();
+        stream_test(
+            addr,
+            VectorConfig::new(VectorVersion::V2, addr.into(), None),
+            sink_config(VectorVersion::V2, addr, false),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn it_works_with_vector_sink_v2_tls() {
+        let addr = next_addr();
+        stream_test(
+            addr,
+            VectorConfig::new(VectorVersion::V2, addr.into(), Some(server_tls())),
+            sink_config(VectorVersion::V2, addr, true),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn it_works_with_vector_sink_v2_gzip() {
+        let addr = next_addr();
+        stream_test(
+            addr,
+            VectorConfig::new(VectorVersion::V2, addr.into(), None),
             VectorSinkConfig {
-                address: format!("localhost:{}", addr.port()),
-                tls: Some(TlsConfig {
-                    enabled: Some(true),
-                    options: TlsOptions {
-                        verify_certificate: Some(false),
-                        ..Default::default()
-                    },
-                }),
+                compression: Some(Compression::gzip_default()),
+                ..sink_config(VectorVersion::V2, addr, false)
             },
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn v2_healthcheck() {
+        let addr = next_addr();
+        let (tx, _rx) = Pipeline::new_test();
+        let server = VectorConfig::new(VectorVersion::V2, addr.into(), None)
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                tx,
+            )
+            .await
+            .unwrap()
+            .compat();
+        tokio::spawn(server);
+        wait_for_tcp(addr).await;
+
+        let (_, healthcheck) = sink_config(VectorVersion::V2, addr, false)
+            .build(SinkContext::new_test())
+            .await
+            .unwrap();
+        healthcheck.await.unwrap();
+    }
 }
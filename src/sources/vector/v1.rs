@@ -0,0 +1,54 @@
+use super::VectorConfig;
+use crate::{
+    event::proto,
+    internal_events::{VectorEventReceived, VectorProtoDecodeError},
+    shutdown::ShutdownSignal,
+    sources::{util::TcpSource, Source},
+    tls::MaybeTlsSettings,
+    Event, Pipeline,
+};
+use bytes::{Bytes, BytesMut};
+use prost::Message;
+use tokio_util::codec::LengthDelimitedCodec;
+
+pub(super) fn build(
+    config: &VectorConfig,
+    shutdown: ShutdownSignal,
+    out: Pipeline,
+) -> crate::Result<Source> {
+    let vector = VectorSource;
+    let tls = MaybeTlsSettings::from_config(&config.tls, true)?;
+    vector.run(
+        config.address,
+        config.shutdown_timeout_secs,
+        tls,
+        shutdown,
+        out,
+    )
+}
+
+#[derive(Debug, Clone)]
+struct VectorSource;
+
+impl TcpSource for VectorSource {
+    type Error = std::io::Error;
+    type Decoder = LengthDelimitedCodec;
+
+    fn decoder(&self) -> Self::Decoder {
+        LengthDelimitedCodec::new()
+    }
+
+    fn build_event(&self, frame: BytesMut, _host: Bytes) -> Option<Event> {
+        let byte_size = frame.len();
+        match proto::EventWrapper::decode(frame).map(Event::from) {
+            Ok(event) => {
+                emit!(VectorEventReceived { byte_size });
+                Some(event)
+            }
+            Err(error) => {
+                emit!(VectorProtoDecodeError { error });
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use super::VectorConfig;
+use crate::{
+    internal_events::{VectorDecompressionError, VectorEventReceived, VectorProtoDecodeError},
+    proto::vector::{
+        vector_server::{Vector, VectorServer},
+        Compression, EventBatch, HealthCheckRequest, HealthCheckResponse, PushEventsRequest,
+        PushEventsResponse, ServingStatus,
+    },
+    shutdown::ShutdownSignal,
+    sources::{
+        util::{make_listener, SocketListenAddr},
+        Source,
+    },
+    tls::MaybeTlsSettings,
+    Event, Pipeline,
+};
+use flate2::read::GzDecoder;
+use futures::{
+    compat::Future01CompatExt,
+    future::{self, Either},
+    FutureExt, TryFutureExt,
+};
+use futures01::Sink;
+use hyper::{server::accept, service::make_service_fn, Server};
+use listenfd::ListenFd;
+use prost::Message;
+use std::{
+    convert::Infallible,
+    io::Read,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::time::{delay_for, Duration};
+use tonic::{Request, Response, Status};
+
+/// The maximum size of the events of a request, once decompressed.
+const MAX_DECOMPRESSED_BYTES: u64 = 100 * 1024 * 1024;
+
+pub(super) fn build(
+    config: &VectorConfig,
+    shutdown: ShutdownSignal,
+    out: Pipeline,
+) -> crate::Result<Source> {
+    let tls = MaybeTlsSettings::from_config(&config.tls, true)?;
+    let address = config.address;
+    let shutdown_timeout_secs = config.shutdown_timeout_secs;
+    let listenfd = ListenFd::from_env();
+    let serving = Arc::new(AtomicBool::new(true));
+    let service = VectorServer::new(Service {
+        pipeline: out,
+        serving: Arc::clone(&serving),
+    });
+
+    let fut = async move {
+        let listener = match make_listener(address, listenfd, &tls).await {
+            None => return Err(()),
+            Some(listener) => listener,
+        };
+
+        info!(
+            message = "Listening.",
+            addr = %listener
+                .local_addr()
+                .map(SocketListenAddr::SocketAddr)
+                .unwrap_or(address)
+        );
+
+        let make_service = make_service_fn(move |_| {
+            let service = service.clone();
+            async move { Ok::<_, Infallible>(service) }
+        });
+
+        let server = Server::builder(accept::from_stream(listener.accept_stream()))
+            .http2_only(true)
+            .serve(make_service)
+            .with_graceful_shutdown(shutdown.clone().map(|_| ()));
+        // The graceful shutdown waits for the connections to be closed by
+        // the clients, which is only given `shutdown_timeout_secs`.
+        let tripwire = shutdown.clone();
+        let tripwire = async move {
+            let _ = tripwire.await;
+            serving.store(false, Ordering::Relaxed);
+            delay_for(Duration::from_secs(shutdown_timeout_secs)).await;
+        };
+        let result = match future::select(Box::pin(server), Box::pin(tripwire)).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => {
+                info!(
+                    message = "Resetting connections (still open after seconds).",
+                    seconds = ?shutdown_timeout_secs
+                );
+                Ok(())
+            }
+        };
+        // We need to drop the last copy of ShutdownSignalToken only after server has shut down.
+        drop(shutdown);
+
+        result.map_err(|error| error!(message = "Server error.", %error))
+    };
+
+    Ok(Box::new(fut.boxed().compat()))
+}
+
+#[derive(Debug, Clone)]
+struct Service {
+    pipeline: Pipeline,
+    /// Cleared once the source starts shutting down, so that the health
+    /// checks steer the clients away from it.
+    serving: Arc<AtomicBool>,
+}
+
+#[tonic::async_trait]
+impl Vector for Service {
+    async fn push_events(
+        &self,
+        request: Request<PushEventsRequest>,
+    ) -> Result<Response<PushEventsResponse>, Status> {
+        let events = decode_request(request.into_inner())?;
+        let accepted = events.len() as u64;
+
+        // The response is only sent once the events are in the pipeline, so
+        // the sink can acknowledge them.
+        self.pipeline
+            .clone()
+            .send_all(futures01::stream::iter_ok(events))
+            .compat()
+            .await
+            .map_err(|error| {
+                error!(message = "Failed to forward events, downstream is closed.", %error);
+                Status::unavailable("Downstream is closed.")
+            })?;
+
+        Ok(Response::new(PushEventsResponse { accepted }))
+    }
+
+    async fn health_check(
+        &self,
+        _request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let status = if self.serving.load(Ordering::Relaxed) {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+        Ok(Response::new(HealthCheckResponse {
+            status: status as i32,
+        }))
+    }
+}
+
+fn decode_request(request: PushEventsRequest) -> Result<Vec<Event>, Status> {
+    let encoded = match request.compression() {
+        Compression::None => request.events,
+        Compression::Gzip => {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(&request.events[..])
+                .take(MAX_DECOMPRESSED_BYTES + 1)
+                .read_to_end(&mut decompressed)
+                .map_err(|error| {
+                    emit!(VectorDecompressionError { error });
+                    Status::invalid_argument("Invalid gzip data.")
+                })?;
+            if decompressed.len() as u64 > MAX_DECOMPRESSED_BYTES {
+                let error = std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!(
+                        "Decompressed events exceed {} bytes.",
+                        MAX_DECOMPRESSED_BYTES
+                    ),
+                );
+                emit!(VectorDecompressionError { error });
+                return Err(Status::resource_exhausted(
+                    "Decompressed events are too large.",
+                ));
+            }
+            decompressed
+        }
+    };
+
+    let batch = EventBatch::decode(&encoded[..]).map_err(|error| {
+        emit!(VectorProtoDecodeError { error });
+        Status::invalid_argument("Invalid event batch.")
+    })?;
+
+    Ok(batch
+        .events
+        .into_iter()
+        .map(|event| {
+            emit!(VectorEventReceived {
+                byte_size: event.encoded_len()
+            });
+            Event::from(event)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use tonic::Code;
+
+    #[test]
+    fn rejects_invalid_requests() {
+        let request = PushEventsRequest {
+            compression: Compression::Gzip as i32,
+            events: b"not gzip".to_vec(),
+        };
+        let status = decode_request(request).unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let request = PushEventsRequest {
+            compression: Compression::None as i32,
+            events: vec![0xff; 8],
+        };
+        let status = decode_request(request).unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn rejects_large_decompressed_requests() {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder
+            .write_all(&vec![0; MAX_DECOMPRESSED_BYTES as usize + 1])
+            .unwrap();
+        let request = PushEventsRequest {
+            compression: Compression::Gzip as i32,
+            events: encoder.finish().unwrap(),
+        };
+        let status = decode_request(request).unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+    }
+
+    #[tokio::test]
+    async fn reports_not_serving_once_shutting_down() {
+        let (pipeline, _rx) = Pipeline::new_test();
+        let service = Service {
+            pipeline,
+            serving: Arc::new(AtomicBool::new(true)),
+        };
+        let status = |service: &Service| {
+            let service = service.clone();
+            async move {
+                service
+                    .health_check(Request::new(HealthCheckRequest {}))
+                    .await
+                    .unwrap()
+                    .into_inner()
+                    .status
+            }
+        };
+
+        assert_eq!(status(&service).await, ServingStatus::Serving as i32);
+        service.serving.store(false, Ordering::Relaxed);
+        assert_eq!(status(&service).await, ServingStatus::NotServing as i32);
+    }
+}